@@ -0,0 +1,507 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actix_web::{HttpRequest, HttpResponse, web};
+use actix_web::error::ErrorInternalServerError;
+use actix_web::http::header::{CONTENT_DISPOSITION, LINK};
+use actix_web::web::Query;
+use bson::Document;
+use bson::oid::ObjectId;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::LogExtensionErr;
+use crate::auth::AdminIdentity;
+use crate::backup::{BACKUP_CONTENT_TYPE, BACKUP_FILENAME, backup_lines, LineSplitter, RestoreMode, RestoreParams, RestoreSummary};
+use crate::body_size::BodySizeMetrics;
+use crate::bulk_import::IMPORT_CHUNK_SIZE;
+use crate::dao::Dao;
+use crate::error_body::ErrorBody;
+use crate::list_response::{ListEnvelope, ListMeta, RecipeCount};
+use crate::model::db_stats::DbStats;
+use crate::pagination::Pagination;
+use crate::query_budget::{BudgetParams, query_budget};
+use crate::recipe_routes::dao_error_response;
+
+/// database stats are expensive to gather and change slowly
+const STATS_CACHE_TTL: Duration = Duration::from_secs(10);
+const DEFAULT_DUPLICATE_GROUPS_PAGE_SIZE: usize = 20;
+const MAX_DUPLICATE_GROUPS_PAGE_SIZE: usize = 100;
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct DuplicatesParams {
+    pub page: Option<usize>,
+    pub items: Option<usize>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub struct TagNormalizationResult {
+    pub modified: u64,
+}
+
+pub struct AdminRoutes {}
+
+/// Last gathered database stats, shared between the workers
+pub struct StatsCache {
+    ttl: Duration,
+    entry: Mutex<Option<(Instant, DbStats)>>,
+}
+
+impl Default for StatsCache {
+    fn default() -> Self { Self::new(STATS_CACHE_TTL) }
+}
+
+impl StatsCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, entry: Mutex::new(None) }
+    }
+
+    fn get(&self) -> Option<DbStats> {
+        match self.entry.lock().ok()?.as_ref() {
+            Some((created, stats)) if created.elapsed() < self.ttl => Some(stats.clone()),
+            _ => None
+        }
+    }
+
+    fn put(&self, stats: DbStats) {
+        if let Ok(mut entry) = self.entry.lock() {
+            *entry = Some((Instant::now(), stats));
+        }
+    }
+}
+
+impl AdminRoutes {
+    /// collection and server stats, partial with notes when the database user lacks privileges
+    pub async fn get_stats(admin: AdminIdentity, cache: web::Data<StatsCache>, body_sizes: Option<web::Data<BodySizeMetrics>>, database: web::Data<Dao>) -> HttpResponse {
+        if let Some(stats) = cache.get() {
+            return HttpResponse::Ok().json(with_counters(stats, &database, body_sizes));
+        }
+
+        info!("Gathering database stats for admin={}", admin.0.user);
+        let stats = DbStats::collect(database.get_collection_stats().await, database.get_server_status().await);
+        cache.put(stats.clone());
+        HttpResponse::Ok().json(with_counters(stats, &database, body_sizes))
+    }
+
+    /// groups of recipes with the same normalized title and ingredients for review, largest first,
+    /// `?page=` and `?items=` page through the groups, 20 per page by default and at most 100,
+    /// `?maxTimeMs=` bounds the detection
+    pub async fn get_duplicates(req: HttpRequest, admin: AdminIdentity, params: Query<DuplicatesParams>, budget: Query<BudgetParams>, database: web::Data<Dao>) -> HttpResponse {
+        let max_time = match query_budget(&req).max_time(*budget) {
+            Ok(max_time) => max_time,
+            Err(err) => return HttpResponse::BadRequest().json(ErrorBody::new(&err.error))
+        };
+        let pagination = Pagination {
+            page: Some(params.page.unwrap_or(1).max(1)),
+            items: Some(params.items.unwrap_or(DEFAULT_DUPLICATE_GROUPS_PAGE_SIZE).clamp(1, MAX_DUPLICATE_GROUPS_PAGE_SIZE)),
+            ..Pagination::default()
+        };
+
+        info!("Detecting duplicate recipes for admin={}", admin.0.user);
+        let (groups, total) = match database.get_duplicate_groups(pagination.skip(), pagination.take(), max_time).await {
+            Ok(page) => page,
+            Err(err) => return dao_error_response(err),
+        };
+        let mut response = HttpResponse::Ok();
+        if let Some(link) = pagination.link_header(req.path(), req.query_string(), total) {
+            response.header(LINK, link);
+        }
+        let meta = ListMeta::new(RecipeCount { total, is_estimate: false }, doc! {}, Some(doc! { "count": -1 }), Some(pagination));
+        response.json(ListEnvelope { data: groups, meta })
+    }
+
+    /// recomputes the content hashes of all recipes and reports the ones not matching the stored hash,
+    /// e.g. after silent corruption or edits bypassing the service
+    pub async fn verify_integrity(admin: AdminIdentity, database: web::Data<Dao>) -> HttpResponse {
+        info!("Verifying integrity for admin={}", admin.0.user);
+        match database.verify_integrity().await {
+            Ok(report) => HttpResponse::Ok().json(report),
+            Err(err) => dao_error_response(err),
+        }
+    }
+
+    /// rewrites the tags of all recipes trimmed, lowercase and without duplicates like they are stored
+    /// since, answers the number of modified recipes. Running it again modifies none
+    pub async fn normalize_tags(admin: AdminIdentity, database: web::Data<Dao>) -> HttpResponse {
+        info!("Normalizing tags for admin={}", admin.0.user);
+        match database.normalize_all_tags().await {
+            Ok(modified) => HttpResponse::Ok().json(TagNormalizationResult { modified }),
+            Err(err) => dao_error_response(err),
+        }
+    }
+
+    /// streams all recipe documents as stored, images included, one canonical extended JSON document
+    /// per line, closed by a `{"count": n}` trailer. `POST /admin/restore` reads the archive back
+    pub async fn backup(admin: AdminIdentity, database: web::Data<Dao>) -> HttpResponse {
+        info!("Backing up recipes for admin={}", admin.0.user);
+        let cursor = match database.get_recipes_cursor(doc! {}, None).await {
+            Ok(cursor) => cursor,
+            Err(err) => return dao_error_response(err),
+        };
+        let documents = cursor.map(|recipe| recipe
+            .log_if_err(|err| error!("Could not read recipe for backup. Err={:#?}", err))
+            .map_err(ErrorInternalServerError));
+        HttpResponse::Ok()
+            .content_type(BACKUP_CONTENT_TYPE)
+            .header(CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", BACKUP_FILENAME))
+            .streaming(backup_lines(documents))
+    }
+
+    /// Restores a backup of `GET /admin/backup` chunk by chunk while the body is streaming in. The recipes
+    /// of the backup replace the stored ones with the same id. `?mode=replace` deletes the recipes missing
+    /// in the backup that were created before the restore started, `?mode=merge`, the default, keeps them.
+    /// Answers 422 with the summary when it restored fewer recipes than the trailer counts, replace mode
+    /// deletes nothing then
+    pub async fn restore(admin: AdminIdentity, mut payload: web::Payload, params: Query<RestoreParams>, database: web::Data<Dao>) -> HttpResponse {
+        let started = ObjectId::new();
+        let mode = params.mode.unwrap_or_default();
+        info!("Restoring recipes for admin={}, mode={:?}", admin.0.user, mode);
+        let mut splitter = LineSplitter::default();
+        let mut summary = RestoreSummary::default();
+        let mut documents: Vec<Document> = Vec::with_capacity(IMPORT_CHUNK_SIZE);
+        let mut ids: Vec<ObjectId> = Vec::new();
+
+        while let Some(bytes) = payload.next().await {
+            let lines = match bytes {
+                Ok(bytes) => splitter.feed(&bytes),
+                Err(err) => {
+                    error!("Could not restore recipes, malformed payload. Err={:#?}", err);
+                    return HttpResponse::BadRequest().json(summary);
+                }
+            };
+            for line in lines {
+                summary.read_line(&line, &mut documents);
+                if documents.len() >= IMPORT_CHUNK_SIZE {
+                    if let Err(response) = flush_restore_chunk(&database, &mut summary, &mut ids, std::mem::take(&mut documents)).await {
+                        return response;
+                    }
+                }
+            }
+        }
+        if let Some(line) = splitter.finish() {
+            summary.read_line(&line, &mut documents);
+        }
+        if !documents.is_empty() {
+            if let Err(response) = flush_restore_chunk(&database, &mut summary, &mut ids, documents).await {
+                return response;
+            }
+        }
+
+        if !summary.is_complete() {
+            info!("The backup was not restored completely, not deleting any recipe. mode={:?}, summary={:?}", mode, summary);
+            return HttpResponse::UnprocessableEntity().json(summary);
+        }
+        if mode == RestoreMode::Replace {
+            match database.delete_recipes_except(ids, started).await {
+                Ok(deleted) => summary.deleted = Some(deleted),
+                Err(err) => return dao_error_response(err),
+            }
+        }
+        info!("Restored recipes. summary={:?}", summary);
+        HttpResponse::Ok().json(summary)
+    }
+}
+
+/// restores the documents and adds them to the summary, answers the error response when the database failed
+async fn flush_restore_chunk(database: &Dao, summary: &mut RestoreSummary, ids: &mut Vec<ObjectId>, documents: Vec<Document>) -> Result<(), HttpResponse> {
+    ids.extend(documents.iter().filter_map(|document| document.get_object_id("_id").ok().cloned()));
+    let (restored, failed) = database.restore_recipe_documents(documents).await.map_err(dao_error_response)?;
+    summary.restored += restored;
+    summary.failed += failed;
+    Ok(())
+}
+
+/// the recipe cache and body size counters change with every request, so they are added after the stats cache
+fn with_counters(mut stats: DbStats, database: &Dao, body_sizes: Option<web::Data<BodySizeMetrics>>) -> DbStats {
+    if database.recipe_cache.is_enabled() {
+        stats.recipe_cache = Some(database.recipe_cache.stats());
+    }
+    stats.request_bodies = body_sizes.map(|body_sizes| body_sizes.stats());
+    stats
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix_web::{App, test, web};
+    use actix_web::http::StatusCode;
+    use bson::Document;
+    use futures_util::StreamExt;
+    use serde_json::Value;
+    use serial_test::serial;
+
+    use crate::admin_routes::{AdminRoutes, StatsCache};
+    use crate::auth::auth_tests::{ADMIN_TOKEN, bearer, create_api_tokens, EDITOR_TOKEN};
+    use crate::dao::Dao;
+    use crate::dao::dao_tests::{before, cleanup_after, create_one_recipe_with_image, create_one_recipe_without_image};
+    use crate::model::db_stats::DbStats;
+    use crate::model::ingredients::Ingredient;
+    use crate::model::measurement_unit::MeasurementUnit;
+
+    #[test]
+    fn stats_cache_expires() {
+        let cache = StatsCache::new(Duration::from_millis(0));
+        cache.put(DbStats::collect(Ok(doc! {}), Ok(doc! {})));
+        assert_eq!(cache.get(), None);
+
+        let cache = StatsCache::new(Duration::from_secs(60));
+        cache.put(DbStats::collect(Ok(doc! { "count": 2 }), Ok(doc! {})));
+        assert_eq!(cache.get().unwrap().collection.unwrap().count, 2);
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_stats() {
+        let dao = before().await;
+        dao.insert_recipe(create_one_recipe_without_image()).await.unwrap();
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .data(StatsCache::default())
+            .route("/admin/stats", web::get().to(AdminRoutes::get_stats))).await;
+
+        let req = test::TestRequest::get().uri("/admin/stats").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let (header, value) = bearer(EDITOR_TOKEN);
+        let req = test::TestRequest::get().header(header, value).uri("/admin/stats").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let (header, value) = bearer(ADMIN_TOKEN);
+        let req = test::TestRequest::get().header(header, value).uri("/admin/stats").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["collection"]["count"], 1);
+        assert_eq!(body["collection"]["indexes"][0]["name"], "_id_");
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_duplicates() {
+        let dao = before().await;
+        let titles = ["Pancakes", " pancakes ", "PANCAKES", "Waffles", "Waffles"];
+        for (index, title) in titles.iter().enumerate() {
+            let mut recipe = create_one_recipe_without_image();
+            recipe.title = title.to_string();
+            recipe.ingredients = vec![Ingredient::new("0", 1.0, "Egg", MeasurementUnit::Piece), Ingredient::new("1", 0.2, " milk", MeasurementUnit::Liter)];
+            if index == 2 {
+                recipe.ingredients.truncate(1);
+            }
+            if index == 4 {
+                recipe.ingredients.reverse();
+            }
+            dao.insert_recipe(recipe).await.unwrap();
+        }
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/admin/duplicates", web::get().to(AdminRoutes::get_duplicates))).await;
+
+        let (header, value) = bearer(EDITOR_TOKEN);
+        let req = test::TestRequest::get().header(header, value).uri("/admin/duplicates").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::FORBIDDEN);
+
+        let (header, value) = bearer(ADMIN_TOKEN);
+        let req = test::TestRequest::get().header(header, value.clone()).uri("/admin/duplicates").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["meta"]["total"], 2);
+        let groups = body["data"].as_array().unwrap();
+        let titles = groups.iter().map(|group| group["title"].as_str().unwrap()).collect::<Vec<&str>>();
+        assert_eq!(titles, vec!["pancakes", "waffles"]);
+        assert_eq!(groups[0]["count"], 2);
+        assert_eq!(groups[0]["recipes"].as_array().unwrap().len(), 2);
+        assert_eq!(groups[0]["recipes"][0]["id"].is_string(), true);
+
+        let req = test::TestRequest::get().header(header, value).uri("/admin/duplicates?page=2&items=1").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["data"][0]["title"], "waffles");
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_verify_integrity() {
+        let dao = before().await;
+        let corrupted = dao.insert_recipe(create_one_recipe_without_image()).await.unwrap();
+        let updated = dao.insert_recipe(create_one_recipe_without_image()).await.unwrap();
+        let unhashed = dao.insert_recipe(create_one_recipe_without_image()).await.unwrap();
+        dao.database.collection("recipes").update_one(doc! { "_id": corrupted.clone() }, doc! { "$set": { "title": "Edited out of band" } }, None).await.unwrap();
+        dao.update_recipe_ingredients(updated.as_object_id().unwrap().clone(), vec![Ingredient::new("0", 1.0, "Egg", MeasurementUnit::Piece)], None).await.unwrap();
+        dao.database.collection("recipes").update_one(doc! { "_id": unhashed }, doc! { "$unset": { "contentHash": "" } }, None).await.unwrap();
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/admin/verifyIntegrity", web::get().to(AdminRoutes::verify_integrity))).await;
+
+        let (header, value) = bearer(EDITOR_TOKEN);
+        let req = test::TestRequest::get().header(header, value).uri("/admin/verifyIntegrity").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::FORBIDDEN);
+
+        let (header, value) = bearer(ADMIN_TOKEN);
+        let req = test::TestRequest::get().header(header, value).uri("/admin/verifyIntegrity").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["checked"], 3);
+        assert_eq!(body["unhashed"], 1);
+        assert_eq!(body["mismatches"].as_array().unwrap().len(), 1);
+        assert_eq!(body["mismatches"][0]["id"], corrupted.as_object_id().unwrap().to_hex());
+        assert_eq!(body["mismatches"][0]["title"], "Edited out of band");
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_normalize_tags() {
+        let dao = before().await;
+        let messy = [vec!["Vegan ", "vegan", " Quick", ""], vec!["dessert"], vec!["DESSERT", "Dessert"], vec![]];
+        let mut ids = Vec::new();
+        for tags in messy.iter() {
+            let id = dao.insert_recipe(create_one_recipe_without_image()).await.unwrap();
+            dao.database.collection("recipes").update_one(doc! { "_id": id.clone() }, doc! { "$set": { "tags": tags.clone() } }, None).await.unwrap();
+            ids.push(id.as_object_id().unwrap().clone());
+        }
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/admin/normalizeTags", web::post().to(AdminRoutes::normalize_tags))).await;
+
+        let (header, value) = bearer(EDITOR_TOKEN);
+        let req = test::TestRequest::post().header(header, value).uri("/admin/normalizeTags").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::FORBIDDEN);
+
+        let (header, value) = bearer(ADMIN_TOKEN);
+        let req = test::TestRequest::post().header(header, value.clone()).uri("/admin/normalizeTags").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["modified"], 2);
+
+        let tags = |index: usize| dao.get_one_recipe_without_image(ids[index].clone());
+        assert_eq!(tags(0).await.unwrap().tags, vec!["vegan", "quick"]);
+        assert_eq!(tags(0).await.unwrap().version, 2);
+        assert_eq!(tags(1).await.unwrap().tags, vec!["dessert"]);
+        assert_eq!(tags(1).await.unwrap().version, 1);
+        assert_eq!(tags(2).await.unwrap().tags, vec!["dessert"]);
+        assert_eq!(tags(3).await.unwrap().tags.is_empty(), true);
+
+        let req = test::TestRequest::post().header(header, value).uri("/admin/normalizeTags").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["modified"], 0);
+
+        cleanup_after(dao).await;
+    }
+
+    async fn stored_documents(dao: &Dao) -> Vec<Document> {
+        let cursor = dao.database.collection("recipes").find(doc! {}, None).await.unwrap();
+        let mut documents = cursor.map(Result::unwrap).collect::<Vec<Document>>().await;
+        documents.sort_by_key(|document| document.get_object_id("_id").unwrap().to_hex());
+        documents
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_backup_and_restore_round_trip() {
+        let dao = before().await;
+        let changed = dao.insert_recipe(create_one_recipe_without_image()).await.unwrap();
+        let deleted = dao.insert_recipe(create_one_recipe_with_image()).await.unwrap();
+        dao.insert_recipe(create_one_recipe_without_image()).await.unwrap();
+        let backed_up = stored_documents(&dao).await;
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/admin/backup", web::get().to(AdminRoutes::backup))
+            .route("/admin/restore", web::post().to(AdminRoutes::restore))).await;
+
+        let (header, value) = bearer(EDITOR_TOKEN);
+        let req = test::TestRequest::get().header(header, value.clone()).uri("/admin/backup").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::FORBIDDEN);
+        let req = test::TestRequest::post().header(header, value).uri("/admin/restore").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::FORBIDDEN);
+
+        let (header, value) = bearer(ADMIN_TOKEN);
+        let req = test::TestRequest::get().header(header, value.clone()).uri("/admin/backup").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let backup = test::read_body(resp).await;
+        assert_eq!(backup.iter().filter(|byte| **byte == b'\n').count(), 4);
+        assert_eq!(backup.ends_with(b"{\"count\":3}\n"), true);
+
+        dao.database.collection("recipes").update_one(doc! { "_id": changed }, doc! { "$set": { "title": "Changed after the backup" } }, None).await.unwrap();
+        dao.database.collection("recipes").delete_one(doc! { "_id": deleted }, None).await.unwrap();
+        dao.insert_recipe(create_one_recipe_without_image()).await.unwrap();
+
+        let req = test::TestRequest::post().header(header, value.clone()).uri("/admin/restore?mode=merge").set_payload(backup.clone()).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, serde_json::json!({ "restored": 3, "invalid": 0, "failed": 0 }));
+        assert_eq!(stored_documents(&dao).await.len(), 4);
+
+        let req = test::TestRequest::post().header(header, value).uri("/admin/restore?mode=replace").set_payload(backup).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, serde_json::json!({ "restored": 3, "invalid": 0, "failed": 0, "deleted": 1 }));
+        assert_eq!(stored_documents(&dao).await, backed_up);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_restore_replace_incomplete_backups() {
+        let dao = before().await;
+        dao.insert_recipe(create_one_recipe_without_image()).await.unwrap();
+        dao.insert_recipe(create_one_recipe_without_image()).await.unwrap();
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/admin/backup", web::get().to(AdminRoutes::backup))
+            .route("/admin/restore", web::post().to(AdminRoutes::restore))).await;
+        let (header, value) = bearer(ADMIN_TOKEN);
+        let req = test::TestRequest::get().header(header, value.clone()).uri("/admin/backup").to_request();
+        let backup = test::read_body(test::call_service(&mut app, req).await).await;
+        dao.insert_recipe(create_one_recipe_without_image()).await.unwrap();
+
+        let req = test::TestRequest::post().header(header, value.clone()).uri("/admin/restore?mode=replace").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body, serde_json::json!({ "restored": 0, "invalid": 0, "failed": 0 }));
+        assert_eq!(stored_documents(&dao).await.len(), 3);
+
+        let first_line = backup.iter().position(|byte| *byte == b'\n').unwrap() + 1;
+        let req = test::TestRequest::post().header(header, value.clone()).uri("/admin/restore?mode=replace")
+            .set_payload(backup.slice(..first_line)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body, serde_json::json!({ "restored": 1, "invalid": 0, "failed": 0 }));
+        assert_eq!(stored_documents(&dao).await.len(), 3);
+
+        let req = test::TestRequest::post().header(header, value).uri("/admin/restore?mode=merge")
+            .set_payload(backup.slice(..first_line)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body, serde_json::json!({ "restored": 1, "invalid": 0, "failed": 0 }));
+        assert_eq!(stored_documents(&dao).await.len(), 3);
+
+        cleanup_after(dao).await;
+    }
+}
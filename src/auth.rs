@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use actix_web::{dev, Error, FromRequest, HttpRequest, HttpResponse, web};
+use actix_web::error::InternalError;
+use actix_web::http::header::AUTHORIZATION;
+use futures_util::future::{ready, Ready};
+use serde::Serialize;
+
+use crate::LogExtensionErr;
+use crate::error_body::ErrorBody;
+
+pub const API_TOKENS_ENV: &str = "API_TOKENS";
+const BEARER_PREFIX: &str = "Bearer ";
+const ENTRY_SEPARATOR: char = ',';
+const FIELD_SEPARATOR: char = ':';
+
+/// ordered by privilege, every role includes the rights of the roles before it
+#[derive(Serialize, Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Role {
+    Viewer,
+    Editor,
+    Admin,
+}
+
+impl TryFrom<&str> for Role {
+    type Error = String;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "viewer" => Ok(Role::Viewer),
+            "editor" => Ok(Role::Editor),
+            "admin" => Ok(Role::Admin),
+            _ => Err(format!("Role '{}' does not match one predefined value", value))
+        }
+    }
+}
+
+/// The authenticated caller, extracted from the `Authorization: Bearer <token>` header.
+/// Responds 401 when the token is missing or unknown.
+#[derive(Serialize, Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Identity {
+    pub user: String,
+    pub role: Role,
+}
+
+impl Identity {
+    pub fn new(user: &str, role: Role) -> Self {
+        Self { user: user.to_string(), role }
+    }
+
+    pub fn has_role(&self, role: Role) -> bool {
+        self.role >= role
+    }
+}
+
+/// Identity with the admin role, responds 403 for other roles.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AdminIdentity(pub Identity);
+
+/// Known api tokens, configured via `API_TOKENS=token:user:role,...`
+#[derive(Debug, Clone, Default)]
+pub struct ApiTokens {
+    identities: HashMap<String, Identity>,
+}
+
+impl ApiTokens {
+    pub fn from_env() -> Self {
+        let tokens = std::env::var(API_TOKENS_ENV).unwrap_or_default();
+        let api_tokens = ApiTokens::parse(&tokens);
+        info!("Loaded {} api tokens", api_tokens.identities.len());
+        api_tokens
+    }
+
+    /// skips malformed entries
+    pub fn parse(value: &str) -> Self {
+        let identities = value.split(ENTRY_SEPARATOR)
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .filter_map(|entry| {
+                let fields = entry.split(FIELD_SEPARATOR).map(str::trim).collect::<Vec<&str>>();
+                match fields.as_slice() {
+                    [token, user, role] if !token.is_empty() && !user.is_empty() => Role::try_from(*role)
+                        .log_if_err(|err| error!("Skipping api token of user={}. Err={}", user, err))
+                        .ok()
+                        .map(|role| (token.to_string(), Identity::new(user, role))),
+                    _ => {
+                        error!("Skipping malformed api token entry, expected token:user:role");
+                        None
+                    }
+                }
+            })
+            .collect();
+        Self { identities }
+    }
+
+    pub fn identify(&self, token: &str) -> Option<Identity> {
+        self.identities.get(token).cloned()
+    }
+}
+
+impl FromRequest for Identity {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut dev::Payload) -> Self::Future {
+        ready(identify_request(req)
+            .ok_or_else(|| rejection(HttpResponse::Unauthorized(), "Missing or unknown api token")))
+    }
+}
+
+impl FromRequest for AdminIdentity {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut dev::Payload) -> Self::Future {
+        ready(match identify_request(req) {
+            Some(identity) if identity.has_role(Role::Admin) => Ok(AdminIdentity(identity)),
+            Some(_) => Err(rejection(HttpResponse::Forbidden(), "Admin role required")),
+            None => Err(rejection(HttpResponse::Unauthorized(), "Missing or unknown api token")),
+        })
+    }
+}
+
+/// the identity of an optional bearer token, None when missing or unknown
+pub(crate) fn identify_request(req: &HttpRequest) -> Option<Identity> {
+    let token = req.headers().get(AUTHORIZATION)?
+        .to_str().ok()?
+        .strip_prefix(BEARER_PREFIX)?
+        .trim();
+    req.app_data::<web::Data<ApiTokens>>()?.identify(token)
+}
+
+fn rejection(mut response: actix_web::dev::HttpResponseBuilder, error: &str) -> Error {
+    InternalError::from_response(error.to_string(), response.json(ErrorBody::new(error))).into()
+}
+
+
+#[cfg(test)]
+pub mod auth_tests {
+    use std::convert::TryFrom;
+
+    use actix_web::{FromRequest, web};
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+
+    use crate::auth::{AdminIdentity, ApiTokens, Identity, Role};
+
+    pub const ADMIN_TOKEN: &str = "admin-token";
+    pub const EDITOR_TOKEN: &str = "editor-token";
+
+    pub fn create_api_tokens() -> ApiTokens {
+        ApiTokens::parse(&format!("{}:alice:admin,{}:bob:editor", ADMIN_TOKEN, EDITOR_TOKEN))
+    }
+
+    pub fn bearer(token: &str) -> (&'static str, String) {
+        ("authorization", format!("Bearer {}", token))
+    }
+
+    #[test]
+    fn parse_api_tokens() {
+        let tokens = ApiTokens::parse(" t1:alice:admin, t2:bob:Editor,broken, t3:carol:chef, :dave:viewer");
+        assert_eq!(tokens.identify("t1"), Some(Identity::new("alice", Role::Admin)));
+        assert_eq!(tokens.identify("t2"), Some(Identity::new("bob", Role::Editor)));
+        assert_eq!(tokens.identify("t3"), None);
+        assert_eq!(tokens.identify(""), None);
+        assert_eq!(tokens.identities.len(), 2);
+    }
+
+    #[test]
+    fn role_order() {
+        assert_eq!(Identity::new("alice", Role::Admin).has_role(Role::Editor), true);
+        assert_eq!(Identity::new("bob", Role::Viewer).has_role(Role::Editor), false);
+        assert_eq!(Role::try_from("ADMIN"), Ok(Role::Admin));
+    }
+
+    #[actix_rt::test]
+    async fn extract_identity() {
+        let (header, value) = bearer(EDITOR_TOKEN);
+        let req = TestRequest::default()
+            .app_data(web::Data::new(create_api_tokens()))
+            .header(header, value)
+            .to_http_request();
+        let identity = Identity::extract(&req).await.unwrap();
+        assert_eq!(identity, Identity::new("bob", Role::Editor));
+
+        let admin = AdminIdentity::extract(&req).await;
+        assert_eq!(admin.err().unwrap().as_response_error().status_code(), StatusCode::FORBIDDEN);
+    }
+
+    #[actix_rt::test]
+    async fn reject_missing_or_unknown_token() {
+        let req = TestRequest::default()
+            .app_data(web::Data::new(create_api_tokens()))
+            .to_http_request();
+        let identity = Identity::extract(&req).await;
+        assert_eq!(identity.err().unwrap().as_response_error().status_code(), StatusCode::UNAUTHORIZED);
+
+        let (header, value) = bearer("unknown");
+        let req = TestRequest::default()
+            .app_data(web::Data::new(create_api_tokens()))
+            .header(header, value)
+            .to_http_request();
+        assert_eq!(Identity::extract(&req).await.is_err(), true);
+
+        let (header, value) = bearer(ADMIN_TOKEN);
+        let req = TestRequest::default().header(header, value).to_http_request();
+        assert_eq!(Identity::extract(&req).await.is_err(), true);
+    }
+}
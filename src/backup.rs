@@ -0,0 +1,217 @@
+use std::convert::TryFrom;
+
+use actix_web::web::Bytes;
+use bson::{Bson, Document};
+use futures_util::{future, stream, StreamExt};
+use futures_util::stream::Stream;
+use serde::{Deserialize, Serialize};
+
+use crate::model::recipe::Recipe;
+
+pub const BACKUP_CONTENT_TYPE: &str = "application/x-ndjson";
+pub const BACKUP_FILENAME: &str = "recipes-backup.ndjson";
+
+/// `merge` keeps the stored recipes missing in the backup, `replace` deletes them
+#[derive(Deserialize, Debug, Clone, Copy, Eq, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RestoreMode {
+    #[default]
+    Merge,
+    Replace,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct RestoreParams {
+    pub mode: Option<RestoreMode>,
+}
+
+/// The last line of a backup, the number of recipes in the lines above it
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct BackupTrailer {
+    pub count: u64,
+}
+
+/// Outcome of a restore. `deleted` is left out when nothing was deleted as the mode merges,
+/// or as a replace skipped deleting since the backup was not restored completely
+#[derive(Serialize, Debug, Clone, Default, Eq, PartialEq)]
+pub struct RestoreSummary {
+    pub restored: u64,
+    pub invalid: u64,
+    pub failed: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted: Option<u64>,
+    /// the count of the trailer, none as long as the trailer was not read
+    #[serde(skip)]
+    pub expected: Option<u64>,
+}
+
+impl RestoreSummary {
+    /// adds the recipe document of the line to the documents to restore, counts it invalid otherwise.
+    /// The trailer is kept as the expected count
+    pub fn read_line(&mut self, line: &[u8], documents: &mut Vec<Document>) {
+        if let Ok(trailer) = serde_json::from_slice::<BackupTrailer>(line) {
+            self.expected = Some(trailer.count);
+            return;
+        }
+        match parse_backup_line(line) {
+            Ok(document) => documents.push(document),
+            Err(err) => {
+                info!("Skipping invalid line in backup. Err={}", err);
+                self.invalid += 1;
+            }
+        }
+    }
+
+    /// Whether every recipe of the backup was restored: the trailer was read, its count matches and
+    /// no line was invalid or failed. An empty or truncated body is never complete
+    pub fn is_complete(&self) -> bool {
+        self.restored > 0 && self.invalid == 0 && self.failed == 0 && self.expected == Some(self.restored)
+    }
+}
+
+/// The lines of a backup of the documents, closed by the trailer with their count.
+/// Actix ends the response at an error, the trailer is then missing
+pub fn backup_lines<S, E>(documents: S) -> impl Stream<Item=Result<Bytes, E>>
+    where S: Stream<Item=Result<Document, E>> {
+    documents.map(Some)
+        .chain(stream::once(future::ready(None)))
+        .scan(0u64, |count, document| future::ready(Some(match document {
+            Some(document) => document.map(|document| {
+                *count += 1;
+                Bytes::from(backup_line(document))
+            }),
+            None => Ok(Bytes::from(backup_trailer(*count))),
+        })))
+}
+
+fn backup_trailer(count: u64) -> Vec<u8> {
+    let mut line = serde_json::to_vec(&BackupTrailer { count }).unwrap_or_default();
+    line.push(b'\n');
+    line
+}
+
+/// One line of a backup: the recipe document as stored in canonical extended JSON, which keeps
+/// the exact types of ids, dates and numbers
+pub fn backup_line(doc: Document) -> Vec<u8> {
+    let mut line = Bson::Document(doc).into_canonical_extjson().to_string().into_bytes();
+    line.push(b'\n');
+    line
+}
+
+/// the recipe document of a backup line, it has to have an id and be a readable recipe
+pub fn parse_backup_line(line: &[u8]) -> Result<Document, String> {
+    let doc = match serde_json::from_slice::<Bson>(line).map_err(|err| err.to_string())? {
+        Bson::Document(doc) => doc,
+        _ => return Err("A backup line has to be a recipe document".to_string()),
+    };
+    doc.get_object_id("_id").map_err(|_| "A backup line has to contain the _id of the recipe".to_string())?;
+    Recipe::try_from(doc.clone()).map_err(|err| err.error)?;
+    Ok(doc)
+}
+
+/// Splits a body streaming in into its non empty lines, a line may span chunks
+#[derive(Debug, Default)]
+pub struct LineSplitter {
+    pending: Vec<u8>,
+}
+
+impl LineSplitter {
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Vec<u8>> {
+        self.pending.extend_from_slice(chunk);
+        let mut lines = Vec::new();
+        while let Some(end) = self.pending.iter().position(|byte| *byte == b'\n') {
+            let line = self.pending.drain(..=end).collect::<Vec<u8>>();
+            lines.push(line);
+        }
+        lines.retain(|line| !line.iter().all(u8::is_ascii_whitespace));
+        lines
+    }
+
+    /// the last line when the body does not end with a line break
+    pub fn finish(self) -> Option<Vec<u8>> {
+        Some(self.pending).filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+    }
+}
+
+
+#[cfg(test)]
+mod backup_tests {
+    use bson::{Bson, Document};
+
+    use futures_util::{stream, StreamExt};
+
+    use crate::backup::{backup_line, backup_lines, LineSplitter, parse_backup_line, RestoreSummary};
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+
+    #[test]
+    fn backup_line_round_trip() {
+        let recipe = create_one_recipe_without_image();
+        let mut doc = Document::from(recipe.clone());
+        doc.insert("_id", recipe._id.clone());
+        doc.insert("ingredientCount", 0);
+        doc.insert("rating", 4.0);
+
+        let line = backup_line(doc.clone());
+        assert_eq!(line.ends_with(b"\n"), true);
+        let parsed = parse_backup_line(&line).unwrap();
+        assert_eq!(parsed, doc);
+        assert_eq!(parsed.get("ingredientCount"), Some(&Bson::Int32(0)));
+        assert_eq!(parsed.get("rating"), Some(&Bson::Double(4.0)));
+    }
+
+    #[test]
+    fn invalid_backup_lines() {
+        assert_eq!(parse_backup_line(b"[1]").is_err(), true);
+        assert_eq!(parse_backup_line(b"{\"title\": \"Soup\"}").is_err(), true);
+        assert_eq!(parse_backup_line(b"{\"_id\": {\"$oid\": \"5f7333360051027600b01a36\"}}").is_err(), true);
+        assert_eq!(parse_backup_line(b"{").is_err(), true);
+    }
+
+    #[actix_rt::test]
+    async fn backup_ends_with_trailer() {
+        let recipe = create_one_recipe_without_image();
+        let mut doc = Document::from(recipe.clone());
+        doc.insert("_id", recipe._id.clone());
+
+        let lines = backup_lines(stream::iter(vec![Ok::<_, ()>(doc.clone()), Ok(doc.clone())]))
+            .map(Result::unwrap).collect::<Vec<_>>().await;
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[2].to_vec(), b"{\"count\":2}\n".to_vec());
+
+        let mut summary = RestoreSummary::default();
+        let mut documents = Vec::new();
+        for line in &lines {
+            summary.read_line(line, &mut documents);
+        }
+        assert_eq!(documents.len(), 2);
+        assert_eq!(summary.invalid, 0);
+        assert_eq!(summary.expected, Some(2));
+
+        let lines = backup_lines(stream::iter(Vec::<Result<Document, ()>>::new()))
+            .map(Result::unwrap).collect::<Vec<_>>().await;
+        assert_eq!(lines, vec![b"{\"count\":0}\n".to_vec()]);
+    }
+
+    #[test]
+    fn complete_restores() {
+        let summary = RestoreSummary { restored: 2, expected: Some(2), ..RestoreSummary::default() };
+        assert_eq!(summary.is_complete(), true);
+        assert_eq!(RestoreSummary { expected: None, ..summary.clone() }.is_complete(), false);
+        assert_eq!(RestoreSummary { restored: 1, ..summary.clone() }.is_complete(), false);
+        assert_eq!(RestoreSummary { invalid: 1, ..summary.clone() }.is_complete(), false);
+        assert_eq!(RestoreSummary { restored: 0, expected: Some(0), ..summary }.is_complete(), false);
+        assert_eq!(RestoreSummary::default().is_complete(), false);
+    }
+
+    #[test]
+    fn split_lines_across_chunks() {
+        let mut splitter = LineSplitter::default();
+        assert_eq!(splitter.feed(b"{\"a\":1}\n{\"b\""), vec![b"{\"a\":1}\n".to_vec()]);
+        assert_eq!(splitter.feed(b":2}\n\n"), vec![b"{\"b\":2}\n".to_vec()]);
+        assert_eq!(splitter.feed(b"{\"c\":3}"), Vec::<Vec<u8>>::new());
+        assert_eq!(splitter.finish(), Some(b"{\"c\":3}".to_vec()));
+
+        assert_eq!(LineSplitter::default().finish(), None);
+    }
+}
@@ -0,0 +1,215 @@
+use std::time::{Duration, Instant};
+
+use actix_web::{HttpResponse, web};
+use actix_web::http::StatusCode;
+use actix_web::web::{Json, Query};
+use bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::auth::Identity;
+use crate::dao::{Dao, DaoError};
+use crate::error_body::ErrorBody;
+use crate::field_encryption::reveal_recipe;
+use crate::recipe_filter::is_visible;
+use crate::recipe_routes::{dao_error_response, DEFAULT_SIMILAR_LIMIT, DEFAULT_TAG_COMBOS_LIMIT, LimitParams,
+                           MAX_SIMILAR_LIMIT, MAX_TAG_COMBOS_LIMIT};
+
+pub const MAX_BATCH_SIZE: usize = 25;
+/// sub requests not started within this budget are answered with 503
+const BATCH_TIME_BUDGET: Duration = Duration::from_secs(5);
+const API_PREFIX: &str = "/api/v1";
+
+pub struct BatchRoutes {}
+
+/// One request of a batch, e.g. `{"method": "GET", "path": "/recipes/{id}/full"}`.
+/// A `body` is ignored as long as only reads can be batched.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SubRequest {
+    pub method: String,
+    pub path: String,
+}
+
+/// Answer to the sub request at the same index: the http status it would have gotten on its own
+/// and its JSON body, `null` for empty bodies, an `ErrorBody` when the batch rejected it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SubResponse {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl SubResponse {
+    fn new(status: StatusCode, body: Value) -> Self {
+        Self { status: status.as_u16(), body }
+    }
+
+    fn error(status: StatusCode, error: &str) -> Self {
+        Self::new(status, serde_json::to_value(ErrorBody::new(error)).unwrap_or_default())
+    }
+
+    fn from_result<T: Serialize>(result: Result<T, DaoError>) -> Self {
+        match result {
+            Ok(body) => Self::new(StatusCode::OK, serde_json::to_value(body).unwrap_or_default()),
+            Err(err) => Self::new(dao_error_response(err).status(), Value::Null),
+        }
+    }
+}
+
+impl BatchRoutes {
+    /// Executes up to `MAX_BATCH_SIZE` read requests in one round trip and answers with an array
+    /// of `SubResponse`s in request order. Only GET on the recipe detail resources is supported,
+    /// as the caller of the batch: drafts only for their author and admins, like encrypted fields.
+    pub async fn execute(requests: Json<Vec<SubRequest>>, identity: Option<Identity>, database: web::Data<Dao>) -> HttpResponse {
+        if requests.len() > MAX_BATCH_SIZE {
+            return HttpResponse::BadRequest().json(ErrorBody::new(
+                &format!("A batch may contain at most {} requests", MAX_BATCH_SIZE)));
+        }
+
+        let start = Instant::now();
+        let mut responses = Vec::with_capacity(requests.len());
+        for request in requests.iter() {
+            if start.elapsed() > BATCH_TIME_BUDGET {
+                responses.push(SubResponse::error(StatusCode::SERVICE_UNAVAILABLE, "Batch time budget exceeded"));
+                continue;
+            }
+            responses.push(execute_one(request, identity.as_ref(), &database).await);
+        }
+        HttpResponse::Ok().json(responses)
+    }
+}
+
+async fn execute_one(request: &SubRequest, identity: Option<&Identity>, database: &Dao) -> SubResponse {
+    if !request.method.eq_ignore_ascii_case("GET") {
+        return SubResponse::error(StatusCode::METHOD_NOT_ALLOWED, "Only GET requests can be batched");
+    }
+
+    let (path, query) = match request.path.split_once('?') {
+        Some((path, query)) => (path, query),
+        None => (request.path.as_str(), "")
+    };
+    let path = path.strip_prefix(API_PREFIX).unwrap_or(path);
+    let limit = match Query::<LimitParams>::from_query(query) {
+        Ok(limit) => limit.into_inner(),
+        Err(_) => return SubResponse::error(StatusCode::BAD_REQUEST, "Invalid query parameters")
+    };
+
+    let segments = path.trim_matches('/').split('/').collect::<Vec<&str>>();
+    match segments.as_slice() {
+        ["recipes", "tagCombos"] => SubResponse::from_result(
+            database.get_tag_combos(limit.limit_or(DEFAULT_TAG_COMBOS_LIMIT, MAX_TAG_COMBOS_LIMIT)).await),
+        ["recipes", id, rest @ ..] => {
+            let id = match ObjectId::with_string(id) {
+                Ok(id) => id,
+                Err(_) => return SubResponse::new(StatusCode::BAD_REQUEST, Value::Null)
+            };
+            match rest {
+                [] => SubResponse::from_result(database.get_one_recipe_without_image(id).await
+                    .and_then(|recipe| match is_visible(&recipe, identity) {
+                        true => Ok(recipe),
+                        false => Err(DaoError::DocumentNotFound),
+                    })
+                    .map(|mut recipe| {
+                        reveal_recipe(&mut recipe, database.field_encryption.as_ref(), identity);
+                        recipe
+                    })),
+                ["full"] => SubResponse::from_result(database.get_one_recipe_full(id).await
+                    .and_then(|full_recipe| match is_visible(&full_recipe.recipe, identity) {
+                        true => Ok(full_recipe),
+                        false => Err(DaoError::DocumentNotFound),
+                    })
+                    .map(|mut full_recipe| {
+                        reveal_recipe(&mut full_recipe.recipe, database.field_encryption.as_ref(), identity);
+                        full_recipe
+                    })),
+                ["similar"] => SubResponse::from_result(
+                    database.get_similar_recipes(id, limit.limit_or(DEFAULT_SIMILAR_LIMIT, MAX_SIMILAR_LIMIT)).await),
+                _ => SubResponse::error(StatusCode::NOT_FOUND, "Resource can not be batched")
+            }
+        }
+        _ => SubResponse::error(StatusCode::NOT_FOUND, "Resource can not be batched")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{App, test, web};
+    use actix_web::http::StatusCode;
+    use bson::oid::ObjectId;
+    use serde_json::{json, Value};
+    use serial_test::serial;
+
+    use crate::auth::auth_tests::{bearer, create_api_tokens, EDITOR_TOKEN};
+    use crate::batch_routes::{BatchRoutes, MAX_BATCH_SIZE};
+    use crate::dao::dao_tests::{before, cleanup_after, create_one_recipe_without_image};
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_execute_batch() {
+        let dao = before().await;
+        let id = dao.insert_recipe(create_one_recipe_without_image()).await.unwrap()
+            .as_object_id().unwrap().to_hex();
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/batch", web::post().to(BatchRoutes::execute))).await;
+
+        let payload = json!([
+            { "method": "GET", "path": format!("/api/v1/recipes/{}", id) },
+            { "method": "get", "path": format!("/recipes/{}/full", id) },
+            { "method": "GET", "path": format!("/recipes/{}/similar?limit=5", id) },
+            { "method": "GET", "path": format!("/recipes/{}", ObjectId::new()) },
+            { "method": "DELETE", "path": format!("/recipes/{}", id) },
+            { "method": "GET", "path": "/recipes/hello" },
+            { "method": "GET", "path": "/admin/stats" }
+        ]);
+        let req = test::TestRequest::post().uri("/batch").set_json(&payload).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let body: Value = test::read_body_json(resp).await;
+        let statuses = body.as_array().unwrap().iter()
+            .map(|response| response["status"].as_u64().unwrap())
+            .collect::<Vec<u64>>();
+        assert_eq!(statuses, vec![200, 200, 200, 404, 405, 400, 404]);
+        assert_eq!(body[0]["body"]["id"], id.as_str());
+        assert_eq!(body[1]["body"]["commentCount"], 0);
+        assert_eq!(body[2]["body"], json!([]));
+        assert_eq!(dao.get_one_recipe_without_image(ObjectId::with_string(&id).unwrap()).await.is_ok(), true);
+
+        let filter = doc! { "_id": ObjectId::with_string(&id).unwrap() };
+        dao.database.collection("recipes").update_one(filter, doc! { "$set": { "status": "draft", "author": "bob" } }, None).await.unwrap();
+        let payload = json!([
+            { "method": "GET", "path": format!("/recipes/{}", id) },
+            { "method": "GET", "path": format!("/recipes/{}/full", id) }
+        ]);
+        let req = test::TestRequest::post().uri("/batch").set_json(&payload).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body[0]["status"], 404);
+        assert_eq!(body[1]["status"], 404);
+        let (header, editor) = bearer(EDITOR_TOKEN);
+        let req = test::TestRequest::post().uri("/batch").header(header, editor).set_json(&payload).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body[0]["status"], 200);
+        assert_eq!(body[1]["status"], 200);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_execute_too_large_batch() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/batch", web::post().to(BatchRoutes::execute))).await;
+
+        let payload = vec![json!({ "method": "GET", "path": "/recipes/tagCombos" }); MAX_BATCH_SIZE + 1];
+        let req = test::TestRequest::post().uri("/batch").set_json(&payload).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        cleanup_after(dao).await;
+    }
+}
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use actix_web::dev::ServiceRequest;
+use actix_web::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use serde::Serialize;
+
+pub const LARGE_BODY_THRESHOLD_ENV: &str = "LARGE_BODY_THRESHOLD_BYTES";
+pub const DEFAULT_LARGE_BODY_THRESHOLD_BYTES: u64 = 1 << 20;
+/// upper bounds of the histogram buckets, larger bodies go into a last unbounded bucket
+const BUCKET_BOUNDS: [u64; 6] = [1 << 10, 4 << 10, 16 << 10, 64 << 10, 256 << 10, 1 << 20];
+
+#[derive(Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub struct BodySizeBucket {
+    /// largest size in bytes counted by the bucket, none for the last one
+    pub le: Option<u64>,
+    pub count: u64,
+}
+
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct BodySizeStats {
+    pub count: u64,
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: u64,
+    /// bodies above the warning threshold
+    pub large: u64,
+    pub buckets: Vec<BodySizeBucket>,
+}
+
+/// Histogram of the sizes of the JSON request bodies since startup, by their `Content-Length`.
+/// Bodies above `LARGE_BODY_THRESHOLD_BYTES`, 1 MiB by default, are logged with route and request id
+/// to find the clients sending bloated recipes before they hit the hard limit of the payload
+#[derive(Debug, Clone)]
+pub struct BodySizeMetrics {
+    threshold: u64,
+    buckets: Arc<[AtomicU64; BUCKET_BOUNDS.len() + 1]>,
+    total_bytes: Arc<AtomicU64>,
+    large: Arc<AtomicU64>,
+}
+
+impl Default for BodySizeMetrics {
+    fn default() -> Self { Self::new(DEFAULT_LARGE_BODY_THRESHOLD_BYTES) }
+}
+
+impl BodySizeMetrics {
+    pub fn new(threshold: u64) -> Self {
+        Self {
+            threshold,
+            buckets: Arc::new(Default::default()),
+            total_bytes: Arc::new(AtomicU64::new(0)),
+            large: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// threshold from `LARGE_BODY_THRESHOLD_BYTES`, the default when unset or invalid
+    pub fn from_env() -> Self {
+        let threshold = std::env::var(LARGE_BODY_THRESHOLD_ENV).ok()
+            .and_then(|threshold| threshold.trim().parse::<u64>().ok())
+            .unwrap_or(DEFAULT_LARGE_BODY_THRESHOLD_BYTES);
+        info!("Loaded large body threshold={}", threshold);
+        Self::new(threshold)
+    }
+
+    /// records the size of a JSON body, returns the warning when the body is large.
+    /// Other bodies and bodies streamed without length are not recorded
+    pub fn observe(&self, req: &ServiceRequest, request_id: &str) -> Option<String> {
+        let is_json = req.headers().get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| content_type.contains("json"));
+        let size = req.headers().get(CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok())
+            .and_then(|length| length.trim().parse::<u64>().ok())
+            .filter(|_| is_json)?;
+        self.record(size);
+        if size <= self.threshold {
+            return None;
+        }
+        let total = self.large.fetch_add(1, Ordering::Relaxed) + 1;
+        let warning = format!("Large request body. route={} {}, size={}, threshold={}, total={}, request_id={}",
+                              req.method(), req.path(), size, self.threshold, total, request_id);
+        warn!("{}", warning);
+        Some(warning)
+    }
+
+    fn record(&self, size: u64) {
+        let bucket = BUCKET_BOUNDS.iter().position(|bound| size <= *bound).unwrap_or(BUCKET_BOUNDS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.total_bytes.fetch_add(size, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> BodySizeStats {
+        let buckets = self.buckets.iter().enumerate()
+            .map(|(index, count)| BodySizeBucket { le: BUCKET_BOUNDS.get(index).copied(), count: count.load(Ordering::Relaxed) })
+            .collect::<Vec<BodySizeBucket>>();
+        BodySizeStats {
+            count: buckets.iter().map(|bucket| bucket.count).sum(),
+            total_bytes: self.total_bytes.load(Ordering::Relaxed),
+            large: self.large.load(Ordering::Relaxed),
+            buckets,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod body_size_tests {
+    use actix_web::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+    use actix_web::test::TestRequest;
+
+    use crate::body_size::BodySizeMetrics;
+
+    #[test]
+    fn observe_json_bodies() {
+        let metrics = BodySizeMetrics::new(2000);
+        let json = |size: u64| TestRequest::post().uri("/api/v1/addOneRecipe")
+            .header(CONTENT_TYPE, "application/json")
+            .header(CONTENT_LENGTH, size.to_string())
+            .to_srv_request();
+
+        assert_eq!(metrics.observe(&json(500), "abc"), None);
+        assert_eq!(metrics.observe(&json(1500), "abc"), None);
+        let image = TestRequest::put().header(CONTENT_TYPE, "image/png").header(CONTENT_LENGTH, "500").to_srv_request();
+        assert_eq!(metrics.observe(&image, "abc"), None);
+
+        let stats = metrics.stats();
+        assert_eq!((stats.count, stats.total_bytes, stats.large), (2, 2000, 0));
+        assert_eq!(stats.buckets[0].count, 1);
+        assert_eq!(stats.buckets[1].count, 1);
+
+        let warning = metrics.observe(&json(3 << 20), "abc").unwrap();
+        assert_eq!(warning.contains("route=POST /api/v1/addOneRecipe"), true);
+        assert_eq!(warning.contains("request_id=abc"), true);
+        let stats = metrics.stats();
+        assert_eq!((stats.count, stats.large), (3, 1));
+        assert_eq!(stats.buckets.last().unwrap().count, 1);
+        assert_eq!(stats.buckets.last().unwrap().le, None);
+    }
+}
@@ -0,0 +1,357 @@
+use std::collections::HashSet;
+
+use actix_web::dev::RequestHead;
+use actix_web::http::header::CONTENT_LENGTH;
+use bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+use crate::model::recipe::{Recipe, RecipeFormatError};
+
+/// Request bodies above this size are imported chunk by chunk instead of being
+/// deserialized into a single `Vec<Recipe>`.
+pub const STREAMING_THRESHOLD_BYTES: u64 = 5 << 20;
+/// Amount of recipes flushed to the database with one `insert_many`.
+pub const IMPORT_CHUNK_SIZE: usize = 500;
+
+/// Route guard selecting the in-memory import for bodies with a known, small size
+pub fn is_small_payload(head: &RequestHead) -> bool {
+    head.headers.get(CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<u64>().ok())
+        .is_some_and(|length| length <= STREAMING_THRESHOLD_BYTES)
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct JsonFormatError { pub error: String }
+
+impl From<&str> for JsonFormatError {
+    fn from(error: &str) -> Self { Self { error: error.to_string() } }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum SplitterState {
+    BeforeArray,
+    BetweenElements,
+    InElement,
+    AfterArray,
+}
+
+/// Incrementally splits a JSON array of objects into the raw bytes of its elements,
+/// so the elements can be deserialized one by one while the body is still streaming in.
+pub struct JsonArraySplitter {
+    state: SplitterState,
+    element: Vec<u8>,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    expect_separator: bool,
+    element_count: usize,
+}
+
+impl Default for JsonArraySplitter {
+    fn default() -> Self { Self::new() }
+}
+
+impl JsonArraySplitter {
+    pub fn new() -> Self {
+        Self {
+            state: SplitterState::BeforeArray,
+            element: Vec::new(),
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            expect_separator: false,
+            element_count: 0,
+        }
+    }
+
+    /// returns all elements completed by this chunk
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Vec<Vec<u8>>, JsonFormatError> {
+        let mut elements = Vec::new();
+        for &byte in chunk {
+            match self.state {
+                SplitterState::BeforeArray => match byte {
+                    b'[' => self.state = SplitterState::BetweenElements,
+                    _ if byte.is_ascii_whitespace() => {}
+                    _ => return Err("Expected a JSON array".into())
+                },
+                SplitterState::BetweenElements => match byte {
+                    _ if byte.is_ascii_whitespace() => {}
+                    b',' if self.expect_separator => self.expect_separator = false,
+                    b']' if self.expect_separator || self.element_count == 0 => {
+                        self.state = SplitterState::AfterArray
+                    }
+                    b'{' if !self.expect_separator => {
+                        self.state = SplitterState::InElement;
+                        self.depth = 1;
+                        self.element.push(byte);
+                    }
+                    _ => return Err("Expected an object or the end of the array".into())
+                },
+                SplitterState::InElement => {
+                    self.element.push(byte);
+                    if self.in_string {
+                        match byte {
+                            _ if self.escaped => self.escaped = false,
+                            b'\\' => self.escaped = true,
+                            b'"' => self.in_string = false,
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    match byte {
+                        b'"' => self.in_string = true,
+                        b'{' | b'[' => self.depth += 1,
+                        b'}' | b']' => {
+                            self.depth -= 1;
+                            if self.depth == 0 {
+                                elements.push(std::mem::take(&mut self.element));
+                                self.element_count += 1;
+                                self.state = SplitterState::BetweenElements;
+                                self.expect_separator = true;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                SplitterState::AfterArray => match byte {
+                    _ if byte.is_ascii_whitespace() => {}
+                    _ => return Err("Unexpected content after the end of the array".into())
+                }
+            }
+        }
+        Ok(elements)
+    }
+
+    pub fn finish(&self) -> Result<(), JsonFormatError> {
+        match self.state {
+            SplitterState::AfterArray => Ok(()),
+            _ => Err("Unexpected end of the JSON array".into())
+        }
+    }
+}
+
+/// What happens to a recipe whose preserved id is stored already
+#[derive(Deserialize, Debug, Clone, Copy, Eq, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    #[default]
+    Skip,
+    Replace,
+}
+
+/// `?preserveIds=true` restores the recipes with the ids they were exported with instead of new ones,
+/// `&onConflict=skip|replace` decides about ids stored already, skipping them by default
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct ImportParams {
+    #[serde(rename = "preserveIds")]
+    pub preserve_ids: Option<bool>,
+    #[serde(rename = "onConflict")]
+    pub on_conflict: Option<ConflictPolicy>,
+}
+
+impl ImportParams {
+    pub fn preserves_ids(&self) -> bool {
+        self.preserve_ids.unwrap_or(false)
+    }
+
+    /// stored recipes are overwritten, which only admins may do
+    pub fn replaces_stored(&self) -> bool {
+        self.preserves_ids() && self.on_conflict == Some(ConflictPolicy::Replace)
+    }
+}
+
+/// A recipe as exported, together with the id it had, which is only used when preserving ids
+#[derive(Deserialize, Debug, Clone)]
+pub struct ImportedRecipe {
+    pub id: Option<String>,
+    #[serde(flatten)]
+    pub recipe: Recipe,
+}
+
+impl ImportedRecipe {
+    /// keeps the exported id when preserving ids, recipes without one get a new id
+    pub fn into_recipe(self, preserve_ids: bool) -> Result<Recipe, RecipeFormatError> {
+        let mut recipe = self.recipe;
+        if let (true, Some(id)) = (preserve_ids, self.id) {
+            recipe._id = ObjectId::with_string(&id)
+                .map_err(|_| RecipeFormatError::from(format!("The id '{}' is no valid ObjectId", id)))?;
+        }
+        Ok(recipe)
+    }
+}
+
+/// the recipes of an import, an id must not appear twice when preserving ids
+pub fn imported_recipes(imported: Vec<ImportedRecipe>, preserve_ids: bool) -> Result<Vec<Recipe>, RecipeFormatError> {
+    let recipes = imported.into_iter()
+        .map(|recipe| recipe.into_recipe(preserve_ids))
+        .collect::<Result<Vec<Recipe>, RecipeFormatError>>()?;
+    let mut ids = HashSet::new();
+    match recipes.iter().find(|recipe| preserve_ids && !ids.insert(recipe._id.to_hex())) {
+        Some(duplicate) => Err(format!("The id '{}' appears more than once", duplicate._id.to_hex()).into()),
+        None => Ok(recipes),
+    }
+}
+
+/// Ids of an import preserving ids by what happened to them
+#[derive(Serialize, Debug, Clone, Default, Eq, PartialEq)]
+pub struct RestoreResult {
+    pub inserted: Vec<String>,
+    pub replaced: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct ChunkResult {
+    pub chunk: usize,
+    pub inserted: usize,
+    pub invalid: usize,
+    /// only when preserving ids
+    pub replaced: usize,
+    pub skipped: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, Eq, PartialEq)]
+pub struct ImportSummary {
+    pub inserted: usize,
+    pub invalid: usize,
+    pub replaced: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub chunks: Vec<ChunkResult>,
+    /// recipes stored although they look like a data entry mistake
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl ImportSummary {
+    pub fn add_chunk(&mut self, result: ChunkResult, chunk_size: usize) {
+        self.inserted += result.inserted;
+        self.invalid += result.invalid;
+        self.replaced += result.replaced;
+        self.skipped += result.skipped;
+        if result.error.is_some() {
+            self.failed += chunk_size;
+        }
+        self.chunks.push(result);
+    }
+}
+
+/// Outcome of validating one element of an import without storing it
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct ValidationResult {
+    pub valid: bool,
+    pub errors: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl From<Result<(), String>> for ValidationResult {
+    fn from(result: Result<(), String>) -> Self {
+        match result {
+            Ok(_) => Self { valid: true, errors: vec![], warnings: vec![] },
+            Err(err) => Self { valid: false, errors: vec![err], warnings: vec![] },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod bulk_import_tests {
+    use actix_web::test::TestRequest;
+
+    use serde_json::json;
+
+    use crate::bulk_import::{ChunkResult, imported_recipes, ImportedRecipe, ImportSummary, is_small_payload, JsonArraySplitter, STREAMING_THRESHOLD_BYTES};
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+
+    fn split_all(chunks: &[&str]) -> Vec<String> {
+        let mut splitter = JsonArraySplitter::new();
+        let elements = chunks.iter()
+            .flat_map(|chunk| splitter.feed(chunk.as_bytes()).unwrap())
+            .map(|element| String::from_utf8(element).unwrap())
+            .collect();
+        splitter.finish().unwrap();
+        elements
+    }
+
+    #[test]
+    fn split_array_in_one_chunk() {
+        let elements = split_all(&[r#"[{"a": 1}, {"b": [1, 2, {"c": 3}]}]"#]);
+        assert_eq!(elements, vec![r#"{"a": 1}"#, r#"{"b": [1, 2, {"c": 3}]}"#]);
+    }
+
+    #[test]
+    fn split_array_over_many_chunks() {
+        let elements = split_all(&[" [{\"a\"", ": \"x}\"", "}", ",{\"b\":", "\"\\\"]\"}", "] "]);
+        assert_eq!(elements, vec![r#"{"a": "x}"}"#, r#"{"b":"\"]"}"#]);
+    }
+
+    #[test]
+    fn split_empty_array() {
+        assert_eq!(split_all(&["[", " ]"]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn split_invalid_input() {
+        assert_eq!(JsonArraySplitter::new().feed(b"{}").is_err(), true);
+        assert_eq!(JsonArraySplitter::new().feed(b"[1, 2]").is_err(), true);
+        assert_eq!(JsonArraySplitter::new().feed(b"[{} {}]").is_err(), true);
+        assert_eq!(JsonArraySplitter::new().feed(b"[{},]").is_err(), true);
+        assert_eq!(JsonArraySplitter::new().feed(b"[{}] x").is_err(), true);
+
+        let mut splitter = JsonArraySplitter::new();
+        splitter.feed(b"[{}").unwrap();
+        assert_eq!(splitter.feed(b",]").is_err(), true);
+
+        let mut splitter = JsonArraySplitter::new();
+        splitter.feed(b"[{}").unwrap();
+        assert_eq!(splitter.finish().is_err(), true);
+    }
+
+    #[test]
+    fn small_payload_guard() {
+        let req = TestRequest::post().header("content-length", "100").to_http_request();
+        assert_eq!(is_small_payload(req.head()), true);
+
+        let req = TestRequest::post()
+            .header("content-length", (STREAMING_THRESHOLD_BYTES + 1).to_string()).to_http_request();
+        assert_eq!(is_small_payload(req.head()), false);
+
+        let req = TestRequest::post().header("transfer-encoding", "chunked").to_http_request();
+        assert_eq!(is_small_payload(req.head()), false);
+    }
+
+    #[test]
+    fn summary_accumulates_chunks() {
+        let mut summary = ImportSummary::default();
+        summary.add_chunk(ChunkResult { chunk: 0, inserted: 3, invalid: 1, replaced: 1, skipped: 0, error: None }, 3);
+        summary.add_chunk(ChunkResult { chunk: 1, inserted: 0, invalid: 0, replaced: 0, skipped: 0, error: Some("down".to_string()) }, 2);
+        assert_eq!(summary.inserted, 3);
+        assert_eq!(summary.replaced, 1);
+        assert_eq!(summary.invalid, 1);
+        assert_eq!(summary.failed, 2);
+        assert_eq!(summary.chunks.len(), 2);
+    }
+
+    fn imported(id: &str) -> ImportedRecipe {
+        let mut recipe = serde_json::to_value(create_one_recipe_without_image()).unwrap();
+        recipe["id"] = json!(id);
+        serde_json::from_value(recipe).unwrap()
+    }
+
+    #[test]
+    fn imported_recipes_preserve_ids() {
+        let id = "5f7333360051027600b01a36";
+        let recipes = imported_recipes(vec![imported(id)], true).unwrap();
+        assert_eq!(recipes[0]._id.to_hex(), id);
+        let recipes = imported_recipes(vec![imported(id)], false).unwrap();
+        assert_eq!(recipes[0]._id.to_hex() == id, false);
+
+        assert_eq!(imported_recipes(vec![imported("no-object-id")], true).unwrap_err().error.contains("no-object-id"), true);
+        assert_eq!(imported_recipes(vec![imported("no-object-id")], false).is_ok(), true);
+        assert_eq!(imported_recipes(vec![imported(id), imported(id)], true).is_err(), true);
+    }
+}
@@ -0,0 +1,181 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+pub const FAILURE_THRESHOLD_ENV: &str = "CIRCUIT_BREAKER_FAILURES";
+pub const COOL_DOWN_ENV: &str = "CIRCUIT_BREAKER_COOL_DOWN_SECONDS";
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+pub const DEFAULT_COOL_DOWN_SECONDS: u64 = 30;
+
+#[derive(Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum BreakerState {
+    /// database calls pass
+    Closed,
+    /// database calls are refused until the cool-down ended
+    Open,
+    /// the cool-down ended, the next call probes whether the database is back
+    HalfOpen,
+}
+
+/// Result of a database call as seen by the breaker
+pub trait QueryOutcome {
+    /// true when the call failed because the database is unreachable or timed out,
+    /// other errors like duplicate keys show a working database
+    fn is_outage(&self) -> bool;
+}
+
+/// Stops calling the database after `failure_threshold` outages in a row, calls fail fast for the
+/// cool-down instead of waiting for timeouts. Afterwards one call probes the database, its success
+/// closes the breaker and its failure opens it for another cool-down. Configured via
+/// `CIRCUIT_BREAKER_FAILURES` and `CIRCUIT_BREAKER_COOL_DOWN_SECONDS`, the defaults when unset or invalid
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cool_down: Duration,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    probe_started_at: Option<Instant>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self { Self::new(DEFAULT_FAILURE_THRESHOLD, Duration::from_secs(DEFAULT_COOL_DOWN_SECONDS)) }
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cool_down: Duration) -> Self {
+        Self { failure_threshold: failure_threshold.max(1), cool_down, state: Arc::new(Mutex::new(State::default())) }
+    }
+
+    pub fn from_env() -> Self {
+        let failure_threshold = std::env::var(FAILURE_THRESHOLD_ENV).ok()
+            .and_then(|threshold| threshold.parse::<u32>().ok())
+            .unwrap_or(DEFAULT_FAILURE_THRESHOLD);
+        let cool_down = std::env::var(COOL_DOWN_ENV).ok()
+            .and_then(|seconds| seconds.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_COOL_DOWN_SECONDS);
+        let breaker = Self::new(failure_threshold, Duration::from_secs(cool_down));
+        info!("Loaded circuit breaker={:?}", breaker);
+        breaker
+    }
+
+    pub fn state(&self) -> BreakerState {
+        match self.state.lock() {
+            Ok(state) => self.state_of(&state),
+            Err(_) => BreakerState::Closed,
+        }
+    }
+
+    fn state_of(&self, state: &State) -> BreakerState {
+        match state.opened_at {
+            None => BreakerState::Closed,
+            Some(opened_at) if opened_at.elapsed() < self.cool_down => BreakerState::Open,
+            Some(_) => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Ok when the call may go to the database, otherwise the time after which to retry.
+    /// While half open only one call at a time probes, a probe without outcome is replaced after a cool-down
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return Ok(()),
+        };
+        match (self.state_of(&state), state.opened_at, state.probe_started_at) {
+            (BreakerState::Closed, _, _) => Ok(()),
+            (BreakerState::Open, Some(opened_at), _) => Err(self.cool_down.saturating_sub(opened_at.elapsed())),
+            (_, _, Some(probe_started_at)) if probe_started_at.elapsed() < self.cool_down => Err(self.cool_down.saturating_sub(probe_started_at.elapsed())),
+            _ => {
+                info!("Circuit breaker half open, probing the database");
+                state.probe_started_at = Some(Instant::now());
+                Ok(())
+            }
+        }
+    }
+
+    pub fn record(&self, outage: bool) {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return,
+        };
+        if !outage {
+            if state.opened_at.is_some() {
+                info!("Database reachable again, closing the circuit breaker");
+            }
+            *state = State::default();
+            return;
+        }
+
+        state.consecutive_failures += 1;
+        let probe_failed = state.probe_started_at.take().is_some();
+        if probe_failed || (state.opened_at.is_none() && state.consecutive_failures >= self.failure_threshold) {
+            warn!("Opening the circuit breaker for {}s after {} failed database calls", self.cool_down.as_secs(), state.consecutive_failures);
+            state.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+/// whole seconds to wait, at least one, for a `Retry-After` header
+pub fn retry_after_seconds(retry_after: Duration) -> u64 {
+    (retry_after.as_millis() as u64).div_ceil(1000).max(1)
+}
+
+
+#[cfg(test)]
+mod circuit_breaker_tests {
+    use std::time::Duration;
+
+    use crate::circuit_breaker::{BreakerState, CircuitBreaker, retry_after_seconds};
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+        breaker.record(true);
+        breaker.record(true);
+        breaker.record(false);
+        breaker.record(true);
+        breaker.record(true);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert_eq!(breaker.try_acquire(), Ok(()));
+
+        breaker.record(true);
+        assert_eq!(breaker.state(), BreakerState::Open);
+        let retry_after = breaker.try_acquire().unwrap_err();
+        assert_eq!(retry_after <= Duration::from_secs(60), true);
+        assert_eq!(retry_after > Duration::from_secs(59), true);
+    }
+
+    #[test]
+    fn half_open_probe_closes_or_reopens() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(20));
+        breaker.record(true);
+        assert_eq!(breaker.state(), BreakerState::Open);
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        assert_eq!(breaker.try_acquire(), Ok(()));
+        assert_eq!(breaker.try_acquire().is_err(), true);
+        breaker.record(true);
+        assert_eq!(breaker.state(), BreakerState::Open);
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert_eq!(breaker.try_acquire(), Ok(()));
+        breaker.record(false);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert_eq!(breaker.try_acquire(), Ok(()));
+        assert_eq!(breaker.try_acquire(), Ok(()));
+    }
+
+    #[test]
+    fn retry_after_in_whole_seconds() {
+        assert_eq!(retry_after_seconds(Duration::from_millis(0)), 1);
+        assert_eq!(retry_after_seconds(Duration::from_millis(1001)), 2);
+        assert_eq!(retry_after_seconds(Duration::from_secs(30)), 30);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::model::recipe::{Recipe, RecipeFormatError};
+
+pub const ALLOWED_CUISINES_ENV: &str = "ALLOWED_CUISINES";
+pub const ALLOWED_LANGUAGES_ENV: &str = "ALLOWED_LANGUAGES";
+const LIST_SEPARATOR: char = ',';
+
+/// Allowed values of the optional `cuisine` and `language` of a recipe, configured via
+/// `ALLOWED_CUISINES=Italian,French` and `ALLOWED_LANGUAGES=de,en`.
+/// An empty list is not enforced, values are compared case insensitive and stored as spelled in the list
+#[derive(Debug, Clone, Default)]
+pub struct ClassificationAllowlist {
+    cuisines: Vec<String>,
+    languages: Vec<String>,
+}
+
+impl ClassificationAllowlist {
+    pub fn from_env() -> Self {
+        let allowlist = ClassificationAllowlist::parse(
+            &std::env::var(ALLOWED_CUISINES_ENV).unwrap_or_default(),
+            &std::env::var(ALLOWED_LANGUAGES_ENV).unwrap_or_default());
+        info!("Loaded classification allowlist={:?}", allowlist);
+        allowlist
+    }
+
+    pub fn parse(cuisines: &str, languages: &str) -> Self {
+        Self { cuisines: split_list(cuisines), languages: split_list(languages) }
+    }
+
+    pub fn validate(&self, recipe: &Recipe) -> Result<(), RecipeFormatError> {
+        if let Some(cuisine) = &recipe.cuisine {
+            if !is_allowed(&self.cuisines, cuisine) {
+                return Err(format!("Cuisine '{}' is not one of {}", cuisine, self.cuisines.join(", ")).into());
+            }
+        }
+        if let Some(language) = &recipe.language {
+            if !is_allowed(&self.languages, language) {
+                return Err(format!("Language '{}' is not one of {}", language, self.languages.join(", ")).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// `validate`, then replaces the values by their spelling in the allowlist, so `italian` is stored as `Italian`
+    pub fn canonicalize(&self, recipe: &mut Recipe) -> Result<(), RecipeFormatError> {
+        self.validate(recipe)?;
+        recipe.cuisine = recipe.cuisine.take().map(|cuisine| canonical(&self.cuisines, cuisine));
+        recipe.language = recipe.language.take().map(|language| canonical(&self.languages, language));
+        Ok(())
+    }
+}
+
+fn canonical(allowed: &[String], value: String) -> String {
+    allowed.iter().find(|allowed| allowed.eq_ignore_ascii_case(value.trim())).cloned().unwrap_or(value)
+}
+
+fn is_allowed(allowed: &[String], value: &str) -> bool {
+    allowed.is_empty() || allowed.iter().any(|allowed| allowed.eq_ignore_ascii_case(value.trim()))
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(LIST_SEPARATOR)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+
+#[cfg(test)]
+mod classification_tests {
+    use crate::classification::ClassificationAllowlist;
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+
+    #[test]
+    fn empty_allowlist_is_not_enforced() {
+        let mut recipe = create_one_recipe_without_image();
+        recipe.cuisine = Some("Martian".to_string());
+        recipe.language = Some("xx".to_string());
+        assert_eq!(ClassificationAllowlist::parse("", " , ").validate(&recipe).is_ok(), true);
+    }
+
+    #[test]
+    fn allowlist_rejects_unknown_values() {
+        let allowlist = ClassificationAllowlist::parse("Italian, French", "de,en");
+        let mut recipe = create_one_recipe_without_image();
+        assert_eq!(allowlist.validate(&recipe).is_ok(), true);
+
+        recipe.cuisine = Some("italian".to_string());
+        recipe.language = Some("DE".to_string());
+        assert_eq!(allowlist.validate(&recipe).is_ok(), true);
+
+        recipe.cuisine = Some("Martian".to_string());
+        assert_eq!(allowlist.validate(&recipe).is_err(), true);
+
+        recipe.cuisine = None;
+        recipe.language = Some("fr".to_string());
+        assert_eq!(allowlist.validate(&recipe).is_err(), true);
+    }
+
+    #[test]
+    fn allowed_values_get_the_allowlist_spelling() {
+        let allowlist = ClassificationAllowlist::parse("Italian, French", "de,en");
+        let mut recipe = create_one_recipe_without_image();
+        recipe.cuisine = Some(" italian ".to_string());
+        recipe.language = Some("DE".to_string());
+        allowlist.canonicalize(&mut recipe).unwrap();
+        assert_eq!(recipe.cuisine, Some("Italian".to_string()));
+        assert_eq!(recipe.language, Some("de".to_string()));
+
+        recipe.cuisine = Some("Martian".to_string());
+        assert_eq!(allowlist.canonicalize(&mut recipe).is_err(), true);
+
+        let mut recipe = create_one_recipe_without_image();
+        recipe.cuisine = Some("Martian".to_string());
+        ClassificationAllowlist::parse("", "").canonicalize(&mut recipe).unwrap();
+        assert_eq!(recipe.cuisine, Some("Martian".to_string()));
+    }
+}
@@ -0,0 +1,153 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use actix_web::web::Json;
+use bson::oid::ObjectId;
+
+use crate::auth::{Identity, Role};
+use crate::dao::Dao;
+use crate::error_body::ErrorBody;
+use crate::model::collection_assignment::AddManyRequest;
+use crate::quick_recipes::quick_recipes;
+use crate::recipe_filter::RecipeFilter;
+use crate::recipe_routes::{dao_error_response, extract_id_from_req};
+
+/// upper bound of recipes added to a collection by one request
+const MAX_ADD_MANY: usize = 1000;
+
+pub struct CollectionRoutes {}
+
+impl CollectionRoutes {
+    /// adds the listed or filtered recipes to the collection, requires the editor role
+    pub async fn add_many_recipes(req: HttpRequest, identity: Identity, body: Json<AddManyRequest>, database: web::Data<Dao>) -> HttpResponse {
+        if !identity.has_role(Role::Editor) {
+            return HttpResponse::Forbidden().json(ErrorBody::new("Editor role required"));
+        }
+        let quick = quick_recipes(&req);
+        let collection_id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return HttpResponse::BadRequest().finish()
+        };
+
+        let recipe_ids = match body.into_inner() {
+            AddManyRequest { recipe_ids: Some(ids), filter: None } => match parse_object_ids(&ids) {
+                Ok(ids) => ids,
+                Err(id) => return HttpResponse::BadRequest().json(ErrorBody::for_field("Recipe id is no object id", "recipeIds", &id))
+            },
+            AddManyRequest { recipe_ids: None, filter: Some(filter) } => {
+                let filter = match (RecipeFilter { quick_recipes: quick, ..filter }).to_document() {
+                    Ok(filter) => filter,
+                    Err(err) => return HttpResponse::BadRequest().json(ErrorBody::new(&err.error))
+                };
+                // one more than allowed is enough to tell the filter matches too many
+                match database.get_first_recipe_ids(filter, Some(MAX_ADD_MANY as i64 + 1)).await {
+                    Ok(ids) => ids,
+                    Err(err) => return dao_error_response(err)
+                }
+            }
+            _ => return HttpResponse::BadRequest().json(ErrorBody::new("Provide either recipeIds or a filter"))
+        };
+        if recipe_ids.len() > MAX_ADD_MANY {
+            return HttpResponse::BadRequest().json(ErrorBody::new(
+                &format!("At most {} recipes can be added at once", MAX_ADD_MANY)));
+        }
+
+        info!("Adding {} recipes to collection={} for user={}", recipe_ids.len(), collection_id, identity.user);
+        match database.add_recipes_to_collection(collection_id, recipe_ids).await {
+            Ok(result) => HttpResponse::Ok().json(result),
+            Err(err) => dao_error_response(err),
+        }
+    }
+}
+
+/// the collection the caller may add recipes to: editors their own ones, admins all including those without owner
+pub(crate) async fn writable_collection(database: &Dao, identity: Option<&Identity>, id: &str) -> Result<ObjectId, HttpResponse> {
+    let identity = identity
+        .ok_or_else(|| HttpResponse::Unauthorized().json(ErrorBody::new("Missing or unknown api token")))?;
+    if !identity.has_role(Role::Editor) {
+        return Err(HttpResponse::Forbidden().json(ErrorBody::new("Editor role required")));
+    }
+    let id = ObjectId::with_string(id)
+        .map_err(|_| HttpResponse::BadRequest().json(ErrorBody::for_field("Collection id is no object id", "collectionId", id)))?;
+    match database.get_collection_owner(id.clone()).await {
+        Ok(_) if identity.has_role(Role::Admin) => Ok(id),
+        Ok(Some(owner)) if owner == identity.user => Ok(id),
+        Ok(Some(_)) => Err(HttpResponse::Forbidden().json(ErrorBody::new("The collection belongs to another user"))),
+        Ok(None) => Err(HttpResponse::Forbidden().json(ErrorBody::new("Admin role required for a collection without owner"))),
+        Err(err) => Err(dao_error_response(err)),
+    }
+}
+
+/// returns the first invalid id on error
+pub(crate) fn parse_object_ids(ids: &[String]) -> Result<Vec<ObjectId>, String> {
+    ids.iter()
+        .map(|id| ObjectId::with_string(id).map_err(|_| id.clone()))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{App, test, web};
+    use actix_web::http::StatusCode;
+    use bson::oid::ObjectId;
+    use serde_json::{json, Value};
+    use serial_test::serial;
+
+    use crate::auth::auth_tests::{bearer, create_api_tokens, EDITOR_TOKEN};
+    use crate::collection_routes::{CollectionRoutes, parse_object_ids};
+    use crate::dao::dao_tests::{before, cleanup_after, create_many_recipes_without_images};
+
+    #[test]
+    fn parse_object_ids_test() {
+        let id = ObjectId::new();
+        assert_eq!(parse_object_ids(&[id.to_hex()]), Ok(vec![id.clone()]));
+        assert_eq!(parse_object_ids(&[id.to_hex(), "nope".to_string()]), Err("nope".to_string()));
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_add_many_recipes_to_collection() {
+        let dao = before().await;
+        let ids = dao.add_many_recipes(create_many_recipes_without_images(3)).await.unwrap()
+            .as_array().unwrap().iter()
+            .map(|id| id.as_object_id().unwrap().to_hex())
+            .collect::<Vec<String>>();
+        let collection_id = ObjectId::new();
+        dao.database.collection("collections").insert_one(
+            doc! {"_id": collection_id.clone(), "name": "Favourites", "recipeIds": [ObjectId::with_string(&ids[0]).unwrap()]},
+            None).await.unwrap();
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/collections/{id}/addMany", web::post().to(CollectionRoutes::add_many_recipes))).await;
+        let uri = format!("/collections/{}/addMany", collection_id);
+
+        let req = test::TestRequest::post().uri(&uri).set_json(&json!({ "recipeIds": ids })).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let (header, value) = bearer(EDITOR_TOKEN);
+        let payload = json!({ "recipeIds": [ids[0], ids[1], ids[2], ids[2], ObjectId::new().to_hex()] });
+        let req = test::TestRequest::post().uri(&uri).header(header, value.clone()).set_json(&payload).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body, json!({ "added": 2, "skipped": 3 }));
+
+        let collection = dao.database.collection("collections")
+            .find_one(doc! {"_id": collection_id.clone()}, None).await.unwrap().unwrap();
+        assert_eq!(collection.get_array("recipeIds").unwrap().len(), 3);
+
+        let req = test::TestRequest::post().uri(&uri).header(header, value.clone())
+            .set_json(&json!({ "recipeIds": ["nope"] })).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::post().uri(&format!("/collections/{}/addMany", ObjectId::new()))
+            .header(header, value).set_json(&json!({ "filter": {} })).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        cleanup_after(dao).await;
+    }
+}
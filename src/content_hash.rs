@@ -0,0 +1,107 @@
+use bson::{Bson, Document};
+use ring::digest::{digest, SHA256};
+use serde::Serialize;
+
+/// hex SHA-256 of the content fields, stored with every recipe written
+pub const JSON_ATTR_CONTENT_HASH: &str = "contentHash";
+
+/// The fields sent by clients. Dates, version, status, slug and the image change through their own
+/// endpoints without the content changing, derived fields follow from the hashed ones and the hash
+/// itself is left out so that it doesn't depend on itself
+const HASHED_FIELDS: [&str; 16] = [
+    "title", "description", "ingredients", "instructions", "tags", "difficulty", "cookingTimeInMinutes",
+    "defaultServings", "yield", "equipment", "cuisine", "language", "notes", "source", "stepTimers", "templateIds",
+];
+
+/// Hash of the content fields of a recipe document as stored, encrypted fields with their ciphertext.
+/// The fields are hashed as BSON in a fixed order, missing ones as null, so the hash of a read
+/// document equals the one computed on write unless the content changed in between
+pub fn content_hash(doc: &Document) -> String {
+    let content = HASHED_FIELDS.iter()
+        .map(|field| (field.to_string(), doc.get(field).cloned().unwrap_or(Bson::Null)))
+        .collect::<Document>();
+    let mut bytes = Vec::new();
+    content.to_writer(&mut bytes).expect("Writing a document into memory cannot fail");
+    digest(&SHA256, &bytes).as_ref().iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// a recipe whose content no longer matches the hash stored with it
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct IntegrityMismatch {
+    pub id: String,
+    pub title: Option<String>,
+    pub stored: String,
+    pub computed: String,
+}
+
+/// Result of recomputing the hashes of all recipes, `unhashed` counts the recipes stored before
+/// hashes were, they get one with their next write
+#[derive(Serialize, Debug, Clone, Default, Eq, PartialEq)]
+pub struct IntegrityReport {
+    pub checked: u64,
+    pub unhashed: u64,
+    pub mismatches: Vec<IntegrityMismatch>,
+}
+
+impl IntegrityReport {
+    /// compares the stored with the recomputed hash of a recipe document
+    pub fn check(&mut self, doc: &Document) {
+        self.checked += 1;
+        let stored = match doc.get_str(JSON_ATTR_CONTENT_HASH) {
+            Ok(stored) => stored,
+            Err(_) => {
+                self.unhashed += 1;
+                return;
+            }
+        };
+        let computed = content_hash(doc);
+        if computed != stored {
+            self.mismatches.push(IntegrityMismatch {
+                id: doc.get_object_id("_id").map(|id| id.to_hex()).unwrap_or_default(),
+                title: doc.get_str("title").ok().map(String::from),
+                stored: stored.to_string(),
+                computed,
+            });
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod content_hash_tests {
+    use crate::content_hash::{content_hash, IntegrityReport, JSON_ATTR_CONTENT_HASH};
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+
+    #[test]
+    fn hash_covers_only_the_content() {
+        let doc = bson::Document::from(create_one_recipe_without_image());
+        let hash = content_hash(&doc);
+        assert_eq!(hash.len(), 64);
+
+        let mut metadata_changed = doc.clone();
+        metadata_changed.insert("version", 7);
+        metadata_changed.insert(JSON_ATTR_CONTENT_HASH, "abc");
+        assert_eq!(content_hash(&metadata_changed), hash);
+
+        let mut content_changed = doc.clone();
+        content_changed.insert("title", "Changed out of band");
+        assert_ne!(content_hash(&content_changed), hash);
+    }
+
+    #[test]
+    fn report_mismatches() {
+        let mut doc = bson::Document::from(create_one_recipe_without_image());
+        let mut report = IntegrityReport::default();
+        report.check(&doc);
+
+        doc.insert(JSON_ATTR_CONTENT_HASH, content_hash(&doc));
+        report.check(&doc);
+        doc.insert("description", "corrupted");
+        report.check(&doc);
+
+        assert_eq!((report.checked, report.unhashed, report.mismatches.len()), (3, 1, 1));
+        assert_eq!(report.mismatches[0].computed, content_hash(&doc));
+    }
+}
@@ -1,28 +1,104 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::TryFrom;
+use std::future::Future;
+use std::time::Duration;
 
 use bson::Document;
 use bson::document::ValueAccessError;
 use bson::oid::ObjectId;
+use chrono::{DateTime, Utc};
 use futures_util::StreamExt;
-use mongodb::{bson::{Bson, doc}, Client, options::FindOptions};
-use mongodb::Database;
-use mongodb::error::Error;
-use mongodb::options::{ClientOptions, FindOneOptions, UpdateModifications};
+use mongodb::{bson::Bson, Client, options::FindOptions};
+use mongodb::{Cursor, Database};
+use mongodb::error::{Error, ErrorKind, WriteFailure};
+use mongodb::options::{AggregateOptions, ClientOptions, FindOneAndUpdateOptions, FindOneOptions, ReplaceOptions, ReturnDocument, UpdateModifications, UpdateOptions};
 
 use crate::{LogExtensionErr, LogExtensionOk};
-use crate::model::recipe::{Recipe, RecipeFormatError};
-use crate::pagination::Pagination;
+use crate::bulk_import::{ConflictPolicy, RestoreResult};
+use crate::circuit_breaker::{CircuitBreaker, QueryOutcome};
+use crate::content_hash::{content_hash, IntegrityReport, JSON_ATTR_CONTENT_HASH};
+use crate::auth::Identity;
+use crate::field_encryption::{FieldEncryption, keep_hidden_fields};
+use crate::field_modified::{FieldModifiedTracking, JSON_ATTR_FIELD_MODIFIED};
+use crate::list_response::RecipeCount;
+use crate::model::collection_assignment::AddManyResult;
+use crate::model::duplicate_group::DuplicateGroup;
+use crate::model::full_recipe::FullRecipe;
+use crate::model::ingredient_template::IngredientTemplate;
+use crate::model::ingredients::Ingredient;
+use crate::model::meal_plan::MealPlan;
+use crate::model::query_explanation::QueryExplanation;
+use crate::model::similar_recipe::SimilarRecipe;
+use crate::model::tag_combo::TagCombo;
+use crate::model::trending_recipe::TrendingRecipe;
+use crate::model::unit_definition::UnitDefinition;
+use crate::model::recipe::{normalize_tags, Recipe, RecipeFormatError};
+use crate::model::recipe_diff::{changed_fields, merge_recipes};
+use crate::model::recipe_group::{GroupBy, RecipeGroup};
+use crate::model::recipe_status::RecipeStatus;
+use crate::model::step_timer::realign_step_timers;
+use crate::model::retag_rule::RetagRule;
+use crate::model::recipe_summary::{JSON_ATTR_INGREDIENT_COUNT, RecipeSummary};
+use crate::pagination::{DefaultSort, Pagination};
+use crate::recipe_filter::is_unfiltered;
+use crate::read_preference::ReadPreferenceSettings;
+use crate::recipe_cache::{CacheMiss, RecipeCache};
+use crate::single_flight::ReadCoalescing;
+use crate::slow_query::SlowQueryLog;
+use crate::slug::{slugify, unique_slug};
+use crate::title_constraint::{fold_title, JSON_ATTR_FOLDED_TITLE, TitleConstraint};
+use crate::write_concern::WriteConcernSettings;
 
 const RECIPE_COLLECTION: &str = "recipes";
+const COLLECTIONS_COLLECTION: &str = "collections";
+const RATINGS_COLLECTION: &str = "ratings";
+/// fields of a recipe document too large to carry through joins and sorts
+const LARGE_RECIPE_FIELDS: [&str; 2] = ["image", "thumbnail"];
+const COMMENTS_COLLECTION: &str = "comments";
+const RECIPE_VERSIONS_COLLECTION: &str = "recipe_versions";
+const TEMPLATES_COLLECTION: &str = "ingredient_templates";
+const UNITS_COLLECTION: &str = "units";
+const MEAL_PLANS_COLLECTION: &str = "meal_plans";
+/// capped, the oldest view events are dropped once it is full
+const RECIPE_VIEWS_COLLECTION: &str = "recipe_views";
+const RECIPE_VIEWS_SIZE_BYTES: i64 = 64 << 20;
+const NAMESPACE_EXISTS_ERROR_CODE: i32 = 48;
 const URL: &str = "mongodb://localhost:26666";
 const APP_NAME: &str = "Zellinotes recipes";
 const DATABASE: &str = "zellinotes_recipes";
+const DUPLICATE_KEY_ERROR_CODE: i32 = 11000;
+const INDEX_NOT_FOUND_ERROR_CODE: i32 = 27;
+const UNAUTHORIZED_ERROR_CODE: i32 = 13;
+const MAX_TIME_EXPIRED_ERROR_CODE: i32 = 50;
+const JSON_ATTR_SLUG: &str = "slug";
+const SLUG_INDEX: &str = "slug_1";
+const TITLE_INDEX: &str = "title_1";
+const TITLE_PER_AUTHOR_INDEX: &str = "author_1_foldedTitle_1";
+const SLUG_INSERT_ATTEMPTS: usize = 3;
+const DELETE_CHUNK_SIZE: usize = 1000;
+/// a merging update reads the recipe again when it changed between reading and writing
+pub const MERGE_ATTEMPTS: usize = 3;
+const ORIGINAL_IMAGE_CONTENT_TYPE: &str = "originalImageContentType";
+const SEARCH_FIELDS: [&str; 2] = ["title", "description"];
+const TEXT_INDEX: &str = "title_text_description_text";
 
 type ImageBase64String = String;
 
 #[derive(Clone)]
 pub struct Dao {
-    pub database: Database
+    pub database: Database,
+    pub slow_query_log: SlowQueryLog,
+    /// encrypts fields of the recipes on write, reads return them encrypted
+    pub field_encryption: Option<FieldEncryption>,
+    pub circuit_breaker: CircuitBreaker,
+    /// sets the modification times of the fields changed by updates when switched on
+    pub field_modified: FieldModifiedTracking,
+    /// shares one query between concurrent reads of the same recipe when switched on
+    pub read_coalescing: ReadCoalescing,
+    /// recently read recipes when switched on, invalidated by the writes of the dao
+    pub recipe_cache: RecipeCache,
+    /// order of the listings requested without `sorting`
+    pub default_sort: DefaultSort,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -30,24 +106,320 @@ pub enum DaoError {
     DatabaseError(String),
     DocumentNotFound,
     RecipeFormatError(String),
+    DuplicateKey { field: String, value: String },
+    TextIndexMissing,
+    MissingPrivileges,
+    /// a stale edit changed fields which were changed since its version, none when its version is unknown
+    VersionConflict { version: u32, fields: Vec<String> },
+    /// the recipe to delete is still part of these collections
+    RecipeReferenced { collections: Vec<ObjectId> },
+    /// the database is unreachable, with the time after which to retry while the circuit breaker is open
+    Unavailable { retry_after: Option<Duration> },
+    /// the database aborted the query once it ran longer than its `maxTimeMS`
+    TimeBudgetExceeded,
+    /// the author already has a recipe with the folded title
+    DuplicateTitle { title: String },
 }
 
 impl Dao {
-    pub async fn new() -> Option<Self> {
-        match get_db_handler().await
+    /// every write of the dao uses the write concern, every read the read preference of the settings
+    pub async fn new(write_concern: WriteConcernSettings, read_preference: ReadPreferenceSettings, field_encryption: Option<FieldEncryption>) -> Option<Self> {
+        get_db_handler(write_concern, read_preference).await
             .log_if_ok(|_| info!("Created database handler"))
             .log_if_err(|err| error!("Could not create database handler. Err={}", err))
-            .ok() {
-            Some(database) => Some(Self { database }),
-            None => None
+            .ok().map(|database| Self { database, slow_query_log: SlowQueryLog::from_env(), field_encryption, circuit_breaker: CircuitBreaker::from_env(), field_modified: FieldModifiedTracking::from_env(), read_coalescing: ReadCoalescing::from_env(), recipe_cache: RecipeCache::from_env(), default_sort: DefaultSort::from_env() })
+    }
+
+    /// Runs a database call unless the circuit breaker is open, times it and reports its outcome to the breaker.
+    /// The result of the call is returned as it is, an open breaker fails with `Unavailable`
+    async fn time<F>(&self, operation: &str, filter: &Document, query: F) -> Result<F::Output, DaoError>
+        where F: Future, F::Output: QueryOutcome {
+        self.circuit_breaker.try_acquire()
+            .map_err(|retry_after| DaoError::Unavailable { retry_after: Some(retry_after) })
+            .log_if_err(|err| warn!("Circuit breaker open, skipping operation={}, Err={:?}", operation, err))?;
+        let result = self.slow_query_log.time(operation, filter, query).await;
+        self.circuit_breaker.record(result.is_outage());
+        Ok(result)
+    }
+
+    /// succeeds while the database answers
+    pub async fn ping(&self) -> Result<(), DaoError> {
+        let command = doc! { "ping": 1 };
+        let ping = self.database.run_command(command.clone(), None);
+        self.time("ping", &command, ping).await?
+            .map(|_| ())
+            .map_err(DaoError::from)
+            .log_if_err(|err| error!("Database ping failed. Err={:#?}", err))
+    }
+
+    /// the document stored for the recipe, with the configured fields encrypted
+    /// together with the derived ingredient count and folded title and the hash of the stored content
+    fn recipe_document(&self, recipe: Recipe) -> Result<Document, DaoError> {
+        let ingredient_count = recipe.ingredients.len() as i32;
+        let folded_title = fold_title(&recipe.title);
+        let mut doc = Document::from(recipe);
+        doc.insert(JSON_ATTR_INGREDIENT_COUNT, ingredient_count);
+        doc.insert(JSON_ATTR_FOLDED_TITLE, folded_title);
+        if let Some(encryption) = &self.field_encryption {
+            encryption.encrypt_document(&mut doc)
+                .map_err(DaoError::DatabaseError)
+                .log_if_err(|err| error!("Could not encrypt recipe fields. Err={:#?}", err))?;
+        }
+        doc.insert(JSON_ATTR_CONTENT_HASH, content_hash(&doc));
+        Ok(doc)
+    }
+
+    /// recomputes the content hash of recipes after updating some of their content fields in place
+    async fn rehash_recipes(&self, ids: &[ObjectId]) -> Result<(), DaoError> {
+        let filter = doc! { "_id": { "$in": ids.to_vec() } };
+        let mut options = FindOptions::default();
+        options.projection = Some(Recipe::default_projection_no_image());
+        let query = async {
+            let cursor = self.database.collection(RECIPE_COLLECTION).find(filter.clone(), options).await?;
+            Ok::<_, Error>(cursor.collect::<Vec<Result<Document, Error>>>().await)
+        };
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        for doc in self.time("rehash_recipes", &filter, query).await?.map_err(DaoError::from)? {
+            let doc = doc.map_err(DaoError::from)?;
+            let query = doc! { "_id": doc.get_object_id("_id")?.clone() };
+            let update = UpdateModifications::Document(doc! { "$set": { JSON_ATTR_CONTENT_HASH: content_hash(&doc) } });
+            let update = collection.update_one(query.clone(), update, None);
+            self.time("rehash_recipes", &query, update).await?
+                .map_err(DaoError::from)
+                .log_if_err(|err| error!("Could not rehash recipe. Err={:#?}", err))?;
+        }
+        Ok(())
+    }
+
+    /// recomputes the content hash of every recipe and reports the ones differing from their stored hash
+    pub async fn verify_integrity(&self) -> Result<IntegrityReport, DaoError> {
+        let mut options = FindOptions::default();
+        options.projection = Some(Recipe::default_projection_no_image());
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let find = collection.find(doc! {}, options);
+        let mut cursor = self.time("verify_integrity", &doc! {}, find).await?.map_err(DaoError::from)?;
+        let mut report = IntegrityReport::default();
+        while let Some(doc) = cursor.next().await {
+            report.check(&doc.map_err(DaoError::from)?);
+        }
+        info!("Verified integrity. checked={}, unhashed={}, mismatches={}", report.checked, report.unhashed, report.mismatches.len());
+        Ok(report)
+    }
+
+    /// stores the ingredient count on the recipes written before it was maintained, returns the number of updated recipes
+    pub async fn backfill_ingredient_counts(&self) -> Result<u64, DaoError> {
+        let filter = doc! { JSON_ATTR_INGREDIENT_COUNT: { "$exists": false } };
+        let update = UpdateModifications::Pipeline(vec![
+            doc! { "$set": { JSON_ATTR_INGREDIENT_COUNT: { "$size": { "$ifNull": ["$ingredients", []] } } } },
+        ]);
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let update = collection.update_many(filter.clone(), update, None);
+        self.time("backfill_ingredient_counts", &filter, update).await?
+            .map(|result| result.modified_count as u64)
+            .map_err(DaoError::from)
+            .log_if_ok(|count| info!("Backfilled ingredient counts. recipes={}", count))
+            .log_if_err(|err| error!("Could not backfill ingredient counts. Err={:#?}", err))
+    }
+
+    /// unique index on the slug, recipes stored before slugs existed are left out
+    pub async fn ensure_slug_index(&self) -> Result<(), DaoError> {
+        let command = doc! {
+            "createIndexes": RECIPE_COLLECTION,
+            "indexes": [{
+                "key": { "slug": 1 },
+                "name": SLUG_INDEX,
+                "unique": true,
+                "partialFilterExpression": { "slug": { "$type": "string" } }
+            }]
+        };
+        let create = self.database.run_command(command.clone(), None);
+        self.time("createIndexes", &command, create).await?
+            .map(|_| ())
+            .map_err(DaoError::from)
+            .log_if_ok(|_| info!("Ensured slug index"))
+            .log_if_err(|err| error!("Could not create slug index. Err={:#?}", err))
+    }
+
+    /// index of the ratings per recipe, which rating filtered listings join
+    pub async fn ensure_ratings_index(&self) -> Result<(), DaoError> {
+        let command = doc! {
+            "createIndexes": RATINGS_COLLECTION,
+            "indexes": [{ "key": { "recipeId": 1 }, "name": "recipeId_1" }]
+        };
+        let create = self.database.run_command(command.clone(), None);
+        self.time("createIndexes", &command, create).await?
+            .map(|_| ())
+            .map_err(DaoError::from)
+            .log_if_ok(|_| info!("Ensured ratings index"))
+            .log_if_err(|err| error!("Could not create ratings index. Err={:#?}", err))
+    }
+
+    /// creates the index the listings are sorted by in their default order, serving both directions
+    pub async fn ensure_created_index(&self) -> Result<(), DaoError> {
+        let command = doc! {
+            "createIndexes": RECIPE_COLLECTION,
+            "indexes": [{ "key": { "created": 1, "_id": 1 }, "name": "created_1__id_1" }]
+        };
+        let create = self.database.run_command(command.clone(), None);
+        self.time("createIndexes", &command, create).await?
+            .map(|_| ())
+            .map_err(DaoError::from)
+            .log_if_ok(|_| info!("Ensured created index"))
+            .log_if_err(|err| error!("Could not create created index. Err={:#?}", err))
+    }
+
+    /// creates the text index `?q=` searches with, fails while a text index on other fields exists
+    /// as a collection has at most one
+    pub async fn ensure_text_index(&self) -> Result<(), DaoError> {
+        let key = SEARCH_FIELDS.iter().map(|field| (field.to_string(), Bson::from("text"))).collect::<Document>();
+        let command = doc! {
+            "createIndexes": RECIPE_COLLECTION,
+            "indexes": [{ "key": key, "name": TEXT_INDEX }]
+        };
+        let create = self.database.run_command(command.clone(), None);
+        self.time("createIndexes", &command, create).await?
+            .map(|_| ())
+            .map_err(DaoError::from)
+            .log_if_ok(|_| info!("Ensured text index"))
+            .log_if_err(|err| error!("Could not create text index, searching falls back to regex. Err={:#?}", err))
+    }
+
+    /// creates the unique title indexes of the constraint, drops the others so a constraint
+    /// turned off no longer rejects duplicate titles. Folds the titles of the recipes stored
+    /// without folded title before creating the per author index
+    pub async fn ensure_title_index(&self, constraint: TitleConstraint) -> Result<(), DaoError> {
+        let title_index = doc! { "key": { "title": 1 }, "name": TITLE_INDEX, "unique": true };
+        let per_author_index = doc! {
+            "key": { "author": 1, JSON_ATTR_FOLDED_TITLE: 1 },
+            "name": TITLE_PER_AUTHOR_INDEX,
+            "unique": true,
+            "partialFilterExpression": { "author": { "$type": "string" }, JSON_ATTR_FOLDED_TITLE: { "$type": "string" } }
+        };
+        let result = match constraint.per_author {
+            Some(true) => self.backfill_folded_titles().await.map(|_| ()),
+            _ => Ok(()),
+        };
+        let result = match result {
+            Ok(()) => self.toggle_index(constraint.unique, title_index).await,
+            err => err,
+        };
+        let result = match result {
+            Ok(()) => self.toggle_index(constraint.per_author, per_author_index).await,
+            err => err,
+        };
+        result
+            .log_if_ok(|_| info!("Ensured title constraint={:?}", constraint))
+            .log_if_err(|err| error!("Could not apply title constraint={:?}. Err={:#?}", constraint, err))
+    }
+
+    /// creates the index when enabled, drops it by its name when disabled, leaves it as it is when unset
+    async fn toggle_index(&self, enabled: Option<bool>, index: Document) -> Result<(), DaoError> {
+        let command = match enabled {
+            Some(true) => doc! { "createIndexes": RECIPE_COLLECTION, "indexes": [index] },
+            Some(false) => doc! { "dropIndexes": RECIPE_COLLECTION, "index": index.get_str("name").unwrap_or_default() },
+            None => return Ok(()),
+        };
+        let operation = command.keys().next().cloned().unwrap_or_default();
+        let result = self.database.run_command(command.clone(), None);
+        match self.time(&operation, &command, result).await? {
+            Err(err) if enabled == Some(false) && is_missing_index(&err) => Ok(()),
+            result => result.map(|_| ()).map_err(DaoError::from),
         }
     }
 
-    /// ignores id
+    /// stores the folded title on the recipes written before it was maintained, returns the number of updated recipes
+    pub async fn backfill_folded_titles(&self) -> Result<u64, DaoError> {
+        let filter = doc! { JSON_ATTR_FOLDED_TITLE: { "$exists": false } };
+        let update = UpdateModifications::Pipeline(vec![
+            doc! { "$set": { JSON_ATTR_FOLDED_TITLE: { "$toLower": { "$trim": { "input": "$title" } } } } },
+        ]);
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let update = collection.update_many(filter.clone(), update, None);
+        self.time("backfill_folded_titles", &filter, update).await?
+            .map(|result| result.modified_count as u64)
+            .map_err(DaoError::from)
+            .log_if_ok(|count| info!("Backfilled folded titles. recipes={}", count))
+            .log_if_err(|err| error!("Could not backfill folded titles. Err={:#?}", err))
+    }
+
+    /// capped collection of the view events with an index on their time. The cap bounds the storage,
+    /// so on a busy service the oldest events of a long window may already be dropped
+    pub async fn ensure_views_collection(&self) -> Result<(), DaoError> {
+        let command = doc! { "create": RECIPE_VIEWS_COLLECTION, "capped": true, "size": RECIPE_VIEWS_SIZE_BYTES };
+        let create = self.database.run_command(command.clone(), None);
+        let result = match self.time("create", &command, create).await? {
+            Err(err) if is_namespace_exists(&err) => Ok(()),
+            result => result.map(|_| ()).map_err(DaoError::from),
+        };
+        result.log_if_err(|err| error!("Could not create views collection. Err={:#?}", err))?;
+        let command = doc! {
+            "createIndexes": RECIPE_VIEWS_COLLECTION,
+            "indexes": [{ "key": { "viewedAt": 1 }, "name": "viewedAt_1" }]
+        };
+        let create = self.database.run_command(command.clone(), None);
+        self.time("createIndexes", &command, create).await?
+            .map(|_| ())
+            .map_err(DaoError::from)
+            .log_if_ok(|_| info!("Ensured views collection"))
+            .log_if_err(|err| error!("Could not create views collection. Err={:#?}", err))
+    }
+
+    pub async fn record_view(&self, id: ObjectId) -> Result<(), DaoError> {
+        let event = doc! { "recipeId": id.clone(), "viewedAt": Utc::now() };
+        let collection = self.database.collection(RECIPE_VIEWS_COLLECTION);
+        let insert = collection.insert_one(event.clone(), None);
+        self.time("record_view", &event, insert).await?
+            .map(|_| ())
+            .map_err(DaoError::from)
+            .log_if_err(|err| error!("Could not record view id={:#?}, Err={:#?}", id, err))
+    }
+
+    /// summaries of the recipes most viewed since the given time, most views first, archived recipes and drafts left out
+    pub async fn get_trending_recipes(&self, since: DateTime<Utc>, limit: i64, max_time: Duration) -> Result<Vec<TrendingRecipe>, DaoError> {
+        let filter = doc! { "viewedAt": { "$gte": since } };
+        let query = async {
+            let cursor = self.database
+                .collection(RECIPE_VIEWS_COLLECTION)
+                .aggregate(trending_recipes_pipeline(filter.clone(), limit), budget(max_time)).await?;
+            Ok::<_, Error>(cursor.collect::<Vec<Result<Document, Error>>>().await)
+        };
+        let recipes = self.time("get_trending_recipes", &filter, query).await?
+            .map_err(DaoError::from)?
+            .into_iter()
+            .map(|doc| doc.map_err(DaoError::from)
+                .and_then(|doc| TrendingRecipe::try_from(doc).map_err(DaoError::from)))
+            .collect::<Result<Vec<TrendingRecipe>, DaoError>>();
+
+        recipes
+            .log_if_ok(|recipes| info!("Got {} trending recipes from db. since={}", recipes.len(), since))
+            .log_if_err(|err| error!("Could not get trending recipes. since={}, Err={:#?}", since, err))
+    }
+
+    /// ignores id and slug, a unique slug is generated from the title.
+    /// A concurrent insert taking the same slug is retried with the next free suffix
     pub async fn insert_recipe(&self, recipe: Recipe) -> Result<Bson, DaoError> {
-        match self.database.collection(RECIPE_COLLECTION).insert_one(recipe.clone().into(), None).await {
+        let mut attempt = 1;
+        loop {
+            let recipe = self.with_unique_slugs(vec![recipe.clone()]).await?.remove(0);
+            match self.insert_recipe_with_slug(recipe).await {
+                Err(DaoError::DuplicateKey { field, .. }) if field == JSON_ATTR_SLUG && attempt < SLUG_INSERT_ATTEMPTS => {
+                    warn!("Slug taken concurrently, retrying insert. attempt={}", attempt);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn insert_recipe_with_slug(&self, recipe: Recipe) -> Result<Bson, DaoError> {
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let insert = collection.insert_one(self.recipe_document(recipe.clone())?, None);
+        match self.time("insert_recipe", &doc! {}, insert).await? {
             Ok(result) => {
                 info!("Added recipe in db. id={:?}", result.inserted_id);
+                if let Some(id) = result.inserted_id.as_object_id() {
+                    self.save_recipe_version(Recipe { _id: id.clone(), ..recipe }).await;
+                }
                 Ok(result.inserted_id)
             }
             Err(err) => {
@@ -57,40 +429,278 @@ impl Dao {
         }
     }
 
-    pub async fn update_recipe_ignore_image(&self, id: ObjectId, recipe: Recipe) -> Result<(), DaoError> {
-        let query = object_id_into_doc(id.clone());
+    /// Updates all fields but the image, with an expected version only while the stored recipe still
+    /// has it, `VersionConflict` otherwise. Sets the modification times of the fields which differ
+    /// from the stored recipe while they are tracked
+    pub async fn update_recipe_at_version(&self, id: ObjectId, recipe: Recipe, expected_version: Option<u32>) -> Result<(), DaoError> {
+        let mut query = object_id_into_doc(id.clone());
+        if let Some(version) = expected_version {
+            query.insert("version", version);
+        }
+        let changed = if self.field_modified.enabled {
+            changed_fields(&self.load_one_recipe_without_image(id.clone()).await?, &recipe)
+        } else {
+            BTreeSet::new()
+        };
 
-        let mut recipe = Document::from(recipe);
+        let mut recipe = self.recipe_document(recipe)?;
         recipe.remove("image");
+        recipe.remove("archived");
+        recipe.remove(JSON_ATTR_SLUG);
+        recipe.remove("status");
+        recipe.remove("author");
+        recipe.remove(JSON_ATTR_FIELD_MODIFIED);
+        recipe.extend(self.field_modified.set_modified(changed.iter().map(String::as_str), Utc::now()));
         let update = UpdateModifications::Document(
             doc! { "$set" : recipe}
         );
 
-        match self.database.collection(RECIPE_COLLECTION)
-            .update_one(query, update, None).await {
-            Ok(result) => match result.modified_count {
+        let updated = self.update_recipe_returning("update_recipe_ignore_image", query, update).await;
+        self.recipe_cache.invalidate(std::iter::once(&id));
+        match (updated, expected_version) {
+            (Ok(Some(recipe)), _) => {
+                info!("Updated recipe in db with id={:#?}", &id);
+                self.save_recipe_version(recipe).await;
+                Ok(())
+            }
+            (Ok(None), Some(version)) => {
+                info!("Not Updated recipe, it changed since version={} id={:#?}", version, &id);
+                Err(DaoError::VersionConflict { version, fields: vec![] })
+            }
+            (Ok(None), None) => {
+                info!("Not Updated recipe, doc not found with id={:#?}", &id);
+                Err(DaoError::DocumentNotFound)
+            }
+            (Err(err), _) => {
+                error!("Could not update recipe with id={:#?}, Err={:#?}", &id, err);
+                Err(err)
+            }
+        }
+    }
+
+    /// applies the update to the recipe matching the query and returns the recipe without image as the update wrote it,
+    /// so its snapshot can't contain a later write. None when no recipe matches
+    async fn update_recipe_returning(&self, operation: &str, query: Document, update: UpdateModifications) -> Result<Option<Recipe>, DaoError> {
+        let mut options = FindOneAndUpdateOptions::default();
+        options.projection = Some(Recipe::default_projection_no_image());
+        options.return_document = Some(ReturnDocument::After);
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let update = collection.find_one_and_update(query.clone(), update, options);
+        self.time(operation, &query, update).await?
+            .map_err(DaoError::from)?
+            .map(|doc| Recipe::try_from(doc).map_err(DaoError::from))
+            .transpose()
+    }
+
+    /// Updates as `update_recipe_at_version` when the edit is based on the stored version or a later one.
+    /// A stale edit is merged into the stored recipe when it changes other fields than the ones
+    /// changed since its version, the merge is stored as the next version. The write only applies while
+    /// the stored recipe still has the version read, otherwise the edit is merged again into the new one.
+    /// Encrypted fields the caller cannot read keep their stored values, unchanged step timers move along with their instructions
+    pub async fn update_recipe_merging(&self, id: ObjectId, recipe: Recipe, identity: Option<&Identity>) -> Result<(), DaoError> {
+        for attempt in 1..=MERGE_ATTEMPTS {
+            let current = self.load_one_recipe_without_image(id.clone()).await?;
+            let mut update = match recipe.version >= current.version {
+                true => recipe.clone(),
+                false => self.merge_stale_recipe(id.clone(), &current, &recipe).await?,
+            };
+            if update.step_timers == current.step_timers {
+                update.step_timers = realign_step_timers(&current.step_timers, &current.instructions, &update.instructions);
+            }
+            keep_hidden_fields(&mut update, &current, identity);
+            match self.update_recipe_at_version(id.clone(), update, Some(current.version)).await {
+                Err(DaoError::VersionConflict { .. }) => warn!("Recipe changed while updating, merging again. id={:#?}, attempt={}", id, attempt),
+                result => return result,
+            }
+        }
+        Err(DaoError::VersionConflict { version: recipe.version, fields: vec![] })
+    }
+
+    async fn merge_stale_recipe(&self, id: ObjectId, current: &Recipe, recipe: &Recipe) -> Result<Recipe, DaoError> {
+        let version = recipe.version;
+        let base = self.get_recipe_version(id.clone(), version).await
+            .map_err(|err| match err {
+                DaoError::DocumentNotFound => DaoError::VersionConflict { version, fields: vec![] },
+                err => err,
+            })?;
+        let merged = merge_recipes(&base, current, recipe)
+            .map_err(|fields| DaoError::VersionConflict { version, fields })
+            .log_if_err(|err| info!("Rejecting stale recipe update id={:#?}, Err={:?}", id, err))?;
+        info!("Merged stale recipe update id={:#?}, version={}, into version={}", id, version, current.version);
+        Ok(merged)
+    }
+
+    /// Keeps a snapshot without image of the stored recipe per version, saving a version again
+    /// replaces its snapshot. Recipes get snapshots when created one by one and when updated.
+    /// A failing snapshot is only logged, the write itself already succeeded
+    async fn save_recipe_version(&self, recipe: Recipe) {
+        let filter = doc! { "recipeId": recipe._id.clone(), "version": recipe.version };
+        let mut snapshot = match self.recipe_document(recipe.clone()) {
+            Ok(snapshot) => snapshot,
+            Err(_) => return,
+        };
+        snapshot.insert("_id", recipe._id.clone());
+        snapshot.remove("image");
+        let update = UpdateModifications::Document(doc! { "$set": { "recipe": snapshot, "savedAt": Utc::now() } });
+        let mut options = UpdateOptions::default();
+        options.upsert = Some(true);
+
+        let collection = self.database.collection(RECIPE_VERSIONS_COLLECTION);
+        let update = collection.update_one(filter.clone(), update, options);
+        self.time("save_recipe_version", &filter, update).await
+            .and_then(|result| result.map_err(DaoError::from))
+            .log_if_ok(|_| info!("Saved recipe version. filter={:?}", filter))
+            .log_if_err(|err| error!("Could not save recipe version. filter={:?}, Err={:#?}", filter, err))
+            .ok();
+    }
+
+    pub async fn get_recipe_version(&self, id: ObjectId, version: u32) -> Result<Recipe, DaoError> {
+        let filter = doc! { "recipeId": id, "version": version };
+        let collection = self.database.collection(RECIPE_VERSIONS_COLLECTION);
+        let find = collection.find_one(filter.clone(), None);
+        let snapshot = self.time("get_recipe_version", &filter, find).await?
+            .map_err(DaoError::from)?
+            .ok_or(DaoError::DocumentNotFound)
+            .log_if_err(|_| error!("Recipe version not found. filter={:?}", filter))?;
+        snapshot.get_document("recipe")
+            .map_err(DaoError::from)
+            .and_then(|recipe| Recipe::try_from(recipe.clone()).map_err(DaoError::from))
+            .log_if_err(|err| error!("Could not format recipe version. filter={:?}, Err={:#?}", filter, err))
+    }
+
+    /// Sets the ingredients and the modification date and counts up the version, the rest of the recipe
+    /// stays untouched. With an expected version only while the stored recipe still has it, `VersionConflict` otherwise
+    pub async fn update_recipe_ingredients(&self, id: ObjectId, ingredients: Vec<Ingredient>, expected_version: Option<u32>) -> Result<(), DaoError> {
+        let mut query = object_id_into_doc(id.clone());
+        if let Some(version) = expected_version {
+            query.insert("version", version);
+        }
+        let modified = Utc::now();
+        let mut set = doc! { JSON_ATTR_INGREDIENT_COUNT: ingredients.len() as i32, "ingredients": ingredients, "last_modified": modified };
+        set.extend(self.field_modified.set_modified(vec!["ingredients"], modified));
+        let update = UpdateModifications::Document(doc! { "$set": set, "$inc": { "version": 1 } });
+
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let update = collection.update_one(query.clone(), update, None);
+        let result = self.time("update_recipe_ingredients", &query, update).await?;
+        self.recipe_cache.invalidate(std::iter::once(&id));
+        match result {
+            Ok(result) => match (expected_version, result.matched_count) {
+                (Some(version), 0) => {
+                    info!("Not updated ingredients, recipe changed since version={} id={:#?}", version, &id);
+                    Err(DaoError::VersionConflict { version, fields: vec!["ingredients".to_string()] })
+                }
+                (None, 0) => {
+                    info!("Not updated ingredients, doc not found with id={:#?}", &id);
+                    Err(DaoError::DocumentNotFound)
+                }
+                _ => {
+                    info!("Updated ingredients of recipe in db. id={:#?}", &id);
+                    self.rehash_recipes(&[id]).await
+                }
+            }
+            Err(err) => {
+                error!("Could not update ingredients of recipe with id={:#?}, Err={:#?}", &id, err);
+                Err(DaoError::from(err))
+            }
+        }
+    }
+
+    /// Sets the modification date to now and counts up the version, the rest of the recipe stays untouched.
+    /// Returns the touched recipe without image, which is also kept as snapshot of its new version
+    pub async fn touch_recipe(&self, id: ObjectId) -> Result<Recipe, DaoError> {
+        let query = object_id_into_doc(id.clone());
+        let update = UpdateModifications::Document(doc! { "$set": { "last_modified": Utc::now() }, "$inc": { "version": 1 } });
+
+        let touched = self.update_recipe_returning("touch_recipe", query, update).await;
+        self.recipe_cache.invalidate(std::iter::once(&id));
+        match touched {
+            Ok(Some(recipe)) => {
+                info!("Touched recipe in db. id={:#?}", &id);
+                self.save_recipe_version(recipe.clone()).await;
+                Ok(recipe)
+            }
+            Ok(None) => {
+                info!("Not touched recipe, doc not found with id={:#?}", &id);
+                Err(DaoError::DocumentNotFound)
+            }
+            Err(err) => {
+                error!("Could not touch recipe with id={:#?}, Err={:#?}", &id, err);
+                Err(err)
+            }
+        }
+    }
+
+    /// archives or restores a recipe, setting the current state again succeeds as well
+    pub async fn set_recipe_archived(&self, id: ObjectId, archived: bool) -> Result<(), DaoError> {
+        let query = object_id_into_doc(id.clone());
+        let mut set = doc! { "archived": archived };
+        set.extend(self.field_modified.set_modified(vec!["archived"], Utc::now()));
+        let update = UpdateModifications::Document(doc! { "$set": set });
+
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let update = collection.update_one(query.clone(), update, None);
+        let result = self.time("set_recipe_archived", &query, update).await?;
+        self.recipe_cache.invalidate(std::iter::once(&id));
+        match result {
+            Ok(result) => match result.matched_count {
+                0 => {
+                    info!("Not archived recipe, doc not found with id={:#?}", &id);
+                    Err(DaoError::DocumentNotFound)
+                }
+                _ => {
+                    info!("Set archived of recipe in db. id={:#?}, archived={}", &id, archived);
+                    Ok(())
+                }
+            }
+            Err(err) => {
+                error!("Could not set archived of recipe with id={:#?}, Err={:#?}", &id, err);
+                Err(DaoError::from(err))
+            }
+        }
+    }
+
+    pub async fn set_recipe_status(&self, id: ObjectId, status: RecipeStatus) -> Result<(), DaoError> {
+        let query = object_id_into_doc(id.clone());
+        let modified = Utc::now();
+        let mut set = doc! { "status": status, "last_modified": modified };
+        set.extend(self.field_modified.set_modified(vec!["status"], modified));
+        let update = UpdateModifications::Document(doc! { "$set": set });
+
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let update = collection.update_one(query.clone(), update, None);
+        let result = self.time("set_recipe_status", &query, update).await?;
+        self.recipe_cache.invalidate(std::iter::once(&id));
+        match result {
+            Ok(result) => match result.matched_count {
                 0 => {
-                    info!("Not Updated recipe, doc not found with id={:#?}", &id);
+                    info!("Not changed status of recipe, doc not found with id={:#?}", &id);
                     Err(DaoError::DocumentNotFound)
                 }
                 _ => {
-                    info!("Updated recipe in db with id={:#?}", &id);
+                    info!("Set status of recipe in db. id={:#?}, status={}", &id, status);
                     Ok(())
                 }
             }
             Err(err) => {
-                error!("Could not update recipe with id={:#?}, Err={:#?}", &id, err);
+                error!("Could not set status of recipe with id={:#?}, Err={:#?}", &id, err);
                 Err(DaoError::from(err))
             }
         }
     }
 
+    /// ignores ids and slugs, unique slugs are generated from the titles
     pub async fn add_many_recipes(&self, recipes: Vec<Recipe>) -> Result<Bson, DaoError> {
-        match self.database.collection(RECIPE_COLLECTION).insert_many(
-            recipes.clone().into_iter().map(|r| r.into()).collect::<Vec<Document>>(), None).await {
+        let recipes = self.with_unique_slugs(recipes).await?;
+        let documents = recipes.clone().into_iter()
+            .map(|recipe| self.recipe_document(recipe))
+            .collect::<Result<Vec<Document>, DaoError>>()?;
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let insert = collection.insert_many(documents, None);
+        match self.time("add_many_recipes", &doc! {}, insert).await? {
             Ok(result) => {
                 info!("Added multiple recipes in db. ids={:#?}", result.inserted_ids);
-                Ok(Bson::from(result.inserted_ids.values().map(|b: &Bson| b.to_owned()).collect::<Vec<Bson>>()))
+                Ok(Bson::from(ids_in_input_order(result.inserted_ids)))
             }
             Err(err) => {
                 error!("Could not add multiple recipes={:#?}, Err={:#?}", recipes, err);
@@ -99,16 +709,172 @@ impl Dao {
         }
     }
 
+    /// Inserts the recipes with their ids. Recipes whose id is stored already are skipped or replace the
+    /// stored one as its next version, which keeps its slug, author and the notes and source the
+    /// replacement lacks, e.g. as they were encrypted. New recipes get unique slugs generated from their titles
+    pub async fn restore_recipes(&self, recipes: Vec<Recipe>, on_conflict: ConflictPolicy) -> Result<RestoreResult, DaoError> {
+        let stored_slugs = self.get_stored_slugs(&recipes).await?;
+        let mut result = RestoreResult::default();
+        let (stored, new): (Vec<Recipe>, Vec<Recipe>) = recipes.into_iter()
+            .partition(|recipe| stored_slugs.contains_key(&recipe._id));
+
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        for mut recipe in stored {
+            let id = recipe._id.clone();
+            if on_conflict == ConflictPolicy::Skip {
+                result.skipped.push(id.to_hex());
+                continue;
+            }
+            let current = self.load_one_recipe_without_image(id.clone()).await?;
+            recipe.slug = current.slug;
+            recipe.author = current.author;
+            recipe.notes = recipe.notes.or(current.notes);
+            recipe.source = recipe.source.or(current.source);
+            recipe.version = current.version + 1;
+            let mut filter = object_id_into_doc(id.clone());
+            filter.insert("version", current.version);
+            let replace = collection.replace_one(filter.clone(), self.recipe_document(recipe.clone())?, None);
+            let replaced = self.time("restore_recipes", &filter, replace).await;
+            self.recipe_cache.invalidate(std::iter::once(&id));
+            let replaced = replaced?
+                .map_err(DaoError::from)
+                .log_if_err(|err| error!("Could not replace recipe with id={}. Err={:#?}", id, err))?;
+            if replaced.matched_count == 0 {
+                info!("Not replaced recipe, it changed while restoring. id={}", id);
+                return Err(DaoError::VersionConflict { version: current.version, fields: vec![] });
+            }
+            self.save_recipe_version(recipe).await;
+            result.replaced.push(id.to_hex());
+        }
+
+        if !new.is_empty() {
+            let documents = self.with_unique_slugs(new).await?.into_iter()
+                .map(|recipe| self.recipe_document(recipe))
+                .collect::<Result<Vec<Document>, DaoError>>()?;
+            let insert = collection.insert_many(documents, None);
+            let inserted = self.time("restore_recipes", &doc! {}, insert).await?
+                .map_err(DaoError::from)
+                .log_if_err(|err| error!("Could not restore recipes. Err={:#?}", err))?;
+            result.inserted = ids_in_input_order(inserted.inserted_ids).into_iter()
+                .filter_map(|id| id.as_object_id().map(ObjectId::to_hex))
+                .collect();
+        }
+        info!("Restored recipes with their ids. result={:?}", result);
+        Ok(result)
+    }
+
+    /// Writes recipe documents from a backup as they are, replacing the stored recipe with the same id.
+    /// Returns the number of restored and of failed documents, e.g. with a slug taken by another recipe
+    pub async fn restore_recipe_documents(&self, documents: Vec<Document>) -> Result<(u64, u64), DaoError> {
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let (mut restored, mut failed) = (0, 0);
+        for document in documents {
+            let id = Recipe::extract_id(&document)?;
+            let filter = object_id_into_doc(id.clone());
+            let options = ReplaceOptions::builder().upsert(true).build();
+            let replace = collection.replace_one(filter.clone(), document, options);
+            let replaced = self.time("restore_recipe_documents", &filter, replace).await;
+            self.recipe_cache.invalidate(std::iter::once(&id));
+            match replaced?.map_err(DaoError::from) {
+                Ok(_) => restored += 1,
+                Err(err @ DaoError::Unavailable { .. }) => return Err(err),
+                Err(err) => {
+                    error!("Could not restore recipe with id={}. Err={:#?}", id, err);
+                    failed += 1;
+                }
+            }
+        }
+        info!("Restored recipe documents. restored={}, failed={}", restored, failed);
+        Ok((restored, failed))
+    }
+
+    /// deletes the recipes created before `created_before` except the given ones chunk by chunk, recipes
+    /// created later are kept. Returns the number of deleted recipes
+    pub async fn delete_recipes_except(&self, ids: Vec<ObjectId>, created_before: ObjectId) -> Result<u64, DaoError> {
+        let kept = ids.into_iter().collect::<HashSet<ObjectId>>();
+        let others = self.get_recipe_ids(doc! { "_id": { "$lt": created_before } }).await?
+            .into_iter()
+            .filter(|id| !kept.contains(id))
+            .collect::<Vec<ObjectId>>();
+        let mut deleted = 0;
+        for chunk in others.chunks(DELETE_CHUNK_SIZE) {
+            deleted += self.delete_many_recipes(chunk.to_vec()).await?;
+        }
+        Ok(deleted)
+    }
+
+    /// the stored recipes among the given ones with their slugs
+    async fn get_stored_slugs(&self, recipes: &[Recipe]) -> Result<HashMap<ObjectId, Option<String>>, DaoError> {
+        let ids = recipes.iter().map(|recipe| Bson::ObjectId(recipe._id.clone())).collect::<Vec<Bson>>();
+        let filter = doc! { "_id": { "$in": ids } };
+        let mut options = FindOptions::default();
+        options.projection = Some(doc! { JSON_ATTR_SLUG: 1 });
+        let query = async {
+            let cursor = self.database.collection(RECIPE_COLLECTION).find(filter.clone(), options).await?;
+            Ok::<_, Error>(cursor.collect::<Vec<Result<Document, Error>>>().await)
+        };
+        self.time("get_stored_slugs", &filter, query).await?
+            .map_err(DaoError::from)?
+            .into_iter()
+            .map(|doc| doc.map_err(DaoError::from)
+                .and_then(|doc| Ok((Recipe::extract_id(&doc)?, doc.get_str(JSON_ATTR_SLUG).ok().map(String::from)))))
+            .collect::<Result<HashMap<ObjectId, Option<String>>, DaoError>>()
+            .log_if_err(|err| error!("Could not get stored recipes. Err={:#?}", err))
+    }
+
+    /// served from the recipe cache while it holds the recipe
     pub async fn get_one_recipe_without_image(&self, id: ObjectId) -> Result<Recipe, DaoError> {
+        if !self.recipe_cache.is_enabled() {
+            return self.read_one_recipe_without_image(id).await.map(|(recipe, _)| recipe);
+        }
+        if let Ok(recipe) = self.recipe_cache.get(&id) {
+            return Ok(recipe);
+        }
+        let (recipe, miss) = self.read_one_recipe_without_image(id).await?;
+        self.recipe_cache.put(miss, recipe.clone());
+        Ok(recipe)
+    }
+
+    /// the recipe, or its last known state from the recipe cache while the database is unavailable,
+    /// together with whether it is this stale one
+    pub async fn get_one_recipe_or_stale(&self, id: ObjectId) -> Result<(Recipe, bool), DaoError> {
+        match self.get_one_recipe_without_image(id.clone()).await {
+            Err(err @ DaoError::Unavailable { .. }) => match self.recipe_cache.get_stale(&id) {
+                Some(recipe) => {
+                    warn!("Serving stale recipe while the database is unavailable. id={}", id);
+                    Ok((recipe, true))
+                }
+                None => Err(err),
+            },
+            result => result.map(|recipe| (recipe, false)),
+        }
+    }
+
+    /// Shares the query with concurrent reads of the recipe while reads are coalesced. The recipe comes with
+    /// the cache token taken when the query started, a read joining it later may not cache an older recipe
+    async fn read_one_recipe_without_image(&self, id: ObjectId) -> Result<(Recipe, CacheMiss), DaoError> {
+        let dao = self.clone();
+        let key = id.clone();
+        let load = move || async move {
+            let miss = dao.recipe_cache.miss();
+            dao.load_one_recipe_without_image(id).await.map(|recipe| (recipe, miss))
+        };
+        match &self.read_coalescing.recipes {
+            Some(recipes) => recipes.run(key, load).await,
+            None => load().await,
+        }
+    }
+
+    async fn load_one_recipe_without_image(&self, id: ObjectId) -> Result<Recipe, DaoError> {
         let filter = object_id_into_doc(id.clone());
 
         let options = Dao::recipe_only_image_find_options();
 
-        let result = self.database
-            .collection(RECIPE_COLLECTION)
-            .find_one(filter, options).await
-            .map_err(|err| DaoError::from(err))?
-            .map(|doc| Recipe::try_from(doc));
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let find = collection.find_one(filter.clone(), options);
+        let result = self.time("get_one_recipe_without_image", &filter, find).await?
+            .map_err(DaoError::from)?
+            .map(Recipe::try_from);
 
         match result {
             Some(Ok(recipe)) => {
@@ -126,23 +892,266 @@ impl Dao {
         }
     }
 
-    pub async fn get_one_recipe_image(&self, id: ObjectId) -> Result<ImageBase64String, DaoError> {
+    /// the recipe document as projected, e.g. by a field mask
+    /// not found when the recipe is not visible, e.g. a draft of someone else
+    pub async fn get_one_recipe_document(&self, id: ObjectId, projection: Document, visibility: Document) -> Result<Document, DaoError> {
+        let mut filter = object_id_into_doc(id.clone());
+        filter.extend(visibility);
+        let mut options = FindOneOptions::default();
+        options.projection = Some(projection);
+
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let find = collection.find_one(filter.clone(), options);
+        self.time("get_one_recipe_document", &filter, find).await?
+            .map_err(DaoError::from)?
+            .ok_or(DaoError::DocumentNotFound)
+            .log_if_ok(|_| info!("Got one recipe document from db. id={:#?}", id))
+            .log_if_err(|err| error!("Could not get recipe document id={:#?}, Err={:#?}", id, err))
+    }
+
+    pub async fn get_one_recipe_by_slug(&self, slug: &str) -> Result<Recipe, DaoError> {
+        let filter = doc! { JSON_ATTR_SLUG: slug };
+
+        let options = Dao::recipe_only_image_find_options();
+
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let find = collection.find_one(filter.clone(), options);
+        let result = self.time("get_one_recipe_by_slug", &filter, find).await?
+            .map_err(DaoError::from)?
+            .map(Recipe::try_from);
+
+        match result {
+            Some(Ok(recipe)) => {
+                info!("Got one recipe from db. slug={}", slug);
+                Ok(recipe)
+            }
+            Some(Err(error)) => {
+                error!("Got one recipe, but could not format slug={}, error={:#?}", slug, error);
+                Err(DaoError::RecipeFormatError(slug.to_string()))
+            }
+            None => {
+                error!("get recipe by slug, recipe Not found: slug={}", slug);
+                Err(DaoError::DocumentNotFound)
+            }
+        }
+    }
+
+    pub async fn get_recipe_id_by_slug(&self, slug: &str) -> Result<ObjectId, DaoError> {
+        self.get_recipe_ids(doc! { JSON_ATTR_SLUG: slug }).await?
+            .into_iter()
+            .next()
+            .ok_or(DaoError::DocumentNotFound)
+            .log_if_err(|_| error!("Recipe not found slug={}", slug))
+    }
+
+    /// assigns each recipe the first free slug for its title, also among the given recipes
+    async fn with_unique_slugs(&self, recipes: Vec<Recipe>) -> Result<Vec<Recipe>, DaoError> {
+        let bases = recipes.iter().map(|recipe| slugify(&recipe.title)).collect::<Vec<String>>();
+        let mut taken = self.get_taken_slugs(&bases).await?;
+        Ok(recipes.into_iter().zip(bases).map(|(mut recipe, base)| {
+            let slug = unique_slug(&base, &taken);
+            taken.insert(slug.clone());
+            recipe.slug = Some(slug);
+            recipe
+        }).collect())
+    }
+
+    /// stored slugs equal to one of the bases or one of their suffixed variants
+    async fn get_taken_slugs(&self, bases: &[String]) -> Result<HashSet<String>, DaoError> {
+        if bases.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let filter = taken_slugs_filter(bases);
+        let mut options = FindOptions::default();
+        options.projection = Some(doc! { JSON_ATTR_SLUG: 1 });
+        let query = async {
+            let cursor = self.database.collection(RECIPE_COLLECTION).find(filter.clone(), options).await?;
+            Ok::<_, Error>(cursor.collect::<Vec<Result<Document, Error>>>().await)
+        };
+        self.time("get_taken_slugs", &filter, query).await?
+            .map_err(DaoError::from)?
+            .into_iter()
+            .map(|doc| doc.map_err(DaoError::from)
+                .and_then(|doc| doc.get_str(JSON_ATTR_SLUG).map(String::from).map_err(DaoError::from)))
+            .collect::<Result<HashSet<String>, DaoError>>()
+            .log_if_err(|err| error!("Could not get taken slugs. filter={:?}, Err={:#?}", filter, err))
+    }
+
+    /// recipe without image, joined with its collections, average rating and comment count
+    pub async fn get_one_recipe_full(&self, id: ObjectId) -> Result<FullRecipe, DaoError> {
+        let query = async {
+            let mut cursor = self.database
+                .collection(RECIPE_COLLECTION)
+                .aggregate(full_recipe_pipeline(id.clone()), None).await?;
+            cursor.next().await.transpose()
+        };
+        let result = self.time("get_one_recipe_full", &object_id_into_doc(id.clone()), query).await?
+            .transpose()
+            .map(|doc| doc.map_err(DaoError::from)
+                .and_then(|doc| FullRecipe::try_from(doc).map_err(DaoError::from)));
+
+        match result {
+            Some(Ok(recipe)) => {
+                info!("Got one full recipe from db. id={:#?}", id.clone());
+                Ok(recipe)
+            }
+            Some(Err(error)) => {
+                error!("Got one full recipe, but could not format id={:#?}, error={:#?}", id.clone(), error);
+                Err(error)
+            }
+            None => {
+                error!("get full recipe, recipe Not found: id={:#?}", id);
+                Err(DaoError::DocumentNotFound)
+            }
+        }
+    }
+
+    /// published, not archived recipes sharing normalized ingredient titles with the given recipe, most similar first
+    pub async fn get_similar_recipes(&self, id: ObjectId, limit: i64) -> Result<Vec<SimilarRecipe>, DaoError> {
+        let titles = self.get_one_recipe_without_image(id.clone()).await?
+            .ingredients
+            .iter()
+            .map(|ingredient| ingredient.normalized_title())
+            .collect::<Vec<String>>();
+
+        let query = async {
+            let cursor = self.database
+                .collection(RECIPE_COLLECTION)
+                .aggregate(similar_recipes_pipeline(id.clone(), titles, limit), None).await?;
+            Ok::<_, Error>(cursor.collect::<Vec<Result<Document, Error>>>().await)
+        };
+        let recipes = self.time("get_similar_recipes", &object_id_into_doc(id.clone()), query).await?
+            .map_err(DaoError::from)?
+            .into_iter()
+            .map(|doc| doc.map_err(DaoError::from)
+                .and_then(|doc| SimilarRecipe::try_from(doc).map_err(DaoError::from)))
+            .collect::<Result<Vec<SimilarRecipe>, DaoError>>();
+
+        recipes
+            .log_if_ok(|recipes| info!("Got {} similar recipes from db. id={:?}", recipes.len(), id))
+            .log_if_err(|err| error!("Could not get similar recipes. id={:?}, Err={:#?}", id, err))
+    }
+
+    /// most common sets of tags used together, most frequent first
+    pub async fn get_tag_combos(&self, limit: i64) -> Result<Vec<TagCombo>, DaoError> {
+        let query = async {
+            let cursor = self.database
+                .collection(RECIPE_COLLECTION)
+                .aggregate(tag_combos_pipeline(limit), None).await?;
+            Ok::<_, Error>(cursor.collect::<Vec<Result<Document, Error>>>().await)
+        };
+        let combos = self.time("get_tag_combos", &doc! {}, query).await?
+            .map_err(DaoError::from)?
+            .into_iter()
+            .map(|doc| doc.map_err(DaoError::from)
+                .and_then(|doc| TagCombo::try_from(doc).map_err(DaoError::from)))
+            .collect::<Result<Vec<TagCombo>, DaoError>>();
+
+        combos
+            .log_if_ok(|combos| info!("Got {} tag combos from db", combos.len()))
+            .log_if_err(|err| error!("Could not get tag combos. Err={:#?}", err))
+    }
+
+    /// number of recipes matching the filter per stored difficulty
+    pub async fn get_difficulty_counts(&self, filter: Document) -> Result<Vec<(String, u64)>, DaoError> {
+        let result = match (self.aggregate_difficulty_counts(&filter).await, text_search_fallback(&filter)) {
+            (Err(DaoError::TextIndexMissing), Some(fallback)) => {
+                warn!("Text index missing, falling back to regex search. filter={:?}", fallback);
+                self.aggregate_difficulty_counts(&fallback).await
+            }
+            (result, _) => result
+        };
+        result.log_if_err(|err| error!("Could not get difficulty counts. filter={:?}, Err={:#?}", filter, err))
+    }
+
+    async fn aggregate_difficulty_counts(&self, filter: &Document) -> Result<Vec<(String, u64)>, DaoError> {
+        let pipeline = vec![
+            doc! { "$match": filter.clone() },
+            doc! { "$group": { "_id": "$difficulty", "count": { "$sum": 1 } } },
+        ];
+        let query = async {
+            let cursor = self.database.collection(RECIPE_COLLECTION).aggregate(pipeline, None).await?;
+            Ok::<_, Error>(cursor.collect::<Vec<Result<Document, Error>>>().await)
+        };
+        self.time("get_difficulty_counts", filter, query).await?
+            .map_err(DaoError::from)?
+            .into_iter()
+            .map(|doc| doc.map_err(DaoError::from)
+                .map(|doc| (doc.get_str("_id").unwrap_or_default().to_string(), doc.get_i32("count").unwrap_or(0).max(0) as u64)))
+            .collect::<Result<Vec<(String, u64)>, DaoError>>()
+    }
+
+    /// the recipes matching the filter grouped by the field, largest group first, with at most `limit` summaries per group
+    pub async fn get_recipe_groups(&self, group_by: GroupBy, filter: Document, limit: i64, max_time: Duration) -> Result<Vec<RecipeGroup>, DaoError> {
+        let query = async {
+            let cursor = self.database
+                .collection(RECIPE_COLLECTION)
+                .aggregate(recipe_groups_pipeline(group_by, filter.clone(), limit), budget(max_time)).await?;
+            Ok::<_, Error>(cursor.collect::<Vec<Result<Document, Error>>>().await)
+        };
+        let groups = self.time("get_recipe_groups", &filter, query).await?
+            .map_err(DaoError::from)?
+            .into_iter()
+            .map(|doc| doc.map_err(DaoError::from)
+                .and_then(|doc| RecipeGroup::try_from(doc).map_err(DaoError::from)))
+            .collect::<Result<Vec<RecipeGroup>, DaoError>>();
+
+        groups
+            .log_if_ok(|groups| info!("Got {} recipe groups by {:?} from db", groups.len(), group_by))
+            .log_if_err(|err| error!("Could not get recipe groups. by={:?}, Err={:#?}", group_by, err))
+    }
+
+    /// one page of the groups of recipes sharing their dedup signature, largest first, with the number of all groups
+    pub async fn get_duplicate_groups(&self, skip: usize, limit: usize, max_time: Duration) -> Result<(Vec<DuplicateGroup>, u64), DaoError> {
+        let query = async {
+            let cursor = self.database
+                .collection(RECIPE_COLLECTION)
+                .aggregate(duplicate_groups_pipeline(i64::try_from(skip).unwrap_or(i64::MAX), limit as i64), budget(max_time)).await?;
+            Ok::<_, Error>(cursor.collect::<Vec<Result<Document, Error>>>().await)
+        };
+        let page = self.time("get_duplicate_groups", &doc! {}, query).await?
+            .map_err(DaoError::from)?
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| Ok(Document::new()))
+            .map_err(DaoError::from)?;
+
+        let total = page.get_array("total").ok()
+            .and_then(|total| total.first())
+            .and_then(|total| total.as_document())
+            .and_then(|total| total.get_i32("count").ok())
+            .unwrap_or(0)
+            .max(0) as u64;
+        page.get_array("groups").cloned().unwrap_or_default()
+            .into_iter()
+            .map(|group| match group {
+                Bson::Document(group) => DuplicateGroup::try_from(group).map_err(DaoError::from),
+                _ => Err(DaoError::RecipeFormatError("Error getting duplicate group".to_string())),
+            })
+            .collect::<Result<Vec<DuplicateGroup>, DaoError>>()
+            .map(|groups| (groups, total))
+            .log_if_ok(|(groups, total)| info!("Got {} of {} duplicate groups from db", groups.len(), total))
+            .log_if_err(|err| error!("Could not get duplicate groups. Err={:#?}", err))
+    }
+
+    /// the image together with the content type it had before being re-encoded on upload
+    pub async fn get_one_recipe_image(&self, id: ObjectId) -> Result<(ImageBase64String, Option<String>), DaoError> {
         let filter = object_id_into_doc(id.clone());
 
         let options = Dao::recipe_without_image_find_options();
 
-        let image: Option<Document> = self.database
-            .collection(RECIPE_COLLECTION)
-            .find_one(filter, options)
-            .await
-            .map_err(|err| DaoError::from(err))?;
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let find = collection.find_one(filter.clone(), options);
+        let image: Option<Document> = self.time("get_one_recipe_image", &filter, find).await?
+            .map_err(DaoError::from)?;
 
         match image {
             Some(image) => {
+                let original_content_type = image.get_str(ORIGINAL_IMAGE_CONTENT_TYPE).ok().map(String::from);
                 match image.get_str("image") {
                     Ok(image) => {
                         info!("Got one recipe from db. id={:?}", id.clone());
-                        Ok(image.to_string())
+                        Ok((image.to_string(), original_content_type))
                     }
                     Err(_) => {
                         error!("Image not found, or not string id={:#?}", id.clone());
@@ -157,21 +1166,49 @@ impl Dao {
         }
     }
 
-    pub async fn update_one_recipe_image(&self, id: ObjectId, image: Option<String>) -> Result<(), DaoError> {
+    /// returns the thumbnail, or the original image when no thumbnail could be generated
+    pub async fn get_one_recipe_thumbnail(&self, id: ObjectId) -> Result<ImageBase64String, DaoError> {
+        let filter = object_id_into_doc(id.clone());
+
+        let mut options = FindOneOptions::default();
+        options.projection = Some(doc! {"image": 1, "thumbnail": 1, "_id": 0});
+
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let find = collection.find_one(filter.clone(), options);
+        let images: Option<Document> = self.time("get_one_recipe_thumbnail", &filter, find).await?
+            .map_err(DaoError::from)?;
+
+        match images.as_ref().and_then(|images| images.get_str("thumbnail").or_else(|_| images.get_str("image")).ok()) {
+            Some(thumbnail) => {
+                info!("Got one recipe thumbnail from db. id={:?}", id);
+                Ok(thumbnail.to_string())
+            }
+            None => {
+                error!("Thumbnail not found id={:#?}", id);
+                Err(DaoError::DocumentNotFound)
+            }
+        }
+    }
+
+    /// sets or removes the image together with its thumbnail
+    /// `original_content_type` is kept for images re-encoded on upload
+    pub async fn update_one_recipe_image(&self, id: ObjectId, image: Option<String>, thumbnail: Option<String>, original_content_type: Option<String>) -> Result<(), DaoError> {
         let query = object_id_into_doc(id.clone());
 
-        let update = match image {
-            Some(image) => UpdateModifications::Document(
-                doc! { "$set" : { "image" : image} }
-            ),
-            None => UpdateModifications::Document(
-                doc! { "$set" : { "image" : Bson::Null} }
-            )
+        let mut set = doc! {
+            "image" : image.map_or(Bson::Null, Bson::String),
+            "thumbnail" : thumbnail.map_or(Bson::Null, Bson::String),
+            ORIGINAL_IMAGE_CONTENT_TYPE: original_content_type.map_or(Bson::Null, Bson::String)
         };
+        set.extend(self.field_modified.set_modified(vec!["image"], Utc::now()));
+        let update = UpdateModifications::Document(doc! { "$set" : set });
 
-        match self.database.collection(RECIPE_COLLECTION)
-            .update_one(query, update, None).await {
-            Ok(result) => match result.modified_count {
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let update = collection.update_one(query.clone(), update, None);
+        let result = self.time("update_one_recipe_image", &query, update).await?;
+        self.recipe_cache.invalidate(std::iter::once(&id));
+        match result {
+            Ok(result) => match result.matched_count {
                 0 => {
                     info!("Not Updated image, doc not found with id={:#?}", &id);
                     Err(DaoError::DocumentNotFound)
@@ -192,25 +1229,186 @@ impl Dao {
     fn recipe_without_image_find_options() -> Option<FindOneOptions> {
         let mut options = FindOneOptions::default();
         options.projection = Some(db_projection_only_image());
-        let options = Some(options);
-        options
+        Some(options)
     }
 
     fn recipe_only_image_find_options() -> Option<FindOneOptions> {
         let mut options = FindOneOptions::default();
         options.projection = Some(Recipe::default_projection_no_image());
-        let options = Some(options);
-        options
+        Some(options)
     }
 
-    pub async fn delete_one_recipe(&self, id: ObjectId) -> Result<(), DaoError> {
-        let query = object_id_into_doc(id.clone());
+    /// refuses to delete a recipe which is part of a collection, unless forced which also removes it from the collections
+    pub async fn delete_one_recipe(&self, id: ObjectId, force: bool) -> Result<(), DaoError> {
+        if !force {
+            let collections = self.get_recipe_references(id.clone()).await?;
+            if !collections.is_empty() {
+                info!("Recipe still referenced, not deleted. id={:#?}, collections={:#?}", &id, &collections);
+                return Err(DaoError::RecipeReferenced { collections });
+            }
+        }
+        self.delete_recipe_document(id.clone()).await?;
+        self.delete_recipe_versions(vec![id.clone()]).await?;
+        if force {
+            self.remove_recipe_from_collections(id).await?;
+        }
+        Ok(())
+    }
 
-        match self.database.collection(RECIPE_COLLECTION).delete_one(query, None).await {
-            Ok(delete_result) => match delete_result.deleted_count {
-                1 => {
-                    info!("Deleted one recipe from db. id={:#?}", &id);
-                    Ok(())
+    /// removes the version snapshots of deleted recipes
+    async fn delete_recipe_versions(&self, ids: Vec<ObjectId>) -> Result<(), DaoError> {
+        let query = doc! { "recipeId": { "$in": ids } };
+        let collection = self.database.collection(RECIPE_VERSIONS_COLLECTION);
+        let delete = collection.delete_many(query.clone(), None);
+        self.time("delete_recipe_versions", &query, delete).await?
+            .map_err(DaoError::from)
+            .log_if_ok(|result| info!("Deleted recipe versions from db. count={}", result.deleted_count))
+            .log_if_err(|err| error!("Could not delete recipe versions. Err={:#?}", err))
+            .map(|_| ())
+    }
+
+    /// Adds the tag of the rule to the recipes with a matching ingredient which lack it as their next version,
+    /// returns the ids of the recipes tagged. Each update checks the rule again, so a recipe tagged
+    /// or changed in the meantime is only reported when this update tagged it
+    pub async fn apply_retag_rule(&self, rule: &RetagRule) -> Result<Vec<ObjectId>, DaoError> {
+        let candidates = self.get_recipe_ids(retag_filter(rule)).await?;
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let mut ids = Vec::with_capacity(candidates.len());
+        for id in candidates {
+            let mut query = retag_filter(rule);
+            query.insert("_id", id.clone());
+            let modified = Utc::now();
+            let mut set = doc! { "last_modified": modified };
+            set.extend(self.field_modified.set_modified(vec!["tags"], modified));
+            let update = UpdateModifications::Document(doc! {
+                "$addToSet": { "tags": rule.add_tag.clone() },
+                "$set": set,
+                "$inc": { "version": 1 }
+            });
+            let update = collection.update_one(query.clone(), update, None);
+            let result = self.time("apply_retag_rule", &query, update).await?;
+            self.recipe_cache.invalidate(std::iter::once(&id));
+            let result = result
+                .map_err(DaoError::from)
+                .log_if_err(|err| error!("Could not apply retag rule={:?}, Err={:#?}", rule, err))?;
+            if result.modified_count > 0 {
+                ids.push(id);
+            }
+        }
+        if !ids.is_empty() {
+            self.rehash_recipes(&ids).await?;
+        }
+        info!("Applied retag rule={:?}, tagged={}", rule, ids.len());
+        Ok(ids)
+    }
+
+    /// Rewrites the tags of every recipe through `normalize_tags` as its next version, recipes with the same
+    /// tags are updated together and only while they still have these tags, a recipe retagged in the meantime
+    /// keeps its new tags. Returns the number of modified recipes, so none once all tags are normalized
+    pub async fn normalize_all_tags(&self) -> Result<u64, DaoError> {
+        let filter = doc! { "tags.0": { "$exists": true } };
+        let mut options = FindOptions::default();
+        options.projection = Some(doc! { "tags": 1 });
+        let query = async {
+            let cursor = self.database.collection(RECIPE_COLLECTION).find(filter.clone(), options).await?;
+            Ok::<_, Error>(cursor.collect::<Vec<Result<Document, Error>>>().await)
+        };
+        let mut changed: BTreeMap<Vec<String>, Vec<ObjectId>> = BTreeMap::new();
+        for doc in self.time("normalize_all_tags", &filter, query).await?.map_err(DaoError::from)? {
+            let doc = doc.map_err(DaoError::from)?;
+            let tags = Recipe::extract_tags(&doc)?;
+            if normalize_tags(&tags) != tags {
+                changed.entry(tags).or_default().push(doc.get_object_id("_id")?.clone());
+            }
+        }
+
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let modified = Utc::now();
+        let mut count = 0;
+        for (tags, ids) in changed {
+            let normalized = normalize_tags(&tags);
+            let query = doc! { "_id": { "$in": ids.clone() }, "tags": tags };
+            let mut set = doc! { "tags": normalized, "last_modified": modified };
+            set.extend(self.field_modified.set_modified(vec!["tags"], modified));
+            let update = UpdateModifications::Document(doc! { "$set": set, "$inc": { "version": 1 } });
+            let update = collection.update_many(query.clone(), update, None);
+            let result = self.time("normalize_all_tags", &query, update).await?;
+            self.recipe_cache.invalidate(ids.iter());
+            count += result
+                .map_err(DaoError::from)
+                .log_if_err(|err| error!("Could not normalize tags. Err={:#?}", err))?
+                .modified_count as u64;
+            self.rehash_recipes(&ids).await?;
+        }
+        info!("Normalized tags. recipes={}", count);
+        Ok(count)
+    }
+
+    /// deletes the recipes with their versions and removes them from the collections, returns the number of deleted recipes
+    pub async fn delete_many_recipes(&self, ids: Vec<ObjectId>) -> Result<u64, DaoError> {
+        let query = doc! { "_id": { "$in": ids.clone() } };
+        let recipes = self.database.collection(RECIPE_COLLECTION);
+        let delete = recipes.delete_many(query.clone(), None);
+        let deleted = self.time("delete_many_recipes", &query, delete).await;
+        self.recipe_cache.invalidate(ids.iter());
+        let deleted = deleted?
+            .map_err(DaoError::from)
+            .log_if_ok(|result| info!("Deleted recipes from db. count={}", result.deleted_count))
+            .log_if_err(|err| error!("Could not delete recipes. Err={:#?}", err))?
+            .deleted_count;
+        self.delete_recipe_versions(ids.clone()).await?;
+
+        let references = doc! { "recipeIds": { "$in": ids.clone() } };
+        let update = doc! { "$pullAll": { "recipeIds": ids } };
+        let collections = self.database.collection(COLLECTIONS_COLLECTION);
+        let update = collections.update_many(references.clone(), update, None);
+        self.time("remove_recipes_from_collections", &references, update).await?
+            .map_err(DaoError::from)
+            .log_if_ok(|result| info!("Removed deleted recipes from collections. count={}", result.modified_count))
+            .log_if_err(|err| error!("Could not remove deleted recipes from collections. Err={:#?}", err))?;
+        Ok(deleted as u64)
+    }
+
+    /// ids of the collections containing the recipe
+    pub async fn get_recipe_references(&self, id: ObjectId) -> Result<Vec<ObjectId>, DaoError> {
+        let filter = doc! { "recipeIds": id.clone() };
+        let options = FindOptions::builder().projection(doc! { "_id": 1 }).sort(doc! { "_id": 1 }).build();
+        let query = async {
+            let cursor = self.database.collection(COLLECTIONS_COLLECTION).find(filter.clone(), options).await?;
+            cursor.collect::<Vec<Result<Document, Error>>>().await.into_iter().collect::<Result<Vec<Document>, Error>>()
+        };
+        self.time("get_recipe_references", &filter, query).await?
+            .map_err(DaoError::from)?
+            .into_iter()
+            .map(|collection| collection.get_object_id("_id").map(|id| id.to_owned()).map_err(DaoError::from))
+            .collect::<Result<Vec<ObjectId>, DaoError>>()
+            .log_if_err(|err| error!("Could not get references of recipe id={:#?}, Err={:#?}", id, err))
+    }
+
+    async fn remove_recipe_from_collections(&self, id: ObjectId) -> Result<(), DaoError> {
+        let query = doc! { "recipeIds": id.clone() };
+        let update = doc! { "$pull": { "recipeIds": id.clone() } };
+        let collection = self.database.collection(COLLECTIONS_COLLECTION);
+        let update = collection.update_many(query.clone(), update, None);
+        self.time("remove_recipe_from_collections", &query, update).await?
+            .map_err(DaoError::from)
+            .log_if_ok(|result| info!("Removed recipe from collections id={:#?}, count={}", id, result.modified_count))
+            .log_if_err(|err| error!("Could not remove recipe from collections id={:#?}, Err={:#?}", id, err))
+            .map(|_| ())
+    }
+
+    async fn delete_recipe_document(&self, id: ObjectId) -> Result<(), DaoError> {
+        let query = object_id_into_doc(id.clone());
+
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let delete = collection.delete_one(query.clone(), None);
+        let result = self.time("delete_one_recipe", &query, delete).await?;
+        self.recipe_cache.invalidate(std::iter::once(&id));
+        match result {
+            Ok(delete_result) => match delete_result.deleted_count {
+                1 => {
+                    info!("Deleted one recipe from db. id={:#?}", &id);
+                    Ok(())
                 }
                 _ => {
                     error!("Deleted no recipe from db. id={:#?}", &id);
@@ -224,17 +1422,503 @@ impl Dao {
         }
     }
 
-    pub async fn get_many_recipes(&self, pagination: Option<Pagination>) -> Result<Vec<Recipe>, DaoError> {
-        get_many_recipes(&self.database, pagination).await
+    /// summaries of the recipes, only the summary fields are read
+    pub async fn get_many_recipes(&self, pagination: Option<Pagination>, filter: Document) -> Result<Vec<RecipeSummary>, DaoError> {
+        self.get_many_recipe_documents(pagination, filter, Some(RecipeSummary::projection())).await?
+            .into_iter()
+            .map(RecipeSummary::try_from)
+            .collect::<Result<Vec<RecipeSummary>, RecipeFormatError>>()
+            .map_err(|err| DaoError::DatabaseError(format!("{:#?}", err)))
             .log_if_ok(|recipes| info!("Get many recipes from db. ids={:#?}", recipes))
             .log_if_err(|err| error!("{:#?}", err))
     }
+
+    /// documents of the recipes as projected, e.g. by a field mask
+    pub async fn get_many_recipe_documents(&self, pagination: Option<Pagination>, filter: Document, projection: Option<Document>) -> Result<Vec<Document>, DaoError> {
+        let query = get_many_recipe_documents(&self.database, pagination, filter.clone(), projection.clone(), self.default_sort);
+        let result = match (self.time("get_many_recipes", &filter, query).await?, text_search_fallback(&filter)) {
+            (Err(DaoError::TextIndexMissing), Some(fallback)) => {
+                warn!("Text index missing, falling back to regex search. filter={:?}", fallback);
+                let query = get_many_recipe_documents(&self.database, pagination, fallback.clone(), projection, self.default_sort);
+                self.time("get_many_recipes", &fallback, query).await?
+            }
+            (result, _) => result
+        };
+        result.log_if_err(|err| error!("{:#?}", err))
+    }
+
+    /// `explain` with execution stats of the query listing the summaries of the recipes in the default order
+    pub async fn explain_recipes(&self, filter: Document) -> Result<QueryExplanation, DaoError> {
+        let explain = |filter: Document| doc! {
+            "explain": {
+                "find": RECIPE_COLLECTION,
+                "filter": filter,
+                "sort": listing_sort(None, self.default_sort),
+                "projection": RecipeSummary::projection(),
+            },
+            "verbosity": "executionStats",
+        };
+        let command = explain(filter.clone());
+        let result = match (self.time("explain_recipes", &filter, self.database.run_command(command, None)).await?.map_err(DaoError::from),
+                            text_search_fallback(&filter)) {
+            (Err(DaoError::TextIndexMissing), Some(fallback)) => {
+                warn!("Text index missing, explaining the regex search. filter={:?}", fallback);
+                self.time("explain_recipes", &fallback, self.database.run_command(explain(fallback.clone()), None)).await?
+                    .map_err(DaoError::from)
+            }
+            (result, _) => result
+        };
+        result
+            .and_then(|explanation| QueryExplanation::try_from(explanation).map_err(DaoError::from))
+            .log_if_err(|err| error!("Could not explain recipes query. filter={:?}, Err={:#?}", filter, err))
+    }
+
+    /// cursor over all recipes matching the filter in the default order, for responses written while reading
+    pub async fn get_recipes_cursor(&self, filter: Document, projection: Option<Document>) -> Result<Cursor, DaoError> {
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let options = |projection: Option<Document>| {
+            let mut options = FindOptions::default();
+            options.projection = projection;
+            options.sort = Some(listing_sort(None, self.default_sort));
+            options
+        };
+        let find = collection.find(filter.clone(), options(projection.clone()));
+        let result = match (self.time("get_recipes_cursor", &filter, find).await?.map_err(DaoError::from),
+                            text_search_fallback(&filter)) {
+            (Err(DaoError::TextIndexMissing), Some(fallback)) => {
+                warn!("Text index missing, falling back to regex search. filter={:?}", fallback);
+                let find = collection.find(fallback.clone(), options(projection));
+                self.time("get_recipes_cursor", &fallback, find).await?.map_err(DaoError::from)
+            }
+            (result, _) => result
+        };
+        result.log_if_err(|err| error!("Could not get recipes cursor. filter={:?}, Err={:#?}", filter, err))
+    }
+
+    /// cursor over the id, title, difficulty, cooking time, tags, equipment and ingredient count of the
+    /// recipes matching the filter, oldest first, for exports which should not hold all recipes in memory
+    pub async fn get_recipe_summaries(&self, filter: Document) -> Result<Cursor, DaoError> {
+        let pipeline = vec![
+            doc! { "$match": filter.clone() },
+            doc! { "$sort": { "_id": 1 } },
+            doc! { "$project": {
+                "title": 1,
+                "difficulty": 1,
+                "cookingTimeInMinutes": 1,
+                "tags": 1,
+                "equipment": 1,
+                JSON_ATTR_INGREDIENT_COUNT: 1,
+            } },
+        ];
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let aggregate = collection.aggregate(pipeline, None);
+        self.time("get_recipe_summaries", &filter, aggregate).await?
+            .map_err(DaoError::from)
+            .log_if_err(|err| error!("Could not get recipe summaries. filter={:?}, Err={:#?}", filter, err))
+    }
+
+    /// distinct cuisines of the recipes matching the filter, sorted alphabetically
+    pub async fn get_cuisines(&self, mut filter: Document) -> Result<Vec<String>, DaoError> {
+        filter.insert("cuisine", doc! { "$type": "string" });
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let distinct = collection.distinct("cuisine", filter.clone(), None);
+        let mut cuisines = self.time("get_cuisines", &filter, distinct).await?
+            .map_err(DaoError::from)
+            .log_if_err(|err| error!("Could not get cuisines. Err={:#?}", err))?
+            .into_iter()
+            .filter_map(|cuisine| cuisine.as_str().map(String::from))
+            .collect::<Vec<String>>();
+        cuisines.sort();
+        Ok(cuisines)
+    }
+
+    /// distinct equipment of the recipes, sorted alphabetically
+    pub async fn get_equipment(&self) -> Result<Vec<String>, DaoError> {
+        let filter = doc! {};
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let distinct = collection.distinct("equipment", filter.clone(), None);
+        let mut equipment = self.time("get_equipment", &filter, distinct).await?
+            .map_err(DaoError::from)
+            .log_if_err(|err| error!("Could not get equipment. Err={:#?}", err))?
+            .into_iter()
+            .filter_map(|tool| tool.as_str().map(String::from))
+            .collect::<Vec<String>>();
+        equipment.sort();
+        Ok(equipment)
+    }
+
+    /// ids of all recipes matching the filter
+    pub async fn get_recipe_ids(&self, filter: Document) -> Result<Vec<ObjectId>, DaoError> {
+        self.get_first_recipe_ids(filter, None).await
+    }
+
+    /// ids of at most `limit` recipes matching the filter, of all without limit
+    pub async fn get_first_recipe_ids(&self, filter: Document, limit: Option<i64>) -> Result<Vec<ObjectId>, DaoError> {
+        let mut options = FindOptions::default();
+        options.projection = Some(doc! {"_id": 1});
+        options.limit = limit;
+        let query = async {
+            let cursor = self.database.collection(RECIPE_COLLECTION).find(filter.clone(), options).await?;
+            Ok::<_, Error>(cursor.collect::<Vec<Result<Document, Error>>>().await)
+        };
+        self.time("get_recipe_ids", &filter, query).await?
+            .map_err(DaoError::from)?
+            .into_iter()
+            .map(|doc| doc.map_err(DaoError::from)
+                .and_then(|doc| doc.get_object_id("_id").cloned().map_err(DaoError::from)))
+            .collect::<Result<Vec<ObjectId>, DaoError>>()
+            .log_if_err(|err| error!("Could not get recipe ids. filter={:?}, Err={:#?}", filter, err))
+    }
+
+    /// cursor over the recipes matching the filter whose average rating matches the condition, e.g. `{ "$gte": 4.0 }`,
+    /// in the listing order and paged like `get_many_recipe_documents`. The average is joined from the ratings per recipe
+    pub async fn get_rated_recipes_cursor(&self, pagination: Option<Pagination>, filter: Document, average: Document, projection: Document) -> Result<Cursor, DaoError> {
+        let mut stages = vec![doc! { "$sort": listing_sort(pagination, self.default_sort) }];
+        if let Some(pagination) = pagination {
+            stages.push(doc! { "$skip": i64::try_from(pagination.skip()).unwrap_or(i64::MAX) });
+            stages.push(doc! { "$limit": i64::try_from(pagination.take()).unwrap_or(i64::MAX) });
+        }
+        stages.extend(large_fields_stages(&projection));
+        stages.push(doc! { "$project": projection.clone() });
+        self.aggregate_rated_recipes(filter, average, projection, stages).await
+    }
+
+    /// `get_rated_recipes_cursor` collected, documents as projected
+    pub async fn get_many_rated_recipe_documents(&self, pagination: Option<Pagination>, filter: Document, average: Document, projection: Document) -> Result<Vec<Document>, DaoError> {
+        self.get_rated_recipes_cursor(pagination, filter, average, projection).await?
+            .collect::<Vec<Result<Document, Error>>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<Document>, Error>>()
+            .map_err(DaoError::from)
+            .log_if_err(|err| error!("Could not read rated recipes. Err={:#?}", err))
+    }
+
+    /// summaries of the rated recipes, see `get_rated_recipes_cursor`
+    pub async fn get_many_rated_recipes(&self, pagination: Option<Pagination>, filter: Document, average: Document) -> Result<Vec<RecipeSummary>, DaoError> {
+        self.get_many_rated_recipe_documents(pagination, filter, average, RecipeSummary::projection()).await?
+            .into_iter()
+            .map(RecipeSummary::try_from)
+            .collect::<Result<Vec<RecipeSummary>, RecipeFormatError>>()
+            .map_err(|err| DaoError::DatabaseError(format!("{:#?}", err)))
+            .log_if_err(|err| error!("{:#?}", err))
+    }
+
+    /// number of the recipes matching the filter whose average rating matches the condition
+    pub async fn count_rated_recipes(&self, filter: Document, average: Document) -> Result<u64, DaoError> {
+        let mut cursor = self.aggregate_rated_recipes(filter, average, doc! { "_id": 1 }, vec![doc! { "$count": "count" }]).await?;
+        match cursor.next().await {
+            Some(result) => result.map_err(DaoError::from)
+                .and_then(|doc| doc.get_i32("count").map(|count| count as u64).map_err(DaoError::from)),
+            None => Ok(0),
+        }
+    }
+
+    /// the stages applied to the rated recipes, with the regex search in place of a missing text index
+    async fn aggregate_rated_recipes(&self, filter: Document, average: Document, projection: Document, stages: Vec<Document>) -> Result<Cursor, DaoError> {
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let pipeline = |filter: Document| rated_recipes_pipeline(filter, average.clone(), projection.clone()).into_iter().chain(stages.clone());
+        let aggregate = collection.aggregate(pipeline(filter.clone()), None);
+        let result = match (self.time("get_rated_recipes", &filter, aggregate).await?.map_err(DaoError::from),
+                            text_search_fallback(&filter)) {
+            (Err(DaoError::TextIndexMissing), Some(fallback)) => {
+                warn!("Text index missing, falling back to regex search. filter={:?}", fallback);
+                let aggregate = collection.aggregate(pipeline(fallback.clone()), None);
+                self.time("get_rated_recipes", &fallback, aggregate).await?.map_err(DaoError::from)
+            }
+            (result, _) => result
+        };
+        result.log_if_err(|err| error!("Could not get rated recipes. filter={:?}, average={:?}, Err={:#?}", filter, average, err))
+    }
+
+    pub async fn get_meal_plan(&self, id: ObjectId) -> Result<MealPlan, DaoError> {
+        let filter = object_id_into_doc(id.clone());
+        let collection = self.database.collection(MEAL_PLANS_COLLECTION);
+        let find = collection.find_one(filter.clone(), None);
+        self.time("get_meal_plan", &filter, find).await?
+            .map_err(DaoError::from)?
+            .ok_or(DaoError::DocumentNotFound)
+            .and_then(|doc| MealPlan::try_from(doc).map_err(DaoError::from))
+            .log_if_err(|err| error!("Could not get meal plan id={:#?}, Err={:#?}", id, err))
+    }
+
+    /// cooking times of the recipes by id in one query, ids of missing recipes are left out
+    pub async fn get_cooking_times(&self, ids: Vec<ObjectId>) -> Result<HashMap<ObjectId, u32>, DaoError> {
+        let filter = doc! { "_id": { "$in": ids } };
+        let mut options = FindOptions::default();
+        options.projection = Some(doc! { "_id": 1, "cookingTimeInMinutes": 1 });
+        let query = async {
+            let cursor = self.database.collection(RECIPE_COLLECTION).find(filter.clone(), options).await?;
+            Ok::<_, Error>(cursor.collect::<Vec<Result<Document, Error>>>().await)
+        };
+        self.time("get_cooking_times", &filter, query).await?
+            .map_err(DaoError::from)?
+            .into_iter()
+            .map(|doc| {
+                let doc = doc.map_err(DaoError::from)?;
+                let id = doc.get_object_id("_id").cloned().map_err(DaoError::from)?;
+                Ok((id, Recipe::extract_cooking_time(&doc)?))
+            })
+            .collect::<Result<HashMap<ObjectId, u32>, DaoError>>()
+            .log_if_err(|err| error!("Could not get cooking times. filter={:?}, Err={:#?}", filter, err))
+    }
+
+    /// the user owning the collection, None for collections without owner
+    pub async fn get_collection_owner(&self, id: ObjectId) -> Result<Option<String>, DaoError> {
+        let query = object_id_into_doc(id.clone());
+        let collections = self.database.collection(COLLECTIONS_COLLECTION);
+        let find = collections.find_one(query.clone(), None);
+        let collection = self.time("find_collection", &query, find).await?
+            .map_err(DaoError::from)?
+            .ok_or(DaoError::DocumentNotFound)
+            .log_if_err(|_| error!("Collection not found id={:#?}", id))?;
+        Ok(collection.get_str("owner").ok().map(String::from))
+    }
+
+    /// inserts the recipe and adds it to the collection, the recipe and its version are deleted again when adding fails
+    pub async fn insert_recipe_into_collection(&self, recipe: Recipe, collection_id: ObjectId) -> Result<Bson, DaoError> {
+        let id = self.insert_recipe(recipe).await?;
+        let recipe_id = id.as_object_id().cloned().ok_or(DaoError::DocumentNotFound)?;
+        if let Err(err) = self.add_recipes_to_collection(collection_id.clone(), vec![recipe_id.clone()]).await {
+            error!("Could not add new recipe to collection, deleting it. id={:#?}, collection={:#?}", recipe_id, collection_id);
+            self.delete_recipe_document(recipe_id.clone()).await.ok();
+            self.delete_recipe_versions(vec![recipe_id]).await.ok();
+            return Err(err);
+        }
+        Ok(id)
+    }
+
+    /// adds the existing recipes not yet part of the collection, the others are skipped
+    pub async fn add_recipes_to_collection(&self, collection_id: ObjectId, recipe_ids: Vec<ObjectId>) -> Result<AddManyResult, DaoError> {
+        let query = object_id_into_doc(collection_id.clone());
+        let collections = self.database.collection(COLLECTIONS_COLLECTION);
+        let find = collections.find_one(query.clone(), None);
+        let collection = self.time("find_collection", &query, find).await?
+            .map_err(DaoError::from)?
+            .ok_or(DaoError::DocumentNotFound)
+            .log_if_err(|_| error!("Collection not found id={:#?}", collection_id))?;
+        let contained = collection.get_array("recipeIds").cloned().unwrap_or_default();
+
+        let mut candidates: Vec<ObjectId> = Vec::new();
+        for id in recipe_ids.iter() {
+            if !candidates.contains(id) && !contained.contains(&Bson::ObjectId(id.clone())) {
+                candidates.push(id.clone());
+            }
+        }
+        let existing = self.get_recipe_ids(doc! {"_id": {"$in": candidates.clone()}}).await?;
+        let to_add = candidates.into_iter().filter(|id| existing.contains(id)).collect::<Vec<ObjectId>>();
+
+        if !to_add.is_empty() {
+            let update = doc! {"$addToSet": {"recipeIds": {"$each": to_add.clone()}}};
+            let update = collections.update_one(query.clone(), update, None);
+            self.time("add_recipes_to_collection", &query, update).await?
+                .map_err(DaoError::from)
+                .log_if_err(|err| error!("Could not add recipes to collection id={:#?}, Err={:#?}", collection_id, err))?;
+        }
+
+        let result = AddManyResult { added: to_add.len(), skipped: recipe_ids.len() - to_add.len() };
+        info!("Added recipes to collection id={:#?}, result={:?}", collection_id, result);
+        Ok(result)
+    }
+
+    /// raw `collStats` of the recipe collection
+    pub async fn get_collection_stats(&self) -> Result<Document, DaoError> {
+        let command = doc! { "collStats": RECIPE_COLLECTION };
+        let stats = self.database.run_command(command.clone(), None);
+        self.time("collStats", &command, stats).await?
+            .map_err(DaoError::from)
+            .log_if_err(|err| error!("Could not get collection stats. Err={:#?}", err))
+    }
+
+    /// raw `serverStatus` of the database server
+    pub async fn get_server_status(&self) -> Result<Document, DaoError> {
+        let command = doc! { "serverStatus": 1 };
+        let status = self.database.run_command(command.clone(), None);
+        self.time("serverStatus", &command, status).await?
+            .map_err(DaoError::from)
+            .log_if_err(|err| error!("Could not get server status. Err={:#?}", err))
+    }
+
+    /// the estimate from the collection metadata for unfiltered listings once it reaches the threshold,
+    /// the exact count otherwise
+    pub async fn count_recipes_or_estimate(&self, filter: Document, estimate_threshold: Option<u64>) -> Result<RecipeCount, DaoError> {
+        if let Some(threshold) = estimate_threshold.filter(|_| is_unfiltered(&filter)) {
+            let estimate = self.estimate_recipe_count().await?;
+            if estimate >= threshold {
+                return Ok(RecipeCount { total: estimate, is_estimate: true });
+            }
+        }
+        self.count_recipes(filter).await
+            .map(|total| RecipeCount { total, is_estimate: false })
+    }
+
+    async fn estimate_recipe_count(&self) -> Result<u64, DaoError> {
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let estimate = collection.estimated_document_count(None);
+        self.time("estimate_recipe_count", &doc! {}, estimate).await?
+            .map(|count| count as u64)
+            .map_err(DaoError::from)
+            .log_if_ok(|count| info!("Estimated recipes in db. count={}", count))
+            .log_if_err(|err| error!("Could not estimate recipe count. Err={:#?}", err))
+    }
+
+    pub async fn insert_template(&self, template: IngredientTemplate) -> Result<Bson, DaoError> {
+        let collection = self.database.collection(TEMPLATES_COLLECTION);
+        let insert = collection.insert_one(Document::from(template), None);
+        self.time("insert_template", &doc! {}, insert).await?
+            .map(|result| result.inserted_id)
+            .map_err(DaoError::from)
+            .log_if_ok(|id| info!("Added template in db. id={:?}", id))
+            .log_if_err(|err| error!("Could not add template. Err={:#?}", err))
+    }
+
+    pub async fn insert_unit(&self, unit: UnitDefinition) -> Result<Bson, DaoError> {
+        let collection = self.database.collection(UNITS_COLLECTION);
+        let insert = collection.insert_one(Document::from(unit), None);
+        self.time("insert_unit", &doc! {}, insert).await?
+            .map(|result| result.inserted_id)
+            .map_err(DaoError::from)
+            .log_if_ok(|id| info!("Added unit in db. id={:?}", id))
+            .log_if_err(|err| error!("Could not add unit. Err={:#?}", err))
+    }
+
+    /// the registered units in the order of their registration
+    pub async fn get_units(&self) -> Result<Vec<UnitDefinition>, DaoError> {
+        let mut options = FindOptions::default();
+        options.sort = Some(doc! { "_id": 1 });
+        let query = async {
+            let cursor = self.database.collection(UNITS_COLLECTION).find(doc! {}, options).await?;
+            Ok::<_, Error>(cursor.collect::<Vec<Result<Document, Error>>>().await)
+        };
+        self.time("get_units", &doc! {}, query).await?
+            .map_err(DaoError::from)?
+            .into_iter()
+            .map(|doc| doc.map_err(DaoError::from)
+                .and_then(|doc| UnitDefinition::try_from(doc).map_err(DaoError::from)))
+            .collect::<Result<Vec<UnitDefinition>, DaoError>>()
+            .log_if_err(|err| error!("Could not get units. Err={:#?}", err))
+    }
+
+    /// all templates when `ids` is None, otherwise the existing ones of the listed, sorted by name
+    pub async fn get_templates(&self, ids: Option<Vec<ObjectId>>) -> Result<Vec<IngredientTemplate>, DaoError> {
+        let filter = match ids {
+            Some(ids) => doc! { "_id": { "$in": ids } },
+            None => doc! {},
+        };
+        let mut options = FindOptions::default();
+        options.sort = Some(doc! { "name": 1 });
+        let query = async {
+            let cursor = self.database.collection(TEMPLATES_COLLECTION).find(filter.clone(), options).await?;
+            Ok::<_, Error>(cursor.collect::<Vec<Result<Document, Error>>>().await)
+        };
+        self.time("get_templates", &filter, query).await?
+            .map_err(DaoError::from)?
+            .into_iter()
+            .map(|doc| doc.map_err(DaoError::from)
+                .and_then(|doc| IngredientTemplate::try_from(doc).map_err(DaoError::from)))
+            .collect::<Result<Vec<IngredientTemplate>, DaoError>>()
+            .log_if_err(|err| error!("Could not get templates. filter={:?}, Err={:#?}", filter, err))
+    }
+
+    pub async fn get_template(&self, id: ObjectId) -> Result<IngredientTemplate, DaoError> {
+        let filter = object_id_into_doc(id.clone());
+        let collection = self.database.collection(TEMPLATES_COLLECTION);
+        let find = collection.find_one(filter.clone(), None);
+        self.time("get_template", &filter, find).await?
+            .map_err(DaoError::from)?
+            .ok_or(DaoError::DocumentNotFound)
+            .and_then(|doc| IngredientTemplate::try_from(doc).map_err(DaoError::from))
+            .log_if_err(|err| error!("Could not get template id={:#?}, Err={:#?}", id, err))
+    }
+
+    pub async fn update_template(&self, id: ObjectId, template: IngredientTemplate) -> Result<(), DaoError> {
+        let query = object_id_into_doc(id.clone());
+        let update = UpdateModifications::Document(doc! { "$set": Document::from(template) });
+        let collection = self.database.collection(TEMPLATES_COLLECTION);
+        let update = collection.update_one(query.clone(), update, None);
+        match self.time("update_template", &query, update).await? {
+            Ok(result) => match result.matched_count {
+                0 => {
+                    info!("Not updated template, doc not found with id={:#?}", &id);
+                    Err(DaoError::DocumentNotFound)
+                }
+                _ => {
+                    info!("Updated template in db with id={:#?}", &id);
+                    Ok(())
+                }
+            }
+            Err(err) => {
+                error!("Could not update template with id={:#?}, Err={:#?}", &id, err);
+                Err(DaoError::from(err))
+            }
+        }
+    }
+
+    /// recipes still referencing the template skip it when expanding
+    pub async fn delete_template(&self, id: ObjectId) -> Result<(), DaoError> {
+        let query = object_id_into_doc(id.clone());
+        let collection = self.database.collection(TEMPLATES_COLLECTION);
+        let delete = collection.delete_one(query.clone(), None);
+        match self.time("delete_template", &query, delete).await? {
+            Ok(result) if result.deleted_count == 0 => {
+                info!("Not deleted template, doc not found with id={:#?}", &id);
+                Err(DaoError::DocumentNotFound)
+            }
+            Ok(_) => {
+                info!("Deleted template from db. id={:#?}", &id);
+                Ok(())
+            }
+            Err(err) => {
+                error!("Could not delete template with id={:#?}, Err={:#?}", &id, err);
+                Err(DaoError::from(err))
+            }
+        }
+    }
+
+    pub async fn count_recipes(&self, filter: Document) -> Result<u64, DaoError> {
+        let collection = self.database.collection(RECIPE_COLLECTION);
+        let count = collection.count_documents(filter.clone(), None);
+        let result = match (self.time("count_recipes", &filter, count).await?.map_err(DaoError::from),
+                            text_search_fallback(&filter)) {
+            (Err(DaoError::TextIndexMissing), Some(fallback)) => {
+                warn!("Text index missing, falling back to regex search. filter={:?}", fallback);
+                let count = collection.count_documents(fallback.clone(), None);
+                self.time("count_recipes", &fallback, count).await?.map_err(DaoError::from)
+            }
+            (result, _) => result
+        };
+        result
+            .map(|count| count as u64)
+            .log_if_ok(|count| info!("Counted recipes in db. filter={:?}, count={}", filter, count))
+            .log_if_err(|err| error!("Could not count recipes. filter={:?}, Err={:#?}", filter, err))
+    }
 }
 
 
 impl From<Error> for DaoError {
     fn from(error: Error) -> Self {
-        DaoError::DatabaseError(format!("{:#?}", error))
+        if is_outage(&error) {
+            return DaoError::Unavailable { retry_after: None };
+        }
+        if is_missing_text_index(&error) {
+            return DaoError::TextIndexMissing;
+        }
+        if let ErrorKind::CommandError(command_error) = error.kind.as_ref() {
+            if command_error.code == UNAUTHORIZED_ERROR_CODE {
+                return DaoError::MissingPrivileges;
+            }
+            if command_error.code == MAX_TIME_EXPIRED_ERROR_CODE {
+                return DaoError::TimeBudgetExceeded;
+            }
+        }
+        match duplicate_key_message(&error) {
+            Some(message) if message.contains(TITLE_PER_AUTHOR_INDEX) => DaoError::DuplicateTitle { title: duplicate_folded_title(&message) },
+            Some(message) => {
+                let (field, value) = parse_duplicate_key_message(&message);
+                DaoError::DuplicateKey { field, value }
+            }
+            None => DaoError::DatabaseError(format!("{:#?}", error))
+        }
     }
 }
 
@@ -250,189 +1934,978 @@ impl From<RecipeFormatError> for DaoError {
     }
 }
 
+/// aborts the aggregation on the server once it ran for `max_time`
+fn budget(max_time: Duration) -> AggregateOptions {
+    AggregateOptions::builder().max_time(max_time).build()
+}
+
+/// the database could not be reached or did not answer in time
+fn is_outage(error: &Error) -> bool {
+    matches!(error.kind.as_ref(),
+        ErrorKind::Io(_) | ErrorKind::ServerSelectionError { .. } | ErrorKind::TokioTimeoutElapsed(_)
+        | ErrorKind::DnsResolve(_) | ErrorKind::NoDnsResults(_))
+}
+
+impl<T> QueryOutcome for Result<T, Error> {
+    fn is_outage(&self) -> bool {
+        matches!(self, Err(error) if is_outage(error))
+    }
+}
+
+impl<T> QueryOutcome for Result<T, DaoError> {
+    fn is_outage(&self) -> bool {
+        matches!(self, Err(DaoError::Unavailable { .. }))
+    }
+}
+
+/// also the error of dropping an index which does not exist, e.g. on a new database
+fn is_missing_index(error: &Error) -> bool {
+    match error.kind.as_ref() {
+        ErrorKind::CommandError(command_error) => command_error.code == INDEX_NOT_FOUND_ERROR_CODE,
+        _ => false
+    }
+}
+
+fn is_namespace_exists(error: &Error) -> bool {
+    match error.kind.as_ref() {
+        ErrorKind::CommandError(command_error) => command_error.code == NAMESPACE_EXISTS_ERROR_CODE,
+        _ => false
+    }
+}
+
+fn is_missing_text_index(error: &Error) -> bool {
+    match error.kind.as_ref() {
+        ErrorKind::CommandError(command_error) => command_error.code == INDEX_NOT_FOUND_ERROR_CODE
+            && command_error.message.contains("text index required"),
+        _ => false
+    }
+}
+
+/// Replaces a `$text` search by a case insensitive regex on the search fields, None without `$text`.
+/// The regex cannot use an index, so every recipe is scanned: fine for small collections,
+/// but search gets linearly slower with the collection size and loses the relevance of the text index.
+fn text_search_fallback(filter: &Document) -> Option<Document> {
+    let search = filter.get_document("$text").ok()?.get_str("$search").ok()?;
+    let pattern = escape_regex(search);
+    let conditions = SEARCH_FIELDS.iter()
+        .map(|field| Bson::Document(doc! { *field: { "$regex": pattern.clone(), "$options": "i" } }))
+        .collect::<Vec<Bson>>();
+
+    let mut fallback = filter.clone();
+    fallback.remove("$text");
+    fallback.insert("$or", conditions);
+    Some(fallback)
+}
+
+/// recipes with an ingredient title containing the pattern of the rule, ignoring case, which lack its tag
+fn retag_filter(rule: &RetagRule) -> Document {
+    doc! {
+        "ingredients.title": { "$regex": escape_regex(&rule.ingredient_contains), "$options": "i" },
+        "tags": { "$ne": rule.add_tag.clone() },
+    }
+}
+
+fn escape_regex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        if "\\.+*?()|[]{}^$".contains(character) {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+    escaped
+}
+
+fn duplicate_key_message(error: &Error) -> Option<String> {
+    match error.kind.as_ref() {
+        ErrorKind::WriteError(WriteFailure::WriteError(write_error))
+        if write_error.code == DUPLICATE_KEY_ERROR_CODE => Some(write_error.message.clone()),
+        ErrorKind::BulkWriteError(failure) => failure.write_errors.as_ref()?
+            .iter()
+            .find(|write_error| write_error.code == DUPLICATE_KEY_ERROR_CODE)
+            .map(|write_error| write_error.message.clone()),
+        ErrorKind::CommandError(command_error)
+        if command_error.code == DUPLICATE_KEY_ERROR_CODE => Some(command_error.message.clone()),
+        _ => None
+    }
+}
+
+/// extracts field and value from messages like
+/// `E11000 duplicate key error collection: db.recipes index: title_1 dup key: { title: "Pasta" }`
+fn parse_duplicate_key_message(message: &str) -> (String, String) {
+    let index_field = message.split("index: ").nth(1)
+        .and_then(|index| index.split_whitespace().next())
+        .and_then(|index| index.rsplit('$').next())
+        .map(|index| index.trim_end_matches(|c: char| c == '_' || c.is_ascii_digit() || c == '-'))
+        .unwrap_or_default();
+
+    let key = message.split("dup key: {").nth(1)
+        .and_then(|key| key.rsplit_once('}'))
+        .map(|(key, _)| key.trim())
+        .unwrap_or_default();
+    let (field, value) = key.split_once(':').unwrap_or(("", key));
+
+    let field = match field.trim() {
+        "" => index_field,
+        field => field,
+    };
+    let value = value.trim().trim_matches('"');
+    (field.to_string(), value.to_string())
+}
+
+/// the folded title of messages like
+/// `E11000 duplicate key error collection: db.recipes index: author_1_foldedTitle_1 dup key: { author: "ada", foldedTitle: "pasta" }`
+fn duplicate_folded_title(message: &str) -> String {
+    message.split(&format!("{}: ", JSON_ATTR_FOLDED_TITLE)).nth(1)
+        .and_then(|key| key.rsplit_once('}'))
+        .map(|(title, _)| title.trim().trim_matches('"').to_string())
+        .unwrap_or_default()
+}
+
+/// slugs only consist of letters, digits and dashes, so the bases need no escaping
+fn taken_slugs_filter(bases: &[String]) -> Document {
+    let mut bases = bases.to_vec();
+    bases.sort();
+    bases.dedup();
+    doc! { JSON_ATTR_SLUG: { "$regex": format!("^({})(-[0-9]+)?$", bases.join("|")) } }
+}
+
+/// `insert_many` reports the ids keyed by the index of the inserted document
+fn ids_in_input_order(inserted_ids: HashMap<usize, Bson>) -> Vec<Bson> {
+    let mut ids = inserted_ids.into_iter().collect::<Vec<(usize, Bson)>>();
+    ids.sort_by_key(|(index, _)| *index);
+    ids.into_iter().map(|(_, id)| id).collect()
+}
+
 fn object_id_into_doc(id: ObjectId) -> Document {
     doc! {"_id": Bson::ObjectId(id)}
 }
 
-fn db_projection_only_image() -> Document {
-    doc! {"image": 1, "_id": 0}
-}
+fn full_recipe_pipeline(id: ObjectId) -> Vec<Document> {
+    vec![
+        doc! { "$match": object_id_into_doc(id) },
+        doc! { "$project": Recipe::default_projection_no_image() },
+        doc! { "$lookup": {
+            "from": COLLECTIONS_COLLECTION,
+            "localField": "_id",
+            "foreignField": "recipeIds",
+            "as": "collections"
+        }},
+        doc! { "$lookup": {
+            "from": RATINGS_COLLECTION,
+            "localField": "_id",
+            "foreignField": "recipeId",
+            "as": "ratings"
+        }},
+        doc! { "$lookup": {
+            "from": COMMENTS_COLLECTION,
+            "localField": "_id",
+            "foreignField": "recipeId",
+            "as": "comments"
+        }},
+        doc! { "$addFields": {
+            "collections": {
+                "$map": { "input": "$collections", "as": "c", "in": { "id": "$$c._id", "name": "$$c.name" } }
+            },
+            "averageRating": { "$avg": "$ratings.value" },
+            "commentCount": { "$size": "$comments" }
+        }},
+        doc! { "$project": { "ratings": 0, "comments": 0 } },
+    ]
+}
+
+/// the recipes matching the filter joined with the average of their ratings, which has to match the condition.
+/// Recipes without numeric ratings have a null average, they only match conditions accepting null.
+/// The recipes are projected before the join, keeping the creation date to sort by and leaving out the large fields
+fn rated_recipes_pipeline(filter: Document, average: Document, projection: Document) -> Vec<Document> {
+    vec![
+        doc! { "$match": filter },
+        doc! { "$project": lean_projection(projection) },
+        doc! { "$lookup": {
+            "from": RATINGS_COLLECTION,
+            "localField": "_id",
+            "foreignField": "recipeId",
+            "as": "ratings"
+        }},
+        doc! { "$addFields": { "averageRating": { "$avg": "$ratings.value" } } },
+        doc! { "$match": { "averageRating": average } },
+        doc! { "$project": { "ratings": 0, "averageRating": 0 } },
+    ]
+}
+
+/// the projection with the creation date and without the large fields
+fn lean_projection(projection: Document) -> Document {
+    let mut lean = match is_inclusion(&projection) {
+        true => projection,
+        false => Document::new(),
+    };
+    match lean.is_empty() {
+        true => LARGE_RECIPE_FIELDS.iter().for_each(|field| { lean.insert(*field, 0); }),
+        false => {
+            LARGE_RECIPE_FIELDS.iter().for_each(|field| { lean.remove(field); });
+            lean.insert("created", 1);
+        }
+    }
+    lean
+}
+
+/// reads the large fields the projection returns again, for the recipes left after sorting and paging
+fn large_fields_stages(projection: &Document) -> Vec<Document> {
+    let fields = LARGE_RECIPE_FIELDS.iter()
+        .filter(|field| match is_inclusion(projection) {
+            true => projection.contains_key(field),
+            false => !projection.contains_key(field),
+        })
+        .map(|field| (field.to_string(), Bson::Document(doc! { "$arrayElemAt": [format!("$stored.{}", field), 0] })))
+        .collect::<Document>();
+    if fields.is_empty() {
+        return vec![];
+    }
+    let stored = LARGE_RECIPE_FIELDS.iter().map(|field| (field.to_string(), Bson::Int32(1))).collect::<Document>();
+    vec![
+        doc! { "$lookup": {
+            "from": RECIPE_COLLECTION,
+            "let": { "id": "$_id" },
+            "pipeline": [{ "$match": { "$expr": { "$eq": ["$_id", "$$id"] } } }, { "$project": stored }],
+            "as": "stored"
+        }},
+        doc! { "$addFields": fields },
+        doc! { "$project": { "stored": 0 } },
+    ]
+}
+
+fn is_inclusion(projection: &Document) -> bool {
+    projection.iter().any(|(key, value)| key != "_id" && !matches!(value, Bson::Int32(0) | Bson::Boolean(false)))
+}
+
+fn similar_recipes_pipeline(id: ObjectId, normalized_titles: Vec<String>, limit: i64) -> Vec<Document> {
+    vec![
+        doc! { "$match": { "_id": { "$ne": id }, "status": { "$ne": RecipeStatus::Draft }, "archived": { "$ne": true } } },
+        doc! { "$project": Recipe::default_projection_no_image() },
+        doc! { "$addFields": {
+            "similarity": { "$size": { "$setIntersection": [
+                { "$map": {
+                    "input": "$ingredients",
+                    "as": "i",
+                    "in": { "$toLower": { "$trim": { "input": "$$i.title" } } }
+                }},
+                normalized_titles
+            ]}}
+        }},
+        doc! { "$match": { "similarity": { "$gt": 0 } } },
+        doc! { "$sort": { "similarity": -1, "_id": 1 } },
+        doc! { "$limit": limit },
+    ]
+}
+
+/// counts the view events per recipe and joins the summaries of the most viewed recipes
+fn trending_recipes_pipeline(filter: Document, limit: i64) -> Vec<Document> {
+    let mut projection = RecipeSummary::projection();
+    projection.insert("views", 1);
+    vec![
+        doc! { "$match": filter },
+        doc! { "$group": { "_id": "$recipeId", "views": { "$sum": 1 } } },
+        doc! { "$sort": { "views": -1, "_id": 1 } },
+        doc! { "$lookup": { "from": RECIPE_COLLECTION, "localField": "_id", "foreignField": "_id", "as": "recipe" } },
+        doc! { "$unwind": "$recipe" },
+        doc! { "$match": { "recipe.archived": { "$ne": true }, "recipe.status": { "$ne": RecipeStatus::Draft } } },
+        doc! { "$limit": limit },
+        doc! { "$replaceRoot": { "newRoot": { "$mergeObjects": ["$recipe", { "views": "$views" }] } } },
+        doc! { "$project": projection },
+    ]
+}
+
+/// groups the recipes by their sorted, deduplicated tags
+fn tag_combos_pipeline(limit: i64) -> Vec<Document> {
+    vec![
+        doc! { "$match": { "tags.0": { "$exists": true } } },
+        doc! { "$project": { "tags": 1 } },
+        doc! { "$unwind": "$tags" },
+        doc! { "$group": { "_id": { "recipe": "$_id", "tag": "$tags" } } },
+        doc! { "$sort": { "_id.tag": 1 } },
+        doc! { "$group": { "_id": "$_id.recipe", "tags": { "$push": "$_id.tag" } } },
+        doc! { "$group": { "_id": "$tags", "count": { "$sum": 1 } } },
+        doc! { "$sort": { "count": -1, "_id": 1 } },
+        doc! { "$limit": limit },
+    ]
+}
+
+/// scalar fields form one group, each element of an array field one, recipes without a value are left out.
+/// Summaries carry the thumbnail only so the groups stay small before being cut to `limit`
+/// the thumbnails are joined after slicing, so that only the recipes returned carry them through the pipeline
+fn recipe_groups_pipeline(group_by: GroupBy, filter: Document, limit: i64) -> Vec<Document> {
+    vec![
+        doc! { "$match": filter },
+        doc! { "$project": {
+            "title": 1,
+            "difficulty": 1,
+            "cookingTimeInMinutes": 1,
+            "tags": 1,
+            JSON_ATTR_INGREDIENT_COUNT: 1,
+            "groupKey": format!("${}", group_by.field()),
+        } },
+        doc! { "$unwind": "$groupKey" },
+        doc! { "$match": { "groupKey": { "$type": "string" } } },
+        doc! { "$sort": { "title": 1, "_id": 1 } },
+        doc! { "$group": { "_id": "$groupKey", "count": { "$sum": 1 }, "recipes": { "$push": "$$ROOT" } } },
+        doc! { "$project": { "count": 1, "recipes": { "$slice": ["$recipes", limit] } } },
+        doc! { "$sort": { "count": -1, "_id": 1 } },
+        doc! { "$lookup": {
+            "from": RECIPE_COLLECTION,
+            "let": { "ids": "$recipes._id" },
+            "pipeline": [
+                { "$match": { "$expr": { "$in": ["$_id", "$$ids"] } } },
+                { "$project": { "thumbnail": 1 } },
+            ],
+            "as": "thumbnails",
+        } },
+        doc! { "$project": { "count": 1, "recipes": { "$map": {
+            "input": "$recipes",
+            "as": "recipe",
+            "in": { "$mergeObjects": [
+                "$$recipe",
+                { "$arrayElemAt": [{ "$filter": { "input": "$thumbnails", "as": "stored", "cond": { "$eq": ["$$stored._id", "$$recipe._id"] } } }, 0] },
+            ] },
+        } } } },
+    ]
+}
+
+/// Groups the recipes by their lowercase trimmed title and the sorted set of their lowercase trimmed ingredient titles.
+/// Ingredients are unwound and sorted to build the set, as `$addToSet` keeps no order to compare by
+fn duplicate_groups_pipeline(skip: i64, limit: i64) -> Vec<Document> {
+    let normalized = |value: &str| doc! { "$toLower": { "$trim": { "input": { "$ifNull": [value, ""] } } } };
+    vec![
+        doc! { "$project": {
+            "title": 1,
+            "signatureTitle": normalized("$title"),
+            "ingredient": { "$map": { "input": { "$ifNull": ["$ingredients", []] }, "in": normalized("$$this.title") } },
+        } },
+        doc! { "$unwind": { "path": "$ingredient", "preserveNullAndEmptyArrays": true } },
+        doc! { "$group": {
+            "_id": { "recipe": "$_id", "ingredient": "$ingredient" },
+            "title": { "$first": "$title" },
+            "signatureTitle": { "$first": "$signatureTitle" },
+        } },
+        doc! { "$sort": { "_id.ingredient": 1 } },
+        doc! { "$group": {
+            "_id": "$_id.recipe",
+            "title": { "$first": "$title" },
+            "signatureTitle": { "$first": "$signatureTitle" },
+            "ingredients": { "$push": "$_id.ingredient" },
+        } },
+        doc! { "$sort": { "title": 1, "_id": 1 } },
+        doc! { "$group": {
+            "_id": { "title": "$signatureTitle", "ingredients": "$ingredients" },
+            "count": { "$sum": 1 },
+            "recipes": { "$push": { "_id": "$_id", "title": "$title" } },
+        } },
+        doc! { "$match": { "count": { "$gt": 1 } } },
+        doc! { "$sort": { "count": -1, "_id.title": 1 } },
+        doc! { "$facet": {
+            "groups": [{ "$skip": skip }, { "$limit": limit }],
+            "total": [{ "$count": "count" }],
+        } },
+    ]
+}
+
+fn db_projection_only_image() -> Document {
+    doc! {"image": 1, ORIGINAL_IMAGE_CONTENT_TYPE: 1, "_id": 0}
+}
+
+async fn get_db_handler(write_concern: WriteConcernSettings, read_preference: ReadPreferenceSettings) -> Result<Database, Error> {
+    let mut client_options = ClientOptions::parse(URL).await?;
+    client_options.app_name = Some(APP_NAME.to_string());
+    write_concern.apply(&mut client_options);
+    read_preference.apply(&mut client_options);
+    let client = Client::with_options(client_options)?;
+    return Ok(client.database(DATABASE));
+}
+
+
+/// sorted by creation, in the direction of the pagination or the default one, ties in the order of the ids
+fn listing_sort(pagination: Option<Pagination>, default: DefaultSort) -> Document {
+    let sorting = pagination.and_then(|pagination| pagination.sorting).unwrap_or(default.sorting);
+    doc! { "created": sorting, "_id": sorting }
+}
+
+/// the projection defaults to leaving out the image on paged queries
+pub async fn get_many_recipe_documents(db: &Database, pagination: Option<Pagination>, filter: Document, projection: Option<Document>, default_sort: DefaultSort) -> Result<Vec<Document>, DaoError> {
+    let mut find_options = FindOptions::default();
+    let mut skip = 0;
+    let mut take = usize::MAX;
+    find_options.sort = Some(listing_sort(pagination, default_sort));
+    if let Some(pagination) = pagination {
+        skip = pagination.skip();
+        take = pagination.take();
+        find_options.projection = Some(Recipe::default_projection_no_image());
+    }
+    if projection.is_some() {
+        find_options.projection = projection;
+    }
+
+    match db.collection(RECIPE_COLLECTION).find(filter, find_options).await {
+        Ok(cursor) => cursor
+            .skip(skip)
+            .take(take)
+            .collect::<Vec<Result<Document, Error>>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<Document>, Error>>()
+            .map_err(|err| {
+                DaoError::DatabaseError(format!("{:#?}", err))
+            }),
+        Err(err) => Err(DaoError::from(err))
+    }
+}
+
+
+#[cfg(test)]
+pub mod dao_tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use bson::{Bson, Document};
+    use bson::oid::ObjectId;
+    use chrono::{Duration, Timelike};
+    use chrono::Utc;
+    use futures_util::future::join_all;
+    use futures_util::poll;
+    use log::LevelFilter;
+    use mongodb::{Client, Database};
+    use mongodb::error::{CommandError, Error, ErrorKind};
+    use mongodb::options::{ClientOptions, SelectionCriteria};
+    use serial_test::serial;
+    use simplelog::{Config, TerminalMode, TermLogger};
+
+    use crate::dao::{Dao, DaoError, ids_in_input_order, RECIPE_COLLECTION, is_missing_text_index, duplicate_folded_title, parse_duplicate_key_message, retag_filter, taken_slugs_filter, text_search_fallback, lean_projection, large_fields_stages};
+    use crate::model::difficulty::Difficulty;
+    use crate::model::ingredients::Ingredient;
+    use crate::model::measurement_unit::MeasurementUnit;
+    use crate::model::recipe::Recipe;
+    use crate::model::recipe_group::GroupBy;
+    use crate::model::retag_rule::RetagRule;
+    use crate::model::recipe_status::RecipeStatus;
+    use crate::model::recipe_summary::RecipeSummary;
+    use crate::model::step_timer::StepTimer;
+    use crate::pagination::{DefaultSort, Pagination};
+    use crate::read_preference::ReadPreferenceSettings;
+    use crate::recipe_cache::RecipeCache;
+    use crate::read_preference::read_preference_tests::secondary_preferred;
+    use crate::circuit_breaker::CircuitBreaker;
+    use crate::field_modified::FieldModifiedTracking;
+    use crate::single_flight::ReadCoalescing;
+    use crate::slow_query::SlowQueryLog;
+    use crate::title_constraint::{JSON_ATTR_FOLDED_TITLE, TitleConstraint};
+    use crate::write_concern::WriteConcernSettings;
+    use crate::write_concern::write_concern_tests::majority;
+
+    const TEST_URL: &str = "mongodb://localhost:26666";
+    const TEST_APP_NAME: &str = "Zellinotes development recipes";
+    const TEST_DATABASE: &str = "test_zellinotes_development_recipes";
+    const UNREACHABLE_TEST_URL: &str = "mongodb://127.0.0.1:1";
+
+    pub fn create_one_recipe_without_image() -> Recipe {
+        Recipe {
+            _id: ObjectId::new(),
+            cooking_time_in_minutes: 10,
+            created: Utc::now().with_nanosecond(0).unwrap(),
+            last_modified: Utc::now().with_nanosecond(0).unwrap(),
+            ingredients: vec![],
+            version: 1,
+            difficulty: Difficulty::Easy,
+            description: "".to_string(),
+            title: "".to_string(),
+            tags: vec![],
+            image_base64: None,
+            instructions: vec![],
+            default_servings: 1,
+            recipe_yield: None,
+            archived: false,
+            slug: None,
+            cuisine: None,
+            language: None,
+            template_ids: vec![],
+            equipment: vec![],
+            status: RecipeStatus::Published,
+            author: None,
+            notes: None,
+            source: None,
+            step_timers: vec![],
+            field_modified: BTreeMap::new(),
+        }
+    }
+
+    pub fn create_one_recipe_with_image() -> Recipe {
+        let mut recipe = create_one_recipe_without_image();
+        recipe.image_base64 = Some("image".to_string());
+        recipe
+    }
+
+    pub fn create_many_recipes_without_images(amount: i32) -> Vec<Recipe> {
+        (0..amount).map(|i| {
+            let mut x = create_one_recipe_without_image();
+            x.title = i.to_string();
+            x.created = Utc::now().with_nanosecond(0).unwrap() + Duration::days(1);
+            x
+        }).collect()
+    }
+
+    async fn init_test_database() -> Result<Database, Error> {
+        init_test_database_with(WriteConcernSettings::default(), ReadPreferenceSettings::default()).await
+    }
+
+    async fn init_test_database_with(write_concern: WriteConcernSettings, read_preference: ReadPreferenceSettings) -> Result<Database, Error> {
+        let mut client_options = ClientOptions::parse(TEST_URL).await?;
+        client_options.app_name = Some(TEST_APP_NAME.to_string());
+        write_concern.apply(&mut client_options);
+        read_preference.apply(&mut client_options);
+        let client = Client::with_options(client_options)?;
+        let db = client.database(TEST_DATABASE);
+        return Ok(db);
+    }
+
+    /// fake of a database outage, every call fails after a short server selection timeout
+    pub async fn unreachable_dao(circuit_breaker: CircuitBreaker) -> Dao {
+        let mut client_options = ClientOptions::parse(UNREACHABLE_TEST_URL).await.unwrap();
+        client_options.server_selection_timeout = Some(std::time::Duration::from_millis(50));
+        let database = Client::with_options(client_options).unwrap().database(TEST_DATABASE);
+        Dao { database, slow_query_log: SlowQueryLog::default(), field_encryption: None, circuit_breaker, field_modified: FieldModifiedTracking::default(), read_coalescing: ReadCoalescing::default(), recipe_cache: RecipeCache::default(), default_sort: DefaultSort::default() }
+    }
+
+    pub async fn before() -> Dao {
+        init_test_logger();
+        let dao = Dao { database: init_test_database().await.unwrap(), slow_query_log: SlowQueryLog::default(), field_encryption: None, circuit_breaker: CircuitBreaker::default(), field_modified: FieldModifiedTracking::default(), read_coalescing: ReadCoalescing::default(), recipe_cache: RecipeCache::default(), default_sort: DefaultSort::default() };
+        cleanup_after(dao).await;
+        Dao { database: init_test_database().await.unwrap(), slow_query_log: SlowQueryLog::default(), field_encryption: None, circuit_breaker: CircuitBreaker::default(), field_modified: FieldModifiedTracking::default(), read_coalescing: ReadCoalescing::default(), recipe_cache: RecipeCache::default(), default_sort: DefaultSort::default() }
+    }
+
+    fn init_test_logger() {
+        TermLogger::init(LevelFilter::Info,
+                                 Config::default(),
+                                 TerminalMode::Mixed).unwrap_or(());
+    }
+
+    pub async fn cleanup_after(dao: Dao) {
+        dao.database.drop(None).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn add_one_recipe_without_image_test() {
+        let dao = before().await;
+        let recipe = create_one_recipe_without_image();
+
+        let result = dao.insert_recipe(recipe).await;
+        assert!(result.is_ok());
+        result.unwrap().as_object_id().unwrap().timestamp().date();
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn dao_with_custom_write_concern_test() {
+        let dao = before().await;
+        let settings = majority();
+        let majority_dao = Dao { database: init_test_database_with(settings.clone(), ReadPreferenceSettings::default()).await.unwrap(), slow_query_log: SlowQueryLog::default(), field_encryption: None, circuit_breaker: CircuitBreaker::default(), field_modified: FieldModifiedTracking::default(), read_coalescing: ReadCoalescing::default(), recipe_cache: RecipeCache::default(), default_sort: DefaultSort::default() };
+        assert_eq!(majority_dao.database.collection(RECIPE_COLLECTION).write_concern(), settings.write_concern.as_ref());
+
+        let id = majority_dao.insert_recipe(create_one_recipe_without_image()).await.unwrap();
+        let id = id.as_object_id().unwrap().clone();
+        assert_eq!(majority_dao.get_one_recipe_without_image(id.clone()).await.is_ok(), true);
+        assert_eq!(majority_dao.delete_one_recipe(id, false).await.is_ok(), true);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn dao_with_secondary_preferred_reads_test() {
+        let dao = before().await;
+        let settings = secondary_preferred();
+        let secondary_dao = Dao { database: init_test_database_with(WriteConcernSettings::default(), settings.clone()).await.unwrap(), slow_query_log: SlowQueryLog::default(), field_encryption: None, circuit_breaker: CircuitBreaker::default(), field_modified: FieldModifiedTracking::default(), read_coalescing: ReadCoalescing::default(), recipe_cache: RecipeCache::default(), default_sort: DefaultSort::default() };
+        let expected = settings.read_preference.map(SelectionCriteria::ReadPreference);
+        assert_eq!(secondary_dao.database.collection(RECIPE_COLLECTION).selection_criteria(), expected.as_ref());
+
+        let id = secondary_dao.insert_recipe(create_one_recipe_without_image()).await.unwrap();
+        let id = id.as_object_id().unwrap().clone();
+        assert_eq!(secondary_dao.get_one_recipe_without_image(id).await.is_ok(), true);
+        assert_eq!(secondary_dao.get_many_recipes(None, Document::new()).await.unwrap().len(), 1);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn add_one_recipe_with_image_test() {
+        let dao = before().await;
+        let recipe = create_one_recipe_with_image();
+
+        let result = dao.insert_recipe(recipe).await;
+        assert!(result.is_ok());
+        result.unwrap().as_object_id().unwrap().timestamp().date();
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn update_one_recipe_ignore_image_test() {
+        let dao = before().await;
+        let mut recipe = create_one_recipe_with_image();
+
+        let result = dao.insert_recipe(recipe.clone()).await.unwrap();
+        let recipe_id = result.as_object_id().unwrap().to_owned();
+        recipe.title = "new".to_string();
+        recipe.image_base64 = Some("new_image".to_string());
+
+        let result = dao.update_recipe_at_version(recipe_id.clone(), recipe.clone(), None).await;
+        assert!(result.is_ok());
+
+        let result = dao.get_one_recipe_without_image(recipe_id.clone()).await;
+        assert_eq!(result.clone().unwrap().title, "new".to_string());
+        assert_eq!(result.unwrap().image_base64, None);
+
+        let result = dao.get_one_recipe_image(recipe_id).await;
+        assert_eq!(result.unwrap().0.as_str(), "image");
+
+        let result = dao.update_recipe_at_version(ObjectId::new(), recipe.clone(), None).await;
+        assert_eq!(result.err().unwrap(), DaoError::DocumentNotFound);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn recipe_versions_are_saved_test() {
+        let dao = before().await;
+        let mut recipe = create_one_recipe_with_image();
+        recipe.title = "first".to_string();
+
+        let result = dao.insert_recipe(recipe.clone()).await.unwrap();
+        let recipe_id = result.as_object_id().unwrap().to_owned();
+
+        let mut update = create_one_recipe_without_image();
+        update.title = "second".to_string();
+        update.version = 2;
+        assert_eq!(dao.update_recipe_at_version(recipe_id.clone(), update, None).await.is_ok(), true);
+
+        let first = dao.get_recipe_version(recipe_id.clone(), 1).await.unwrap();
+        assert_eq!(first._id, recipe_id);
+        assert_eq!(first.title, "first");
+        assert_eq!(first.image_base64, None);
+        assert_eq!(dao.get_recipe_version(recipe_id.clone(), 2).await.unwrap().title, "second");
+
+        let result = dao.get_recipe_version(recipe_id.clone(), 3).await;
+        assert_eq!(result.err().unwrap(), DaoError::DocumentNotFound);
+
+        let touched = dao.touch_recipe(recipe_id.clone()).await.unwrap();
+        assert_eq!(dao.get_recipe_version(recipe_id.clone(), touched.version).await.unwrap().title, "second");
+
+        let other_id = dao.insert_recipe(create_one_recipe_without_image()).await.unwrap().as_object_id().unwrap().to_owned();
+        dao.delete_one_recipe(recipe_id.clone(), false).await.unwrap();
+        assert_eq!(dao.get_recipe_version(recipe_id, 1).await.err().unwrap(), DaoError::DocumentNotFound);
+        dao.delete_many_recipes(vec![other_id.clone()]).await.unwrap();
+        assert_eq!(dao.get_recipe_version(other_id, 1).await.err().unwrap(), DaoError::DocumentNotFound);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn ingredient_count_test() {
+        let dao = before().await;
+        let stored_count = |id: ObjectId| {
+            let collection = dao.database.collection(RECIPE_COLLECTION);
+            async move { collection.find_one(doc! {"_id": id}, None).await.unwrap().unwrap().get_i32("ingredientCount").ok() }
+        };
+        let mut recipe = create_one_recipe_without_image();
+        recipe.ingredients = vec![Ingredient::new("0", 1.0, "Egg", MeasurementUnit::Piece)];
+        let recipe_id = dao.insert_recipe(recipe).await.unwrap().as_object_id().unwrap().to_owned();
+        assert_eq!(stored_count(recipe_id.clone()).await, Some(1));
+
+        let ingredients = vec![Ingredient::new("0", 1.0, "Egg", MeasurementUnit::Piece), Ingredient::new("1", 0.2, "Milk", MeasurementUnit::Liter)];
+        dao.update_recipe_ingredients(recipe_id.clone(), ingredients, None).await.unwrap();
+        assert_eq!(stored_count(recipe_id.clone()).await, Some(2));
+
+        let mut update = dao.get_one_recipe_without_image(recipe_id.clone()).await.unwrap();
+        update.ingredients.pop();
+        dao.update_recipe_at_version(recipe_id.clone(), update, None).await.unwrap();
+        assert_eq!(stored_count(recipe_id.clone()).await, Some(1));
+
+        dao.database.collection(RECIPE_COLLECTION)
+            .update_one(doc! {"_id": recipe_id.clone()}, doc! {"$unset": {"ingredientCount": ""}}, None).await.unwrap();
+        assert_eq!(stored_count(recipe_id.clone()).await, None);
+        assert_eq!(dao.backfill_ingredient_counts().await.unwrap(), 1);
+        assert_eq!(stored_count(recipe_id).await, Some(1));
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn coalesced_recipe_reads_test() {
+        let mut dao = before().await;
+        dao.read_coalescing = ReadCoalescing::new(true);
+        let recipe_id = dao.insert_recipe(create_one_recipe_without_image()).await.unwrap().as_object_id().unwrap().to_owned();
+
+        let reads = join_all((0..20).map(|_| dao.get_one_recipe_without_image(recipe_id.clone()))).await;
+        assert_eq!(reads.iter().all(|read| read.as_ref().unwrap()._id == recipe_id), true);
+        let result = dao.get_one_recipe_without_image(ObjectId::new()).await;
+        assert_eq!(result.err().unwrap(), DaoError::DocumentNotFound);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn concurrent_stale_updates_merge_test() {
+        let dao = before().await;
+        let base = create_one_recipe_without_image();
+        let recipe_id = dao.insert_recipe(base.clone()).await.unwrap().as_object_id().unwrap().to_owned();
+        let mut current = base.clone();
+        current.title = "Changed first".to_string();
+        current.version = base.version + 1;
+        dao.update_recipe_merging(recipe_id.clone(), current, None).await.unwrap();
+
+        let mut described = base.clone();
+        described.description = "Creamy".to_string();
+        let mut tagged = base.clone();
+        tagged.tags = vec!["pasta".to_string()];
+        let updates = join_all(vec![
+            dao.update_recipe_merging(recipe_id.clone(), described, None),
+            dao.update_recipe_merging(recipe_id.clone(), tagged, None),
+        ]).await;
+        assert_eq!(updates, vec![Ok(()), Ok(())]);
+
+        let stored = dao.get_one_recipe_without_image(recipe_id).await.unwrap();
+        assert_eq!(stored.title, "Changed first");
+        assert_eq!(stored.description, "Creamy");
+        assert_eq!(stored.tags, vec!["pasta".to_string()]);
+        assert_eq!(stored.version, base.version + 3);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn step_timers_follow_their_instructions_test() {
+        let dao = before().await;
+        let mut base = create_one_recipe_without_image();
+        base.instructions = vec!["Boil water".to_string(), "Cook pasta".to_string()];
+        base.step_timers = vec![StepTimer::new(1, 540, Some("Pasta"))];
+        let recipe_id = dao.insert_recipe(base.clone()).await.unwrap().as_object_id().unwrap().to_owned();
+
+        let mut update = dao.get_one_recipe_without_image(recipe_id.clone()).await.unwrap();
+        update.instructions.insert(0, "Salt the water".to_string());
+        dao.update_recipe_merging(recipe_id.clone(), update, None).await.unwrap();
+        let stored = dao.get_one_recipe_without_image(recipe_id.clone()).await.unwrap();
+        assert_eq!(stored.step_timers, vec![StepTimer::new(2, 540, Some("Pasta"))]);
+
+        let mut update = stored.clone();
+        update.instructions.pop();
+        dao.update_recipe_merging(recipe_id.clone(), update, None).await.unwrap();
+        let stored = dao.get_one_recipe_without_image(recipe_id).await.unwrap();
+        assert_eq!(stored.step_timers, vec![]);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn update_ingredients_at_stale_version_test() {
+        let dao = before().await;
+        let base = create_one_recipe_without_image();
+        let recipe_id = dao.insert_recipe(base.clone()).await.unwrap().as_object_id().unwrap().to_owned();
+        let eggs = vec![Ingredient::new("0", 2.0, "Eggs", MeasurementUnit::Piece)];
 
-async fn get_db_handler() -> Result<Database, Error> {
-    let mut client_options = ClientOptions::parse(URL).await?;
-    client_options.app_name = Some(APP_NAME.to_string());
-    let client = Client::with_options(client_options)?;
-    return Ok(client.database(DATABASE));
-}
+        dao.update_recipe_ingredients(recipe_id.clone(), eggs.clone(), Some(base.version)).await.unwrap();
+        let stored = dao.get_one_recipe_without_image(recipe_id.clone()).await.unwrap();
+        assert_eq!(stored.ingredients, eggs);
+        assert_eq!(stored.version, base.version + 1);
 
+        let milk = vec![Ingredient::new("1", 1.0, "Milk", MeasurementUnit::Liter)];
+        let stale = dao.update_recipe_ingredients(recipe_id.clone(), milk, Some(base.version)).await;
+        assert_eq!(stale, Err(DaoError::VersionConflict { version: base.version, fields: vec!["ingredients".to_string()] }));
+        assert_eq!(dao.get_one_recipe_without_image(recipe_id).await.unwrap().ingredients, eggs);
 
-pub async fn get_many_recipes(db: &Database, pagination: Option<Pagination>) -> Result<Vec<Recipe>, DaoError> {
-    let mut find_options = FindOptions::default();
-    let mut skip = 0;
-    let mut take = usize::MAX;
-    if pagination.is_some() {
-        skip = (pagination.unwrap().page.unwrap() - 1) * pagination.unwrap().items.unwrap();
-        take = pagination.unwrap().items.unwrap();
-        find_options.sort = Some(doc! { "created": Bson::Int32(pagination.unwrap().sorting.unwrap() as i32) });
-        find_options.projection = Some(Recipe::default_projection_no_image());
+        cleanup_after(dao).await;
     }
 
-    match db.collection(RECIPE_COLLECTION).find(None, find_options).await {
-        Ok(cursor) => {
-            let recipes = cursor
-                .skip(skip)
-                .take(take)
-                .collect::<Vec<Result<Document, Error>>>()
-                .await
-                .into_iter()
-                .collect::<Result<Vec<Document>, Error>>()
-                .map_err(|err| {
-                    DaoError::DatabaseError(format!("{:#?}", err))
-                })?;
+    #[actix_rt::test]
+    #[serial]
+    async fn get_first_recipe_ids_test() {
+        let dao = before().await;
+        dao.add_many_recipes(create_many_recipes_without_images(3)).await.unwrap();
 
-            let recipes = recipes
-                .into_iter()
-                .map(|recipe| Recipe::try_from(recipe))
-                .collect::<Result<Vec<Recipe>, RecipeFormatError>>()
-                .map_err(|err| {
-                    DaoError::DatabaseError(format!("{:#?}", err))
-                })?;
+        assert_eq!(dao.get_first_recipe_ids(doc! {}, Some(2)).await.unwrap().len(), 2);
+        assert_eq!(dao.get_first_recipe_ids(doc! {}, None).await.unwrap().len(), 3);
+        assert_eq!(dao.get_recipe_ids(doc! {}).await.unwrap().len(), 3);
 
-            Ok(recipes)
-        }
-        Err(err) => Err(DaoError::DatabaseError(format!("{:#?}", err)))
+        cleanup_after(dao).await;
     }
-}
 
+    #[actix_rt::test]
+    #[serial]
+    async fn insert_recipe_into_missing_collection_test() {
+        let dao = before().await;
 
-#[cfg(test)]
-pub mod dao_tests {
-    use bson::Bson;
-    use bson::oid::ObjectId;
-    use chrono::{Duration, Timelike};
-    use chrono::Utc;
-    use log::LevelFilter;
-    use mongodb::{Client, Database};
-    use mongodb::error::Error;
-    use mongodb::options::ClientOptions;
-    use serial_test::serial;
-    use simplelog::{Config, TerminalMode, TermLogger};
+        let inserted = dao.insert_recipe_into_collection(create_one_recipe_without_image(), ObjectId::new()).await;
+        assert_eq!(inserted, Err(DaoError::DocumentNotFound));
+        assert_eq!(dao.count_recipes(doc! {}).await.unwrap(), 0);
+        assert_eq!(dao.database.collection("recipe_versions").count_documents(doc! {}, None).await.unwrap(), 0);
 
-    use crate::dao::{Dao, DaoError};
-    use crate::model::difficulty::Difficulty;
-    use crate::model::recipe::Recipe;
-    use crate::pagination::Pagination;
+        cleanup_after(dao).await;
+    }
 
-    const TEST_URL: &str = "mongodb://localhost:26666";
-    const TEST_APP_NAME: &str = "Zellinotes development recipes";
-    const TEST_DATABASE: &str = "test_zellinotes_development_recipes";
+    #[actix_rt::test]
+    #[serial]
+    async fn delete_recipes_except_test() {
+        let dao = before().await;
+        let kept = dao.insert_recipe(create_one_recipe_without_image()).await.unwrap().as_object_id().unwrap().to_owned();
+        dao.insert_recipe(create_one_recipe_without_image()).await.unwrap();
+        let started = ObjectId::new();
+        let later = dao.insert_recipe(create_one_recipe_without_image()).await.unwrap().as_object_id().unwrap().to_owned();
 
-    pub fn create_one_recipe_without_image() -> Recipe {
-        Recipe {
-            _id: ObjectId::new(),
-            cooking_time_in_minutes: 10,
-            created: Utc::now().with_nanosecond(0).unwrap(),
-            last_modified: Utc::now().with_nanosecond(0).unwrap(),
-            ingredients: vec![],
-            version: 1,
-            difficulty: Difficulty::Easy,
-            description: "".to_string(),
-            title: "".to_string(),
-            tags: vec![],
-            image_base64: None,
-            instructions: vec![],
-            default_servings: 1,
-        }
-    }
+        assert_eq!(dao.delete_recipes_except(vec![kept.clone()], started).await, Ok(1));
+        let remaining = dao.get_recipe_ids(doc! {}).await.unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert_eq!(remaining.contains(&kept), true);
+        assert_eq!(remaining.contains(&later), true);
 
-    pub fn create_one_recipe_with_image() -> Recipe {
-        let mut recipe = create_one_recipe_without_image();
-        recipe.image_base64 = Some("image".to_string());
-        recipe
+        cleanup_after(dao).await;
     }
 
-    pub fn create_many_recipes_without_images(amount: i32) -> Vec<Recipe> {
-        (0..amount).into_iter().map(|i| {
-            let mut x = create_one_recipe_without_image();
-            x.title = i.to_string();
-            x.created = Utc::now().with_nanosecond(0).unwrap() + Duration::days(1);
-            x
-        }).collect()
-    }
+    #[actix_rt::test]
+    #[serial]
+    async fn update_at_stale_version_test() {
+        let dao = before().await;
+        let base = create_one_recipe_without_image();
+        let recipe_id = dao.insert_recipe(base.clone()).await.unwrap().as_object_id().unwrap().to_owned();
+        let mut update = base.clone();
+        update.title = "Scaled".to_string();
+        update.version = base.version + 1;
 
-    async fn init_test_database() -> Result<Database, Error> {
-        let mut client_options = ClientOptions::parse(TEST_URL).await?;
-        client_options.app_name = Some(TEST_APP_NAME.to_string());
-        let client = Client::with_options(client_options)?;
-        let db = client.database(TEST_DATABASE);
-        return Ok(db);
-    }
+        let stale = dao.update_recipe_at_version(recipe_id.clone(), update.clone(), Some(base.version + 1)).await;
+        assert_eq!(stale, Err(DaoError::VersionConflict { version: base.version + 1, fields: vec![] }));
+        assert_eq!(dao.get_one_recipe_without_image(recipe_id.clone()).await.unwrap().title, base.title);
+
+        dao.update_recipe_at_version(recipe_id.clone(), update, Some(base.version)).await.unwrap();
+        assert_eq!(dao.get_one_recipe_without_image(recipe_id).await.unwrap().title, "Scaled");
 
-    pub async fn before() -> Dao {
-        init_test_logger();
-        let dao = Dao { database: init_test_database().await.unwrap() };
         cleanup_after(dao).await;
-        Dao { database: init_test_database().await.unwrap() }
     }
 
-    fn init_test_logger() {
-        let _ = TermLogger::init(LevelFilter::Info,
-                                 Config::default(),
-                                 TerminalMode::Mixed).unwrap_or_else(|_| ());
-    }
+    #[actix_rt::test]
+    #[serial]
+    async fn coalesced_reads_during_update_test() {
+        let mut dao = before().await;
+        dao.read_coalescing = ReadCoalescing::new(true);
+        dao.recipe_cache = RecipeCache::new(std::time::Duration::from_secs(60), 10);
+        let recipe_id = dao.insert_recipe(create_one_recipe_without_image()).await.unwrap().as_object_id().unwrap().to_owned();
+
+        let mut started = Box::pin(dao.get_one_recipe_without_image(recipe_id.clone()));
+        assert_eq!(poll!(&mut started).is_pending(), true);
+        let mut update = create_one_recipe_without_image();
+        update.title = "Updated while read".to_string();
+        dao.update_recipe_at_version(recipe_id.clone(), update, None).await.unwrap();
+        let joined = dao.get_one_recipe_without_image(recipe_id.clone());
+        let (started, joined) = futures_util::future::join(started, joined).await;
+        started.unwrap();
+        joined.unwrap();
+
+        assert_eq!(dao.get_one_recipe_without_image(recipe_id).await.unwrap().title, "Updated while read");
 
-    pub async fn cleanup_after(dao: Dao) {
-        dao.database.drop(None).await.unwrap();
+        cleanup_after(dao).await;
     }
 
     #[actix_rt::test]
     #[serial]
-    async fn add_one_recipe_without_image_test() {
-        let dao = before().await;
-        let recipe = create_one_recipe_without_image();
+    async fn cached_recipe_reads_test() {
+        let mut dao = before().await;
+        dao.recipe_cache = RecipeCache::new(std::time::Duration::from_secs(60), 10);
+        let recipe_id = dao.insert_recipe(create_one_recipe_without_image()).await.unwrap().as_object_id().unwrap().to_owned();
 
-        let result = dao.insert_recipe(recipe).await;
-        assert!(result.is_ok());
-        result.unwrap().as_object_id().unwrap().timestamp().date();
+        let stored = dao.get_one_recipe_without_image(recipe_id.clone()).await.unwrap();
+        assert_eq!(dao.get_one_recipe_without_image(recipe_id.clone()).await.unwrap(), stored);
+        assert_eq!((dao.recipe_cache.stats().hits, dao.recipe_cache.stats().misses), (1, 1));
+
+        let mut update = stored.clone();
+        update.title = "Cached no more".to_string();
+        dao.update_recipe_at_version(recipe_id.clone(), update, None).await.unwrap();
+        assert_eq!(dao.get_one_recipe_without_image(recipe_id.clone()).await.unwrap().title, "Cached no more");
+        assert_eq!((dao.recipe_cache.stats().hits, dao.recipe_cache.stats().misses), (1, 2));
+
+        dao.delete_one_recipe(recipe_id.clone(), true).await.unwrap();
+        assert_eq!(dao.get_one_recipe_without_image(recipe_id).await.err().unwrap(), DaoError::DocumentNotFound);
 
         cleanup_after(dao).await;
     }
 
     #[actix_rt::test]
     #[serial]
-    async fn add_one_recipe_with_image_test() {
-        let dao = before().await;
-        let recipe = create_one_recipe_with_image();
-
-        let result = dao.insert_recipe(recipe).await;
-        assert!(result.is_ok());
-        result.unwrap().as_object_id().unwrap().timestamp().date();
+    async fn field_modified_of_touched_fields_test() {
+        let mut dao = before().await;
+        dao.field_modified = FieldModifiedTracking { enabled: true };
+        let recipe = create_one_recipe_without_image();
+        let recipe_id = dao.insert_recipe(recipe.clone()).await.unwrap().as_object_id().unwrap().to_owned();
+        assert_eq!(dao.get_one_recipe_without_image(recipe_id.clone()).await.unwrap().field_modified.is_empty(), true);
+
+        let ingredients = vec![Ingredient::new("0", 1.0, "Egg", MeasurementUnit::Piece)];
+        dao.update_recipe_ingredients(recipe_id.clone(), ingredients.clone(), None).await.unwrap();
+        let stored = dao.get_one_recipe_without_image(recipe_id.clone()).await.unwrap();
+        assert_eq!(stored.field_modified.keys().collect::<Vec<&String>>(), vec!["ingredients"]);
+        let ingredients_modified = stored.field_modified["ingredients"];
+
+        let mut update = stored.clone();
+        update.title = "Omelette".to_string();
+        update.tags = vec!["breakfast".to_string()];
+        dao.update_recipe_at_version(recipe_id.clone(), update, None).await.unwrap();
+        let stored = dao.get_one_recipe_without_image(recipe_id.clone()).await.unwrap();
+        assert_eq!(stored.field_modified.keys().collect::<Vec<&String>>(), vec!["ingredients", "tags", "title"]);
+        assert_eq!(stored.field_modified["ingredients"], ingredients_modified);
+        assert_eq!(stored.field_modified["title"] >= ingredients_modified, true);
+
+        dao.set_recipe_status(recipe_id.clone(), RecipeStatus::Draft).await.unwrap();
+        dao.set_recipe_archived(recipe_id.clone(), true).await.unwrap();
+        dao.update_one_recipe_image(recipe_id.clone(), Some("image".to_string()), None, None).await.unwrap();
+        let stored = dao.get_one_recipe_without_image(recipe_id.clone()).await.unwrap();
+        assert_eq!(stored.field_modified.keys().collect::<Vec<&String>>(), vec!["archived", "image", "ingredients", "status", "tags", "title"]);
+        assert_eq!(stored.field_modified["image"] >= stored.field_modified["title"], true);
+
+        dao.field_modified = FieldModifiedTracking::default();
+        let mut update = stored.clone();
+        update.description = "untracked".to_string();
+        dao.update_recipe_at_version(recipe_id.clone(), update, None).await.unwrap();
+        let stored = dao.get_one_recipe_without_image(recipe_id).await.unwrap();
+        assert_eq!(stored.field_modified.contains_key("description"), false);
+        assert_eq!(stored.field_modified.len(), 6);
 
         cleanup_after(dao).await;
     }
 
     #[actix_rt::test]
     #[serial]
-    async fn update_one_recipe_ignore_image_test() {
+    async fn set_recipe_archived_test() {
         let dao = before().await;
-        let mut recipe = create_one_recipe_with_image();
+        let recipe = create_one_recipe_without_image();
 
         let result = dao.insert_recipe(recipe.clone()).await.unwrap();
         let recipe_id = result.as_object_id().unwrap().to_owned();
-        recipe.title = "new".to_string();
-        recipe.image_base64 = Some("new_image".to_string());
+        let not_archived = doc! { "archived": { "$ne": true } };
 
-        let result = dao.update_recipe_ignore_image(recipe_id.clone(), recipe.clone()).await;
-        assert!(result.is_ok());
+        assert_eq!(dao.set_recipe_archived(recipe_id.clone(), true).await.is_ok(), true);
+        assert_eq!(dao.get_one_recipe_without_image(recipe_id.clone()).await.unwrap().archived, true);
+        assert_eq!(dao.count_recipes(not_archived.clone()).await.unwrap(), 0);
 
-        let result = dao.get_one_recipe_without_image(recipe_id.clone()).await;
-        assert_eq!(result.clone().unwrap().title, "new".to_string());
-        assert_eq!(result.unwrap().image_base64, None);
+        let result = dao.update_recipe_at_version(recipe_id.clone(), create_one_recipe_without_image(), None).await;
+        assert_eq!(result.is_ok(), true);
+        assert_eq!(dao.get_one_recipe_without_image(recipe_id.clone()).await.unwrap().archived, true);
 
-        let result = dao.get_one_recipe_image(recipe_id).await;
-        assert_eq!(result.unwrap().as_str(), "image");
+        assert_eq!(dao.set_recipe_archived(recipe_id.clone(), false).await.is_ok(), true);
+        assert_eq!(dao.set_recipe_archived(recipe_id.clone(), false).await.is_ok(), true);
+        assert_eq!(dao.count_recipes(not_archived).await.unwrap(), 1);
 
-        let result = dao.update_recipe_ignore_image(ObjectId::new(), recipe.clone()).await;
+        let result = dao.set_recipe_archived(ObjectId::new(), true).await;
         assert_eq!(result.err().unwrap(), DaoError::DocumentNotFound);
 
         cleanup_after(dao).await;
@@ -447,18 +2920,24 @@ pub mod dao_tests {
 
         let result = dao.insert_recipe(recipe.clone()).await.unwrap();
         let recipe_id = result.as_object_id().unwrap().to_owned();
-        let result = dao.update_one_recipe_image(recipe_id.clone(), Some("new_image".to_string())).await;
+        let result = dao.update_one_recipe_image(recipe_id.clone(), Some("new_image".to_string()), None, Some("image/png".to_string())).await;
         assert!(result.is_ok());
 
         let result = dao.get_one_recipe_image(recipe_id.clone()).await;
-        assert_eq!(result.unwrap(), "new_image".to_string());
+        assert_eq!(result.unwrap(), ("new_image".to_string(), Some("image/png".to_string())));
 
-        let result = dao.update_one_recipe_image(recipe_id.clone(),None).await;
+        let result = dao.update_one_recipe_image(recipe_id.clone(), None, None, None).await;
         assert!(result.is_ok());
 
         let result = dao.get_one_recipe_image(recipe_id.clone()).await;
         assert_eq!(result.err().unwrap(), DaoError::DocumentNotFound);
 
+        let result = dao.update_one_recipe_image(recipe_id.clone(), None, None, None).await;
+        assert_eq!(result.is_ok(), true);
+
+        let result = dao.update_one_recipe_image(ObjectId::new(), None, None, None).await;
+        assert_eq!(result.err().unwrap(), DaoError::DocumentNotFound);
+
         cleanup_after(dao).await;
     }
 
@@ -471,9 +2950,9 @@ pub mod dao_tests {
         let result = dao.insert_recipe(recipe.clone()).await.unwrap();
         let recipe_id = result.as_object_id().unwrap().to_owned();
         let result = dao.get_one_recipe_image(recipe_id.clone()).await;
-        assert_eq!(result.unwrap(), "image".to_string());
+        assert_eq!(result.unwrap().0, "image".to_string());
 
-        let result = dao.delete_one_recipe(recipe_id.clone()).await;
+        let result = dao.delete_one_recipe(recipe_id.clone(), false).await;
         assert!(result.is_ok());
 
         let result = dao.get_one_recipe_image(recipe_id.clone()).await;
@@ -482,6 +2961,165 @@ pub mod dao_tests {
         cleanup_after(dao).await;
     }
 
+    #[test]
+    fn ids_in_input_order_test() {
+        let ids = (0..20).map(|_| Bson::ObjectId(ObjectId::new())).collect::<Vec<Bson>>();
+        let inserted_ids = ids.iter().cloned().enumerate().collect::<HashMap<usize, Bson>>();
+        assert_eq!(ids_in_input_order(inserted_ids), ids);
+    }
+
+    #[test]
+    fn parse_duplicate_key_message_test() {
+        let (field, value) = parse_duplicate_key_message(
+            "E11000 duplicate key error collection: db.recipes index: title_1 dup key: { title: \"Pasta\" }");
+        assert_eq!(field, "title");
+        assert_eq!(value, "Pasta");
+
+        let (field, value) = parse_duplicate_key_message(
+            "E11000 duplicate key error collection: db.recipes index: _id_ dup key: { _id: ObjectId('5f7333360051027600b01a36') }");
+        assert_eq!(field, "_id");
+        assert_eq!(value, "ObjectId('5f7333360051027600b01a36')");
+
+        let (field, value) = parse_duplicate_key_message(
+            "E11000 duplicate key error index: db.recipes.$slug_1 dup key: { : \"pasta\" }");
+        assert_eq!(field, "slug");
+        assert_eq!(value, "pasta");
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn add_duplicate_recipe_test() {
+        let dao = before().await;
+        dao.ensure_title_index(TitleConstraint { unique: Some(true), per_author: None }).await.unwrap();
+
+        let recipe = create_one_recipe_without_image();
+        assert!(dao.insert_recipe(recipe.clone()).await.is_ok());
+
+        let result = dao.insert_recipe(recipe).await;
+        assert_eq!(result.err().unwrap(), DaoError::DuplicateKey { field: "title".to_string(), value: "".to_string() });
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn title_constraint_toggle_test() {
+        let dao = before().await;
+        assert!(dao.ensure_title_index(TitleConstraint { unique: Some(false), per_author: None }).await.is_ok());
+
+        dao.ensure_title_index(TitleConstraint { unique: Some(true), per_author: None }).await.unwrap();
+        let recipe = create_one_recipe_without_image();
+        assert!(dao.insert_recipe(recipe.clone()).await.is_ok());
+        assert_eq!(dao.insert_recipe(recipe.clone()).await.is_err(), true);
+
+        dao.ensure_title_index(TitleConstraint::default()).await.unwrap();
+        assert_eq!(dao.insert_recipe(recipe.clone()).await.is_err(), true);
+
+        dao.ensure_title_index(TitleConstraint { unique: Some(false), per_author: None }).await.unwrap();
+        assert!(dao.insert_recipe(recipe.clone()).await.is_ok());
+        assert_eq!(dao.ensure_title_index(TitleConstraint { unique: Some(true), per_author: None }).await.is_err(), true);
+
+        cleanup_after(dao).await;
+    }
+
+    #[test]
+    fn duplicate_folded_title_test() {
+        assert_eq!(duplicate_folded_title(
+            "E11000 duplicate key error collection: db.recipes index: author_1_foldedTitle_1 dup key: { author: \"ada\", foldedTitle: \"pasta\" }"),
+                   "pasta");
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn unique_title_per_author_test() {
+        let dao = before().await;
+        let mut stored_before = create_one_recipe_without_image();
+        stored_before.title = "Soup".to_string();
+        stored_before.author = Some("ada".to_string());
+        dao.insert_recipe(stored_before.clone()).await.unwrap();
+        dao.database.collection(RECIPE_COLLECTION).update_many(doc! {}, doc! { "$unset": { JSON_ATTR_FOLDED_TITLE: "" } }, None).await.unwrap();
+        dao.ensure_title_index(TitleConstraint { unique: None, per_author: Some(true) }).await.unwrap();
+
+        let mut recipe = create_one_recipe_without_image();
+        recipe.title = "Pasta".to_string();
+        recipe.author = Some("ada".to_string());
+        assert!(dao.insert_recipe(recipe.clone()).await.is_ok());
+
+        let mut same_title = recipe.clone();
+        same_title.title = " pasta ".to_string();
+        assert_eq!(dao.insert_recipe(same_title.clone()).await.err().unwrap(), DaoError::DuplicateTitle { title: "pasta".to_string() });
+        stored_before.title = "SOUP".to_string();
+        assert_eq!(dao.insert_recipe(stored_before).await.err().unwrap(), DaoError::DuplicateTitle { title: "soup".to_string() });
+
+        same_title.author = Some("grace".to_string());
+        assert!(dao.insert_recipe(same_title.clone()).await.is_ok());
+        same_title.author = None;
+        assert!(dao.insert_recipe(same_title.clone()).await.is_ok());
+        assert!(dao.insert_recipe(same_title).await.is_ok());
+
+        dao.ensure_title_index(TitleConstraint::default()).await.unwrap();
+        assert_eq!(dao.insert_recipe(recipe.clone()).await.is_err(), true);
+        dao.ensure_title_index(TitleConstraint { unique: None, per_author: Some(false) }).await.unwrap();
+        assert!(dao.insert_recipe(recipe).await.is_ok());
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn listing_in_default_order_uses_created_index_test() {
+        let dao = before().await;
+        dao.add_many_recipes(create_many_recipes_without_images(3)).await.unwrap();
+
+        dao.ensure_created_index().await.unwrap();
+        dao.ensure_created_index().await.unwrap();
+        let explanation = dao.explain_recipes(Document::new()).await.unwrap();
+        assert_eq!(explanation.collection_scan, false);
+        assert_eq!(explanation.indexes, vec!["created_1__id_1".to_string()]);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn insert_recipe_generates_unique_slug_test() {
+        let dao = before().await;
+        dao.ensure_slug_index().await.unwrap();
+
+        let mut recipe = create_one_recipe_without_image();
+        recipe.title = "Spaghetti Carbonara".to_string();
+        let first_id = dao.insert_recipe(recipe.clone()).await.unwrap().as_object_id().unwrap().to_owned();
+        recipe.title = "Spaghetti carbonara!".to_string();
+        let second_id = dao.insert_recipe(recipe.clone()).await.unwrap().as_object_id().unwrap().to_owned();
+
+        let mut recipes = create_many_recipes_without_images(2);
+        recipes.iter_mut().for_each(|recipe| recipe.title = "spaghetti-carbonara".to_string());
+        dao.add_many_recipes(recipes).await.unwrap();
+
+        let first = dao.get_one_recipe_by_slug("spaghetti-carbonara").await.unwrap();
+        assert_eq!(first._id, first_id);
+        let second = dao.get_one_recipe_by_slug("spaghetti-carbonara-2").await.unwrap();
+        assert_eq!(second._id, second_id);
+        assert_eq!(dao.get_one_recipe_by_slug("spaghetti-carbonara-3").await.is_ok(), true);
+        assert_eq!(dao.get_one_recipe_by_slug("spaghetti-carbonara-4").await.is_ok(), true);
+
+        let result = dao.update_recipe_at_version(first_id.clone(), create_one_recipe_without_image(), None).await;
+        assert_eq!(result.is_ok(), true);
+        let first = dao.get_one_recipe_without_image(first_id).await.unwrap();
+        assert_eq!(first.slug, Some("spaghetti-carbonara".to_string()));
+
+        let result = dao.get_one_recipe_by_slug("unknown").await;
+        assert_eq!(result.err().unwrap(), DaoError::DocumentNotFound);
+
+        cleanup_after(dao).await;
+    }
+
+    #[test]
+    fn taken_slugs_filter_test() {
+        let bases = vec!["pasta".to_string(), "pizza".to_string(), "pasta".to_string()];
+        assert_eq!(taken_slugs_filter(&bases), doc! { "slug": { "$regex": "^(pasta|pizza)(-[0-9]+)?$" } });
+    }
+
     #[actix_rt::test]
     #[serial]
     async fn add_many_recipes_test() {
@@ -489,12 +3127,17 @@ pub mod dao_tests {
         let recipes = create_many_recipes_without_images(50);
         let inserted_ids: Vec<ObjectId> = recipes.clone().into_iter().map(|recipe| recipe._id).collect();
 
-        let result = dao.add_many_recipes(recipes).await;
+        let result = dao.add_many_recipes(recipes.clone()).await;
         assert!(result.is_ok());
 
         let added_recipes: Vec<Bson> = result.unwrap().as_array().unwrap().to_owned();
         let added_recipes: Vec<ObjectId> = added_recipes.into_iter().map(|e| e.as_object_id().unwrap().to_owned()).collect();
-        assert_eq!(inserted_ids.len(), added_recipes.len()); //todo compare correct
+        assert_eq!(inserted_ids.len(), added_recipes.len());
+
+        for (recipe, id) in recipes.iter().zip(added_recipes) {
+            let added_recipe = dao.get_one_recipe_without_image(id).await.unwrap();
+            assert_eq!(added_recipe.title, recipe.title);
+        }
 
         cleanup_after(dao).await;
     }
@@ -522,6 +3165,74 @@ pub mod dao_tests {
         cleanup_after(dao).await;
     }
 
+    #[actix_rt::test]
+    #[serial]
+    async fn get_one_recipe_full_test() {
+        let dao = before().await;
+        let recipe = create_one_recipe_with_image();
+        let result = dao.insert_recipe(recipe).await.unwrap();
+        let inserted_oid = result.as_object_id().unwrap().to_owned();
+
+        dao.database.collection("collections").insert_one(
+            doc! {"name": "Favourites", "recipeIds": [inserted_oid.clone()]}, None).await.unwrap();
+        dao.database.collection("ratings").insert_many(
+            vec![doc! {"recipeId": inserted_oid.clone(), "value": 4},
+                 doc! {"recipeId": inserted_oid.clone(), "value": 5}], None).await.unwrap();
+        dao.database.collection("comments").insert_one(
+            doc! {"recipeId": inserted_oid.clone(), "text": "tasty"}, None).await.unwrap();
+
+        let full_recipe = dao.get_one_recipe_full(inserted_oid.clone()).await.unwrap();
+        assert_eq!(full_recipe.recipe._id, inserted_oid);
+        assert_eq!(full_recipe.recipe.image_base64, None);
+        assert_eq!(full_recipe.collections.len(), 1);
+        assert_eq!(full_recipe.collections[0].name, "Favourites");
+        assert_eq!(full_recipe.average_rating, Some(4.5));
+        assert_eq!(full_recipe.comment_count, 1);
+
+        let doc_with_wrong_id_not_found = dao.get_one_recipe_full(ObjectId::new()).await;
+        assert_eq!(doc_with_wrong_id_not_found.err().unwrap(), DaoError::DocumentNotFound);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn get_similar_recipes_test() {
+        let dao = before().await;
+        let mut source = create_one_recipe_without_image();
+        source.ingredients = vec![
+            Ingredient::new("0", 1.0, "Milk", MeasurementUnit::Liter),
+            Ingredient::new("1", 2.0, "Eggs", MeasurementUnit::Piece),
+            Ingredient::new("2", 500.0, "Flour", MeasurementUnit::Gramm)];
+        let mut one_shared = create_one_recipe_without_image();
+        one_shared.title = "one".to_string();
+        one_shared.ingredients = vec![Ingredient::new("0", 1.0, " milk ", MeasurementUnit::Liter)];
+        let mut two_shared = create_one_recipe_without_image();
+        two_shared.title = "two".to_string();
+        two_shared.ingredients = vec![
+            Ingredient::new("0", 3.0, "EGGS", MeasurementUnit::Piece),
+            Ingredient::new("1", 200.0, "Flour", MeasurementUnit::Gramm)];
+        let mut nothing_shared = create_one_recipe_without_image();
+        nothing_shared.ingredients = vec![Ingredient::new("0", 1.0, "Tofu", MeasurementUnit::Pack)];
+
+        let source_id = dao.insert_recipe(source).await.unwrap().as_object_id().unwrap().to_owned();
+        dao.add_many_recipes(vec![one_shared, two_shared, nothing_shared]).await.unwrap();
+
+        let similar = dao.get_similar_recipes(source_id.clone(), 10).await.unwrap();
+        assert_eq!(similar.iter().map(|r| r.recipe.title.as_str()).collect::<Vec<&str>>(), vec!["two", "one"]);
+        assert_eq!(similar.iter().map(|r| r.similarity).collect::<Vec<u32>>(), vec![2, 1]);
+        assert_eq!(similar.iter().any(|r| r.recipe._id == source_id), false);
+
+        dao.set_recipe_archived(similar[0].recipe._id.clone(), true).await.unwrap();
+        let similar = dao.get_similar_recipes(source_id.clone(), 10).await.unwrap();
+        assert_eq!(similar.iter().map(|r| r.recipe.title.as_str()).collect::<Vec<&str>>(), vec!["one"]);
+
+        let similar = dao.get_similar_recipes(ObjectId::new(), 10).await;
+        assert_eq!(similar.err().unwrap(), DaoError::DocumentNotFound);
+
+        cleanup_after(dao).await;
+    }
+
     #[actix_rt::test]
     #[serial]
     async fn get_one_recipe_image() {
@@ -535,7 +3246,7 @@ pub mod dao_tests {
             .await;
 
         assert_eq!(image.is_ok(), true);
-        assert_eq!(image.unwrap(), "image");
+        assert_eq!(image.unwrap().0, "image");
 
         let doc_with_wrong_id_not_found = dao.get_one_recipe_without_image(ObjectId::new()).await;
         assert_eq!(doc_with_wrong_id_not_found.err().unwrap(), DaoError::DocumentNotFound);
@@ -551,15 +3262,39 @@ pub mod dao_tests {
         let result = dao.insert_recipe(recipe.clone()).await.unwrap();
         let recipe_id = result.as_object_id().unwrap().to_owned();
 
-        let result = dao.delete_one_recipe(recipe_id.clone()).await;
+        let result = dao.delete_one_recipe(recipe_id.clone(), false).await;
         assert!(result.is_ok());
 
-        let result = dao.delete_one_recipe(ObjectId::new()).await;
+        let result = dao.delete_one_recipe(ObjectId::new(), false).await;
         assert_eq!(result.err().unwrap(), DaoError::DocumentNotFound);
 
         cleanup_after(dao).await;
     }
 
+    #[actix_rt::test]
+    #[serial]
+    async fn delete_referenced_recipe_test() {
+        let dao = before().await;
+        let recipe_id = dao.insert_recipe(create_one_recipe_without_image()).await.unwrap().as_object_id().unwrap().to_owned();
+        let collection_id = ObjectId::new();
+        dao.database.collection("collections").insert_one(
+            doc! {"_id": collection_id.clone(), "name": "Favourites", "recipeIds": [recipe_id.clone(), ObjectId::new()]},
+            None).await.unwrap();
+
+        let result = dao.delete_one_recipe(recipe_id.clone(), false).await;
+        assert_eq!(result.err().unwrap(), DaoError::RecipeReferenced { collections: vec![collection_id.clone()] });
+        assert_eq!(dao.get_one_recipe_without_image(recipe_id.clone()).await.is_ok(), true);
+
+        assert!(dao.delete_one_recipe(recipe_id.clone(), true).await.is_ok());
+        assert_eq!(dao.get_one_recipe_without_image(recipe_id.clone()).await.err().unwrap(), DaoError::DocumentNotFound);
+        assert_eq!(dao.get_recipe_references(recipe_id).await.unwrap(), Vec::<ObjectId>::new());
+        let collection = dao.database.collection("collections")
+            .find_one(doc! {"_id": collection_id}, None).await.unwrap().unwrap();
+        assert_eq!(collection.get_array("recipeIds").unwrap().len(), 1);
+
+        cleanup_after(dao).await;
+    }
+
     #[actix_rt::test]
     #[serial]
     async fn get_all_recipes() {
@@ -571,7 +3306,7 @@ pub mod dao_tests {
         assert!(result.clone().is_ok());
         assert_eq!(result.clone().unwrap().as_array().unwrap().len(), recipes.clone().len());
 
-        let read_recipes = dao.get_many_recipes(None).await.unwrap();
+        let read_recipes = dao.get_many_recipes(None, Document::new()).await.unwrap();
         assert_eq!(amount_of_recipes, read_recipes.len());
 
         cleanup_after(dao).await;
@@ -623,20 +3358,20 @@ pub mod dao_tests {
             page: Some(page),
             items: Some(items),
             sorting: Some(sorting),
-        })).await.unwrap();
-        let read_recipes: Vec<Recipe> = read_recipes.into_iter().map(|mut r| {
+            ..Pagination::default()
+        }), Document::new()).await.unwrap();
+        let read_recipes: Vec<RecipeSummary> = read_recipes.into_iter().map(|mut r| {
             r._id = ObjectId::with_bytes([0; 12]);
             r
         }).collect();
 
-        recipes_to_insert.sort_by(|l, r| l.created.cmp(&r.created));
+        recipes_to_insert.sort_by_key(|l| l.created);
 
-        let recipes_to_insert: Vec<Recipe> = recipes_to_insert
+        let recipes_to_insert: Vec<RecipeSummary> = recipes_to_insert
             .into_iter()
             .map(|mut recipe| {
                 recipe._id = ObjectId::with_bytes([0; 12]);
-                recipe.image_base64 = None;
-                recipe
+                RecipeSummary::from(recipe)
             })
             .skip((page - 1) * items)
             .take(items)
@@ -645,4 +3380,131 @@ pub mod dao_tests {
         assert_eq!(read_recipes, recipes_to_insert);
         println!("{:#?}", read_recipes);
     }
+
+    #[test]
+    fn text_search_fallback_test() {
+        let filter = doc! { "$text": { "$search": "pasta (v2)" }, "tags": { "$all": ["vegan"] } };
+        assert_eq!(text_search_fallback(&filter).unwrap(), doc! {
+            "tags": { "$all": ["vegan"] },
+            "$or": [
+                { "title": { "$regex": "pasta \\(v2\\)", "$options": "i" } },
+                { "description": { "$regex": "pasta \\(v2\\)", "$options": "i" } }
+            ]
+        });
+        assert_eq!(text_search_fallback(&doc! { "tags": "vegan" }), None);
+    }
+
+    #[test]
+    fn rated_recipes_leave_out_large_fields_test() {
+        assert_eq!(lean_projection(doc! { "title": 1, "image": 1, "thumbnail": 1 }), doc! { "title": 1, "created": 1 });
+        assert_eq!(lean_projection(doc! { "description": 0, "created": 0, "thumbnail": 0 }), doc! { "image": 0, "thumbnail": 0 });
+
+        assert_eq!(large_fields_stages(&doc! { "title": 1 }), Vec::<Document>::new());
+        assert_eq!(large_fields_stages(&doc! { "image": 0, "thumbnail": 0 }), Vec::<Document>::new());
+        let stages = large_fields_stages(&doc! { "title": 1, "thumbnail": 1 });
+        assert_eq!(stages[1], doc! { "$addFields": { "thumbnail": { "$arrayElemAt": ["$stored.thumbnail", 0] } } });
+        let stages = large_fields_stages(&doc! { "thumbnail": 0 });
+        assert_eq!(stages[1], doc! { "$addFields": { "image": { "$arrayElemAt": ["$stored.image", 0] } } });
+    }
+
+    fn command_error(error: Document) -> Error {
+        let command_error: CommandError = bson::from_bson(Bson::Document(error)).unwrap();
+        Error::from(ErrorKind::CommandError(command_error))
+    }
+
+    #[test]
+    fn missing_text_index_error_test() {
+        let error = command_error(doc! { "code": 27, "errmsg": "text index required for $text query" });
+        assert_eq!(is_missing_text_index(&error), true);
+        assert_eq!(DaoError::from(error), DaoError::TextIndexMissing);
+
+        let error = command_error(doc! { "code": 27, "errmsg": "index not found with name [title_1]" });
+        assert_eq!(is_missing_text_index(&error), false);
+    }
+
+    #[test]
+    fn retag_filter_test() {
+        let rule = RetagRule { ingredient_contains: "tofu (smoked)".to_string(), add_tag: "vegetarian".to_string() };
+        assert_eq!(retag_filter(&rule), doc! {
+            "ingredients.title": { "$regex": "tofu \\(smoked\\)", "$options": "i" },
+            "tags": { "$ne": "vegetarian" },
+        });
+    }
+
+    #[test]
+    fn max_time_expired_error_test() {
+        let error = command_error(doc! { "code": 50, "codeName": "MaxTimeMSExpired", "errmsg": "operation exceeded time limit" });
+        assert_eq!(DaoError::from(error), DaoError::TimeBudgetExceeded);
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn aggregation_exceeding_its_budget_is_aborted() {
+        let dao = before().await;
+        dao.add_many_recipes(create_many_recipes_without_images(3)).await.unwrap();
+
+        let slow = doc! { "$where": "sleep(100) || true" };
+        let groups = dao.get_recipe_groups(GroupBy::Tag, slow.clone(), 5, std::time::Duration::from_millis(10)).await;
+        assert_eq!(groups.err(), Some(DaoError::TimeBudgetExceeded));
+        assert_eq!(dao.get_recipe_groups(GroupBy::Tag, slow, 5, std::time::Duration::from_secs(5)).await.is_ok(), true);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn search_without_text_index_falls_back_to_regex() {
+        let dao = before().await;
+        let mut recipes = create_many_recipes_without_images(3);
+        recipes[0].title = "Spaghetti Bolognese".to_string();
+        recipes[1].description = "Better than spaghetti".to_string();
+        dao.add_many_recipes(recipes).await.unwrap();
+
+        let filter = doc! { "$text": { "$search": "SPAGHETTI" } };
+        let result = dao.get_many_recipes(None, filter.clone()).await;
+        assert_eq!(result.unwrap().len(), 2);
+        assert_eq!(dao.count_recipes(filter.clone()).await.unwrap(), 2);
+        assert_eq!(dao.get_difficulty_counts(filter).await.unwrap(), vec![("Easy".to_string(), 2)]);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn search_with_text_index() {
+        let dao = before().await;
+        let mut recipes = create_many_recipes_without_images(3);
+        recipes[0].title = "Spaghetti Bolognese".to_string();
+        recipes[1].description = "Better than spaghetti".to_string();
+        dao.add_many_recipes(recipes).await.unwrap();
+
+        assert_eq!(dao.ensure_text_index().await.is_ok(), true);
+        assert_eq!(dao.ensure_text_index().await.is_ok(), true);
+        let filter = doc! { "$text": { "$search": "SPAGHETTI" } };
+        assert_eq!(dao.get_many_recipes(None, filter.clone()).await.unwrap().len(), 2);
+        assert_eq!(dao.get_difficulty_counts(filter).await.unwrap(), vec![("Easy".to_string(), 2)]);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn get_tag_combos_test() {
+        let dao = before().await;
+        let mut recipes = create_many_recipes_without_images(4);
+        recipes[0].tags = vec!["vegan".to_string(), "fast".to_string()];
+        recipes[1].tags = vec!["fast".to_string(), "vegan".to_string(), "vegan".to_string()];
+        recipes[2].tags = vec!["dessert".to_string()];
+        dao.add_many_recipes(recipes).await.unwrap();
+
+        let combos = dao.get_tag_combos(20).await.unwrap();
+        assert_eq!(combos.len(), 2);
+        assert_eq!(combos[0].tags, vec!["fast".to_string(), "vegan".to_string()]);
+        assert_eq!(combos[0].count, 2);
+        assert_eq!(combos[1].tags, vec!["dessert".to_string()]);
+
+        assert_eq!(dao.get_tag_combos(1).await.unwrap().len(), 1);
+
+        cleanup_after(dao).await;
+    }
 }
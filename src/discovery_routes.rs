@@ -0,0 +1,139 @@
+use actix_web::{HttpResponse, web};
+use serde::Serialize;
+
+use crate::features::{Feature, FeatureFlags};
+
+pub const API_BASE_PATH: &str = "/api/v1";
+
+const FILTER_PARAMS: &[&str] = &["q", "tags", "difficulty", "maxDifficulty", "cuisine", "equipment",
+    "createdAfter", "createdBefore", "modifiedAfter", "modifiedBefore", "includeArchived", "fuzzy", "minIngredients", "maxIngredients", "isQuick"];
+const LIST_PARAMS: &[&str] = &["page", "items", "pageSize", "sorting", "offset", "limit",
+    "q", "tags", "difficulty", "maxDifficulty", "cuisine", "equipment",
+    "createdAfter", "createdBefore", "modifiedAfter", "modifiedBefore", "includeArchived", "fuzzy", "minIngredients", "maxIngredients", "isQuick",
+    "envelope", "fields", "fields[recipe]", "exclude", "explain", "minRating", "includeUnrated"];
+
+/// One method of a path below `/api/v1` with the query parameters it accepts
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct RouteDoc {
+    pub method: &'static str,
+    pub path: &'static str,
+    #[serde(rename = "queryParams")]
+    pub query_params: &'static [&'static str],
+    #[serde(skip)]
+    pub feature: Option<Feature>,
+}
+
+const fn route(method: &'static str, path: &'static str, query_params: &'static [&'static str]) -> RouteDoc {
+    RouteDoc { method, path, query_params, feature: None }
+}
+
+const fn feature_route(feature: Feature, method: &'static str, path: &'static str, query_params: &'static [&'static str]) -> RouteDoc {
+    RouteDoc { method, path, query_params, feature: Some(feature) }
+}
+
+/// The routes registered in `routes::configure`, maintained by hand, a test checks each of them answers
+pub const ROUTES: &[RouteDoc] = &[
+    route("GET", "/", &[]),
+    route("GET", "/recipes", LIST_PARAMS),
+    feature_route(Feature::BulkImport, "POST", "/recipes", &["preserveIds", "onConflict"]),
+    feature_route(Feature::BulkImport, "POST", "/recipes/validateMany", &[]),
+    feature_route(Feature::UrlImport, "POST", "/recipes/importFromUrl", &[]),
+    route("POST", "/recipes/deleteMany", &[]),
+    route("POST", "/recipes/retag/apply", &[]),
+    route("GET", "/recipes/mine", LIST_PARAMS),
+    route("GET", "/recipes/cookableNow", &["maxTime", "have", "page", "items", "sorting"]),
+    route("GET", "/recipes/grouped", &["by", "limit",
+        "q", "tags", "difficulty", "maxDifficulty", "cuisine", "equipment",
+        "createdAfter", "createdBefore", "modifiedAfter", "modifiedBefore", "includeArchived",
+        "minIngredients", "maxIngredients", "isQuick", "maxTimeMs"]),
+    route("GET", "/recipes/stats", &["q", "tags", "difficulty", "maxDifficulty", "cuisine", "equipment",
+        "createdAfter", "createdBefore", "modifiedAfter", "modifiedBefore", "includeArchived",
+        "minIngredients", "maxIngredients", "isQuick"]),
+    route("GET", "/recipes/tagCombos", &["limit"]),
+    route("GET", "/recipes/cuisines", &[]),
+    feature_route(Feature::Export, "GET", "/recipes/export/csv", FILTER_PARAMS),
+    feature_route(Feature::Export, "POST", "/recipes/preview", &["format", "servings", "locale"]),
+    feature_route(Feature::Export, "POST", "/recipes/cookbook", &["servings", "locale"]),
+    route("GET", "/recipes/trending", &["window", "limit", "maxTimeMs"]),
+    route("GET", "/recipes/equipment", &[]),
+    route("GET", "/recipes/by-slug/{slug}", &[]),
+    route("POST", "/recipes/{id}", &["collectionId"]),
+    route("GET", "/recipes/{id}", &["fields", "fields[recipe]", "exclude", "expandTemplates"]),
+    route("PUT", "/recipes/{id}", &[]),
+    route("DELETE", "/recipes/{id}", &["force"]),
+    route("PATCH", "/recipes/{id}/ingredients", &["ingredientMode"]),
+    route("GET", "/recipes/{id}/full", &[]),
+    route("POST", "/recipes/{id}/normalizeServings", &["to", "version"]),
+    route("GET", "/recipes/{id}/cook-mode", &[]),
+    route("GET", "/recipes/{id}/substitutions", &[]),
+    feature_route(Feature::Export, "GET", "/recipes/{id}/print", &["servings", "locale"]),
+    feature_route(Feature::Export, "GET", "/recipes/{id}/export", &["format", "servings", "locale"]),
+    route("POST", "/recipes/{id}/publish", &[]),
+    route("POST", "/recipes/{id}/touch", &[]),
+    route("POST", "/recipes/{id}/archive", &[]),
+    route("POST", "/recipes/{id}/unarchive", &[]),
+    route("GET", "/recipes/{id}/diff", &["from", "to"]),
+    route("GET", "/recipes/{id}/similar", &["limit"]),
+    route("GET", "/recipes/{id}/image", &["size"]),
+    feature_route(Feature::ImageUpload, "PUT", "/recipes/{id}/image", &["compress"]),
+    feature_route(Feature::ImageUpload, "DELETE", "/recipes/{id}/image", &[]),
+    feature_route(Feature::ImageUpload, "PUT", "/recipes/{id}/image/url", &[]),
+    route("GET", "/templates", &[]),
+    route("POST", "/templates", &[]),
+    route("GET", "/templates/{id}", &[]),
+    route("PUT", "/templates/{id}", &[]),
+    route("DELETE", "/templates/{id}", &[]),
+    route("GET", "/units", &[]),
+    route("POST", "/units", &[]),
+    route("GET", "/units/convert", &["amount", "from", "to"]),
+    route("GET", "/meal-plans/{id}/workload", &[]),
+    feature_route(Feature::Batch, "POST", "/batch", &[]),
+    feature_route(Feature::Collections, "POST", "/collections/{id}/addMany", &[]),
+    feature_route(Feature::Admin, "GET", "/admin/stats", &[]),
+    feature_route(Feature::Admin, "GET", "/admin/duplicates", &["page", "items", "maxTimeMs"]),
+    feature_route(Feature::Admin, "GET", "/admin/verifyIntegrity", &[]),
+    feature_route(Feature::Admin, "POST", "/admin/normalizeTags", &[]),
+    feature_route(Feature::Admin, "GET", "/admin/backup", &[]),
+    feature_route(Feature::Admin, "POST", "/admin/restore", &["mode"]),
+];
+
+/// the documented routes without the ones of disabled features
+pub fn enabled_routes(features: &FeatureFlags) -> Vec<RouteDoc> {
+    ROUTES.iter()
+        .filter(|route| route.feature.is_none_or(|feature| features.is_enabled(feature)))
+        .copied()
+        .collect()
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Discovery {
+    #[serde(rename = "basePath")]
+    pub base_path: &'static str,
+    pub routes: Vec<RouteDoc>,
+}
+
+pub struct DiscoveryRoutes {}
+
+impl DiscoveryRoutes {
+    /// the routes of the enabled features with their methods and query parameters, for exploring the api with curl
+    pub async fn get_routes(routes: web::Data<Vec<RouteDoc>>) -> HttpResponse {
+        HttpResponse::Ok().json(Discovery { base_path: API_BASE_PATH, routes: routes.get_ref().clone() })
+    }
+}
+
+
+#[cfg(test)]
+mod discovery_routes_tests {
+    use crate::discovery_routes::{enabled_routes, ROUTES};
+    use crate::features::FeatureFlags;
+
+    #[test]
+    fn disabled_features_are_left_out() {
+        assert_eq!(enabled_routes(&FeatureFlags::default()).len(), ROUTES.len());
+
+        let routes = enabled_routes(&FeatureFlags::parse("batch,image-upload"));
+        assert_eq!(routes.iter().any(|route| route.path == "/batch"), false);
+        assert_eq!(routes.iter().any(|route| route.path == "/recipes/{id}/image" && route.method == "PUT"), false);
+        assert_eq!(routes.iter().any(|route| route.path == "/recipes/{id}/image" && route.method == "GET"), true);
+    }
+}
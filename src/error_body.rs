@@ -0,0 +1,49 @@
+use serde::Serialize;
+
+use crate::model::recipe::RecipeFormatError;
+
+/// JSON body returned alongside client errors
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct ErrorBody {
+    pub error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// what blocks the request, e.g. the collections of a recipe to delete
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub references: Option<Vec<String>>,
+    /// how many recipes the request affects, e.g. for the confirmation of a bulk delete
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+    /// the maximum length of the field which is too long
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// the type a field of the body should have had
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+}
+
+impl ErrorBody {
+    pub fn new(error: &str) -> Self {
+        Self { error: error.to_string(), field: None, value: None, references: None, count: None, limit: None, expected: None }
+    }
+
+    pub fn for_field(error: &str, field: &str, value: &str) -> Self {
+        Self { error: error.to_string(), field: Some(field.to_string()), value: Some(value.to_string()), references: None, count: None, limit: None, expected: None }
+    }
+
+    pub fn with_references(error: &str, references: Vec<String>) -> Self {
+        Self { error: error.to_string(), field: None, value: None, references: Some(references), count: None, limit: None, expected: None }
+    }
+
+    pub fn with_count(error: &str, count: u64) -> Self {
+        Self { error: error.to_string(), field: None, value: None, references: None, count: Some(count), limit: None, expected: None }
+    }
+}
+
+impl From<RecipeFormatError> for ErrorBody {
+    fn from(error: RecipeFormatError) -> Self {
+        Self { error: error.error, field: error.field, value: None, references: None, count: None, limit: error.limit, expected: None }
+    }
+}
@@ -0,0 +1,146 @@
+use serde::Deserialize;
+
+use crate::export::locale::Locale;
+use crate::export::pdf::{Font, PdfDocument};
+use crate::model::recipe::Recipe;
+use crate::recipe_filter::RecipeFilter;
+
+pub const COOKBOOK_FILENAME: &str = "cookbook.pdf";
+pub const DEFAULT_COOKBOOK_TITLE: &str = "Cookbook";
+pub const MAX_COOKBOOK_RECIPES: usize = 50;
+
+/// a recipe of the cookbook, scaled to its own servings instead of the shared `?servings=`
+#[derive(Deserialize, Debug, Clone)]
+pub struct CookbookEntry {
+    pub id: String,
+    pub servings: Option<f64>,
+}
+
+/// Recipes of a cookbook, either listed by id, listed as entries with their servings or selected by a filter
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct CookbookRequest {
+    pub title: Option<String>,
+    #[serde(rename = "recipeIds")]
+    pub recipe_ids: Option<Vec<String>>,
+    pub recipes: Option<Vec<CookbookEntry>>,
+    pub filter: Option<RecipeFilter>,
+}
+
+impl CookbookRequest {
+    pub fn title(&self) -> String {
+        self.title.as_deref()
+            .map(str::trim)
+            .filter(|title| !title.is_empty())
+            .unwrap_or(DEFAULT_COOKBOOK_TITLE)
+            .to_string()
+    }
+}
+
+/// The cookbook as PDF, a title page listing the recipes, then each recipe starting on a new page.
+/// The recipes are expected to be scaled already
+pub fn render_cookbook(title: &str, recipes: &[Recipe], locale: Locale) -> Vec<u8> {
+    let mut document = PdfDocument::default();
+    document.start_page();
+    document.space(200.0);
+    document.paragraph(title, Font::Bold, 32.0, 0.0);
+    document.space(12.0);
+    let count = match recipes.len() {
+        1 => "1 recipe".to_string(),
+        count => format!("{} recipes", count),
+    };
+    document.paragraph(&count, Font::Regular, 14.0, 0.0);
+    document.space(24.0);
+    for (index, recipe) in recipes.iter().enumerate() {
+        document.paragraph(&format!("{}. {}", index + 1, recipe.title), Font::Regular, 12.0, 0.0);
+    }
+
+    for recipe in recipes {
+        document.start_page();
+        render_recipe(&mut document, recipe, locale);
+    }
+    document.into_bytes()
+}
+
+/// the same sections as the print view
+fn render_recipe(document: &mut PdfDocument, recipe: &Recipe, locale: Locale) {
+    let servings = match &recipe.recipe_yield {
+        Some(recipe_yield) => format!("{} {}", locale.format_amount(recipe_yield.amount), recipe_yield.unit),
+        None => format!("{} servings", recipe.default_servings),
+    };
+    document.paragraph(&recipe.title, Font::Bold, 22.0, 0.0);
+    document.paragraph(&format!("{} · {} min · {}", servings, recipe.cooking_time_in_minutes, recipe.difficulty), Font::Regular, 11.0, 0.0);
+    if !recipe.description.trim().is_empty() {
+        document.space(8.0);
+        document.paragraph(&recipe.description, Font::Regular, 11.0, 0.0);
+    }
+
+    heading(document, "Ingredients");
+    for ingredient in recipe.ingredients.iter() {
+        let mut line = format!("• {} {}", locale.format_quantity(ingredient.amount, &ingredient.measurement_unit), ingredient.title);
+        let substitutes = ingredient.normalized_substitutes();
+        if !substitutes.is_empty() {
+            line.push_str(&format!(" (or {})", substitutes.join(", ")));
+        }
+        document.paragraph(&line, Font::Regular, 11.0, 8.0);
+    }
+
+    if !recipe.equipment.is_empty() {
+        heading(document, "Equipment");
+        for tool in recipe.equipment.iter() {
+            document.paragraph(&format!("• {}", tool), Font::Regular, 11.0, 8.0);
+        }
+    }
+
+    heading(document, "Steps");
+    for (index, instruction) in recipe.instructions.iter().enumerate() {
+        document.paragraph(&format!("{}. {}", index + 1, instruction), Font::Regular, 11.0, 8.0);
+        document.space(4.0);
+    }
+}
+
+fn heading(document: &mut PdfDocument, text: &str) {
+    document.space(14.0);
+    document.paragraph(text, Font::Bold, 14.0, 0.0);
+    document.space(4.0);
+}
+
+
+#[cfg(test)]
+mod cookbook_tests {
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+    use crate::export::cookbook::{CookbookRequest, render_cookbook};
+    use crate::export::locale::Locale;
+    use crate::model::ingredients::Ingredient;
+    use crate::model::measurement_unit::MeasurementUnit;
+
+    #[test]
+    fn render_two_recipe_cookbook() {
+        let mut pasta = create_one_recipe_without_image();
+        pasta.title = "Pasta".to_string();
+        pasta.ingredients = vec![Ingredient::new("0", 100.0, "Spaghetti", MeasurementUnit::Gramm)];
+        pasta.instructions = vec!["Boil water".to_string()];
+        let mut soup = create_one_recipe_without_image();
+        soup.title = "Tomato soup".to_string();
+        soup.instructions = vec!["Simmer".to_string()];
+
+        let pdf = render_cookbook("Family recipes", &[pasta.scaled_to(4.0), soup], Locale::Neutral);
+        let text = String::from_utf8_lossy(&pdf);
+        assert_eq!(text.starts_with("%PDF-1.4\n"), true);
+        assert_eq!(text.ends_with("%%EOF\n"), true);
+        assert_eq!(text.matches("/Type /Page ").count(), 3);
+        assert_eq!(text.contains("/Count 3"), true);
+        assert_eq!(text.contains("(Family recipes) Tj"), true);
+        assert_eq!(text.contains("(2 recipes) Tj"), true);
+        assert_eq!(text.contains("(1. Pasta) Tj"), true);
+        assert_eq!(text.contains("(2. Tomato soup) Tj"), true);
+        assert_eq!(text.contains("400 Gramm Spaghetti) Tj"), true);
+        assert_eq!(text.contains("(1. Simmer) Tj"), true);
+    }
+
+    #[test]
+    fn cookbook_title() {
+        assert_eq!(CookbookRequest::default().title(), "Cookbook");
+        let request = CookbookRequest { title: Some(" Sunday ".to_string()), ..CookbookRequest::default() };
+        assert_eq!(request.title(), "Sunday");
+    }
+}
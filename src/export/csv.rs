@@ -0,0 +1,105 @@
+use bson::{Bson, Document};
+
+pub const CSV_CONTENT_TYPE: &str = "text/csv; charset=utf-8";
+pub const CSV_FILENAME: &str = "recipes.csv";
+pub const CSV_HEADER: &str = "id,title,difficulty,cookingTimeInMinutes,tags,equipment,ingredientCount\r\n";
+/// separates the entries inside the tags and equipment columns
+const TAG_SEPARATOR: &str = ";";
+const LINE_END: &str = "\r\n";
+/// spreadsheets run cells starting with one of these as formula
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// quotes the value as defined by RFC 4180 when it contains a separator, a quote or a line break
+pub fn escape_csv(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// user text as cell, text a spreadsheet would run as formula is prefixed with `'` to keep it text
+pub fn text_cell(value: &str) -> String {
+    match value.starts_with(FORMULA_PREFIXES) {
+        true => format!("'{}", value),
+        false => value.to_string(),
+    }
+}
+
+/// one line of the export from a recipe summary as projected by the dao,
+/// missing or malformed fields become empty cells
+pub fn summary_row(summary: &Document) -> String {
+    let id = summary.get_object_id("_id").map(|id| id.to_hex()).unwrap_or_default();
+    let cells = [
+        id,
+        text_cell(summary.get_str("title").unwrap_or_default()),
+        text_cell(summary.get_str("difficulty").unwrap_or_default()),
+        number_cell(summary.get("cookingTimeInMinutes")),
+        list_cell(summary, "tags"),
+        list_cell(summary, "equipment"),
+        number_cell(summary.get("ingredientCount")),
+    ];
+    let mut row = cells.iter().map(|cell| escape_csv(cell)).collect::<Vec<String>>().join(",");
+    row.push_str(LINE_END);
+    row
+}
+
+fn list_cell(summary: &Document, key: &str) -> String {
+    summary.get_array(key)
+        .map(|entries| text_cell(&entries.iter().filter_map(Bson::as_str).collect::<Vec<&str>>().join(TAG_SEPARATOR)))
+        .unwrap_or_default()
+}
+
+fn number_cell(value: Option<&Bson>) -> String {
+    match value {
+        Some(Bson::Int32(number)) => number.to_string(),
+        Some(Bson::Int64(number)) => number.to_string(),
+        _ => String::new(),
+    }
+}
+
+
+#[cfg(test)]
+mod csv_tests {
+    use bson::oid::ObjectId;
+
+    use crate::export::csv::{escape_csv, summary_row, text_cell};
+
+    #[test]
+    fn escape_values() {
+        assert_eq!(escape_csv("Spaghetti"), "Spaghetti");
+        assert_eq!(escape_csv("Salt, pepper"), "\"Salt, pepper\"");
+        assert_eq!(escape_csv("The \"best\" pie"), "\"The \"\"best\"\" pie\"");
+        assert_eq!(escape_csv("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn formulas_stay_text() {
+        assert_eq!(text_cell("=HYPERLINK(\"http://evil\")"), "'=HYPERLINK(\"http://evil\")");
+        assert_eq!(text_cell("+1"), "'+1");
+        assert_eq!(text_cell("-2+3"), "'-2+3");
+        assert_eq!(text_cell("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(text_cell("\t=1"), "'\t=1");
+        assert_eq!(text_cell("Pie = love"), "Pie = love");
+
+        let summary = doc! { "title": "=1+1", "tags": ["-x", "y"], "cookingTimeInMinutes": 5 };
+        assert_eq!(summary_row(&summary), ",'=1+1,,5,'-x;y,,\r\n");
+    }
+
+    #[test]
+    fn row_of_summary() {
+        let id = ObjectId::new();
+        let summary = doc! {
+            "_id": id.clone(),
+            "title": "Mac, \"cheese\"",
+            "difficulty": "Easy",
+            "cookingTimeInMinutes": 20,
+            "tags": ["pasta", "quick,easy"],
+            "equipment": ["pot", "colander"],
+            "ingredientCount": 3,
+        };
+        assert_eq!(summary_row(&summary),
+                   format!("{},\"Mac, \"\"cheese\"\"\",Easy,20,\"pasta;quick,easy\",pot;colander,3\r\n", id.to_hex()));
+        assert_eq!(summary_row(&doc! {}), ",,,,,,\r\n");
+    }
+}
@@ -0,0 +1,103 @@
+use chrono::SecondsFormat;
+use serde_json::{json, Map, Value};
+
+use crate::export::locale::Locale;
+use crate::model::recipe::Recipe;
+
+pub const JSON_LD_CONTENT_TYPE: &str = "application/ld+json";
+
+/// ISO 8601 duration as used by schema.org, e.g. `PT12M` or `PT1H30M`
+pub fn iso_duration(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("PT{}M", minutes),
+        (hours, 0) => format!("PT{}H", hours),
+        (hours, minutes) => format!("PT{}H{}M", hours, minutes),
+    }
+}
+
+/// schema.org `Recipe` of the recipe for rich search results, optional fields are left out when unset.
+/// The image is not part of it, as schema.org expects an url instead of the stored image data
+pub fn recipe_json_ld(recipe: &Recipe, locale: Locale) -> Value {
+    let recipe_yield = match &recipe.recipe_yield {
+        Some(recipe_yield) => format!("{} {}", locale.format_amount(recipe_yield.amount), recipe_yield.unit),
+        None => format!("{} servings", recipe.default_servings),
+    };
+    let ingredients = recipe.ingredients.iter()
+        .map(|ingredient| format!("{} {}", locale.format_quantity(ingredient.amount, &ingredient.measurement_unit), ingredient.title))
+        .collect::<Vec<String>>();
+    let instructions = recipe.instructions.iter()
+        .map(|instruction| json!({ "@type": "HowToStep", "text": instruction }))
+        .collect::<Vec<Value>>();
+
+    let mut json_ld = Map::new();
+    json_ld.insert("@context".to_string(), json!("https://schema.org"));
+    json_ld.insert("@type".to_string(), json!("Recipe"));
+    json_ld.insert("name".to_string(), json!(recipe.title));
+    if !recipe.description.is_empty() {
+        json_ld.insert("description".to_string(), json!(recipe.description));
+    }
+    json_ld.insert("datePublished".to_string(), json!(recipe.created.to_rfc3339_opts(SecondsFormat::Secs, true)));
+    json_ld.insert("dateModified".to_string(), json!(recipe.last_modified.to_rfc3339_opts(SecondsFormat::Secs, true)));
+    json_ld.insert("cookTime".to_string(), json!(iso_duration(recipe.cooking_time_in_minutes)));
+    json_ld.insert("recipeYield".to_string(), json!(recipe_yield));
+    json_ld.insert("recipeIngredient".to_string(), json!(ingredients));
+    json_ld.insert("recipeInstructions".to_string(), json!(instructions));
+    if !recipe.tags.is_empty() {
+        json_ld.insert("keywords".to_string(), json!(recipe.tags.join(", ")));
+    }
+    if let Some(cuisine) = &recipe.cuisine {
+        json_ld.insert("recipeCuisine".to_string(), json!(cuisine));
+    }
+    if let Some(language) = &recipe.language {
+        json_ld.insert("inLanguage".to_string(), json!(language));
+    }
+    if !recipe.equipment.is_empty() {
+        json_ld.insert("tool".to_string(), json!(recipe.equipment));
+    }
+    Value::Object(json_ld)
+}
+
+
+#[cfg(test)]
+mod json_ld_tests {
+    use serde_json::json;
+
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+    use crate::export::json_ld::{iso_duration, recipe_json_ld};
+    use crate::export::locale::Locale;
+    use crate::model::ingredients::Ingredient;
+    use crate::model::measurement_unit::MeasurementUnit;
+
+    #[test]
+    fn iso_duration_test() {
+        assert_eq!(iso_duration(12), "PT12M");
+        assert_eq!(iso_duration(0), "PT0M");
+        assert_eq!(iso_duration(60), "PT1H");
+        assert_eq!(iso_duration(95), "PT1H35M");
+    }
+
+    #[test]
+    fn recipe_json_ld_test() {
+        let mut recipe = create_one_recipe_without_image();
+        recipe.title = "Pancakes".to_string();
+        recipe.cooking_time_in_minutes = 12;
+        recipe.tags = vec!["breakfast".to_string(), "sweet".to_string()];
+        recipe.ingredients = vec![Ingredient::new("0", 250.0, "Flour", MeasurementUnit::Gramm)];
+        recipe.instructions = vec!["Mix".to_string(), "Fry".to_string()];
+
+        let json_ld = recipe_json_ld(&recipe, Locale::EnUs);
+        assert_eq!(json_ld["@context"], "https://schema.org");
+        assert_eq!(json_ld["@type"], "Recipe");
+        assert_eq!(json_ld["name"], "Pancakes");
+        assert_eq!(json_ld["cookTime"], "PT12M");
+        assert_eq!(json_ld["recipeYield"], "1 servings");
+        assert_eq!(json_ld["keywords"], "breakfast, sweet");
+        assert_eq!(json_ld["recipeIngredient"], json!(["250 g Flour"]));
+        assert_eq!(json_ld["recipeInstructions"], json!([
+            { "@type": "HowToStep", "text": "Mix" },
+            { "@type": "HowToStep", "text": "Fry" }
+        ]));
+        assert_eq!(json_ld.get("description").is_none(), true);
+        assert_eq!(json_ld.get("recipeCuisine").is_none(), true);
+    }
+}
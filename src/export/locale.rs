@@ -0,0 +1,135 @@
+use std::convert::TryFrom;
+
+use crate::model::measurement_unit::MeasurementUnit;
+
+/// Number and unit formatting of the exports, requested via `?locale=`
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Locale {
+    /// point decimals without grouping, units as stored
+    #[default]
+    Neutral,
+    DeDe,
+    EnUs,
+}
+
+impl TryFrom<&str> for Locale {
+    type Error = String;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().replace('_', "-").to_lowercase().as_str() {
+            "" | "neutral" => Ok(Locale::Neutral),
+            "de" | "de-de" => Ok(Locale::DeDe),
+            "en" | "en-us" => Ok(Locale::EnUs),
+            _ => Err(format!("Locale '{}' is not supported", value))
+        }
+    }
+}
+
+impl Locale {
+    fn decimal_separator(&self) -> char {
+        match self {
+            Locale::DeDe => ',',
+            Locale::Neutral | Locale::EnUs => '.',
+        }
+    }
+
+    fn group_separator(&self) -> Option<char> {
+        match self {
+            Locale::Neutral => None,
+            Locale::DeDe => Some('.'),
+            Locale::EnUs => Some(','),
+        }
+    }
+
+    /// formats with at most two decimals, dropping trailing zeros
+    pub fn format_amount(&self, amount: f64) -> String {
+        let formatted = format!("{:.2}", amount);
+        let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+        let (sign, formatted) = match formatted.strip_prefix('-') {
+            Some(unsigned) => ("-", unsigned),
+            None => ("", formatted)
+        };
+        let (integer, fraction) = match formatted.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (formatted, None)
+        };
+
+        let mut result = String::from(sign);
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                if let Some(separator) = self.group_separator() {
+                    result.push(separator);
+                }
+            }
+            result.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            result.push(self.decimal_separator());
+            result.push_str(fraction);
+        }
+        if result == "-0" { "0".to_string() } else { result }
+    }
+
+    pub fn format_unit(&self, unit: &MeasurementUnit) -> String {
+        match (self, unit) {
+            (Locale::Neutral, unit) => unit.to_string(),
+            (_, MeasurementUnit::Kilogramm) => "kg".to_string(),
+            (_, MeasurementUnit::Gramm) => "g".to_string(),
+            (_, MeasurementUnit::Milliliter) => "ml".to_string(),
+            (_, MeasurementUnit::Liter) => "l".to_string(),
+            (Locale::DeDe, MeasurementUnit::Piece) => "Stk.".to_string(),
+            (Locale::DeDe, MeasurementUnit::Pack) => "Pck.".to_string(),
+            (Locale::EnUs, MeasurementUnit::Piece) => "pc".to_string(),
+            (Locale::EnUs, MeasurementUnit::Pack) => "pack".to_string(),
+        }
+    }
+
+    pub fn format_quantity(&self, amount: f64, unit: &MeasurementUnit) -> String {
+        format!("{} {}", self.format_amount(amount), self.format_unit(unit))
+    }
+}
+
+
+#[cfg(test)]
+mod locale_tests {
+    use std::convert::TryFrom;
+
+    use crate::export::locale::Locale;
+    use crate::model::measurement_unit::MeasurementUnit;
+
+    #[test]
+    fn locale_from_str() {
+        assert_eq!(Locale::try_from("de-DE"), Ok(Locale::DeDe));
+        assert_eq!(Locale::try_from("en_us"), Ok(Locale::EnUs));
+        assert_eq!(Locale::try_from(""), Ok(Locale::Neutral));
+        assert_eq!(Locale::try_from("fr-FR").is_err(), true);
+    }
+
+    #[test]
+    fn format_amount_de_de() {
+        assert_eq!(Locale::DeDe.format_amount(1.5), "1,5");
+        assert_eq!(Locale::DeDe.format_amount(1234.25), "1.234,25");
+        assert_eq!(Locale::DeDe.format_amount(200.0), "200");
+    }
+
+    #[test]
+    fn format_amount_en_us() {
+        assert_eq!(Locale::EnUs.format_amount(1.5), "1.5");
+        assert_eq!(Locale::EnUs.format_amount(1234.25), "1,234.25");
+        assert_eq!(Locale::EnUs.format_amount(1_000_000.0), "1,000,000");
+    }
+
+    #[test]
+    fn format_amount_neutral() {
+        assert_eq!(Locale::Neutral.format_amount(1234.5), "1234.5");
+        assert_eq!(Locale::Neutral.format_amount(2.0 / 3.0), "0.67");
+        assert_eq!(Locale::Neutral.format_amount(-0.001), "0");
+    }
+
+    #[test]
+    fn format_quantity() {
+        assert_eq!(Locale::DeDe.format_quantity(0.5, &MeasurementUnit::Liter), "0,5 l");
+        assert_eq!(Locale::DeDe.format_quantity(2.0, &MeasurementUnit::Piece), "2 Stk.");
+        assert_eq!(Locale::EnUs.format_quantity(2.0, &MeasurementUnit::Piece), "2 pc");
+        assert_eq!(Locale::Neutral.format_quantity(300.0, &MeasurementUnit::Gramm), "300 Gramm");
+    }
+}
@@ -0,0 +1,115 @@
+use crate::export::locale::Locale;
+use crate::model::recipe::Recipe;
+
+pub const MARKDOWN_CONTENT_TYPE: &str = "text/markdown; charset=utf-8";
+
+/// escapes the characters which would start markdown formatting, e.g. emphasis, links or inline html
+/// line breaks become spaces, so the value cannot start a line with its own list item or heading
+pub fn escape_markdown(value: &str) -> String {
+    let line = value.replace("\r\n", " ").replace(['\n', '\r'], " ");
+    let mut escaped = String::with_capacity(line.len());
+    for character in line.chars() {
+        if "\\`*_[]<>#|".contains(character) {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+    escape_list_marker(escaped)
+}
+
+/// escapes a leading `-`, `+` or `1.` which would turn the line into a list item
+fn escape_list_marker(mut line: String) -> String {
+    let start = line.len() - line.trim_start().len();
+    let content = &line[start..];
+    let digits = content.chars().take_while(char::is_ascii_digit).count();
+    let marker = if content.starts_with('-') || content.starts_with('+') {
+        Some(start)
+    } else if digits > 0 && (content[digits..].starts_with('.') || content[digits..].starts_with(')')) {
+        Some(start + digits)
+    } else {
+        None
+    };
+    if let Some(marker) = marker {
+        line.insert(marker, '\\');
+    }
+    line
+}
+
+/// the recipe as markdown document with the same sections as the print view, all user content is escaped
+pub fn render_markdown(recipe: &Recipe, locale: Locale) -> String {
+    let servings = match &recipe.recipe_yield {
+        Some(recipe_yield) => format!("{} {}", locale.format_amount(recipe_yield.amount), escape_markdown(&recipe_yield.unit)),
+        None => format!("{} servings", recipe.default_servings),
+    };
+
+    let mut markdown = format!("# {}\n\n{} · {} min · {}\n",
+                               escape_markdown(&recipe.title), servings, recipe.cooking_time_in_minutes, recipe.difficulty);
+    if !recipe.description.trim().is_empty() {
+        markdown.push_str(&format!("\n{}\n", escape_markdown(&recipe.description)));
+    }
+
+    markdown.push_str("\n## Ingredients\n\n");
+    for ingredient in recipe.ingredients.iter() {
+        markdown.push_str(&format!("- {} {}",
+                                   locale.format_quantity(ingredient.amount, &ingredient.measurement_unit),
+                                   escape_markdown(&ingredient.title)));
+        let substitutes = ingredient.normalized_substitutes();
+        if !substitutes.is_empty() {
+            markdown.push_str(&format!(" (or {})", escape_markdown(&substitutes.join(", "))));
+        }
+        markdown.push('\n');
+    }
+
+    if !recipe.equipment.is_empty() {
+        markdown.push_str("\n## Equipment\n\n");
+        for tool in recipe.equipment.iter() {
+            markdown.push_str(&format!("- {}\n", escape_markdown(tool)));
+        }
+    }
+
+    markdown.push_str("\n## Steps\n\n");
+    for (index, instruction) in recipe.instructions.iter().enumerate() {
+        markdown.push_str(&format!("{}. {}\n", index + 1, escape_markdown(instruction)));
+    }
+    markdown
+}
+
+
+#[cfg(test)]
+mod markdown_tests {
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+    use crate::export::locale::Locale;
+    use crate::export::markdown::{escape_markdown, render_markdown};
+    use crate::model::ingredients::Ingredient;
+    use crate::model::measurement_unit::MeasurementUnit;
+
+    #[test]
+    fn escape_markdown_test() {
+        assert_eq!(escape_markdown("*bold* [link](x) <b>"), "\\*bold\\* \\[link\\](x) \\<b\\>");
+        assert_eq!(escape_markdown("Spaghetti"), "Spaghetti");
+        assert_eq!(escape_markdown("Pasta\n# Heading\r\n- item"), "Pasta \\# Heading - item");
+        assert_eq!(escape_markdown("- item"), "\\- item");
+        assert_eq!(escape_markdown("  + item"), "  \\+ item");
+        assert_eq!(escape_markdown("12. step"), "12\\. step");
+        assert_eq!(escape_markdown("3) step"), "3\\) step");
+        assert_eq!(escape_markdown("# Heading"), "\\# Heading");
+        assert_eq!(escape_markdown("2 eggs, 1.5 cups"), "2 eggs, 1.5 cups");
+        assert_eq!(escape_markdown("Stir-fry"), "Stir-fry");
+    }
+
+    #[test]
+    fn render_markdown_sections() {
+        let mut recipe = create_one_recipe_without_image();
+        recipe.title = "Pasta *al forno*".to_string();
+        let mut butter = Ingredient::new("1", 10.0, "Butter", MeasurementUnit::Gramm);
+        butter.substitutes = vec!["Margarine".to_string(), "butter".to_string()];
+        recipe.ingredients = vec![Ingredient::new("0", 150.0, "Flour", MeasurementUnit::Gramm), butter];
+        recipe.instructions = vec!["Boil water".to_string(), "Serve".to_string()];
+
+        let markdown = render_markdown(&recipe.scaled_to(2.0), Locale::Neutral);
+        assert_eq!(markdown.starts_with("# Pasta \\*al forno\\*\n\n2 servings"), true);
+        assert_eq!(markdown.contains("## Ingredients\n\n- 300 Gramm Flour\n- 20 Gramm Butter (or margarine)\n"), true);
+        assert_eq!(markdown.contains("Equipment"), false);
+        assert_eq!(markdown.ends_with("## Steps\n\n1. Boil water\n2. Serve\n"), true);
+    }
+}
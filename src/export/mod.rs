@@ -0,0 +1,7 @@
+pub mod cookbook;
+pub mod csv;
+pub mod json_ld;
+pub mod locale;
+pub mod markdown;
+pub mod pdf;
+pub mod print_view;
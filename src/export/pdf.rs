@@ -0,0 +1,247 @@
+pub const PDF_CONTENT_TYPE: &str = "application/pdf";
+
+/// A4 in points
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 56.0;
+const LINE_SPACING: f64 = 1.4;
+
+/// the standard fonts every viewer has, so no font has to be embedded
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource(&self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        }
+    }
+
+    /// approximated width of the character in units of the font size, good enough to wrap lines
+    fn width(&self, character: u8) -> f64 {
+        let width = match character {
+            b'i' | b'j' | b'l' | b'I' | b'.' | b',' | b';' | b':' | b'\'' | b'|' | b'!' | b' ' => 0.28,
+            b'f' | b't' | b'r' | b'(' | b')' | b'-' => 0.34,
+            b'm' | b'w' | b'M' | b'W' => 0.84,
+            b'A'..=b'Z' => 0.68,
+            _ => 0.56,
+        };
+        match self {
+            Font::Regular => width,
+            Font::Bold => width * 1.08,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TextLine {
+    font: Font,
+    size: f64,
+    x: f64,
+    y: f64,
+    text: Vec<u8>,
+}
+
+/// A text only PDF in the standard Helvetica fonts. Paragraphs wrap at the margins and continue
+/// on a new page when the page is full
+#[derive(Debug, Clone, Default)]
+pub struct PdfDocument {
+    pages: Vec<Vec<TextLine>>,
+    y: f64,
+}
+
+impl PdfDocument {
+    pub fn start_page(&mut self) {
+        self.pages.push(Vec::new());
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    /// moves the next line down by the given points
+    pub fn space(&mut self, points: f64) {
+        self.y -= points;
+    }
+
+    /// the text wrapped to the width between the margins, line breaks of the text are kept
+    pub fn paragraph(&mut self, text: &str, font: Font, size: f64, indent: f64) {
+        let max_width = PAGE_WIDTH - 2.0 * MARGIN - indent;
+        for line in text.lines() {
+            for wrapped in wrap(&to_win_ansi(line), font, size, max_width) {
+                self.line(wrapped, font, size, indent);
+            }
+        }
+    }
+
+    fn line(&mut self, text: Vec<u8>, font: Font, size: f64, indent: f64) {
+        let height = size * LINE_SPACING;
+        if self.pages.is_empty() || self.y - height < MARGIN {
+            self.start_page();
+        }
+        self.y -= height;
+        let y = self.y;
+        if let Some(page) = self.pages.last_mut() {
+            page.push(TextLine { font, size, x: MARGIN + indent, y, text });
+        }
+    }
+
+    /// the complete file, a document without pages gets one empty page
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        if self.pages.is_empty() {
+            self.start_page();
+        }
+        let page_count = self.pages.len();
+        let kids = (0..page_count).map(|index| format!("{} 0 R", 5 + 2 * index)).collect::<Vec<String>>().join(" ");
+
+        let mut objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids, page_count).into_bytes(),
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec(),
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec(),
+        ];
+        for (index, page) in self.pages.iter().enumerate() {
+            objects.push(format!("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                                  /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                                 PAGE_WIDTH, PAGE_HEIGHT, 6 + 2 * index).into_bytes());
+            let content = page_content(page);
+            let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+            stream.extend(content);
+            stream.extend_from_slice(b"\nendstream");
+            objects.push(stream);
+        }
+
+        let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n", index + 1).into_bytes());
+            pdf.extend_from_slice(object);
+            pdf.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = pdf.len();
+        pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+        for offset in offsets {
+            pdf.extend(format!("{:010} 00000 n \n", offset).into_bytes());
+        }
+        pdf.extend(format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).into_bytes());
+        pdf
+    }
+}
+
+fn page_content(page: &[TextLine]) -> Vec<u8> {
+    let mut content = Vec::new();
+    for line in page {
+        content.extend(format!("BT /{} {:.1} Tf {:.1} {:.1} Td (", line.font.resource(), line.size, line.x, line.y).into_bytes());
+        content.extend(escape_pdf_string(&line.text));
+        content.extend_from_slice(b") Tj ET\n");
+    }
+    content
+}
+
+/// escapes the delimiters of a literal string
+fn escape_pdf_string(text: &[u8]) -> Vec<u8> {
+    let mut escaped = Vec::with_capacity(text.len());
+    for byte in text {
+        if matches!(byte, b'(' | b')' | b'\\') {
+            escaped.push(b'\\');
+        }
+        escaped.push(*byte);
+    }
+    escaped
+}
+
+/// the text in the encoding of the standard fonts, characters it lacks become `?`
+pub fn to_win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|character| match character {
+            '\t' => b' ',
+            ' '..='~' | '\u{a0}'..='\u{ff}' => character as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// splits the text into lines at spaces, words wider than a line are split where they overflow
+fn wrap(text: &[u8], font: Font, size: f64, max_width: f64) -> Vec<Vec<u8>> {
+    let width = |text: &[u8]| text.iter().map(|character| font.width(*character)).sum::<f64>() * size;
+    let mut lines = Vec::new();
+    let mut line: Vec<u8> = Vec::new();
+    for word in text.split(|character| *character == b' ').filter(|word| !word.is_empty()) {
+        let mut candidate = line.clone();
+        if !candidate.is_empty() {
+            candidate.push(b' ');
+        }
+        candidate.extend_from_slice(word);
+        if width(&candidate) <= max_width {
+            line = candidate;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        for character in word {
+            if !line.is_empty() && width(&line) + font.width(*character) * size > max_width {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push(*character);
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+
+#[cfg(test)]
+mod pdf_tests {
+    use crate::export::pdf::{Font, PdfDocument, to_win_ansi, wrap};
+
+    #[test]
+    fn win_ansi_encoding() {
+        assert_eq!(to_win_ansi("Crème brûlée – 5 €"), b"Cr\xE8me br\xFBl\xE9e \x96 5 \x80".to_vec());
+        assert_eq!(to_win_ansi("寿司"), b"??".to_vec());
+    }
+
+    #[test]
+    fn wrap_long_lines() {
+        let lines = wrap(b"Boil the water and add the pasta", Font::Regular, 10.0, 60.0);
+        assert_eq!(lines.len() > 1, true);
+        assert_eq!(lines.join(&b' '), b"Boil the water and add the pasta".to_vec());
+
+        let lines = wrap(&[b'm'; 40], Font::Regular, 10.0, 100.0);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(wrap(b"", Font::Regular, 10.0, 100.0), vec![Vec::<u8>::new()]);
+    }
+
+    #[test]
+    fn full_pages_continue_on_a_new_page() {
+        let mut document = PdfDocument::default();
+        document.start_page();
+        for _ in 0..60 {
+            document.paragraph("Stir (gently) \\ repeat", Font::Regular, 12.0, 0.0);
+        }
+
+        let pdf = document.into_bytes();
+        let text = String::from_utf8_lossy(&pdf);
+        assert_eq!(text.starts_with("%PDF-1.4\n"), true);
+        assert_eq!(text.ends_with("%%EOF\n"), true);
+        assert_eq!(text.contains("/Count 2"), true);
+        assert_eq!(text.contains("(Stir \\(gently\\) \\\\ repeat) Tj"), true);
+
+        let xref = text.rfind("startxref\n").unwrap();
+        let offset = text[xref + 10..].lines().next().unwrap().parse::<usize>().unwrap();
+        assert_eq!(pdf[offset..].starts_with(b"xref\n0 9\n"), true);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::export::locale::Locale;
+use crate::model::recipe::Recipe;
+
+const PRINT_STYLE: &str = "body{font-family:Georgia,serif;max-width:42em;margin:2em auto;color:#000}\
+h1{margin-bottom:.2em}.meta{color:#444;margin-bottom:1.5em}\
+ul.ingredients{padding-left:1.2em}.substitutes{color:#444}ol.steps li{margin-bottom:.6em}\
+@media print{body{margin:0}}";
+
+/// escapes the characters with a meaning in html text and attribute values
+pub fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// self-contained printable html page of the recipe, all user content is escaped
+pub fn render_print_view(recipe: &Recipe, locale: Locale) -> String {
+    let servings = match &recipe.recipe_yield {
+        Some(recipe_yield) => format!("{} {}", locale.format_amount(recipe_yield.amount), escape_html(&recipe_yield.unit)),
+        None => format!("{} servings", recipe.default_servings),
+    };
+
+    let ingredients = recipe.ingredients.iter()
+        .map(|ingredient| {
+            let substitutes = ingredient.normalized_substitutes();
+            let substitutes = match substitutes.is_empty() {
+                true => String::new(),
+                false => format!(" <span class=\"substitutes\">(or {})</span>", escape_html(&substitutes.join(", "))),
+            };
+            format!("<li>{} {}{}</li>",
+                    locale.format_quantity(ingredient.amount, &ingredient.measurement_unit),
+                    escape_html(&ingredient.title), substitutes)
+        })
+        .collect::<String>();
+
+    let equipment = match recipe.equipment.is_empty() {
+        true => String::new(),
+        false => format!("<h2>Equipment</h2><ul class=\"equipment\">{}</ul>", recipe.equipment.iter()
+            .map(|tool| format!("<li>{}</li>", escape_html(tool)))
+            .collect::<String>()),
+    };
+
+    let steps = recipe.instructions.iter()
+        .map(|instruction| format!("<li>{}</li>", escape_html(instruction)))
+        .collect::<String>();
+
+    format!("<!DOCTYPE html>\
+<html><head><meta charset=\"utf-8\"><title>{title}</title><style>{style}</style></head>\
+<body><h1>{title}</h1>\
+<div class=\"meta\">{servings} &middot; {minutes} min &middot; {difficulty}</div>\
+<p>{description}</p>\
+<h2>Ingredients</h2><ul class=\"ingredients\">{ingredients}</ul>\
+{equipment}\
+<h2>Steps</h2><ol class=\"steps\">{steps}</ol>\
+</body></html>",
+            title = escape_html(&recipe.title),
+            style = PRINT_STYLE,
+            servings = servings,
+            minutes = recipe.cooking_time_in_minutes,
+            difficulty = recipe.difficulty,
+            description = escape_html(&recipe.description),
+            ingredients = ingredients,
+            equipment = equipment,
+            steps = steps)
+}
+
+
+#[cfg(test)]
+mod print_view_tests {
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+    use crate::export::locale::Locale;
+    use crate::model::ingredients::Ingredient;
+    use crate::model::measurement_unit::MeasurementUnit;
+    use crate::export::print_view::{escape_html, render_print_view};
+
+    #[test]
+    fn escape_html_test() {
+        assert_eq!(escape_html("<script>alert('x') & \"y\"</script>"),
+                   "&lt;script&gt;alert(&#39;x&#39;) &amp; &quot;y&quot;&lt;/script&gt;");
+        assert_eq!(escape_html("Spaghetti"), "Spaghetti");
+    }
+
+    #[test]
+    fn render_print_view_escapes_user_content() {
+        let mut recipe = create_one_recipe_without_image();
+        recipe.title = "<b>Pasta</b>".to_string();
+        recipe.instructions = vec!["Boil <water>".to_string(), "Serve".to_string()];
+
+        let html = render_print_view(&recipe, Locale::Neutral);
+        assert_eq!(html.starts_with("<!DOCTYPE html>"), true);
+        assert_eq!(html.contains("<b>Pasta</b>"), false);
+        assert_eq!(html.contains("<h1>&lt;b&gt;Pasta&lt;/b&gt;</h1>"), true);
+        assert_eq!(html.contains("<ol class=\"steps\"><li>Boil &lt;water&gt;</li><li>Serve</li></ol>"), true);
+    }
+
+    #[test]
+    fn render_print_view_lists_scaled_ingredients() {
+        let mut recipe = create_one_recipe_without_image();
+        recipe.ingredients = vec![Ingredient::new("0", 150.0, "Flour", MeasurementUnit::Gramm)];
+
+        let html = render_print_view(&recipe.scaled_to(2.0), Locale::Neutral);
+        assert_eq!(html.contains("<li>300 Gramm Flour</li>"), true);
+        assert_eq!(html.contains("2 servings"), true);
+    }
+
+    #[test]
+    fn render_print_view_lists_substitutes() {
+        let mut recipe = create_one_recipe_without_image();
+        let mut butter = Ingredient::new("0", 50.0, "Butter", MeasurementUnit::Gramm);
+        butter.substitutes = vec!["Margarine".to_string(), "<oil>".to_string()];
+        recipe.ingredients = vec![butter];
+
+        let html = render_print_view(&recipe, Locale::Neutral);
+        assert_eq!(html.contains("<li>50 Gramm Butter <span class=\"substitutes\">(or margarine, &lt;oil&gt;)</span></li>"), true);
+    }
+
+    #[test]
+    fn render_print_view_lists_equipment() {
+        let mut recipe = create_one_recipe_without_image();
+        assert_eq!(render_print_view(&recipe, Locale::Neutral).contains("Equipment"), false);
+
+        recipe.equipment = vec!["oven".to_string(), "<pan>".to_string()];
+        assert_eq!(render_print_view(&recipe, Locale::Neutral)
+                       .contains("<h2>Equipment</h2><ul class=\"equipment\"><li>oven</li><li>&lt;pan&gt;</li></ul>"), true);
+    }
+
+    #[test]
+    fn render_print_view_with_locale() {
+        let mut recipe = create_one_recipe_without_image();
+        recipe.ingredients = vec![Ingredient::new("0", 1.5, "Milk", MeasurementUnit::Liter)];
+
+        assert_eq!(render_print_view(&recipe, Locale::DeDe).contains("<li>1,5 l Milk</li>"), true);
+        assert_eq!(render_print_view(&recipe, Locale::EnUs).contains("<li>1.5 l Milk</li>"), true);
+    }
+}
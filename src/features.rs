@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+use crate::LogExtensionErr;
+
+pub const DISABLED_FEATURES_ENV: &str = "DISABLED_FEATURES";
+const FEATURE_SEPARATOR: char = ',';
+
+/// Optional parts of the api which operators can switch off
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Feature {
+    /// `/admin/*`
+    Admin,
+    /// `POST /batch`
+    Batch,
+    /// `POST /recipes`, the in memory and the streamed import, and `POST /recipes/validateMany`
+    BulkImport,
+    /// `/collections/*`
+    Collections,
+    /// `GET /recipes/{id}/print`, `GET /recipes/{id}/export` and `GET /recipes/export/csv`
+    Export,
+    /// `PUT` and `DELETE /recipes/{id}/image` as well as `PUT /recipes/{id}/image/url`, reading images stays available
+    ImageUpload,
+    /// `GET /recipes/{id}` taking the slug of the recipe in place of its id
+    SlugPaths,
+    /// `POST /recipes/importFromUrl`, which makes the service load the given pages
+    UrlImport,
+}
+
+impl TryFrom<&str> for Feature {
+    type Error = String;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "admin" => Ok(Feature::Admin),
+            "batch" => Ok(Feature::Batch),
+            "bulk-import" => Ok(Feature::BulkImport),
+            "collections" => Ok(Feature::Collections),
+            "export" => Ok(Feature::Export),
+            "image-upload" => Ok(Feature::ImageUpload),
+            "slug-paths" => Ok(Feature::SlugPaths),
+            "url-import" => Ok(Feature::UrlImport),
+            _ => Err(format!("Feature '{}' does not match one predefined value", value))
+        }
+    }
+}
+
+/// Features switched off at startup via `DISABLED_FEATURES=admin,image-upload,...`,
+/// the routes of disabled features are not registered and answer 404
+#[derive(Debug, Clone, Default)]
+pub struct FeatureFlags {
+    disabled: HashSet<Feature>,
+}
+
+impl FeatureFlags {
+    pub fn from_env() -> Self {
+        let features = std::env::var(DISABLED_FEATURES_ENV).unwrap_or_default();
+        let flags = FeatureFlags::parse(&features);
+        info!("Disabled features={:?}", flags.disabled);
+        flags
+    }
+
+    /// skips unknown features
+    pub fn parse(value: &str) -> Self {
+        let disabled = value.split(FEATURE_SEPARATOR)
+            .map(str::trim)
+            .filter(|feature| !feature.is_empty())
+            .filter_map(|feature| Feature::try_from(feature)
+                .log_if_err(|err| error!("Ignoring disabled feature. Err={}", err))
+                .ok())
+            .collect();
+        Self { disabled }
+    }
+
+    pub fn is_enabled(&self, feature: Feature) -> bool {
+        !self.disabled.contains(&feature)
+    }
+}
+
+
+#[cfg(test)]
+mod features_tests {
+    use crate::features::{Feature, FeatureFlags};
+
+    #[test]
+    fn all_features_enabled_by_default() {
+        let flags = FeatureFlags::default();
+        assert_eq!(flags.is_enabled(Feature::Admin), true);
+        assert_eq!(flags.is_enabled(Feature::ImageUpload), true);
+    }
+
+    #[test]
+    fn parse_disabled_features() {
+        let flags = FeatureFlags::parse(" admin, Image-Upload,,unknown,url-import,slug-paths ");
+        assert_eq!(flags.is_enabled(Feature::Admin), false);
+        assert_eq!(flags.is_enabled(Feature::ImageUpload), false);
+        assert_eq!(flags.is_enabled(Feature::UrlImport), false);
+        assert_eq!(flags.is_enabled(Feature::SlugPaths), false);
+        assert_eq!(flags.is_enabled(Feature::Batch), true);
+        assert_eq!(flags.is_enabled(Feature::BulkImport), true);
+    }
+}
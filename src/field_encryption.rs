@@ -0,0 +1,281 @@
+use std::sync::Arc;
+
+use bson::{Bson, Document};
+use ring::aead::{Aad, AES_256_GCM, LessSafeKey, Nonce, NONCE_LEN, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::auth::Identity;
+use crate::model::recipe::Recipe;
+use crate::recipe_filter::is_author_or_admin;
+
+pub const ENCRYPTED_FIELDS_ENV: &str = "ENCRYPTED_FIELDS";
+pub const ENCRYPTION_KEY_ENV: &str = "FIELD_ENCRYPTION_KEY";
+/// free text fields which may hold private data, only these can be encrypted
+pub const ENCRYPTABLE_FIELDS: [&str; 2] = ["notes", "source"];
+/// prefix of every encrypted value in the database, followed by the base64 of nonce and ciphertext
+pub const ENCRYPTED_PREFIX: &str = "encrypted:AES-256-GCM:";
+const FIELD_SEPARATOR: char = ',';
+
+/// Application level encryption of recipe fields, switched on by listing the fields,
+/// e.g. `ENCRYPTED_FIELDS=notes,source`, and off by default.
+///
+/// The key is the base64 of 32 random bytes in `FIELD_ENCRYPTION_KEY`, e.g. from `openssl rand -base64 32`.
+/// It is required once fields are listed, the service refuses to start without it rather than storing
+/// the fields in plain text. Keep the key out of the database and its backups, e.g. in the secret store
+/// of the deployment: whoever holds both can read the fields, whoever loses the key loses the fields.
+/// There is no key rotation, changing the key makes the values encrypted with the old one unreadable.
+///
+/// Values are encrypted with AES-256-GCM before they are stored and marked by `ENCRYPTED_PREFIX`.
+/// Every response with a single recipe decrypts them for its author and admins, everyone else and
+/// listings get the recipe without them. Encrypted fields cannot be searched or filtered by.
+#[derive(Clone)]
+pub struct FieldEncryption {
+    key: Arc<LessSafeKey>,
+    fields: Vec<String>,
+}
+
+impl std::fmt::Debug for FieldEncryption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FieldEncryption").field("fields", &self.fields).finish()
+    }
+}
+
+impl FieldEncryption {
+    /// None when no fields are listed, an error when the fields or the key are invalid
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let fields = parse_fields(&std::env::var(ENCRYPTED_FIELDS_ENV).unwrap_or_default())?;
+        if fields.is_empty() {
+            info!("Field encryption disabled");
+            return Ok(None);
+        }
+        let key = std::env::var(ENCRYPTION_KEY_ENV)
+            .map_err(|_| format!("{} is required to encrypt the fields {:?}", ENCRYPTION_KEY_ENV, fields))?;
+        let key = base64::decode(key.trim()).map_err(|_| format!("{} is no base64", ENCRYPTION_KEY_ENV))?;
+        let encryption = Self::new(&key, fields)?;
+        info!("Loaded field encryption={:?}", encryption);
+        Ok(Some(encryption))
+    }
+
+    pub fn new(key: &[u8], fields: Vec<String>) -> Result<Self, String> {
+        let key = UnboundKey::new(&AES_256_GCM, key)
+            .map_err(|_| format!("The encryption key must be {} bytes long", AES_256_GCM.key_len()))?;
+        Ok(Self { key: Arc::new(LessSafeKey::new(key)), fields })
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new().fill(&mut nonce).map_err(|_| "Could not generate a nonce".to_string())?;
+        let mut sealed = plaintext.as_bytes().to_vec();
+        self.key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut sealed)
+            .map_err(|_| "Could not encrypt the value".to_string())?;
+
+        let mut stored = nonce.to_vec();
+        stored.extend(sealed);
+        Ok(format!("{}{}", ENCRYPTED_PREFIX, base64::encode(stored)))
+    }
+
+    /// values without the prefix are returned as they are, e.g. ones stored before encrypting the field
+    pub fn decrypt(&self, value: &str) -> Result<String, String> {
+        let encoded = match value.strip_prefix(ENCRYPTED_PREFIX) {
+            Some(encoded) => encoded,
+            None => return Ok(value.to_string()),
+        };
+        let mut stored = base64::decode(encoded).map_err(|_| "The encrypted value is no base64".to_string())?;
+        if stored.len() < NONCE_LEN {
+            return Err("The encrypted value is too short".to_string());
+        }
+        let mut sealed = stored.split_off(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(&stored).map_err(|_| "Invalid nonce".to_string())?;
+        let plaintext = self.key.open_in_place(nonce, Aad::empty(), &mut sealed)
+            .map_err(|_| "Could not decrypt the value, the key differs or the value was altered".to_string())?;
+        String::from_utf8(plaintext.to_vec()).map_err(|_| "The decrypted value is no text".to_string())
+    }
+
+    /// encrypts the listed fields of a recipe document about to be stored, values already encrypted stay as they are
+    pub fn encrypt_document(&self, doc: &mut Document) -> Result<(), String> {
+        for field in &self.fields {
+            if let Some(Bson::String(value)) = doc.get(field) {
+                if !is_encrypted(value) {
+                    let encrypted = self.encrypt(value)?;
+                    doc.insert(field.clone(), encrypted);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn decrypt_value(&self, value: &mut Option<String>) {
+        if let Some(encrypted) = value.as_deref().filter(|value| is_encrypted(value)) {
+            *value = self.decrypt(encrypted)
+                .map_err(|err| error!("Could not decrypt recipe field. Err={}", err))
+                .ok();
+        }
+    }
+}
+
+fn parse_fields(value: &str) -> Result<Vec<String>, String> {
+    let fields = value.split(FIELD_SEPARATOR)
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(String::from)
+        .collect::<Vec<String>>();
+    match fields.iter().find(|field| !ENCRYPTABLE_FIELDS.contains(&field.as_str())) {
+        Some(field) => Err(format!("Field '{}' cannot be encrypted, only {:?}", field, ENCRYPTABLE_FIELDS)),
+        None => Ok(fields),
+    }
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// Decrypts the encrypted fields for the author of the recipe and admins, leaves them out for everyone else.
+/// Values which cannot be decrypted are left out as well
+pub fn reveal_recipe(recipe: &mut Recipe, encryption: Option<&FieldEncryption>, identity: Option<&Identity>) {
+    let authorized = is_author_or_admin(recipe.author.as_deref(), identity);
+    for value in [&mut recipe.notes, &mut recipe.source] {
+        match encryption {
+            Some(encryption) if authorized => encryption.decrypt_value(value),
+            _ => {}
+        }
+        if value.as_deref().is_some_and(is_encrypted) {
+            *value = None;
+        }
+    }
+}
+
+/// Keeps the stored encrypted values in an update by a caller who got the recipe without them,
+/// so that sending back what was read does not clear them
+pub fn keep_hidden_fields(recipe: &mut Recipe, stored: &Recipe, identity: Option<&Identity>) {
+    if is_author_or_admin(stored.author.as_deref(), identity) {
+        return;
+    }
+    for (value, stored) in [(&mut recipe.notes, &stored.notes), (&mut recipe.source, &stored.source)] {
+        if stored.as_deref().is_some_and(is_encrypted) {
+            value.clone_from(stored);
+        }
+    }
+}
+
+/// `reveal_recipe` for recipe documents as projected by a field mask, which needs the author to decrypt
+pub fn reveal_document(doc: &mut Document, encryption: Option<&FieldEncryption>, identity: Option<&Identity>) {
+    let authorized = is_author_or_admin(doc.get_str("author").ok(), identity);
+    for field in ENCRYPTABLE_FIELDS {
+        let mut value = match doc.get(field) {
+            Some(Bson::String(value)) => Some(value.clone()),
+            _ => continue,
+        };
+        match encryption {
+            Some(encryption) if authorized => encryption.decrypt_value(&mut value),
+            _ => {}
+        }
+        match value.filter(|value| !is_encrypted(value)) {
+            Some(value) => doc.insert(field, value),
+            None => doc.remove(field),
+        };
+    }
+}
+
+
+#[cfg(test)]
+pub mod field_encryption_tests {
+    use crate::auth::{Identity, Role};
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+    use crate::field_encryption::{ENCRYPTED_PREFIX, FieldEncryption, is_encrypted, keep_hidden_fields, parse_fields, reveal_document, reveal_recipe};
+
+    pub fn create_field_encryption() -> FieldEncryption {
+        FieldEncryption::new(&[7u8; 32], vec!["notes".to_string(), "source".to_string()]).unwrap()
+    }
+
+    #[test]
+    fn encrypt_decrypt_round_trip() {
+        let encryption = create_field_encryption();
+        let encrypted = encryption.encrypt("Grandma's secret: more butter").unwrap();
+        assert_eq!(encrypted.starts_with(ENCRYPTED_PREFIX), true);
+        assert_eq!(encrypted.contains("butter"), false);
+        assert_ne!(encryption.encrypt("Grandma's secret: more butter").unwrap(), encrypted);
+        assert_eq!(encryption.decrypt(&encrypted).unwrap(), "Grandma's secret: more butter");
+        assert_eq!(encryption.decrypt("plain").unwrap(), "plain");
+
+        let other = FieldEncryption::new(&[8u8; 32], vec![]).unwrap();
+        assert_eq!(other.decrypt(&encrypted).is_err(), true);
+        assert_eq!(encryption.decrypt(&format!("{}AAAA", ENCRYPTED_PREFIX)).is_err(), true);
+        assert_eq!(FieldEncryption::new(&[7u8; 16], vec![]).is_err(), true);
+    }
+
+    #[test]
+    fn encrypt_and_reveal_recipe() {
+        let encryption = create_field_encryption();
+        let mut recipe = create_one_recipe_without_image();
+        recipe.notes = Some("call Anna before".to_string());
+        recipe.source = None;
+        recipe.author = Some("bob".to_string());
+        let author = Identity::new("bob", Role::Editor);
+        let other = Identity::new("carol", Role::Editor);
+        let admin = Identity::new("alice", Role::Admin);
+
+        let mut doc = bson::Document::from(recipe.clone());
+        encryption.encrypt_document(&mut doc).unwrap();
+        let stored = doc.get_str("notes").unwrap().to_string();
+        assert_eq!(is_encrypted(&stored), true);
+        encryption.encrypt_document(&mut doc).unwrap();
+        assert_eq!(doc.get_str("notes").unwrap(), stored);
+
+        recipe.notes = Some(stored.clone());
+        let mut anonymous = recipe.clone();
+        reveal_recipe(&mut anonymous, Some(&encryption), None);
+        assert_eq!(anonymous.notes, None);
+        let mut not_author = recipe.clone();
+        reveal_recipe(&mut not_author, Some(&encryption), Some(&other));
+        assert_eq!(not_author.notes, None);
+        let mut without_key = recipe.clone();
+        reveal_recipe(&mut without_key, None, Some(&author));
+        assert_eq!(without_key.notes, None);
+        let mut as_admin = recipe.clone();
+        reveal_recipe(&mut as_admin, Some(&encryption), Some(&admin));
+        assert_eq!(as_admin.notes, Some("call Anna before".to_string()));
+        reveal_recipe(&mut recipe, Some(&encryption), Some(&author));
+        assert_eq!(recipe.notes, Some("call Anna before".to_string()));
+
+        let mut masked = doc! { "title": "Pasta", "author": "bob", "notes": stored.clone() };
+        reveal_document(&mut masked, Some(&encryption), Some(&author));
+        assert_eq!(masked, doc! { "title": "Pasta", "author": "bob", "notes": "call Anna before" });
+        let mut masked = doc! { "title": "Pasta", "author": "bob", "notes": stored.clone() };
+        reveal_document(&mut masked, Some(&encryption), Some(&other));
+        assert_eq!(masked, doc! { "title": "Pasta", "author": "bob" });
+        let mut masked = doc! { "title": "Pasta", "notes": stored };
+        reveal_document(&mut masked, Some(&encryption), None);
+        assert_eq!(masked, doc! { "title": "Pasta" });
+    }
+
+    #[test]
+    fn keep_hidden_fields_of_stored_recipe() {
+        let mut stored = create_one_recipe_without_image();
+        stored.author = Some("bob".to_string());
+        stored.notes = Some(format!("{}c2VjcmV0", ENCRYPTED_PREFIX));
+        stored.source = Some("Aunt Mary".to_string());
+        let mut update = stored.clone();
+        update.notes = None;
+        update.source = None;
+
+        let mut anonymous = update.clone();
+        keep_hidden_fields(&mut anonymous, &stored, None);
+        assert_eq!(anonymous.notes, stored.notes);
+        assert_eq!(anonymous.source, None);
+        let mut not_author = update.clone();
+        keep_hidden_fields(&mut not_author, &stored, Some(&Identity::new("carol", Role::Editor)));
+        assert_eq!(not_author.notes, stored.notes);
+        let mut author = update.clone();
+        keep_hidden_fields(&mut author, &stored, Some(&Identity::new("bob", Role::Editor)));
+        assert_eq!(author.notes, None);
+        keep_hidden_fields(&mut update, &stored, Some(&Identity::new("alice", Role::Admin)));
+        assert_eq!(update.notes, None);
+    }
+
+    #[test]
+    fn parse_encrypted_fields() {
+        assert_eq!(parse_fields(""), Ok(vec![]));
+        assert_eq!(parse_fields(" notes, source "), Ok(vec!["notes".to_string(), "source".to_string()]));
+        assert_eq!(parse_fields("notes,title").is_err(), true);
+    }
+}
@@ -0,0 +1,225 @@
+use bson::{Bson, Document};
+use chrono::SecondsFormat;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::field_encryption::reveal_document;
+use crate::model::recipe::RecipeFormatError;
+
+const FIELD_SEPARATOR: char = ',';
+/// stored next to the image and never part of a recipe response
+const DB_ATTR_THUMBNAIL: &str = "thumbnail";
+/// recipe fields of the api with their name in the database
+const RECIPE_FIELDS: [(&str, &str); 26] = [
+    ("id", "_id"),
+    ("cookingTimeInMinutes", "cookingTimeInMinutes"),
+    ("created", "created"),
+    ("lastModified", "last_modified"),
+    ("ingredients", "ingredients"),
+    ("version", "version"),
+    ("difficulty", "difficulty"),
+    ("description", "description"),
+    ("title", "title"),
+    ("tags", "tags"),
+    ("image", "image"),
+    ("instructions", "instructions"),
+    ("defaultServings", "defaultServings"),
+    ("yield", "yield"),
+    ("archived", "archived"),
+    ("slug", "slug"),
+    ("cuisine", "cuisine"),
+    ("language", "language"),
+    ("templateIds", "templateIds"),
+    ("equipment", "equipment"),
+    ("status", "status"),
+    ("author", "author"),
+    ("notes", "notes"),
+    ("source", "source"),
+    ("stepTimers", "stepTimers"),
+    ("fieldModified", "fieldModified"),
+];
+
+/// `?fields=title,tags` returns only the listed fields, `?exclude=image,description` all but the listed ones.
+/// `?fields[recipe]=title,tags` is the sparse fieldset of JSON:API for the same, fieldsets of other types are ignored
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct FieldMaskParams {
+    pub fields: Option<String>,
+    #[serde(rename = "fields[recipe]")]
+    pub sparse_fieldset: Option<String>,
+    pub exclude: Option<String>,
+}
+
+/// Recipe fields to return, applied as projection by the database.
+/// The id is always part of the response, like the database does for inclusions
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FieldMask {
+    Include(Vec<&'static str>),
+    Exclude(Vec<&'static str>),
+}
+
+impl FieldMaskParams {
+    /// None when neither parameter is set, Err on unknown fields or when both are set
+    /// as the database can not combine inclusion and exclusion
+    pub fn to_mask(&self) -> Result<Option<FieldMask>, RecipeFormatError> {
+        let fields = match (&self.fields, &self.sparse_fieldset) {
+            (Some(_), Some(_)) => return Err("Query parameters fields and fields[recipe] can not be combined".into()),
+            (fields, sparse_fieldset) => fields.as_ref().or(sparse_fieldset.as_ref()),
+        };
+        match (fields, &self.exclude) {
+            (Some(_), Some(_)) => Err("Query parameters fields and exclude can not be combined".into()),
+            (Some(fields), None) => Ok(Some(FieldMask::Include(parse_fields(fields)?))),
+            (None, Some(fields)) => Ok(Some(FieldMask::Exclude(parse_fields(fields)?))),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+impl FieldMask {
+    pub fn projection(&self) -> Document {
+        let mut projection = Document::new();
+        match self {
+            FieldMask::Include(fields) => fields.iter().for_each(|field| { projection.insert(*field, 1); }),
+            FieldMask::Exclude(fields) => {
+                fields.iter().for_each(|field| { projection.insert(*field, 0); });
+                projection.insert(DB_ATTR_THUMBNAIL, 0);
+            }
+        }
+        projection
+    }
+
+    /// the projection returning `field` as well, e.g. to decide on the response, and whether the mask itself returns it
+    pub fn projection_with(&self, field: &'static str) -> (Document, bool) {
+        let mut projection = self.projection();
+        let returned = match self {
+            FieldMask::Include(fields) => fields.contains(&field),
+            FieldMask::Exclude(fields) => !fields.contains(&field),
+        };
+        match self {
+            FieldMask::Include(_) => { projection.insert(field, 1); }
+            FieldMask::Exclude(_) => { projection.remove(field); }
+        }
+        (projection, returned)
+    }
+}
+
+fn parse_fields(fields: &str) -> Result<Vec<&'static str>, RecipeFormatError> {
+    let mut db_fields = Vec::new();
+    for field in fields.split(FIELD_SEPARATOR).map(str::trim).filter(|field| !field.is_empty()) {
+        match RECIPE_FIELDS.iter().find(|(api_field, _)| *api_field == field) {
+            Some((_, db_field)) if !db_fields.contains(db_field) => db_fields.push(*db_field),
+            Some(_) => {}
+            None => return Err(format!("Field '{}' does not match one recipe field", field).into()),
+        }
+    }
+    if db_fields.is_empty() {
+        return Err("At least one field is required".into());
+    }
+    Ok(db_fields)
+}
+
+/// response of a projected recipe document, in the shape of a serialized recipe without the left out fields
+pub fn masked_recipe_json(mut doc: Document) -> Value {
+    reveal_document(&mut doc, None, None);
+    let mut recipe = Map::new();
+    for (key, value) in doc {
+        let api_field = match RECIPE_FIELDS.iter().find(|(_, db_field)| *db_field == key) {
+            Some((api_field, _)) => *api_field,
+            None => continue,
+        };
+        recipe.insert(api_field.to_string(), masked_value(value));
+    }
+    Value::Object(recipe)
+}
+
+/// ids and dates as serialized by the api, also inside documents like the field modified times
+fn masked_value(value: Bson) -> Value {
+    match value {
+        Bson::ObjectId(id) => Value::String(id.to_hex()),
+        Bson::DateTime(date) => Value::String(date.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+        Bson::Document(doc) => Value::Object(doc.into_iter().map(|(key, value)| (key, masked_value(value))).collect()),
+        value => value.into_relaxed_extjson(),
+    }
+}
+
+
+#[cfg(test)]
+mod field_mask_tests {
+    use actix_web::web::Query;
+    use bson::oid::ObjectId;
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    use crate::field_mask::{FieldMask, FieldMaskParams, masked_recipe_json};
+
+    fn params(fields: Option<&str>, exclude: Option<&str>) -> FieldMaskParams {
+        FieldMaskParams { fields: fields.map(String::from), exclude: exclude.map(String::from), ..FieldMaskParams::default() }
+    }
+
+    #[test]
+    fn exclusion_mask() {
+        let mask = params(None, Some("image, description,lastModified")).to_mask().unwrap().unwrap();
+        assert_eq!(mask, FieldMask::Exclude(vec!["image", "description", "last_modified"]));
+        assert_eq!(mask.projection(), doc! { "image": 0, "description": 0, "last_modified": 0, "thumbnail": 0 });
+    }
+
+    #[test]
+    fn inclusion_mask() {
+        let mask = params(Some("title,tags,title"), None).to_mask().unwrap().unwrap();
+        assert_eq!(mask.projection(), doc! { "title": 1, "tags": 1 });
+        assert_eq!(params(None, None).to_mask().unwrap(), None);
+    }
+
+    #[test]
+    fn projection_with_field() {
+        let mask = params(Some("title"), None).to_mask().unwrap().unwrap();
+        assert_eq!(mask.projection_with("author"), (doc! { "title": 1, "author": 1 }, false));
+        let mask = params(Some("title,author"), None).to_mask().unwrap().unwrap();
+        assert_eq!(mask.projection_with("author"), (doc! { "title": 1, "author": 1 }, true));
+        let mask = params(None, Some("author,image")).to_mask().unwrap().unwrap();
+        assert_eq!(mask.projection_with("author"), (doc! { "image": 0, "thumbnail": 0 }, false));
+        let mask = params(None, Some("image")).to_mask().unwrap().unwrap();
+        assert_eq!(mask.projection_with("author"), (doc! { "image": 0, "thumbnail": 0 }, true));
+    }
+
+    #[test]
+    fn sparse_fieldset_mask() {
+        let params = Query::<FieldMaskParams>::from_query("fields%5Brecipe%5D=title,tags&fields[user]=name").unwrap().into_inner();
+        assert_eq!(params.to_mask().unwrap(), Some(FieldMask::Include(vec!["title", "tags"])));
+        let params = Query::<FieldMaskParams>::from_query("fields[recipe]=lastModified").unwrap().into_inner();
+        assert_eq!(params.to_mask().unwrap().unwrap().projection(), doc! { "last_modified": 1 });
+
+        let params = Query::<FieldMaskParams>::from_query("fields[recipe]=title,calories").unwrap().into_inner();
+        assert_eq!(params.to_mask().unwrap_err().error, "Field 'calories' does not match one recipe field");
+        let params = Query::<FieldMaskParams>::from_query("fields[recipe]=title&fields=tags").unwrap().into_inner();
+        assert_eq!(params.to_mask().is_err(), true);
+        let params = Query::<FieldMaskParams>::from_query("fields[recipe]=title&exclude=image").unwrap().into_inner();
+        assert_eq!(params.to_mask().is_err(), true);
+    }
+
+    #[test]
+    fn invalid_masks() {
+        assert_eq!(params(Some("title"), Some("image")).to_mask().is_err(), true);
+        assert_eq!(params(None, Some("image,thumbnail")).to_mask().is_err(), true);
+        assert_eq!(params(Some(" , "), None).to_mask().is_err(), true);
+    }
+
+    #[test]
+    fn masked_recipe_in_api_shape() {
+        let id = ObjectId::new();
+        let doc = doc! {
+            "_id": id.clone(),
+            "last_modified": Utc.ymd(2020, 9, 11).and_hms(12, 21, 21),
+            "title": "Spaghetti",
+            "defaultServings": 2,
+            "thumbnail": "thumb",
+            "fieldModified": { "title": Utc.ymd(2020, 9, 10).and_hms(8, 0, 0) },
+        };
+        assert_eq!(masked_recipe_json(doc), json!({
+            "id": id.to_hex(),
+            "lastModified": "2020-09-11T12:21:21Z",
+            "title": "Spaghetti",
+            "defaultServings": 2,
+            "fieldModified": { "title": "2020-09-10T08:00:00Z" },
+        }));
+    }
+}
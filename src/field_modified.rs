@@ -0,0 +1,54 @@
+use bson::Document;
+use chrono::{DateTime, Utc};
+
+pub const TRACK_FIELD_MODIFIED_ENV: &str = "TRACK_FIELD_MODIFIED";
+/// recipe attribute holding the time of the last change per field
+pub const JSON_ATTR_FIELD_MODIFIED: &str = "fieldModified";
+
+/// Tracks per recipe when each top level field last changed, e.g. `fieldModified: { "title": <time> }`,
+/// so sync clients can resolve conflicts field by field. Switched on by `TRACK_FIELD_MODIFIED=true`,
+/// off by default to keep the documents small. Only fields changed by updates get a time, recipes
+/// created or stored before tracking was switched on have none until their first change
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct FieldModifiedTracking {
+    pub enabled: bool,
+}
+
+impl FieldModifiedTracking {
+    pub fn from_env() -> Self {
+        let enabled = std::env::var(TRACK_FIELD_MODIFIED_ENV)
+            .is_ok_and(|enabled| matches!(enabled.trim().to_lowercase().as_str(), "true" | "1"));
+        let tracking = Self { enabled };
+        info!("Loaded field modified tracking={:?}", tracking);
+        tracking
+    }
+
+    /// `$set` entries giving the fields the time of the change, none while tracking is off
+    pub fn set_modified<'a>(&self, fields: impl IntoIterator<Item=&'a str>, modified: DateTime<Utc>) -> Document {
+        let mut set = Document::new();
+        if self.enabled {
+            for field in fields {
+                set.insert(format!("{}.{}", JSON_ATTR_FIELD_MODIFIED, field), modified);
+            }
+        }
+        set
+    }
+}
+
+
+#[cfg(test)]
+mod field_modified_tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::field_modified::FieldModifiedTracking;
+
+    #[test]
+    fn set_modified_of_touched_fields() {
+        let modified = Utc.ymd(2020, 10, 1).and_hms(12, 0, 0);
+        let tracking = FieldModifiedTracking { enabled: true };
+        assert_eq!(tracking.set_modified(vec!["title", "ingredients"], modified),
+                   doc! { "fieldModified.title": modified, "fieldModified.ingredients": modified });
+        assert_eq!(tracking.set_modified(vec![], modified), doc! {});
+        assert_eq!(FieldModifiedTracking::default().set_modified(vec!["title"], modified), doc! {});
+    }
+}
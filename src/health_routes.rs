@@ -0,0 +1,80 @@
+use actix_web::{HttpResponse, web};
+use actix_web::http::header::RETRY_AFTER;
+use serde::Serialize;
+
+use crate::circuit_breaker::{BreakerState, retry_after_seconds};
+use crate::dao::{Dao, DaoError};
+
+pub struct HealthRoutes {}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Readiness {
+    pub ready: bool,
+    #[serde(rename = "circuitBreaker")]
+    pub circuit_breaker: BreakerState,
+}
+
+impl HealthRoutes {
+    /// 200 while the database answers a ping, 503 otherwise, with `Retry-After` while the circuit breaker is open
+    pub async fn ready(database: web::Data<Dao>) -> HttpResponse {
+        let ping = database.ping().await;
+        let readiness = Readiness { ready: ping.is_ok(), circuit_breaker: database.circuit_breaker.state() };
+        match ping {
+            Ok(_) => HttpResponse::Ok().json(readiness),
+            Err(DaoError::Unavailable { retry_after: Some(retry_after) }) => HttpResponse::ServiceUnavailable()
+                .header(RETRY_AFTER, retry_after_seconds(retry_after).to_string())
+                .json(readiness),
+            Err(_) => HttpResponse::ServiceUnavailable().json(readiness),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod health_routes_tests {
+    use std::time::Duration;
+
+    use actix_web::{App, test, web};
+    use actix_web::http::StatusCode;
+    use actix_web::http::header::RETRY_AFTER;
+    use serde_json::{json, Value};
+
+    use crate::circuit_breaker::{BreakerState, CircuitBreaker};
+    use crate::dao::DaoError;
+    use crate::dao::dao_tests::unreachable_dao;
+    use crate::health_routes::HealthRoutes;
+
+    #[actix_rt::test]
+    async fn ready_reports_circuit_breaker_transitions() {
+        let dao = unreachable_dao(CircuitBreaker::new(2, Duration::from_millis(300))).await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/ready", web::get().to(HealthRoutes::ready))).await;
+
+        let req = test::TestRequest::get().uri("/ready").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(resp.headers().get(RETRY_AFTER).is_none(), true);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body, json!({ "ready": false, "circuitBreaker": "closed" }));
+
+        let req = test::TestRequest::get().uri("/ready").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["circuitBreaker"], "open");
+
+        let req = test::TestRequest::get().uri("/ready").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(resp.headers().get(RETRY_AFTER).unwrap(), "1");
+        match dao.get_recipe_ids(doc! {}).await {
+            Err(DaoError::Unavailable { retry_after }) => assert_eq!(retry_after.is_some(), true),
+            result => panic!("Expected the open breaker to refuse the call, got {:?}", result),
+        }
+
+        actix_rt::time::delay_for(Duration::from_millis(350)).await;
+        assert_eq!(dao.circuit_breaker.state(), BreakerState::HalfOpen);
+        let req = test::TestRequest::get().uri("/ready").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, json!({ "ready": false, "circuitBreaker": "open" }));
+    }
+}
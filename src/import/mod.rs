@@ -0,0 +1,2 @@
+pub mod page_fetch;
+pub mod schema_org;
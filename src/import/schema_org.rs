@@ -0,0 +1,390 @@
+use std::collections::BTreeMap;
+
+use bson::oid::ObjectId;
+use chrono::Utc;
+use serde_json::Value;
+
+use crate::export::json_ld::JSON_LD_CONTENT_TYPE;
+use crate::model::ingredients::Ingredient;
+use crate::model::measurement_unit::MeasurementUnit;
+use crate::model::recipe::{Recipe, RecipeFormatError};
+use crate::model::recipe_status::RecipeStatus;
+use crate::recipe_defaults::RecipeDefaults;
+
+const SCRIPT_START: &str = "<script";
+const SCRIPT_END: &str = "</script";
+const SCHEMA_ORG_RECIPE: &str = "Recipe";
+const KEYWORD_SEPARATOR: char = ',';
+
+/// First schema.org `Recipe` of the JSON-LD scripts of a page, also found inside arrays and `@graph`.
+/// Scripts which are no valid JSON are skipped
+pub fn find_recipe_json_ld(html: &str) -> Option<Value> {
+    json_ld_scripts(html).into_iter()
+        .filter_map(|script| serde_json::from_str::<Value>(script.trim()).ok())
+        .find_map(|json_ld| find_recipe(&json_ld).cloned())
+}
+
+/// contents of the `<script type="application/ld+json">` elements
+fn json_ld_scripts(html: &str) -> Vec<&str> {
+    // ascii lowercase keeps the byte offsets, so they apply to the original html as well
+    let lowercase = html.to_ascii_lowercase();
+    let mut scripts = Vec::new();
+    let mut position = 0;
+    while let Some(start) = lowercase[position..].find(SCRIPT_START).map(|start| position + start) {
+        let content_start = match lowercase[start..].find('>') {
+            Some(tag_end) => start + tag_end + 1,
+            None => break,
+        };
+        let content_end = lowercase[content_start..].find(SCRIPT_END)
+            .map_or(html.len(), |end| content_start + end);
+        if lowercase[start..content_start].contains(JSON_LD_CONTENT_TYPE) {
+            scripts.push(&html[content_start..content_end]);
+        }
+        position = content_end;
+    }
+    scripts
+}
+
+fn find_recipe(json_ld: &Value) -> Option<&Value> {
+    match json_ld {
+        Value::Array(values) => values.iter().find_map(find_recipe),
+        Value::Object(_) if is_recipe(json_ld) => Some(json_ld),
+        Value::Object(object) => object.get("@graph").and_then(find_recipe),
+        _ => None,
+    }
+}
+
+/// `@type` is either `Recipe` or a list containing it
+fn is_recipe(json_ld: &Value) -> bool {
+    match json_ld.get("@type") {
+        Some(Value::String(schema_type)) => schema_type == SCHEMA_ORG_RECIPE,
+        Some(Value::Array(schema_types)) => schema_types.iter().any(|schema_type| schema_type == SCHEMA_ORG_RECIPE),
+        _ => false,
+    }
+}
+
+/// Minutes of an ISO 8601 duration like `PT1H30M` or `P1DT2H`, seconds are rounded up to whole minutes
+pub fn parse_iso_duration(duration: &str) -> Option<u32> {
+    let duration = duration.trim().to_uppercase();
+    let mut rest = duration.strip_prefix('P')?;
+    let mut seconds = 0.0;
+    let mut in_time = false;
+    let mut number = String::new();
+    while let Some(character) = rest.chars().next() {
+        rest = &rest[character.len_utf8()..];
+        match character {
+            'T' if number.is_empty() => in_time = true,
+            '0'..='9' | '.' | ',' => number.push(if character == ',' { '.' } else { character }),
+            unit => {
+                let value = number.parse::<f64>().ok()?;
+                number.clear();
+                seconds += value * match (unit, in_time) {
+                    ('W', false) => 7.0 * 24.0 * 3600.0,
+                    ('D', false) => 24.0 * 3600.0,
+                    ('H', true) => 3600.0,
+                    ('M', true) => 60.0,
+                    ('S', true) => 1.0,
+                    _ => return None,
+                };
+            }
+        }
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    Some((seconds / 60.0).ceil() as u32)
+}
+
+/// Recipe of a schema.org `Recipe`, its url becomes the source. The cooking time is the total time,
+/// or preparation and cooking time added up. Difficulty and servings fall back to the defaults when
+/// the yield names no number, cuisine and tags when the recipe names none
+pub fn recipe_from_json_ld(json_ld: &Value, url: &str, defaults: &RecipeDefaults) -> Result<Recipe, RecipeFormatError> {
+    let title = text(json_ld.get("name"))
+        .filter(|title| !title.is_empty())
+        .ok_or("The schema.org recipe has no name")?;
+    let cooking_time = match json_ld.get("totalTime").and_then(Value::as_str) {
+        Some(total_time) => parse_iso_duration(total_time),
+        None => None,
+    }.unwrap_or_else(|| ["prepTime", "cookTime"].iter()
+        .filter_map(|field| json_ld.get(*field).and_then(Value::as_str).and_then(parse_iso_duration))
+        .sum());
+    let ingredients = texts(json_ld.get("recipeIngredient").or_else(|| json_ld.get("ingredients")))
+        .iter()
+        .enumerate()
+        .map(|(index, ingredient)| parse_ingredient(&index.to_string(), ingredient))
+        .collect::<Vec<Ingredient>>();
+    let mut instructions = Vec::new();
+    collect_instructions(json_ld.get("recipeInstructions"), &mut instructions);
+    let now = Utc::now();
+
+    Ok(Recipe {
+        _id: ObjectId::new(),
+        cooking_time_in_minutes: cooking_time,
+        created: now,
+        last_modified: now,
+        ingredients,
+        version: 1,
+        difficulty: defaults.difficulty.clone(),
+        description: text(json_ld.get("description")).unwrap_or_default(),
+        title,
+        tags: Some(keywords(json_ld.get("keywords")))
+            .filter(|tags| !tags.is_empty())
+            .unwrap_or_else(|| defaults.tags.clone()),
+        image_base64: None,
+        instructions,
+        default_servings: servings(json_ld.get("recipeYield")).unwrap_or(defaults.servings),
+        recipe_yield: None,
+        archived: false,
+        slug: None,
+        cuisine: texts(json_ld.get("recipeCuisine")).into_iter().next().or_else(|| defaults.cuisine.clone()),
+        language: text(json_ld.get("inLanguage")),
+        template_ids: vec![],
+        equipment: texts(json_ld.get("tool")),
+        status: RecipeStatus::default(),
+        author: None,
+        notes: None,
+        source: Some(url.to_string()),
+        step_timers: vec![],
+        field_modified: BTreeMap::new(),
+    })
+}
+
+/// Ingredient of a text like `250 g flour`, `1/2 l milk` or `2 eggs`. Texts without a known unit count
+/// pieces, texts without an amount like `salt to taste` keep their text as title with an amount of zero
+pub fn parse_ingredient(id: &str, text: &str) -> Ingredient {
+    let words = text.split_whitespace().collect::<Vec<&str>>();
+    let (amount, unit_attached, mut rest) = match words.first().map(|word| split_amount(word)) {
+        Some((Some(amount), unit)) => (amount, unit, &words[1..]),
+        _ => return Ingredient::new(id, 0.0, &words.join(" "), MeasurementUnit::Piece),
+    };
+    let mut amount = amount;
+    if let Some(fraction) = rest.first().and_then(|word| parse_fraction(word)) {
+        amount += fraction;
+        rest = &rest[1..];
+    }
+    let unit = if unit_attached.is_empty() {
+        let unit = rest.first().and_then(|word| parse_unit(word));
+        if unit.is_some() {
+            rest = &rest[1..];
+        }
+        unit
+    } else {
+        parse_unit(unit_attached)
+    };
+    let title = match (&unit, unit_attached) {
+        (None, unit) if !unit.is_empty() => format!("{} {}", unit, rest.join(" ")),
+        _ => rest.join(" "),
+    };
+    Ingredient::new(id, amount, title.trim(), unit.unwrap_or(MeasurementUnit::Piece))
+}
+
+/// leading amount of a word and the text after it, e.g. `250g` becomes 250 and `g`
+fn split_amount(word: &str) -> (Option<f64>, &str) {
+    let end = word.find(|character: char| !(character.is_ascii_digit() || ".,/".contains(character) || is_vulgar_fraction(character)))
+        .unwrap_or(word.len());
+    (parse_amount(&word[..end]), &word[end..])
+}
+
+fn parse_amount(amount: &str) -> Option<f64> {
+    parse_fraction(amount).or_else(|| amount.replace(',', ".").parse::<f64>().ok().filter(|amount| amount.is_finite()))
+}
+
+fn parse_fraction(fraction: &str) -> Option<f64> {
+    let mut characters = fraction.chars();
+    if let (Some(character), None) = (characters.next(), characters.next()) {
+        return vulgar_fraction(character);
+    }
+    let (numerator, denominator) = fraction.split_once('/')?;
+    let numerator = numerator.parse::<f64>().ok()?;
+    let denominator = denominator.parse::<f64>().ok().filter(|denominator| *denominator != 0.0)?;
+    Some(numerator / denominator)
+}
+
+fn is_vulgar_fraction(character: char) -> bool {
+    vulgar_fraction(character).is_some()
+}
+
+fn vulgar_fraction(character: char) -> Option<f64> {
+    match character {
+        '½' => Some(0.5),
+        '⅓' => Some(1.0 / 3.0),
+        '⅔' => Some(2.0 / 3.0),
+        '¼' => Some(0.25),
+        '¾' => Some(0.75),
+        _ => None,
+    }
+}
+
+fn parse_unit(unit: &str) -> Option<MeasurementUnit> {
+    match unit.trim_end_matches('.').to_lowercase().as_str() {
+        "g" | "gr" | "gram" | "grams" | "gramm" | "gramme" => Some(MeasurementUnit::Gramm),
+        "kg" | "kilo" | "kilogram" | "kilograms" | "kilogramm" => Some(MeasurementUnit::Kilogramm),
+        "ml" | "milliliter" | "milliliters" | "millilitre" | "millilitres" => Some(MeasurementUnit::Milliliter),
+        "l" | "liter" | "liters" | "litre" | "litres" => Some(MeasurementUnit::Liter),
+        "pc" | "pcs" | "piece" | "pieces" | "stk" | "stück" => Some(MeasurementUnit::Piece),
+        "pack" | "packs" | "package" | "packages" | "packet" | "packets" | "pck" | "pkg" => Some(MeasurementUnit::Pack),
+        _ => None,
+    }
+}
+
+/// steps as plain text, `HowToStep` as its text and `HowToSection` as its steps, texts split into lines
+fn collect_instructions(instructions: Option<&Value>, collected: &mut Vec<String>) {
+    match instructions {
+        Some(Value::String(instructions)) => collected.extend(instructions.lines()
+            .map(|line| decode_entities(line.trim()))
+            .filter(|line| !line.is_empty())),
+        Some(Value::Array(steps)) => steps.iter().for_each(|step| collect_instructions(Some(step), collected)),
+        Some(step @ Value::Object(_)) => match step.get("itemListElement") {
+            Some(steps) => collect_instructions(Some(steps), collected),
+            None => collect_instructions(step.get("text").or_else(|| step.get("name")), collected),
+        },
+        _ => {}
+    }
+}
+
+/// first number of the yield, e.g. 4 of `4 servings` or of `["4", "4 servings"]`
+fn servings(recipe_yield: Option<&Value>) -> Option<u32> {
+    match recipe_yield {
+        Some(Value::Number(servings)) => servings.as_f64().map(|servings| servings.round() as u32),
+        Some(Value::String(servings)) => servings.split_whitespace().find_map(|word| word.parse::<u32>().ok()),
+        Some(Value::Array(yields)) => yields.iter().find_map(|recipe_yield| servings(Some(recipe_yield))),
+        _ => None,
+    }.filter(|servings| *servings > 0)
+}
+
+/// keywords are either a comma separated text or a list
+fn keywords(keywords: Option<&Value>) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for keyword in texts(keywords).iter().flat_map(|keywords| keywords.split(KEYWORD_SEPARATOR)) {
+        let keyword = keyword.trim().to_string();
+        if !keyword.is_empty() && !tags.contains(&keyword) {
+            tags.push(keyword);
+        }
+    }
+    tags
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+    texts(value).into_iter().next()
+}
+
+/// non-empty texts of a text, a list of texts or objects named by `name`
+fn texts(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::String(text)) => Some(decode_entities(text.trim())).filter(|text| !text.is_empty()).into_iter().collect(),
+        Some(Value::Array(values)) => values.iter().flat_map(|value| texts(Some(value))).collect(),
+        Some(object @ Value::Object(_)) => texts(object.get("name")),
+        _ => vec![],
+    }
+}
+
+/// many sites escape their JSON-LD texts like html
+fn decode_entities(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#039;", "'")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+
+#[cfg(test)]
+pub mod schema_org_tests {
+    use serde_json::json;
+
+    use crate::import::schema_org::{find_recipe_json_ld, parse_ingredient, parse_iso_duration, recipe_from_json_ld};
+    use crate::model::difficulty::Difficulty;
+    use crate::model::ingredients::Ingredient;
+    use crate::model::measurement_unit::MeasurementUnit;
+    use crate::recipe_defaults::RecipeDefaults;
+
+    pub fn create_recipe_page() -> String {
+        r#"<html><head>
+            <script type="application/ld+json">{ "@context": "https://schema.org", "@type": "WebSite", "name": "Cooking" }</script>
+            <SCRIPT Type="application/ld+json">
+            { "@context": "https://schema.org", "@graph": [
+                { "@type": "BreadcrumbList" },
+                { "@type": ["Recipe"], "name": "Mac &amp; Cheese",
+                  "description": "Creamy", "prepTime": "PT10M", "cookTime": "PT1H5M",
+                  "recipeYield": ["4", "4 servings"], "keywords": "pasta, cheese, pasta",
+                  "recipeCuisine": ["American"], "inLanguage": "en",
+                  "recipeIngredient": ["250 g macaroni", "1/2 l milk", "2 eggs", "salt to taste"],
+                  "recipeInstructions": [
+                    { "@type": "HowToSection", "name": "Pasta", "itemListElement": [
+                        { "@type": "HowToStep", "text": "Boil the macaroni" } ] },
+                    { "@type": "HowToStep", "text": "Stir in the milk" },
+                    "Bake" ] }
+            ] }
+            </SCRIPT>
+        </head><body></body></html>"#.to_string()
+    }
+
+    #[test]
+    fn find_recipe_json_ld_test() {
+        let json_ld = find_recipe_json_ld(&create_recipe_page()).unwrap();
+        assert_eq!(json_ld["name"], "Mac &amp; Cheese");
+
+        let page = r#"<script type="application/ld+json">[{ "@type": "Recipe", "name": "Soup" }]</script>"#;
+        assert_eq!(find_recipe_json_ld(page).unwrap()["name"], "Soup");
+        assert_eq!(find_recipe_json_ld("<html><script>var recipe = 1;</script></html>"), None);
+        assert_eq!(find_recipe_json_ld(r#"<script type="application/ld+json">{ broken</script>"#), None);
+        assert_eq!(find_recipe_json_ld(r#"<script type="application/ld+json">{ "@type": "Person" }"#), None);
+    }
+
+    #[test]
+    fn parse_iso_duration_test() {
+        assert_eq!(parse_iso_duration("PT12M"), Some(12));
+        assert_eq!(parse_iso_duration("PT1H30M"), Some(90));
+        assert_eq!(parse_iso_duration("P1DT2H"), Some(1560));
+        assert_eq!(parse_iso_duration("PT90S"), Some(2));
+        assert_eq!(parse_iso_duration("PT0.5H"), Some(30));
+        assert_eq!(parse_iso_duration("P0D"), Some(0));
+        assert_eq!(parse_iso_duration("12 minutes"), None);
+        assert_eq!(parse_iso_duration("PT12"), None);
+        assert_eq!(parse_iso_duration("P5M"), None);
+    }
+
+    #[test]
+    fn parse_ingredient_test() {
+        assert_eq!(parse_ingredient("0", "250 g flour"), Ingredient::new("0", 250.0, "flour", MeasurementUnit::Gramm));
+        assert_eq!(parse_ingredient("1", "250g flour"), Ingredient::new("1", 250.0, "flour", MeasurementUnit::Gramm));
+        assert_eq!(parse_ingredient("2", "1,5 kg potatoes"), Ingredient::new("2", 1.5, "potatoes", MeasurementUnit::Kilogramm));
+        assert_eq!(parse_ingredient("3", "1 1/2 l milk"), Ingredient::new("3", 1.5, "milk", MeasurementUnit::Liter));
+        assert_eq!(parse_ingredient("4", "½ Pack. yeast"), Ingredient::new("4", 0.5, "yeast", MeasurementUnit::Pack));
+        assert_eq!(parse_ingredient("5", "2 eggs"), Ingredient::new("5", 2.0, "eggs", MeasurementUnit::Piece));
+        assert_eq!(parse_ingredient("6", "2 cups sugar"), Ingredient::new("6", 2.0, "cups sugar", MeasurementUnit::Piece));
+        assert_eq!(parse_ingredient("7", "3tbsp oil"), Ingredient::new("7", 3.0, "tbsp oil", MeasurementUnit::Piece));
+        assert_eq!(parse_ingredient("8", " salt to  taste"), Ingredient::new("8", 0.0, "salt to taste", MeasurementUnit::Piece));
+    }
+
+    #[test]
+    fn recipe_from_json_ld_test() {
+        let json_ld = find_recipe_json_ld(&create_recipe_page()).unwrap();
+        let defaults = RecipeDefaults { difficulty: Difficulty::Medium, servings: 2, ..RecipeDefaults::default() };
+        let recipe = recipe_from_json_ld(&json_ld, "https://example.org/mac", &defaults).unwrap();
+
+        assert_eq!(recipe.title, "Mac & Cheese");
+        assert_eq!(recipe.description, "Creamy");
+        assert_eq!(recipe.cooking_time_in_minutes, 75);
+        assert_eq!(recipe.default_servings, 4);
+        assert_eq!(recipe.difficulty, Difficulty::Medium);
+        assert_eq!(recipe.tags, vec!["pasta".to_string(), "cheese".to_string()]);
+        assert_eq!(recipe.cuisine, Some("American".to_string()));
+        assert_eq!(recipe.language, Some("en".to_string()));
+        assert_eq!(recipe.source, Some("https://example.org/mac".to_string()));
+        assert_eq!(recipe.ingredients.len(), 4);
+        assert_eq!(recipe.ingredients[1], Ingredient::new("1", 0.5, "milk", MeasurementUnit::Liter));
+        assert_eq!(recipe.instructions, vec!["Boil the macaroni".to_string(), "Stir in the milk".to_string(), "Bake".to_string()]);
+
+        let recipe = recipe_from_json_ld(&json!({ "@type": "Recipe", "name": "Soup", "totalTime": "PT20M", "cookTime": "PT5M",
+            "recipeInstructions": "Chop\n\nCook" }), "https://example.org/soup", &defaults).unwrap();
+        assert_eq!(recipe.cooking_time_in_minutes, 20);
+        assert_eq!(recipe.default_servings, 2);
+        assert_eq!(recipe.instructions, vec!["Chop".to_string(), "Cook".to_string()]);
+
+        assert_eq!(recipe_from_json_ld(&json!({ "@type": "Recipe" }), "https://example.org", &defaults).is_err(), true);
+    }
+}
@@ -0,0 +1,245 @@
+use actix_web::{error, HttpRequest, HttpResponse};
+use actix_web::error::JsonPayloadError;
+use actix_web::web::JsonConfig;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::error_body::ErrorBody;
+
+/// largest JSON body accepted, recipes carry their image
+pub const JSON_LIMIT_BYTES: usize = 5 << 20;
+
+/// The config of the JSON bodies of all routes, bodies which are no JSON or don't match the expected
+/// type are answered with 400 and an `ErrorBody` naming the field and the expected type
+pub fn json_config() -> JsonConfig {
+    JsonConfig::default()
+        .limit(JSON_LIMIT_BYTES)
+        .error_handler(json_error_handler)
+}
+
+fn json_error_handler(err: JsonPayloadError, req: &HttpRequest) -> error::Error {
+    info!("Invalid JSON body. route={} {}, Err={}", req.method(), req.path(), err);
+    let body = match &err {
+        JsonPayloadError::Overflow => ErrorBody::new(&format!("The body is larger than {} bytes", JSON_LIMIT_BYTES)),
+        JsonPayloadError::ContentType => ErrorBody::new("The body has to be sent as application/json"),
+        JsonPayloadError::Deserialize(err) => json_error_body(err, None),
+        JsonPayloadError::Payload(err) => ErrorBody::new(&err.to_string()),
+    };
+    error::InternalError::from_response(err, HttpResponse::BadRequest().json(body)).into()
+}
+
+/// The value as `T`. Unlike extracting `T` from the body, a mismatch names the whole path of
+/// the field, e.g. `ingredients[0].amount`, as the JSON is at hand to locate the error in
+pub fn from_json_value<T: DeserializeOwned>(value: Value) -> Result<T, Box<ErrorBody>> {
+    let json = value.to_string();
+    serde_json::from_str::<T>(&json).map_err(|err| Box::new(json_error_body(&err, Some(&json))))
+}
+
+/// The serde error without its position. The field is the path found at the position of the error in the
+/// JSON when given, otherwise only the field a missing or unknown field error names
+pub fn json_error_body(err: &serde_json::Error, json: Option<&str>) -> ErrorBody {
+    let message = err.to_string();
+    let position = format!(" at line {} column {}", err.line(), err.column());
+    let message = message.strip_suffix(&position).unwrap_or(&message);
+
+    let named = named_field(message);
+    let missing = message.starts_with("missing field");
+    let field = match json {
+        Some(json) => {
+            let mut path = path_at(json, err.line(), err.column(), named.is_some() && !missing);
+            if let Some(named) = named.filter(|_| missing) {
+                path.push(Segment::Key(named));
+            }
+            Some(path).filter(|path| !path.is_empty()).map(|path| format_path(&path))
+        }
+        None => named,
+    };
+    let expected = message.split_once(", expected ").map(|(_, expected)| expected.to_string());
+    ErrorBody { field, expected, ..ErrorBody::new(message) }
+}
+
+/// the field in backticks of `missing field`, `unknown field` and `duplicate field` errors
+fn named_field(message: &str) -> Option<String> {
+    ["missing field `", "unknown field `", "duplicate field `"].iter()
+        .find_map(|prefix| message.strip_prefix(prefix))
+        .and_then(|rest| rest.split_once('`'))
+        .map(|(field, _)| field.to_string())
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug)]
+enum Container {
+    Object { key: Option<String>, after_colon: bool },
+    Array { index: usize },
+}
+
+/// The keys and indices of the values enclosing the position, 1 based like the positions of serde.
+/// A field error points behind the key of the field, so the key is only part of the path once its
+/// value started, unless `with_pending_key` is set
+fn path_at(json: &str, line: usize, column: usize, with_pending_key: bool) -> Vec<Segment> {
+    let mut stack: Vec<Container> = Vec::new();
+    let mut string: Option<String> = None;
+    let mut escaped = false;
+    let (mut current_line, mut current_column) = (1, 0);
+
+    for character in json.chars() {
+        if current_line > line || (current_line == line && current_column >= column) {
+            break;
+        }
+        if character == '\n' {
+            current_line += 1;
+            current_column = 0;
+        } else {
+            current_column += character.len_utf8();
+        }
+
+        if let Some(value) = string.as_mut() {
+            match (escaped, character) {
+                (false, '\\') => escaped = true,
+                (false, '"') => {
+                    let value = string.take().unwrap_or_default();
+                    if let Some(Container::Object { key, after_colon: false }) = stack.last_mut() {
+                        *key = Some(value);
+                    }
+                }
+                _ => {
+                    escaped = false;
+                    value.push(character);
+                }
+            }
+            continue;
+        }
+        match (character, stack.last_mut()) {
+            ('"', _) => string = Some(String::new()),
+            ('{', _) => stack.push(Container::Object { key: None, after_colon: false }),
+            ('[', _) => stack.push(Container::Array { index: 0 }),
+            ('}', _) | (']', _) => { stack.pop(); }
+            (':', Some(Container::Object { after_colon, .. })) => *after_colon = true,
+            (',', Some(Container::Object { key, after_colon })) => {
+                *key = None;
+                *after_colon = false;
+            }
+            (',', Some(Container::Array { index })) => *index += 1,
+            _ => {}
+        }
+    }
+
+    let last = stack.len().saturating_sub(1);
+    stack.into_iter().enumerate()
+        .filter_map(|(position, container)| match container {
+            Container::Object { key, after_colon } if after_colon || (with_pending_key && position == last) => key.map(Segment::Key),
+            Container::Object { .. } => None,
+            Container::Array { index } => Some(Segment::Index(index)),
+        })
+        .collect()
+}
+
+fn format_path(path: &[Segment]) -> String {
+    let mut formatted = String::new();
+    for segment in path {
+        match segment {
+            Segment::Key(key) if formatted.is_empty() => formatted.push_str(key),
+            Segment::Key(key) => formatted.push_str(&format!(".{}", key)),
+            Segment::Index(index) => formatted.push_str(&format!("[{}]", index)),
+        }
+    }
+    formatted
+}
+
+
+#[cfg(test)]
+mod json_error_tests {
+    use actix_web::{App, HttpResponse, test, web};
+    use actix_web::http::StatusCode;
+    use serde_json::{json, Value};
+
+    use crate::json_error::{from_json_value, json_config};
+    use crate::model::ingredients::Ingredient;
+    use crate::model::recipe::Recipe;
+
+    fn recipe_json() -> Value {
+        json!({
+            "cookingTimeInMinutes": 10, "created": "2020-10-01T00:00:00Z", "lastModified": "2020-10-01T00:00:00Z",
+            "ingredients": [{ "id": "0", "amount": 100.0, "title": "Flour", "measurementUnit": "Gramm" }],
+            "version": 0, "difficulty": "Easy", "description": "", "title": "Bread", "tags": [],
+            "image": null, "instructions": ["Bake"], "defaultServings": 1
+        })
+    }
+
+    #[test]
+    fn valid_value() {
+        assert_eq!(from_json_value::<Recipe>(recipe_json()).is_ok(), true);
+    }
+
+    #[test]
+    fn wrong_typed_fields() {
+        let mut recipe = recipe_json();
+        recipe["cookingTimeInMinutes"] = json!("ten");
+        let err = from_json_value::<Recipe>(recipe).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("cookingTimeInMinutes"));
+        assert_eq!(err.expected.as_deref(), Some("u32"));
+        assert_eq!(err.error, "invalid type: string \"ten\", expected u32");
+
+        let mut recipe = recipe_json();
+        recipe["ingredients"][0]["amount"] = json!([1]);
+        let err = from_json_value::<Recipe>(recipe).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("ingredients[0].amount"));
+
+        let mut recipe = recipe_json();
+        recipe["instructions"] = json!(["Knead", 2]);
+        let err = from_json_value::<Recipe>(recipe).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("instructions[1]"));
+
+        let mut recipe = recipe_json();
+        recipe["difficulty"] = json!("Extreme");
+        let err = from_json_value::<Recipe>(recipe).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("difficulty"));
+    }
+
+    #[test]
+    fn missing_fields() {
+        let mut recipe = recipe_json();
+        recipe.as_object_mut().unwrap().remove("title");
+        let err = from_json_value::<Recipe>(recipe).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("title"));
+        assert_eq!(err.expected, None);
+
+        let mut recipe = recipe_json();
+        recipe["ingredients"][0].as_object_mut().unwrap().remove("title");
+        let err = from_json_value::<Recipe>(recipe).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("ingredients[0].title"));
+    }
+
+    #[actix_rt::test]
+    async fn extracted_bodies() {
+        let mut app = test::init_service(App::new()
+            .app_data(json_config())
+            .route("/ingredients", web::post().to(|_: web::Json<Ingredient>| HttpResponse::Ok()))).await;
+
+        let req = test::TestRequest::post().uri("/ingredients")
+            .set_json(&json!({ "id": "0", "amount": "much", "title": "Flour", "measurementUnit": "Gramm" })).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["expected"], "f64");
+
+        let req = test::TestRequest::post().uri("/ingredients")
+            .set_json(&json!({ "id": "0", "amount": 1, "measurementUnit": "Gramm" })).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["field"], "title");
+
+        let req = test::TestRequest::post().uri("/ingredients")
+            .header("content-type", "application/json").set_payload("{\"id\": ").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body.get("field"), None);
+    }
+}
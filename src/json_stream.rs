@@ -0,0 +1,62 @@
+use actix_web::web::Bytes;
+use futures_util::{future, stream, StreamExt};
+use futures_util::stream::Stream;
+
+/// Writes the items as one JSON array while they are produced: `[`, the items separated by commas, `]`.
+/// Actix ends the response at an error, the client then receives a truncated body
+pub fn json_array<S, E>(items: S) -> impl Stream<Item=Result<Bytes, E>>
+    where S: Stream<Item=Result<Vec<u8>, E>> {
+    let items = items.enumerate().map(|(index, item)| item.map(|item| {
+        let mut bytes = Vec::with_capacity(item.len() + 1);
+        if index > 0 {
+            bytes.push(b',');
+        }
+        bytes.extend(item);
+        Bytes::from(bytes)
+    }));
+    stream::once(future::ok(Bytes::from_static(b"[")))
+        .chain(items)
+        .chain(stream::once(future::ok(Bytes::from_static(b"]"))))
+}
+
+
+#[cfg(test)]
+mod json_stream_tests {
+    use futures_util::{stream, StreamExt};
+    use serde_json::{json, Value};
+
+    use crate::json_stream::json_array;
+
+    async fn collect(items: Vec<Value>) -> Value {
+        let items = stream::iter(items.into_iter().map(|item| Ok::<_, ()>(serde_json::to_vec(&item).unwrap())));
+        let body = json_array(items)
+            .map(|bytes| bytes.unwrap().to_vec())
+            .collect::<Vec<Vec<u8>>>().await
+            .concat();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn empty_array() {
+        assert_eq!(collect(vec![]).await, json!([]));
+    }
+
+    #[actix_rt::test]
+    async fn commas_between_items() {
+        assert_eq!(collect(vec![json!({ "a": 1 })]).await, json!([{ "a": 1 }]));
+        assert_eq!(collect(vec![json!(1), json!("two"), json!([3])]).await, json!([1, "two", [3]]));
+    }
+
+    #[actix_rt::test]
+    async fn large_array_is_valid_json() {
+        let items = (0..10_000).map(|index| json!({ "id": index, "title": format!("Recipe, \"{}\"", index) })).collect::<Vec<Value>>();
+        assert_eq!(collect(items.clone()).await, Value::Array(items));
+    }
+
+    #[actix_rt::test]
+    async fn error_ends_stream() {
+        let items = stream::iter(vec![Ok(b"1".to_vec()), Err("broken")]);
+        let chunks = json_array(items).collect::<Vec<_>>().await;
+        assert_eq!(chunks.iter().any(|chunk| chunk.is_err()), true);
+    }
+}
@@ -0,0 +1,125 @@
+use actix_web::{HttpRequest, HttpResponse};
+use actix_web::dev::HttpResponseBuilder;
+use actix_web::http::header::{ACCEPT, VARY};
+use bson::{Bson, Document};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::pagination::Pagination;
+
+pub const ESTIMATED_COUNT_THRESHOLD_ENV: &str = "ESTIMATED_COUNT_THRESHOLD";
+/// `Accept` media type asking for the envelope, the alternative to `?envelope=true`
+pub const ENVELOPE_MEDIA_TYPE: &str = "application/vnd.zellinotes.envelope+json";
+/// set on listings answered with the bare array, which is going to be replaced by the envelope
+pub const DEPRECATION_HEADER: &str = "Deprecation";
+
+/// Listings answer a bare array unless the envelope is asked for, to keep array consuming clients working.
+/// `?envelope=` takes precedence over the `Accept` header
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct EnvelopeParams {
+    pub envelope: Option<bool>,
+}
+
+impl EnvelopeParams {
+    pub fn is_requested(&self, req: &HttpRequest) -> bool {
+        self.envelope.unwrap_or_else(|| accepts_envelope(req))
+    }
+}
+
+/// 200 for a listing, its shape depends on `Accept` so caches have to keep the shapes apart
+pub fn listing_response() -> HttpResponseBuilder {
+    let mut response = HttpResponse::Ok();
+    response.header(VARY, ACCEPT.as_str());
+    response
+}
+
+fn accepts_envelope(req: &HttpRequest) -> bool {
+    req.headers().get_all(ACCEPT)
+        .filter_map(|accept| accept.to_str().ok())
+        .flat_map(|accept| accept.split(','))
+        .any(|media_type| media_type.split(';').next().unwrap_or_default().trim().eq_ignore_ascii_case(ENVELOPE_MEDIA_TYPE))
+}
+
+/// Counting exactly gets slow on huge collections. With `ESTIMATED_COUNT_THRESHOLD=100000` listings
+/// without filter report the estimate from the collection metadata once it reaches the threshold.
+/// The estimate includes archived recipes, filtered listings are always counted exactly.
+/// Unset or invalid, all counts are exact
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CountSettings {
+    pub estimate_threshold: Option<u64>,
+}
+
+impl CountSettings {
+    pub fn from_env() -> Self {
+        let estimate_threshold = std::env::var(ESTIMATED_COUNT_THRESHOLD_ENV).ok()
+            .and_then(|threshold| threshold.parse::<u64>().ok());
+        info!("Estimated count threshold={:?}", estimate_threshold);
+        Self { estimate_threshold }
+    }
+}
+
+/// Total of a listing, either counted or estimated
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RecipeCount {
+    pub total: u64,
+    pub is_estimate: bool,
+}
+
+/// List response wrapping the items together with metadata about the query
+#[derive(Serialize, Debug, Clone)]
+pub struct ListEnvelope<T> {
+    pub data: Vec<T>,
+    pub meta: ListMeta,
+}
+
+/// Echo of how the query parameters were interpreted, including applied defaults.
+/// Paged listings add the number of pages and whether the page lies past the last one
+#[derive(Serialize, Debug, Clone)]
+pub struct ListMeta {
+    pub total: u64,
+    #[serde(rename = "countIsEstimate")]
+    pub count_is_estimate: bool,
+    pub filter: Value,
+    pub sort: Value,
+    pub pagination: Option<Pagination>,
+    #[serde(rename = "totalPages", skip_serializing_if = "Option::is_none")]
+    pub total_pages: Option<usize>,
+    #[serde(rename = "beyondLastPage", skip_serializing_if = "Option::is_none")]
+    pub beyond_last_page: Option<bool>,
+}
+
+impl ListMeta {
+    pub fn new(count: RecipeCount, filter: Document, sort: Option<Document>, pagination: Option<Pagination>) -> Self {
+        Self {
+            total: count.total,
+            count_is_estimate: count.is_estimate,
+            filter: Bson::Document(filter).into_relaxed_extjson(),
+            sort: Bson::Document(sort.unwrap_or_default()).into_relaxed_extjson(),
+            pagination,
+            total_pages: pagination.and_then(|pagination| pagination.last_page(count.total)),
+            beyond_last_page: pagination.map(|pagination| pagination.is_beyond_last_page(count.total)),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod list_response_tests {
+    use actix_web::http::header::ACCEPT;
+    use actix_web::test;
+
+    use crate::list_response::{ENVELOPE_MEDIA_TYPE, EnvelopeParams};
+
+    #[test]
+    fn envelope_from_query_or_accept_header() {
+        let plain = test::TestRequest::get().to_http_request();
+        let accepting = test::TestRequest::get()
+            .header(ACCEPT, format!("application/json, {};q=0.9", ENVELOPE_MEDIA_TYPE))
+            .to_http_request();
+
+        assert_eq!(EnvelopeParams::default().is_requested(&plain), false);
+        assert_eq!(EnvelopeParams { envelope: Some(true) }.is_requested(&plain), true);
+        assert_eq!(EnvelopeParams::default().is_requested(&accepting), true);
+        assert_eq!(EnvelopeParams { envelope: Some(false) }.is_requested(&accepting), false);
+    }
+}
@@ -6,20 +6,80 @@ extern crate mongodb;
 extern crate simplelog;
 
 use std::fs::File;
-use std::io::Bytes;
 
-use actix_web::{App, error, HttpResponse, HttpServer, web};
+use actix_web::{App, HttpServer, web};
+use actix_web::dev::Service;
+use actix_web::http::{HeaderName, HeaderValue};
+use actix_web::http::header::WARNING;
 use actix_web::middleware::Logger;
 use simplelog::{CombinedLogger, Config, LevelFilter, TerminalMode, TermLogger, WriteLogger};
 
+use crate::admin_routes::StatsCache;
+use crate::auth::ApiTokens;
+use crate::body_size::BodySizeMetrics;
+use crate::classification::ClassificationAllowlist;
 use crate::dao::Dao;
+use crate::discovery_routes::API_BASE_PATH;
+use crate::features::FeatureFlags;
+use crate::health_routes::HealthRoutes;
+use crate::field_encryption::FieldEncryption;
+use crate::import::page_fetch::FetchPolicy;
+use crate::list_response::CountSettings;
+use crate::quick_recipes::QuickRecipes;
+use crate::query_budget::QueryBudget;
+use crate::read_preference::ReadPreferenceSettings;
+use crate::recipe_defaults::RecipeDefaults;
+use crate::recipe_limits::RecipeLimits;
+use crate::title_constraint::TitleConstraint;
+use crate::unit_registry::UnitRegistry;
+use crate::write_concern::WriteConcernSettings;
 mod ssl;
-use crate::recipe_routes::RecipeRoutes;
 
 mod model;
+mod admin_routes;
+mod auth;
+mod backup;
+mod batch_routes;
+mod body_size;
+mod collection_routes;
+mod content_hash;
+mod bulk_import;
+mod circuit_breaker;
+mod classification;
 mod dao;
+mod discovery_routes;
+mod error_body;
+mod export;
+mod features;
+mod field_encryption;
+mod field_mask;
+mod field_modified;
+mod health_routes;
+mod import;
+mod json_error;
+mod json_stream;
+mod list_response;
+mod meal_plan_routes;
 mod pagination;
+mod quick_recipes;
+mod query_budget;
+mod read_preference;
+mod recipe_cache;
+mod recipe_defaults;
+mod recipe_filter;
+mod recipe_limits;
 mod recipe_routes;
+mod request_id;
+mod routes;
+mod single_flight;
+mod slow_query;
+mod slug;
+mod template_routes;
+mod title_constraint;
+mod unit_registry;
+mod unit_routes;
+mod thumbnail;
+mod write_concern;
 
 
 #[actix_rt::main]
@@ -28,44 +88,70 @@ async fn main() -> std::io::Result<()> {
 
     let config = ssl::init();
 
-    let dao = Dao::new().await.unwrap();
+    let field_encryption = FieldEncryption::from_env().expect("Invalid field encryption configuration");
+    let dao = Dao::new(WriteConcernSettings::from_env(), ReadPreferenceSettings::from_env(), field_encryption).await.unwrap();
+    dao.ensure_slug_index().await.ok();
+    dao.ensure_title_index(TitleConstraint::from_env()).await.ok();
+    dao.ensure_created_index().await.ok();
+    dao.ensure_views_collection().await.ok();
+    dao.ensure_ratings_index().await.ok();
+    dao.ensure_text_index().await.ok();
+    dao.backfill_ingredient_counts().await.ok();
+    let api_tokens = web::Data::new(ApiTokens::from_env());
+    let stats_cache = web::Data::new(StatsCache::default());
+    let features = FeatureFlags::from_env();
+    let allowlist = web::Data::new(ClassificationAllowlist::from_env());
+    let recipe_limits = web::Data::new(RecipeLimits::from_env());
+    let recipe_defaults = web::Data::new(RecipeDefaults::from_env());
+    let count_settings = web::Data::new(CountSettings::from_env());
+    let quick_recipes = web::Data::new(QuickRecipes::from_env());
+    let query_budget = web::Data::new(QueryBudget::from_env());
+    let fetch_policy = web::Data::new(FetchPolicy::from_env());
+    let units = web::Data::new(UnitRegistry::load(&dao).await);
+    let body_sizes = web::Data::new(BodySizeMetrics::from_env());
 
     let addr = "127.0.0.1:8080";
 
     println!("Running on: {}", addr);
 
     HttpServer::new(move || {
+        let request_body_sizes = body_sizes.clone();
         App::new()
+            .wrap(routes::normalize_path())
             .wrap(Logger::default())
+            .wrap_fn(move |req, srv| {
+                let id = request_id::from_request(&req);
+                request_body_sizes.observe(&req, &id);
+                let response = request_id::scope(id.clone(), srv.call(req));
+                async move {
+                    let mut response = response.await?;
+                    if let Ok(id) = HeaderValue::from_str(&id) {
+                        response.headers_mut().insert(HeaderName::from_static(request_id::REQUEST_ID_HEADER), id);
+                    }
+                    Ok(response)
+                }
+            })
             .wrap(
                 actix_cors::Cors::new() // <- Construct CORS middleware builder
+                    .expose_headers(vec![WARNING])
                     .max_age(3600)
                     .finish())
             .data(dao.clone())
+            .app_data(api_tokens.clone())
+            .app_data(stats_cache.clone())
+            .app_data(allowlist.clone())
+            .app_data(recipe_limits.clone())
+            .app_data(recipe_defaults.clone())
+            .app_data(count_settings.clone())
+            .app_data(quick_recipes.clone())
+            .app_data(query_budget.clone())
+            .app_data(fetch_policy.clone())
+            .app_data(units.clone())
+            .app_data(body_sizes.clone())
             .data(web::PayloadConfig::new(5 << 20))
-            .app_data(web::JsonConfig::default().limit(5 << 20)
-                .error_handler(|err, req| {
-                    error!("Error={:#?}", err);
-                    error::InternalError::from_response(err, HttpResponse::BadRequest().finish()).into()
-                }))
-            .service(
-                web::scope("/api/v1")
-                    .service(web::resource("/recipes")
-                        .route(web::get().to(RecipeRoutes::get_many_recipes))
-                        .route(web::post().to(RecipeRoutes::add_many_recipes))
-                    )
-                    .service(web::resource("/recipes/{id}")
-                        .route(web::post().to(RecipeRoutes::add_one_recipe))
-                        .route(web::get().to(RecipeRoutes::get_one_recipe_without_image))
-                        .route(web::put().to(RecipeRoutes::update_one_recipe_without_image))
-                        .route(web::delete().to(RecipeRoutes::delete_one_recipe))
-                    )
-                    .service(web::resource("/recipes/{id}/image")
-                        .route(web::get().to(RecipeRoutes::get_one_recipe_image))
-                        .route(web::put().to(RecipeRoutes::update_one_recipe_image))
-                        .route(web::delete().to(RecipeRoutes::delete_one_recipe_image))
-                    )
-            )
+            .app_data(json_error::json_config())
+            .route("/ready", web::get().to(HealthRoutes::ready))
+            .service(web::scope(API_BASE_PATH).configure(|cfg| routes::configure(cfg, &features)))
     }).bind_rustls(addr, config)?.run().await
 
     // }).bind(addr, config)?.run().await
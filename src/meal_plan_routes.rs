@@ -0,0 +1,83 @@
+use actix_web::{Either, HttpRequest, HttpResponse, Responder, web};
+
+use crate::dao::Dao;
+use crate::recipe_routes::{dao_error_response, extract_id_from_req};
+
+pub struct MealPlanRoutes {}
+
+impl MealPlanRoutes {
+    /// cooking time of the planned recipes per day and for the whole plan
+    pub async fn get_workload(req: HttpRequest, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        let id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return Either::B(HttpResponse::BadRequest().finish())
+        };
+
+        let plan = match database.get_meal_plan(id).await {
+            Ok(plan) => plan,
+            Err(err) => return Either::B(dao_error_response(err)),
+        };
+        match database.get_cooking_times(plan.recipe_ids()).await {
+            Ok(cooking_times) => Either::A(HttpResponse::Ok().json(plan.workload(&cooking_times))),
+            Err(err) => Either::B(dao_error_response(err)),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{App, test, web};
+    use actix_web::http::StatusCode;
+    use bson::oid::ObjectId;
+    use serde_json::{json, Value};
+    use serial_test::serial;
+
+    use crate::dao::dao_tests::{before, cleanup_after, create_many_recipes_without_images};
+    use crate::meal_plan_routes::MealPlanRoutes;
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_workload() {
+        let dao = before().await;
+        let mut recipes = create_many_recipes_without_images(2);
+        recipes[0].cooking_time_in_minutes = 20;
+        recipes[1].cooking_time_in_minutes = 45;
+        let ids = dao.add_many_recipes(recipes).await.unwrap()
+            .as_array().unwrap().iter()
+            .map(|id| id.as_object_id().unwrap().clone())
+            .collect::<Vec<ObjectId>>();
+        let (plan_id, missing) = (ObjectId::new(), ObjectId::new());
+        dao.database.collection("meal_plans").insert_one(doc! {
+            "_id": plan_id.clone(),
+            "days": [
+                { "date": "2020-10-05", "recipeIds": [ids[0].clone(), ids[1].clone()] },
+                { "date": "2020-10-06", "recipeIds": [ids[0].clone(), missing.clone()] },
+            ],
+        }, None).await.unwrap();
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/meal-plans/{id}/workload", web::get().to(MealPlanRoutes::get_workload))).await;
+
+        let req = test::TestRequest::get().uri(&format!("/meal-plans/{}/workload", plan_id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body, json!({
+            "mealPlanId": plan_id.to_hex(),
+            "days": [
+                { "date": "2020-10-05", "recipes": 2, "cookingTimeInMinutes": 65 },
+                { "date": "2020-10-06", "recipes": 1, "cookingTimeInMinutes": 20 },
+            ],
+            "totalCookingTimeInMinutes": 85,
+            "notes": [format!("Recipe {} planned for 2020-10-06 does not exist, it was skipped", missing)],
+        }));
+
+        let req = test::TestRequest::get().uri(&format!("/meal-plans/{}/workload", ObjectId::new())).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        cleanup_after(dao).await;
+    }
+}
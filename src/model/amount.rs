@@ -0,0 +1,99 @@
+use bson::Bson;
+use serde::Serializer;
+
+/// decimal places kept when amounts are scaled
+const AMOUNT_PRECISION: f64 = 100.0;
+const MAX_EXACT_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// Serializes whole amounts as integers, `200.0` becomes `200` while `0.5` stays `0.5`
+pub fn serialize_amount<S>(amount: &f64, ser: S) -> Result<S::Ok, S::Error> where S: Serializer {
+    if amount.fract() == 0.0 && amount.abs() <= MAX_EXACT_INTEGER {
+        ser.serialize_i64(*amount as i64)
+    } else {
+        ser.serialize_f64(*amount)
+    }
+}
+
+/// Stores whole amounts as int32 like documents written before amounts became floats,
+/// so re-saving an unchanged recipe does not rewrite its amounts
+pub fn amount_to_bson(amount: f64) -> Bson {
+    if amount.fract() == 0.0 && amount >= i32::MIN as f64 && amount <= i32::MAX as f64 {
+        Bson::Int32(amount as i32)
+    } else {
+        Bson::Double(amount)
+    }
+}
+
+/// reads amounts stored as int32, int64 or double
+pub fn amount_from_bson(bson: Option<&Bson>) -> Option<f64> {
+    match bson {
+        Some(Bson::Double(amount)) => Some(*amount),
+        Some(Bson::Int32(amount)) => Some(*amount as f64),
+        Some(Bson::Int64(amount)) => Some(*amount as f64),
+        _ => None
+    }
+}
+
+/// rounds scaled amounts, so `0.1 * 3` does not end up as `0.30000000000000004`
+pub fn round_amount(amount: f64) -> f64 {
+    (amount * AMOUNT_PRECISION).round() / AMOUNT_PRECISION
+}
+
+
+#[cfg(test)]
+mod amount_tests {
+    use bson::Bson;
+    use serde::Serialize;
+
+    use crate::model::amount::{amount_from_bson, amount_to_bson, round_amount, serialize_amount};
+
+    #[derive(Serialize)]
+    struct Amount(#[serde(serialize_with = "serialize_amount")] f64);
+
+    #[test]
+    fn serialize_whole_amount_without_fraction() {
+        assert_eq!(serde_json::to_string(&Amount(200.0)).unwrap(), "200");
+        assert_eq!(serde_json::to_string(&Amount(-3.0)).unwrap(), "-3");
+    }
+
+    #[test]
+    fn serialize_fractional_amount() {
+        assert_eq!(serde_json::to_string(&Amount(0.5)).unwrap(), "0.5");
+        assert_eq!(serde_json::to_string(&Amount(1.5)).unwrap(), "1.5");
+    }
+
+    #[test]
+    fn amount_from_bson_test() {
+        assert_eq!(amount_from_bson(Some(&Bson::Int32(200))), Some(200.0));
+        assert_eq!(amount_from_bson(Some(&Bson::Int64(200))), Some(200.0));
+        assert_eq!(amount_from_bson(Some(&Bson::Double(0.5))), Some(0.5));
+        assert_eq!(amount_from_bson(Some(&Bson::String("0.5".to_string()))), None);
+        assert_eq!(amount_from_bson(None), None);
+    }
+
+    #[test]
+    fn amount_to_bson_test() {
+        assert_eq!(amount_to_bson(200.0), Bson::Int32(200));
+        assert_eq!(amount_to_bson(200.5), Bson::Double(200.5));
+        assert_eq!(amount_to_bson(1e12), Bson::Double(1e12));
+    }
+
+    #[test]
+    fn amount_round_trip() {
+        for amount in [200.0, 200.5].iter() {
+            let json = serde_json::to_string(&Amount(*amount)).unwrap();
+            let parsed: f64 = serde_json::from_str(&json).unwrap();
+            assert_eq!(amount_from_bson(Some(&amount_to_bson(parsed))), Some(*amount));
+            assert_eq!(serde_json::to_string(&Amount(parsed)).unwrap(), json);
+        }
+        assert_eq!(serde_json::to_string(&Amount(200.0)).unwrap(), "200");
+        assert_eq!(serde_json::to_string(&Amount(200.5)).unwrap(), "200.5");
+    }
+
+    #[test]
+    fn round_amount_test() {
+        assert_eq!(round_amount(0.1 * 3.0), 0.3);
+        assert_eq!(round_amount(2.0 / 3.0), 0.67);
+        assert_eq!(round_amount(1.5), 1.5);
+    }
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+use crate::recipe_filter::RecipeFilter;
+
+/// Recipes to add to a collection, either listed by id or selected by a filter
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct AddManyRequest {
+    #[serde(rename = "recipeIds")]
+    pub recipe_ids: Option<Vec<String>>,
+    pub filter: Option<RecipeFilter>,
+}
+
+/// `skipped` counts duplicates, recipes already in the collection and unknown recipes
+#[derive(Serialize, Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct AddManyResult {
+    pub added: usize,
+    pub skipped: usize,
+}
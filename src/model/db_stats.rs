@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+
+use bson::{Bson, Document};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::dao::DaoError;
+use crate::body_size::BodySizeStats;
+use crate::recipe_cache::RecipeCacheStats;
+
+/// Database health for ops dashboards. Parts the database user may not read are None
+/// and explained in `notes`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DbStats {
+    pub collection: Option<CollectionStats>,
+    pub server: Option<ServerStatus>,
+    pub notes: Vec<String>,
+    /// hits and misses of the recipe cache since startup, left out while the cache is off
+    #[serde(rename = "recipeCache", skip_serializing_if = "Option::is_none")]
+    pub recipe_cache: Option<RecipeCacheStats>,
+    /// sizes of the JSON request bodies since startup, left out when not recorded
+    #[serde(rename = "requestBodies", skip_serializing_if = "Option::is_none")]
+    pub request_bodies: Option<BodySizeStats>,
+    #[serde(rename = "generatedAt")]
+    pub generated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CollectionStats {
+    pub count: i64,
+    pub size: i64,
+    #[serde(rename = "avgObjSize")]
+    pub avg_obj_size: f64,
+    #[serde(rename = "storageSize")]
+    pub storage_size: i64,
+    #[serde(rename = "totalIndexSize")]
+    pub total_index_size: i64,
+    pub indexes: Vec<IndexStats>,
+}
+
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct IndexStats {
+    pub name: String,
+    pub size: i64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ServerStatus {
+    pub version: String,
+    #[serde(rename = "uptimeSeconds")]
+    pub uptime_seconds: i64,
+    #[serde(rename = "currentConnections")]
+    pub current_connections: i64,
+    #[serde(rename = "availableConnections")]
+    pub available_connections: i64,
+    pub opcounters: BTreeMap<String, i64>,
+}
+
+impl DbStats {
+    /// combines the results of `collStats` and `serverStatus`, failed commands become notes
+    pub fn collect(collection: Result<Document, DaoError>, server: Result<Document, DaoError>) -> Self {
+        let mut notes = Vec::new();
+        let collection = collection
+            .map_err(|err| notes.push(note("collStats", err)))
+            .ok()
+            .map(|doc| CollectionStats::from(&doc));
+        let server = server
+            .map_err(|err| notes.push(note("serverStatus", err)))
+            .ok()
+            .map(|doc| ServerStatus::from(&doc));
+        Self { collection, server, notes, recipe_cache: None, request_bodies: None, generated_at: Utc::now() }
+    }
+}
+
+fn note(command: &str, error: DaoError) -> String {
+    match error {
+        DaoError::MissingPrivileges => format!("The database user lacks the privileges to run {}", command),
+        err => format!("Could not run {}. Err={:?}", command, err),
+    }
+}
+
+impl From<&Document> for CollectionStats {
+    fn from(doc: &Document) -> Self {
+        let indexes = doc.get_document("indexSizes")
+            .map(|sizes| sizes.iter()
+                .map(|(name, size)| IndexStats { name: name.to_string(), size: as_i64(Some(size)) })
+                .collect())
+            .unwrap_or_default();
+        Self {
+            count: as_i64(doc.get("count")),
+            size: as_i64(doc.get("size")),
+            avg_obj_size: as_f64(doc.get("avgObjSize")),
+            storage_size: as_i64(doc.get("storageSize")),
+            total_index_size: as_i64(doc.get("totalIndexSize")),
+            indexes,
+        }
+    }
+}
+
+impl From<&Document> for ServerStatus {
+    fn from(doc: &Document) -> Self {
+        let connections = doc.get_document("connections").ok();
+        let opcounters = doc.get_document("opcounters")
+            .map(|counters| counters.iter()
+                .map(|(name, count)| (name.to_string(), as_i64(Some(count))))
+                .collect())
+            .unwrap_or_default();
+        Self {
+            version: doc.get_str("version").unwrap_or_default().to_string(),
+            uptime_seconds: as_i64(doc.get("uptime")),
+            current_connections: as_i64(connections.and_then(|c| c.get("current"))),
+            available_connections: as_i64(connections.and_then(|c| c.get("available"))),
+            opcounters,
+        }
+    }
+}
+
+/// mongo reports numbers as int32, int64 or double depending on their size
+fn as_i64(value: Option<&Bson>) -> i64 {
+    match value {
+        Some(Bson::Int32(value)) => *value as i64,
+        Some(Bson::Int64(value)) => *value,
+        Some(Bson::Double(value)) => *value as i64,
+        _ => 0
+    }
+}
+
+fn as_f64(value: Option<&Bson>) -> f64 {
+    match value {
+        Some(Bson::Int32(value)) => *value as f64,
+        Some(Bson::Int64(value)) => *value as f64,
+        Some(Bson::Double(value)) => *value,
+        _ => 0.0
+    }
+}
+
+
+#[cfg(test)]
+mod db_stats_tests {
+    use crate::dao::DaoError;
+    use crate::model::db_stats::{CollectionStats, DbStats, IndexStats, ServerStatus};
+
+    #[test]
+    fn collection_stats_from_document() {
+        let stats = CollectionStats::from(&doc! {
+            "count": 3,
+            "size": 3000_i64,
+            "avgObjSize": 1000,
+            "storageSize": 4096.0,
+            "totalIndexSize": 8192,
+            "indexSizes": { "_id_": 4096, "title_1": 4096_i64 }
+        });
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.size, 3000);
+        assert_eq!(stats.avg_obj_size, 1000.0);
+        assert_eq!(stats.storage_size, 4096);
+        assert_eq!(stats.indexes, vec![
+            IndexStats { name: "_id_".to_string(), size: 4096 },
+            IndexStats { name: "title_1".to_string(), size: 4096 }]);
+    }
+
+    #[test]
+    fn server_status_from_document() {
+        let status = ServerStatus::from(&doc! {
+            "version": "4.4.1",
+            "uptime": 120.5,
+            "connections": { "current": 4, "available": 800 },
+            "opcounters": { "insert": 1, "query": 2_i64 }
+        });
+        assert_eq!(status.version, "4.4.1");
+        assert_eq!(status.uptime_seconds, 120);
+        assert_eq!(status.current_connections, 4);
+        assert_eq!(status.available_connections, 800);
+        assert_eq!(status.opcounters.get("query"), Some(&2));
+    }
+
+    #[test]
+    fn partial_stats_without_privileges() {
+        let stats = DbStats::collect(Ok(doc! { "count": 1 }), Err(DaoError::MissingPrivileges));
+        assert_eq!(stats.collection.unwrap().count, 1);
+        assert_eq!(stats.server, None);
+        assert_eq!(stats.notes, vec!["The database user lacks the privileges to run serverStatus".to_string()]);
+    }
+}
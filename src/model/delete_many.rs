@@ -0,0 +1,51 @@
+use actix_web::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+
+use crate::recipe_filter::RecipeFilter;
+
+/// header repeating the number of recipes a bulk delete removes
+pub const CONFIRM_DELETE_HEADER: &str = "x-confirm-delete";
+
+/// Recipes to delete, either listed by id or selected by a filter
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct DeleteManyRequest {
+    #[serde(rename = "recipeIds")]
+    pub recipe_ids: Option<Vec<String>>,
+    pub filter: Option<RecipeFilter>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct DeleteManyResult {
+    pub deleted: u64,
+}
+
+/// true when the confirmation header names exactly the amount of recipes to delete
+pub fn is_confirmed(headers: &HeaderMap, count: u64) -> bool {
+    headers.get(CONFIRM_DELETE_HEADER)
+        .and_then(|confirmed| confirmed.to_str().ok())
+        .and_then(|confirmed| confirmed.trim().parse::<u64>().ok())
+        == Some(count)
+}
+
+
+#[cfg(test)]
+mod delete_many_tests {
+    use actix_web::http::{HeaderMap, HeaderName, HeaderValue};
+
+    use crate::model::delete_many::{CONFIRM_DELETE_HEADER, is_confirmed};
+
+    fn headers(confirmation: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(HeaderName::from_static(CONFIRM_DELETE_HEADER), HeaderValue::from_str(confirmation).unwrap());
+        headers
+    }
+
+    #[test]
+    fn confirmation_matches_count() {
+        assert_eq!(is_confirmed(&headers("3"), 3), true);
+        assert_eq!(is_confirmed(&headers(" 0 "), 0), true);
+        assert_eq!(is_confirmed(&headers("2"), 3), false);
+        assert_eq!(is_confirmed(&headers("all"), 3), false);
+        assert_eq!(is_confirmed(&HeaderMap::new(), 0), false);
+    }
+}
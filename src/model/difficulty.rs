@@ -8,13 +8,39 @@ use serde::Serialize;
 
 use crate::model::recipe::RecipeFormatError;
 
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+/// hard recipes cooked in fewer minutes are most likely a data entry mistake
+pub const HARD_MIN_COOKING_MINUTES: u32 = 10;
+/// easy recipes cooking for more minutes are most likely a data entry mistake
+pub const EASY_MAX_COOKING_MINUTES: u32 = 240;
+
+/// ordered from easiest to hardest, the declaration order is the ordinal
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
 pub enum Difficulty {
     Easy,
     Medium,
     Hard,
 }
 
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    /// all difficulties at or below this one
+    pub fn at_most(&self) -> Vec<Difficulty> {
+        Difficulty::ALL.iter().filter(|difficulty| *difficulty <= self).cloned().collect()
+    }
+
+    /// a warning when the cooking time is implausible for the difficulty, the recipe stays valid
+    pub fn cooking_time_warning(&self, cooking_time_in_minutes: u32) -> Option<String> {
+        let implausible = match self {
+            Difficulty::Easy => cooking_time_in_minutes > EASY_MAX_COOKING_MINUTES,
+            Difficulty::Medium => false,
+            Difficulty::Hard => cooking_time_in_minutes < HARD_MIN_COOKING_MINUTES,
+        };
+        Some(format!("Difficulty {} is unusual for a cooking time of {} minutes", self, cooking_time_in_minutes))
+            .filter(|_| implausible)
+    }
+}
+
 impl fmt::Display for Difficulty {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
         write!(f, "{:?}", self)
@@ -46,7 +72,7 @@ mod difficulty_tests {
 
     use bson::Bson;
 
-    use crate::model::difficulty::Difficulty;
+    use crate::model::difficulty::{Difficulty, EASY_MAX_COOKING_MINUTES, HARD_MIN_COOKING_MINUTES};
 
     #[test]
     fn from_string_to_difficulty_test() {
@@ -55,6 +81,23 @@ mod difficulty_tests {
         assert_eq!(Difficulty::try_from("Hard").unwrap(), Difficulty::Hard);
     }
 
+    #[test]
+    fn difficulty_at_most_test() {
+        assert_eq!(Difficulty::Easy.at_most(), vec![Difficulty::Easy]);
+        assert_eq!(Difficulty::Medium.at_most(), vec![Difficulty::Easy, Difficulty::Medium]);
+        assert_eq!(Difficulty::Hard.at_most(), Difficulty::ALL.to_vec());
+    }
+
+    #[test]
+    fn cooking_time_warning_test() {
+        assert_eq!(Difficulty::Hard.cooking_time_warning(2), Some("Difficulty Hard is unusual for a cooking time of 2 minutes".to_string()));
+        assert_eq!(Difficulty::Easy.cooking_time_warning(300).is_some(), true);
+        assert_eq!(Difficulty::Hard.cooking_time_warning(HARD_MIN_COOKING_MINUTES), None);
+        assert_eq!(Difficulty::Easy.cooking_time_warning(EASY_MAX_COOKING_MINUTES), None);
+        assert_eq!(Difficulty::Medium.cooking_time_warning(0), None);
+        assert_eq!(Difficulty::Medium.cooking_time_warning(1000), None);
+    }
+
     #[test]
     fn from_difficulty_to_string_test() {
         assert_eq!(Bson::from(Difficulty::Easy), Bson::String("Easy".to_string()));
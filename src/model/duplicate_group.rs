@@ -0,0 +1,86 @@
+use std::convert::TryFrom;
+
+use bson::{Bson, Document};
+use serde::Serialize;
+
+use crate::model::recipe::{Recipe, RecipeFormatError};
+
+const JSON_ATTR_KEY: &str = "_id";
+const JSON_ATTR_COUNT: &str = "count";
+const JSON_ATTR_RECIPES: &str = "recipes";
+
+/// Recipes sharing their dedup signature: the lowercase trimmed title and the set of lowercase trimmed ingredient titles
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DuplicateGroup {
+    pub title: String,
+    pub ingredients: Vec<String>,
+    pub count: u32,
+    pub recipes: Vec<DuplicateRecipe>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DuplicateRecipe {
+    pub id: String,
+    pub title: String,
+}
+
+impl TryFrom<Document> for DuplicateGroup {
+    type Error = RecipeFormatError;
+
+    fn try_from(doc: Document) -> Result<Self, Self::Error> {
+        let key = doc.get_document(JSON_ATTR_KEY)
+            .map_err(|_| RecipeFormatError::from("Error getting signature from duplicate group document"))?;
+        let ingredients = key.get_array("ingredients")
+            .map_err(|_| RecipeFormatError::from("Error getting ingredients from duplicate group document"))?
+            .iter()
+            .filter_map(|ingredient| ingredient.as_str().map(String::from))
+            .collect();
+        let recipes = doc.get_array(JSON_ATTR_RECIPES)
+            .map_err(|_| RecipeFormatError::from("Error getting recipes from duplicate group document"))?
+            .iter()
+            .map(|recipe| match recipe {
+                Bson::Document(recipe) => Ok(DuplicateRecipe {
+                    id: Recipe::extract_id(recipe)?.to_hex(),
+                    title: Recipe::extract_title(recipe)?,
+                }),
+                _ => Err(RecipeFormatError::from("Error getting recipe from duplicate group document")),
+            })
+            .collect::<Result<Vec<DuplicateRecipe>, RecipeFormatError>>()?;
+
+        Ok(DuplicateGroup {
+            title: key.get_str("title").unwrap_or_default().to_string(),
+            ingredients,
+            count: doc.get_i32(JSON_ATTR_COUNT)
+                .map(|x| if x < 0 { 0 } else { x as u32 })
+                .map_err(|_| RecipeFormatError::from("Error getting count from duplicate group document"))?,
+            recipes,
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod duplicate_group_tests {
+    use std::convert::TryFrom;
+
+    use bson::oid::ObjectId;
+
+    use crate::model::duplicate_group::DuplicateGroup;
+
+    #[test]
+    fn duplicate_group_from_document() {
+        let (first, second) = (ObjectId::new(), ObjectId::new());
+        let group = DuplicateGroup::try_from(doc! {
+            "_id": { "title": "pancakes", "ingredients": ["egg", "flour", "milk"] },
+            "count": 2,
+            "recipes": [{ "_id": first.clone(), "title": "Pancakes" }, { "_id": second.clone(), "title": " pancakes" }],
+        }).unwrap();
+        assert_eq!(group.title, "pancakes");
+        assert_eq!(group.ingredients, vec!["egg", "flour", "milk"]);
+        assert_eq!(group.count, 2);
+        assert_eq!(group.recipes[0].id, first.to_hex());
+        assert_eq!(group.recipes[1].title, " pancakes");
+
+        assert_eq!(DuplicateGroup::try_from(doc! { "_id": "pancakes", "count": 2, "recipes": [] }).is_err(), true);
+    }
+}
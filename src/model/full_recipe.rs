@@ -0,0 +1,138 @@
+use std::convert::TryFrom;
+
+use bson::{Bson, Document};
+use serde::Serialize;
+
+use crate::model::recipe::{Recipe, RecipeFormatError};
+
+const JSON_ATTR_COLLECTIONS: &str = "collections";
+const JSON_ATTR_AVERAGE_RATING: &str = "averageRating";
+const JSON_ATTR_COMMENT_COUNT: &str = "commentCount";
+const JSON_ATTR_COLLECTION_ID: &str = "id";
+const JSON_ATTR_COLLECTION_NAME: &str = "name";
+
+/// Recipe detail enriched with the data of the surrounding features,
+/// assembled by a single aggregation in the dao.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FullRecipe {
+    #[serde(flatten)]
+    pub recipe: Recipe,
+    pub collections: Vec<CollectionReference>,
+    #[serde(rename = "averageRating")]
+    pub average_rating: Option<f64>,
+    #[serde(rename = "commentCount")]
+    pub comment_count: u32,
+    /// false when the recipe has neither servings nor a yield
+    pub scalable: bool,
+}
+
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct CollectionReference {
+    pub id: String,
+    pub name: String,
+}
+
+impl TryFrom<Document> for FullRecipe {
+    type Error = RecipeFormatError;
+
+    fn try_from(doc: Document) -> Result<Self, Self::Error> {
+        let collections = FullRecipe::extract_collections(&doc)?;
+        let average_rating = FullRecipe::extract_average_rating(&doc)?;
+        let comment_count = FullRecipe::extract_comment_count(&doc)?;
+        let recipe = Recipe::try_from(doc)?;
+        return Ok(FullRecipe {
+            scalable: recipe.is_scalable(),
+            recipe,
+            collections,
+            average_rating,
+            comment_count,
+        });
+    }
+}
+
+impl FullRecipe {
+    fn extract_collections(doc: &Document) -> Result<Vec<CollectionReference>, RecipeFormatError> {
+        doc.get_array(JSON_ATTR_COLLECTIONS)
+            .map_err(|_| RecipeFormatError::from("Error getting collections from document"))?
+            .iter()
+            .map(|collection| collection.as_document()
+                .and_then(|collection| Some(CollectionReference {
+                    id: collection.get_object_id(JSON_ATTR_COLLECTION_ID).ok()?.to_hex(),
+                    name: collection.get_str(JSON_ATTR_COLLECTION_NAME).unwrap_or_default().to_string(),
+                }))
+                .ok_or_else(|| RecipeFormatError::from("Error getting collection from document")))
+            .collect()
+    }
+
+    /// returns None when the recipe has not been rated yet
+    fn extract_average_rating(doc: &Document) -> Result<Option<f64>, RecipeFormatError> {
+        match doc.get(JSON_ATTR_AVERAGE_RATING) {
+            Some(Bson::Double(rating)) => Ok(Some(*rating)),
+            Some(Bson::Int32(rating)) => Ok(Some(*rating as f64)),
+            Some(Bson::Int64(rating)) => Ok(Some(*rating as f64)),
+            Some(Bson::Null) | None => Ok(None),
+            _ => Err(RecipeFormatError::from("Error getting average rating from document"))
+        }
+    }
+
+    fn extract_comment_count(doc: &Document) -> Result<u32, RecipeFormatError> {
+        doc.get_i32(JSON_ATTR_COMMENT_COUNT)
+            .map(|x| if x < 0 { 0 } else { x as u32 })
+            .map_err(|_| RecipeFormatError::from("Error getting comment count from document"))
+    }
+}
+
+
+#[cfg(test)]
+mod full_recipe_tests {
+    use std::convert::TryFrom;
+
+    use bson::{Bson, Document};
+    use bson::oid::ObjectId;
+
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+    use crate::model::full_recipe::{FullRecipe, JSON_ATTR_AVERAGE_RATING, JSON_ATTR_COLLECTIONS, JSON_ATTR_COMMENT_COUNT};
+
+    fn create_full_recipe_doc() -> Document {
+        let recipe = create_one_recipe_without_image();
+        let mut doc = Document::from(recipe.clone());
+        doc.insert("_id", recipe._id);
+        doc.insert(JSON_ATTR_COLLECTIONS, vec![Bson::Document(doc! {"id": ObjectId::new(), "name": "Favourites"})]);
+        doc.insert(JSON_ATTR_AVERAGE_RATING, 4.5);
+        doc.insert(JSON_ATTR_COMMENT_COUNT, 3);
+        doc
+    }
+
+    #[test]
+    fn full_recipe_from_document() {
+        let result = FullRecipe::try_from(create_full_recipe_doc());
+        assert_eq!(result.is_ok(), true, "{}", result.err().unwrap().error);
+
+        let full_recipe = result.unwrap();
+        assert_eq!(full_recipe.collections.len(), 1);
+        assert_eq!(full_recipe.collections[0].name, "Favourites");
+        assert_eq!(full_recipe.average_rating, Some(4.5));
+        assert_eq!(full_recipe.comment_count, 3);
+        assert_eq!(full_recipe.scalable, true);
+    }
+
+    #[test]
+    fn full_recipe_from_document_without_ratings() {
+        let mut doc = create_full_recipe_doc();
+        doc.insert(JSON_ATTR_AVERAGE_RATING, Bson::Null);
+        doc.insert(JSON_ATTR_COLLECTIONS, Vec::<Bson>::new());
+        doc.insert(JSON_ATTR_COMMENT_COUNT, 0);
+
+        let full_recipe = FullRecipe::try_from(doc).unwrap();
+        assert_eq!(full_recipe.collections.is_empty(), true);
+        assert_eq!(full_recipe.average_rating, None);
+        assert_eq!(full_recipe.comment_count, 0);
+    }
+
+    #[test]
+    fn full_recipe_from_document_without_collections_fails() {
+        let mut doc = create_full_recipe_doc();
+        doc.remove(JSON_ATTR_COLLECTIONS);
+        assert_eq!(FullRecipe::try_from(doc).is_err(), true);
+    }
+}
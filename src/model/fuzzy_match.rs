@@ -0,0 +1,109 @@
+use serde::Serialize;
+
+use crate::model::recipe_summary::RecipeSummary;
+
+/// Recipe summary with the edit distance of its title to a fuzzy search query
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FuzzyMatch {
+    #[serde(flatten)]
+    pub recipe: RecipeSummary,
+    pub distance: usize,
+}
+
+/// Ranks the candidates by the edit distance of their titles, read by `title`, to the query,
+/// closest first, and leaves out titles further away than `max_distance` of the query. Equal
+/// distances keep the order of the candidates.
+///
+/// Every candidate title is compared, each comparison takes time proportional to the length of
+/// the query times the length of the title, so a search reads and compares all recipes passing
+/// the other filters: fine for some thousand recipes, linearly slower beyond.
+pub fn rank_by_distance<T>(query: &str, candidates: Vec<T>, title: impl Fn(&T) -> &str) -> Vec<(T, usize)> {
+    let query = query.split_whitespace().collect::<Vec<&str>>().join(" ").to_lowercase();
+    let max_distance = max_distance(&query);
+    let mut matches = candidates.into_iter()
+        .map(|candidate| { let distance = title_distance(&query, title(&candidate)); (candidate, distance) })
+        .filter(|(_, distance)| *distance <= max_distance)
+        .collect::<Vec<(T, usize)>>();
+    matches.sort_by_key(|(_, distance)| *distance);
+    matches
+}
+
+/// a typo every four characters, at least one
+pub fn max_distance(query: &str) -> usize {
+    (query.chars().count() / 4).max(1)
+}
+
+/// Distance of the lowercase query to the closest run of as many consecutive title words as the
+/// query has, so "spagetti" matches "Spaghetti Bolognese" with a distance of one
+pub fn title_distance(query: &str, title: &str) -> usize {
+    let title = title.to_lowercase();
+    let title_words = title.split_whitespace().collect::<Vec<&str>>();
+    let query_words = query.split_whitespace().count().max(1);
+    if title_words.len() <= query_words {
+        return levenshtein(query, &title_words.join(" "));
+    }
+    title_words.windows(query_words)
+        .map(|words| levenshtein(query, &words.join(" ")))
+        .min()
+        .unwrap_or_else(|| levenshtein(query, &title))
+}
+
+/// number of inserted, removed or replaced characters turning one text into the other
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut previous = (0..=b.len()).collect::<Vec<usize>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let replaced = previous[j] + if a_char == *b_char { 0 } else { 1 };
+            current[j + 1] = replaced.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+
+#[cfg(test)]
+mod fuzzy_match_tests {
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+    use crate::model::fuzzy_match::{levenshtein, max_distance, rank_by_distance, title_distance};
+    use crate::model::recipe_summary::RecipeSummary;
+
+    fn summary(title: &str) -> RecipeSummary {
+        let mut recipe = create_one_recipe_without_image();
+        recipe.title = title.to_string();
+        RecipeSummary::from(recipe)
+    }
+
+    #[test]
+    fn levenshtein_test() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("pasta", "pasta"), 0);
+        assert_eq!(levenshtein("spagetti", "spaghetti"), 1);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("käse", "kase"), 1);
+    }
+
+    #[test]
+    fn title_distance_test() {
+        assert_eq!(title_distance("spagetti", "Spaghetti Bolognese"), 1);
+        assert_eq!(title_distance("spagetti bolognese", "Spaghetti Bolognese"), 1);
+        assert_eq!(title_distance("pasta", "Pesto"), 2);
+        assert_eq!(title_distance("bolognese sauce", "Bolognese"), 6);
+        assert_eq!(max_distance("pie"), 1);
+        assert_eq!(max_distance("spagetti"), 2);
+    }
+
+    #[test]
+    fn rank_by_distance_test() {
+        let candidates = vec![summary("Pancakes"), summary("Spaghetti Carbonara"), summary("Spätzle"), summary("Spaghetto")];
+
+        let ranked = rank_by_distance(" Spagetti ", candidates, |recipe| recipe.title.as_str());
+        let titles = ranked.iter().map(|(recipe, _)| recipe.title.as_str()).collect::<Vec<&str>>();
+        assert_eq!(titles, vec!["Spaghetti Carbonara", "Spaghetto"]);
+        assert_eq!(ranked[0].1, 1);
+        assert_eq!(ranked[1].1, 2);
+    }
+}
@@ -0,0 +1,123 @@
+use std::convert::TryFrom;
+
+use bson::{Bson, Document};
+use bson::oid::ObjectId;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::model::ingredients::{Ingredient, validate_ingredient_ids};
+use crate::model::recipe::RecipeFormatError;
+
+const JSON_ATTR_ID: &str = "_id";
+const JSON_ATTR_NAME: &str = "name";
+const JSON_ATTR_INGREDIENTS: &str = "ingredients";
+
+/// Named group of ingredients shared by recipes, e.g. a basic pie crust.
+/// Recipes reference templates by id and get their ingredients appended on expansion
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IngredientTemplate {
+    #[serde(skip_deserializing)]
+    #[serde(rename = "id")]
+    #[serde(serialize_with = "serialize_object_id")]
+    pub _id: ObjectId,
+    pub name: String,
+    pub ingredients: Vec<Ingredient>,
+}
+
+fn serialize_object_id<S>(oid: &ObjectId, ser: S) -> Result<S::Ok, S::Error> where S: Serializer {
+    oid.to_string().serialize(ser)
+}
+
+impl TryFrom<Document> for IngredientTemplate {
+    type Error = RecipeFormatError;
+
+    fn try_from(doc: Document) -> Result<Self, Self::Error> {
+        Ok(Self {
+            _id: doc.get_object_id(JSON_ATTR_ID)
+                .map(|id| id.to_owned())
+                .map_err(|_| RecipeFormatError::from("Error getting id from template document"))?,
+            name: doc.get_str(JSON_ATTR_NAME)
+                .map(String::from)
+                .map_err(|_| RecipeFormatError::from("Error getting name from template document"))?,
+            ingredients: doc.get_array(JSON_ATTR_INGREDIENTS)
+                .map_err(|_| RecipeFormatError::from("Error getting ingredients from template document"))?
+                .iter()
+                .map(|ingredient| Ingredient::try_from(ingredient.clone()))
+                .collect::<Result<Vec<Ingredient>, RecipeFormatError>>()?,
+        })
+    }
+}
+
+impl From<IngredientTemplate> for Document {
+    fn from(template: IngredientTemplate) -> Self {
+        let mut doc = Document::new();
+        doc.insert(JSON_ATTR_NAME, template.name);
+        doc.insert(JSON_ATTR_INGREDIENTS, template.ingredients.into_iter().map(Bson::from).collect::<Vec<Bson>>());
+        doc
+    }
+}
+
+impl IngredientTemplate {
+    pub fn validate(&self) -> Result<(), RecipeFormatError> {
+        if self.name.trim().is_empty() {
+            return Err(RecipeFormatError::from("The template name must not be empty"));
+        }
+        if self.ingredients.is_empty() {
+            return Err(RecipeFormatError::from("A template needs at least one ingredient"));
+        }
+        validate_ingredient_ids(&self.ingredients)
+    }
+
+    /// the template ingredients with ids prefixed by the template id, so they stay unique inside a recipe
+    pub fn expanded_ingredients(&self) -> Vec<Ingredient> {
+        self.ingredients.iter()
+            .map(|ingredient| Ingredient { id: format!("{}-{}", self._id, ingredient.id), ..ingredient.clone() })
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod ingredient_template_tests {
+    use std::convert::TryFrom;
+
+    use bson::Document;
+    use bson::oid::ObjectId;
+
+    use crate::model::ingredient_template::IngredientTemplate;
+    use crate::model::ingredients::Ingredient;
+    use crate::model::measurement_unit::MeasurementUnit;
+
+    fn create_pie_crust() -> IngredientTemplate {
+        IngredientTemplate {
+            _id: ObjectId::new(),
+            name: "Basic pie crust".to_string(),
+            ingredients: vec![
+                Ingredient::new("0", 250.0, "Flour", MeasurementUnit::Gramm),
+                Ingredient::new("1", 125.0, "Butter", MeasurementUnit::Gramm),
+            ],
+        }
+    }
+
+    #[test]
+    fn template_document_round_trip() {
+        let template = create_pie_crust();
+        let mut doc = Document::from(template.clone());
+        doc.insert("_id", template._id.clone());
+        assert_eq!(IngredientTemplate::try_from(doc).unwrap(), template);
+    }
+
+    #[test]
+    fn validate_template() {
+        assert_eq!(create_pie_crust().validate().is_ok(), true);
+        assert_eq!(IngredientTemplate { name: " ".to_string(), ..create_pie_crust() }.validate().is_err(), true);
+        assert_eq!(IngredientTemplate { ingredients: vec![], ..create_pie_crust() }.validate().is_err(), true);
+    }
+
+    #[test]
+    fn expanded_ingredients_have_prefixed_ids() {
+        let template = create_pie_crust();
+        let ingredients = template.expanded_ingredients();
+        assert_eq!(ingredients[0].id, format!("{}-0", template._id));
+        assert_eq!(ingredients[1].title, "Butter");
+    }
+}
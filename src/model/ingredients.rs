@@ -1,25 +1,46 @@
+use std::collections::HashSet;
 use std::convert::TryFrom;
 
 use bson::{Bson, Document};
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::model::amount::{amount_from_bson, amount_to_bson, serialize_amount};
 use crate::model::measurement_unit::MeasurementUnit;
-use crate::model::recipe::RecipeFormatError;
+use crate::model::recipe::{normalize_list, RecipeFormatError};
 
 const JSON_ATTR_ID: &str = "id";
 const JSON_ATTR_TITLE: &str = "title";
 const JSON_ATTR_AMOUNT: &str = "amount";
 const JSON_ATTR_MEASUREMENT_UNIT: &str = "measurementUnit";
+const JSON_ATTR_SUBSTITUTES: &str = "substitutes";
 
 
-#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Ingredient {
     pub id: String,
-    pub amount: i32,
+    #[serde(serialize_with = "serialize_amount")]
+    pub amount: f64,
     pub title: String,
     #[serde(rename = "measurementUnit")]
     pub measurement_unit: MeasurementUnit,
+    /// what the ingredient can be swapped for, stored normalized like the compared titles
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub substitutes: Vec<String>,
+}
+
+/// an ingredient of a recipe with what it can be swapped for
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct IngredientSubstitutes {
+    pub id: String,
+    pub title: String,
+    pub substitutes: Vec<String>,
+}
+
+impl From<&Ingredient> for IngredientSubstitutes {
+    fn from(ingredient: &Ingredient) -> Self {
+        Self { id: ingredient.id.clone(), title: ingredient.title.clone(), substitutes: ingredient.normalized_substitutes() }
+    }
 }
 
 
@@ -28,15 +49,15 @@ impl TryFrom<Bson> for Ingredient {
 
     fn try_from(bson: Bson) -> Result<Self, Self::Error> {
         let doc = bson.as_document()
-            .ok_or_else(|| "Error getting ingredients from document")?;
+            .ok_or("Error getting ingredients from document")?;
 
         return Ok(Self {
             id: doc.get_str(JSON_ATTR_ID)
                 .map(String::from)
                 .map_err(|_| RecipeFormatError::from(
                     "Error getting id from ingredient from document"))?,
-            amount: doc.get_i32(JSON_ATTR_AMOUNT)
-                .map_err(|_| RecipeFormatError::from(
+            amount: amount_from_bson(doc.get(JSON_ATTR_AMOUNT))
+                .ok_or_else(|| RecipeFormatError::from(
                     "Error getting amount from ingredient from document"))?,
             title: doc.get_str(JSON_ATTR_TITLE)
                 .map(String::from)
@@ -48,29 +69,81 @@ impl TryFrom<Bson> for Ingredient {
                 .and_then(MeasurementUnit::try_from)
                 .map_err(|_| RecipeFormatError::from(
                     "Error converting measurement unit from ingredient to enum"))?,
+            substitutes: match doc.get(JSON_ATTR_SUBSTITUTES) {
+                Some(Bson::Array(substitutes)) => substitutes.iter()
+                    .map(|substitute| substitute.as_str().map(String::from))
+                    .collect::<Option<Vec<String>>>()
+                    .ok_or_else(|| RecipeFormatError::from("Error getting substitutes from ingredient from document"))?,
+                _ => vec![],
+            },
         });
     }
 }
 
 
 impl Ingredient {
-    pub fn new(id: &str, amount: i32, title: &str, measurement_unit: MeasurementUnit) -> Self {
+    pub fn new(id: &str, amount: f64, title: &str, measurement_unit: MeasurementUnit) -> Self {
         return Self {
             id: id.to_string(),
             amount,
             title: title.to_string(),
             measurement_unit,
+            substitutes: vec![],
         };
     }
+
+    /// title used to compare ingredients across recipes
+    pub fn normalized_title(&self) -> String {
+        self.title.trim().to_lowercase()
+    }
+
+    /// the substitutes compared like titles, without blank ones, duplicates and the ingredient itself
+    pub fn normalized_substitutes(&self) -> Vec<String> {
+        let title = self.normalized_title();
+        normalize_list(&self.substitutes).into_iter()
+            .filter(|substitute| *substitute != title)
+            .collect()
+    }
+}
+
+/// ids must be non-empty and unique, merging relies on them
+pub fn validate_ingredient_ids(ingredients: &[Ingredient]) -> Result<(), RecipeFormatError> {
+    let mut ids = HashSet::new();
+    for ingredient in ingredients {
+        if ingredient.id.trim().is_empty() {
+            return Err(RecipeFormatError::from("Ingredient ids must not be empty"));
+        }
+        if !ids.insert(ingredient.id.as_str()) {
+            return Err(format!("Ingredient id '{}' is used more than once", ingredient.id).into());
+        }
+    }
+    Ok(())
+}
+
+/// replaces the current ingredients sharing an id with a change in place,
+/// changes with a new id are appended in their order
+pub fn merge_ingredients(current: Vec<Ingredient>, changes: Vec<Ingredient>) -> Vec<Ingredient> {
+    let mut merged = current;
+    for change in changes {
+        match merged.iter_mut().find(|ingredient| ingredient.id == change.id) {
+            Some(ingredient) => *ingredient = change,
+            None => merged.push(change),
+        }
+    }
+    merged
 }
 
 impl From<Ingredient> for Bson {
     fn from(ing: Ingredient) -> Self {
+        let substitutes = ing.normalized_substitutes();
         let mut doc = Document::new();
         doc.insert(JSON_ATTR_ID, ing.id);
-        doc.insert(JSON_ATTR_AMOUNT, ing.amount);
+        doc.insert(JSON_ATTR_AMOUNT, amount_to_bson(ing.amount));
         doc.insert(JSON_ATTR_TITLE, ing.title);
         doc.insert(JSON_ATTR_MEASUREMENT_UNIT, ing.measurement_unit);
+        if !substitutes.is_empty() {
+            doc.insert(JSON_ATTR_SUBSTITUTES, substitutes);
+        }
         Bson::Document(doc)
     }
 }
@@ -89,7 +162,7 @@ mod ingredients_tests {
 
     use bson::{Bson, Document};
 
-    use crate::model::ingredients::{Ingredient, JSON_ATTR_AMOUNT, JSON_ATTR_ID, JSON_ATTR_MEASUREMENT_UNIT, JSON_ATTR_TITLE};
+    use crate::model::ingredients::{Ingredient, IngredientSubstitutes, JSON_ATTR_AMOUNT, JSON_ATTR_ID, JSON_ATTR_MEASUREMENT_UNIT, JSON_ATTR_SUBSTITUTES, JSON_ATTR_TITLE, merge_ingredients, validate_ingredient_ids};
     use crate::model::measurement_unit::MeasurementUnit;
 
     #[test]
@@ -101,7 +174,7 @@ mod ingredients_tests {
             "measurementUnit": "Kilogramm"
         })).unwrap();
         assert_eq!(ingredient.title, "Bread");
-        assert_eq!(ingredient.amount, 1000);
+        assert_eq!(ingredient.amount, 1000.0);
         assert_eq!(ingredient.measurement_unit, MeasurementUnit::Kilogramm);
         assert_eq!(ingredient.id, "0");
     }
@@ -135,19 +208,109 @@ mod ingredients_tests {
     }
 
 
+    #[test]
+    fn normalized_title_test() {
+        let ingredient = Ingredient::new("0", 1.0, "  Whole Milk ", MeasurementUnit::Liter);
+        assert_eq!(ingredient.normalized_title(), "whole milk");
+    }
+
     #[test]
     fn from_ingredient_to_bson_test() {
         let ingredient = Ingredient {
             id: "0".to_string(),
-            amount: 200,
+            amount: 200.0,
             title: "wheat".to_string(),
             measurement_unit: MeasurementUnit::Kilogramm,
+            substitutes: vec![],
         };
         let bson: Document = Bson::from(ingredient).as_document().unwrap().to_owned();
+        assert_eq!(bson.contains_key(JSON_ATTR_SUBSTITUTES), false);
 
         assert_eq!(bson.get_str(JSON_ATTR_ID).unwrap(), "0");
         assert_eq!(bson.get_i32(JSON_ATTR_AMOUNT).unwrap(), 200);
         assert_eq!(bson.get_str(JSON_ATTR_TITLE).unwrap(), "wheat");
         assert_eq!(bson.get_str(JSON_ATTR_MEASUREMENT_UNIT).unwrap(), MeasurementUnit::Kilogramm.to_string());
     }
+
+    #[test]
+    fn ingredient_substitutes_test() {
+        let ingredient: Ingredient = serde_json::from_str(
+            r#"{"id": "0", "amount": 50, "title": "Butter", "measurementUnit": "Gramm", "substitutes": [" Margarine", "margarine", "", "BUTTER", "Coconut oil"]}"#).unwrap();
+        assert_eq!(ingredient.normalized_substitutes(), vec!["margarine", "coconut oil"]);
+
+        let stored = Ingredient::try_from(Bson::from(ingredient.clone())).unwrap();
+        assert_eq!(stored.substitutes, vec!["margarine", "coconut oil"]);
+        assert_eq!(serde_json::to_value(&stored).unwrap()["substitutes"], serde_json::json!(["margarine", "coconut oil"]));
+        assert_eq!(IngredientSubstitutes::from(&ingredient),
+                   IngredientSubstitutes { id: "0".to_string(), title: "Butter".to_string(), substitutes: vec!["margarine".to_string(), "coconut oil".to_string()] });
+
+        let without: Ingredient = serde_json::from_str(r#"{"id": "0", "amount": 1, "title": "Salt", "measurementUnit": "Gramm"}"#).unwrap();
+        assert_eq!(without.substitutes.is_empty(), true);
+        assert_eq!(serde_json::to_value(&without).unwrap().get("substitutes"), None);
+
+        let invalid = Ingredient::try_from(Bson::Document(doc! { "id": "0", "amount": 1, "title": "Salt", "measurementUnit": "Gramm", "substitutes": [1] }));
+        assert_eq!(invalid.is_err(), true);
+    }
+
+    #[test]
+    fn ingredient_amount_json_test() {
+        let ingredient: Ingredient = serde_json::from_str(
+            r#"{"id": "0", "amount": 200, "title": "Flour", "measurementUnit": "Gramm"}"#).unwrap();
+        assert_eq!(ingredient.amount, 200.0);
+        assert_eq!(serde_json::to_value(&ingredient).unwrap()["amount"], serde_json::json!(200));
+
+        let ingredient: Ingredient = serde_json::from_str(
+            r#"{"id": "0", "amount": 0.5, "title": "Milk", "measurementUnit": "Liter"}"#).unwrap();
+        assert_eq!(ingredient.amount, 0.5);
+        assert_eq!(serde_json::to_value(&ingredient).unwrap()["amount"], serde_json::json!(0.5));
+    }
+
+    #[test]
+    fn from_bson_with_fractional_amount_to_ingredient_test() {
+        let ingredient = Ingredient::try_from(Bson::Document(doc! {
+            "id": "0",
+            "amount": 0.5,
+            "title": "Milk",
+            "measurementUnit": "Liter"
+        })).unwrap();
+        assert_eq!(ingredient.amount, 0.5);
+    }
+
+    #[test]
+    fn ingredient_round_trip_keeps_amounts() {
+        for json in [r#"{"id":"0","amount":200,"title":"Flour","measurementUnit":"Gramm"}"#,
+            r#"{"id":"0","amount":200.5,"title":"Flour","measurementUnit":"Gramm"}"#].iter() {
+            let ingredient: Ingredient = serde_json::from_str(json).unwrap();
+            let stored = Ingredient::try_from(Bson::from(ingredient)).unwrap();
+            assert_eq!(serde_json::to_string(&stored).unwrap(), *json);
+        }
+    }
+
+    #[test]
+    fn validate_ingredient_ids_test() {
+        let flour = Ingredient::new("0", 200.0, "Flour", MeasurementUnit::Gramm);
+        let milk = Ingredient::new("1", 0.5, "Milk", MeasurementUnit::Liter);
+        assert_eq!(validate_ingredient_ids(&[flour.clone(), milk.clone()]).is_ok(), true);
+        assert_eq!(validate_ingredient_ids(&[flour.clone(), flour.clone()]).is_err(), true);
+        assert_eq!(validate_ingredient_ids(&[Ingredient::new(" ", 1.0, "Salt", MeasurementUnit::Piece)]).is_err(), true);
+    }
+
+    #[test]
+    fn merge_ingredients_test() {
+        let current = vec![
+            Ingredient::new("0", 200.0, "Flour", MeasurementUnit::Gramm),
+            Ingredient::new("1", 0.5, "Milk", MeasurementUnit::Liter),
+            Ingredient::new("2", 2.0, "Eggs", MeasurementUnit::Piece),
+        ];
+        let changes = vec![
+            Ingredient::new("3", 1.0, "Salt", MeasurementUnit::Piece),
+            Ingredient::new("1", 0.75, "Milk", MeasurementUnit::Liter),
+        ];
+
+        let merged = merge_ingredients(current, changes);
+        let ids = merged.iter().map(|ingredient| ingredient.id.as_str()).collect::<Vec<&str>>();
+        assert_eq!(ids, vec!["0", "1", "2", "3"]);
+        assert_eq!(merged[1].amount, 0.75);
+        assert_eq!(merged[0].amount, 200.0);
+    }
 }
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use bson::{Bson, Document};
+use bson::oid::ObjectId;
+use serde::Serialize;
+
+use crate::model::recipe::RecipeFormatError;
+
+const JSON_ATTR_ID: &str = "_id";
+const JSON_ATTR_DAYS: &str = "days";
+const JSON_ATTR_DATE: &str = "date";
+const JSON_ATTR_RECIPE_IDS: &str = "recipeIds";
+
+/// Stored meal plan, maintained by the meal planning frontend.
+/// `{ _id, days: [{ date: "2020-10-05", recipeIds: [ObjectId] }] }`, dates may be datetimes as well
+#[derive(Debug, Clone, PartialEq)]
+pub struct MealPlan {
+    pub _id: ObjectId,
+    pub days: Vec<MealPlanDay>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MealPlanDay {
+    pub date: String,
+    pub recipe_ids: Vec<ObjectId>,
+}
+
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct DayWorkload {
+    pub date: String,
+    pub recipes: u32,
+    #[serde(rename = "cookingTimeInMinutes")]
+    pub cooking_time_in_minutes: u32,
+}
+
+/// Cooking time of the plan per day and in total, recipes no longer existing are skipped and named in `notes`
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct MealPlanWorkload {
+    #[serde(rename = "mealPlanId")]
+    pub meal_plan_id: String,
+    pub days: Vec<DayWorkload>,
+    #[serde(rename = "totalCookingTimeInMinutes")]
+    pub total_cooking_time_in_minutes: u32,
+    pub notes: Vec<String>,
+}
+
+impl MealPlan {
+    /// every recipe referenced by the plan, each once
+    pub fn recipe_ids(&self) -> Vec<ObjectId> {
+        let mut ids: Vec<ObjectId> = Vec::new();
+        for id in self.days.iter().flat_map(|day| day.recipe_ids.iter()) {
+            if !ids.contains(id) {
+                ids.push(id.clone());
+            }
+        }
+        ids
+    }
+
+    /// sums the cooking times of the recipes per day, a recipe planned twice counts twice
+    /// the sums stop at `u32::MAX` instead of overflowing
+    pub fn workload(&self, cooking_times: &HashMap<ObjectId, u32>) -> MealPlanWorkload {
+        let mut notes = Vec::new();
+        let days = self.days.iter()
+            .map(|day| {
+                let mut workload = DayWorkload { date: day.date.clone(), recipes: 0, cooking_time_in_minutes: 0 };
+                for id in day.recipe_ids.iter() {
+                    match cooking_times.get(id) {
+                        Some(minutes) => {
+                            workload.recipes += 1;
+                            workload.cooking_time_in_minutes = workload.cooking_time_in_minutes.saturating_add(*minutes);
+                        }
+                        None => notes.push(format!("Recipe {} planned for {} does not exist, it was skipped", id, day.date)),
+                    }
+                }
+                workload
+            })
+            .collect::<Vec<DayWorkload>>();
+        MealPlanWorkload {
+            meal_plan_id: self._id.to_hex(),
+            total_cooking_time_in_minutes: days.iter().fold(0, |total: u32, day| total.saturating_add(day.cooking_time_in_minutes)),
+            days,
+            notes,
+        }
+    }
+}
+
+impl TryFrom<Document> for MealPlan {
+    type Error = RecipeFormatError;
+
+    fn try_from(doc: Document) -> Result<Self, Self::Error> {
+        let days = doc.get_array(JSON_ATTR_DAYS)
+            .map_err(|_| RecipeFormatError::from("Error getting days from meal plan document"))?
+            .iter()
+            .map(|day| match day {
+                Bson::Document(day) => MealPlanDay::try_from(day),
+                _ => Err(RecipeFormatError::from("Error getting day from meal plan document")),
+            })
+            .collect::<Result<Vec<MealPlanDay>, RecipeFormatError>>()?;
+
+        Ok(MealPlan {
+            _id: doc.get_object_id(JSON_ATTR_ID)
+                .map_err(|_| RecipeFormatError::from("Error getting id from meal plan document"))?
+                .clone(),
+            days,
+        })
+    }
+}
+
+impl TryFrom<&Document> for MealPlanDay {
+    type Error = RecipeFormatError;
+
+    fn try_from(doc: &Document) -> Result<Self, Self::Error> {
+        let date = match doc.get(JSON_ATTR_DATE) {
+            Some(Bson::String(date)) => date.clone(),
+            Some(Bson::DateTime(date)) => date.format("%Y-%m-%d").to_string(),
+            _ => return Err(RecipeFormatError::from("Error getting date from meal plan day")),
+        };
+        let recipe_ids = doc.get_array(JSON_ATTR_RECIPE_IDS)
+            .map(|ids| ids.iter()
+                .map(|id| id.as_object_id().cloned()
+                    .ok_or_else(|| RecipeFormatError::from(format!("Recipe id of {} is no object id", date))))
+                .collect::<Result<Vec<ObjectId>, RecipeFormatError>>())
+            .unwrap_or_else(|_| Ok(Vec::new()))?;
+        Ok(MealPlanDay { date, recipe_ids })
+    }
+}
+
+
+#[cfg(test)]
+mod meal_plan_tests {
+    use std::collections::HashMap;
+    use std::convert::TryFrom;
+
+    use bson::oid::ObjectId;
+    use chrono::{TimeZone, Utc};
+
+    use crate::model::meal_plan::{DayWorkload, MealPlan};
+
+    #[test]
+    fn meal_plan_from_document() {
+        let (plan_id, recipe_id) = (ObjectId::new(), ObjectId::new());
+        let plan = MealPlan::try_from(doc! {
+            "_id": plan_id.clone(),
+            "days": [
+                { "date": "2020-10-05", "recipeIds": [recipe_id.clone(), recipe_id.clone()] },
+                { "date": Utc.ymd(2020, 10, 6).and_hms(0, 0, 0) },
+            ],
+        }).unwrap();
+        assert_eq!(plan._id, plan_id);
+        assert_eq!(plan.days[0].date, "2020-10-05");
+        assert_eq!(plan.days[1].date, "2020-10-06");
+        assert_eq!(plan.days[1].recipe_ids.is_empty(), true);
+        assert_eq!(plan.recipe_ids(), vec![recipe_id]);
+
+        assert_eq!(MealPlan::try_from(doc! { "_id": plan_id.clone(), "days": [{ "date": 5 }] }).is_err(), true);
+        assert_eq!(MealPlan::try_from(doc! { "_id": plan_id, "days": [{ "date": "2020-10-05", "recipeIds": ["nope"] }] }).is_err(), true);
+    }
+
+    #[test]
+    fn workload_of_two_days() {
+        let (pasta, soup, missing) = (ObjectId::new(), ObjectId::new(), ObjectId::new());
+        let plan = MealPlan::try_from(doc! {
+            "_id": ObjectId::new(),
+            "days": [
+                { "date": "2020-10-05", "recipeIds": [pasta.clone(), soup.clone()] },
+                { "date": "2020-10-06", "recipeIds": [pasta.clone(), missing.clone()] },
+            ],
+        }).unwrap();
+        let cooking_times = vec![(pasta, 20), (soup, 45)].into_iter().collect::<HashMap<ObjectId, u32>>();
+
+        let workload = plan.workload(&cooking_times);
+        assert_eq!(workload.days, vec![
+            DayWorkload { date: "2020-10-05".to_string(), recipes: 2, cooking_time_in_minutes: 65 },
+            DayWorkload { date: "2020-10-06".to_string(), recipes: 1, cooking_time_in_minutes: 20 },
+        ]);
+        assert_eq!(workload.total_cooking_time_in_minutes, 85);
+        assert_eq!(workload.notes, vec![format!("Recipe {} planned for 2020-10-06 does not exist, it was skipped", missing)]);
+    }
+
+    #[test]
+    fn workload_saturates() {
+        let (pasta, soup) = (ObjectId::new(), ObjectId::new());
+        let plan = MealPlan::try_from(doc! {
+            "_id": ObjectId::new(),
+            "days": [
+                { "date": "2020-10-05", "recipeIds": [pasta.clone(), soup.clone()] },
+                { "date": "2020-10-06", "recipeIds": [soup.clone()] },
+            ],
+        }).unwrap();
+        let cooking_times = vec![(pasta, u32::MAX), (soup, u32::MAX - 1)].into_iter().collect::<HashMap<ObjectId, u32>>();
+
+        let workload = plan.workload(&cooking_times);
+        assert_eq!(workload.days[0].cooking_time_in_minutes, u32::MAX);
+        assert_eq!(workload.days[1].cooking_time_in_minutes, u32::MAX - 1);
+        assert_eq!(workload.total_cooking_time_in_minutes, u32::MAX);
+    }
+}
@@ -1,4 +1,27 @@
 pub mod recipe;
 pub mod ingredients;
+pub mod amount;
 pub mod difficulty;
 pub mod measurement_unit;
+pub mod recipe_yield;
+pub mod full_recipe;
+pub mod similar_recipe;
+pub mod tag_combo;
+pub mod db_stats;
+pub mod collection_assignment;
+pub mod recipe_diff;
+pub mod ingredient_template;
+pub mod recipe_summary;
+pub mod trending_recipe;
+pub mod recipe_status;
+pub mod fuzzy_match;
+pub mod unit_definition;
+pub mod delete_many;
+pub mod recipe_detail;
+pub mod step_timer;
+pub mod recipe_group;
+pub mod meal_plan;
+pub mod duplicate_group;
+pub mod retag_rule;
+pub mod query_explanation;
+pub mod recipe_stats;
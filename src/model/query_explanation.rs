@@ -0,0 +1,117 @@
+use std::convert::TryFrom;
+
+use bson::{Bson, Document};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::model::recipe::RecipeFormatError;
+
+const JSON_ATTR_QUERY_PLANNER: &str = "queryPlanner";
+const JSON_ATTR_WINNING_PLAN: &str = "winningPlan";
+const JSON_ATTR_EXECUTION_STATS: &str = "executionStats";
+const JSON_ATTR_INDEX_NAME: &str = "indexName";
+const JSON_ATTR_STAGE: &str = "stage";
+const COLLECTION_SCAN_STAGE: &str = "COLLSCAN";
+
+/// Plan and execution stats of the `explain` of a listing query, for diagnosing missing indexes.
+/// `indexes` names every index of the winning plan, `collectionScan` tells whether it reads the whole collection
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct QueryExplanation {
+    pub plan: Value,
+    pub indexes: Vec<String>,
+    #[serde(rename = "collectionScan")]
+    pub collection_scan: bool,
+    pub returned: i64,
+    #[serde(rename = "keysExamined")]
+    pub keys_examined: i64,
+    #[serde(rename = "docsExamined")]
+    pub docs_examined: i64,
+    #[serde(rename = "executionTimeMillis")]
+    pub execution_time_millis: i64,
+}
+
+impl TryFrom<Document> for QueryExplanation {
+    type Error = RecipeFormatError;
+
+    fn try_from(doc: Document) -> Result<Self, Self::Error> {
+        let plan = doc.get_document(JSON_ATTR_QUERY_PLANNER)
+            .and_then(|planner| planner.get_document(JSON_ATTR_WINNING_PLAN))
+            .map_err(|_| RecipeFormatError::from("Error getting winning plan from explain document"))?;
+        let stats = doc.get_document(JSON_ATTR_EXECUTION_STATS)
+            .map_err(|_| RecipeFormatError::from("Error getting execution stats from explain document"))?;
+
+        let mut indexes = Vec::new();
+        let mut stages = Vec::new();
+        collect_stages(&Bson::Document(plan.clone()), &mut indexes, &mut stages);
+        Ok(QueryExplanation {
+            plan: Bson::Document(plan.clone()).into_relaxed_extjson(),
+            indexes,
+            collection_scan: stages.iter().any(|stage| stage == COLLECTION_SCAN_STAGE),
+            returned: count(stats, "nReturned")?,
+            keys_examined: count(stats, "totalKeysExamined")?,
+            docs_examined: count(stats, "totalDocsExamined")?,
+            execution_time_millis: count(stats, "executionTimeMillis")?,
+        })
+    }
+}
+
+/// walks the nested input stages, the layout differs between server versions
+fn collect_stages(plan: &Bson, indexes: &mut Vec<String>, stages: &mut Vec<String>) {
+    match plan {
+        Bson::Document(doc) => {
+            if let Ok(index) = doc.get_str(JSON_ATTR_INDEX_NAME) {
+                if !indexes.iter().any(|known| known == index) {
+                    indexes.push(index.to_string());
+                }
+            }
+            if let Ok(stage) = doc.get_str(JSON_ATTR_STAGE) {
+                stages.push(stage.to_string());
+            }
+            doc.values().for_each(|value| collect_stages(value, indexes, stages));
+        }
+        Bson::Array(values) => values.iter().for_each(|value| collect_stages(value, indexes, stages)),
+        _ => {}
+    }
+}
+
+/// counters are 32 or 64 bit depending on their size
+fn count(stats: &Document, key: &str) -> Result<i64, RecipeFormatError> {
+    match stats.get(key) {
+        Some(Bson::Int32(count)) => Ok(*count as i64),
+        Some(Bson::Int64(count)) => Ok(*count),
+        Some(Bson::Double(count)) => Ok(*count as i64),
+        _ => Err(RecipeFormatError::from(format!("Error getting {} from explain document", key))),
+    }
+}
+
+
+#[cfg(test)]
+mod query_explanation_tests {
+    use std::convert::TryFrom;
+
+    use crate::model::query_explanation::QueryExplanation;
+
+    #[test]
+    fn explanation_from_document() {
+        let explanation = QueryExplanation::try_from(doc! {
+            "queryPlanner": { "winningPlan": {
+                "stage": "FETCH",
+                "inputStage": { "stage": "IXSCAN", "indexName": "tags_1", "keyPattern": { "tags": 1 } },
+            } },
+            "executionStats": { "nReturned": 2, "executionTimeMillis": 1, "totalKeysExamined": 2_i64, "totalDocsExamined": 2 },
+        }).unwrap();
+        assert_eq!(explanation.indexes, vec!["tags_1".to_string()]);
+        assert_eq!(explanation.collection_scan, false);
+        assert_eq!((explanation.returned, explanation.keys_examined, explanation.docs_examined), (2, 2, 2));
+        assert_eq!(explanation.plan["inputStage"]["stage"], "IXSCAN");
+
+        let explanation = QueryExplanation::try_from(doc! {
+            "queryPlanner": { "winningPlan": { "queryPlan": { "stage": "SORT", "inputStage": { "stage": "COLLSCAN" } } } },
+            "executionStats": { "nReturned": 3, "executionTimeMillis": 0, "totalKeysExamined": 0, "totalDocsExamined": 3 },
+        }).unwrap();
+        assert_eq!(explanation.indexes.is_empty(), true);
+        assert_eq!(explanation.collection_scan, true);
+
+        assert_eq!(QueryExplanation::try_from(doc! { "queryPlanner": {} }).is_err(), true);
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 
 use bson::{Bson, Document};
@@ -6,8 +7,15 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serializer};
 use serde::Serialize;
 
+use crate::field_modified::JSON_ATTR_FIELD_MODIFIED;
 use crate::model::difficulty::Difficulty;
+use crate::model::amount::round_amount;
+use crate::model::ingredient_template::IngredientTemplate;
 use crate::model::ingredients::Ingredient;
+use crate::model::recipe_status::RecipeStatus;
+use crate::model::recipe_yield::RecipeYield;
+use crate::model::step_timer::{StepTimer, validate_step_timers};
+use crate::recipe_limits::RecipeLimits;
 
 const JSON_ATTR_ID: &str = "_id";
 const JSON_ATTR_COOKING_TIME: &str = "cookingTimeInMinutes";
@@ -22,8 +30,21 @@ const JSON_ATTR_TAGS: &str = "tags";
 const JSON_ATTR_IMAGE: &str = "image";
 const JSON_ATTR_INSTRUCTIONS: &str = "instructions";
 const JSON_ATTR_DEFAULT_SERVINGS: &str = "defaultServings";
-
-#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+const JSON_ATTR_YIELD: &str = "yield";
+const JSON_ATTR_THUMBNAIL: &str = "thumbnail";
+const JSON_ATTR_ARCHIVED: &str = "archived";
+const JSON_ATTR_SLUG: &str = "slug";
+const JSON_ATTR_CUISINE: &str = "cuisine";
+const JSON_ATTR_LANGUAGE: &str = "language";
+const JSON_ATTR_TEMPLATE_IDS: &str = "templateIds";
+const JSON_ATTR_EQUIPMENT: &str = "equipment";
+const JSON_ATTR_STATUS: &str = "status";
+const JSON_ATTR_AUTHOR: &str = "author";
+const JSON_ATTR_NOTES: &str = "notes";
+const JSON_ATTR_SOURCE: &str = "source";
+const JSON_ATTR_STEP_TIMERS: &str = "stepTimers";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Recipe {
     #[serde(skip_deserializing)]
     #[serde(rename = "id")]
@@ -46,6 +67,42 @@ pub struct Recipe {
     pub instructions: Vec<String>,
     #[serde(rename = "defaultServings")]
     pub default_servings: u32,
+    #[serde(rename = "yield", default)]
+    pub recipe_yield: Option<RecipeYield>,
+    /// only changed through the archive endpoints, archived recipes are hidden from listings
+    #[serde(skip_deserializing)]
+    pub archived: bool,
+    /// generated from the title on create, stays the same when the title changes
+    #[serde(skip_deserializing)]
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub cuisine: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    /// ingredient templates whose ingredients are appended when expanding, in this order
+    #[serde(rename = "templateIds", default)]
+    pub template_ids: Vec<String>,
+    /// required tools like `oven`, stored normalized
+    #[serde(default)]
+    pub equipment: Vec<String>,
+    /// sent on create, afterwards only changed through the publish endpoint
+    #[serde(default)]
+    pub status: RecipeStatus,
+    /// user of the api token which created the recipe, None for anonymous and bulk creates
+    #[serde(skip_deserializing)]
+    pub author: Option<String>,
+    /// private remarks of the cook, may be stored encrypted
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// where the recipe comes from, e.g. a book or a person, may be stored encrypted
+    #[serde(default)]
+    pub source: Option<String>,
+    /// timers of the instructions for the cook mode, recipes without timers have none
+    #[serde(rename = "stepTimers", default)]
+    pub step_timers: Vec<StepTimer>,
+    /// when each field last changed, only tracked when switched on and left out while empty
+    #[serde(rename = "fieldModified", skip_deserializing, skip_serializing_if = "BTreeMap::is_empty")]
+    pub field_modified: BTreeMap<String, DateTime<Utc>>,
 }
 
 
@@ -53,16 +110,74 @@ fn serialize_object_id<S>(oid: &ObjectId, ser: S) -> Result<S::Ok, S::Error> whe
     oid.to_string().serialize(ser)
 }
 
+/// trimmed and lowercase, without empty entries and duplicates, e.g. ` Oven` and `oven` become `oven`
+pub fn normalize_equipment(equipment: &[String]) -> Vec<String> {
+    normalize_list(equipment)
+}
+
+/// trimmed and lowercase like the equipment, e.g. `Vegan ` and `vegan` become `vegan`
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    normalize_list(tags)
+}
+
+/// trimmed, lowercase and without blank values or duplicates, keeping the first occurrence
+pub fn normalize_list(values: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(values.len());
+    for value in values.iter().map(|value| value.trim().to_lowercase()).filter(|value| !value.is_empty()) {
+        if !normalized.contains(&value) {
+            normalized.push(value);
+        }
+    }
+    normalized
+}
+
+/// steps are unnumbered strings ordered by their position, so only blank ones can break the cook mode and print views
+fn validate_instructions(instructions: &[String]) -> Result<(), RecipeFormatError> {
+    match instructions.iter().position(|instruction| instruction.trim().is_empty()) {
+        Some(index) => Err(RecipeFormatError::empty(&format!("/instructions/{}", index))),
+        None => Ok(()),
+    }
+}
+
 
+/// `field` names the invalid text, e.g. `/instructions/2`, `limit` the limit it exceeds
 #[derive(Debug, Serialize)]
-pub struct RecipeFormatError { pub error: String }
+pub struct RecipeFormatError {
+    pub error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
 
 impl From<&str> for RecipeFormatError {
-    fn from(error: &str) -> Self { Self { error: error.to_string() } }
+    fn from(error: &str) -> Self { Self::from(error.to_string()) }
+}
+
+impl RecipeFormatError {
+    pub fn too_long(field: &str, limit: usize, length: usize) -> Self {
+        Self {
+            error: format!("The field {} may have at most {} characters, has {}", field, limit, length),
+            field: Some(field.to_string()),
+            limit: Some(limit),
+        }
+    }
+
+    pub fn empty(field: &str) -> Self {
+        Self { error: format!("The field {} must not be empty", field), field: Some(field.to_string()), limit: None }
+    }
+
+    pub fn too_many(field: &str, limit: usize, count: usize) -> Self {
+        Self {
+            error: format!("A recipe may have at most {} {}, has {}", limit, field.trim_start_matches('/'), count),
+            field: Some(field.to_string()),
+            limit: Some(limit),
+        }
+    }
 }
 
 impl From<String> for RecipeFormatError {
-    fn from(error: String) -> Self { Self { error } }
+    fn from(error: String) -> Self { Self { error, field: None, limit: None } }
 }
 
 impl TryFrom<Document> for Recipe {
@@ -83,6 +198,19 @@ impl TryFrom<Document> for Recipe {
             image_base64: Recipe::extract_image(&doc)?,
             instructions: Recipe::extract_instructions(&doc)?,
             default_servings: Recipe::extract_default_servings(&doc)?,
+            recipe_yield: Recipe::extract_yield(&doc)?,
+            archived: Recipe::extract_archived(&doc)?,
+            slug: Recipe::extract_slug(&doc)?,
+            cuisine: Recipe::extract_optional_str(&doc, JSON_ATTR_CUISINE)?,
+            language: Recipe::extract_optional_str(&doc, JSON_ATTR_LANGUAGE)?,
+            template_ids: Recipe::extract_template_ids(&doc)?,
+            equipment: Recipe::extract_equipment(&doc)?,
+            status: Recipe::extract_status(&doc)?,
+            author: Recipe::extract_optional_str(&doc, JSON_ATTR_AUTHOR)?,
+            notes: Recipe::extract_optional_str(&doc, JSON_ATTR_NOTES)?,
+            source: Recipe::extract_optional_str(&doc, JSON_ATTR_SOURCE)?,
+            step_timers: Recipe::extract_step_timers(&doc)?,
+            field_modified: Recipe::extract_field_modified(&doc)?,
         });
     }
 }
@@ -99,10 +227,27 @@ impl From<Recipe> for Document {
         doc.insert(JSON_ATTR_DIFFICULTY, recipe.difficulty);
         doc.insert(JSON_ATTR_DESCRIPTION, recipe.description);
         doc.insert(JSON_ATTR_TITLE, recipe.title);
-        doc.insert(JSON_ATTR_TAGS, recipe.tags);
-        doc.insert(JSON_ATTR_IMAGE, recipe.image_base64.map_or_else(|| Bson::Null, |oid| Bson::String(oid)));
+        doc.insert(JSON_ATTR_TAGS, normalize_tags(&recipe.tags));
+        doc.insert(JSON_ATTR_IMAGE, recipe.image_base64.map_or_else(|| Bson::Null, Bson::String));
         doc.insert(JSON_ATTR_INSTRUCTIONS, recipe.instructions);
         doc.insert(JSON_ATTR_DEFAULT_SERVINGS, recipe.default_servings);
+        doc.insert(JSON_ATTR_YIELD, recipe.recipe_yield.map_or(Bson::Null, Bson::from));
+        doc.insert(JSON_ATTR_ARCHIVED, recipe.archived);
+        doc.insert(JSON_ATTR_SLUG, recipe.slug.map_or(Bson::Null, Bson::String));
+        doc.insert(JSON_ATTR_CUISINE, recipe.cuisine.map_or(Bson::Null, Bson::String));
+        doc.insert(JSON_ATTR_LANGUAGE, recipe.language.map_or(Bson::Null, Bson::String));
+        doc.insert(JSON_ATTR_TEMPLATE_IDS, recipe.template_ids);
+        doc.insert(JSON_ATTR_EQUIPMENT, normalize_equipment(&recipe.equipment));
+        doc.insert(JSON_ATTR_STATUS, recipe.status);
+        doc.insert(JSON_ATTR_AUTHOR, recipe.author.map_or(Bson::Null, Bson::String));
+        doc.insert(JSON_ATTR_NOTES, recipe.notes.map_or(Bson::Null, Bson::String));
+        doc.insert(JSON_ATTR_SOURCE, recipe.source.map_or(Bson::Null, Bson::String));
+        doc.insert(JSON_ATTR_STEP_TIMERS, recipe.step_timers.into_iter().map(Bson::from).collect::<Vec<Bson>>());
+        if !recipe.field_modified.is_empty() {
+            doc.insert(JSON_ATTR_FIELD_MODIFIED, recipe.field_modified.into_iter()
+                .map(|(field, modified)| (field, Bson::DateTime(modified)))
+                .collect::<Document>());
+        }
         doc
     }
 }
@@ -111,11 +256,86 @@ impl Recipe {
     pub fn default_projection_no_image() -> Document {
         let mut doc = Document::new();
         doc.insert(JSON_ATTR_IMAGE, 0);
+        doc.insert(JSON_ATTR_THUMBNAIL, 0);
         return doc;
     }
 
+    pub fn validate(&self, limits: &RecipeLimits) -> Result<(), RecipeFormatError> {
+        limits.validate(self)?;
+        if let Some(recipe_yield) = &self.recipe_yield {
+            recipe_yield.validate()?;
+        }
+        self.template_object_ids()?;
+        validate_instructions(&self.instructions)?;
+        validate_step_timers(&self.step_timers, self.instructions.len())?;
+        Ok(())
+    }
+
+    /// drafts may be incomplete, a published recipe needs a title, ingredients and instructions
+    pub fn validate_for_publishing(&self, limits: &RecipeLimits) -> Result<(), RecipeFormatError> {
+        self.validate(limits)?;
+        if self.title.trim().is_empty() {
+            return Err("A published recipe needs a title".into());
+        }
+        if self.ingredients.is_empty() {
+            return Err("A published recipe needs at least one ingredient".into());
+        }
+        if self.instructions.is_empty() {
+            return Err("A published recipe needs at least one instruction".into());
+        }
+        Ok(())
+    }
+
+    pub fn template_object_ids(&self) -> Result<Vec<ObjectId>, RecipeFormatError> {
+        self.template_ids.iter()
+            .map(|id| ObjectId::with_string(id).map_err(|_| format!("Template id '{}' is no object id", id).into()))
+            .collect()
+    }
+
+    /// appends the ingredients of the referenced templates, templates deleted in the meantime are skipped
+    pub fn expand_templates(&mut self, templates: &[IngredientTemplate]) {
+        for id in &self.template_ids {
+            if let Some(template) = templates.iter().find(|template| template._id.to_hex() == *id) {
+                self.ingredients.extend(template.expanded_ingredients());
+            }
+        }
+    }
+
+    /// amount the ingredient amounts refer to: the yield when present, the default servings otherwise
+    pub fn scaling_basis(&self) -> f64 {
+        match &self.recipe_yield {
+            Some(recipe_yield) => recipe_yield.amount,
+            None => self.default_servings as f64,
+        }
+    }
+
+    /// recipes without servings or yield have no basis to scale their ingredients from
+    pub fn is_scalable(&self) -> bool {
+        let basis = self.scaling_basis();
+        basis.is_finite() && basis > 0.0
+    }
+
+    /// returns the recipe with all ingredient amounts scaled from the scaling basis to `target`
+    pub fn scaled_to(&self, target: f64) -> Recipe {
+        let mut recipe = self.clone();
+        let basis = self.scaling_basis();
+        if basis <= 0.0 || target <= 0.0 {
+            return recipe;
+        }
+
+        let factor = target / basis;
+        for ingredient in recipe.ingredients.iter_mut() {
+            ingredient.amount = round_amount(ingredient.amount * factor);
+        }
+        match recipe.recipe_yield.as_mut() {
+            Some(recipe_yield) => recipe_yield.amount = target,
+            None => recipe.default_servings = target.round().max(1.0) as u32,
+        }
+        return recipe;
+    }
+
 
-    fn extract_difficulty(doc: &Document) -> Result<Difficulty, RecipeFormatError> {
+    pub(crate) fn extract_difficulty(doc: &Document) -> Result<Difficulty, RecipeFormatError> {
         doc.get_str(JSON_ATTR_DIFFICULTY)
             .map(Difficulty::try_from)
             .map_err(|_| RecipeFormatError::from("Error getting difficulty from document"))?
@@ -128,11 +348,11 @@ impl Recipe {
     }
 
 
-    fn extract_tags(doc: &Document) -> Result<Vec<String>, RecipeFormatError> {
+    pub(crate) fn extract_tags(doc: &Document) -> Result<Vec<String>, RecipeFormatError> {
         doc.get_array(JSON_ATTR_TAGS)
             .map_err(|_| RecipeFormatError::from("Error getting tag from document"))
             .map(|tags| {
-                tags.into_iter()
+                tags.iter()
                     .map(|f| f.as_str().map(String::from))
                     .collect::<Option<Vec<String>>>()
                     .ok_or_else(|| RecipeFormatError::from("Error getting tag from document"))
@@ -140,7 +360,7 @@ impl Recipe {
     }
 
     /// returns image when set, when not set or not available return None, on Error RecipeFormatError
-    fn extract_image(doc: &Document) -> Result<Option<String>, RecipeFormatError> {
+    pub(crate) fn extract_image(doc: &Document) -> Result<Option<String>, RecipeFormatError> {
         match doc.get(JSON_ATTR_IMAGE) {
             Some(Bson::Null) => Ok(None),
             Some(Bson::String(image)) => Ok(Some(image.to_owned())),
@@ -152,7 +372,7 @@ impl Recipe {
     fn extract_instructions(doc: &Document) -> Result<Vec<String>, RecipeFormatError> {
         doc.get_array(JSON_ATTR_INSTRUCTIONS)
             .map_err(|_| RecipeFormatError::from("Error getting instructions from document"))
-            .map(|instructions| instructions.into_iter()
+            .map(|instructions| instructions.iter()
                 .map(|instruction| instruction.as_str().map(String::from))
                 .collect::<Option<Vec<String>>>()
                 .ok_or_else(|| RecipeFormatError::from("Error getting instructions from document"))
@@ -165,7 +385,96 @@ impl Recipe {
             .map_err(|_| RecipeFormatError::from("Error getting default_servings from document"))
     }
 
-    fn extract_title(doc: &Document) -> Result<String, RecipeFormatError> {
+    /// returns None when no yield is set, on invalid yield RecipeFormatError
+    fn extract_yield(doc: &Document) -> Result<Option<RecipeYield>, RecipeFormatError> {
+        match doc.get(JSON_ATTR_YIELD) {
+            Some(Bson::Null) | None => Ok(None),
+            Some(recipe_yield) => RecipeYield::try_from(recipe_yield).map(Some),
+        }
+    }
+
+    /// recipes stored before equipment existed need none
+    fn extract_equipment(doc: &Document) -> Result<Vec<String>, RecipeFormatError> {
+        match doc.get(JSON_ATTR_EQUIPMENT) {
+            Some(Bson::Array(equipment)) => equipment.iter()
+                .map(|tool| tool.as_str().map(String::from))
+                .collect::<Option<Vec<String>>>()
+                .ok_or_else(|| RecipeFormatError::from("Error getting equipment from document")),
+            Some(Bson::Null) | None => Ok(vec![]),
+            _ => Err(RecipeFormatError::from("Error getting equipment from document")),
+        }
+    }
+
+    /// recipes stored before step timers existed have none
+    fn extract_step_timers(doc: &Document) -> Result<Vec<StepTimer>, RecipeFormatError> {
+        match doc.get(JSON_ATTR_STEP_TIMERS) {
+            Some(Bson::Array(timers)) => timers.iter().map(StepTimer::try_from).collect(),
+            Some(Bson::Null) | None => Ok(vec![]),
+            _ => Err(RecipeFormatError::from("Error getting step timers from document")),
+        }
+    }
+
+    /// recipes stored before templates existed reference none
+    fn extract_template_ids(doc: &Document) -> Result<Vec<String>, RecipeFormatError> {
+        match doc.get(JSON_ATTR_TEMPLATE_IDS) {
+            Some(Bson::Array(ids)) => ids.iter()
+                .map(|id| id.as_str().map(String::from))
+                .collect::<Option<Vec<String>>>()
+                .ok_or_else(|| RecipeFormatError::from("Error getting template ids from document")),
+            Some(Bson::Null) | None => Ok(vec![]),
+            _ => Err(RecipeFormatError::from("Error getting template ids from document")),
+        }
+    }
+
+    /// recipes without tracked changes have no times
+    fn extract_field_modified(doc: &Document) -> Result<BTreeMap<String, DateTime<Utc>>, RecipeFormatError> {
+        match doc.get(JSON_ATTR_FIELD_MODIFIED) {
+            Some(Bson::Document(modified)) => modified.iter()
+                .map(|(field, modified)| modified.as_datetime().map(|modified| (field.clone(), *modified)))
+                .collect::<Option<BTreeMap<String, DateTime<Utc>>>>()
+                .ok_or_else(|| RecipeFormatError::from("Error getting field modified times from document")),
+            Some(Bson::Null) | None => Ok(BTreeMap::new()),
+            _ => Err(RecipeFormatError::from("Error getting field modified times from document")),
+        }
+    }
+
+    /// recipes stored before statuses existed are published
+    fn extract_status(doc: &Document) -> Result<RecipeStatus, RecipeFormatError> {
+        match doc.get(JSON_ATTR_STATUS) {
+            Some(Bson::String(status)) => RecipeStatus::try_from(status.as_str()),
+            Some(Bson::Null) | None => Ok(RecipeStatus::Published),
+            _ => Err(RecipeFormatError::from("Error getting status from document")),
+        }
+    }
+
+    /// recipes stored before archiving existed are not archived
+    fn extract_archived(doc: &Document) -> Result<bool, RecipeFormatError> {
+        match doc.get(JSON_ATTR_ARCHIVED) {
+            Some(Bson::Boolean(archived)) => Ok(*archived),
+            Some(Bson::Null) | None => Ok(false),
+            _ => Err(RecipeFormatError::from("Error getting archived from document")),
+        }
+    }
+
+    /// recipes stored before slugs existed have none
+    fn extract_slug(doc: &Document) -> Result<Option<String>, RecipeFormatError> {
+        match doc.get(JSON_ATTR_SLUG) {
+            Some(Bson::String(slug)) => Ok(Some(slug.clone())),
+            Some(Bson::Null) | None => Ok(None),
+            _ => Err(RecipeFormatError::from("Error getting slug from document")),
+        }
+    }
+
+    /// optional classification fields, absent in recipes stored before they existed
+    fn extract_optional_str(doc: &Document, key: &str) -> Result<Option<String>, RecipeFormatError> {
+        match doc.get(key) {
+            Some(Bson::String(value)) => Ok(Some(value.clone())),
+            Some(Bson::Null) | None => Ok(None),
+            _ => Err(format!("Error getting {} from document", key).into()),
+        }
+    }
+
+    pub(crate) fn extract_title(doc: &Document) -> Result<String, RecipeFormatError> {
         doc.get_str(JSON_ATTR_TITLE)
             .map(String::from)
             .map_err(|_| RecipeFormatError::from("Error getting title from document"))
@@ -186,7 +495,7 @@ impl Recipe {
     fn extract_ingredients(doc: &Document) -> Result<Vec<Ingredient>, RecipeFormatError> {
         doc.get_array(JSON_ATTR_INGREDIENTS)
             .map_err(|_| RecipeFormatError::from("Error getting ingredients from document"))
-            .map(|ingredients| ingredients.into_iter()
+            .map(|ingredients| ingredients.iter()
                 .map(|ing| Ingredient::try_from(ing.clone())
                     .map_err(|_| RecipeFormatError::from("")))
                 .collect::<Result<Vec<Ingredient>, RecipeFormatError>>()
@@ -199,13 +508,13 @@ impl Recipe {
             .map_err(|_| RecipeFormatError::from("Error getting last modified from document"))
     }
 
-    fn extract_cooking_time(doc: &Document) -> Result<u32, RecipeFormatError> {
+    pub(crate) fn extract_cooking_time(doc: &Document) -> Result<u32, RecipeFormatError> {
         doc.get_i32(JSON_ATTR_COOKING_TIME)
             .map(|x| if x < 0 { 0 } else { x as u32 })
             .map_err(|_| RecipeFormatError::from("Error getting cooking timefrom document"))
     }
 
-    fn extract_id(doc: &Document) -> Result<ObjectId, RecipeFormatError> {
+    pub(crate) fn extract_id(doc: &Document) -> Result<ObjectId, RecipeFormatError> {
         doc.get_object_id(JSON_ATTR_ID)
             .map(|x| x.to_owned())
             .map_err(|_| RecipeFormatError::from("Error getting  Object Id document"))
@@ -238,8 +547,10 @@ mod convert_tests {
                                JSON_ATTR_TAGS,
                                JSON_ATTR_TITLE,
                                JSON_ATTR_VERSION,
+                               JSON_ATTR_YIELD,
                                Recipe,
                                RecipeFormatError};
+    use crate::model::recipe_yield::RecipeYield;
 
     #[test]
     fn from_str_to_recipe_format_error_works() {
@@ -255,9 +566,9 @@ mod convert_tests {
         doc.insert(JSON_ATTR_CREATED, DateTime::from(SystemTime::now()));
         doc.insert(JSON_ATTR_LAST_MODIFIED, DateTime::from(SystemTime::now()));
         doc.insert(JSON_ATTR_INGREDIENTS, vec![
-            Ingredient::new("0", 100, "Cheese",
+            Ingredient::new("0", 100.0, "Cheese",
                             MeasurementUnit::Kilogramm),
-            Ingredient::new("1", 200, "Bread",
+            Ingredient::new("1", 200.0, "Bread",
                             MeasurementUnit::Piece)]);
         doc.insert(JSON_ATTR_VERSION, 1);
         doc.insert(JSON_ATTR_DIFFICULTY, Difficulty::Easy);
@@ -309,6 +620,37 @@ mod convert_tests {
         assert_eq!(result.is_ok(), true, "{}", result.err().unwrap().error);
     }
 
+    #[test]
+    fn basic_recipe_from_document_with_yield() {
+        let mut doc = create_basic_recipe_doc();
+        doc.insert(JSON_ATTR_YIELD, doc! { "amount": 12, "unit": "cookies" });
+        let result = Recipe::try_from(doc);
+        assert_eq!(result.unwrap().recipe_yield, Some(RecipeYield::new(12.0, "cookies")));
+    }
+
+    #[test]
+    fn document_from_recipe_with_yield() {
+        let mut recipe: Recipe = create_basic_recipe_doc().try_into().unwrap();
+        recipe.recipe_yield = Some(RecipeYield::new(1.0, "loaf"));
+        let mut result = Document::from(recipe.clone());
+        result.insert(JSON_ATTR_ID, recipe._id.clone());
+        assert_eq!(Recipe::try_from(result).unwrap().recipe_yield, recipe.recipe_yield);
+    }
+
+    #[test]
+    fn recipe_yield_json_serialization() {
+        let mut recipe: Recipe = create_basic_recipe_doc().try_into().unwrap();
+        let json = serde_json::to_value(&recipe).unwrap();
+        assert_eq!(json["yield"], serde_json::Value::Null);
+
+        recipe.recipe_yield = Some(RecipeYield::new(12.0, "cookies"));
+        let json = serde_json::to_value(&recipe).unwrap();
+        assert_eq!(json["yield"], serde_json::json!({ "amount": 12, "unit": "cookies" }));
+
+        let deserialized: Recipe = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized.recipe_yield, recipe.recipe_yield);
+    }
+
     #[test]
     fn document_from_recipe() {
         let recipe: Recipe = create_basic_recipe_doc().try_into().unwrap();
@@ -319,16 +661,23 @@ mod convert_tests {
 
 #[cfg(test)]
 mod recipe_tests {
+    use std::convert::TryFrom;
     use std::time::SystemTime;
 
     use bson::{Bson, Document};
     use bson::oid::ObjectId;
     use chrono::DateTime;
 
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+    use crate::field_modified::JSON_ATTR_FIELD_MODIFIED;
     use crate::model::difficulty::Difficulty;
     use crate::model::ingredients::Ingredient;
     use crate::model::measurement_unit::MeasurementUnit;
-    use crate::model::recipe::{JSON_ATTR_COOKING_TIME, JSON_ATTR_CREATED, JSON_ATTR_DEFAULT_SERVINGS, JSON_ATTR_DESCRIPTION, JSON_ATTR_DIFFICULTY, JSON_ATTR_ID, JSON_ATTR_IMAGE, JSON_ATTR_INGREDIENTS, JSON_ATTR_INSTRUCTIONS, JSON_ATTR_LAST_MODIFIED, JSON_ATTR_TAGS, JSON_ATTR_TITLE, JSON_ATTR_VERSION, Recipe};
+    use crate::model::recipe::{JSON_ATTR_COOKING_TIME, JSON_ATTR_CREATED, JSON_ATTR_DEFAULT_SERVINGS, JSON_ATTR_DESCRIPTION, JSON_ATTR_DIFFICULTY, JSON_ATTR_ID, JSON_ATTR_IMAGE, JSON_ATTR_INGREDIENTS, JSON_ATTR_INSTRUCTIONS, JSON_ATTR_LAST_MODIFIED, JSON_ATTR_TAGS, JSON_ATTR_TITLE, JSON_ATTR_VERSION, JSON_ATTR_YIELD, JSON_ATTR_ARCHIVED, JSON_ATTR_CUISINE, JSON_ATTR_SLUG, JSON_ATTR_TEMPLATE_IDS, JSON_ATTR_EQUIPMENT, JSON_ATTR_STATUS, normalize_equipment, normalize_tags, Recipe};
+    use crate::model::recipe_status::RecipeStatus;
+    use crate::model::recipe_yield::RecipeYield;
+    use crate::model::step_timer::StepTimer;
+    use crate::recipe_limits::RecipeLimits;
 
     #[test]
     fn extract_difficulty_test() {
@@ -468,6 +817,226 @@ mod recipe_tests {
         assert_eq!(result.unwrap(), 1);
     }
 
+    #[test]
+    fn extract_yield() {
+        let mut doc = Document::new();
+
+        let result = Recipe::extract_yield(&doc);
+        assert_eq!(result.unwrap(), None);
+
+        doc.insert(JSON_ATTR_YIELD, Bson::Null);
+        let result = Recipe::extract_yield(&doc);
+        assert_eq!(result.unwrap(), None);
+
+        doc.insert(JSON_ATTR_YIELD, doc! { "amount": 2.5, "unit": "loaf" });
+        let result = Recipe::extract_yield(&doc);
+        assert_eq!(result.unwrap(), Some(RecipeYield::new(2.5, "loaf")));
+
+        doc.insert(JSON_ATTR_YIELD, doc! { "amount": -1, "unit": "loaf" });
+        let result = Recipe::extract_yield(&doc);
+        assert_eq!(result.is_err(), true);
+    }
+
+    #[test]
+    fn extract_archived() {
+        let mut doc = Document::new();
+        assert_eq!(Recipe::extract_archived(&doc).unwrap(), false);
+
+        doc.insert(JSON_ATTR_ARCHIVED, true);
+        assert_eq!(Recipe::extract_archived(&doc).unwrap(), true);
+
+        doc.insert(JSON_ATTR_ARCHIVED, "yes");
+        assert_eq!(Recipe::extract_archived(&doc).is_err(), true);
+    }
+
+    #[test]
+    fn extract_status() {
+        let mut doc = Document::new();
+        assert_eq!(Recipe::extract_status(&doc).unwrap(), RecipeStatus::Published);
+
+        doc.insert(JSON_ATTR_STATUS, "draft");
+        assert_eq!(Recipe::extract_status(&doc).unwrap(), RecipeStatus::Draft);
+
+        doc.insert(JSON_ATTR_STATUS, "live");
+        assert_eq!(Recipe::extract_status(&doc).is_err(), true);
+    }
+
+    #[test]
+    fn validate_for_publishing() {
+        let mut recipe = create_one_recipe_without_image();
+        recipe.title = "Soup".to_string();
+        assert_eq!(recipe.validate_for_publishing(&RecipeLimits::default()).is_err(), true);
+
+        recipe.ingredients = vec![Ingredient::new("0", 1.0, "Water", MeasurementUnit::Liter)];
+        assert_eq!(recipe.validate_for_publishing(&RecipeLimits::default()).is_err(), true);
+
+        recipe.instructions = vec!["Boil".to_string()];
+        assert_eq!(recipe.validate_for_publishing(&RecipeLimits::default()).is_ok(), true);
+
+        recipe.title = " ".to_string();
+        assert_eq!(recipe.validate_for_publishing(&RecipeLimits::default()).is_err(), true);
+    }
+
+    #[test]
+    fn extract_slug() {
+        let mut doc = Document::new();
+        assert_eq!(Recipe::extract_slug(&doc).unwrap(), None);
+
+        doc.insert(JSON_ATTR_SLUG, "spaghetti-carbonara");
+        assert_eq!(Recipe::extract_slug(&doc).unwrap(), Some("spaghetti-carbonara".to_string()));
+
+        doc.insert(JSON_ATTR_SLUG, 1);
+        assert_eq!(Recipe::extract_slug(&doc).is_err(), true);
+    }
+
+    #[test]
+    fn extract_equipment() {
+        let mut doc = Document::new();
+        assert_eq!(Recipe::extract_equipment(&doc).unwrap(), Vec::<String>::new());
+
+        doc.insert(JSON_ATTR_EQUIPMENT, vec!["oven", "blender"]);
+        assert_eq!(Recipe::extract_equipment(&doc).unwrap(), vec!["oven", "blender"]);
+
+        doc.insert(JSON_ATTR_EQUIPMENT, "oven");
+        assert_eq!(Recipe::extract_equipment(&doc).is_err(), true);
+    }
+
+    #[test]
+    fn normalize_equipment_test() {
+        let equipment = [" Oven", "blender", "oven", "  ", "Stand Mixer "].map(String::from);
+        assert_eq!(normalize_equipment(&equipment), vec!["oven", "blender", "stand mixer"]);
+        let tags = vec![" Vegan".to_string(), "vegan ".to_string(), "".to_string(), "Quick Dinner".to_string()];
+        assert_eq!(normalize_tags(&tags), vec!["vegan", "quick dinner"]);
+    }
+
+    #[test]
+    fn extract_template_ids() {
+        let mut doc = Document::new();
+        assert_eq!(Recipe::extract_template_ids(&doc).unwrap(), Vec::<String>::new());
+
+        doc.insert(JSON_ATTR_TEMPLATE_IDS, vec!["5f7333360051027600b01a36"]);
+        assert_eq!(Recipe::extract_template_ids(&doc).unwrap(), vec!["5f7333360051027600b01a36"]);
+
+        doc.insert(JSON_ATTR_TEMPLATE_IDS, vec![1]);
+        assert_eq!(Recipe::extract_template_ids(&doc).is_err(), true);
+    }
+
+    #[test]
+    fn extract_field_modified() {
+        let mut doc = Document::new();
+        assert_eq!(Recipe::extract_field_modified(&doc).unwrap().is_empty(), true);
+
+        let modified = DateTime::from(SystemTime::now());
+        doc.insert(JSON_ATTR_FIELD_MODIFIED, doc! { "title": modified });
+        assert_eq!(Recipe::extract_field_modified(&doc).unwrap().get("title"), Some(&modified));
+
+        doc.insert(JSON_ATTR_FIELD_MODIFIED, doc! { "title": "yesterday" });
+        assert_eq!(Recipe::extract_field_modified(&doc).is_err(), true);
+    }
+
+    #[test]
+    fn extract_optional_str() {
+        let mut doc = Document::new();
+        assert_eq!(Recipe::extract_optional_str(&doc, JSON_ATTR_CUISINE).unwrap(), None);
+
+        doc.insert(JSON_ATTR_CUISINE, "Italian");
+        assert_eq!(Recipe::extract_optional_str(&doc, JSON_ATTR_CUISINE).unwrap(), Some("Italian".to_string()));
+
+        doc.insert(JSON_ATTR_CUISINE, 1);
+        assert_eq!(Recipe::extract_optional_str(&doc, JSON_ATTR_CUISINE).is_err(), true);
+    }
+
+    #[test]
+    fn scaling_prefers_yield_over_servings() {
+        let mut recipe = create_scalable_recipe();
+        assert_eq!(recipe.scaling_basis(), 2.0);
+
+        recipe.recipe_yield = Some(RecipeYield::new(12.0, "cookies"));
+        assert_eq!(recipe.scaling_basis(), 12.0);
+
+        let scaled = recipe.scaled_to(24.0);
+        assert_eq!(scaled.ingredients[0].amount, 400.0);
+        assert_eq!(scaled.recipe_yield, Some(RecipeYield::new(24.0, "cookies")));
+        assert_eq!(scaled.default_servings, 2);
+    }
+
+    #[test]
+    fn scaling_by_servings() {
+        let recipe = create_scalable_recipe();
+        let scaled = recipe.scaled_to(3.0);
+        assert_eq!(scaled.ingredients[0].amount, 300.0);
+        assert_eq!(scaled.default_servings, 3);
+    }
+
+    #[test]
+    fn scaling_fractional_amounts() {
+        let mut recipe = create_scalable_recipe();
+        recipe.ingredients = vec![Ingredient::new("0", 0.5, "Milk", MeasurementUnit::Liter)];
+        let scaled = recipe.scaled_to(6.0);
+        assert_eq!(scaled.ingredients[0].amount, 1.5);
+        assert_eq!(serde_json::to_value(&scaled).unwrap()["ingredients"][0]["amount"], serde_json::json!(1.5));
+
+        let scaled = recipe.scaled_to(8.0);
+        assert_eq!(serde_json::to_value(&scaled).unwrap()["ingredients"][0]["amount"], serde_json::json!(2));
+    }
+
+    #[test]
+    fn validate_recipe() {
+        let mut recipe = create_scalable_recipe();
+        assert_eq!(recipe.validate(&RecipeLimits::default()).is_ok(), true);
+
+        recipe.recipe_yield = Some(RecipeYield::new(0.0, "cookies"));
+        assert_eq!(recipe.validate(&RecipeLimits::default()).is_err(), true);
+    }
+
+    #[test]
+    fn validate_recipe_steps() {
+        let mut recipe = create_scalable_recipe();
+        recipe.instructions = vec!["Chop".to_string(), "  ".to_string(), "Cook".to_string()];
+        let err = recipe.validate(&RecipeLimits::default()).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("/instructions/1"));
+        assert_eq!(err.error, "The field /instructions/1 must not be empty");
+        recipe.instructions[1] = String::new();
+        assert_eq!(recipe.validate(&RecipeLimits::default()).is_err(), true);
+
+        recipe.instructions[1] = "Stir".to_string();
+        recipe.step_timers = vec![StepTimer::new(1, 60, None), StepTimer::new(1, 120, None)];
+        assert_eq!(recipe.validate(&RecipeLimits::default()).unwrap_err().error, "Step 1 has more than one timer");
+    }
+
+    #[test]
+    fn validate_recipe_with_many_ingredients() {
+        let mut recipe = create_scalable_recipe();
+        recipe.ingredients = (0..200).map(|i| Ingredient::new(&i.to_string(), 1.0, "Salt", MeasurementUnit::Gramm)).collect();
+        recipe.instructions = vec!["Stir".to_string(); 200];
+        assert_eq!(recipe.validate(&RecipeLimits::default()).is_ok(), true);
+
+        recipe.ingredients.push(Ingredient::new("200", 1.0, "Salt", MeasurementUnit::Gramm));
+        assert_eq!(recipe.validate(&RecipeLimits::default()).unwrap_err().field.as_deref(), Some("/ingredients"));
+
+        recipe.ingredients.pop();
+        recipe.instructions.push("Stir".to_string());
+        assert_eq!(recipe.validate(&RecipeLimits::default()).unwrap_err().field.as_deref(), Some("/instructions"));
+    }
+
+    fn create_scalable_recipe() -> Recipe {
+        let mut doc = Document::new();
+        doc.insert(JSON_ATTR_ID, ObjectId::new());
+        doc.insert(JSON_ATTR_COOKING_TIME, 10);
+        doc.insert(JSON_ATTR_CREATED, DateTime::from(SystemTime::now()));
+        doc.insert(JSON_ATTR_LAST_MODIFIED, DateTime::from(SystemTime::now()));
+        doc.insert(JSON_ATTR_INGREDIENTS, vec![
+            Ingredient::new("0", 200.0, "Flour", MeasurementUnit::Gramm)]);
+        doc.insert(JSON_ATTR_VERSION, 1);
+        doc.insert(JSON_ATTR_DIFFICULTY, Difficulty::Easy);
+        doc.insert(JSON_ATTR_DESCRIPTION, "");
+        doc.insert(JSON_ATTR_TITLE, "Cookies");
+        doc.insert(JSON_ATTR_TAGS, Vec::<String>::new());
+        doc.insert(JSON_ATTR_INSTRUCTIONS, Vec::<String>::new());
+        doc.insert(JSON_ATTR_DEFAULT_SERVINGS, 2);
+        Recipe::try_from(doc).unwrap()
+    }
+
     #[test]
     fn extract_title() {
         let mut doc = Document::new();
@@ -516,7 +1085,7 @@ mod recipe_tests {
         assert_eq!(result.is_ok(), true);
 
         doc.insert(JSON_ATTR_INGREDIENTS, vec![
-            Ingredient::new("0", 100, "Cheese",
+            Ingredient::new("0", 100.0, "Cheese",
                             MeasurementUnit::Kilogramm)]);
         let result = Recipe::extract_ingredients(&doc);
         assert_eq!(result.is_ok(), true);
@@ -527,7 +1096,7 @@ mod recipe_tests {
         doc.insert(JSON_ATTR_INGREDIENTS, vec![
             ing,
             Ingredient::new("0",
-                            100,
+                            100.0,
                             "Cheese",
                             MeasurementUnit::Kilogramm).into()
         ]);
@@ -0,0 +1,57 @@
+use serde::Serialize;
+
+use crate::model::recipe::Recipe;
+use crate::quick_recipes::QuickRecipes;
+
+/// Recipe as answered by the detail endpoints, together with the fields computed when reading it
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RecipeDetail {
+    #[serde(flatten)]
+    pub recipe: Recipe,
+    /// false when the recipe has neither servings nor a yield, clients hide the servings slider then
+    pub scalable: bool,
+    /// cooked within the configured threshold
+    #[serde(rename = "isQuick")]
+    pub is_quick: bool,
+}
+
+impl RecipeDetail {
+    pub fn new(recipe: Recipe, quick: &QuickRecipes) -> Self {
+        Self { scalable: recipe.is_scalable(), is_quick: quick.is_quick(recipe.cooking_time_in_minutes), recipe }
+    }
+}
+
+
+#[cfg(test)]
+mod recipe_detail_tests {
+    use serde_json::Value;
+
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+    use crate::model::recipe::Recipe;
+    use crate::model::recipe_detail::RecipeDetail;
+    use crate::model::recipe_yield::RecipeYield;
+    use crate::quick_recipes::QuickRecipes;
+
+    #[test]
+    fn recipe_detail_is_scalable() {
+        let recipe = create_one_recipe_without_image();
+        let detail = serde_json::to_value(RecipeDetail::new(recipe.clone(), &QuickRecipes::default())).unwrap();
+        assert_eq!(detail["scalable"], Value::Bool(true));
+        assert_eq!(detail["title"], Value::String(recipe.title.clone()));
+
+        let no_servings = RecipeDetail::new(Recipe { default_servings: 0, recipe_yield: None, ..recipe.clone() }, &QuickRecipes::default());
+        assert_eq!(no_servings.scalable, false);
+        let with_yield = RecipeDetail::new(Recipe { default_servings: 0, recipe_yield: Some(RecipeYield::new(12.0, "cookies")), ..recipe }, &QuickRecipes::default());
+        assert_eq!(with_yield.scalable, true);
+    }
+
+    #[test]
+    fn recipe_detail_is_quick_at_the_threshold() {
+        let recipe = create_one_recipe_without_image();
+        let quick = QuickRecipes { max_minutes: 30 };
+        let detail = |minutes| RecipeDetail::new(Recipe { cooking_time_in_minutes: minutes, ..recipe.clone() }, &quick);
+        assert_eq!(detail(30).is_quick, true);
+        assert_eq!(detail(31).is_quick, false);
+        assert_eq!(serde_json::to_value(detail(30)).unwrap()["isQuick"], Value::Bool(true));
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::model::recipe::Recipe;
+use crate::model::step_timer::realign_step_timers;
+
+const JSON_ATTR_ID: &str = "id";
+/// fields an update does not write or which change on every write, they never conflict
+const UNMERGED_FIELDS: [&str; 9] = ["id", "version", "lastModified", "image", "archived", "slug", "status", "author", "fieldModified"];
+
+#[derive(Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// One changed field, `path` is a JSON pointer into the later version for added and changed
+/// fields and into the earlier version for removed ones
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub op: ChangeKind,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<Value>,
+}
+
+/// Field level diff of two versions of a recipe as they are serialized by the api.
+/// Arrays of objects with ids, like the ingredients, are matched by id, other arrays by position
+pub fn diff_recipes(from: &Recipe, to: &Recipe) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    let from = serde_json::to_value(from).unwrap_or(Value::Null);
+    let to = serde_json::to_value(to).unwrap_or(Value::Null);
+    diff_values("", "", &from, &to, &mut changes);
+    changes
+}
+
+/// top level fields with a change between the two versions, e.g. `title` or `ingredients`
+pub fn changed_fields(from: &Recipe, to: &Recipe) -> BTreeSet<String> {
+    diff_recipes(from, to).iter()
+        .filter_map(|change| change.path.split('/').nth(1))
+        .filter(|field| !UNMERGED_FIELDS.contains(field))
+        .map(String::from)
+        .collect()
+}
+
+/// Three way merge of an edit based on `base` into the stored `current` recipe.
+/// Succeeds when the fields changed by the edit were not changed since `base`,
+/// otherwise returns the fields changed on both sides
+pub fn merge_recipes(base: &Recipe, current: &Recipe, edit: &Recipe) -> Result<Recipe, Vec<String>> {
+    let edited_fields = changed_fields(base, edit);
+    let conflicts = changed_fields(base, current).intersection(&edited_fields).cloned().collect::<Vec<String>>();
+    if !conflicts.is_empty() {
+        return Err(conflicts);
+    }
+
+    let mut merged = current.clone();
+    for field in &edited_fields {
+        take_field(&mut merged, edit, field);
+    }
+    let timers_of = if edited_fields.contains("stepTimers") { edit } else { current };
+    merged.step_timers = realign_step_timers(&timers_of.step_timers, &timers_of.instructions, &merged.instructions);
+    merged.version = current.version + 1;
+    merged.last_modified = edit.last_modified;
+    Ok(merged)
+}
+
+fn take_field(merged: &mut Recipe, edit: &Recipe, field: &str) {
+    match field {
+        "cookingTimeInMinutes" => merged.cooking_time_in_minutes = edit.cooking_time_in_minutes,
+        "created" => merged.created = edit.created,
+        "ingredients" => merged.ingredients = edit.ingredients.clone(),
+        "difficulty" => merged.difficulty = edit.difficulty.clone(),
+        "description" => merged.description = edit.description.clone(),
+        "title" => merged.title = edit.title.clone(),
+        "tags" => merged.tags = edit.tags.clone(),
+        "instructions" => merged.instructions = edit.instructions.clone(),
+        "defaultServings" => merged.default_servings = edit.default_servings,
+        "yield" => merged.recipe_yield = edit.recipe_yield.clone(),
+        "cuisine" => merged.cuisine = edit.cuisine.clone(),
+        "language" => merged.language = edit.language.clone(),
+        "templateIds" => merged.template_ids = edit.template_ids.clone(),
+        "equipment" => merged.equipment = edit.equipment.clone(),
+        "notes" => merged.notes = edit.notes.clone(),
+        "source" => merged.source = edit.source.clone(),
+        "stepTimers" => merged.step_timers = edit.step_timers.clone(),
+        _ => warn!("Not merging unknown recipe field={}", field),
+    }
+}
+
+fn diff_values(from_path: &str, to_path: &str, from: &Value, to: &Value, changes: &mut Vec<FieldChange>) {
+    match (from, to) {
+        (Value::Object(from), Value::Object(to)) => diff_objects(from_path, to_path, from, to, changes),
+        (Value::Array(from), Value::Array(to)) if has_ids(from) && has_ids(to) => diff_arrays_by_id(from_path, to_path, from, to, changes),
+        (Value::Array(from), Value::Array(to)) => diff_arrays_by_index(from_path, to_path, from, to, changes),
+        _ if from != to => changes.push(FieldChange { op: ChangeKind::Changed, path: to_path.to_string(), old: Some(from.clone()), new: Some(to.clone()) }),
+        _ => {}
+    }
+}
+
+fn diff_objects(from_path: &str, to_path: &str, from: &Map<String, Value>, to: &Map<String, Value>, changes: &mut Vec<FieldChange>) {
+    for (key, from_value) in from {
+        match to.get(key) {
+            Some(to_value) => diff_values(&pointer(from_path, key), &pointer(to_path, key), from_value, to_value, changes),
+            None => changes.push(removed(&pointer(from_path, key), from_value)),
+        }
+    }
+    for (key, to_value) in to.iter().filter(|(key, _)| !from.contains_key(*key)) {
+        changes.push(added(&pointer(to_path, key), to_value));
+    }
+}
+
+fn diff_arrays_by_index(from_path: &str, to_path: &str, from: &[Value], to: &[Value], changes: &mut Vec<FieldChange>) {
+    for (index, from_value) in from.iter().enumerate() {
+        match to.get(index) {
+            Some(to_value) => diff_values(&pointer(from_path, &index.to_string()), &pointer(to_path, &index.to_string()), from_value, to_value, changes),
+            None => changes.push(removed(&pointer(from_path, &index.to_string()), from_value)),
+        }
+    }
+    for (index, to_value) in to.iter().enumerate().skip(from.len()) {
+        changes.push(added(&pointer(to_path, &index.to_string()), to_value));
+    }
+}
+
+fn diff_arrays_by_id(from_path: &str, to_path: &str, from: &[Value], to: &[Value], changes: &mut Vec<FieldChange>) {
+    let position = |values: &[Value], id: &Value| values.iter().position(|value| value.get(JSON_ATTR_ID) == Some(id));
+    for (from_index, from_value) in from.iter().enumerate() {
+        let from_pointer = pointer(from_path, &from_index.to_string());
+        match position(to, &from_value[JSON_ATTR_ID]) {
+            Some(to_index) => diff_values(&from_pointer, &pointer(to_path, &to_index.to_string()), from_value, &to[to_index], changes),
+            None => changes.push(removed(&from_pointer, from_value)),
+        }
+    }
+    for (to_index, to_value) in to.iter().enumerate() {
+        if position(from, &to_value[JSON_ATTR_ID]).is_none() {
+            changes.push(added(&pointer(to_path, &to_index.to_string()), to_value));
+        }
+    }
+}
+
+fn has_ids(values: &[Value]) -> bool {
+    values.iter().all(|value| value.get(JSON_ATTR_ID).is_some_and(Value::is_string))
+}
+
+fn added(path: &str, value: &Value) -> FieldChange {
+    FieldChange { op: ChangeKind::Added, path: path.to_string(), old: None, new: Some(value.clone()) }
+}
+
+fn removed(path: &str, value: &Value) -> FieldChange {
+    FieldChange { op: ChangeKind::Removed, path: path.to_string(), old: Some(value.clone()), new: None }
+}
+
+/// appends the escaped reference token as defined by RFC 6901
+fn pointer(path: &str, token: &str) -> String {
+    format!("{}/{}", path, token.replace('~', "~0").replace('/', "~1"))
+}
+
+
+#[cfg(test)]
+mod recipe_diff_tests {
+    use serde_json::json;
+
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+    use crate::model::ingredients::Ingredient;
+    use crate::model::measurement_unit::MeasurementUnit;
+    use crate::model::recipe_diff::{ChangeKind, changed_fields, diff_recipes, FieldChange, merge_recipes, pointer};
+    use crate::model::step_timer::StepTimer;
+
+    #[test]
+    fn diff_of_equal_recipes_is_empty() {
+        let recipe = create_one_recipe_without_image();
+        assert_eq!(diff_recipes(&recipe, &recipe).is_empty(), true);
+    }
+
+    #[test]
+    fn diff_title_change_and_ingredient_removal() {
+        let mut from = create_one_recipe_without_image();
+        from.title = "Carbonara".to_string();
+        from.ingredients = vec![
+            Ingredient::new("0", 200.0, "Spaghetti", MeasurementUnit::Gramm),
+            Ingredient::new("1", 2.0, "Eggs", MeasurementUnit::Piece),
+            Ingredient::new("2", 100.0, "Cream", MeasurementUnit::Milliliter),
+        ];
+        let mut to = from.clone();
+        to.title = "Spaghetti Carbonara".to_string();
+        to.ingredients.remove(2);
+        to.ingredients[1].amount = 3.0;
+
+        let changes = diff_recipes(&from, &to);
+        assert_eq!(changes.len(), 3, "{:?}", changes);
+        assert_eq!(changes.contains(&FieldChange {
+            op: ChangeKind::Changed,
+            path: "/title".to_string(),
+            old: Some(json!("Carbonara")),
+            new: Some(json!("Spaghetti Carbonara")),
+        }), true);
+        assert_eq!(changes.contains(&FieldChange {
+            op: ChangeKind::Changed,
+            path: "/ingredients/1/amount".to_string(),
+            old: Some(json!(2)),
+            new: Some(json!(3)),
+        }), true);
+        assert_eq!(changes.contains(&FieldChange {
+            op: ChangeKind::Removed,
+            path: "/ingredients/2".to_string(),
+            old: Some(json!({ "id": "2", "amount": 100, "title": "Cream", "measurementUnit": "Milliliter" })),
+            new: None,
+        }), true);
+    }
+
+    #[test]
+    fn ingredients_are_matched_by_id() {
+        let mut from = create_one_recipe_without_image();
+        from.ingredients = vec![
+            Ingredient::new("0", 200.0, "Spaghetti", MeasurementUnit::Gramm),
+            Ingredient::new("1", 2.0, "Eggs", MeasurementUnit::Piece),
+        ];
+        let mut to = from.clone();
+        to.ingredients.remove(0);
+        to.tags = vec!["pasta".to_string()];
+
+        let changes = diff_recipes(&from, &to);
+        assert_eq!(changes.iter().map(|change| (change.op, change.path.as_str())).collect::<Vec<_>>(), vec![
+            (ChangeKind::Removed, "/ingredients/0"),
+            (ChangeKind::Added, "/tags/0"),
+        ]);
+    }
+
+    #[test]
+    fn changed_fields_ignore_version_and_modification_date() {
+        let from = create_one_recipe_without_image();
+        let mut to = from.clone();
+        to.version = 2;
+        to.last_modified = to.last_modified + chrono::Duration::minutes(1);
+        to.tags = vec!["pasta".to_string()];
+        to.ingredients = vec![Ingredient::new("0", 200.0, "Spaghetti", MeasurementUnit::Gramm)];
+        assert_eq!(changed_fields(&from, &to).into_iter().collect::<Vec<String>>(), vec!["ingredients", "tags"]);
+    }
+
+    #[test]
+    fn merge_non_overlapping_edits() {
+        let base = create_one_recipe_without_image();
+        let mut current = base.clone();
+        current.version = 2;
+        current.title = "Carbonara".to_string();
+        let mut edit = base.clone();
+        edit.description = "Creamy".to_string();
+
+        let merged = merge_recipes(&base, &current, &edit).unwrap();
+        assert_eq!(merged.title, "Carbonara");
+        assert_eq!(merged.description, "Creamy");
+        assert_eq!(merged.version, 3);
+    }
+
+    #[test]
+    fn merge_keeps_timers_on_their_instructions() {
+        let mut base = create_one_recipe_without_image();
+        base.instructions = vec!["Boil water".to_string(), "Cook pasta".to_string()];
+        let mut current = base.clone();
+        current.version = 2;
+        current.instructions = vec!["Salt the water".to_string(), "Boil water".to_string(), "Cook pasta".to_string()];
+        let mut edit = base.clone();
+        edit.step_timers = vec![StepTimer::new(1, 540, Some("Pasta"))];
+
+        let merged = merge_recipes(&base, &current, &edit).unwrap();
+        assert_eq!(merged.step_timers, vec![StepTimer::new(2, 540, Some("Pasta"))]);
+
+        let mut base = merged.clone();
+        base.version = 3;
+        let mut current = base.clone();
+        current.version = 4;
+        current.instructions = vec!["Boil water".to_string()];
+        let mut edit = base.clone();
+        edit.description = "Quick".to_string();
+
+        let merged = merge_recipes(&base, &current, &edit).unwrap();
+        assert_eq!(merged.step_timers, vec![]);
+    }
+
+    #[test]
+    fn merge_overlapping_edits_conflicts() {
+        let base = create_one_recipe_without_image();
+        let mut current = base.clone();
+        current.version = 2;
+        current.title = "Carbonara".to_string();
+        current.tags = vec!["pasta".to_string()];
+        let mut edit = base.clone();
+        edit.title = "Amatriciana".to_string();
+        edit.description = "Spicy".to_string();
+
+        assert_eq!(merge_recipes(&base, &current, &edit).err().unwrap(), vec!["title"]);
+    }
+
+    #[test]
+    fn pointer_escapes_tokens() {
+        assert_eq!(pointer("/a", "b/c~d"), "/a/b~1c~0d");
+    }
+}
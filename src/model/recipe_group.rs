@@ -0,0 +1,100 @@
+use std::convert::TryFrom;
+
+use bson::{Bson, Document};
+use serde::{Deserialize, Serialize};
+
+use crate::model::recipe::RecipeFormatError;
+use crate::model::recipe_summary::RecipeSummary;
+
+const JSON_ATTR_KEY: &str = "_id";
+const JSON_ATTR_COUNT: &str = "count";
+const JSON_ATTR_RECIPES: &str = "recipes";
+
+/// Field the recipes are grouped by, `category` names the cuisine, recipes are listed once per tag they carry
+#[derive(Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    #[serde(alias = "category")]
+    Cuisine,
+    Difficulty,
+    Tag,
+}
+
+impl GroupBy {
+    /// the field of the stored recipes holding the group
+    pub fn field(&self) -> &'static str {
+        match self {
+            GroupBy::Cuisine => "cuisine",
+            GroupBy::Difficulty => "difficulty",
+            GroupBy::Tag => "tags",
+        }
+    }
+}
+
+/// Recipes sharing the value of the grouped field, with the amount of all of them and the first few as summaries
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RecipeGroup {
+    pub key: String,
+    pub count: u32,
+    pub recipes: Vec<RecipeSummary>,
+}
+
+impl TryFrom<Document> for RecipeGroup {
+    type Error = RecipeFormatError;
+
+    fn try_from(doc: Document) -> Result<Self, Self::Error> {
+        let recipes = doc.get_array(JSON_ATTR_RECIPES)
+            .map_err(|_| RecipeFormatError::from("Error getting recipes from recipe group document"))?
+            .iter()
+            .map(|recipe| match recipe {
+                Bson::Document(recipe) => RecipeSummary::try_from(recipe.clone()),
+                _ => Err(RecipeFormatError::from("Error getting recipe from recipe group document")),
+            })
+            .collect::<Result<Vec<RecipeSummary>, RecipeFormatError>>()?;
+
+        Ok(RecipeGroup {
+            key: doc.get_str(JSON_ATTR_KEY)
+                .map_err(|_| RecipeFormatError::from("Error getting key from recipe group document"))?
+                .to_string(),
+            count: doc.get_i32(JSON_ATTR_COUNT)
+                .map(|x| if x < 0 { 0 } else { x as u32 })
+                .map_err(|_| RecipeFormatError::from("Error getting count from recipe group document"))?,
+            recipes,
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod recipe_group_tests {
+    use std::convert::TryFrom;
+
+    use bson::oid::ObjectId;
+
+    use crate::model::recipe_group::{GroupBy, RecipeGroup};
+
+    #[test]
+    fn recipe_group_from_document() {
+        let id = ObjectId::new();
+        let group = RecipeGroup::try_from(doc! {
+            "_id": "Italian",
+            "count": 7,
+            "recipes": [{ "_id": id.clone(), "title": "Pasta", "difficulty": "Easy", "cookingTimeInMinutes": 20, "tags": ["fast"] }],
+        }).unwrap();
+        assert_eq!(group.key, "Italian");
+        assert_eq!(group.count, 7);
+        assert_eq!(group.recipes.len(), 1);
+        assert_eq!(group.recipes[0]._id, id);
+        assert_eq!(group.recipes[0].title, "Pasta");
+
+        assert_eq!(RecipeGroup::try_from(doc! { "_id": "Italian", "count": 7, "recipes": [1] }).is_err(), true);
+        assert_eq!(RecipeGroup::try_from(doc! { "_id": null, "count": 7, "recipes": [] }).is_err(), true);
+    }
+
+    #[test]
+    fn group_by_category_is_the_cuisine() {
+        assert_eq!(serde_json::from_str::<GroupBy>("\"category\"").unwrap(), GroupBy::Cuisine);
+        assert_eq!(serde_json::from_str::<GroupBy>("\"tag\"").unwrap().field(), "tags");
+        assert_eq!(serde_json::from_str::<GroupBy>("\"author\"").is_err(), true);
+    }
+}
@@ -0,0 +1,85 @@
+use serde::Serialize;
+
+use crate::model::difficulty::Difficulty;
+
+/// percentages are rounded to tenths
+const PERCENT_STEPS: u64 = 1000;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DifficultyShare {
+    pub difficulty: Difficulty,
+    pub count: u64,
+    pub percentage: f64,
+}
+
+/// Distribution of the recipes over the difficulties, every difficulty listed even without recipes
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RecipeStats {
+    pub total: u64,
+    pub difficulties: Vec<DifficultyShare>,
+}
+
+impl RecipeStats {
+    /// counts per stored difficulty, values which are no difficulty are left out
+    pub fn from_difficulty_counts(counts: &[(String, u64)]) -> Self {
+        let counts = Difficulty::ALL.iter()
+            .map(|difficulty| counts.iter()
+                .filter(|(stored, _)| *stored == difficulty.to_string())
+                .map(|(_, count)| count)
+                .sum::<u64>())
+            .collect::<Vec<u64>>();
+        let percentages = percentages(&counts);
+        RecipeStats {
+            total: counts.iter().sum(),
+            difficulties: Difficulty::ALL.iter().cloned()
+                .zip(counts.into_iter().zip(percentages))
+                .map(|(difficulty, (count, percentage))| DifficultyShare { difficulty, count, percentage })
+                .collect(),
+        }
+    }
+}
+
+/// Shares of the total in tenths of a percent which add up to exactly 100, all zero without any count.
+/// Each share is rounded down, the tenths missing then go to the largest remainders, the first on ties
+fn percentages(counts: &[u64]) -> Vec<f64> {
+    let total: u64 = counts.iter().sum();
+    if total == 0 {
+        return vec![0.0; counts.len()];
+    }
+    let mut steps = counts.iter().map(|count| count * PERCENT_STEPS / total).collect::<Vec<u64>>();
+    let mut by_remainder = (0..counts.len()).collect::<Vec<usize>>();
+    by_remainder.sort_by_key(|index| std::cmp::Reverse(counts[*index] * PERCENT_STEPS % total));
+    let missing = PERCENT_STEPS - steps.iter().sum::<u64>();
+    for index in by_remainder.into_iter().take(missing as usize) {
+        steps[index] += 1;
+    }
+    steps.into_iter().map(|steps| steps as f64 / 10.0).collect()
+}
+
+
+#[cfg(test)]
+mod recipe_stats_tests {
+    use crate::model::difficulty::Difficulty;
+    use crate::model::recipe_stats::{percentages, RecipeStats};
+
+    #[test]
+    fn percentages_add_up_to_100() {
+        assert_eq!(percentages(&[1, 1, 1]), vec![33.4, 33.3, 33.3]);
+        assert_eq!(percentages(&[2, 1, 1]), vec![50.0, 25.0, 25.0]);
+        assert_eq!(percentages(&[1, 5, 1]), vec![14.3, 71.4, 14.3]);
+        assert_eq!(percentages(&[0, 3, 0]), vec![0.0, 100.0, 0.0]);
+        assert_eq!(percentages(&[0, 0, 0]), vec![0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn stats_of_known_distribution() {
+        let stats = RecipeStats::from_difficulty_counts(&[("Hard".to_string(), 1), ("Easy".to_string(), 3), ("Unknown".to_string(), 4)]);
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.difficulties.iter().map(|share| (share.difficulty.clone(), share.count, share.percentage)).collect::<Vec<_>>(),
+                   vec![(Difficulty::Easy, 3, 75.0), (Difficulty::Medium, 0, 0.0), (Difficulty::Hard, 1, 25.0)]);
+
+        let empty = RecipeStats::from_difficulty_counts(&[]);
+        assert_eq!(empty.total, 0);
+        assert_eq!(empty.difficulties.iter().all(|share| share.count == 0 && share.percentage == 0.0), true);
+    }
+}
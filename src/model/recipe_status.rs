@@ -0,0 +1,64 @@
+use std::convert;
+use std::fmt;
+use std::fmt::Formatter;
+
+use bson::Bson;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::model::recipe::RecipeFormatError;
+
+/// Drafts are only visible to their author and admins, recipes stored before statuses existed are published
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RecipeStatus {
+    Draft,
+    #[default]
+    Published,
+}
+
+impl fmt::Display for RecipeStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            RecipeStatus::Draft => write!(f, "draft"),
+            RecipeStatus::Published => write!(f, "published"),
+        }
+    }
+}
+
+impl convert::TryFrom<&str> for RecipeStatus {
+    type Error = RecipeFormatError;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "draft" => Ok(RecipeStatus::Draft),
+            "published" => Ok(RecipeStatus::Published),
+            _ => Err(format!("Status '{}' does not match one predefined value", value).into())
+        }
+    }
+}
+
+impl From<RecipeStatus> for Bson {
+    fn from(status: RecipeStatus) -> Self {
+        Bson::String(status.to_string())
+    }
+}
+
+
+#[cfg(test)]
+mod recipe_status_tests {
+    use std::convert::TryFrom;
+
+    use bson::Bson;
+
+    use crate::model::recipe_status::RecipeStatus;
+
+    #[test]
+    fn status_round_trip() {
+        for status in [RecipeStatus::Draft, RecipeStatus::Published] {
+            assert_eq!(RecipeStatus::try_from(status.to_string().as_str()).unwrap(), status);
+        }
+        assert_eq!(Bson::from(RecipeStatus::Draft), Bson::String("draft".to_string()));
+        assert_eq!(RecipeStatus::try_from("Draft").is_err(), true);
+        assert_eq!(RecipeStatus::default(), RecipeStatus::Published);
+    }
+}
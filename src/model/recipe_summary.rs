@@ -0,0 +1,176 @@
+use std::convert::TryFrom;
+
+use bson::{Bson, Document};
+use bson::oid::ObjectId;
+use serde::{Serialize, Serializer};
+
+use crate::model::difficulty::Difficulty;
+use crate::model::recipe::{Recipe, RecipeFormatError};
+use crate::quick_recipes::QuickRecipes;
+
+const JSON_ATTR_THUMBNAIL: &str = "thumbnail";
+pub const JSON_ATTR_INGREDIENT_COUNT: &str = "ingredientCount";
+
+/// Recipe as listed, without ingredients, instructions and the other detail fields.
+/// The image is the thumbnail, or the original image when no thumbnail could be generated
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RecipeSummary {
+    #[serde(rename = "id")]
+    #[serde(serialize_with = "serialize_object_id")]
+    pub _id: ObjectId,
+    pub title: String,
+    pub difficulty: Difficulty,
+    #[serde(rename = "cookingTimeInMinutes")]
+    pub cooking_time_in_minutes: u32,
+    pub tags: Vec<String>,
+    pub image: Option<String>,
+    #[serde(rename = "ingredientCount")]
+    pub ingredient_count: u32,
+    /// derived by the listings from the configured threshold, left out elsewhere
+    #[serde(rename = "isQuick", skip_serializing_if = "Option::is_none")]
+    pub is_quick: Option<bool>,
+}
+
+fn serialize_object_id<S>(oid: &ObjectId, ser: S) -> Result<S::Ok, S::Error> where S: Serializer {
+    oid.to_string().serialize(ser)
+}
+
+impl RecipeSummary {
+    /// the fields read for a summary, both images as the thumbnail is missing for some recipes
+    pub fn projection() -> Document {
+        doc! {
+            "title": 1,
+            "difficulty": 1,
+            "cookingTimeInMinutes": 1,
+            "tags": 1,
+            "image": 1,
+            JSON_ATTR_THUMBNAIL: 1,
+            JSON_ATTR_INGREDIENT_COUNT: 1,
+        }
+    }
+
+    pub fn with_quick(self, quick: &QuickRecipes) -> Self {
+        Self { is_quick: Some(quick.is_quick(self.cooking_time_in_minutes)), ..self }
+    }
+
+    /// the stored count, counted from the ingredients for documents written before the count existed
+    fn extract_ingredient_count(doc: &Document) -> u32 {
+        match doc.get(JSON_ATTR_INGREDIENT_COUNT) {
+            Some(Bson::Int32(count)) => (*count).max(0) as u32,
+            Some(Bson::Int64(count)) => (*count).max(0) as u32,
+            _ => doc.get_array("ingredients").map_or(0, |ingredients| ingredients.len() as u32),
+        }
+    }
+}
+
+impl TryFrom<Document> for RecipeSummary {
+    type Error = RecipeFormatError;
+
+    fn try_from(doc: Document) -> Result<Self, Self::Error> {
+        let image = match doc.get(JSON_ATTR_THUMBNAIL) {
+            Some(Bson::String(thumbnail)) => Some(thumbnail.to_owned()),
+            _ => Recipe::extract_image(&doc)?,
+        };
+        Ok(Self {
+            _id: Recipe::extract_id(&doc)?,
+            title: Recipe::extract_title(&doc)?,
+            difficulty: Recipe::extract_difficulty(&doc)?,
+            cooking_time_in_minutes: Recipe::extract_cooking_time(&doc)?,
+            tags: Recipe::extract_tags(&doc)?,
+            image,
+            ingredient_count: RecipeSummary::extract_ingredient_count(&doc),
+            is_quick: None,
+        })
+    }
+}
+
+impl From<Recipe> for RecipeSummary {
+    fn from(recipe: Recipe) -> Self {
+        Self {
+            _id: recipe._id,
+            title: recipe.title,
+            difficulty: recipe.difficulty,
+            cooking_time_in_minutes: recipe.cooking_time_in_minutes,
+            tags: recipe.tags,
+            image: recipe.image_base64,
+            ingredient_count: recipe.ingredients.len() as u32,
+            is_quick: None,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod recipe_summary_tests {
+    use std::convert::TryFrom;
+
+    use bson::oid::ObjectId;
+    use serde_json::json;
+
+    use crate::model::difficulty::Difficulty;
+    use crate::model::recipe_summary::RecipeSummary;
+
+    #[test]
+    fn summary_prefers_thumbnail() {
+        let id = ObjectId::new();
+        let mut doc = doc! {
+            "_id": id.clone(),
+            "title": "Pasta",
+            "difficulty": "Medium",
+            "cookingTimeInMinutes": 20,
+            "tags": ["quick"],
+            "image": "original",
+            "thumbnail": "thumb",
+        };
+        let summary = RecipeSummary::try_from(doc.clone()).unwrap();
+        assert_eq!(summary.image, Some("thumb".to_string()));
+        assert_eq!(summary.difficulty, Difficulty::Medium);
+
+        doc.insert("thumbnail", bson::Bson::Null);
+        assert_eq!(RecipeSummary::try_from(doc.clone()).unwrap().image, Some("original".to_string()));
+        doc.remove("thumbnail");
+        doc.remove("image");
+        assert_eq!(RecipeSummary::try_from(doc).unwrap().image, None);
+    }
+
+    #[test]
+    fn summary_json() {
+        let id = ObjectId::new();
+        let summary = RecipeSummary::try_from(doc! {
+            "_id": id.clone(),
+            "title": "Pasta",
+            "difficulty": "Easy",
+            "cookingTimeInMinutes": 20,
+            "tags": [],
+        }).unwrap();
+        assert_eq!(serde_json::to_value(summary).unwrap(), json!({
+            "id": id.to_hex(),
+            "title": "Pasta",
+            "difficulty": "Easy",
+            "cookingTimeInMinutes": 20,
+            "tags": [],
+            "image": null,
+            "ingredientCount": 0,
+        }));
+    }
+
+    #[test]
+    fn summary_ingredient_count() {
+        let mut doc = doc! {
+            "_id": ObjectId::new(),
+            "title": "Pasta",
+            "difficulty": "Easy",
+            "cookingTimeInMinutes": 20,
+            "tags": [],
+            "ingredients": [{}, {}],
+        };
+        assert_eq!(RecipeSummary::try_from(doc.clone()).unwrap().ingredient_count, 2);
+        doc.insert("ingredientCount", 3);
+        assert_eq!(RecipeSummary::try_from(doc).unwrap().ingredient_count, 3);
+    }
+
+    #[test]
+    fn summary_missing_title_is_error() {
+        assert_eq!(RecipeSummary::try_from(doc! { "_id": ObjectId::new() }).is_err(), true);
+    }
+}
@@ -0,0 +1,96 @@
+use std::convert::TryFrom;
+
+use bson::{Bson, Document};
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::model::amount::{amount_from_bson, amount_to_bson, serialize_amount};
+use crate::model::recipe::RecipeFormatError;
+
+const JSON_ATTR_AMOUNT: &str = "amount";
+const JSON_ATTR_UNIT: &str = "unit";
+
+/// What a recipe produces when it is not measured in servings, e.g. `12 cookies` or `1 loaf`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecipeYield {
+    #[serde(serialize_with = "serialize_amount")]
+    pub amount: f64,
+    pub unit: String,
+}
+
+impl RecipeYield {
+    pub fn new(amount: f64, unit: &str) -> Self {
+        return Self { amount, unit: unit.to_string() };
+    }
+
+    pub fn validate(&self) -> Result<(), RecipeFormatError> {
+        if !self.amount.is_finite() || self.amount <= 0.0 {
+            return Err(format!("Yield amount must be greater than 0, was {}", self.amount).into());
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&Bson> for RecipeYield {
+    type Error = RecipeFormatError;
+
+    fn try_from(bson: &Bson) -> Result<Self, Self::Error> {
+        let doc = bson.as_document()
+            .ok_or("Error getting yield from document")?;
+
+        let amount = amount_from_bson(doc.get(JSON_ATTR_AMOUNT))
+            .ok_or("Error getting amount from yield from document")?;
+        let recipe_yield = Self {
+            amount,
+            unit: doc.get_str(JSON_ATTR_UNIT)
+                .map(String::from)
+                .map_err(|_| RecipeFormatError::from("Error getting unit from yield from document"))?,
+        };
+        recipe_yield.validate()?;
+        return Ok(recipe_yield);
+    }
+}
+
+impl From<RecipeYield> for Bson {
+    fn from(recipe_yield: RecipeYield) -> Self {
+        let mut doc = Document::new();
+        doc.insert(JSON_ATTR_AMOUNT, amount_to_bson(recipe_yield.amount));
+        doc.insert(JSON_ATTR_UNIT, recipe_yield.unit);
+        Bson::Document(doc)
+    }
+}
+
+
+#[cfg(test)]
+mod recipe_yield_tests {
+    use std::convert::TryFrom;
+
+    use bson::Bson;
+
+    use crate::model::recipe_yield::RecipeYield;
+
+    #[test]
+    fn recipe_yield_bson_round_trip() {
+        let recipe_yield = RecipeYield::new(12.0, "cookies");
+        let bson = Bson::from(recipe_yield.clone());
+        assert_eq!(RecipeYield::try_from(&bson).unwrap(), recipe_yield);
+
+        let bson = Bson::Document(doc! { "amount": 1, "unit": "loaf" });
+        assert_eq!(RecipeYield::try_from(&bson).unwrap(), RecipeYield::new(1.0, "loaf"));
+    }
+
+    #[test]
+    fn invalid_recipe_yield_from_bson() {
+        assert_eq!(RecipeYield::try_from(&Bson::Document(doc! { "amount": 0, "unit": "loaf" })).is_err(), true);
+        assert_eq!(RecipeYield::try_from(&Bson::Document(doc! { "amount": 1 })).is_err(), true);
+        assert_eq!(RecipeYield::try_from(&Bson::String("12 cookies".to_string())).is_err(), true);
+    }
+
+    #[test]
+    fn validate_recipe_yield() {
+        assert_eq!(RecipeYield::new(0.5, "loaf").validate().is_ok(), true);
+        assert_eq!(RecipeYield::new(0.0, "loaf").validate().is_err(), true);
+        assert_eq!(RecipeYield::new(-2.0, "cookies").validate().is_err(), true);
+        assert_eq!(RecipeYield::new(f64::NAN, "cookies").validate().is_err(), true);
+    }
+}
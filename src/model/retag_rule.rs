@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::recipe::RecipeFormatError;
+
+/// rules applied by one request
+pub const MAX_RETAG_RULES: usize = 50;
+
+/// Adds `addTag` to every recipe with an ingredient whose title contains `ingredientContains`, ignoring case
+#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct RetagRule {
+    #[serde(rename = "ingredientContains")]
+    pub ingredient_contains: String,
+    #[serde(rename = "addTag")]
+    pub add_tag: String,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct RetagRequest {
+    pub rules: Vec<RetagRule>,
+}
+
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct RetagRuleResult {
+    #[serde(rename = "ingredientContains")]
+    pub ingredient_contains: String,
+    #[serde(rename = "addTag")]
+    pub add_tag: String,
+    /// recipes which got the tag from this rule, the ones having it already are not counted
+    pub tagged: u64,
+}
+
+/// `tagged` counts every recipe changed by any of the rules once
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct RetagResult {
+    pub rules: Vec<RetagRuleResult>,
+    pub tagged: u64,
+}
+
+impl RetagRule {
+    /// the rule with the pattern trimmed and the tag normalized like the tags of recipes
+    pub fn trimmed(&self) -> RetagRule {
+        RetagRule { ingredient_contains: self.ingredient_contains.trim().to_string(), add_tag: self.add_tag.trim().to_lowercase() }
+    }
+}
+
+impl RetagRequest {
+    /// at least one and at most `MAX_RETAG_RULES` rules, none with a blank pattern or tag
+    pub fn validate(&self) -> Result<(), RecipeFormatError> {
+        if self.rules.is_empty() {
+            return Err(RecipeFormatError::empty("/rules"));
+        }
+        if self.rules.len() > MAX_RETAG_RULES {
+            return Err(RecipeFormatError::from(format!("At most {} rules can be applied at once, got {}", MAX_RETAG_RULES, self.rules.len())));
+        }
+        for (index, rule) in self.rules.iter().enumerate() {
+            if rule.ingredient_contains.trim().is_empty() {
+                return Err(RecipeFormatError::empty(&format!("/rules/{}/ingredientContains", index)));
+            }
+            if rule.add_tag.trim().is_empty() {
+                return Err(RecipeFormatError::empty(&format!("/rules/{}/addTag", index)));
+            }
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod retag_rule_tests {
+    use crate::model::retag_rule::{RetagRequest, RetagRule};
+
+    fn rule(ingredient_contains: &str, add_tag: &str) -> RetagRule {
+        RetagRule { ingredient_contains: ingredient_contains.to_string(), add_tag: add_tag.to_string() }
+    }
+
+    #[test]
+    fn validate_rules() {
+        assert_eq!(RetagRequest { rules: vec![rule("tofu", "vegetarian")] }.validate().is_ok(), true);
+        assert_eq!(RetagRequest::default().validate().unwrap_err().field, Some("/rules".to_string()));
+
+        let error = RetagRequest { rules: vec![rule("tofu", "vegetarian"), rule(" ", "vegan")] }.validate().unwrap_err();
+        assert_eq!(error.field, Some("/rules/1/ingredientContains".to_string()));
+        let error = RetagRequest { rules: vec![rule("tofu", "")] }.validate().unwrap_err();
+        assert_eq!(error.field, Some("/rules/0/addTag".to_string()));
+
+        assert_eq!(rule(" tofu ", "Vegetarian ").trimmed(), rule("tofu", "vegetarian"));
+    }
+}
@@ -0,0 +1,29 @@
+use std::convert::TryFrom;
+
+use bson::Document;
+use serde::Serialize;
+
+use crate::model::recipe::{Recipe, RecipeFormatError};
+
+const JSON_ATTR_SIMILARITY: &str = "similarity";
+
+/// Recipe ranked by the amount of normalized ingredient titles it shares with another recipe
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SimilarRecipe {
+    #[serde(flatten)]
+    pub recipe: Recipe,
+    pub similarity: u32,
+}
+
+impl TryFrom<Document> for SimilarRecipe {
+    type Error = RecipeFormatError;
+
+    fn try_from(doc: Document) -> Result<Self, Self::Error> {
+        return Ok(SimilarRecipe {
+            similarity: doc.get_i32(JSON_ATTR_SIMILARITY)
+                .map(|x| if x < 0 { 0 } else { x as u32 })
+                .map_err(|_| RecipeFormatError::from("Error getting similarity from document"))?,
+            recipe: Recipe::try_from(doc)?,
+        });
+    }
+}
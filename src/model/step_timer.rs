@@ -0,0 +1,175 @@
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+use bson::{Bson, Document};
+use serde::{Deserialize, Serialize};
+
+use crate::model::recipe::{Recipe, RecipeFormatError};
+
+const JSON_ATTR_STEP: &str = "step";
+const JSON_ATTR_DURATION_SECONDS: &str = "durationSeconds";
+const JSON_ATTR_TIMER_LABEL: &str = "timerLabel";
+
+/// Timer of one instruction, `step` is the index of the instruction it belongs to
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StepTimer {
+    pub step: usize,
+    #[serde(rename = "durationSeconds")]
+    pub duration_seconds: i64,
+    #[serde(rename = "timerLabel", default)]
+    pub timer_label: Option<String>,
+}
+
+impl StepTimer {
+    pub fn new(step: usize, duration_seconds: i64, timer_label: Option<&str>) -> Self {
+        Self { step, duration_seconds, timer_label: timer_label.map(String::from) }
+    }
+}
+
+impl TryFrom<&Bson> for StepTimer {
+    type Error = RecipeFormatError;
+
+    fn try_from(bson: &Bson) -> Result<Self, Self::Error> {
+        let doc = bson.as_document()
+            .ok_or("Error getting step timer from document")?;
+        Ok(Self {
+            step: doc.get_i64(JSON_ATTR_STEP)
+                .map_err(|_| RecipeFormatError::from("Error getting step from step timer from document"))? as usize,
+            duration_seconds: doc.get_i64(JSON_ATTR_DURATION_SECONDS)
+                .map_err(|_| RecipeFormatError::from("Error getting duration from step timer from document"))?,
+            timer_label: doc.get_str(JSON_ATTR_TIMER_LABEL).ok().map(String::from),
+        })
+    }
+}
+
+impl From<StepTimer> for Bson {
+    fn from(timer: StepTimer) -> Self {
+        let mut doc = Document::new();
+        doc.insert(JSON_ATTR_STEP, timer.step as i64);
+        doc.insert(JSON_ATTR_DURATION_SECONDS, timer.duration_seconds);
+        doc.insert(JSON_ATTR_TIMER_LABEL, timer.timer_label.map_or(Bson::Null, Bson::String));
+        Bson::Document(doc)
+    }
+}
+
+/// durations must not be negative, each instruction has at most one timer
+pub fn validate_step_timers(timers: &[StepTimer], instruction_count: usize) -> Result<(), RecipeFormatError> {
+    let mut steps = HashSet::new();
+    for timer in timers {
+        if timer.duration_seconds < 0 {
+            return Err(format!("The timer of step {} must not be negative, was {}", timer.step, timer.duration_seconds).into());
+        }
+        if timer.step >= instruction_count {
+            return Err(format!("The timer refers to step {} but the recipe has {} instructions", timer.step, instruction_count).into());
+        }
+        if !steps.insert(timer.step) {
+            return Err(format!("Step {} has more than one timer", timer.step).into());
+        }
+    }
+    Ok(())
+}
+
+/// The timers written against `instructions` moved along with their instructions into `changed`,
+/// so inserting or removing an instruction keeps each timer on its step. Timers of instructions
+/// no longer in `changed` are dropped, repeated instructions are matched in order
+pub fn realign_step_timers(timers: &[StepTimer], instructions: &[String], changed: &[String]) -> Vec<StepTimer> {
+    let mut taken = HashSet::new();
+    let mut realigned = timers.iter()
+        .filter_map(|timer| {
+            let instruction = instructions.get(timer.step)?;
+            let occurrence = instructions[..timer.step].iter().filter(|other| *other == instruction).count();
+            let step = changed.iter().enumerate()
+                .filter(|(_, other)| *other == instruction)
+                .map(|(step, _)| step)
+                .nth(occurrence)?;
+            taken.insert(step).then(|| StepTimer { step, ..timer.clone() })
+        })
+        .collect::<Vec<StepTimer>>();
+    realigned.sort_by_key(|timer| timer.step);
+    realigned
+}
+
+/// One instruction of the guided cooking view with its timer
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CookModeStep {
+    pub step: usize,
+    pub instruction: String,
+    #[serde(rename = "durationSeconds", skip_serializing_if = "Option::is_none")]
+    pub duration_seconds: Option<i64>,
+    #[serde(rename = "timerLabel", skip_serializing_if = "Option::is_none")]
+    pub timer_label: Option<String>,
+}
+
+/// the instructions in order, each with its timer when it has one
+pub fn cook_mode_steps(recipe: &Recipe) -> Vec<CookModeStep> {
+    recipe.instructions.iter().enumerate()
+        .map(|(step, instruction)| {
+            let timer = recipe.step_timers.iter().find(|timer| timer.step == step);
+            CookModeStep {
+                step,
+                instruction: instruction.clone(),
+                duration_seconds: timer.map(|timer| timer.duration_seconds),
+                timer_label: timer.and_then(|timer| timer.timer_label.clone()),
+            }
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod step_timer_tests {
+    use std::convert::TryFrom;
+
+    use bson::Bson;
+    use serde_json::json;
+
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+    use crate::model::step_timer::{cook_mode_steps, realign_step_timers, StepTimer, validate_step_timers};
+
+    #[test]
+    fn step_timer_bson_round_trip() {
+        let timer = StepTimer::new(1, 600, Some("Simmer"));
+        assert_eq!(StepTimer::try_from(&Bson::from(timer.clone())).unwrap(), timer);
+        let timer = StepTimer::new(0, 30, None);
+        assert_eq!(StepTimer::try_from(&Bson::from(timer.clone())).unwrap(), timer);
+        assert_eq!(StepTimer::try_from(&Bson::Document(doc! { "step": 0_i64 })).is_err(), true);
+    }
+
+    #[test]
+    fn validate_timers() {
+        assert_eq!(validate_step_timers(&[StepTimer::new(0, 0, None), StepTimer::new(1, 60, None)], 2).is_ok(), true);
+        assert_eq!(validate_step_timers(&[StepTimer::new(0, -1, None)], 2).is_err(), true);
+        assert_eq!(validate_step_timers(&[StepTimer::new(2, 60, None)], 2).is_err(), true);
+        assert_eq!(validate_step_timers(&[StepTimer::new(1, 60, None), StepTimer::new(1, 30, None)], 2).is_err(), true);
+    }
+
+    #[test]
+    fn realign_timers_to_changed_instructions() {
+        let instructions = vec!["Boil water".to_string(), "Cook pasta".to_string(), "Serve".to_string()];
+        let timers = vec![StepTimer::new(0, 300, None), StepTimer::new(1, 540, Some("Pasta"))];
+
+        let inserted = vec!["Salt the water".to_string(), "Boil water".to_string(), "Cook pasta".to_string(), "Serve".to_string()];
+        assert_eq!(realign_step_timers(&timers, &instructions, &inserted), vec![StepTimer::new(1, 300, None), StepTimer::new(2, 540, Some("Pasta"))]);
+
+        let removed = vec!["Cook pasta".to_string(), "Serve".to_string()];
+        assert_eq!(realign_step_timers(&timers, &instructions, &removed), vec![StepTimer::new(0, 540, Some("Pasta"))]);
+
+        assert_eq!(realign_step_timers(&[StepTimer::new(5, 60, None)], &instructions, &instructions), vec![]);
+
+        let repeated = vec!["Stir".to_string(), "Stir".to_string()];
+        let timers = vec![StepTimer::new(1, 60, None)];
+        let moved = vec!["Stir".to_string(), "Rest".to_string(), "Stir".to_string()];
+        assert_eq!(realign_step_timers(&timers, &repeated, &moved), vec![StepTimer::new(2, 60, None)]);
+    }
+
+    #[test]
+    fn cook_mode_steps_with_timers() {
+        let mut recipe = create_one_recipe_without_image();
+        recipe.instructions = vec!["Boil water".to_string(), "Cook pasta".to_string()];
+        recipe.step_timers = vec![StepTimer::new(1, 540, Some("Pasta"))];
+        assert_eq!(serde_json::to_value(cook_mode_steps(&recipe)).unwrap(), json!([
+            { "step": 0, "instruction": "Boil water" },
+            { "step": 1, "instruction": "Cook pasta", "durationSeconds": 540, "timerLabel": "Pasta" },
+        ]));
+    }
+}
@@ -0,0 +1,57 @@
+use std::convert::TryFrom;
+
+use bson::Document;
+use serde::Serialize;
+
+use crate::model::recipe::RecipeFormatError;
+
+const JSON_ATTR_TAGS: &str = "_id";
+const JSON_ATTR_COUNT: &str = "count";
+
+/// Set of tags occurring together on recipes, with the amount of recipes carrying exactly this set
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct TagCombo {
+    pub tags: Vec<String>,
+    pub count: u32,
+}
+
+impl TryFrom<Document> for TagCombo {
+    type Error = RecipeFormatError;
+
+    fn try_from(doc: Document) -> Result<Self, Self::Error> {
+        let tags = doc.get_array(JSON_ATTR_TAGS)
+            .map_err(|_| RecipeFormatError::from("Error getting tags from tag combo document"))?
+            .iter()
+            .map(|tag| tag.as_str().map(String::from)
+                .ok_or_else(|| RecipeFormatError::from("Error getting tag from tag combo document")))
+            .collect::<Result<Vec<String>, RecipeFormatError>>()?;
+
+        return Ok(TagCombo {
+            tags,
+            count: doc.get_i32(JSON_ATTR_COUNT)
+                .map(|x| if x < 0 { 0 } else { x as u32 })
+                .map_err(|_| RecipeFormatError::from("Error getting count from tag combo document"))?,
+        });
+    }
+}
+
+
+#[cfg(test)]
+mod tag_combo_tests {
+    use std::convert::TryFrom;
+
+    use crate::model::tag_combo::TagCombo;
+
+    #[test]
+    fn tag_combo_from_document() {
+        let combo = TagCombo::try_from(doc! { "_id": ["fast", "vegan"], "count": 3 }).unwrap();
+        assert_eq!(combo, TagCombo { tags: vec!["fast".to_string(), "vegan".to_string()], count: 3 });
+    }
+
+    #[test]
+    fn tag_combo_from_invalid_document_fails() {
+        assert_eq!(TagCombo::try_from(doc! { "_id": "vegan", "count": 3 }).is_err(), true);
+        assert_eq!(TagCombo::try_from(doc! { "_id": [1], "count": 3 }).is_err(), true);
+        assert_eq!(TagCombo::try_from(doc! { "_id": ["vegan"] }).is_err(), true);
+    }
+}
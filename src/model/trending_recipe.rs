@@ -0,0 +1,89 @@
+use std::convert::TryFrom;
+
+use bson::Document;
+use chrono::Duration;
+use serde::Serialize;
+
+use crate::model::recipe::RecipeFormatError;
+use crate::model::recipe_summary::RecipeSummary;
+
+const JSON_ATTR_VIEWS: &str = "views";
+
+/// Recipe summary with the amount of views inside the requested window
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TrendingRecipe {
+    #[serde(flatten)]
+    pub recipe: RecipeSummary,
+    pub views: u32,
+}
+
+impl TryFrom<Document> for TrendingRecipe {
+    type Error = RecipeFormatError;
+
+    fn try_from(doc: Document) -> Result<Self, Self::Error> {
+        return Ok(TrendingRecipe {
+            views: doc.get_i32(JSON_ATTR_VIEWS)
+                .map(|x| if x < 0 { 0 } else { x as u32 })
+                .map_err(|_| RecipeFormatError::from("Error getting views from trending recipe document"))?,
+            recipe: RecipeSummary::try_from(doc)?,
+        });
+    }
+}
+
+/// windows like `7d` or `12h`, at least one hour and at most 90 days
+pub fn parse_window(window: &str) -> Result<Duration, RecipeFormatError> {
+    let window = window.trim();
+    let invalid = || RecipeFormatError::from(format!("Window '{}' must be a number of days or hours like 7d or 12h", window));
+    if window.len() < 2 || !window.is_char_boundary(window.len() - 1) {
+        return Err(invalid());
+    }
+    let (amount, unit) = window.split_at(window.len() - 1);
+    let amount = amount.parse::<i64>().map_err(|_| invalid())?;
+    let duration = match unit {
+        "d" => Duration::days(amount),
+        "h" => Duration::hours(amount),
+        _ => return Err(invalid()),
+    };
+    if duration < Duration::hours(1) || duration > Duration::days(90) {
+        return Err(format!("Window '{}' must be between 1h and 90d", window).into());
+    }
+    Ok(duration)
+}
+
+
+#[cfg(test)]
+mod trending_recipe_tests {
+    use std::convert::TryFrom;
+
+    use bson::oid::ObjectId;
+    use chrono::Duration;
+
+    use crate::model::trending_recipe::{parse_window, TrendingRecipe};
+
+    #[test]
+    fn parse_window_test() {
+        assert_eq!(parse_window("7d").unwrap(), Duration::days(7));
+        assert_eq!(parse_window(" 12h ").unwrap(), Duration::hours(12));
+        assert_eq!(parse_window("0h").is_err(), true);
+        assert_eq!(parse_window("91d").is_err(), true);
+        assert_eq!(parse_window("7w").is_err(), true);
+        assert_eq!(parse_window("d").is_err(), true);
+        assert_eq!(parse_window("-1d").is_err(), true);
+        assert_eq!(parse_window("7ä").is_err(), true);
+    }
+
+    #[test]
+    fn trending_recipe_from_document() {
+        let doc = doc! {
+            "_id": ObjectId::new(),
+            "title": "Pasta",
+            "difficulty": "Easy",
+            "cookingTimeInMinutes": 20,
+            "tags": [],
+            "views": 12,
+        };
+        let trending = TrendingRecipe::try_from(doc).unwrap();
+        assert_eq!(trending.views, 12);
+        assert_eq!(trending.recipe.title, "Pasta");
+    }
+}
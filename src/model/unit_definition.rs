@@ -0,0 +1,123 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::fmt::Formatter;
+
+use bson::{Bson, Document};
+use serde::{Deserialize, Serialize};
+
+use crate::model::recipe::RecipeFormatError;
+
+const JSON_ATTR_NAME: &str = "name";
+const JSON_ATTR_FAMILY: &str = "family";
+const JSON_ATTR_FACTOR: &str = "factor";
+
+/// Units convert into each other within their family, the factors refer to gram, milliliter and piece
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitFamily {
+    Mass,
+    Volume,
+    Count,
+}
+
+impl fmt::Display for UnitFamily {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            UnitFamily::Mass => write!(f, "mass"),
+            UnitFamily::Volume => write!(f, "volume"),
+            UnitFamily::Count => write!(f, "count"),
+        }
+    }
+}
+
+impl TryFrom<&str> for UnitFamily {
+    type Error = RecipeFormatError;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "mass" => Ok(UnitFamily::Mass),
+            "volume" => Ok(UnitFamily::Volume),
+            "count" => Ok(UnitFamily::Count),
+            _ => Err(format!("Could not create UnitFamily from string: {}", value).into())
+        }
+    }
+}
+
+/// A unit of a family with its size in the base unit of the family, e.g. a cup is 240 milliliters
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UnitDefinition {
+    pub name: String,
+    pub family: UnitFamily,
+    pub factor: f64,
+}
+
+impl TryFrom<Document> for UnitDefinition {
+    type Error = RecipeFormatError;
+
+    fn try_from(doc: Document) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: doc.get_str(JSON_ATTR_NAME)
+                .map(String::from)
+                .map_err(|_| RecipeFormatError::from("Error getting name from unit document"))?,
+            family: doc.get_str(JSON_ATTR_FAMILY)
+                .map_err(|_| RecipeFormatError::from("Error getting family from unit document"))
+                .and_then(UnitFamily::try_from)?,
+            factor: doc.get_f64(JSON_ATTR_FACTOR)
+                .map_err(|_| RecipeFormatError::from("Error getting factor from unit document"))?,
+        })
+    }
+}
+
+impl From<UnitDefinition> for Document {
+    fn from(unit: UnitDefinition) -> Self {
+        let mut doc = Document::new();
+        doc.insert(JSON_ATTR_NAME, unit.name);
+        doc.insert(JSON_ATTR_FAMILY, Bson::String(unit.family.to_string()));
+        doc.insert(JSON_ATTR_FACTOR, unit.factor);
+        doc
+    }
+}
+
+impl UnitDefinition {
+    pub fn new(name: &str, family: UnitFamily, factor: f64) -> Self {
+        Self { name: name.to_string(), family, factor }
+    }
+
+    pub fn validate(&self) -> Result<(), RecipeFormatError> {
+        if self.name.trim().is_empty() {
+            return Err("The unit name must not be empty".into());
+        }
+        if !self.factor.is_finite() || self.factor <= 0.0 {
+            return Err(format!("The unit factor must be greater than 0, was {}", self.factor).into());
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod unit_definition_tests {
+    use std::convert::TryFrom;
+
+    use bson::Document;
+    use serde_json::json;
+
+    use crate::model::unit_definition::{UnitDefinition, UnitFamily};
+
+    #[test]
+    fn unit_document_round_trip() {
+        let unit = UnitDefinition::new("cup", UnitFamily::Volume, 240.0);
+        let doc = Document::from(unit.clone());
+        assert_eq!(doc, doc! { "name": "cup", "family": "volume", "factor": 240.0 });
+        assert_eq!(UnitDefinition::try_from(doc).unwrap(), unit);
+        assert_eq!(UnitDefinition::try_from(doc! { "name": "cup", "family": "length", "factor": 1.0 }).is_err(), true);
+    }
+
+    #[test]
+    fn validate_unit() {
+        assert_eq!(UnitDefinition::new("cup", UnitFamily::Volume, 240.0).validate().is_ok(), true);
+        assert_eq!(UnitDefinition::new(" ", UnitFamily::Volume, 240.0).validate().is_err(), true);
+        assert_eq!(UnitDefinition::new("cup", UnitFamily::Volume, 0.0).validate().is_err(), true);
+        assert_eq!(UnitDefinition::new("cup", UnitFamily::Volume, f64::NAN).validate().is_err(), true);
+        assert_eq!(serde_json::from_value::<UnitDefinition>(json!({ "name": "cup", "factor": 240 })).is_err(), true);
+    }
+}
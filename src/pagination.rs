@@ -1,24 +1,271 @@
+use bson::{Bson, Document};
 use serde::Deserialize;
 use serde::Serialize;
 
-#[derive(Deserialize, Serialize, Debug, Copy, Clone)]
+pub const DEFAULT_SORT_ENV: &str = "DEFAULT_SORT";
+
+/// Order of listings requested without `sorting`, configured via `DEFAULT_SORT` as `created asc` or
+/// `created desc`, oldest first when unset or invalid. Recipes created at the same time keep the order of their ids
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct DefaultSort {
+    pub sorting: i32,
+}
+
+impl Default for DefaultSort {
+    fn default() -> Self {
+        Self { sorting: 1 }
+    }
+}
+
+impl DefaultSort {
+    pub fn from_env() -> Self {
+        let sort = match std::env::var(DEFAULT_SORT_ENV) {
+            Ok(value) => Self::parse(&value).unwrap_or_else(|| {
+                warn!("Invalid {}={}, sorting by created asc", DEFAULT_SORT_ENV, value);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        info!("Loaded default sort={:?}", sort);
+        sort
+    }
+
+    /// `created`, `created asc` or `created desc`, ignoring case
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        match value.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            ["created"] | ["created", "asc"] => Some(Self { sorting: 1 }),
+            ["created", "desc"] => Some(Self { sorting: -1 }),
+            _ => None,
+        }
+    }
+
+    pub fn sort_document(&self) -> Document {
+        doc! { "created": self.sorting }
+    }
+}
+
+/// Either page style `page`/`items` (alias `pageSize`) or offset style `offset`/`limit`, both with `sorting`
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, Default)]
 pub struct Pagination {
     pub page: Option<usize>,
+    #[serde(alias = "pageSize")]
     pub items: Option<usize>,
     pub sorting: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
 }
 
 impl Pagination {
     pub fn is_fully_set(&self) -> bool {
-        return self.page.is_some() && self.page.unwrap() > 0
-            && self.items.is_some() && self.items.unwrap() > 0
+        let page_style = self.page.is_some() && self.page.unwrap() > 0
+            && self.items.is_some() && self.items.unwrap() > 0;
+        let offset_style = self.offset.is_some()
+            && self.limit.is_some() && self.limit.unwrap() > 0;
+        return !self.is_mixed_style() && (page_style || offset_style)
             && self.sorting.is_some() && (self.sorting.unwrap() == 1 || self.sorting.unwrap() == -1);
     }
 
     pub fn is_fully_empty(&self) -> bool {
-        return self.page.is_none() && self.items.is_none() && self.sorting.is_none();
+        return self.page.is_none() && self.items.is_none() && self.sorting.is_none()
+            && self.offset.is_none() && self.limit.is_none();
+    }
+
+    /// page and offset style parameters must not be combined in one request
+    pub fn is_mixed_style(&self) -> bool {
+        (self.page.is_some() || self.items.is_some()) && (self.offset.is_some() || self.limit.is_some())
+    }
+
+    /// number of results to skip, only meaningful once fully set. Saturates instead of
+    /// overflowing for absurd pages, which are beyond the last one anyway
+    pub fn skip(&self) -> usize {
+        match (self.page, self.items) {
+            (Some(page), Some(items)) => (page.max(1) - 1).saturating_mul(items),
+            _ => self.offset.unwrap_or(0),
+        }
+    }
+
+    /// number of results on a page, only meaningful once fully set
+    pub fn take(&self) -> usize {
+        self.items.or(self.limit).unwrap_or(0)
+    }
+
+    pub fn sort_document(&self) -> Document {
+        let mut doc = Document::new();
+        doc.insert("created", Bson::Int32(self.sorting.unwrap_or(1)));
+        return doc;
+    }
+
+    /// the pagination with the default direction once paged without `sorting`
+    pub fn with_default_sort(self, default: DefaultSort) -> Self {
+        match self.sorting {
+            None if !self.is_fully_empty() => Pagination { sorting: Some(default.sorting), ..self },
+            _ => self,
+        }
+    }
+
+    /// number of pages for the total, at least one so that an empty result still has a first page
+    pub fn last_page(&self, total: u64) -> Option<usize> {
+        let items = self.items.or(self.limit).filter(|items| *items > 0)?;
+        Some((total as usize).div_ceil(items).max(1))
+    }
+
+    /// probing past the end yields an empty page instead of an error, this tells clients it was the end
+    pub fn is_beyond_last_page(&self, total: u64) -> bool {
+        self.skip() > 0 && self.skip() as u64 >= total
+    }
+
+    /// RFC 5988 `Link` header value pointing at the first, previous, next and last page,
+    /// links to pages outside of `1..=last` are left out. The other query parameters are kept.
+    /// None for offset style requests, whose offsets need not fall on page boundaries
+    pub fn link_header(&self, path: &str, query: &str, total: u64) -> Option<String> {
+        let page = self.page.filter(|page| *page > 0)?;
+        let last = self.last_page(total)?;
+
+        let mut links = vec![(1, "first")];
+        if page > 1 && page <= last {
+            links.push((page - 1, "prev"));
+        }
+        if page < last {
+            links.push((page + 1, "next"));
+        }
+        links.push((last, "last"));
+
+        Some(links.into_iter()
+            .map(|(page, rel)| format!("<{}?{}>; rel=\"{}\"", path, with_page(query, page), rel))
+            .collect::<Vec<String>>()
+            .join(", "))
+    }
+}
+
+/// replaces the page parameter of the query string, appends it when missing
+fn with_page(query: &str, page: usize) -> String {
+    let page_param = format!("page={}", page);
+    let mut replaced = false;
+    let mut params = query.split('&')
+        .filter(|param| !param.is_empty())
+        .map(|param| if param.split('=').next() == Some("page") {
+            replaced = true;
+            page_param.clone()
+        } else {
+            param.to_string()
+        })
+        .collect::<Vec<String>>();
+    if !replaced {
+        params.push(page_param);
     }
+    params.join("&")
 }
 
 
+#[cfg(test)]
+mod pagination_tests {
+    use actix_web::web::Query;
+
+    use crate::pagination::{DefaultSort, Pagination};
+
+    fn page(page: usize) -> Pagination {
+        Pagination { page: Some(page), items: Some(10), sorting: Some(1), ..Pagination::default() }
+    }
+
+    fn offset(offset: usize) -> Pagination {
+        Pagination { offset: Some(offset), limit: Some(10), sorting: Some(1), ..Pagination::default() }
+    }
+
+    #[test]
+    fn link_header_on_middle_page() {
+        let header = page(3).link_header("/api/v1/recipes", "tags=vegan&page=3&items=10&sorting=1", 45).unwrap();
+        assert_eq!(header, [
+            r#"</api/v1/recipes?tags=vegan&page=1&items=10&sorting=1>; rel="first""#,
+            r#"</api/v1/recipes?tags=vegan&page=2&items=10&sorting=1>; rel="prev""#,
+            r#"</api/v1/recipes?tags=vegan&page=4&items=10&sorting=1>; rel="next""#,
+            r#"</api/v1/recipes?tags=vegan&page=5&items=10&sorting=1>; rel="last""#,
+        ].join(", "));
+    }
+
+    #[test]
+    fn link_header_on_first_and_last_page() {
+        let header = page(1).link_header("/recipes", "page=1&items=10&sorting=1", 20).unwrap();
+        assert_eq!(header.contains("rel=\"prev\""), false);
+        assert_eq!(header.contains("</recipes?page=2&items=10&sorting=1>; rel=\"next\""), true);
+
+        let header = page(2).link_header("/recipes", "page=2&items=10&sorting=1", 20).unwrap();
+        assert_eq!(header.contains("rel=\"next\""), false);
+        assert_eq!(header.contains("</recipes?page=2&items=10&sorting=1>; rel=\"last\""), true);
+    }
 
+    #[test]
+    fn link_header_without_results() {
+        let header = page(1).link_header("/recipes", "page=1&items=10&sorting=1", 0).unwrap();
+        assert_eq!(header, r#"</recipes?page=1&items=10&sorting=1>; rel="first", </recipes?page=1&items=10&sorting=1>; rel="last""#);
+
+        assert_eq!(Pagination::default().link_header("/recipes", "", 10), None);
+    }
+
+    #[test]
+    fn page_beyond_last_page() {
+        assert_eq!(page(1).last_page(0), Some(1));
+        assert_eq!(page(1).last_page(1), Some(1));
+        assert_eq!(page(1).last_page(10), Some(1));
+        assert_eq!(page(1).last_page(11), Some(2));
+        assert_eq!(page(5).last_page(45), Some(5));
+        assert_eq!(page(5).is_beyond_last_page(45), false);
+        assert_eq!(page(6).is_beyond_last_page(45), true);
+        assert_eq!(page(1).is_beyond_last_page(0), false);
+        assert_eq!(page(2).is_beyond_last_page(0), true);
+        assert_eq!(Pagination::default().is_beyond_last_page(0), false);
+        assert_eq!(offset(40).is_beyond_last_page(45), false);
+        assert_eq!(offset(45).is_beyond_last_page(45), true);
+        assert_eq!(page(999).is_beyond_last_page(3), true);
+        assert_eq!(page(usize::MAX).skip(), usize::MAX);
+        assert_eq!(page(usize::MAX).is_beyond_last_page(3), true);
+    }
+
+    #[test]
+    fn page_style() {
+        let pagination = Query::<Pagination>::from_query("page=3&pageSize=20&sorting=-1").unwrap().into_inner();
+        assert_eq!(pagination.is_fully_set(), true);
+        assert_eq!((pagination.skip(), pagination.take()), (40, 20));
+        assert_eq!(page(1).skip(), 0);
+        assert_eq!(Pagination { sorting: Some(2), ..page(1) }.is_fully_set(), false);
+        assert_eq!(Pagination { items: None, ..page(1) }.is_fully_set(), false);
+    }
+
+    #[test]
+    fn offset_style() {
+        let pagination = Query::<Pagination>::from_query("offset=15&limit=5&sorting=1").unwrap().into_inner();
+        assert_eq!(pagination.is_fully_set(), true);
+        assert_eq!((pagination.skip(), pagination.take()), (15, 5));
+        assert_eq!(offset(0).is_fully_set(), true);
+        assert_eq!(Pagination { limit: Some(0), ..offset(0) }.is_fully_set(), false);
+        assert_eq!(offset(15).link_header("/recipes", "offset=15&limit=10&sorting=1", 45), None);
+        assert_eq!(offset(15).last_page(45), Some(5));
+    }
+
+    #[test]
+    fn mixed_style_is_rejected() {
+        let pagination = Query::<Pagination>::from_query("page=2&limit=5&sorting=1").unwrap().into_inner();
+        assert_eq!(pagination.is_mixed_style(), true);
+        assert_eq!(pagination.is_fully_set(), false);
+        assert_eq!(pagination.is_fully_empty(), false);
+        assert_eq!(page(2).is_mixed_style(), false);
+        assert_eq!(offset(2).is_mixed_style(), false);
+    }
+
+    #[test]
+    fn default_sort_of_unsorted_pages() {
+        assert_eq!(DefaultSort::parse("created desc"), Some(DefaultSort { sorting: -1 }));
+        assert_eq!(DefaultSort::parse(" Created ASC "), Some(DefaultSort { sorting: 1 }));
+        assert_eq!(DefaultSort::parse("created"), Some(DefaultSort { sorting: 1 }));
+        assert_eq!(DefaultSort::parse("title desc"), None);
+
+        let newest_first = DefaultSort { sorting: -1 };
+        let pagination = Query::<Pagination>::from_query("page=2&items=10").unwrap().into_inner().with_default_sort(newest_first);
+        assert_eq!(pagination.sorting, Some(-1));
+        assert_eq!(pagination.is_fully_set(), true);
+        assert_eq!(page(2).with_default_sort(newest_first).sorting, Some(1));
+        assert_eq!(Pagination::default().with_default_sort(newest_first).is_fully_empty(), true);
+    }
+}
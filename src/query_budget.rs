@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use actix_web::{HttpRequest, web};
+use serde::Deserialize;
+
+use crate::model::recipe::RecipeFormatError;
+
+pub const QUERY_MAX_TIME_MS_ENV: &str = "QUERY_MAX_TIME_MS";
+const DEFAULT_QUERY_MAX_TIME_MS: u64 = 5000;
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct BudgetParams {
+    #[serde(rename = "maxTimeMs")]
+    pub max_time_ms: Option<u64>,
+}
+
+/// Time the database may spend on an aggregation before aborting it, passed as `maxTimeMS`.
+/// Requests may lower it with `?maxTimeMs=`, never raise it above `QUERY_MAX_TIME_MS`, 5 seconds by default
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct QueryBudget {
+    pub ceiling: Duration,
+}
+
+impl Default for QueryBudget {
+    fn default() -> Self {
+        Self { ceiling: Duration::from_millis(DEFAULT_QUERY_MAX_TIME_MS) }
+    }
+}
+
+impl QueryBudget {
+    pub fn from_env() -> Self {
+        let max_time_ms = std::env::var(QUERY_MAX_TIME_MS_ENV).ok()
+            .and_then(|millis| millis.trim().parse::<u64>().ok())
+            .filter(|millis| *millis > 0)
+            .unwrap_or(DEFAULT_QUERY_MAX_TIME_MS);
+        info!("Loaded query max time ms={}", max_time_ms);
+        Self { ceiling: Duration::from_millis(max_time_ms) }
+    }
+
+    /// the requested budget capped at the ceiling, the ceiling without one.
+    /// Zero is rejected since the database reads it as no limit at all
+    pub fn max_time(&self, params: BudgetParams) -> Result<Duration, RecipeFormatError> {
+        match params.max_time_ms {
+            None => Ok(self.ceiling),
+            Some(0) => Err(RecipeFormatError::from("maxTimeMs must be positive")),
+            Some(millis) => Ok(Duration::from_millis(millis).min(self.ceiling)),
+        }
+    }
+}
+
+/// the budget registered as app data, the default one without
+pub fn query_budget(req: &HttpRequest) -> QueryBudget {
+    req.app_data::<web::Data<QueryBudget>>()
+        .map_or_else(QueryBudget::default, |budget| *budget.get_ref())
+}
+
+
+#[cfg(test)]
+mod query_budget_tests {
+    use std::time::Duration;
+
+    use crate::query_budget::{BudgetParams, QueryBudget};
+
+    #[test]
+    fn max_time_capped_at_the_ceiling() {
+        let budget = QueryBudget { ceiling: Duration::from_millis(1000) };
+        assert_eq!(budget.max_time(BudgetParams::default()).unwrap(), Duration::from_millis(1000));
+        assert_eq!(budget.max_time(BudgetParams { max_time_ms: Some(20) }).unwrap(), Duration::from_millis(20));
+        assert_eq!(budget.max_time(BudgetParams { max_time_ms: Some(60000) }).unwrap(), Duration::from_millis(1000));
+        assert_eq!(budget.max_time(BudgetParams { max_time_ms: Some(0) }).is_err(), true);
+    }
+}
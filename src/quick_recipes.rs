@@ -0,0 +1,61 @@
+use actix_web::{HttpRequest, web};
+use bson::Document;
+
+pub const QUICK_RECIPE_MAX_MINUTES_ENV: &str = "QUICK_RECIPE_MAX_MINUTES";
+const DEFAULT_QUICK_RECIPE_MAX_MINUTES: u32 = 30;
+
+/// Recipes cooked in at most `QUICK_RECIPE_MAX_MINUTES` minutes, 30 by default, are quick.
+/// Nothing is stored, `isQuick` is derived when reading, so the threshold may change between deployments
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct QuickRecipes {
+    pub max_minutes: u32,
+}
+
+impl Default for QuickRecipes {
+    fn default() -> Self {
+        Self { max_minutes: DEFAULT_QUICK_RECIPE_MAX_MINUTES }
+    }
+}
+
+impl QuickRecipes {
+    pub fn from_env() -> Self {
+        let max_minutes = std::env::var(QUICK_RECIPE_MAX_MINUTES_ENV).ok()
+            .and_then(|minutes| minutes.trim().parse::<u32>().ok())
+            .unwrap_or(DEFAULT_QUICK_RECIPE_MAX_MINUTES);
+        info!("Loaded quick recipe max minutes={}", max_minutes);
+        Self { max_minutes }
+    }
+
+    pub fn is_quick(&self, cooking_time_in_minutes: u32) -> bool {
+        cooking_time_in_minutes <= self.max_minutes
+    }
+
+    /// the condition on the cooking time selecting the quick recipes, or all others
+    pub fn filter(&self, quick: bool) -> Document {
+        match quick {
+            true => doc! { "$lte": self.max_minutes },
+            false => doc! { "$gt": self.max_minutes },
+        }
+    }
+}
+
+/// the threshold registered as app data, the default one without
+pub fn quick_recipes(req: &HttpRequest) -> QuickRecipes {
+    req.app_data::<web::Data<QuickRecipes>>()
+        .map_or_else(QuickRecipes::default, |quick| *quick.get_ref())
+}
+
+
+#[cfg(test)]
+mod quick_recipes_tests {
+    use crate::quick_recipes::QuickRecipes;
+
+    #[test]
+    fn quick_up_to_the_threshold() {
+        let quick = QuickRecipes::default();
+        assert_eq!(quick.is_quick(29), true);
+        assert_eq!(quick.is_quick(30), true);
+        assert_eq!(quick.is_quick(31), false);
+        assert_eq!(QuickRecipes { max_minutes: 15 }.is_quick(30), false);
+    }
+}
@@ -0,0 +1,69 @@
+use mongodb::options::{ClientOptions, ReadPreference, ReadPreferenceOptions, SelectionCriteria};
+
+use crate::LogExtensionErr;
+
+pub const READ_PREFERENCE_ENV: &str = "READ_PREFERENCE";
+
+/// The members of the replica set the reads of the `Dao` go to, the primary when unset.
+///
+/// `READ_PREFERENCE=secondaryPreferred` moves reads to the secondaries while one is available and
+/// `nearest` to the member with the lowest latency, which offloads the primary of read heavy
+/// deployments. Reads from a secondary may lag behind the latest writes, so a recipe read right
+/// after its update can still show the old version. Writes and index builds always go to the primary
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReadPreferenceSettings {
+    pub read_preference: Option<ReadPreference>,
+}
+
+impl ReadPreferenceSettings {
+    /// falls back to the primary when the configuration is invalid
+    pub fn from_env() -> Self {
+        let settings = std::env::var(READ_PREFERENCE_ENV).ok()
+            .map_or(Ok(Self::default()), |mode| parse(&mode))
+            .log_if_err(|err| error!("Invalid read preference, reading from the primary. Err={}", err))
+            .unwrap_or_default();
+        info!("Loaded read preference settings={:?}", settings);
+        settings
+    }
+
+    /// the client handles every database and collection inherit the read preference from
+    pub fn apply(&self, options: &mut ClientOptions) {
+        options.selection_criteria = self.read_preference.clone().map(SelectionCriteria::ReadPreference);
+    }
+}
+
+fn parse(mode: &str) -> Result<ReadPreferenceSettings, String> {
+    let options = ReadPreferenceOptions::default();
+    let read_preference = match mode.trim().to_lowercase().as_str() {
+        "" => return Ok(ReadPreferenceSettings::default()),
+        "primary" => ReadPreference::Primary,
+        "primarypreferred" => ReadPreference::PrimaryPreferred { options },
+        "secondary" => ReadPreference::Secondary { options },
+        "secondarypreferred" => ReadPreference::SecondaryPreferred { options },
+        "nearest" => ReadPreference::Nearest { options },
+        _ => return Err(format!("Unknown read preference {}, expected one of primary, primaryPreferred, secondary, secondaryPreferred or nearest", mode)),
+    };
+    Ok(ReadPreferenceSettings { read_preference: Some(read_preference) })
+}
+
+
+#[cfg(test)]
+pub mod read_preference_tests {
+    use mongodb::options::{ReadPreference, ReadPreferenceOptions};
+
+    use crate::read_preference::{parse, ReadPreferenceSettings};
+
+    pub fn secondary_preferred() -> ReadPreferenceSettings {
+        ReadPreferenceSettings { read_preference: Some(ReadPreference::SecondaryPreferred { options: ReadPreferenceOptions::default() }) }
+    }
+
+    #[test]
+    fn parse_read_preference() {
+        assert_eq!(parse(""), Ok(ReadPreferenceSettings::default()));
+        assert_eq!(parse("primary"), Ok(ReadPreferenceSettings { read_preference: Some(ReadPreference::Primary) }));
+        assert_eq!(parse(" secondaryPreferred "), Ok(secondary_preferred()));
+        assert_eq!(parse("nearest").unwrap().read_preference,
+                   Some(ReadPreference::Nearest { options: ReadPreferenceOptions::default() }));
+        assert_eq!(parse("anywhere").is_err(), true);
+    }
+}
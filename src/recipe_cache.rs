@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use bson::oid::ObjectId;
+use serde::Serialize;
+
+use crate::model::recipe::Recipe;
+
+pub const RECIPE_CACHE_TTL_ENV: &str = "RECIPE_CACHE_TTL_SECONDS";
+pub const RECIPE_CACHE_CAPACITY_ENV: &str = "RECIPE_CACHE_CAPACITY";
+pub const RECIPE_CACHE_STALE_ENV: &str = "RECIPE_CACHE_STALE_SECONDS";
+/// set on responses with a recipe of the cache served while the database is unavailable
+pub const STALE_HEADER: &str = "x-stale";
+pub const DEFAULT_RECIPE_CACHE_CAPACITY: usize = 1000;
+
+#[derive(Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RecipeCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    /// recipes served from the cache while the database was unavailable
+    pub stale: u64,
+}
+
+/// Token of a missed read, a recipe loaded for it is only kept when the recipe was not changed meanwhile
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct CacheMiss {
+    generation: u64,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    recipes: HashMap<ObjectId, Entry>,
+    /// increased by every invalidation, reads missing before it may not fill the cache
+    generation: u64,
+    last_use: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    stored_at: Instant,
+    last_use: u64,
+    recipe: Recipe,
+}
+
+/// Least recently used recipes read by id, each kept for a short time. Switched on by
+/// `RECIPE_CACHE_TTL_SECONDS`, holding at most `RECIPE_CACHE_CAPACITY` recipes.
+/// Every write of the dao invalidates the written recipes.
+/// `RECIPE_CACHE_STALE_SECONDS` keeps the recipes as last known good for longer, they are only served
+/// from then on while the database is unavailable. It works without `RECIPE_CACHE_TTL_SECONDS` as well
+#[derive(Debug, Clone)]
+pub struct RecipeCache {
+    ttl: Duration,
+    stale_ttl: Duration,
+    capacity: usize,
+    entries: Arc<Mutex<Entries>>,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+    stale: Arc<AtomicU64>,
+}
+
+impl Default for RecipeCache {
+    fn default() -> Self { Self::new(Duration::from_secs(0), DEFAULT_RECIPE_CACHE_CAPACITY) }
+}
+
+impl RecipeCache {
+    /// a ttl or capacity of zero switches the cache off
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            stale_ttl: Duration::from_secs(0),
+            capacity,
+            entries: Arc::new(Mutex::new(Entries::default())),
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
+            stale: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// keeps the recipes up to `stale_ttl` after they were read for `get_stale`, zero serves no stale recipes
+    pub fn with_stale_ttl(self, stale_ttl: Duration) -> Self {
+        Self { stale_ttl, ..self }
+    }
+
+    pub fn from_env() -> Self {
+        let ttl = std::env::var(RECIPE_CACHE_TTL_ENV).ok()
+            .and_then(|seconds| seconds.trim().parse::<u64>().ok())
+            .unwrap_or(0);
+        let capacity = std::env::var(RECIPE_CACHE_CAPACITY_ENV).ok()
+            .and_then(|capacity| capacity.trim().parse::<usize>().ok())
+            .unwrap_or(DEFAULT_RECIPE_CACHE_CAPACITY);
+        let stale_ttl = std::env::var(RECIPE_CACHE_STALE_ENV).ok()
+            .and_then(|seconds| seconds.trim().parse::<u64>().ok())
+            .unwrap_or(0);
+        let cache = Self::new(Duration::from_secs(ttl), capacity).with_stale_ttl(Duration::from_secs(stale_ttl));
+        info!("Loaded recipe cache ttl={:?}, stale_ttl={:?}, capacity={}", cache.ttl, cache.stale_ttl, cache.capacity);
+        cache
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.retention().is_zero() && self.capacity > 0
+    }
+
+    /// how long a recipe is kept after it was read, fresh or as last known good
+    fn retention(&self) -> Duration {
+        self.ttl.max(self.stale_ttl)
+    }
+
+    /// the cached recipe, or the token to store the recipe read instead
+    pub fn get(&self, id: &ObjectId) -> Result<Recipe, CacheMiss> {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let generation = entries.generation;
+        entries.last_use += 1;
+        let last_use = entries.last_use;
+        let cached = match entries.recipes.get_mut(id) {
+            Some(entry) if entry.stored_at.elapsed() < self.ttl => {
+                entry.last_use = last_use;
+                Some(entry.recipe.clone())
+            }
+            Some(entry) if entry.stored_at.elapsed() < self.retention() => None,
+            Some(_) => {
+                entries.recipes.remove(id);
+                None
+            }
+            None => None,
+        };
+        match cached {
+            Some(recipe) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Ok(recipe)
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Err(CacheMiss { generation })
+            }
+        }
+    }
+
+    /// the token of a read starting now, a query shared by coalesced reads takes it when it starts
+    pub fn miss(&self) -> CacheMiss {
+        CacheMiss { generation: self.entries.lock().unwrap_or_else(PoisonError::into_inner).generation }
+    }
+
+    /// keeps the recipe read after the miss, evicting the least recently used one when full
+    pub fn put(&self, miss: CacheMiss, recipe: Recipe) {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if entries.generation != miss.generation {
+            return;
+        }
+        if entries.recipes.len() >= self.capacity && !entries.recipes.contains_key(&recipe._id) {
+            let oldest = entries.recipes.iter()
+                .min_by_key(|(_, entry)| entry.last_use)
+                .map(|(id, _)| id.clone());
+            if let Some(oldest) = oldest {
+                entries.recipes.remove(&oldest);
+            }
+        }
+        entries.last_use += 1;
+        let last_use = entries.last_use;
+        entries.recipes.insert(recipe._id.clone(), Entry { stored_at: Instant::now(), last_use, recipe });
+    }
+
+    /// the last known recipe for when the database is unavailable, None unless stale recipes are kept
+    pub fn get_stale(&self, id: &ObjectId) -> Option<Recipe> {
+        if self.stale_ttl.is_zero() {
+            return None;
+        }
+        let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let recipe = entries.recipes.get(id)
+            .filter(|entry| entry.stored_at.elapsed() < self.retention())
+            .map(|entry| entry.recipe.clone())?;
+        self.stale.fetch_add(1, Ordering::Relaxed);
+        Some(recipe)
+    }
+
+    /// to be called after every write of the recipes
+    pub fn invalidate<'a>(&self, ids: impl IntoIterator<Item=&'a ObjectId>) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries.generation += 1;
+        for id in ids {
+            entries.recipes.remove(id);
+        }
+    }
+
+    pub fn stats(&self) -> RecipeCacheStats {
+        RecipeCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.lock().unwrap_or_else(PoisonError::into_inner).recipes.len(),
+            stale: self.stale.load(Ordering::Relaxed),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod recipe_cache_tests {
+    use std::time::Duration;
+
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+    use crate::recipe_cache::{RecipeCache, RecipeCacheStats};
+
+    #[test]
+    fn miss_then_hit() {
+        let cache = RecipeCache::new(Duration::from_secs(60), 10);
+        let recipe = create_one_recipe_without_image();
+
+        let miss = cache.get(&recipe._id).unwrap_err();
+        cache.put(miss, recipe.clone());
+        assert_eq!(cache.get(&recipe._id), Ok(recipe));
+        assert_eq!(cache.stats(), RecipeCacheStats { hits: 1, misses: 1, entries: 1, stale: 0 });
+    }
+
+    #[test]
+    fn expired_recipe_is_a_miss() {
+        let cache = RecipeCache::new(Duration::from_nanos(1), 10);
+        let recipe = create_one_recipe_without_image();
+
+        cache.put(cache.get(&recipe._id).unwrap_err(), recipe.clone());
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(cache.get(&recipe._id).is_err(), true);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn invalidated_recipe_is_a_miss() {
+        let cache = RecipeCache::new(Duration::from_secs(60), 10);
+        let recipe = create_one_recipe_without_image();
+        cache.put(cache.get(&recipe._id).unwrap_err(), recipe.clone());
+
+        cache.invalidate(std::iter::once(&recipe._id));
+        let stale = cache.get(&recipe._id).unwrap_err();
+        cache.invalidate(std::iter::once(&recipe._id));
+        cache.put(stale, recipe.clone());
+        assert_eq!(cache.stats(), RecipeCacheStats { hits: 0, misses: 2, entries: 0, stale: 0 });
+
+        let started = cache.miss();
+        cache.invalidate(std::iter::once(&recipe._id));
+        cache.put(started, recipe.clone());
+        assert_eq!(cache.stats().entries, 0);
+        cache.put(cache.miss(), recipe);
+        assert_eq!(cache.stats().entries, 1);
+    }
+
+    #[test]
+    fn least_recently_used_is_evicted() {
+        let cache = RecipeCache::new(Duration::from_secs(60), 2);
+        let recipes = (0..3).map(|_| {
+            let mut recipe = create_one_recipe_without_image();
+            recipe._id = bson::oid::ObjectId::new();
+            recipe
+        }).collect::<Vec<_>>();
+        cache.put(cache.get(&recipes[0]._id).unwrap_err(), recipes[0].clone());
+        cache.put(cache.get(&recipes[1]._id).unwrap_err(), recipes[1].clone());
+        assert_eq!(cache.get(&recipes[0]._id).is_ok(), true);
+
+        cache.put(cache.get(&recipes[2]._id).unwrap_err(), recipes[2].clone());
+        assert_eq!(cache.get(&recipes[0]._id).is_ok(), true);
+        assert_eq!(cache.get(&recipes[1]._id).is_err(), true);
+        assert_eq!(cache.get(&recipes[2]._id).is_ok(), true);
+    }
+
+    #[test]
+    fn stale_recipe_is_kept_past_its_ttl() {
+        let cache = RecipeCache::new(Duration::from_nanos(1), 10).with_stale_ttl(Duration::from_secs(60));
+        let recipe = create_one_recipe_without_image();
+        assert_eq!(cache.get_stale(&recipe._id), None);
+
+        cache.put(cache.get(&recipe._id).unwrap_err(), recipe.clone());
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(cache.get(&recipe._id).is_err(), true);
+        assert_eq!(cache.get_stale(&recipe._id), Some(recipe.clone()));
+        assert_eq!(cache.stats(), RecipeCacheStats { hits: 0, misses: 2, entries: 1, stale: 1 });
+
+        cache.invalidate(std::iter::once(&recipe._id));
+        assert_eq!(cache.get_stale(&recipe._id), None);
+    }
+
+    #[test]
+    fn no_stale_recipes_by_default() {
+        let cache = RecipeCache::new(Duration::from_nanos(1), 10);
+        let recipe = create_one_recipe_without_image();
+        cache.put(cache.get(&recipe._id).unwrap_err(), recipe.clone());
+        assert_eq!(cache.get_stale(&recipe._id), None);
+    }
+
+    #[test]
+    fn cache_is_off_by_default() {
+        assert_eq!(RecipeCache::default().is_enabled(), false);
+        assert_eq!(RecipeCache::new(Duration::from_secs(0), 1).with_stale_ttl(Duration::from_secs(5)).is_enabled(), true);
+        assert_eq!(RecipeCache::new(Duration::from_secs(5), 0).is_enabled(), false);
+        assert_eq!(RecipeCache::new(Duration::from_secs(5), 1).is_enabled(), true);
+    }
+}
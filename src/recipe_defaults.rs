@@ -0,0 +1,123 @@
+use std::convert::TryFrom;
+
+use serde_json::Value;
+
+use crate::LogExtensionErr;
+use crate::model::difficulty::Difficulty;
+use crate::model::recipe::normalize_tags;
+
+pub const DEFAULT_DIFFICULTY_ENV: &str = "DEFAULT_DIFFICULTY";
+pub const DEFAULT_SERVINGS_ENV: &str = "DEFAULT_SERVINGS";
+pub const DEFAULT_CUISINE_ENV: &str = "DEFAULT_CUISINE";
+pub const DEFAULT_TAGS_ENV: &str = "DEFAULT_TAGS";
+const JSON_ATTR_DIFFICULTY: &str = "difficulty";
+const JSON_ATTR_DEFAULT_SERVINGS: &str = "defaultServings";
+const JSON_ATTR_CUISINE: &str = "cuisine";
+const JSON_ATTR_TAGS: &str = "tags";
+const LIST_SEPARATOR: char = ',';
+
+/// Values for the fields a created recipe omits, each configured on its own:
+/// - `DEFAULT_DIFFICULTY=Medium` and `DEFAULT_SERVINGS=4`, Easy and 2 when unset or invalid
+/// - `DEFAULT_CUISINE=Italian`, no cuisine when unset
+/// - `DEFAULT_TAGS=uncategorized,new` comma separated, also for recipes sent with no tags, none when unset
+///
+/// A field sent by the client always wins over its default. The defaults go through the same validation
+/// as sent values, e.g. a cuisine missing in the classification allowlist rejects the recipe
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecipeDefaults {
+    pub difficulty: Difficulty,
+    pub servings: u32,
+    pub cuisine: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl Default for RecipeDefaults {
+    fn default() -> Self { Self { difficulty: Difficulty::Easy, servings: 2, cuisine: None, tags: vec![] } }
+}
+
+impl RecipeDefaults {
+    pub fn from_env() -> Self {
+        let fallback = RecipeDefaults::default();
+        let difficulty = std::env::var(DEFAULT_DIFFICULTY_ENV).ok()
+            .and_then(|difficulty| Difficulty::try_from(difficulty.as_str())
+                .log_if_err(|err| error!("Ignoring default difficulty. Err={}", err.error))
+                .ok())
+            .unwrap_or(fallback.difficulty);
+        let servings = std::env::var(DEFAULT_SERVINGS_ENV).ok()
+            .and_then(|servings| servings.parse::<u32>().ok())
+            .filter(|servings| *servings > 0)
+            .unwrap_or(fallback.servings);
+        let cuisine = std::env::var(DEFAULT_CUISINE_ENV).ok()
+            .map(|cuisine| cuisine.trim().to_string())
+            .filter(|cuisine| !cuisine.is_empty());
+        let tags = std::env::var(DEFAULT_TAGS_ENV)
+            .map(|tags| normalize_tags(&tags.split(LIST_SEPARATOR).map(String::from).collect::<Vec<String>>()))
+            .unwrap_or_default();
+        let defaults = Self { difficulty, servings, cuisine, tags };
+        info!("Loaded recipe defaults={:?}", defaults);
+        defaults
+    }
+
+    /// fills the missing or null fields of a recipe body, other bodies are left as they are
+    pub fn apply(&self, recipe: &mut Value) {
+        if let Value::Object(recipe) = recipe {
+            if recipe.get(JSON_ATTR_DIFFICULTY).is_none_or(Value::is_null) {
+                recipe.insert(JSON_ATTR_DIFFICULTY.to_string(), Value::String(self.difficulty.to_string()));
+            }
+            if recipe.get(JSON_ATTR_DEFAULT_SERVINGS).is_none_or(Value::is_null) {
+                recipe.insert(JSON_ATTR_DEFAULT_SERVINGS.to_string(), Value::from(self.servings));
+            }
+            if let Some(cuisine) = &self.cuisine {
+                if recipe.get(JSON_ATTR_CUISINE).is_none_or(Value::is_null) {
+                    recipe.insert(JSON_ATTR_CUISINE.to_string(), Value::from(cuisine.clone()));
+                }
+            }
+            let without_tags = recipe.get(JSON_ATTR_TAGS)
+                .is_none_or(|tags| tags.is_null() || tags.as_array().is_some_and(Vec::is_empty));
+            if !self.tags.is_empty() && without_tags {
+                recipe.insert(JSON_ATTR_TAGS.to_string(), Value::from(self.tags.clone()));
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod recipe_defaults_tests {
+    use serde_json::json;
+
+    use crate::model::difficulty::Difficulty;
+    use crate::recipe_defaults::RecipeDefaults;
+
+    #[test]
+    fn apply_fills_missing_fields() {
+        let defaults = RecipeDefaults { difficulty: Difficulty::Medium, servings: 4, ..RecipeDefaults::default() };
+        let mut recipe = json!({ "title": "Soup", "difficulty": null });
+        defaults.apply(&mut recipe);
+        assert_eq!(recipe, json!({ "title": "Soup", "difficulty": "Medium", "defaultServings": 4 }));
+    }
+
+    #[test]
+    fn apply_default_cuisine_and_tags() {
+        let defaults = RecipeDefaults { cuisine: Some("Italian".to_string()), tags: vec!["uncategorized".to_string()], ..RecipeDefaults::default() };
+        let mut recipe = json!({ "tags": [] });
+        defaults.apply(&mut recipe);
+        assert_eq!(recipe, json!({ "difficulty": "Easy", "defaultServings": 2, "cuisine": "Italian", "tags": ["uncategorized"] }));
+
+        let mut recipe = json!({ "cuisine": "French", "tags": ["soup"] });
+        defaults.apply(&mut recipe);
+        assert_eq!(recipe["cuisine"], "French");
+        assert_eq!(recipe["tags"], json!(["soup"]));
+    }
+
+    #[test]
+    fn apply_keeps_sent_fields() {
+        let mut recipe = json!({ "difficulty": "Hard", "defaultServings": 1 });
+        RecipeDefaults::default().apply(&mut recipe);
+        assert_eq!(recipe, json!({ "difficulty": "Hard", "defaultServings": 1 }));
+
+        let mut not_a_recipe = json!([1, 2]);
+        RecipeDefaults::default().apply(&mut not_a_recipe);
+        assert_eq!(not_a_recipe, json!([1, 2]));
+    }
+}
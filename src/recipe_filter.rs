@@ -0,0 +1,566 @@
+use std::convert::TryFrom;
+
+use bson::{Bson, Document};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::{Identity, Role};
+use crate::model::difficulty::Difficulty;
+use crate::model::recipe::{normalize_equipment, normalize_tags, Recipe, RecipeFormatError};
+use crate::model::recipe_status::RecipeStatus;
+use crate::model::recipe_summary::JSON_ATTR_INGREDIENT_COUNT;
+use crate::quick_recipes::QuickRecipes;
+
+const LIST_SEPARATOR: char = ',';
+const JSON_ATTR_LAST_MODIFIED: &str = "last_modified";
+
+/// Query parameters narrowing down the recipes of a listing.
+/// List values are comma separated, e.g. `?tags=vegan,fast&difficulty=Easy,Medium`,
+/// `?cuisine=Italian,French` selects recipes of one of the cuisines,
+/// `?equipment=Oven,blender` selects recipes needing all of the equipment, compared normalized,
+/// `?maxDifficulty=Medium` selects all recipes not harder than medium,
+/// `?q=pasta` searches title and description via the text index,
+/// `?q=spagetti&fuzzy=true` ranks the titles by their edit distance to `q` instead, tolerating typos,
+/// `?createdAfter=2020-09-01T00:00:00Z` and friends take inclusive RFC3339 bounds,
+/// `?minIngredients=1&maxIngredients=5` bounds the amount of ingredients inclusively,
+/// `?isQuick=true` selects the recipes cooked within the threshold of `quick_recipes`, `false` the others,
+/// archived recipes are left out unless `?includeArchived=true`.
+/// The author is no query parameter, it is set from the identity for the recipes of the caller
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct RecipeFilter {
+    pub q: Option<String>,
+    pub tags: Option<String>,
+    pub difficulty: Option<String>,
+    pub cuisine: Option<String>,
+    pub equipment: Option<String>,
+    #[serde(rename = "maxDifficulty")]
+    pub max_difficulty: Option<String>,
+    #[serde(rename = "createdAfter")]
+    pub created_after: Option<String>,
+    #[serde(rename = "createdBefore")]
+    pub created_before: Option<String>,
+    #[serde(rename = "modifiedAfter")]
+    pub modified_after: Option<String>,
+    #[serde(rename = "modifiedBefore")]
+    pub modified_before: Option<String>,
+    #[serde(rename = "includeArchived")]
+    pub include_archived: Option<bool>,
+    pub fuzzy: Option<bool>,
+    #[serde(rename = "minIngredients")]
+    pub min_ingredients: Option<u32>,
+    #[serde(rename = "maxIngredients")]
+    pub max_ingredients: Option<u32>,
+    #[serde(rename = "isQuick")]
+    pub is_quick: Option<bool>,
+    #[serde(skip)]
+    pub quick_recipes: QuickRecipes,
+    #[serde(skip)]
+    pub author: Option<String>,
+}
+
+impl RecipeFilter {
+    /// translates the query parameters into a mongo filter document
+    pub fn to_document(&self) -> Result<Document, RecipeFormatError> {
+        let mut filter = Document::new();
+
+        if let (Some(q), None) = (self.search_query(), self.fuzzy_query()) {
+            filter.insert("$text", doc! { "$search": q });
+        }
+
+        if let Some(tags) = &self.tags {
+            let tags = normalize_tags(&split_list(tags));
+            if !tags.is_empty() {
+                filter.insert("tags", doc! { "$all": tags });
+            }
+        }
+
+        if let Some(cuisines) = &self.cuisine {
+            let cuisines = split_list(cuisines);
+            if !cuisines.is_empty() {
+                filter.insert("cuisine", doc! { "$in": cuisines });
+            }
+        }
+
+        if let Some(equipment) = &self.equipment {
+            let equipment = normalize_equipment(&split_list(equipment));
+            if !equipment.is_empty() {
+                filter.insert("equipment", doc! { "$all": equipment });
+            }
+        }
+
+        if let Some(difficulties) = self.difficulties()? {
+            let difficulties = difficulties.into_iter().map(Bson::from).collect::<Vec<Bson>>();
+            filter.insert("difficulty", doc! { "$in": difficulties });
+        }
+
+        if let Some(range) = date_range("created", &self.created_after, &self.created_before)? {
+            filter.insert("created", range);
+        }
+        if let Some(range) = date_range("lastModified", &self.modified_after, &self.modified_before)? {
+            filter.insert(JSON_ATTR_LAST_MODIFIED, range);
+        }
+
+        if let Some(quick) = self.is_quick {
+            filter.insert("cookingTimeInMinutes", self.quick_recipes.filter(quick));
+        }
+
+        if let Some(range) = ingredient_count_range(self.min_ingredients, self.max_ingredients)? {
+            filter.insert("$and", vec![range]);
+        }
+
+        if !self.include_archived.unwrap_or(false) {
+            filter.insert("archived", doc! { "$ne": true });
+        }
+
+        if let Some(author) = &self.author {
+            filter.insert("author", author);
+        }
+
+        Ok(filter)
+    }
+
+    /// the query of a fuzzy search, which is left out of the filter document
+    pub fn fuzzy_query(&self) -> Option<&str> {
+        self.search_query().filter(|_| self.fuzzy.unwrap_or(false))
+    }
+
+    fn search_query(&self) -> Option<&str> {
+        self.q.as_deref().map(str::trim).filter(|q| !q.is_empty())
+    }
+
+    /// difficulties matching both the listed and the maximum difficulty, None when unrestricted
+    fn difficulties(&self) -> Result<Option<Vec<Difficulty>>, RecipeFormatError> {
+        let listed = match &self.difficulty {
+            Some(difficulty) => split_list(difficulty)
+                .iter()
+                .map(|difficulty| Difficulty::try_from(difficulty.as_str()))
+                .collect::<Result<Vec<Difficulty>, RecipeFormatError>>()?,
+            None => Vec::new()
+        };
+
+        let max_difficulty = match self.max_difficulty.as_deref().map(str::trim) {
+            Some(max_difficulty) if !max_difficulty.is_empty() => Some(Difficulty::try_from(max_difficulty)?),
+            _ => None
+        };
+
+        match max_difficulty {
+            Some(max_difficulty) if listed.is_empty() => Ok(Some(max_difficulty.at_most())),
+            Some(max_difficulty) => Ok(Some(listed.into_iter()
+                .filter(|difficulty| *difficulty <= max_difficulty)
+                .collect())),
+            None if listed.is_empty() => Ok(None),
+            None => Ok(Some(listed))
+        }
+    }
+}
+
+/// `?maxTime=30&have=milk&have=eggs` of the recipes which can be cooked right now: done within the minutes
+/// and needing no other ingredients than the ones at hand. The ingredients may be repeated or comma separated
+/// and are compared to the ingredient titles case insensitively. Recipes without ingredients are left out
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CookableNow {
+    pub max_time: u32,
+    pub have: Vec<String>,
+}
+
+impl CookableNow {
+    pub fn from_query(params: &[(String, String)]) -> Result<Self, RecipeFormatError> {
+        let max_time = match params.iter().find(|(key, _)| key == "maxTime") {
+            Some((_, max_time)) => max_time.trim().parse::<u32>().ok()
+                .filter(|max_time| *max_time > 0)
+                .ok_or_else(|| RecipeFormatError::from(format!("maxTime '{}' is no positive number of minutes", max_time)))?,
+            None => return Err("Query parameter maxTime is required".into()),
+        };
+        let mut have = Vec::new();
+        for ingredient in params.iter().filter(|(key, _)| key == "have").flat_map(|(_, value)| split_list(value)) {
+            let ingredient = ingredient.to_lowercase();
+            if !have.contains(&ingredient) {
+                have.push(ingredient);
+            }
+        }
+        if have.is_empty() {
+            return Err("At least one ingredient at hand is required as have".into());
+        }
+        Ok(CookableNow { max_time, have })
+    }
+
+    pub fn to_document(&self) -> Document {
+        let titles = doc! { "$map": {
+            "input": { "$ifNull": ["$ingredients", []] },
+            "as": "ingredient",
+            "in": { "$toLower": { "$trim": { "input": "$$ingredient.title" } } },
+        } };
+        doc! {
+            "cookingTimeInMinutes": { "$lte": self.max_time },
+            "ingredients.0": { "$exists": true },
+            "$expr": { "$setIsSubset": [titles, self.have.clone()] },
+            "archived": { "$ne": true },
+        }
+    }
+}
+
+/// `?minRating=4` of the recipes whose average rating is at least 4. Recipes nobody rated yet are
+/// left out unless `?includeUnrated=true`. The averages are computed from the ratings, so rating filtered
+/// listings join them per recipe, see `Dao::get_rated_recipes_cursor`
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct RatingFilter {
+    #[serde(rename = "minRating")]
+    pub min_rating: Option<f64>,
+    #[serde(rename = "includeUnrated")]
+    pub include_unrated: Option<bool>,
+}
+
+impl RatingFilter {
+    /// the condition on the average rating of the recipes, None without a minimum rating.
+    /// Including the unrated recipes, whose average is null, it leaves out the ones rated below the minimum
+    pub fn average_condition(&self) -> Result<Option<Document>, RecipeFormatError> {
+        let min_rating = match self.min_rating {
+            Some(min_rating) if min_rating.is_finite() && min_rating >= 0.0 => min_rating,
+            Some(min_rating) => return Err(format!("minRating '{}' is no rating", min_rating).into()),
+            None => return Ok(None),
+        };
+        match self.include_unrated.unwrap_or(false) {
+            true => Ok(Some(doc! { "$not": { "$lt": min_rating } })),
+            false => Ok(Some(doc! { "$gte": min_rating })),
+        }
+    }
+}
+
+/// true when the filter only holds the default of leaving out archived recipes, or nothing at all.
+/// The visibility of drafts is no filter of the caller and ignored
+pub fn is_unfiltered(filter: &Document) -> bool {
+    let mut filter = filter.clone();
+    filter.remove(VISIBILITY_KEY);
+    filter.is_empty() || filter == doc! { "archived": { "$ne": true } }
+}
+
+const VISIBILITY_KEY: &str = "$nor";
+
+/// Drafts are visible to their author and admins only, published recipes to everyone.
+/// Archiving is independent of the status: archived recipes stay out of listings unless requested,
+/// but remain readable by id as far as their status allows, so a listing shows published recipes
+/// which are not archived. Empty for admins, who see all recipes
+pub fn visibility_filter(identity: Option<&Identity>) -> Document {
+    let draft = Bson::from(RecipeStatus::Draft);
+    match identity {
+        Some(identity) if identity.has_role(Role::Admin) => Document::new(),
+        Some(identity) => doc! { VISIBILITY_KEY: [{ "status": draft, "author": { "$ne": identity.user.clone() } }] },
+        None => doc! { VISIBILITY_KEY: [{ "status": draft }] },
+    }
+}
+
+/// the check of `visibility_filter` on an already read recipe
+pub fn is_visible(recipe: &Recipe, identity: Option<&Identity>) -> bool {
+    match (recipe.status, identity) {
+        (RecipeStatus::Published, _) => true,
+        (RecipeStatus::Draft, identity) => is_author_or_admin(recipe.author.as_deref(), identity),
+    }
+}
+
+/// whether the caller wrote the recipe or is an admin, anonymous callers never are
+pub fn is_author_or_admin(author: Option<&str>, identity: Option<&Identity>) -> bool {
+    identity.is_some_and(|identity| identity.has_role(Role::Admin) || author == Some(identity.user.as_str()))
+}
+
+/// inclusive range on a date field, None when both bounds are absent
+fn date_range(field: &str, after: &Option<String>, before: &Option<String>) -> Result<Option<Document>, RecipeFormatError> {
+    let after = after.as_deref().map(|after| parse_date(field, after)).transpose()?;
+    let before = before.as_deref().map(|before| parse_date(field, before)).transpose()?;
+
+    let mut range = Document::new();
+    if let (Some(after), Some(before)) = (after, before) {
+        if after > before {
+            return Err(format!("The lower bound of {} must not be after its upper bound", field).into());
+        }
+    }
+    if let Some(after) = after {
+        range.insert("$gte", after);
+    }
+    if let Some(before) = before {
+        range.insert("$lte", before);
+    }
+    Ok(if range.is_empty() { None } else { Some(range) })
+}
+
+/// Inclusive range on the stored ingredient count, None when both bounds are absent.
+/// Recipes written before the count was stored are matched by the size of their ingredients.
+/// Wrapped into `$and` by the caller, as the text search fallback replaces a top level `$or`
+fn ingredient_count_range(min: Option<u32>, max: Option<u32>) -> Result<Option<Document>, RecipeFormatError> {
+    if let (Some(min), Some(max)) = (min, max) {
+        if min > max {
+            return Err(format!("minIngredients {} must not be greater than maxIngredients {}", min, max).into());
+        }
+    }
+    let size = doc! { "$size": { "$ifNull": ["$ingredients", []] } };
+    let mut range = Document::new();
+    let mut size_range = Vec::new();
+    if let Some(min) = min {
+        range.insert("$gte", min);
+        size_range.push(Bson::Document(doc! { "$gte": [size.clone(), min] }));
+    }
+    if let Some(max) = max {
+        range.insert("$lte", max);
+        size_range.push(Bson::Document(doc! { "$lte": [size, max] }));
+    }
+    if range.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(doc! { "$or": [
+        { JSON_ATTR_INGREDIENT_COUNT: range },
+        { JSON_ATTR_INGREDIENT_COUNT: { "$exists": false }, "$expr": { "$and": size_range } },
+    ] }))
+}
+
+fn parse_date(field: &str, value: &str) -> Result<DateTime<Utc>, RecipeFormatError> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .map(|date| date.with_timezone(&Utc))
+        .map_err(|_| format!("Bound of {} '{}' is no RFC3339 timestamp", field, value).into())
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(LIST_SEPARATOR)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+
+#[cfg(test)]
+mod recipe_filter_tests {
+    use bson::Document;
+    use chrono::{TimeZone, Utc};
+
+    use crate::auth::{Identity, Role};
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+    use crate::model::recipe_status::RecipeStatus;
+    use crate::quick_recipes::QuickRecipes;
+    use crate::recipe_filter::{CookableNow, is_unfiltered, is_visible, RatingFilter, RecipeFilter, visibility_filter};
+
+    #[test]
+    fn empty_filter_to_document() {
+        assert_eq!(RecipeFilter::default().to_document().unwrap(), doc! { "archived": { "$ne": true } });
+
+        let filter = RecipeFilter { tags: Some(" , ".to_string()), difficulty: Some("".to_string()), max_difficulty: Some("".to_string()), ..RecipeFilter::default() };
+        assert_eq!(filter.to_document().unwrap(), doc! { "archived": { "$ne": true } });
+    }
+
+    #[test]
+    fn equipment_filter_to_document() {
+        let filter = RecipeFilter { equipment: Some(" Oven,blender,, oven".to_string()), ..RecipeFilter::default() };
+        assert_eq!(filter.to_document().unwrap(), doc! {
+            "equipment": { "$all": ["oven", "blender"] },
+            "archived": { "$ne": true }
+        });
+    }
+
+    #[test]
+    fn author_filter_to_document() {
+        let filter = RecipeFilter { author: Some("alice".to_string()), ..RecipeFilter::default() };
+        assert_eq!(filter.to_document().unwrap(), doc! { "archived": { "$ne": true }, "author": "alice" });
+        assert_eq!(is_unfiltered(&filter.to_document().unwrap()), false);
+    }
+
+    #[test]
+    fn unfiltered_documents() {
+        assert_eq!(is_unfiltered(&RecipeFilter::default().to_document().unwrap()), true);
+        let filter = RecipeFilter { include_archived: Some(true), ..RecipeFilter::default() };
+        assert_eq!(is_unfiltered(&filter.to_document().unwrap()), true);
+        let filter = RecipeFilter { tags: Some("vegan".to_string()), ..RecipeFilter::default() };
+        assert_eq!(is_unfiltered(&filter.to_document().unwrap()), false);
+
+        let mut filter = RecipeFilter::default().to_document().unwrap();
+        filter.extend(visibility_filter(None));
+        assert_eq!(is_unfiltered(&filter), true);
+    }
+
+    #[test]
+    fn visibility_of_drafts() {
+        let alice = Identity::new("alice", Role::Editor);
+        let bob = Identity::new("bob", Role::Editor);
+        let admin = Identity::new("carol", Role::Admin);
+        assert_eq!(visibility_filter(None), doc! { "$nor": [{ "status": "draft" }] });
+        assert_eq!(visibility_filter(Some(&alice)), doc! { "$nor": [{ "status": "draft", "author": { "$ne": "alice" } }] });
+        assert_eq!(visibility_filter(Some(&admin)), doc! {});
+
+        let mut recipe = create_one_recipe_without_image();
+        assert_eq!(is_visible(&recipe, None), true);
+        recipe.status = RecipeStatus::Draft;
+        recipe.author = Some("alice".to_string());
+        assert_eq!(is_visible(&recipe, None), false);
+        assert_eq!(is_visible(&recipe, Some(&alice)), true);
+        assert_eq!(is_visible(&recipe, Some(&bob)), false);
+        assert_eq!(is_visible(&recipe, Some(&admin)), true);
+    }
+
+    #[test]
+    fn tags_and_difficulty_filter_to_document() {
+        let filter = RecipeFilter {
+            tags: Some("vegan, fast".to_string()),
+            difficulty: Some("Easy,Medium".to_string()),
+            ..RecipeFilter::default()
+        };
+        assert_eq!(filter.to_document().unwrap(), doc! {
+            "tags": { "$all": ["vegan", "fast"] },
+            "difficulty": { "$in": ["Easy", "Medium"] },
+            "archived": { "$ne": true }
+        });
+    }
+
+    #[test]
+    fn cuisine_filter_to_document() {
+        let filter = RecipeFilter { cuisine: Some("Italian, French".to_string()), ..RecipeFilter::default() };
+        assert_eq!(filter.to_document().unwrap(), doc! {
+            "cuisine": { "$in": ["Italian", "French"] },
+            "archived": { "$ne": true }
+        });
+    }
+
+    #[test]
+    fn include_archived_filter_to_document() {
+        let filter = RecipeFilter { include_archived: Some(true), ..RecipeFilter::default() };
+        assert_eq!(filter.to_document().unwrap(), Document::new());
+
+        let filter = RecipeFilter { include_archived: Some(false), ..RecipeFilter::default() };
+        assert_eq!(filter.to_document().unwrap(), doc! { "archived": { "$ne": true } });
+    }
+
+    #[test]
+    fn unknown_difficulty_filter_fails() {
+        let filter = RecipeFilter { difficulty: Some("Easy,Super Hard".to_string()), ..RecipeFilter::default() };
+        assert_eq!(filter.to_document().is_err(), true);
+
+        let filter = RecipeFilter { max_difficulty: Some("Medium-ish".to_string()), ..RecipeFilter::default() };
+        assert_eq!(filter.to_document().is_err(), true);
+    }
+
+    #[test]
+    fn search_query_to_document() {
+        let filter = RecipeFilter { q: Some(" pasta ".to_string()), ..RecipeFilter::default() };
+        assert_eq!(filter.to_document().unwrap(), doc! { "$text": { "$search": "pasta" }, "archived": { "$ne": true } });
+    }
+
+    #[test]
+    fn fuzzy_search_query_is_no_text_search() {
+        let filter = RecipeFilter { q: Some(" spagetti ".to_string()), fuzzy: Some(true), ..RecipeFilter::default() };
+        assert_eq!(filter.fuzzy_query(), Some("spagetti"));
+        assert_eq!(filter.to_document().unwrap(), doc! { "archived": { "$ne": true } });
+
+        let filter = RecipeFilter { fuzzy: Some(true), ..RecipeFilter::default() };
+        assert_eq!(filter.fuzzy_query(), None);
+        let filter = RecipeFilter { q: Some("pasta".to_string()), fuzzy: Some(false), ..RecipeFilter::default() };
+        assert_eq!(filter.fuzzy_query(), None);
+    }
+
+    #[test]
+    fn max_difficulty_excludes_harder_recipes() {
+        let filter = RecipeFilter { max_difficulty: Some("Medium".to_string()), ..RecipeFilter::default() };
+        assert_eq!(filter.to_document().unwrap(), doc! {
+            "difficulty": { "$in": ["Easy", "Medium"] },
+            "archived": { "$ne": true }
+        });
+    }
+
+    #[test]
+    fn max_difficulty_combined_with_difficulty_list() {
+        let filter = RecipeFilter {
+            difficulty: Some("Medium,Hard".to_string()),
+            max_difficulty: Some("Medium".to_string()),
+            ..RecipeFilter::default()
+        };
+        assert_eq!(filter.to_document().unwrap(), doc! {
+            "difficulty": { "$in": ["Medium"] },
+            "archived": { "$ne": true }
+        });
+    }
+
+    #[test]
+    fn date_range_filter_to_document() {
+        let filter = RecipeFilter {
+            created_after: Some("2020-09-01T00:00:00Z".to_string()),
+            created_before: Some("2020-09-30T23:59:59+00:00".to_string()),
+            modified_after: Some("2020-09-15T12:00:00+02:00".to_string()),
+            ..RecipeFilter::default()
+        };
+        assert_eq!(filter.to_document().unwrap(), doc! {
+            "created": {
+                "$gte": Utc.ymd(2020, 9, 1).and_hms(0, 0, 0),
+                "$lte": Utc.ymd(2020, 9, 30).and_hms(23, 59, 59)
+            },
+            "last_modified": { "$gte": Utc.ymd(2020, 9, 15).and_hms(10, 0, 0) },
+            "archived": { "$ne": true }
+        });
+    }
+
+    #[test]
+    fn ingredient_count_filter_to_document() {
+        let filter = RecipeFilter { min_ingredients: Some(2), ..RecipeFilter::default() };
+        assert_eq!(filter.to_document().unwrap(), doc! {
+            "$and": [{ "$or": [
+                { "ingredientCount": { "$gte": 2 } },
+                { "ingredientCount": { "$exists": false }, "$expr": { "$and": [
+                    { "$gte": [{ "$size": { "$ifNull": ["$ingredients", []] } }, 2] },
+                ] } },
+            ] }],
+            "archived": { "$ne": true }
+        });
+
+        let filter = RecipeFilter { min_ingredients: Some(5), max_ingredients: Some(5), ..RecipeFilter::default() };
+        assert_eq!(filter.to_document().is_ok(), true);
+        let filter = RecipeFilter { min_ingredients: Some(6), max_ingredients: Some(5), ..RecipeFilter::default() };
+        assert_eq!(filter.to_document().is_err(), true);
+    }
+
+    #[test]
+    fn quick_filter_includes_the_threshold() {
+        let filter = RecipeFilter { is_quick: Some(true), ..RecipeFilter::default() };
+        assert_eq!(filter.to_document().unwrap(), doc! { "cookingTimeInMinutes": { "$lte": 30 }, "archived": { "$ne": true } });
+
+        let filter = RecipeFilter { is_quick: Some(false), quick_recipes: QuickRecipes { max_minutes: 15 }, ..RecipeFilter::default() };
+        assert_eq!(filter.to_document().unwrap(), doc! { "cookingTimeInMinutes": { "$gt": 15 }, "archived": { "$ne": true } });
+    }
+
+    #[test]
+    fn invalid_date_range_filter_fails() {
+        let filter = RecipeFilter { created_after: Some("yesterday".to_string()), ..RecipeFilter::default() };
+        assert_eq!(filter.to_document().is_err(), true);
+
+        let filter = RecipeFilter {
+            modified_after: Some("2020-10-01T00:00:00Z".to_string()),
+            modified_before: Some("2020-09-01T00:00:00Z".to_string()),
+            ..RecipeFilter::default()
+        };
+        assert_eq!(filter.to_document().is_err(), true);
+    }
+
+    fn pairs(query: &[(&str, &str)]) -> Vec<(String, String)> {
+        query.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn cookable_now_params() {
+        let cookable = CookableNow::from_query(&pairs(&[("maxTime", "30"), ("have", "Milk"), ("have", "eggs, flour"), ("have", "milk"), ("page", "1")])).unwrap();
+        assert_eq!(cookable, CookableNow { max_time: 30, have: vec!["milk".to_string(), "eggs".to_string(), "flour".to_string()] });
+        let filter = cookable.to_document();
+        assert_eq!(filter.get_document("cookingTimeInMinutes").unwrap(), &doc! { "$lte": 30 });
+        assert_eq!(filter.get_document("$expr").unwrap().get_array("$setIsSubset").unwrap()[1], bson::Bson::from(vec!["milk", "eggs", "flour"]));
+
+        assert_eq!(CookableNow::from_query(&pairs(&[("have", "milk")])).is_err(), true);
+        assert_eq!(CookableNow::from_query(&pairs(&[("maxTime", "0"), ("have", "milk")])).is_err(), true);
+        assert_eq!(CookableNow::from_query(&pairs(&[("maxTime", "soon"), ("have", "milk")])).is_err(), true);
+        assert_eq!(CookableNow::from_query(&pairs(&[("maxTime", "30"), ("have", " , ")])).is_err(), true);
+    }
+
+    #[test]
+    fn rating_filter() {
+        assert_eq!(RatingFilter::default().average_condition().unwrap(), None);
+        assert_eq!(RatingFilter { min_rating: Some(-1.0), include_unrated: None }.average_condition().is_err(), true);
+        assert_eq!(RatingFilter { min_rating: Some(f64::NAN), include_unrated: None }.average_condition().is_err(), true);
+
+        let rated = RatingFilter { min_rating: Some(4.0), include_unrated: None };
+        assert_eq!(rated.average_condition().unwrap(), Some(doc! { "$gte": 4.0 }));
+
+        let with_unrated = RatingFilter { min_rating: Some(4.0), include_unrated: Some(true) };
+        assert_eq!(with_unrated.average_condition().unwrap(), Some(doc! { "$not": { "$lt": 4.0 } }));
+    }
+}
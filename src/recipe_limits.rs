@@ -0,0 +1,165 @@
+use crate::model::ingredients::Ingredient;
+use crate::model::recipe::{Recipe, RecipeFormatError};
+
+pub const MAX_TITLE_LENGTH_ENV: &str = "MAX_TITLE_LENGTH";
+pub const MAX_DESCRIPTION_LENGTH_ENV: &str = "MAX_DESCRIPTION_LENGTH";
+pub const MAX_INSTRUCTION_LENGTH_ENV: &str = "MAX_INSTRUCTION_LENGTH";
+pub const MAX_INGREDIENT_TITLE_LENGTH_ENV: &str = "MAX_INGREDIENT_TITLE_LENGTH";
+pub const MAX_INGREDIENTS_ENV: &str = "MAX_INGREDIENTS";
+pub const MAX_INSTRUCTIONS_ENV: &str = "MAX_INSTRUCTIONS";
+
+/// Maximum lengths in characters of the free text of recipes, e.g. `MAX_DESCRIPTION_LENGTH=5000`,
+/// substitutes of ingredients are limited like the ingredient titles,
+/// and maximum numbers of ingredients and instructions, e.g. `MAX_INGREDIENTS=100`.
+/// The defaults leave plenty of room for real recipes and only stop pathological documents,
+/// unset or invalid limits keep their default
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RecipeLimits {
+    pub title: usize,
+    pub description: usize,
+    pub instruction: usize,
+    pub ingredient_title: usize,
+    pub ingredients: usize,
+    pub instructions: usize,
+}
+
+impl Default for RecipeLimits {
+    fn default() -> Self {
+        Self { title: 300, description: 20_000, instruction: 5_000, ingredient_title: 300, ingredients: 200, instructions: 200 }
+    }
+}
+
+impl RecipeLimits {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let limits = Self {
+            title: limit_from_env(MAX_TITLE_LENGTH_ENV, defaults.title),
+            description: limit_from_env(MAX_DESCRIPTION_LENGTH_ENV, defaults.description),
+            instruction: limit_from_env(MAX_INSTRUCTION_LENGTH_ENV, defaults.instruction),
+            ingredient_title: limit_from_env(MAX_INGREDIENT_TITLE_LENGTH_ENV, defaults.ingredient_title),
+            ingredients: limit_from_env(MAX_INGREDIENTS_ENV, defaults.ingredients),
+            instructions: limit_from_env(MAX_INSTRUCTIONS_ENV, defaults.instructions),
+        };
+        info!("Loaded recipe limits={:?}", limits);
+        limits
+    }
+
+    /// the first list or text over its limit, named by its JSON pointer like `/instructions/2`
+    pub fn validate(&self, recipe: &Recipe) -> Result<(), RecipeFormatError> {
+        check_count("/instructions", recipe.instructions.len(), self.instructions)?;
+        check_length("/title", &recipe.title, self.title)?;
+        check_length("/description", &recipe.description, self.description)?;
+        for (index, instruction) in recipe.instructions.iter().enumerate() {
+            check_length(&format!("/instructions/{}", index), instruction, self.instruction)?;
+        }
+        self.validate_ingredients(&recipe.ingredients)
+    }
+
+    /// the checks of `validate` on the ingredients of a recipe
+    pub fn validate_ingredients(&self, ingredients: &[Ingredient]) -> Result<(), RecipeFormatError> {
+        check_count("/ingredients", ingredients.len(), self.ingredients)?;
+        for (index, ingredient) in ingredients.iter().enumerate() {
+            check_length(&format!("/ingredients/{}/title", index), &ingredient.title, self.ingredient_title)?;
+            for (substitute_index, substitute) in ingredient.substitutes.iter().enumerate() {
+                check_length(&format!("/ingredients/{}/substitutes/{}", index, substitute_index), substitute, self.ingredient_title)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn limit_from_env(name: &str, default: usize) -> usize {
+    std::env::var(name).ok()
+        .and_then(|limit| limit.trim().parse::<usize>().ok())
+        .filter(|limit| *limit > 0)
+        .unwrap_or(default)
+}
+
+fn check_count(field: &str, count: usize, limit: usize) -> Result<(), RecipeFormatError> {
+    match count {
+        count if count > limit => Err(RecipeFormatError::too_many(field, limit, count)),
+        _ => Ok(()),
+    }
+}
+
+fn check_length(field: &str, text: &str, limit: usize) -> Result<(), RecipeFormatError> {
+    match text.chars().count() {
+        length if length > limit => Err(RecipeFormatError::too_long(field, limit, length)),
+        _ => Ok(()),
+    }
+}
+
+
+#[cfg(test)]
+mod recipe_limits_tests {
+    use crate::dao::dao_tests::create_one_recipe_without_image;
+    use crate::model::ingredients::Ingredient;
+    use crate::model::measurement_unit::MeasurementUnit;
+    use crate::recipe_limits::RecipeLimits;
+
+    const LIMITS: RecipeLimits = RecipeLimits { title: 5, description: 6, instruction: 7, ingredient_title: 8, ingredients: 3, instructions: 2 };
+
+    #[test]
+    fn texts_at_their_limit() {
+        let mut recipe = create_one_recipe_without_image();
+        recipe.title = "Pasta".to_string();
+        recipe.description = "Tasty!".to_string();
+        recipe.instructions = vec!["Boil".to_string(), "Cook it".to_string()];
+        recipe.ingredients = vec![Ingredient::new("0", 1.0, "Linguine", MeasurementUnit::Gramm)];
+        assert_eq!(LIMITS.validate(&recipe).is_ok(), true);
+
+        recipe.title = "Größe".to_string();
+        assert_eq!(LIMITS.validate(&recipe).is_ok(), true);
+    }
+
+    #[test]
+    fn texts_over_their_limit() {
+        let recipe = create_one_recipe_without_image();
+
+        let mut long = recipe.clone();
+        long.title = "Pastas".to_string();
+        let err = LIMITS.validate(&long).unwrap_err();
+        assert_eq!((err.field.as_deref(), err.limit), (Some("/title"), Some(5)));
+
+        let mut long = recipe.clone();
+        long.description = "Tasty!!".to_string();
+        assert_eq!(LIMITS.validate(&long).unwrap_err().field.as_deref(), Some("/description"));
+
+        let mut long = recipe.clone();
+        long.instructions = vec!["Boil".to_string(), "Cook it!".to_string()];
+        let err = LIMITS.validate(&long).unwrap_err();
+        assert_eq!((err.field.as_deref(), err.limit), (Some("/instructions/1"), Some(7)));
+
+        let mut long = recipe.clone();
+        long.ingredients = vec![Ingredient::new("0", 1.0, "Spaghetti", MeasurementUnit::Gramm)];
+        let err = LIMITS.validate(&long).unwrap_err();
+        assert_eq!((err.field.as_deref(), err.limit), (Some("/ingredients/0/title"), Some(8)));
+        assert_eq!(err.error.contains("8"), true);
+
+        let mut long = recipe;
+        let mut pasta = Ingredient::new("0", 1.0, "Pasta", MeasurementUnit::Gramm);
+        pasta.substitutes = vec!["Rice".to_string(), "Zucchini noodles".to_string()];
+        long.ingredients = vec![pasta];
+        let err = LIMITS.validate(&long).unwrap_err();
+        assert_eq!((err.field.as_deref(), err.limit), (Some("/ingredients/0/substitutes/1"), Some(8)));
+    }
+
+    #[test]
+    fn counts_at_and_over_their_limit() {
+        let mut recipe = create_one_recipe_without_image();
+        recipe.ingredients = (0..3).map(|i| Ingredient::new(&i.to_string(), 1.0, "Salt", MeasurementUnit::Gramm)).collect();
+        recipe.instructions = vec!["Boil".to_string(), "Cook".to_string()];
+        assert_eq!(LIMITS.validate(&recipe).is_ok(), true);
+
+        let mut many = recipe.clone();
+        many.ingredients.push(Ingredient::new("3", 1.0, "Salt", MeasurementUnit::Gramm));
+        let err = LIMITS.validate(&many).unwrap_err();
+        assert_eq!((err.field.as_deref(), err.limit), (Some("/ingredients"), Some(3)));
+
+        let mut many = recipe;
+        many.instructions.push("Eat".to_string());
+        let err = LIMITS.validate(&many).unwrap_err();
+        assert_eq!((err.field.as_deref(), err.limit), (Some("/instructions"), Some(2)));
+        assert_eq!(err.error, "A recipe may have at most 2 instructions, has 3");
+    }
+}
@@ -1,196 +1,1648 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+
 use actix_web::{Either, HttpRequest, HttpResponse, Responder, web};
+use actix_web::error::ErrorInternalServerError;
+use actix_web::dev::HttpResponseBuilder;
+use actix_web::http::header::{CONTENT_DISPOSITION, LINK, RETRY_AFTER, WARNING};
+use actix_web::web::Bytes;
 use actix_web::web::{Json, Query};
+use bson::Document;
 use bson::oid::ObjectId;
+use chrono::{DateTime, Utc};
+use futures_util::{future, stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::dao::{Dao, DaoError};
-use crate::model::recipe::Recipe;
+use crate::LogExtensionErr;
+use crate::auth::{AdminIdentity, Identity, identify_request, Role};
+use crate::circuit_breaker::retry_after_seconds;
+use crate::field_encryption::{reveal_document, reveal_recipe};
+use crate::bulk_import::{ChunkResult, IMPORT_CHUNK_SIZE, imported_recipes, ImportedRecipe, ImportParams, ImportSummary, JsonArraySplitter, ValidationResult};
+use crate::classification::ClassificationAllowlist;
+use crate::collection_routes::{parse_object_ids, writable_collection};
+use crate::dao::{Dao, DaoError, MERGE_ATTEMPTS};
+use crate::error_body::ErrorBody;
+use crate::export::cookbook::{COOKBOOK_FILENAME, CookbookRequest, MAX_COOKBOOK_RECIPES, render_cookbook};
+use crate::export::csv::{CSV_CONTENT_TYPE, CSV_FILENAME, CSV_HEADER, summary_row};
+use crate::export::json_ld::{JSON_LD_CONTENT_TYPE, recipe_json_ld};
+use crate::export::markdown::{MARKDOWN_CONTENT_TYPE, render_markdown};
+use crate::export::locale::Locale;
+use crate::export::pdf::PDF_CONTENT_TYPE;
+use crate::export::print_view::render_print_view;
+use crate::field_mask::{FieldMask, FieldMaskParams, masked_recipe_json};
+use crate::import::page_fetch::{fetch_page, FetchError, FetchPolicy};
+use crate::import::schema_org::{find_recipe_json_ld, recipe_from_json_ld};
+use crate::json_error::from_json_value;
+use crate::json_stream::json_array;
+use crate::list_response::{CountSettings, DEPRECATION_HEADER, EnvelopeParams, ListEnvelope, ListMeta, listing_response, RecipeCount};
+use crate::model::ingredients::{Ingredient, IngredientSubstitutes, merge_ingredients, validate_ingredient_ids};
+use crate::model::recipe::{Recipe, RecipeFormatError};
+use crate::model::delete_many::{CONFIRM_DELETE_HEADER, DeleteManyRequest, DeleteManyResult, is_confirmed};
+use crate::model::recipe_stats::RecipeStats;
+use crate::model::retag_rule::{RetagRequest, RetagResult, RetagRule, RetagRuleResult};
+use crate::model::recipe_detail::RecipeDetail;
+use crate::model::recipe_diff::diff_recipes;
+use crate::model::recipe_group::GroupBy;
+use crate::model::fuzzy_match::{FuzzyMatch, rank_by_distance};
+use crate::model::recipe_status::RecipeStatus;
+use crate::model::recipe_summary::RecipeSummary;
+use crate::model::step_timer::cook_mode_steps;
+use crate::query_budget::{BudgetParams, query_budget};
+use crate::quick_recipes::{quick_recipes, QuickRecipes};
+use crate::model::trending_recipe::parse_window;
 use crate::pagination::Pagination;
+use crate::recipe_defaults::RecipeDefaults;
+use crate::recipe_cache::STALE_HEADER;
+use crate::recipe_filter::{CookableNow, is_visible, RatingFilter, RecipeFilter, visibility_filter};
+use crate::recipe_limits::RecipeLimits;
+use crate::slug::{is_valid_slug, SlugPaths};
+use crate::thumbnail;
+use crate::thumbnail::{ImageCompression, ProcessedImage};
+
+pub const DEFAULT_SIMILAR_LIMIT: i64 = 10;
+pub const MAX_SIMILAR_LIMIT: i64 = 50;
+pub const DEFAULT_TRENDING_LIMIT: i64 = 10;
+pub const MAX_TRENDING_LIMIT: i64 = 50;
+const DEFAULT_TRENDING_WINDOW: &str = "7d";
+pub const DEFAULT_TAG_COMBOS_LIMIT: i64 = 20;
+pub const MAX_TAG_COMBOS_LIMIT: i64 = 100;
+pub const DEFAULT_GROUP_LIMIT: i64 = 5;
+pub const MAX_GROUP_LIMIT: i64 = 20;
+pub const DEFAULT_COOKABLE_PAGE_SIZE: usize = 20;
+pub const MAX_COOKABLE_PAGE_SIZE: usize = 100;
+pub const IMAGE_CONTENT_TYPE_HEADER: &str = "x-image-content-type";
+pub const ORIGINAL_CONTENT_TYPE_HEADER: &str = "x-original-content-type";
 
 pub struct RecipeRoutes {}
 
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct LimitParams {
+    pub limit: Option<i64>,
+}
+
+impl LimitParams {
+    /// requested limit clamped into `1..=max`, `default` when absent
+    pub fn limit_or(&self, default: i64, max: i64) -> i64 {
+        self.limit.unwrap_or(default).max(1).min(max)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateParams {
+    #[serde(rename = "collectionId")]
+    pub collection_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct GroupedParams {
+    pub by: GroupBy,
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ImportUrl {
+    pub url: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExportParams {
+    pub servings: Option<f64>,
+    pub locale: Option<String>,
+}
+
+impl ExportParams {
+    /// the locale to format for, servings must be greater than 0 when given
+    fn validate(&self) -> Result<Locale, String> {
+        if let Some(servings) = self.servings {
+            if !servings.is_finite() || servings <= 0.0 {
+                return Err("Servings must be greater than 0".to_string());
+            }
+        }
+        Locale::try_from(self.locale.as_deref().unwrap_or_default())
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// schema.org `Recipe` as JSON-LD
+    #[serde(rename = "jsonld")]
+    JsonLd,
+    /// the print view
+    Html,
+    Markdown,
+}
+
+/// `?format=jsonld`, `html` or `markdown` of the single recipe export and the preview
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct ExportFormatParams {
+    pub format: ExportFormat,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IngredientMode {
+    /// the sent ingredients become the whole list
+    Replace,
+    /// the sent ingredients are upserted by id, the others are kept
+    Merge,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct IngredientParams {
+    #[serde(rename = "ingredientMode")]
+    pub ingredient_mode: Option<IngredientMode>,
+}
+
+/// Modification date and version of a touched recipe
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct TouchResult {
+    #[serde(rename = "lastModified")]
+    pub last_modified: DateTime<Utc>,
+    pub version: u32,
+}
+
+/// `?explain=true` answers the plan of the listing query instead of the recipes
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct ExplainParams {
+    pub explain: Option<bool>,
+}
+
+impl ExplainParams {
+    /// only admins may explain, everyone else gets the listing as if they had not asked
+    pub fn is_requested(&self, identity: Option<&Identity>) -> bool {
+        self.explain.unwrap_or(false) && identity.is_some_and(|identity| identity.has_role(Role::Admin))
+    }
+}
+
+/// Image stored as a plain value, e.g. a link to a photo, without generating a thumbnail
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct ImageValue {
+    pub image: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ImageParams {
+    pub size: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct ImageUploadParams {
+    pub compress: Option<bool>,
+}
+
+/// `?force=true` deletes a recipe which is still part of collections and removes it from them
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct DeleteParams {
+    pub force: Option<bool>,
+}
+
+/// `?window=7d&limit=10`, the window in days or hours
+#[derive(Deserialize, Debug, Clone)]
+pub struct TrendingParams {
+    pub window: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct ExpandParams {
+    #[serde(rename = "expandTemplates")]
+    pub expand_templates: Option<bool>,
+}
+
+/// `?to=1` the servings, or the yield amount for recipes with a yield, `&version=` the version scaled from
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct NormalizeServingsParams {
+    pub to: u32,
+    pub version: Option<u32>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct DiffParams {
+    pub from: Option<u32>,
+    pub to: Option<u32>,
+}
+
 impl RecipeRoutes {
-    pub async fn update_one_recipe_without_image(req: HttpRequest, database: web::Data<Dao>, recipe: Json<Recipe>) -> impl Responder {
+    /// a difficulty implausible for the cooking time is stored anyway and answered with a `Warning` header
+    pub async fn update_one_recipe_without_image(req: HttpRequest, identity: Option<Identity>, database: web::Data<Dao>, allowlist: Option<web::Data<ClassificationAllowlist>>, limits: Option<web::Data<RecipeLimits>>, recipe: Json<Value>) -> impl Responder {
+        let id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return HttpResponse::BadRequest().finish()
+        };
+        let mut recipe = match from_json_value::<Recipe>(recipe.into_inner()) {
+            Ok(recipe) => recipe,
+            Err(err) => return HttpResponse::BadRequest().json(err)
+        };
+        if let Err(err) = validate_recipe(&mut recipe, &allowlist, &limits) {
+            return recipe_error_response(err);
+        }
+        if let Err(response) = check_template_references(&database, std::slice::from_ref(&recipe)).await {
+            return response;
+        }
+
+        let warning = recipe.difficulty.cooking_time_warning(recipe.cooking_time_in_minutes);
+        match database.update_recipe_merging(id, recipe, identity.as_ref()).await {
+            Ok(_) => ok_with_warnings(warning).finish(),
+            Err(err) => dao_error_response(err),
+        }
+    }
+
+    /// stores the recipe scaled to `?to=` as its next version and answers it, 409 when the stored
+    /// version differs from `?version=` or changes while scaling, 422 for recipes without servings or yield to scale from
+    pub async fn normalize_servings(req: HttpRequest, identity: Option<Identity>, params: Query<NormalizeServingsParams>, database: web::Data<Dao>) -> HttpResponse {
+        let quick = quick_recipes(&req);
+        let id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return HttpResponse::BadRequest().finish()
+        };
+        if params.to == 0 {
+            return validation_error_response("The servings to normalize to must be greater than 0".to_string());
+        }
+        let current = match database.get_one_recipe_without_image(id.clone()).await {
+            Ok(recipe) => recipe,
+            Err(err) => return dao_error_response(err),
+        };
+        if let Some(version) = params.version.filter(|version| *version != current.version) {
+            return dao_error_response(DaoError::VersionConflict { version, fields: vec![] });
+        }
+        if !current.is_scalable() {
+            return validation_error_response("The recipe has neither servings nor a yield to scale from".to_string());
+        }
+
+        let mut scaled = current.scaled_to(params.to as f64);
+        scaled.version = current.version + 1;
+        scaled.last_modified = Utc::now();
+        match database.update_recipe_at_version(id, scaled.clone(), Some(current.version)).await {
+            Ok(_) => {
+                info!("Normalized servings of recipe id={} from={} to={}", scaled._id, current.scaling_basis(), params.to);
+                reveal_recipe(&mut scaled, database.field_encryption.as_ref(), identity.as_ref());
+                HttpResponse::Ok().json(RecipeDetail::new(scaled, &quick))
+            }
+            Err(err) => dao_error_response(err),
+        }
+    }
+
+    /// a missing `difficulty` or `defaultServings` is filled from the configured recipe defaults
+    /// the caller becomes the author, which is needed to read a draft again without admin role
+    /// `?collectionId=` adds the new recipe to a collection the caller may write to
+    /// a difficulty implausible for the cooking time is stored anyway and answered with a `Warning` header
+    pub async fn add_one_recipe(database: web::Data<Dao>, identity: Option<Identity>, params: Query<CreateParams>, allowlist: Option<web::Data<ClassificationAllowlist>>, limits: Option<web::Data<RecipeLimits>>, defaults: Option<web::Data<RecipeDefaults>>, recipe: Json<Value>) -> Either<impl Responder, impl Responder> {
+        let collection_id = match &params.collection_id {
+            Some(id) => match writable_collection(&database, identity.as_ref(), id).await {
+                Ok(id) => Some(id),
+                Err(response) => return Either::B(response),
+            },
+            None => None,
+        };
+        let mut recipe = recipe.into_inner();
+        match defaults {
+            Some(defaults) => defaults.apply(&mut recipe),
+            None => RecipeDefaults::default().apply(&mut recipe),
+        }
+        let mut recipe = match from_json_value::<Recipe>(recipe) {
+            Ok(recipe) => recipe,
+            Err(err) => return Either::B(HttpResponse::BadRequest().json(err))
+        };
+        recipe.author = identity.map(|identity| identity.user);
+        if let Err(err) = validate_new_recipe(&mut recipe, &allowlist, &limits) {
+            return Either::B(recipe_error_response(err));
+        }
+        if let Err(response) = check_template_references(&database, std::slice::from_ref(&recipe)).await {
+            return Either::B(response);
+        }
+        let warning = recipe.difficulty.cooking_time_warning(recipe.cooking_time_in_minutes);
+        let inserted = match collection_id {
+            Some(collection_id) => database.insert_recipe_into_collection(recipe, collection_id).await,
+            None => database.insert_recipe(recipe).await,
+        };
+        match inserted {
+            Ok(bson) => Either::A(ok_with_warnings(warning).json(bson)),
+            Err(err) => Either::B(dao_error_response(err)),
+        }
+    }
+
+    /// creates the schema.org recipe embedded as JSON-LD in the page at the url, responds like creating a recipe.
+    /// 422 for invalid urls and pages without a recipe, 502 when the page cannot be loaded
+    pub async fn import_recipe_from_url(database: web::Data<Dao>, identity: Identity, policy: Option<web::Data<FetchPolicy>>, allowlist: Option<web::Data<ClassificationAllowlist>>, limits: Option<web::Data<RecipeLimits>>, defaults: Option<web::Data<RecipeDefaults>>, body: Json<ImportUrl>) -> HttpResponse {
+        let url = body.into_inner().url.trim().to_string();
+        let policy = policy.map_or_else(FetchPolicy::default, |policy| *policy.get_ref());
+        let page = match fetch_page(&url, policy).await {
+            Ok(page) => page,
+            Err(FetchError::InvalidUrl(err)) => return validation_error_response(err),
+            Err(FetchError::Unreachable(err)) => {
+                error!("Could not import recipe. Err={}", err);
+                return HttpResponse::BadGateway().json(ErrorBody::new(&err));
+            }
+        };
+        let json_ld = match find_recipe_json_ld(&page) {
+            Some(json_ld) => json_ld,
+            None => return validation_error_response(format!("The page {} contains no schema.org Recipe as JSON-LD", url)),
+        };
+        let defaults = defaults.map_or_else(RecipeDefaults::default, |defaults| defaults.get_ref().clone());
+        let mut recipe = match recipe_from_json_ld(&json_ld, &url, &defaults) {
+            Ok(recipe) => recipe,
+            Err(err) => return validation_error_response(err.error),
+        };
+        recipe.author = Some(identity.user);
+        if let Err(err) = validate_new_recipe(&mut recipe, &allowlist, &limits) {
+            return recipe_error_response(err);
+        }
+        let warning = recipe.difficulty.cooking_time_warning(recipe.cooking_time_in_minutes);
+        match database.insert_recipe(recipe).await {
+            Ok(bson) => ok_with_warnings(warning).json(bson),
+            Err(err) => dao_error_response(err),
+        }
+    }
+
+    pub async fn delete_one_recipe(req: HttpRequest, params: Query<DeleteParams>, database: web::Data<Dao>) -> impl Responder {
+        let id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return HttpResponse::BadRequest().finish()
+        };
+
+        match database.delete_one_recipe(id, params.force.unwrap_or(false)).await {
+            Ok(_) => HttpResponse::Ok().finish(),
+            Err(err) => dao_error_response(err),
+        }
+    }
+
+    /// applies the rules one after another, each adding its tag to the recipes with a matching ingredient
+    pub async fn apply_retag_rules(admin: AdminIdentity, body: Json<RetagRequest>, database: web::Data<Dao>) -> HttpResponse {
+        let request = body.into_inner();
+        if let Err(err) = request.validate() {
+            return HttpResponse::UnprocessableEntity().json(ErrorBody::from(err));
+        }
+
+        info!("Applying {} retag rules for admin={}", request.rules.len(), admin.0.user);
+        let mut tagged = HashSet::new();
+        let mut rules = Vec::with_capacity(request.rules.len());
+        for rule in request.rules.iter().map(RetagRule::trimmed) {
+            let ids = match database.apply_retag_rule(&rule).await {
+                Ok(ids) => ids,
+                Err(err) => return dao_error_response(err),
+            };
+            rules.push(RetagRuleResult { tagged: ids.len() as u64, ingredient_contains: rule.ingredient_contains, add_tag: rule.add_tag });
+            tagged.extend(ids);
+        }
+        HttpResponse::Ok().json(RetagResult { rules, tagged: tagged.len() as u64 })
+    }
+
+    /// Deletes the listed or filtered recipes once confirmed: the `X-Confirm-Delete` header has to name
+    /// the number of recipes to delete, otherwise 409 with this number so the client can confirm it
+    pub async fn delete_many_recipes(req: HttpRequest, admin: AdminIdentity, body: Json<DeleteManyRequest>, database: web::Data<Dao>) -> HttpResponse {
+        let filter = match body.into_inner() {
+            DeleteManyRequest { recipe_ids: Some(ids), filter: None } => match parse_object_ids(&ids) {
+                Ok(ids) => doc! { "_id": { "$in": ids } },
+                Err(id) => return HttpResponse::BadRequest().json(ErrorBody::for_field("Recipe id is no object id", "recipeIds", &id))
+            },
+            DeleteManyRequest { recipe_ids: None, filter: Some(filter) } => match (RecipeFilter { quick_recipes: quick_recipes(&req), ..filter }).to_document() {
+                Ok(filter) => filter,
+                Err(err) => return HttpResponse::BadRequest().json(ErrorBody::new(&err.error))
+            },
+            _ => return HttpResponse::BadRequest().json(ErrorBody::new("Provide either recipeIds or a filter"))
+        };
+        let recipe_ids = match database.get_recipe_ids(filter).await {
+            Ok(ids) => ids,
+            Err(err) => return dao_error_response(err)
+        };
+
+        let count = recipe_ids.len() as u64;
+        if !is_confirmed(req.headers(), count) {
+            info!("Unconfirmed delete of {} recipes by admin={}", count, admin.0.user);
+            return HttpResponse::Conflict().json(ErrorBody::with_count(
+                &format!("Confirm deleting {} recipes with the header {}: {}", count, CONFIRM_DELETE_HEADER, count), count));
+        }
+        info!("Deleting {} recipes for admin={}", count, admin.0.user);
+        match database.delete_many_recipes(recipe_ids).await {
+            Ok(deleted) => HttpResponse::Ok().json(DeleteManyResult { deleted }),
+            Err(err) => dao_error_response(err),
+        }
+    }
+
+
+    /// responds with the resulting ingredient list, 422 when it exceeds the recipe limits.
+    /// Merges are written only while the recipe still has the version read, otherwise merged again
+    pub async fn patch_recipe_ingredients(req: HttpRequest, params: Query<IngredientParams>, limits: Option<web::Data<RecipeLimits>>, database: web::Data<Dao>, ingredients: Json<Vec<Ingredient>>) -> HttpResponse {
+        let id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return HttpResponse::BadRequest().finish()
+        };
+        if let Err(err) = validate_ingredient_ids(&ingredients) {
+            return validation_error_response(err.error);
+        }
+
+        let changes = ingredients.into_inner();
+        for attempt in 1..=MERGE_ATTEMPTS {
+            let (ingredients, version) = match params.ingredient_mode.unwrap_or(IngredientMode::Replace) {
+                IngredientMode::Replace => (changes.clone(), None),
+                IngredientMode::Merge => match database.get_one_recipe_without_image(id.clone()).await {
+                    Ok(recipe) => (merge_ingredients(recipe.ingredients, changes.clone()), Some(recipe.version)),
+                    Err(err) => return dao_error_response(err),
+                }
+            };
+            if let Err(err) = recipe_limits(&limits).validate_ingredients(&ingredients) {
+                return recipe_error_response(err);
+            }
+
+            match database.update_recipe_ingredients(id.clone(), ingredients.clone(), version).await {
+                Ok(_) => return HttpResponse::Ok().json(ingredients),
+                Err(DaoError::VersionConflict { .. }) if attempt < MERGE_ATTEMPTS =>
+                    warn!("Recipe changed while merging ingredients, merging again. id={:#?}, attempt={}", id, attempt),
+                Err(err) => return dao_error_response(err),
+            }
+        }
+        HttpResponse::Conflict().finish()
+    }
+
+    /// moves a draft to published once it is complete, by its author or an admin
+    pub async fn publish_recipe(req: HttpRequest, identity: Identity, allowlist: Option<web::Data<ClassificationAllowlist>>, limits: Option<web::Data<RecipeLimits>>, database: web::Data<Dao>) -> HttpResponse {
         let id = match extract_id_from_req(req) {
             Some(id) => id,
-            None => return HttpResponse::BadRequest()
+            None => return HttpResponse::BadRequest().finish()
+        };
+
+        let mut recipe = match database.get_one_recipe_without_image(id.clone()).await {
+            Ok(recipe) if is_visible(&recipe, Some(&identity)) => recipe,
+            Ok(_) => return HttpResponse::NotFound().finish(),
+            Err(err) => return dao_error_response(err),
         };
+        if recipe.status == RecipeStatus::Published {
+            return HttpResponse::Conflict().json(ErrorBody::new("The recipe is already published"));
+        }
+        if let Err(err) = recipe.validate_for_publishing(&recipe_limits(&limits)).and_then(|_| validate_recipe(&mut recipe, &allowlist, &limits)) {
+            return recipe_error_response(err);
+        }
 
-        match database.update_recipe_ignore_image(id, recipe.into_inner()).await {
-            Ok(_) => HttpResponse::Ok(),
-            Err(DaoError::DocumentNotFound) => HttpResponse::NotFound(),
-            Err(DaoError::DatabaseError(_)) => HttpResponse::InternalServerError(),
-            Err(DaoError::RecipeFormatError(_)) => HttpResponse::InternalServerError(),
+        match database.set_recipe_status(id, RecipeStatus::Published).await {
+            Ok(_) => HttpResponse::Ok().finish(),
+            Err(err) => dao_error_response(err),
         }
     }
 
-    pub async fn add_one_recipe(database: web::Data<Dao>, recipe: Json<Recipe>) -> Either<impl Responder, impl Responder> {
-        match database.insert_recipe(recipe.into_inner()).await {
-            Ok(bson) => Either::A(HttpResponse::Ok().json(bson)),
-            Err(DaoError::DocumentNotFound) => Either::B(HttpResponse::NotFound()),
-            Err(DaoError::DatabaseError(_)) => Either::B(HttpResponse::InternalServerError()),
-            Err(DaoError::RecipeFormatError(_)) => Either::B(HttpResponse::InternalServerError()),
+    /// bumps the modification date and version without changing the recipe, so that clients sync it again
+    pub async fn touch_recipe(req: HttpRequest, database: web::Data<Dao>) -> HttpResponse {
+        let id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return HttpResponse::BadRequest().finish()
+        };
+
+        match database.touch_recipe(id).await {
+            Ok(recipe) => HttpResponse::Ok().json(TouchResult { last_modified: recipe.last_modified, version: recipe.version }),
+            Err(err) => dao_error_response(err),
         }
     }
 
-    pub async fn delete_one_recipe(req: HttpRequest, database: web::Data<Dao>) -> impl Responder {
+    pub async fn archive_recipe(req: HttpRequest, database: web::Data<Dao>) -> impl Responder {
+        RecipeRoutes::set_recipe_archived(req, database, true).await
+    }
+
+    pub async fn unarchive_recipe(req: HttpRequest, database: web::Data<Dao>) -> impl Responder {
+        RecipeRoutes::set_recipe_archived(req, database, false).await
+    }
+
+    async fn set_recipe_archived(req: HttpRequest, database: web::Data<Dao>, archived: bool) -> HttpResponse {
         let id = match extract_id_from_req(req) {
             Some(id) => id,
-            None => return HttpResponse::BadRequest()
+            None => return HttpResponse::BadRequest().finish()
+        };
+
+        match database.set_recipe_archived(id, archived).await {
+            Ok(_) => HttpResponse::Ok().finish(),
+            Err(err) => dao_error_response(err),
+        }
+    }
+
+    /// the ids of the new recipes, when preserving ids the ids inserted, replaced and skipped
+    /// replacing stored recipes with `onConflict=replace` requires the admin role
+    /// recipes with a difficulty implausible for the cooking time are stored anyway, each answered with a `Warning` header
+    pub async fn add_many_recipes(database: web::Data<Dao>, identity: Option<Identity>, allowlist: Option<web::Data<ClassificationAllowlist>>, limits: Option<web::Data<RecipeLimits>>, params: Query<ImportParams>, recipes: Json<Vec<ImportedRecipe>>) -> Either<impl Responder, impl Responder> {
+        if let Err(response) = check_replace_allowed(&params, identity.as_ref()) {
+            return Either::B(response);
+        }
+        let mut recipes = match imported_recipes(recipes.into_inner(), params.preserves_ids()) {
+            Ok(recipes) => recipes,
+            Err(err) => return Either::B(validation_error_response(err.error)),
+        };
+        if let Some(err) = recipes.iter_mut().find_map(|recipe| validate_new_recipe(recipe, &allowlist, &limits).err()) {
+            return Either::B(recipe_error_response(err));
+        }
+        if let Err(response) = check_template_references(&database, &recipes).await {
+            return Either::B(response);
+        }
+        let warnings = recipes.iter().enumerate()
+            .filter_map(|(position, recipe)| listed_recipe_warning(position, recipe))
+            .collect::<Vec<String>>();
+        if params.preserves_ids() {
+            return match database.restore_recipes(recipes, params.on_conflict.unwrap_or_default()).await {
+                Ok(result) => Either::A(ok_with_warnings(warnings).json(result)),
+                Err(err) => Either::B(dao_error_response(err)),
+            };
+        }
+        match database.add_many_recipes(recipes).await {
+            Ok(bson) => Either::A(ok_with_warnings(warnings).json(bson)),
+            Err(err) => Either::B(dao_error_response(err)),
+        }
+    }
+
+    /// checks the recipes as the import would, one result per element in the order sent, nothing is stored
+    /// a valid recipe with a difficulty implausible for the cooking time has the warning in its result
+    pub async fn validate_many_recipes(database: web::Data<Dao>, allowlist: Option<web::Data<ClassificationAllowlist>>, limits: Option<web::Data<RecipeLimits>>, recipes: Json<Vec<Value>>) -> HttpResponse {
+        let validated = recipes.into_inner().into_iter()
+            .map(|recipe| serde_json::from_value::<Recipe>(recipe).map_err(|err| err.to_string())
+                .and_then(|mut recipe| validate_new_recipe(&mut recipe, &allowlist, &limits).map(|_| recipe).map_err(|err| err.error)))
+            .collect::<Vec<Result<Recipe, String>>>();
+        let missing = match missing_templates(&database, validated.iter().filter_map(|recipe| recipe.as_ref().ok())).await {
+            Ok(missing) => missing,
+            Err(err) => return dao_error_response(err),
+        };
+        let results = validated.into_iter()
+            .map(|recipe| recipe.and_then(|recipe| {
+                match recipe.template_object_ids().unwrap_or_default().iter().find(|id| missing.contains(id)) {
+                    Some(id) => Err(missing_template_error(id)),
+                    None => Ok(recipe),
+                }
+            }))
+            .map(|recipe| {
+                let warning = recipe.as_ref().ok().and_then(|recipe| recipe.difficulty.cooking_time_warning(recipe.cooking_time_in_minutes));
+                let mut result = ValidationResult::from(recipe.map(|_| ()));
+                result.warnings.extend(warning);
+                result
+            })
+            .collect::<Vec<ValidationResult>>();
+        info!("Validated recipes. amount={}, invalid={}", results.len(), results.iter().filter(|result| !result.valid).count());
+        HttpResponse::Ok().json(results)
+    }
+
+    /// imports a JSON array of recipes chunk by chunk while the body is streaming in
+    /// recipes with a difficulty implausible for the cooking time are stored anyway and listed in the `warnings` of the summary
+    pub async fn add_many_recipes_streamed(mut payload: web::Payload, identity: Option<Identity>, params: Query<ImportParams>, database: web::Data<Dao>, allowlist: Option<web::Data<ClassificationAllowlist>>, limits: Option<web::Data<RecipeLimits>>) -> HttpResponse {
+        let params = params.into_inner();
+        if let Err(response) = check_replace_allowed(&params, identity.as_ref()) {
+            return response;
+        }
+        let mut splitter = JsonArraySplitter::new();
+        let mut summary = ImportSummary::default();
+        let mut recipes: Vec<Recipe> = Vec::with_capacity(IMPORT_CHUNK_SIZE);
+        let mut invalid = 0;
+        let mut position = 0;
+
+        while let Some(bytes) = payload.next().await {
+            let elements = match bytes {
+                Ok(bytes) => splitter.feed(&bytes).map_err(|err| err.error),
+                Err(err) => Err(format!("{:#?}", err))
+            };
+            let elements = match elements {
+                Ok(elements) => elements,
+                Err(err) => {
+                    error!("Could not import recipes, malformed payload. Err={}", err);
+                    return HttpResponse::BadRequest().json(summary);
+                }
+            };
+
+            for element in elements {
+                match serde_json::from_slice::<ImportedRecipe>(&element).map_err(|err| err.to_string())
+                    .and_then(|recipe| recipe.into_recipe(params.preserves_ids()).map_err(|err| err.error))
+                    .and_then(|mut recipe| validate_new_recipe(&mut recipe, &allowlist, &limits).map(|_| recipe).map_err(|err| err.error)) {
+                    Ok(recipe) => {
+                        summary.warnings.extend(listed_recipe_warning(position, &recipe));
+                        recipes.push(recipe);
+                    }
+                    Err(err) => {
+                        info!("Skipping invalid recipe in import. Err={}", err);
+                        invalid += 1;
+                    }
+                }
+                position += 1;
+                if recipes.len() >= IMPORT_CHUNK_SIZE {
+                    flush_import_chunk(&database, &params, &mut summary, std::mem::take(&mut recipes), invalid).await;
+                    invalid = 0;
+                }
+            }
+        }
+
+        if let Err(err) = splitter.finish() {
+            error!("Could not import recipes, malformed payload. Err={}", err.error);
+            return HttpResponse::BadRequest().json(summary);
+        }
+        if !recipes.is_empty() || invalid > 0 {
+            flush_import_chunk(&database, &params, &mut summary, recipes, invalid).await;
+        }
+
+        HttpResponse::Ok().json(summary)
+    }
+
+    /// `?fields=`, `?fields[recipe]=` or `?exclude=` leave out fields of the recipe, `?expandTemplates=true` appends
+    /// the ingredients of the referenced templates to the ones of the recipe, masked responses are not expanded
+    /// drafts are not found for others than their author and admins
+    /// the recipe may be addressed by its slug as well, unless the feature `slug-paths` is disabled
+    /// while the database is unavailable a recipe kept by `RECIPE_CACHE_STALE_SECONDS` is answered with `X-Stale: true`
+    pub async fn get_one_recipe_without_image(req: HttpRequest, identity: Option<Identity>, mask: Query<FieldMaskParams>, expand: Query<ExpandParams>, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        let quick = quick_recipes(&req);
+        let id = match resolve_id_or_slug(&req, &database).await {
+            Ok(id) => id,
+            Err(response) => return Either::B(response)
+        };
+        let mask = match mask.to_mask() {
+            Ok(mask) => mask,
+            Err(err) => return Either::B(HttpResponse::BadRequest().json(ErrorBody::new(&err.error)))
         };
 
-        match database.delete_one_recipe(id).await {
-            Ok(_) => HttpResponse::Ok(),
-            Err(DaoError::DocumentNotFound) => HttpResponse::NotFound(),
-            Err(DaoError::DatabaseError(_)) => HttpResponse::InternalServerError(),
-            Err(DaoError::RecipeFormatError(_)) => HttpResponse::InternalServerError(),
+        if let Some(mask) = mask {
+            let (projection, author_requested) = mask.projection_with("author");
+            return match database.get_one_recipe_document(id.clone(), projection, visibility_filter(identity.as_ref())).await {
+                Ok(mut recipe) => {
+                    database.record_view(id).await.ok();
+                    reveal_document(&mut recipe, database.field_encryption.as_ref(), identity.as_ref());
+                    if !author_requested {
+                        recipe.remove("author");
+                    }
+                    Either::A(HttpResponse::Ok().json(masked_recipe_json(recipe)))
+                }
+                Err(err) => Either::B(dao_error_response(err)),
+            };
+        }
+        let (mut recipe, stale) = match database.get_one_recipe_or_stale(id.clone()).await {
+            Ok((recipe, stale)) if is_visible(&recipe, identity.as_ref()) => (recipe, stale),
+            Ok(_) => return Either::B(HttpResponse::NotFound().finish()),
+            Err(err) => return Either::B(dao_error_response(err)),
+        };
+        if !stale {
+            database.record_view(id).await.ok();
+        }
+        reveal_recipe(&mut recipe, database.field_encryption.as_ref(), identity.as_ref());
+        if expand.expand_templates.unwrap_or(false) && !recipe.template_ids.is_empty() {
+            let ids = match recipe.template_object_ids() {
+                Ok(ids) => ids,
+                Err(err) => return Either::B(dao_error_response(DaoError::from(err))),
+            };
+            match database.get_templates(Some(ids)).await {
+                Ok(templates) => recipe.expand_templates(&templates),
+                Err(err) => return Either::B(dao_error_response(err)),
+            }
+        }
+        let mut response = HttpResponse::Ok();
+        if stale {
+            response.header(STALE_HEADER, "true");
+        }
+        Either::A(response.json(RecipeDetail::new(recipe, &quick)))
+    }
+
+    pub async fn get_one_recipe_by_slug(req: HttpRequest, slug: web::Path<String>, identity: Option<Identity>, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        if !is_valid_slug(&slug) {
+            return Either::B(HttpResponse::BadRequest().json(ErrorBody::new("The slug may only contain lowercase letters, digits and single dashes")));
         }
 
+        match database.get_one_recipe_by_slug(&slug).await {
+            Ok(recipe) if !is_visible(&recipe, identity.as_ref()) => Either::B(HttpResponse::NotFound().finish()),
+            Ok(mut recipe) => {
+                database.record_view(recipe._id.clone()).await.ok();
+                reveal_recipe(&mut recipe, database.field_encryption.as_ref(), identity.as_ref());
+                Either::A(HttpResponse::Ok().json(RecipeDetail::new(recipe, &quick_recipes(&req))))
+            }
+            Err(err) => Either::B(dao_error_response(err)),
+        }
     }
 
-    pub async fn add_many_recipes(database: web::Data<Dao>, recipes: Json<Vec<Recipe>>) -> Either<impl Responder, impl Responder> {
-        match database.add_many_recipes(recipes.into_inner()).await {
-            Ok(bson) => Either::A(HttpResponse::Ok().json(bson)),
-            Err(DaoError::DocumentNotFound) =>  Either::B(HttpResponse::NotFound()),
-            Err(DaoError::DatabaseError(_)) => Either::B(HttpResponse::InternalServerError()),
-            Err(DaoError::RecipeFormatError(_)) =>  Either::B(HttpResponse::InternalServerError()),
+    pub async fn get_one_recipe_full(req: HttpRequest, identity: Option<Identity>, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        let id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return Either::B(HttpResponse::BadRequest().finish())
+        };
+
+        match database.get_one_recipe_full(id).await {
+            Ok(full_recipe) if !is_visible(&full_recipe.recipe, identity.as_ref()) => Either::B(HttpResponse::NotFound().finish()),
+            Ok(mut full_recipe) => {
+                reveal_recipe(&mut full_recipe.recipe, database.field_encryption.as_ref(), identity.as_ref());
+                Either::A(HttpResponse::Ok().json(full_recipe))
+            }
+            Err(err) => Either::B(dao_error_response(err)),
         }
     }
 
-    pub async fn get_one_recipe_without_image(req: HttpRequest, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+    /// the instructions in order with their timers for the guided cooking view, drafts only for their author and admins
+    pub async fn get_cook_mode(req: HttpRequest, identity: Option<Identity>, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
         let id = match extract_id_from_req(req) {
             Some(id) => id,
-            None => return Either::B(HttpResponse::BadRequest())
+            None => return Either::B(HttpResponse::BadRequest().finish())
         };
 
         match database.get_one_recipe_without_image(id).await {
-            Ok(recipe) => Either::A(HttpResponse::Ok().json(recipe)),
-            Err(DaoError::DocumentNotFound) =>  Either::B(HttpResponse::NotFound()),
-            Err(DaoError::DatabaseError(_)) => Either::B(HttpResponse::InternalServerError()),
-            Err(DaoError::RecipeFormatError(_)) =>  Either::B(HttpResponse::InternalServerError()),
+            Ok(recipe) if is_visible(&recipe, identity.as_ref()) => Either::A(HttpResponse::Ok().json(cook_mode_steps(&recipe))),
+            Ok(_) => Either::B(HttpResponse::NotFound().finish()),
+            Err(err) => Either::B(dao_error_response(err)),
         }
     }
 
-    pub async fn get_one_recipe_image(req: HttpRequest, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+    /// every ingredient of the recipe with what it can be swapped for, drafts only for their author and admins
+    pub async fn get_substitutions(req: HttpRequest, identity: Option<Identity>, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
         let id = match extract_id_from_req(req) {
             Some(id) => id,
-            None => return Either::A(HttpResponse::BadRequest())
+            None => return Either::B(HttpResponse::BadRequest().finish())
         };
 
-        match database.get_one_recipe_image(id).await {
-            Ok(image) => Either::B(HttpResponse::Ok().body(image)),
-            Err(DaoError::DocumentNotFound) => Either::A(HttpResponse::NotFound()),
-            Err(DaoError::DatabaseError(_)) => Either::A(HttpResponse::InternalServerError()),
-            Err(DaoError::RecipeFormatError(_)) => Either::A(HttpResponse::InternalServerError()),
+        match database.get_one_recipe_without_image(id).await {
+            Ok(recipe) if is_visible(&recipe, identity.as_ref()) => Either::A(HttpResponse::Ok().json(recipe.ingredients.iter()
+                .map(IngredientSubstitutes::from)
+                .collect::<Vec<IngredientSubstitutes>>())),
+            Ok(_) => Either::B(HttpResponse::NotFound().finish()),
+            Err(err) => Either::B(dao_error_response(err)),
         }
     }
 
-    pub async fn update_one_recipe_image(req: HttpRequest, database: web::Data<Dao>, image: String) -> impl Responder {
+    /// printable html page of the recipe, ingredients scaled to `?servings=` and formatted for `?locale=` when given
+    pub async fn get_one_recipe_print(req: HttpRequest, params: Query<ExportParams>, identity: Option<Identity>, database: web::Data<Dao>) -> HttpResponse {
         let id = match extract_id_from_req(req) {
             Some(id) => id,
-            None => return HttpResponse::BadRequest()
+            None => return HttpResponse::BadRequest().finish()
+        };
+        let locale = match params.validate() {
+            Ok(locale) => locale,
+            Err(err) => return HttpResponse::BadRequest().json(ErrorBody::new(&err))
         };
 
-        match database.update_one_recipe_image(id, Some(image)).await {
-            Ok(_) => HttpResponse::Ok(),
-            Err(DaoError::DocumentNotFound) => HttpResponse::NotFound(),
-            Err(DaoError::DatabaseError(_)) => HttpResponse::InternalServerError(),
-            Err(DaoError::RecipeFormatError(_)) => HttpResponse::InternalServerError(),
+        match database.get_one_recipe_without_image(id).await {
+            Ok(recipe) if is_visible(&recipe, identity.as_ref()) => export_response(recipe, ExportFormat::Html, params.servings, locale),
+            Ok(_) => HttpResponse::NotFound().finish(),
+            Err(err) => dao_error_response(err),
         }
     }
 
-    pub async fn delete_one_recipe_image(req: HttpRequest, database: web::Data<Dao>) -> impl Responder {
+    /// the recipe in the requested format, ingredients scaled to `?servings=` and formatted for `?locale=` when given
+    pub async fn export_one_recipe(req: HttpRequest, format: Query<ExportFormatParams>, params: Query<ExportParams>, identity: Option<Identity>, database: web::Data<Dao>) -> HttpResponse {
         let id = match extract_id_from_req(req) {
             Some(id) => id,
-            None => return HttpResponse::BadRequest()
+            None => return HttpResponse::BadRequest().finish()
+        };
+        let locale = match params.validate() {
+            Ok(locale) => locale,
+            Err(err) => return HttpResponse::BadRequest().json(ErrorBody::new(&err))
         };
 
-        match database.update_one_recipe_image(id, None).await {
-            Ok(_) => HttpResponse::Ok(),
-            Err(DaoError::DocumentNotFound) => HttpResponse::NotFound(),
-            Err(DaoError::DatabaseError(_)) => HttpResponse::InternalServerError(),
-            Err(DaoError::RecipeFormatError(_)) => HttpResponse::InternalServerError(),
+        match database.get_one_recipe_without_image(id).await {
+            Ok(recipe) if is_visible(&recipe, identity.as_ref()) => export_response(recipe, format.format, params.servings, locale),
+            Ok(_) => HttpResponse::NotFound().finish(),
+            Err(err) => dao_error_response(err),
         }
     }
 
-    pub async fn get_many_recipes(params: Query<Pagination>, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
-        let result = if params.0.is_fully_set() {
-            database.get_many_recipes(Some(params.0)).await
-        } else if params.is_fully_empty() {
-            database.get_many_recipes(None).await
-        } else {
-            return Either::B(HttpResponse::BadRequest());
+    /// renders the sent recipe like the export without storing it, for previews while editing.
+    /// The recipe is completed with the defaults and validated as when creating it
+    pub async fn preview_recipe(format: Query<ExportFormatParams>, params: Query<ExportParams>, allowlist: Option<web::Data<ClassificationAllowlist>>, limits: Option<web::Data<RecipeLimits>>, defaults: Option<web::Data<RecipeDefaults>>, recipe: Json<Value>) -> HttpResponse {
+        let locale = match params.validate() {
+            Ok(locale) => locale,
+            Err(err) => return HttpResponse::BadRequest().json(ErrorBody::new(&err))
+        };
+        let mut recipe = recipe.into_inner();
+        match defaults {
+            Some(defaults) => defaults.apply(&mut recipe),
+            None => RecipeDefaults::default().apply(&mut recipe),
+        }
+        let mut recipe = match from_json_value::<Recipe>(recipe) {
+            Ok(recipe) => recipe,
+            Err(err) => return HttpResponse::BadRequest().json(err)
+        };
+        if let Err(err) = validate_recipe(&mut recipe, &allowlist, &limits) {
+            return recipe_error_response(err);
+        }
+        export_response(recipe, format.format, params.servings, locale)
+    }
+
+    pub async fn get_similar_recipes(req: HttpRequest, params: Query<LimitParams>, identity: Option<Identity>, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        let id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return Either::B(HttpResponse::BadRequest().finish())
         };
+        match database.get_one_recipe_without_image(id.clone()).await {
+            Ok(recipe) if is_visible(&recipe, identity.as_ref()) => {}
+            Ok(_) => return Either::B(HttpResponse::NotFound().finish()),
+            Err(err) => return Either::B(dao_error_response(err)),
+        }
 
-        match result {
+        match database.get_similar_recipes(id, params.limit_or(DEFAULT_SIMILAR_LIMIT, MAX_SIMILAR_LIMIT)).await {
             Ok(recipes) => Either::A(HttpResponse::Ok().json(recipes)),
-            Err(DaoError::DatabaseError(_)) => Either::B(HttpResponse::InternalServerError()),
-            Err(DaoError::DocumentNotFound) => Either::B(HttpResponse::NotFound()),
-            Err(DaoError::RecipeFormatError(_)) => Either::B(HttpResponse::InternalServerError()),
+            Err(err) => Either::B(dao_error_response(err)),
         }
     }
-}
 
+    /// recipe summaries matching the filter as a csv download, rows are streamed while the cursor is read
+    pub async fn export_recipes_csv(req: HttpRequest, filter: Query<RecipeFilter>, identity: Option<Identity>, database: web::Data<Dao>) -> HttpResponse {
+        let filter = RecipeFilter { quick_recipes: quick_recipes(&req), ..filter.into_inner() };
+        let mut filter = match filter.to_document() {
+            Ok(filter) => filter,
+            Err(err) => return HttpResponse::BadRequest().json(ErrorBody::new(&err.error))
+        };
+        filter.extend(visibility_filter(identity.as_ref()));
+        let cursor = match database.get_recipe_summaries(filter).await {
+            Ok(cursor) => cursor,
+            Err(err) => return dao_error_response(err),
+        };
+
+        let rows = cursor.map(|summary| summary
+            .map(|summary| Bytes::from(summary_row(&summary)))
+            .log_if_err(|err| error!("Could not read recipe summary. Err={:#?}", err))
+            .map_err(ErrorInternalServerError));
+        HttpResponse::Ok()
+            .content_type(CSV_CONTENT_TYPE)
+            .header(CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", CSV_FILENAME))
+            .streaming(stream::once(future::ok(Bytes::from(CSV_HEADER))).chain(rows))
+    }
 
-fn extract_id_from_req(req: HttpRequest) -> Option<ObjectId> {
-    match req.match_info().get("id") {
-        Some(id) => match ObjectId::with_string(&id) {
-            Ok(oid) => return Some(oid),
-            _ => error!("Error provided id is no Object id")
+    /// the recipes as one PDF with a title page and each recipe on its own page. Recipes are scaled to the
+    /// servings of their entry, otherwise to `?servings=`, and formatted for `?locale=`
+    pub async fn export_cookbook(req: HttpRequest, params: Query<ExportParams>, identity: Option<Identity>, body: Json<CookbookRequest>, database: web::Data<Dao>) -> HttpResponse {
+        let locale = match params.validate() {
+            Ok(locale) => locale,
+            Err(err) => return HttpResponse::BadRequest().json(ErrorBody::new(&err))
+        };
+        let body = body.into_inner();
+        let title = body.title();
+        let entries = match body {
+            CookbookRequest { recipe_ids: Some(ids), recipes: None, filter: None, .. } => match parse_object_ids(&ids) {
+                Ok(ids) => ids.into_iter().map(|id| (id, None)).collect::<Vec<(ObjectId, Option<f64>)>>(),
+                Err(id) => return HttpResponse::BadRequest().json(ErrorBody::for_field("Recipe id is no object id", "recipeIds", &id))
+            },
+            CookbookRequest { recipe_ids: None, recipes: Some(recipes), filter: None, .. } => {
+                let mut entries = Vec::with_capacity(recipes.len());
+                for entry in recipes {
+                    let id = match ObjectId::with_string(&entry.id) {
+                        Ok(id) => id,
+                        Err(_) => return HttpResponse::BadRequest().json(ErrorBody::for_field("Recipe id is no object id", "recipes", &entry.id))
+                    };
+                    if entry.servings.is_some_and(|servings| !servings.is_finite() || servings <= 0.0) {
+                        return HttpResponse::BadRequest().json(ErrorBody::for_field("Servings must be greater than 0", "recipes", &entry.id));
+                    }
+                    entries.push((id, entry.servings));
+                }
+                entries
+            }
+            CookbookRequest { recipe_ids: None, recipes: None, filter: Some(filter), .. } => {
+                let mut filter = match (RecipeFilter { quick_recipes: quick_recipes(&req), ..filter }).to_document() {
+                    Ok(filter) => filter,
+                    Err(err) => return HttpResponse::BadRequest().json(ErrorBody::new(&err.error))
+                };
+                filter.extend(visibility_filter(identity.as_ref()));
+                match database.get_recipe_ids(filter).await {
+                    Ok(ids) => ids.into_iter().map(|id| (id, None)).collect(),
+                    Err(err) => return dao_error_response(err)
+                }
+            }
+            _ => return HttpResponse::BadRequest().json(ErrorBody::new("Provide either recipeIds, recipes or a filter"))
+        };
+        if entries.is_empty() {
+            return HttpResponse::BadRequest().json(ErrorBody::new("A cookbook needs at least one recipe"));
         }
-        None => error!("Error getting id param from HTTP request={:#?}", req)
+        if entries.len() > MAX_COOKBOOK_RECIPES {
+            return HttpResponse::BadRequest().json(ErrorBody::with_count(
+                &format!("A cookbook has at most {} recipes", MAX_COOKBOOK_RECIPES), entries.len() as u64));
+        }
+
+        let mut recipes = Vec::with_capacity(entries.len());
+        for (id, servings) in entries {
+            let recipe = match database.get_one_recipe_without_image(id.clone()).await {
+                Ok(recipe) if is_visible(&recipe, identity.as_ref()) => recipe,
+                Ok(_) | Err(DaoError::DocumentNotFound) =>
+                    return HttpResponse::NotFound().json(ErrorBody::for_field("Recipe not found", "recipeIds", &id.to_hex())),
+                Err(err) => return dao_error_response(err),
+            };
+            recipes.push(match servings.or(params.servings) {
+                Some(servings) => recipe.scaled_to(servings),
+                None => recipe,
+            });
+        }
+        HttpResponse::Ok()
+            .content_type(PDF_CONTENT_TYPE)
+            .header(CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", COOKBOOK_FILENAME))
+            .body(render_cookbook(&title, &recipes, locale))
     }
-    return None;
-}
 
+    /// field level changes between two saved versions, e.g. `?from=2&to=5`, of drafts only for their author and admins
+    pub async fn get_recipe_diff(req: HttpRequest, params: Query<DiffParams>, identity: Option<Identity>, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        let id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return Either::B(HttpResponse::BadRequest().finish())
+        };
+        let (from, to) = match (params.from, params.to) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return Either::B(HttpResponse::BadRequest().json(ErrorBody::new("Query parameters from and to are required")))
+        };
+        match database.get_one_recipe_without_image(id.clone()).await {
+            Ok(recipe) if is_visible(&recipe, identity.as_ref()) => {}
+            Ok(_) => return Either::B(HttpResponse::NotFound().finish()),
+            Err(err) => return Either::B(dao_error_response(err)),
+        }
 
-#[cfg(test)]
-mod tests {
-    use actix_web::{App, test, web};
-    use actix_web::http::StatusCode;
-    use bson::Bson;
-    use serial_test::serial;
+        let mut from = match database.get_recipe_version(id.clone(), from).await {
+            Ok(recipe) => recipe,
+            Err(err) => return Either::B(dao_error_response(err)),
+        };
+        reveal_recipe(&mut from, database.field_encryption.as_ref(), identity.as_ref());
+        match database.get_recipe_version(id, to).await {
+            Ok(mut to) => {
+                reveal_recipe(&mut to, database.field_encryption.as_ref(), identity.as_ref());
+                Either::A(HttpResponse::Ok().json(diff_recipes(&from, &to)))
+            }
+            Err(err) => Either::B(dao_error_response(err)),
+        }
+    }
 
-    use crate::dao::dao_tests::{before, cleanup_after};
-    use crate::recipe_routes::RecipeRoutes;
+    pub async fn get_equipment(database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        match database.get_equipment().await {
+            Ok(equipment) => Either::A(HttpResponse::Ok().json(equipment)),
+            Err(err) => Either::B(dao_error_response(err)),
+        }
+    }
 
-    fn create_many_recipes() -> Bson {
-        let vector = vec!(create_one_recipe_no_ingredients(),
-                          create_one_recipe_with_ingredients(),
-                          create_one_recipe_with_ingredients()
-        );
-        return Bson::Array(vector);
+    /// cuisines of the recipes the caller may see, leaving out archived recipes
+    pub async fn get_cuisines(req: HttpRequest, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        let mut filter = doc! { "archived": { "$ne": true } };
+        filter.extend(visibility_filter(identify_request(&req).as_ref()));
+        match database.get_cuisines(filter).await {
+            Ok(cuisines) => Either::A(HttpResponse::Ok().json(cuisines)),
+            Err(err) => Either::B(dao_error_response(err)),
+        }
     }
 
-    fn create_one_recipe_no_ingredients() -> Bson {
-        bson!(
-        {
-            "cookingTimeInMinutes": 12,
-            "created": "2020-09-11T12:21:21+00:00",
-            "lastModified": "2020-09-11T12:21:21+00:00",
-            "ingredients": [],
-            "version": 1,
-            "difficulty": "Easy",
-            "description": "",
-            "title": "Spaghetti",
-            "tags": [],
-            "image": null,
-            "instructions": [],
-            "defaultServings": 2
-        })
+    /// most viewed recipes of the recent `?window=`, a failed view recording does not fail the viewed recipe
+    pub async fn get_trending_recipes(req: HttpRequest, params: Query<TrendingParams>, budget: Query<BudgetParams>, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        let window = match parse_window(params.window.as_deref().unwrap_or(DEFAULT_TRENDING_WINDOW)) {
+            Ok(window) => window,
+            Err(err) => return Either::B(HttpResponse::BadRequest().json(ErrorBody::new(&err.error)))
+        };
+        let max_time = match query_budget(&req).max_time(*budget) {
+            Ok(max_time) => max_time,
+            Err(err) => return Either::B(HttpResponse::BadRequest().json(ErrorBody::new(&err.error)))
+        };
+        let limit = LimitParams { limit: params.limit }.limit_or(DEFAULT_TRENDING_LIMIT, MAX_TRENDING_LIMIT);
+
+        match database.get_trending_recipes(Utc::now() - window, limit, max_time).await {
+            Ok(recipes) => Either::A(HttpResponse::Ok().json(recipes)),
+            Err(err) => Either::B(dao_error_response(err)),
+        }
     }
 
-    fn create_one_recipe_with_image() -> Bson {
-        let bson = create_one_recipe_no_ingredients();
-        let mut doc = bson.as_document().unwrap().to_owned();
-        doc.insert("image", "image".to_string()).unwrap()
+    pub async fn get_tag_combos(params: Query<LimitParams>, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        match database.get_tag_combos(params.limit_or(DEFAULT_TAG_COMBOS_LIMIT, MAX_TAG_COMBOS_LIMIT)).await {
+            Ok(combos) => Either::A(HttpResponse::Ok().json(combos)),
+            Err(err) => Either::B(dao_error_response(err)),
+        }
     }
 
-    fn create_one_recipe_with_ingredients() -> Bson {
-        bson!(
-        {
-            "cookingTimeInMinutes": 12,
-            "created": "2020-09-11T12:21:21+00:00",
-            "lastModified": "2020-09-11T12:21:21+00:00",
+    /// `?by=cuisine`, `?by=difficulty` or `?by=tag` buckets the recipes matching the listing filters,
+    /// each group with its count and at most `?limit=` summaries, largest group first.
+    /// The grouping is aborted with 503 once it runs longer than `?maxTimeMs=`
+    pub async fn get_grouped_recipes(req: HttpRequest, params: Query<GroupedParams>, filter: Query<RecipeFilter>, budget: Query<BudgetParams>, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        let filter = RecipeFilter { quick_recipes: quick_recipes(&req), ..filter.into_inner() };
+        let mut filter = match filter.to_document() {
+            Ok(filter) => filter,
+            Err(err) => return Either::B(HttpResponse::BadRequest().json(ErrorBody::new(&err.error)))
+        };
+        filter.extend(visibility_filter(identify_request(&req).as_ref()));
+        let max_time = match query_budget(&req).max_time(*budget) {
+            Ok(max_time) => max_time,
+            Err(err) => return Either::B(HttpResponse::BadRequest().json(ErrorBody::new(&err.error)))
+        };
+        let limit = LimitParams { limit: params.limit }.limit_or(DEFAULT_GROUP_LIMIT, MAX_GROUP_LIMIT);
+        match database.get_recipe_groups(params.by, filter, limit, max_time).await {
+            Ok(groups) => Either::A(HttpResponse::Ok().json(groups)),
+            Err(err) => Either::B(dao_error_response(err)),
+        }
+    }
+
+    /// number and share in percent of the recipes matching the listing filters per difficulty
+    pub async fn get_recipe_stats(req: HttpRequest, filter: Query<RecipeFilter>, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        let filter = RecipeFilter { quick_recipes: quick_recipes(&req), ..filter.into_inner() };
+        let mut filter = match filter.to_document() {
+            Ok(filter) => filter,
+            Err(err) => return Either::B(HttpResponse::BadRequest().json(ErrorBody::new(&err.error)))
+        };
+        filter.extend(visibility_filter(identify_request(&req).as_ref()));
+        match database.get_difficulty_counts(filter).await {
+            Ok(counts) => Either::A(HttpResponse::Ok().json(RecipeStats::from_difficulty_counts(&counts))),
+            Err(err) => Either::B(dao_error_response(err)),
+        }
+    }
+
+    /// original image, or its thumbnail with `?size=thumb`
+    /// the base64 body is described by `x-image-content-type`, re-encoded uploads carry `x-original-content-type`
+    pub async fn get_one_recipe_image(req: HttpRequest, params: Query<ImageParams>, identity: Option<Identity>, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        let id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return Either::A(HttpResponse::BadRequest().finish())
+        };
+        match database.get_one_recipe_without_image(id.clone()).await {
+            Ok(recipe) if is_visible(&recipe, identity.as_ref()) => {}
+            Ok(_) => return Either::A(HttpResponse::NotFound().finish()),
+            Err(err) => return Either::A(dao_error_response(err)),
+        }
+
+        let image = match params.size.as_deref() {
+            None | Some("original") => database.get_one_recipe_image(id).await,
+            Some("thumb") => database.get_one_recipe_thumbnail(id).await.map(|thumbnail| (thumbnail, None)),
+            Some(_) => return Either::A(HttpResponse::BadRequest().json(ErrorBody::new("Size must be 'thumb' or 'original'")))
+        };
+        match image {
+            Ok((image, original_content_type)) => {
+                let mut response = HttpResponse::Ok();
+                if let Some(content_type) = thumbnail::content_type_of(&image) {
+                    response.header(IMAGE_CONTENT_TYPE_HEADER, content_type);
+                }
+                if let Some(original_content_type) = original_content_type {
+                    response.header(ORIGINAL_CONTENT_TYPE_HEADER, original_content_type);
+                }
+                Either::B(response.body(image))
+            }
+            Err(err) => Either::A(dao_error_response(err)),
+        }
+    }
+
+    /// `?compress=true` re-encodes the image as jpeg, uploads above the configured threshold always are
+    pub async fn update_one_recipe_image(req: HttpRequest, params: Query<ImageUploadParams>, database: web::Data<Dao>, image: String) -> impl Responder {
+        let id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return HttpResponse::BadRequest().finish()
+        };
+
+        let max_dimension = thumbnail::max_dimension_from_env();
+        let compress = ImageCompression::from_env().should_compress(&image, params.compress.unwrap_or(false));
+        let upload = image.clone();
+        let processed = web::block(move || Ok::<_, ()>(thumbnail::process_upload(upload, compress, max_dimension))).await
+            .log_if_err(|err| warn!("Could not process image, storing the original instead. Err={:?}", err))
+            .unwrap_or(ProcessedImage { image, original_content_type: None, thumbnail: None });
+
+        match database.update_one_recipe_image(id, Some(processed.image), processed.thumbnail, processed.original_content_type).await {
+            Ok(_) => HttpResponse::Ok().finish(),
+            Err(err) => dao_error_response(err),
+        }
+    }
+
+    pub async fn delete_one_recipe_image(req: HttpRequest, database: web::Data<Dao>) -> impl Responder {
+        let id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return HttpResponse::BadRequest().finish()
+        };
+
+        match database.update_one_recipe_image(id, None, None, None).await {
+            Ok(_) => HttpResponse::Ok().json(ImageValue { image: None }),
+            Err(err) => dao_error_response(err),
+        }
+    }
+
+    /// responds with the stored image value
+    pub async fn update_one_recipe_image_url(req: HttpRequest, database: web::Data<Dao>, body: Json<ImageValue>) -> HttpResponse {
+        let id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return HttpResponse::BadRequest().finish()
+        };
+        let image = match body.into_inner().image.map(|image| image.trim().to_string()) {
+            Some(image) if !image.is_empty() => image,
+            _ => return validation_error_response("The image must not be empty, use DELETE to clear it".to_string())
+        };
+
+        match database.update_one_recipe_image(id, Some(image.clone()), None, None).await {
+            Ok(_) => HttpResponse::Ok().json(ImageValue { image: Some(image) }),
+            Err(err) => dao_error_response(err),
+        }
+    }
+
+    /// the recipes created by the caller with the filters and pagination of the listing, 401 without identity
+    pub async fn get_my_recipes(req: HttpRequest, params: Query<Pagination>, filter: Query<RecipeFilter>, envelope: Query<EnvelopeParams>, mask: Query<FieldMaskParams>, count_settings: Option<web::Data<CountSettings>>, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        let identity = match identify_request(&req) {
+            Some(identity) => identity,
+            None => return Either::B(HttpResponse::Unauthorized().json(ErrorBody::new("Missing or unknown api token"))),
+        };
+        let filter = RecipeFilter { author: Some(identity.user), ..filter.into_inner() };
+        Either::A(RecipeRoutes::get_many_recipes(req, params, Query(filter), envelope, mask, count_settings, database).await)
+    }
+
+    /// paged listings carry a `Link` header to the neighbouring pages, pages past the last one are empty
+    /// `?fields=`, `?fields[recipe]=` or `?exclude=` leave out fields of the recipes
+    /// listings leave out the drafts the caller may not see, the meta shows the filter without this visibility
+    /// `?fuzzy=true` lists the recipe summaries, or the masked recipes, closest to `q` with their distance, pages of them when paged, closest first
+    /// the bare array is answered with a `Deprecation` header unless the envelope is requested, listings carry `Vary: Accept`
+    /// without `sorting` the recipes are listed in the configured default order, `DEFAULT_SORT`
+    /// admins get the plan and execution stats of the query with `?explain=true`, without the rating filter
+    /// `?minRating=4` lists the recipes rated 4 or better on average, see `RatingFilter`
+    pub async fn get_many_recipes(req: HttpRequest, params: Query<Pagination>, filter: Query<RecipeFilter>, envelope: Query<EnvelopeParams>, mask: Query<FieldMaskParams>, count_settings: Option<web::Data<CountSettings>>, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        let params = params.into_inner().with_default_sort(database.default_sort);
+        let pagination = if params.is_mixed_style() {
+            return Either::B(HttpResponse::BadRequest().json(ErrorBody::new("Use either page and items or offset and limit, not both")));
+        } else if params.is_fully_set() {
+            Some(params)
+        } else if params.is_fully_empty() {
+            None
+        } else {
+            return Either::B(HttpResponse::BadRequest().finish());
+        };
+
+        let quick = quick_recipes(&req);
+        let filter = RecipeFilter { quick_recipes: quick, ..filter.into_inner() };
+        let fuzzy_query = filter.fuzzy_query().map(str::to_string);
+        let requested_filter = match filter.to_document() {
+            Ok(filter) => filter,
+            Err(err) => return Either::B(HttpResponse::BadRequest().json(ErrorBody::new(&err.error)))
+        };
+        let identity = identify_request(&req);
+        let mut filter = requested_filter.clone();
+        filter.extend(visibility_filter(identity.as_ref()));
+
+        let rating = match Query::<RatingFilter>::from_query(req.query_string()) {
+            Ok(rating) => rating.into_inner(),
+            Err(err) => return Either::B(HttpResponse::BadRequest().json(ErrorBody::new(&err.to_string()))),
+        };
+        let average = match rating.average_condition() {
+            Ok(average) => average,
+            Err(err) => return Either::B(HttpResponse::BadRequest().json(ErrorBody::new(&err.error))),
+        };
+
+        let explain = Query::<ExplainParams>::from_query(req.query_string()).map(Query::into_inner).unwrap_or_default();
+        if explain.is_requested(identity.as_ref()) {
+            return match database.explain_recipes(filter).await {
+                Ok(explanation) => Either::A(HttpResponse::Ok().json(explanation)),
+                Err(err) => Either::B(dao_error_response(err)),
+            };
+        }
+
+        let mask = match mask.to_mask() {
+            Ok(mask) => mask,
+            Err(err) => return Either::B(HttpResponse::BadRequest().json(ErrorBody::new(&err.error)))
+        };
+
+        let envelope = envelope.is_requested(&req);
+        if let Some(query) = fuzzy_query {
+            let titles = doc! { "title": 1 };
+            let candidates = match average {
+                Some(average) => database.get_many_rated_recipe_documents(None, filter, average, titles).await,
+                None => database.get_many_recipe_documents(None, filter, Some(titles)).await,
+            };
+            let ranked = match candidates {
+                Ok(candidates) => rank_by_distance(&query, candidates, |candidate| candidate.get_str("title").unwrap_or_default()),
+                Err(err) => return Either::B(dao_error_response(err)),
+            };
+            let count = RecipeCount { total: ranked.len() as u64, is_estimate: false };
+            let ranked = match pagination {
+                Some(pagination) => ranked.into_iter()
+                    .skip(pagination.skip())
+                    .take(pagination.take())
+                    .collect::<Vec<(Document, usize)>>(),
+                None => ranked,
+            };
+            let ranked = match fuzzy_matches(&database, ranked, mask.as_ref(), &quick).await {
+                Ok(ranked) => ranked,
+                Err(err) => return Either::B(dao_error_response(err)),
+            };
+            let mut response = listing_response();
+            if let Some(link) = pagination.and_then(|pagination| pagination.link_header(req.path(), req.query_string(), count.total)) {
+                response.header(LINK, link);
+            }
+            if !envelope {
+                return Either::A(response.header(DEPRECATION_HEADER, "true").json(ranked));
+            }
+            let meta = ListMeta::new(count, requested_filter, None, pagination);
+            return Either::A(response.json(ListEnvelope { data: ranked, meta }));
+        }
+
+        if !envelope && pagination.is_none() {
+            let projection = mask.as_ref().map_or_else(RecipeSummary::projection, FieldMask::projection);
+            let cursor = match average {
+                Some(average) => database.get_rated_recipes_cursor(None, filter, average, projection).await,
+                None => database.get_recipes_cursor(filter, Some(projection)).await,
+            };
+            return match cursor {
+                Ok(cursor) => Either::A(listing_response()
+                    .content_type("application/json")
+                    .header(DEPRECATION_HEADER, "true")
+                    .streaming(json_array(cursor.map(move |recipe| recipe_json_bytes(recipe, mask.is_some(), &quick))))),
+                Err(err) => Either::B(dao_error_response(err)),
+            };
+        }
+
+        let recipes = match mask {
+            Some(mask) => match &average {
+                Some(average) => database.get_many_rated_recipe_documents(pagination, filter.clone(), average.clone(), mask.projection()).await,
+                None => database.get_many_recipe_documents(pagination, filter.clone(), Some(mask.projection())).await,
+            }.map(|recipes| recipes.into_iter().map(masked_recipe_json).collect::<Vec<Value>>()),
+            None => match &average {
+                Some(average) => database.get_many_rated_recipes(pagination, filter.clone(), average.clone()).await,
+                None => database.get_many_recipes(pagination, filter.clone()).await,
+            }.map(|recipes| recipes.into_iter()
+                    .map(|recipe| serde_json::to_value(recipe.with_quick(&quick)).unwrap_or(Value::Null))
+                    .collect()),
+        };
+        let recipes = match recipes {
+            Ok(recipes) => recipes,
+            Err(err) => return Either::B(dao_error_response(err)),
+        };
+
+        let estimate_threshold = count_settings.and_then(|settings| settings.estimate_threshold);
+        let count = match average {
+            Some(average) => database.count_rated_recipes(filter.clone(), average).await
+                .map(|total| RecipeCount { total, is_estimate: false }),
+            None => database.count_recipes_or_estimate(filter.clone(), estimate_threshold).await,
+        };
+        let count = match count {
+            Ok(count) => count,
+            Err(err) => return Either::B(dao_error_response(err)),
+        };
+        let mut response = listing_response();
+        if let Some(link) = pagination.and_then(|pagination| pagination.link_header(req.path(), req.query_string(), count.total)) {
+            response.header(LINK, link);
+        }
+        if !envelope {
+            return Either::A(response.header(DEPRECATION_HEADER, "true").json(recipes));
+        }
+
+        let sort = pagination.map_or_else(|| database.default_sort.sort_document(), |pagination| pagination.sort_document());
+        let meta = ListMeta::new(count, requested_filter, Some(sort), pagination);
+        Either::A(response.json(ListEnvelope { data: recipes, meta }))
+    }
+
+    /// The recipes which can be cooked within `?maxTime=` minutes from the ingredients at hand, `?have=milk&have=eggs`,
+    /// see `CookableNow`. Always answered as envelope, paged by `page` and `items`, the first 20 by default
+    pub async fn get_cookable_now(req: HttpRequest, params: Query<Pagination>, database: web::Data<Dao>) -> HttpResponse {
+        let query = Query::<Vec<(String, String)>>::from_query(req.query_string()).map(Query::into_inner).unwrap_or_default();
+        let cookable = match CookableNow::from_query(&query) {
+            Ok(cookable) => cookable,
+            Err(err) => return HttpResponse::BadRequest().json(ErrorBody::new(&err.error))
+        };
+        let pagination = Pagination {
+            page: Some(params.page.unwrap_or(1).max(1)),
+            items: Some(params.items.unwrap_or(DEFAULT_COOKABLE_PAGE_SIZE).clamp(1, MAX_COOKABLE_PAGE_SIZE)),
+            sorting: params.sorting,
+            ..Pagination::default()
+        }.with_default_sort(database.default_sort);
+
+        let quick = quick_recipes(&req);
+        let requested_filter = cookable.to_document();
+        let mut filter = requested_filter.clone();
+        filter.extend(visibility_filter(identify_request(&req).as_ref()));
+        let recipes = match database.get_many_recipes(Some(pagination), filter.clone()).await {
+            Ok(recipes) => recipes.into_iter().map(|recipe| recipe.with_quick(&quick)).collect::<Vec<RecipeSummary>>(),
+            Err(err) => return dao_error_response(err),
+        };
+        let total = match database.count_recipes(filter).await {
+            Ok(total) => total,
+            Err(err) => return dao_error_response(err),
+        };
+
+        let mut response = HttpResponse::Ok();
+        if let Some(link) = pagination.link_header(req.path(), req.query_string(), total) {
+            response.header(LINK, link);
+        }
+        let meta = ListMeta::new(RecipeCount { total, is_estimate: false }, requested_filter, Some(pagination.sort_document()), Some(pagination));
+        response.json(ListEnvelope { data: recipes, meta })
+    }
+}
+
+
+/// maps dao errors onto the status codes of the api
+pub fn dao_error_response(error: DaoError) -> HttpResponse {
+    match error {
+        DaoError::DocumentNotFound => HttpResponse::NotFound().finish(),
+        DaoError::DatabaseError(_) => HttpResponse::InternalServerError().finish(),
+        DaoError::RecipeFormatError(_) => HttpResponse::InternalServerError().finish(),
+        DaoError::TextIndexMissing => HttpResponse::InternalServerError().finish(),
+        DaoError::MissingPrivileges => HttpResponse::InternalServerError().finish(),
+        DaoError::VersionConflict { version, fields } if fields.is_empty() => HttpResponse::Conflict().json(ErrorBody::new(
+            &format!("The recipe changed since version {}, which can not be merged anymore", version))),
+        DaoError::VersionConflict { version, fields } => HttpResponse::Conflict().json(ErrorBody::new(
+            &format!("The recipe changed since version {} in the fields {}", version, fields.join(", ")))),
+        DaoError::RecipeReferenced { collections } => HttpResponse::Conflict().json(ErrorBody::with_references(
+            "The recipe is still part of collections, delete it with force=true to remove it from them",
+            collections.iter().map(|id| format!("collections/{}", id)).collect())),
+        DaoError::DuplicateKey { field, value } => HttpResponse::Conflict().json(ErrorBody::for_field(
+            &format!("A recipe with this {} already exists", field), &field, &value)),
+        DaoError::Unavailable { retry_after } => {
+            let mut response = HttpResponse::ServiceUnavailable();
+            if let Some(retry_after) = retry_after {
+                response.header(RETRY_AFTER, retry_after_seconds(retry_after).to_string());
+            }
+            response.json(ErrorBody::new("The database is unavailable"))
+        }
+        DaoError::TimeBudgetExceeded => HttpResponse::ServiceUnavailable().json(ErrorBody::new(
+            "The query exceeded its time budget, narrow it down or allow more time with maxTimeMs")),
+        DaoError::DuplicateTitle { title } => HttpResponse::Conflict().json(ErrorBody::for_field(
+            "You already have a recipe with this title, titles only differing in case or surrounding spaces are the same", "title", &title)),
+    }
+}
+
+/// the id of the path, or of the recipe with the slug in place of the id while slug paths are enabled
+async fn resolve_id_or_slug(req: &HttpRequest, database: &Dao) -> Result<ObjectId, HttpResponse> {
+    let segment = req.match_info().get("id").unwrap_or_default();
+    if let Ok(id) = ObjectId::with_string(segment) {
+        return Ok(id);
+    }
+    let slug_paths = req.app_data::<web::Data<SlugPaths>>().is_none_or(|paths| paths.enabled);
+    if !slug_paths || !is_valid_slug(segment) {
+        error!("Error provided id is neither object id nor slug={}", segment);
+        return Err(HttpResponse::BadRequest().finish());
+    }
+    database.get_recipe_id_by_slug(segment).await.map_err(dao_error_response)
+}
+
+/// a recipe document of a cursor serialized for a response as summary, masked documents are kept as projected
+fn recipe_json_bytes(recipe: Result<Document, mongodb::error::Error>, masked: bool, quick: &QuickRecipes) -> Result<Vec<u8>, actix_web::Error> {
+    let recipe = recipe.map_err(DaoError::from)
+        .and_then(|recipe| match masked {
+            true => serde_json::to_vec(&masked_recipe_json(recipe)).map_err(|err| DaoError::DatabaseError(err.to_string())),
+            false => RecipeSummary::try_from(recipe).map_err(DaoError::from)
+                .and_then(|recipe| serde_json::to_vec(&recipe.with_quick(quick)).map_err(|err| DaoError::DatabaseError(err.to_string()))),
+        });
+    recipe
+        .log_if_err(|err| error!("Could not stream recipe. Err={:#?}", err))
+        .map_err(|err| ErrorInternalServerError(format!("{:?}", err)))
+}
+
+/// the ranked recipes of a fuzzy search read as summaries, or as projected by the mask, each with its distance and in the ranked order
+async fn fuzzy_matches(database: &Dao, ranked: Vec<(Document, usize)>, mask: Option<&FieldMask>, quick: &QuickRecipes) -> Result<Vec<Value>, DaoError> {
+    let ids = ranked.iter().filter_map(|(candidate, _)| candidate.get_object_id("_id").ok().cloned()).collect::<Vec<ObjectId>>();
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut projection = mask.map_or_else(RecipeSummary::projection, FieldMask::projection);
+    let id_hidden = projection.remove("_id").is_some() && matches!(mask, Some(FieldMask::Exclude(_)));
+    let mut recipes = database.get_many_recipe_documents(None, doc! { "_id": { "$in": ids } }, Some(projection)).await?
+        .into_iter()
+        .filter_map(|recipe| Some((recipe.get_object_id("_id").ok()?.clone(), recipe)))
+        .collect::<HashMap<ObjectId, Document>>();
+    let mut matches = Vec::new();
+    for (candidate, distance) in ranked {
+        let mut recipe = match candidate.get_object_id("_id").ok().and_then(|id| recipes.remove(id)) {
+            Some(recipe) => recipe,
+            None => continue,
+        };
+        let fuzzy_match = match mask {
+            Some(_) => {
+                if id_hidden {
+                    recipe.remove("_id");
+                }
+                let mut recipe = masked_recipe_json(recipe);
+                if let Value::Object(fields) = &mut recipe {
+                    fields.insert("distance".to_string(), Value::from(distance));
+                }
+                recipe
+            }
+            None => {
+                let recipe = RecipeSummary::try_from(recipe)?.with_quick(quick);
+                serde_json::to_value(FuzzyMatch { recipe, distance }).map_err(|err| DaoError::DatabaseError(err.to_string()))?
+            }
+        };
+        matches.push(fuzzy_match);
+    }
+    Ok(matches)
+}
+
+/// referenced templates have to exist when saving, the format of their ids is checked by `validate_recipe`
+async fn check_template_references(database: &Dao, recipes: &[Recipe]) -> Result<(), HttpResponse> {
+    match missing_templates(database, recipes).await.map_err(dao_error_response)?.first() {
+        Some(id) => Err(validation_error_response(missing_template_error(id))),
+        None => Ok(()),
+    }
+}
+
+fn missing_template_error(id: &ObjectId) -> String {
+    format!("Template '{}' does not exist", id)
+}
+
+/// the ids of the templates referenced by the recipes which do not exist
+async fn missing_templates<'a>(database: &Dao, recipes: impl IntoIterator<Item = &'a Recipe>) -> Result<Vec<ObjectId>, DaoError> {
+    let ids = recipes.into_iter()
+        .flat_map(|recipe| recipe.template_object_ids().unwrap_or_default())
+        .collect::<Vec<ObjectId>>();
+    if ids.is_empty() {
+        return Ok(ids);
+    }
+    let templates = database.get_templates(Some(ids.clone())).await?;
+    Ok(ids.into_iter().filter(|id| !templates.iter().any(|template| template._id == *id)).collect())
+}
+
+/// the recipe rendered in the format, scaled to the servings when given
+fn export_response(recipe: Recipe, format: ExportFormat, servings: Option<f64>, locale: Locale) -> HttpResponse {
+    let recipe = match servings {
+        Some(servings) => recipe.scaled_to(servings),
+        None => recipe
+    };
+    match format {
+        ExportFormat::JsonLd => HttpResponse::Ok()
+            .content_type(JSON_LD_CONTENT_TYPE)
+            .json(recipe_json_ld(&recipe, locale)),
+        ExportFormat::Html => HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body(render_print_view(&recipe, locale)),
+        ExportFormat::Markdown => HttpResponse::Ok()
+            .content_type(MARKDOWN_CONTENT_TYPE)
+            .body(render_markdown(&recipe, locale)),
+    }
+}
+
+/// the allowlist is only enforced when registered as app data, allowed values get its spelling
+fn validate_recipe(recipe: &mut Recipe, allowlist: &Option<web::Data<ClassificationAllowlist>>, limits: &Option<web::Data<RecipeLimits>>) -> Result<(), RecipeFormatError> {
+    recipe.validate(&recipe_limits(limits))?;
+    match allowlist {
+        Some(allowlist) => allowlist.canonicalize(recipe),
+        None => Ok(()),
+    }
+}
+
+/// `validate_recipe` for a recipe about to be stored, a published one also has to be complete
+fn validate_new_recipe(recipe: &mut Recipe, allowlist: &Option<web::Data<ClassificationAllowlist>>, limits: &Option<web::Data<RecipeLimits>>) -> Result<(), RecipeFormatError> {
+    if recipe.status == RecipeStatus::Published {
+        recipe.validate_for_publishing(&recipe_limits(limits))?;
+    }
+    validate_recipe(recipe, allowlist, limits)
+}
+
+/// 200 with a `Warning` header for each stored recipe which looks like a data entry mistake without being invalid
+fn ok_with_warnings(warnings: impl IntoIterator<Item = String>) -> HttpResponseBuilder {
+    let mut response = HttpResponse::Ok();
+    for warning in warnings {
+        info!("Stored recipe with warning={}", warning);
+        response.header(WARNING, format!("199 - \"{}\"", warning));
+    }
+    response
+}
+
+/// the warning of a recipe sent in a list, naming its position in the list
+fn listed_recipe_warning(position: usize, recipe: &Recipe) -> Option<String> {
+    recipe.difficulty.cooking_time_warning(recipe.cooking_time_in_minutes)
+        .map(|warning| format!("Recipe {}: {}", position, warning))
+}
+
+/// the configured text limits, the default ones when none are configured
+fn recipe_limits(limits: &Option<web::Data<RecipeLimits>>) -> RecipeLimits {
+    limits.as_ref().map_or_else(RecipeLimits::default, |limits| *limits.get_ref())
+}
+
+/// 422 naming the field and limit of a text which is too long
+pub fn recipe_error_response(error: RecipeFormatError) -> HttpResponse {
+    error!("Rejecting invalid recipe. Err={}", error.error);
+    HttpResponse::UnprocessableEntity().json(ErrorBody::from(error))
+}
+
+pub fn validation_error_response(error: String) -> HttpResponse {
+    error!("Rejecting invalid recipe. Err={}", error);
+    HttpResponse::UnprocessableEntity().json(ErrorBody::new(&error))
+}
+
+/// recipes referencing templates which do not exist are counted as invalid and not stored
+fn check_replace_allowed(params: &ImportParams, identity: Option<&Identity>) -> Result<(), HttpResponse> {
+    match params.replaces_stored() && !identity.is_some_and(|identity| identity.has_role(Role::Admin)) {
+        true => Err(HttpResponse::Forbidden().json(ErrorBody::new("Replacing stored recipes requires the admin role"))),
+        false => Ok(()),
+    }
+}
+
+async fn flush_import_chunk(database: &Dao, params: &ImportParams, summary: &mut ImportSummary, mut recipes: Vec<Recipe>, invalid: usize) {
+    let mut result = ChunkResult { chunk: summary.chunks.len(), inserted: 0, invalid, replaced: 0, skipped: 0, error: None };
+    match missing_templates(database, &recipes).await {
+        Ok(missing) => recipes.retain(|recipe| {
+            let references_missing = recipe.template_object_ids().unwrap_or_default().iter().any(|id| missing.contains(id));
+            if references_missing {
+                info!("Skipping recipe in import referencing a missing template. title={}", recipe.title);
+                result.invalid += 1;
+            }
+            !references_missing
+        }),
+        Err(err) => {
+            let chunk_size = recipes.len();
+            result.error = Some(format!("{:?}", err));
+            summary.add_chunk(result, chunk_size);
+            return;
+        }
+    }
+    let chunk_size = recipes.len();
+    if chunk_size > 0 && params.preserves_ids() {
+        match database.restore_recipes(recipes, params.on_conflict.unwrap_or_default()).await {
+            Ok(restored) => {
+                result.inserted = restored.inserted.len();
+                result.replaced = restored.replaced.len();
+                result.skipped = restored.skipped.len();
+            }
+            Err(err) => result.error = Some(format!("{:?}", err)),
+        }
+    } else if chunk_size > 0 {
+        match database.add_many_recipes(recipes).await {
+            Ok(ids) => result.inserted = ids.as_array().map_or(0, |ids| ids.len()),
+            Err(err) => result.error = Some(format!("{:?}", err)),
+        }
+    }
+    summary.add_chunk(result, chunk_size);
+}
+
+pub fn extract_id_from_req(req: HttpRequest) -> Option<ObjectId> {
+    match req.match_info().get("id") {
+        Some(id) => match ObjectId::with_string(id) {
+            Ok(oid) => return Some(oid),
+            _ => error!("Error provided id is no Object id")
+        }
+        None => error!("Error getting id param from HTTP request={:#?}", req)
+    }
+    return None;
+}
+
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{App, HttpResponse, test, web};
+    use actix_web::http::header::{ACCEPT, LINK, VARY, WARNING};
+    use actix_web::http::StatusCode;
+    use bson::{Bson, Document};
+    use bson::oid::ObjectId;
+    use chrono::{Duration, Utc};
+    use image::GenericImageView;
+    use serde_json::{json, Value};
+    use serial_test::serial;
+
+    use crate::auth::auth_tests::{ADMIN_TOKEN, bearer, create_api_tokens, EDITOR_TOKEN};
+    use crate::classification::ClassificationAllowlist;
+    use crate::circuit_breaker::CircuitBreaker;
+    use crate::dao::Dao;
+    use crate::dao::dao_tests::{before, cleanup_after, create_many_recipes_without_images, create_one_recipe_without_image, unreachable_dao};
+    use crate::field_encryption::field_encryption_tests::create_field_encryption;
+    use crate::export::cookbook::MAX_COOKBOOK_RECIPES;
+    use crate::export::markdown::MARKDOWN_CONTENT_TYPE;
+    use crate::export::pdf::PDF_CONTENT_TYPE;
+    use crate::import::page_fetch::FetchPolicy;
+    use crate::import::schema_org::schema_org_tests::create_recipe_page;
+    use crate::json_error::json_config;
+    use crate::list_response::{CountSettings, DEPRECATION_HEADER, ENVELOPE_MEDIA_TYPE};
+    use crate::model::delete_many::CONFIRM_DELETE_HEADER;
+    use crate::model::difficulty::Difficulty;
+    use crate::model::ingredient_template::IngredientTemplate;
+    use crate::model::ingredients::Ingredient;
+    use crate::model::measurement_unit::MeasurementUnit;
+    use crate::model::recipe::Recipe;
+    use crate::model::recipe_status::RecipeStatus;
+    use crate::pagination::DefaultSort;
+    use crate::quick_recipes::QuickRecipes;
+    use crate::recipe_cache::{RecipeCache, STALE_HEADER};
+    use crate::recipe_defaults::RecipeDefaults;
+    use crate::recipe_limits::RecipeLimits;
+    use crate::recipe_routes::{LimitParams, RecipeRoutes};
+    use crate::slug::SlugPaths;
+    use crate::thumbnail::thumbnail_tests::{create_noisy_png_base64, create_png_base64};
+
+    /// turns the stored recipe into a draft of a user without a token in the tests
+    async fn hide_as_draft(dao: &Dao, id: &str) {
+        let filter = doc! { "_id": ObjectId::with_string(id).unwrap() };
+        dao.database.collection("recipes").update_one(filter, doc! { "$set": { "status": "draft", "author": "carol" } }, None).await.unwrap();
+    }
+
+    fn create_many_recipes() -> Bson {
+        let vector = vec!(create_one_recipe(),
+                          create_one_recipe_with_ingredients(),
+                          create_one_recipe_with_ingredients()
+        );
+        return Bson::Array(vector);
+    }
+
+    fn create_one_recipe() -> Bson {
+        bson!(
+        {
+            "cookingTimeInMinutes": 12,
+            "created": "2020-09-11T12:21:21+00:00",
+            "lastModified": "2020-09-11T12:21:21+00:00",
+            "ingredients": [
+                {
+                    "id": "0",
+                    "amount": 500,
+                    "title" : "Spaghetti",
+                    "measurementUnit": "Gramm"
+                }
+            ],
+            "version": 1,
+            "difficulty": "Easy",
+            "description": "",
+            "title": "Spaghetti",
+            "tags": [],
+            "image": null,
+            "instructions": ["Cook the spaghetti"],
+            "defaultServings": 2
+        })
+    }
+
+    /// the recipe of the dao tests with what storing it as published needs
+    fn create_one_publishable_recipe() -> Recipe {
+        let mut recipe = create_one_recipe_without_image();
+        recipe.title = "Soup".to_string();
+        recipe.ingredients = vec![Ingredient::new("0", 1.0, "Water", MeasurementUnit::Liter)];
+        recipe.instructions = vec!["Boil".to_string()];
+        recipe
+    }
+
+    fn create_one_recipe_with_image() -> Bson {
+        let bson = create_one_recipe();
+        let mut doc = bson.as_document().unwrap().to_owned();
+        doc.insert("image", "image".to_string()).unwrap()
+    }
+
+    fn create_one_recipe_with_ingredients() -> Bson {
+        bson!(
+        {
+            "cookingTimeInMinutes": 12,
+            "created": "2020-09-11T12:21:21+00:00",
+            "lastModified": "2020-09-11T12:21:21+00:00",
             "ingredients": [
                 {
                     "id": "0",
@@ -211,195 +1663,2767 @@ mod tests {
             "title": "Spaghetti",
             "tags": [],
             "image": null,
-            "instructions": [],
+            "instructions": ["Cook the spaghetti"],
             "defaultServings": 2
         })
     }
 
     #[actix_rt::test]
     #[serial]
-    async fn test_add_single_recipe() {
+    async fn test_add_single_recipe() {
+        let dao = before().await;
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/addOneRecipe", web::post().to(RecipeRoutes::add_one_recipe))).await;
+
+        let req = test::TestRequest::post().uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_client_error());
+
+        let payload = create_many_recipes();
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_client_error(), "{}", resp.status());
+
+        let payload = create_one_recipe();
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        println!("{:#?}", resp);
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let payload = create_one_recipe_with_ingredients();
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        println!("{:#?}", resp);
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let payload = create_one_recipe_with_image();
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        println!("{:#?}", resp);
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let mut incomplete = create_one_recipe().as_document().unwrap().to_owned();
+        incomplete.insert("ingredients", Bson::Array(vec![]));
+        let req = test::TestRequest::post()
+            .set_json(&incomplete).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        incomplete.insert("status", "draft");
+        let req = test::TestRequest::post()
+            .set_json(&incomplete).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_add_recipe_with_invalid_yield() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/addOneRecipe", web::post().to(RecipeRoutes::add_one_recipe))).await;
+
+        let mut payload = create_one_recipe().as_document().unwrap().to_owned();
+        payload.insert("yield", doc! { "amount": 0, "unit": "cookies" });
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        payload.insert("yield", doc! { "amount": 12, "unit": "cookies" });
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    async fn test_validate_many_recipes() {
+        let mut app = test::init_service(App::new()
+            .data(unreachable_dao(CircuitBreaker::default()).await)
+            .app_data(web::Data::new(ClassificationAllowlist::parse("Italian", "")))
+            .route("/recipes/validateMany", web::post().to(RecipeRoutes::validate_many_recipes))).await;
+
+        let valid = create_one_recipe().as_document().unwrap().to_owned();
+        let mut missing_title = valid.clone();
+        missing_title.remove("title");
+        let mut invalid_yield = valid.clone();
+        invalid_yield.insert("yield", doc! { "amount": 0, "unit": "cookies" });
+        let mut unknown_cuisine = valid.clone();
+        unknown_cuisine.insert("cuisine", "Martian");
+        let mut incomplete = valid.clone();
+        incomplete.insert("instructions", Bson::Array(vec![]));
+        let mut incomplete_draft = incomplete.clone();
+        incomplete_draft.insert("status", "draft");
+        let mut implausible = valid.clone();
+        implausible.insert("difficulty", "Hard");
+        implausible.insert("cookingTimeInMinutes", 5);
+        let payload = [valid, missing_title, invalid_yield, unknown_cuisine, incomplete, incomplete_draft, implausible];
+
+        let req = test::TestRequest::post().set_json(&payload).uri("/recipes/validateMany").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        let results = body.as_array().unwrap();
+        assert_eq!(results.len(), 7);
+        assert_eq!(results[0], json!({ "valid": true, "errors": [] }));
+        assert_eq!(results[1]["valid"], false);
+        assert_eq!(results[1]["errors"][0].as_str().unwrap().contains("title"), true);
+        assert_eq!(results[2]["valid"], false);
+        assert_eq!(results[3]["valid"], false);
+        assert_eq!(results[3]["errors"][0].as_str().unwrap().contains("Martian"), true);
+        assert_eq!(results[4]["valid"], false);
+        assert_eq!(results[4]["errors"][0].as_str().unwrap().contains("instruction"), true);
+        assert_eq!(results[5], json!({ "valid": true, "errors": [] }));
+        assert_eq!(results[6], json!({ "valid": true, "errors": [], "warnings": ["Difficulty Hard is unusual for a cooking time of 5 minutes"] }));
+
+        let req = test::TestRequest::post().set_payload("{}").header("content-type", "application/json")
+            .uri("/recipes/validateMany").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_add_recipe_without_difficulty_uses_defaults() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .app_data(web::Data::new(RecipeDefaults { difficulty: Difficulty::Medium, servings: 4, ..RecipeDefaults::default() }))
+            .route("/addOneRecipe", web::post().to(RecipeRoutes::add_one_recipe))).await;
+
+        let mut payload = create_one_recipe().as_document().unwrap().to_owned();
+        payload.remove("difficulty");
+        payload.remove("defaultServings");
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Bson = test::read_body_json(resp).await;
+
+        let recipe = dao.get_one_recipe_without_image(body.as_object_id().unwrap().to_owned()).await.unwrap();
+        assert_eq!(recipe.difficulty, Difficulty::Medium);
+        assert_eq!(recipe.default_servings, 4);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_add_recipe_without_tags_uses_default_tags() {
+        let dao = before().await;
+        let defaults = RecipeDefaults { tags: vec!["uncategorized".to_string()], ..RecipeDefaults::default() };
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .app_data(web::Data::new(defaults))
+            .route("/addOneRecipe", web::post().to(RecipeRoutes::add_one_recipe))).await;
+
+        let mut payload = create_one_recipe().as_document().unwrap().to_owned();
+        payload.remove("tags");
+        let req = test::TestRequest::post().set_json(&payload).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Bson = test::read_body_json(resp).await;
+        let recipe = dao.get_one_recipe_without_image(body.as_object_id().unwrap().to_owned()).await.unwrap();
+        assert_eq!(recipe.tags, vec!["uncategorized"]);
+
+        payload.insert("tags", vec!["soup"]);
+        let req = test::TestRequest::post().set_json(&payload).uri("/addOneRecipe").to_request();
+        let body: Bson = test::read_body_json(test::call_service(&mut app, req).await).await;
+        let recipe = dao.get_one_recipe_without_image(body.as_object_id().unwrap().to_owned()).await.unwrap();
+        assert_eq!(recipe.tags, vec!["soup"]);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_add_recipe_with_too_long_instruction() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .app_data(web::Data::new(RecipeLimits { instruction: 10, ..RecipeLimits::default() }))
+            .route("/addOneRecipe", web::post().to(RecipeRoutes::add_one_recipe))).await;
+
+        let mut payload = create_one_recipe().as_document().unwrap().to_owned();
+        payload.insert("instructions", vec!["Boil water", "Cook the pasta"]);
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["field"], "/instructions/1");
+        assert_eq!(body["limit"], 10);
+
+        payload.insert("instructions", vec!["Boil water", "Cook pasta"]);
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_one_recipe_print() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/addOneRecipe", web::post().to(RecipeRoutes::add_one_recipe))
+            .route("/recipes/{id}/print", web::get().to(RecipeRoutes::get_one_recipe_print))).await;
+
+        let mut payload = create_one_recipe_with_ingredients().as_document().unwrap().to_owned();
+        payload.insert("title", "<script>alert(1)</script>");
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        let body: Bson = test::read_body_json(resp).await;
+        let id = body.as_object_id().unwrap().to_string();
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/print?servings=4", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        assert_eq!(resp.headers().get("content-type").unwrap(), "text/html; charset=utf-8");
+
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert_eq!(body.contains("<script>"), false);
+        assert_eq!(body.contains("&lt;script&gt;"), true);
+        assert_eq!(body.contains("4 servings"), true);
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/print?servings=0", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        hide_as_draft(&dao, &id).await;
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/print", id)).to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::NOT_FOUND);
+        let (header, value) = bearer(EDITOR_TOKEN);
+        let req = test::TestRequest::get().header(header, value).uri(&format!("/recipes/{}/print", id)).to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::NOT_FOUND);
+        let (header, value) = bearer(ADMIN_TOKEN);
+        let req = test::TestRequest::get().header(header, value).uri(&format!("/recipes/{}/print", id)).to_request();
+        assert!(test::call_service(&mut app, req).await.status().is_success());
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_update_image_and_get_thumbnail() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/{id}", web::post().to(RecipeRoutes::add_one_recipe))
+            .route("/recipes/{id}/image", web::get().to(RecipeRoutes::get_one_recipe_image))
+            .route("/recipes/{id}/image", web::put().to(RecipeRoutes::update_one_recipe_image))
+            .route("/recipes/{id}/similar", web::get().to(RecipeRoutes::get_similar_recipes))).await;
+
+        let req = test::TestRequest::post()
+            .set_json(&create_one_recipe()).uri("/recipes/new").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        let body: Bson = test::read_body_json(resp).await;
+        let id = body.as_object_id().unwrap().to_string();
+
+        let image = create_png_base64(1024, 512);
+        let req = test::TestRequest::put()
+            .set_payload(image.clone()).uri(&format!("/recipes/{}/image", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/image", id)).to_request();
+        let original = test::read_response(&mut app, req).await;
+        assert_eq!(original, image.as_bytes());
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/image?size=thumb", id)).to_request();
+        let thumbnail = test::read_response(&mut app, req).await;
+        let thumbnail = image::load_from_memory(&base64::decode(thumbnail).unwrap()).unwrap();
+        assert_eq!(thumbnail.dimensions(), (256, 128));
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/image?size=huge", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/similar", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        hide_as_draft(&dao, &id).await;
+        for uri in ["image", "image?size=thumb", "similar"] {
+            let req = test::TestRequest::get().uri(&format!("/recipes/{}/{}", id, uri)).to_request();
+            let resp = test::call_service(&mut app, req).await;
+            assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        }
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_set_and_clear_image_url() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/{id}", web::post().to(RecipeRoutes::add_one_recipe))
+            .route("/recipes/{id}/image", web::get().to(RecipeRoutes::get_one_recipe_image))
+            .route("/recipes/{id}/image", web::delete().to(RecipeRoutes::delete_one_recipe_image))
+            .route("/recipes/{id}/image/url", web::put().to(RecipeRoutes::update_one_recipe_image_url))).await;
+
+        let req = test::TestRequest::post()
+            .set_json(&create_one_recipe()).uri("/recipes/new").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        let body: Bson = test::read_body_json(resp).await;
+        let id = body.as_object_id().unwrap().to_string();
+
+        let url = "https://example.org/carbonara.jpg";
+        let req = test::TestRequest::put().set_json(&json!({ "image": url }))
+            .uri(&format!("/recipes/{}/image/url", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body, json!({ "image": url }));
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/image?size=thumb", id)).to_request();
+        assert_eq!(test::read_response(&mut app, req).await, url.as_bytes());
+
+        let req = test::TestRequest::put().set_json(&json!({ "image": " " }))
+            .uri(&format!("/recipes/{}/image/url", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let req = test::TestRequest::delete().uri(&format!("/recipes/{}/image", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body, json!({ "image": null }));
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/image", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::put().set_json(&json!({ "image": url }))
+            .uri(&format!("/recipes/{}/image/url", ObjectId::new())).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_update_image_compressed() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/{id}", web::post().to(RecipeRoutes::add_one_recipe))
+            .route("/recipes/{id}/image", web::get().to(RecipeRoutes::get_one_recipe_image))
+            .route("/recipes/{id}/image", web::put().to(RecipeRoutes::update_one_recipe_image))).await;
+
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let mut recipe = create_one_recipe().as_document().unwrap().clone();
+            recipe.insert("title", format!("Recipe {}", ids.len()));
+            let req = test::TestRequest::post().set_json(&recipe).uri("/recipes/new").to_request();
+            let body: Bson = test::read_body_json(test::call_service(&mut app, req).await).await;
+            ids.push(body.as_object_id().unwrap().to_string());
+        }
+
+        let image = create_noisy_png_base64(400, 300);
+        for (id, query) in ids.iter().zip(["", "?compress=true"].iter()) {
+            let req = test::TestRequest::put()
+                .set_payload(image.clone()).uri(&format!("/recipes/{}/image{}", id, query)).to_request();
+            let resp = test::call_service(&mut app, req).await;
+            assert!(resp.status().is_success(), "{}", resp.status());
+        }
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/image", ids[0])).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.headers().get("x-image-content-type").unwrap(), "image/png");
+        assert_eq!(resp.headers().get("x-original-content-type"), None);
+        let original = test::read_body(resp).await;
+        assert_eq!(original, image.as_bytes());
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/image", ids[1])).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.headers().get("x-image-content-type").unwrap(), "image/jpeg");
+        assert_eq!(resp.headers().get("x-original-content-type").unwrap(), "image/png");
+        let compressed = test::read_body(resp).await;
+        assert_eq!(compressed.len() < original.len(), true);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_update_invalid_image_falls_back_to_original() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/{id}", web::post().to(RecipeRoutes::add_one_recipe))
+            .route("/recipes/{id}/image", web::get().to(RecipeRoutes::get_one_recipe_image))
+            .route("/recipes/{id}/image", web::put().to(RecipeRoutes::update_one_recipe_image))).await;
+
+        let req = test::TestRequest::post()
+            .set_json(&create_one_recipe()).uri("/recipes/new").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        let body: Bson = test::read_body_json(resp).await;
+        let id = body.as_object_id().unwrap().to_string();
+
+        let req = test::TestRequest::put()
+            .set_payload("no image").uri(&format!("/recipes/{}/image", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/image?size=thumb", id)).to_request();
+        let thumbnail = test::read_response(&mut app, req).await;
+        assert_eq!(thumbnail, "no image".as_bytes());
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_add_duplicate_recipe() {
+        let dao = before().await;
+        dao.database.run_command(doc! {
+            "createIndexes": "recipes",
+            "indexes": [{ "key": { "title": 1 }, "name": "title_1", "unique": true }]
+        }, None).await.unwrap();
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/addOneRecipe", web::post().to(RecipeRoutes::add_one_recipe))).await;
+
+        let payload = create_one_recipe();
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["field"], "title");
+        assert_eq!(body["value"], "Spaghetti");
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_delete_single_recipe() {
+        let dao = before().await;
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/deleteOneRecipe/{id}", web::delete().to(RecipeRoutes::delete_one_recipe))
+            .route("/addOneRecipe", web::post().to(RecipeRoutes::add_one_recipe))).await;
+
+        let payload = create_one_recipe_with_ingredients();
+
+        let req = test::TestRequest::delete()
+            .set_json(&payload).uri("/deleteOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::delete()
+            .set_json(&payload).uri("/deleteOneRecipe/hello").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::delete()
+            .set_json(&payload).uri("/deleteOneRecipe/5f7333360051027600b01a36").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+
+        let body: Bson = test::read_body_json(resp).await;
+        let inserted_id = body.as_object_id().unwrap().to_string();
+
+        let path = format!("/deleteOneRecipe/{}", inserted_id);
+        let req = test::TestRequest::delete().set_json(&payload).uri(&path).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_delete_referenced_recipe() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/{id}", web::delete().to(RecipeRoutes::delete_one_recipe))).await;
+
+        let recipe_id = dao.insert_recipe(create_one_recipe_without_image()).await.unwrap().as_object_id().unwrap().to_owned();
+        let collection_id = ObjectId::new();
+        dao.database.collection("collections").insert_one(
+            doc! {"_id": collection_id.clone(), "name": "Favourites", "recipeIds": [recipe_id.clone()]},
+            None).await.unwrap();
+
+        let req = test::TestRequest::delete().uri(&format!("/recipes/{}", recipe_id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["references"], json!([format!("collections/{}", collection_id)]));
+
+        let req = test::TestRequest::delete().uri(&format!("/recipes/{}?force=true", recipe_id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let collection = dao.database.collection("collections")
+            .find_one(doc! {"_id": collection_id}, None).await.unwrap().unwrap();
+        assert_eq!(collection.get_array("recipeIds").unwrap().len(), 0);
+
+        let req = test::TestRequest::delete().uri(&format!("/recipes/{}?force=true", recipe_id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_add_many_recipes() {
+        let dao = before().await;
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/addManyRecipes", web::post().to(RecipeRoutes::add_many_recipes))).await;
+
+        let req = test::TestRequest::post().uri("/addManyRecipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_client_error());
+
+        let payload = create_one_recipe();
+        let req = test::TestRequest::post()
+            .set_json(&payload)
+            .uri("/addManyRecipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_client_error());
+
+        let payload = create_many_recipes();
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addManyRecipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        assert_eq!(resp.headers().get(WARNING), None);
+
+        let mut implausible = create_one_publishable_recipe();
+        implausible.difficulty = Difficulty::Hard;
+        implausible.cooking_time_in_minutes = 5;
+        let req = test::TestRequest::post()
+            .set_json(&vec![create_one_publishable_recipe(), implausible]).uri("/addManyRecipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let warnings = resp.headers().get_all(WARNING).map(|value| value.to_str().unwrap().to_string()).collect::<Vec<String>>();
+        assert_eq!(warnings, vec!["199 - \"Recipe 1: Difficulty Hard is unusual for a cooking time of 5 minutes\"".to_string()]);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_reimport_export_with_preserved_ids() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/recipes", web::post().to(RecipeRoutes::add_many_recipes))
+            .route("/recipes/{id}", web::get().to(RecipeRoutes::get_one_recipe_without_image))).await;
+
+        let id = dao.add_many_recipes(vec![create_one_publishable_recipe()]).await.unwrap()
+            .as_array().unwrap()[0].as_object_id().unwrap().clone();
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}", id)).to_request();
+        let mut exported: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        dao.delete_many_recipes(vec![id.clone()]).await.unwrap();
+
+        let req = test::TestRequest::post().set_json(&json!([exported])).uri("/recipes?preserveIds=true").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, json!({ "inserted": [id.to_hex()], "replaced": [], "skipped": [] }));
+        let restored = dao.get_one_recipe_without_image(id.clone()).await.unwrap();
+        assert_eq!(restored.title, exported["title"]);
+
+        exported["title"] = json!("Restored title");
+        let req = test::TestRequest::post().set_json(&json!([exported])).uri("/recipes?preserveIds=true").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["skipped"], json!([id.to_hex()]));
+
+        let req = test::TestRequest::post().set_json(&json!([exported])).uri("/recipes?preserveIds=true&onConflict=replace").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::FORBIDDEN);
+        let (header, editor) = bearer(EDITOR_TOKEN);
+        let req = test::TestRequest::post().set_json(&json!([exported])).uri("/recipes?preserveIds=true&onConflict=replace")
+            .header(header, editor).to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::FORBIDDEN);
+
+        let filter = doc! { "_id": id.clone() };
+        dao.database.collection("recipes").update_one(filter, doc! { "$set": { "author": "bob", "notes": "encrypted:AES-256-GCM:c2VjcmV0" } }, None).await.unwrap();
+        let (_, admin) = bearer(ADMIN_TOKEN);
+        let req = test::TestRequest::post().set_json(&json!([exported])).uri("/recipes?preserveIds=true&onConflict=replace")
+            .header(header, admin).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["replaced"], json!([id.to_hex()]));
+        let replaced = dao.get_one_recipe_without_image(id.clone()).await.unwrap();
+        assert_eq!(replaced.title, "Restored title");
+        assert_eq!(replaced.slug, restored.slug);
+        assert_eq!(replaced.author, Some("bob".to_string()));
+        assert_eq!(replaced.notes, Some("encrypted:AES-256-GCM:c2VjcmV0".to_string()));
+        assert_eq!(replaced.version, restored.version + 1);
+        assert_eq!(dao.get_recipe_version(id.clone(), replaced.version).await.unwrap().title, "Restored title");
+
+        let req = test::TestRequest::post().set_json(&json!([exported])).uri("/recipes").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body[0] == json!({ "$oid": id.to_hex() }), false);
+
+        exported["id"] = json!("no-object-id");
+        let req = test::TestRequest::post().set_json(&json!([exported])).uri("/recipes?preserveIds=true").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_add_many_recipes_streamed() {
+        let dao = before().await;
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/addManyRecipes", web::post().to(RecipeRoutes::add_many_recipes_streamed))).await;
+
+        let req = test::TestRequest::post()
+            .set_payload("{}").uri("/addManyRecipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let mut recipes = create_many_recipes().as_array().unwrap().clone();
+        recipes.push(bson!({"title": "invalid"}));
+        let payload = Bson::Array(recipes).into_relaxed_extjson().to_string();
+        let req = test::TestRequest::post()
+            .set_payload(payload).uri("/addManyRecipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["inserted"], 3);
+        assert_eq!(body["invalid"], 1);
+        assert_eq!(body.get("warnings"), None);
+        assert_eq!(dao.get_many_recipes(None, Document::new()).await.unwrap().len(), 3);
+
+        let mut referencing = create_one_publishable_recipe();
+        referencing.template_ids = vec![ObjectId::new().to_hex()];
+        let payload = serde_json::to_string(&vec![referencing, create_one_publishable_recipe()]).unwrap();
+        let req = test::TestRequest::post()
+            .set_payload(payload).uri("/addManyRecipes").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["inserted"], 1);
+        assert_eq!(body["invalid"], 1);
+        assert_eq!(dao.get_many_recipes(None, Document::new()).await.unwrap().len(), 4);
+
+        let mut implausible = create_one_publishable_recipe();
+        implausible.difficulty = Difficulty::Hard;
+        implausible.cooking_time_in_minutes = 5;
+        let payload = serde_json::to_string(&vec![create_one_publishable_recipe(), implausible]).unwrap();
+        let req = test::TestRequest::post()
+            .set_payload(payload).uri("/addManyRecipes").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["inserted"], 2);
+        assert_eq!(body["warnings"], json!(["Recipe 1: Difficulty Hard is unusual for a cooking time of 5 minutes"]));
+
+        cleanup_after(dao).await;
+    }
+
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_many_recipes() {
+        let dao = before().await;
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))
+            .route("/addManyRecipes", web::post().to(RecipeRoutes::add_many_recipes))).await;
+
+        let req = test::TestRequest::get().uri("/recipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+
+        let payload = create_many_recipes();
+        let payload = payload.as_array().unwrap().clone();
+        let payload: Vec<Bson> = (0..50).map(|_| payload.first().unwrap().clone()).collect();
+        let payload = Bson::Array(payload);
+
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addManyRecipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+
+        let req = test::TestRequest::get().uri("/recipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body.as_array().unwrap().len(), 50);
+        let mut fields = body[0].as_object().unwrap().keys().cloned().collect::<Vec<String>>();
+        fields.sort();
+        assert_eq!(fields, vec!["cookingTimeInMinutes", "difficulty", "id", "image", "tags", "title"]);
+
+        let req = test::TestRequest::get().uri("/recipes?page=1&items=10&sorting=1").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), 10);
+        assert_eq!(body[0].get("ingredients").is_none(), true);
+        assert_eq!(body[0].get("instructions").is_none(), true);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_many_recipes_link_header() {
+        let dao = before().await;
+        dao.add_many_recipes(create_many_recipes_without_images(50)).await.unwrap();
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))).await;
+
+        let req = test::TestRequest::get().uri("/recipes?tags=&page=3&items=10&sorting=1").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        assert_eq!(resp.headers().get("link").unwrap(), &[
+            r#"</recipes?tags=&page=1&items=10&sorting=1>; rel="first""#,
+            r#"</recipes?tags=&page=2&items=10&sorting=1>; rel="prev""#,
+            r#"</recipes?tags=&page=4&items=10&sorting=1>; rel="next""#,
+            r#"</recipes?tags=&page=5&items=10&sorting=1>; rel="last""#,
+        ].join(", "));
+
+        let req = test::TestRequest::get().uri("/recipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.headers().get("link"), None);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_cuisine_filter_and_distinct_cuisines() {
+        let dao = before().await;
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .app_data(web::Data::new(ClassificationAllowlist::parse("Italian,French,German", "de,en")))
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))
+            .route("/recipes/cuisines", web::get().to(RecipeRoutes::get_cuisines))
+            .route("/addManyRecipes", web::post().to(RecipeRoutes::add_many_recipes))).await;
+
+        let payload = ["Italian", "French", "italian"].iter().enumerate().map(|(i, cuisine)| {
+            let mut recipe = create_one_recipe().as_document().unwrap().clone();
+            recipe.insert("title", format!("Recipe {}", i));
+            recipe.insert("cuisine", *cuisine);
+            recipe.insert("language", "de");
+            Bson::Document(recipe)
+        }).chain(std::iter::once(create_one_recipe())).collect::<Vec<Bson>>();
+        let req = test::TestRequest::post()
+            .set_json(&Bson::Array(payload)).uri("/addManyRecipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let req = test::TestRequest::get().uri("/recipes?cuisine=Italian&fields=title,cuisine").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), 2);
+        assert_eq!(body[0]["cuisine"], "Italian");
+
+        let req = test::TestRequest::get().uri("/recipes/cuisines").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, json!(["French", "Italian"]));
+
+        let mut draft = create_one_recipe_without_image();
+        draft.cuisine = Some("German".to_string());
+        let draft_id = dao.insert_recipe(draft).await.unwrap().as_object_id().unwrap().to_hex();
+        hide_as_draft(&dao, &draft_id).await;
+        let mut archived = create_one_recipe_without_image();
+        archived.title = "Archived".to_string();
+        archived.cuisine = Some("Spanish".to_string());
+        let archived_id = dao.insert_recipe(archived).await.unwrap().as_object_id().unwrap().to_owned();
+        dao.set_recipe_archived(archived_id, true).await.unwrap();
+        let req = test::TestRequest::get().uri("/recipes/cuisines").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, json!(["French", "Italian"]));
+
+        let mut unknown = create_one_recipe().as_document().unwrap().clone();
+        unknown.insert("cuisine", "Martian");
+        let req = test::TestRequest::post()
+            .set_json(&Bson::Array(vec![Bson::Document(unknown)])).uri("/addManyRecipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_recipe_stats() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/stats", web::get().to(RecipeRoutes::get_recipe_stats))).await;
+
+        let req = test::TestRequest::get().uri("/recipes/stats").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, json!({ "total": 0, "difficulties": [
+            { "difficulty": "Easy", "count": 0, "percentage": 0.0 },
+            { "difficulty": "Medium", "count": 0, "percentage": 0.0 },
+            { "difficulty": "Hard", "count": 0, "percentage": 0.0 },
+        ] }));
+
+        let mut recipes = create_many_recipes_without_images(6);
+        for (recipe, difficulty) in recipes.iter_mut().zip([Difficulty::Easy, Difficulty::Easy, Difficulty::Easy, Difficulty::Medium, Difficulty::Hard, Difficulty::Hard]) {
+            recipe.difficulty = difficulty;
+        }
+        dao.add_many_recipes(recipes).await.unwrap();
+
+        let req = test::TestRequest::get().uri("/recipes/stats").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body, json!({ "total": 6, "difficulties": [
+            { "difficulty": "Easy", "count": 3, "percentage": 50.0 },
+            { "difficulty": "Medium", "count": 1, "percentage": 16.7 },
+            { "difficulty": "Hard", "count": 2, "percentage": 33.3 },
+        ] }));
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_grouped_recipes() {
+        let dao = before().await;
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/grouped", web::get().to(RecipeRoutes::get_grouped_recipes))
+            .route("/addManyRecipes", web::post().to(RecipeRoutes::add_many_recipes))).await;
+
+        let tags = [vec!["fast", "vegan"], vec!["fast"], vec!["fast"], vec!["vegan"]];
+        let payload = ["Italian", "Italian", "French", "Italian"].iter().zip(tags.iter()).enumerate().map(|(i, (cuisine, tags))| {
+            let mut recipe = create_one_recipe().as_document().unwrap().clone();
+            recipe.insert("title", format!("Recipe {}", i));
+            recipe.insert("cuisine", *cuisine);
+            recipe.insert("tags", tags.clone());
+            Bson::Document(recipe)
+        }).chain(std::iter::once(create_one_recipe())).collect::<Vec<Bson>>();
+        let req = test::TestRequest::post()
+            .set_json(&Bson::Array(payload)).uri("/addManyRecipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        dao.database.collection("recipes").update_one(doc! { "title": "Recipe 0" }, doc! { "$set": { "thumbnail": "thumb" } }, None).await.unwrap();
+
+        let req = test::TestRequest::get().uri("/recipes/grouped?by=category&limit=2").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        let groups = body.as_array().unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!((&groups[0]["key"], &groups[0]["count"]), (&json!("Italian"), &json!(3)));
+        let titles = groups[0]["recipes"].as_array().unwrap().iter().map(|recipe| recipe["title"].clone()).collect::<Vec<Value>>();
+        assert_eq!(titles, vec![json!("Recipe 0"), json!("Recipe 1")]);
+        assert_eq!(groups[0]["recipes"][0]["image"], "thumb");
+        assert_eq!(groups[0]["recipes"][1]["image"], Value::Null);
+        assert_eq!((&groups[1]["key"], &groups[1]["count"]), (&json!("French"), &json!(1)));
+
+        let req = test::TestRequest::get().uri("/recipes/grouped?by=tag&limit=1").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        let groups = body.as_array().unwrap();
+        assert_eq!(groups.iter().map(|group| (group["key"].clone(), group["count"].clone())).collect::<Vec<(Value, Value)>>(),
+                   vec![(json!("fast"), json!(3)), (json!("vegan"), json!(2))]);
+        assert_eq!(groups.iter().all(|group| group["recipes"].as_array().unwrap().len() == 1), true);
+
+        let req = test::TestRequest::get().uri("/recipes/grouped?by=author").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_many_recipes_max_difficulty() {
+        let dao = before().await;
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))
+            .route("/addManyRecipes", web::post().to(RecipeRoutes::add_many_recipes))).await;
+
+        let mut medium = create_one_recipe().as_document().unwrap().clone();
+        medium.insert("difficulty", "Medium");
+        let mut hard = medium.clone();
+        hard.insert("difficulty", "Hard");
+        let payload = Bson::Array(vec![
+            Bson::Document(medium),
+            Bson::Document(hard),
+            create_one_recipe()]);
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addManyRecipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let req = test::TestRequest::get().uri("/recipes?maxDifficulty=Medium").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        let body: Value = test::read_body_json(resp).await;
+        let difficulties = body.as_array().unwrap().iter()
+            .map(|recipe| recipe["difficulty"].as_str().unwrap().to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(difficulties.len(), 2);
+        assert_eq!(difficulties.contains(&"Hard".to_string()), false);
+
+        let req = test::TestRequest::get().uri("/recipes?maxDifficulty=Impossible").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_many_recipes_min_rating() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))).await;
+
+        for (title, ratings) in [("Great", vec![5, 4]), ("Good", vec![4]), ("Poor", vec![1, 3]), ("Unrated", vec![])] {
+            let mut recipe = create_one_recipe_without_image();
+            recipe.title = title.to_string();
+            let id = dao.insert_recipe(recipe).await.unwrap().as_object_id().unwrap().clone();
+            let ratings = ratings.into_iter().map(|value| doc! { "recipeId": id.clone(), "value": value }).collect::<Vec<Document>>();
+            if !ratings.is_empty() {
+                dao.database.collection("ratings").insert_many(ratings, None).await.unwrap();
+            }
+        }
+
+        let titles = |body: Value| {
+            let mut titles = body.as_array().unwrap().iter()
+                .map(|recipe| recipe["title"].as_str().unwrap().to_string())
+                .collect::<Vec<String>>();
+            titles.sort();
+            titles
+        };
+        for (query, expected) in [
+            ("minRating=4", vec!["Good", "Great"]),
+            ("minRating=4.5", vec!["Great"]),
+            ("minRating=4&includeUnrated=true", vec!["Good", "Great", "Unrated"]),
+            ("minRating=0", vec!["Good", "Great", "Poor"]),
+            ("minRating=5", vec![]),
+            ("includeUnrated=true", vec!["Good", "Great", "Poor", "Unrated"]),
+        ] {
+            let req = test::TestRequest::get().uri(&format!("/recipes?{}", query)).to_request();
+            let resp = test::call_service(&mut app, req).await;
+            assert!(resp.status().is_success(), "{} {}", query, resp.status());
+            assert_eq!(titles(test::read_body_json(resp).await), expected, "{}", query);
+        }
+
+        let req = test::TestRequest::get().uri("/recipes?page=1&items=10&envelope=true&minRating=4").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["meta"]["total"], 2);
+        let req = test::TestRequest::get().uri("/recipes?page=2&items=1&sorting=1&envelope=true&minRating=4&includeUnrated=true").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["meta"]["total"], 3);
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+        assert_eq!(body["data"][0]["title"], "Good");
+
+        let req = test::TestRequest::get().uri("/recipes?fields=title&minRating=4.5").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, json!([{ "id": body[0]["id"].clone(), "title": "Great" }]));
+        let req = test::TestRequest::get().uri("/recipes?q=god&fuzzy=true&minRating=4").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(titles(body), vec!["Good"]);
+        let req = test::TestRequest::get().uri("/recipes?q=god&fuzzy=true&minRating=4.5").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(titles(body), Vec::<String>::new());
+
+        for query in ["minRating=-1", "minRating=good"] {
+            let req = test::TestRequest::get().uri(&format!("/recipes?{}", query)).to_request();
+            let resp = test::call_service(&mut app, req).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{}", query);
+        }
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_many_recipes_min_ingredients() {
+        let dao = before().await;
+        let egg = Ingredient::new("0", 1.0, "Egg", MeasurementUnit::Piece);
+        for (title, ingredients) in [("None", 0), ("One", 1), ("Three", 3)] {
+            let mut recipe = create_one_recipe_without_image();
+            recipe.title = title.to_string();
+            recipe.ingredients = vec![egg.clone(); ingredients];
+            dao.insert_recipe(recipe).await.unwrap();
+        }
+        let id = dao.insert_recipe(create_one_recipe_without_image()).await.unwrap();
+        dao.database.collection("recipes").update_one(doc! { "_id": id }, doc! {
+            "$unset": { "ingredientCount": "" },
+            "$set": { "title": "Legacy", "ingredients": [{ "id": "0", "amount": 1, "title": "Egg", "measurementUnit": "Piece" },
+                { "id": "1", "amount": 1, "title": "Egg", "measurementUnit": "Piece" }] },
+        }, None).await.unwrap();
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))).await;
+
+        let req = test::TestRequest::get().uri("/recipes?minIngredients=2").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        let mut titles = body.as_array().unwrap().iter()
+            .map(|recipe| recipe["title"].as_str().unwrap().to_string())
+            .collect::<Vec<String>>();
+        titles.sort();
+        assert_eq!(titles, vec!["Legacy", "Three"]);
+
+        let req = test::TestRequest::get().uri("/recipes?minIngredients=3&maxIngredients=1").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_many_recipes_quick() {
+        let dao = before().await;
+        for (title, minutes) in [("Exactly", 20), ("Longer", 21)] {
+            let mut recipe = create_one_recipe_without_image();
+            recipe.title = title.to_string();
+            recipe.cooking_time_in_minutes = minutes;
+            dao.insert_recipe(recipe).await.unwrap();
+        }
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .app_data(web::Data::new(QuickRecipes { max_minutes: 20 }))
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))).await;
+
+        let req = test::TestRequest::get().uri("/recipes?isQuick=true").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["title"], "Exactly");
+        assert_eq!(body[0]["isQuick"], true);
+
+        let req = test::TestRequest::get().uri("/recipes?isQuick=false&page=1&items=10&sorting=1").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["title"], "Longer");
+        assert_eq!(body[0]["isQuick"], false);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_many_recipes_created_range() {
+        let dao = before().await;
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))
+            .route("/addManyRecipes", web::post().to(RecipeRoutes::add_many_recipes))).await;
+
+        let mut october = create_one_recipe().as_document().unwrap().clone();
+        october.insert("created", "2020-10-11T12:21:21+00:00");
+        let payload = Bson::Array(vec![Bson::Document(october), create_one_recipe()]);
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addManyRecipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let req = test::TestRequest::get()
+            .uri("/recipes?createdAfter=2020-10-01T00:00:00Z&createdBefore=2020-10-31T23:59:59Z&fields=created").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["created"], "2020-10-11T12:21:21Z");
+
+        let req = test::TestRequest::get().uri("/recipes?createdAfter=last-month").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::get()
+            .uri("/recipes?createdAfter=2020-10-31T00:00:00Z&createdBefore=2020-10-01T00:00:00Z").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_many_recipes_modified_range() {
+        let dao = before().await;
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))
+            .route("/addManyRecipes", web::post().to(RecipeRoutes::add_many_recipes))).await;
+
+        let mut october = create_one_recipe().as_document().unwrap().clone();
+        october.insert("lastModified", "2020-10-11T12:21:21+00:00");
+        let payload = Bson::Array(vec![Bson::Document(october), create_one_recipe()]);
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addManyRecipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let req = test::TestRequest::get()
+            .uri("/recipes?modifiedAfter=2020-10-01T00:00:00Z&modifiedBefore=2020-10-31T23:59:59Z&fields=lastModified").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["lastModified"], "2020-10-11T12:21:21Z");
+
+        let req = test::TestRequest::get().uri("/recipes?modifiedBefore=2020-09-30T00:00:00Z&fields=lastModified").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["lastModified"], "2020-09-11T12:21:21Z");
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_normalize_servings() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/{id}/normalizeServings", web::post().to(RecipeRoutes::normalize_servings))).await;
+
+        let mut recipe = create_one_recipe_without_image();
+        recipe.default_servings = 4;
+        recipe.ingredients = vec![
+            Ingredient::new("0", 500.0, "Spaghetti", MeasurementUnit::Gramm),
+            Ingredient::new("1", 3.0, "Eggs", MeasurementUnit::Piece),
+        ];
+        let id = dao.insert_recipe(recipe).await.unwrap().as_object_id().unwrap().to_hex();
+
+        let req = test::TestRequest::post().uri(&format!("/recipes/{}/normalizeServings?to=1&version=0", id)).to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::CONFLICT);
+        let req = test::TestRequest::post().uri(&format!("/recipes/{}/normalizeServings?to=0", id)).to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let req = test::TestRequest::post().uri(&format!("/recipes/{}/normalizeServings?to=1&version=1", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["defaultServings"], 1);
+        assert_eq!(body["version"], 2);
+
+        let stored = dao.get_one_recipe_without_image(ObjectId::with_string(&id).unwrap()).await.unwrap();
+        assert_eq!(stored.default_servings, 1);
+        assert_eq!(stored.version, 2);
+        assert_eq!(stored.ingredients.iter().map(|ingredient| ingredient.amount).collect::<Vec<f64>>(), vec![125.0, 0.75]);
+
+        let unscalable = Recipe { default_servings: 0, ..create_one_recipe_without_image() };
+        let unscalable = dao.insert_recipe(unscalable).await.unwrap().as_object_id().unwrap().to_hex();
+        let req = test::TestRequest::post().uri(&format!("/recipes/{}/normalizeServings?to=1", unscalable)).to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_patch_recipe_ingredients() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(RecipeLimits { ingredients: 3, ingredient_title: 20, ..RecipeLimits::default() })
+            .route("/addOneRecipe", web::post().to(RecipeRoutes::add_one_recipe))
+            .route("/recipes/{id}", web::get().to(RecipeRoutes::get_one_recipe_without_image))
+            .route("/recipes/{id}/ingredients", web::patch().to(RecipeRoutes::patch_recipe_ingredients))).await;
+
+        let req = test::TestRequest::post()
+            .set_json(&create_one_recipe_with_ingredients()).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        let body: Bson = test::read_body_json(resp).await;
+        let id = body.as_object_id().unwrap().to_string();
+
+        let changes = json!([
+            { "id": "1", "amount": 250, "title": "Milk", "measurementUnit": "Milliliter" },
+            { "id": "2", "amount": 2, "title": "Eggs", "measurementUnit": "Piece" }
+        ]);
+        let req = test::TestRequest::patch().set_json(&changes)
+            .uri(&format!("/recipes/{}/ingredients?ingredientMode=merge", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}", id)).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        let titles = body["ingredients"].as_array().unwrap().iter()
+            .map(|ingredient| ingredient["title"].as_str().unwrap()).collect::<Vec<&str>>();
+        assert_eq!(titles, vec!["Wheat", "Milk", "Eggs"]);
+        assert_eq!(body["ingredients"][1]["amount"], 250);
+
+        let req = test::TestRequest::patch().set_json(&json!([changes[1]]))
+            .uri(&format!("/recipes/{}/ingredients?ingredientMode=replace", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}", id)).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["ingredients"].as_array().unwrap().len(), 1);
+        assert_eq!(body["ingredients"][0]["title"], "Eggs");
+
+        let duplicate_ids = json!([changes[0], changes[0]]);
+        let req = test::TestRequest::patch().set_json(&duplicate_ids)
+            .uri(&format!("/recipes/{}/ingredients?ingredientMode=merge", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let req = test::TestRequest::patch().set_json(&changes)
+            .uri(&format!("/recipes/{}/ingredients?ingredientMode=append", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::patch().set_json(&changes)
+            .uri(&format!("/recipes/{}/ingredients?ingredientMode=merge", ObjectId::new())).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let added = json!([
+            { "id": "3", "amount": 1, "title": "Salt", "measurementUnit": "Gramm" },
+            { "id": "4", "amount": 1, "title": "Pepper", "measurementUnit": "Gramm" },
+            { "id": "5", "amount": 1, "title": "Oil", "measurementUnit": "Milliliter" }
+        ]);
+        let req = test::TestRequest::patch().set_json(&added)
+            .uri(&format!("/recipes/{}/ingredients?ingredientMode=merge", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["field"], "/ingredients");
+
+        let long_title = json!([{ "id": "1", "amount": 1, "title": "Freshly ground black pepper", "measurementUnit": "Gramm" }]);
+        let req = test::TestRequest::patch().set_json(&long_title)
+            .uri(&format!("/recipes/{}/ingredients?ingredientMode=replace", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}", id)).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["ingredients"].as_array().unwrap().len(), 1);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_touch_recipe() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/{id}/touch", web::post().to(RecipeRoutes::touch_recipe))).await;
+        let mut recipe = create_one_recipe_without_image();
+        recipe.last_modified = recipe.last_modified - Duration::days(1);
+        let id = dao.insert_recipe(recipe).await.unwrap().as_object_id().unwrap().clone();
+        let before_touch = dao.get_one_recipe_without_image(id.clone()).await.unwrap();
+
+        let req = test::TestRequest::post().uri(&format!("/recipes/{}/touch", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        let touched = dao.get_one_recipe_without_image(id.clone()).await.unwrap();
+        assert_eq!(touched.last_modified > before_touch.last_modified, true);
+        assert_eq!(touched.version, before_touch.version + 1);
+        assert_eq!(body, json!({ "lastModified": touched.last_modified, "version": touched.version }));
+        assert_eq!(Recipe { last_modified: before_touch.last_modified, version: before_touch.version, ..touched }, before_touch);
+
+        let req = test::TestRequest::post().uri(&format!("/recipes/{}/touch", ObjectId::new())).to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::NOT_FOUND);
+        let req = test::TestRequest::post().uri("/recipes/nope/touch").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::BAD_REQUEST);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_archive_recipe_visibility() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))
+            .route("/addOneRecipe", web::post().to(RecipeRoutes::add_one_recipe))
+            .route("/recipes/{id}/archive", web::post().to(RecipeRoutes::archive_recipe))
+            .route("/recipes/{id}/unarchive", web::post().to(RecipeRoutes::unarchive_recipe))).await;
+
+        let req = test::TestRequest::post()
+            .set_json(&create_one_recipe()).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        let body: Bson = test::read_body_json(resp).await;
+        let id = body.as_object_id().unwrap().to_string();
+
+        let req = test::TestRequest::post().uri(&format!("/recipes/{}/archive", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let req = test::TestRequest::get().uri("/recipes").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().is_empty(), true);
+
+        let req = test::TestRequest::get().uri("/recipes?includeArchived=true&fields=archived").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["archived"], true);
+
+        let req = test::TestRequest::post().uri(&format!("/recipes/{}/unarchive", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let req = test::TestRequest::get().uri("/recipes?fields=archived").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["archived"], false);
+
+        let req = test::TestRequest::post().uri(&format!("/recipes/{}/archive", ObjectId::new())).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post().uri("/recipes/unknown/archive").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_fuzzy_search_tolerates_typos() {
+        let dao = before().await;
+        let mut recipes = create_many_recipes_without_images(4);
+        recipes[0].title = "Pancakes".to_string();
+        recipes[1].title = "Spaghetti Bolognese".to_string();
+        recipes[2].title = "Spaghetto".to_string();
+        recipes[3].title = "Spätzle".to_string();
+        dao.add_many_recipes(recipes).await.unwrap();
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))).await;
+
+        let req = test::TestRequest::get().uri("/recipes?q=spagetti&fuzzy=true").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body.as_array().unwrap().len(), 2);
+        assert_eq!(body[0]["title"], "Spaghetti Bolognese");
+        assert_eq!(body[0]["distance"], 1);
+        assert_eq!(body[1]["title"], "Spaghetto");
+
+        let req = test::TestRequest::get().uri("/recipes?q=spagetti&fuzzy=true&fields=title").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body[0]["title"], "Spaghetti Bolognese");
+        assert_eq!(body[0]["distance"], 1);
+        assert_eq!(body[0].get("description").is_none(), true);
+        assert_eq!(body[1]["title"], "Spaghetto");
+
+        let req = test::TestRequest::get().uri("/recipes?q=spagetti&fuzzy=true&exclude=id,description").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body[0]["title"], "Spaghetti Bolognese");
+        assert_eq!(body[0].get("id").is_none(), true);
+        assert_eq!(body[0].get("description").is_none(), true);
+
+        let req = test::TestRequest::get().uri("/recipes?q=spagetti&fuzzy=true&fields=unknown").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::get().uri("/recipes?q=spagetti&fuzzy=true&page=2&items=1&sorting=1").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.headers().get(DEPRECATION_HEADER).unwrap(), "true");
+        assert_eq!(resp.headers().get(VARY).unwrap(), "accept");
+        assert_eq!(resp.headers().contains_key(LINK), true);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["title"], "Spaghetto");
+
+        let req = test::TestRequest::get().uri("/recipes?q=spagetti&fuzzy=true&page=1&items=1&sorting=1")
+            .header(ACCEPT, ENVELOPE_MEDIA_TYPE).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.headers().contains_key(DEPRECATION_HEADER), false);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+        assert_eq!(body["data"][0]["title"], "Spaghetti Bolognese");
+        assert_eq!(body["meta"]["total"], 2);
+        assert_eq!(body["meta"]["totalPages"], 2);
+
+        let req = test::TestRequest::get().uri("/recipes?q=spagetti&fuzzy=true&envelope=true").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["data"].as_array().unwrap().len(), 2);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_encrypted_notes_round_trip() {
+        let mut dao = before().await;
+        dao.field_encryption = Some(create_field_encryption());
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))
+            .route("/recipes/{id}", web::get().to(RecipeRoutes::get_one_recipe_without_image))
+            .route("/recipes/{id}", web::put().to(RecipeRoutes::update_one_recipe_without_image))
+            .route("/recipes/{id}/diff", web::get().to(RecipeRoutes::get_recipe_diff))
+            .route("/recipes/{id}/normalizeServings", web::post().to(RecipeRoutes::normalize_servings))
+            .route("/addOneRecipe", web::post().to(RecipeRoutes::add_one_recipe))).await;
+        let (header, editor) = bearer(EDITOR_TOKEN);
+        let (_, admin) = bearer(ADMIN_TOKEN);
+
+        let mut recipe = create_one_publishable_recipe();
+        recipe.notes = Some("less salt for Tom".to_string());
+        recipe.source = Some("Aunt Mary".to_string());
+        let req = test::TestRequest::post().set_json(&recipe).uri("/addOneRecipe").header(header, editor.clone()).to_request();
+        let body: Bson = test::read_body_json(test::call_service(&mut app, req).await).await;
+        let id = body.as_object_id().unwrap().clone();
+
+        let stored = dao.database.collection("recipes").find_one(doc! {"_id": id.clone()}, None).await.unwrap().unwrap();
+        assert_eq!(stored.get_str("notes").unwrap().starts_with("encrypted:AES-256-GCM:"), true);
+        assert_eq!(stored.get_str("source").unwrap().contains("Mary"), false);
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}", id)).header(header, editor.clone()).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["notes"], "less salt for Tom");
+        assert_eq!(body["source"], "Aunt Mary");
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}?fields=title,notes", id)).header(header, editor.clone()).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["notes"], "less salt for Tom");
+        assert_eq!(body.get("author").is_none(), true);
+
+        recipe.notes = Some("no salt for Tom".to_string());
+        recipe.version = 2;
+        let req = test::TestRequest::put().set_json(&recipe).uri(&format!("/recipes/{}", id)).header(header, editor.clone()).to_request();
+        assert!(test::call_service(&mut app, req).await.status().is_success());
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/diff?from=1&to=2", id)).header(header, editor.clone()).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().iter().any(|change| change["path"] == "/notes"
+            && change["old"] == "less salt for Tom" && change["new"] == "no salt for Tom"), true);
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/diff?from=1&to=2", id)).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.to_string().contains("encrypted:"), false);
+        assert_eq!(body.as_array().unwrap().iter().any(|change| change["path"] == "/notes"), false);
+
+        let req = test::TestRequest::post().uri(&format!("/recipes/{}/normalizeServings?to=1", id)).header(header, editor.clone()).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["notes"], "no salt for Tom");
+        let req = test::TestRequest::post().uri(&format!("/recipes/{}/normalizeServings?to=2", id)).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["defaultServings"], 2);
+        assert_eq!(body["notes"], Value::Null);
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}", id)).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["notes"], Value::Null);
+        assert_eq!(body["source"], Value::Null);
+
+        let req = test::TestRequest::get().uri("/recipes?fields=notes").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body[0].get("notes").is_none(), true);
+
+        dao.database.collection("recipes").update_one(doc! { "_id": id.clone() }, doc! { "$set": { "author": "carol" } }, None).await.unwrap();
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}", id)).header(header, editor.clone()).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["notes"], Value::Null);
+        let mut read_back = dao.get_one_recipe_without_image(id.clone()).await.unwrap();
+        read_back.notes = None;
+        read_back.source = None;
+        read_back.description = "Edited without the notes".to_string();
+        let req = test::TestRequest::put().set_json(&read_back).uri(&format!("/recipes/{}", id)).header(header, editor.clone()).to_request();
+        assert!(test::call_service(&mut app, req).await.status().is_success());
+        let stored = dao.database.collection("recipes").find_one(doc! {"_id": id.clone()}, None).await.unwrap().unwrap();
+        assert_eq!(stored.get_str("description").unwrap(), "Edited without the notes");
+        assert_eq!(stored.get_str("notes").unwrap().starts_with("encrypted:AES-256-GCM:"), true);
+        assert_eq!(stored.get_str("source").unwrap().starts_with("encrypted:AES-256-GCM:"), true);
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}?fields=notes", id)).header(header, editor).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.get("notes").is_none(), true);
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}", id)).header(header, admin).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["notes"], "no salt for Tom");
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_publish_draft_recipe() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))
+            .route("/recipes/{id}", web::get().to(RecipeRoutes::get_one_recipe_without_image))
+            .route("/addOneRecipe", web::post().to(RecipeRoutes::add_one_recipe))
+            .route("/recipes/{id}/publish", web::post().to(RecipeRoutes::publish_recipe))).await;
+        let (header, editor) = bearer(EDITOR_TOKEN);
+        let (_, admin) = bearer(ADMIN_TOKEN);
+
+        let mut draft = create_one_recipe_without_image();
+        draft.status = RecipeStatus::Draft;
+        draft.instructions = vec![];
+        let req = test::TestRequest::post().header(header, editor.clone())
+            .set_json(&draft).uri("/addOneRecipe").to_request();
+        let body: Bson = test::read_body_json(test::call_service(&mut app, req).await).await;
+        let id = body.as_object_id().unwrap().to_string();
+
+        let req = test::TestRequest::get().uri("/recipes").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().is_empty(), true);
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get().uri("/recipes").header(header, editor.clone()).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}", id)).header(header, admin.clone()).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["status"], "draft");
+        assert_eq!(body["author"], "bob");
+
+        let req = test::TestRequest::post().uri(&format!("/recipes/{}/publish", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::post().uri(&format!("/recipes/{}/publish", id)).header(header, editor.clone()).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let object_id = ObjectId::with_string(&id).unwrap();
+        let mut recipe = dao.get_one_recipe_without_image(object_id.clone()).await.unwrap();
+        recipe.instructions = vec!["Mix".to_string()];
+        dao.update_recipe_at_version(object_id, recipe, None).await.unwrap();
+
+        let req = test::TestRequest::post().uri(&format!("/recipes/{}/publish", id)).header(header, editor.clone()).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let req = test::TestRequest::get().uri("/recipes").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+
+        let req = test::TestRequest::post().uri(&format!("/recipes/{}/publish", id)).header(header, editor).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_export_recipe_json_ld() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/recipes/{id}/export", web::get().to(RecipeRoutes::export_one_recipe))).await;
+
+        let mut recipe = create_one_recipe_without_image();
+        recipe.title = "Pancakes".to_string();
+        recipe.cooking_time_in_minutes = 12;
+        recipe.ingredients = vec![Ingredient::new("0", 250.0, "Flour", MeasurementUnit::Gramm)];
+        recipe.instructions = vec!["Mix".to_string()];
+        let id = dao.insert_recipe(recipe).await.unwrap().as_object_id().unwrap().to_hex();
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/export?format=jsonld&locale=de-DE", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        assert_eq!(resp.headers().get("content-type").unwrap(), "application/ld+json");
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["@type"], "Recipe");
+        assert_eq!(body["name"], "Pancakes");
+        assert_eq!(body["cookTime"], "PT12M");
+        assert_eq!(body["recipeIngredient"], json!(["250 g Flour"]));
+        assert_eq!(body["recipeInstructions"][0]["text"], "Mix");
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/export?format=pdf", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/export?format=jsonld", ObjectId::new())).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        hide_as_draft(&dao, &id).await;
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/export?format=jsonld", id)).to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::NOT_FOUND);
+        let (header, value) = bearer(ADMIN_TOKEN);
+        let req = test::TestRequest::get().header(header, value).uri(&format!("/recipes/{}/export?format=jsonld", id)).to_request();
+        assert!(test::call_service(&mut app, req).await.status().is_success());
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_export_cookbook() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/cookbook", web::post().to(RecipeRoutes::export_cookbook))).await;
+
+        let mut pasta = create_one_recipe_without_image();
+        pasta.title = "Pasta".to_string();
+        pasta.ingredients = vec![Ingredient::new("0", 100.0, "Spaghetti", MeasurementUnit::Gramm)];
+        let pasta = dao.insert_recipe(pasta).await.unwrap().as_object_id().unwrap().to_hex();
+        let mut soup = create_one_recipe_without_image();
+        soup.title = "Soup".to_string();
+        soup.ingredients = vec![Ingredient::new("0", 50.0, "Tomatoes", MeasurementUnit::Gramm)];
+        let soup = dao.insert_recipe(soup).await.unwrap().as_object_id().unwrap().to_hex();
+
+        let body = json!({ "title": "Weekend", "recipes": [{ "id": pasta, "servings": 3 }, { "id": soup }] });
+        let req = test::TestRequest::post().uri("/recipes/cookbook?servings=2").set_json(&body).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        assert_eq!(resp.headers().get("content-type").unwrap(), PDF_CONTENT_TYPE);
+        assert_eq!(resp.headers().get("content-disposition").unwrap(), "attachment; filename=\"cookbook.pdf\"");
+        let pdf = String::from_utf8_lossy(&test::read_body(resp).await).to_string();
+        assert_eq!(pdf.starts_with("%PDF-"), true);
+        assert_eq!(pdf.contains("/Count 3"), true);
+        assert_eq!(pdf.contains("(Weekend) Tj"), true);
+        assert_eq!(pdf.contains("300 Gramm Spaghetti) Tj"), true);
+        assert_eq!(pdf.contains("100 Gramm Tomatoes) Tj"), true);
+
+        let req = test::TestRequest::post().uri("/recipes/cookbook").set_json(&json!({ "filter": {} })).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let pdf = String::from_utf8_lossy(&test::read_body(resp).await).to_string();
+        assert_eq!(pdf.contains("/Count 3"), true);
+        assert_eq!(pdf.contains("(Cookbook) Tj"), true);
+
+        let req = test::TestRequest::post().uri("/recipes/cookbook").set_json(&json!({ "recipeIds": [pasta, ObjectId::new().to_hex()] })).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    async fn test_invalid_cookbook_requests() {
+        let mut app = test::init_service(App::new()
+            .data(unreachable_dao(CircuitBreaker::default()).await)
+            .route("/recipes/cookbook", web::post().to(RecipeRoutes::export_cookbook))).await;
+        let id = ObjectId::new().to_hex();
+
+        for (uri, body) in [
+            ("/recipes/cookbook", json!({})),
+            ("/recipes/cookbook", json!({ "recipeIds": [] })),
+            ("/recipes/cookbook", json!({ "recipeIds": [id], "filter": {} })),
+            ("/recipes/cookbook", json!({ "recipeIds": ["pasta"] })),
+            ("/recipes/cookbook", json!({ "recipes": [{ "id": id, "servings": 0 }] })),
+            ("/recipes/cookbook?servings=-1", json!({ "recipeIds": [id] })),
+            ("/recipes/cookbook", json!({ "recipeIds": vec![id.clone(); MAX_COOKBOOK_RECIPES + 1] })),
+        ] {
+            let req = test::TestRequest::post().uri(uri).set_json(&body).to_request();
+            let resp = test::call_service(&mut app, req).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{} {}", uri, body);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_add_recipe_with_wrong_typed_field() {
+        let mut app = test::init_service(App::new()
+            .data(unreachable_dao(CircuitBreaker::default()).await)
+            .app_data(json_config())
+            .route("/recipes/{id}", web::post().to(RecipeRoutes::add_one_recipe))
+            .route("/recipes/{id}", web::put().to(RecipeRoutes::update_one_recipe_without_image))).await;
+
+        let mut recipe = create_one_recipe();
+        recipe.as_document_mut().unwrap().insert("cookingTimeInMinutes", "ten minutes");
+        let req = test::TestRequest::post().uri("/recipes/new").set_json(&recipe).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["field"], "cookingTimeInMinutes");
+        assert_eq!(body["expected"], "u32");
+
+        let req = test::TestRequest::put().uri(&format!("/recipes/{}", ObjectId::new())).set_json(&recipe).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["field"], "cookingTimeInMinutes");
+    }
+
+    #[actix_rt::test]
+    async fn test_preview_recipe_markdown() {
+        let mut app = test::init_service(App::new()
+            .route("/recipes/preview", web::post().to(RecipeRoutes::preview_recipe))).await;
+
+        let req = test::TestRequest::post().uri("/recipes/preview?format=markdown&servings=4").set_json(&create_one_recipe()).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        assert_eq!(resp.headers().get("content-type").unwrap(), MARKDOWN_CONTENT_TYPE);
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert_eq!(body.starts_with("# Spaghetti\n\n4 servings"), true);
+
+        let req = test::TestRequest::post().uri("/recipes/preview?format=html").set_json(&create_one_recipe()).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.headers().get("content-type").unwrap(), "text/html; charset=utf-8");
+
+        let mut invalid = create_one_recipe();
+        invalid.as_document_mut().unwrap().insert("yield", doc! { "amount": 0, "unit": "cookies" });
+        let req = test::TestRequest::post().uri("/recipes/preview?format=markdown").set_json(&invalid).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let req = test::TestRequest::post().uri("/recipes/preview?format=pdf").set_json(&create_one_recipe()).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::post().uri("/recipes/preview?format=markdown&servings=0").set_json(&create_one_recipe()).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_trending_recipes() {
+        let dao = before().await;
+        dao.ensure_views_collection().await.unwrap();
+        dao.ensure_views_collection().await.unwrap();
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/trending", web::get().to(RecipeRoutes::get_trending_recipes))
+            .route("/recipes/{id}", web::get().to(RecipeRoutes::get_one_recipe_without_image))).await;
+
+        let mut recipes = create_many_recipes_without_images(3);
+        recipes[0].title = "Soup".to_string();
+        recipes[1].title = "Cake".to_string();
+        let ids = dao.add_many_recipes(recipes).await.unwrap().as_array().unwrap().iter()
+            .map(|id| id.as_object_id().unwrap().to_owned())
+            .collect::<Vec<ObjectId>>();
+        for (id, views) in ids.iter().zip([1, 3, 0]) {
+            for _ in 0..views {
+                let req = test::TestRequest::get().uri(&format!("/recipes/{}", id)).to_request();
+                assert!(test::call_service(&mut app, req).await.status().is_success());
+            }
+        }
+        dao.database.collection("recipe_views").insert_one(
+            doc! { "recipeId": ids[0].clone(), "viewedAt": Utc::now() - Duration::days(10) }, None).await.unwrap();
+
+        let req = test::TestRequest::get().uri("/recipes/trending?window=7d").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        let trending = body.as_array().unwrap();
+        assert_eq!(trending.len(), 2);
+        assert_eq!(trending[0]["title"], "Cake");
+        assert_eq!(trending[0]["views"], 3);
+        assert_eq!(trending[1]["title"], "Soup");
+        assert_eq!(trending[1]["views"], 1);
+
+        let req = test::TestRequest::get().uri("/recipes/trending?window=30d&limit=1").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, json!([trending[0]]));
+
+        let req = test::TestRequest::get().uri("/recipes/trending?window=1y").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_export_recipes_csv() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/export/csv", web::get().to(RecipeRoutes::export_recipes_csv))).await;
+
+        let mut first = create_one_recipe_without_image();
+        first.title = "Mac, \"cheese\"".to_string();
+        first.tags = vec!["pasta".to_string(), "quick".to_string()];
+        first.equipment = vec!["Pot".to_string()];
+        first.ingredients = vec![Ingredient::new("0", 200.0, "Macaroni", MeasurementUnit::Gramm)];
+        let mut second = create_one_recipe_without_image();
+        second.title = "Salad".to_string();
+        let first_id = dao.insert_recipe(first).await.unwrap().as_object_id().unwrap().to_hex();
+        let second_id = dao.insert_recipe(second).await.unwrap().as_object_id().unwrap().to_hex();
+
+        let req = test::TestRequest::get().uri("/recipes/export/csv").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        assert_eq!(resp.headers().get("content-type").unwrap(), "text/csv; charset=utf-8");
+        assert_eq!(resp.headers().get("content-disposition").unwrap(), "attachment; filename=\"recipes.csv\"");
+
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        let rows = body.split("\r\n").collect::<Vec<&str>>();
+        assert_eq!(rows[0], "id,title,difficulty,cookingTimeInMinutes,tags,equipment,ingredientCount");
+        assert_eq!(rows[1], format!("{},\"Mac, \"\"cheese\"\"\",Easy,10,pasta;quick,pot,1", first_id));
+        assert_eq!(rows[2], format!("{},Salad,Easy,10,,,0", second_id));
+        assert_eq!(rows[3], "");
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_equipment_filter_and_distinct() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/equipment", web::get().to(RecipeRoutes::get_equipment))
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))).await;
+
+        let mut cake = create_one_recipe_without_image();
+        cake.title = "Cake".to_string();
+        cake.equipment = vec![" Oven".to_string(), "Stand mixer".to_string(), "oven".to_string()];
+        let mut smoothie = create_one_recipe_without_image();
+        smoothie.title = "Smoothie".to_string();
+        smoothie.equipment = vec!["Blender".to_string()];
+        dao.insert_recipe(cake).await.unwrap();
+        dao.insert_recipe(smoothie).await.unwrap();
+        dao.insert_recipe(create_one_recipe_without_image()).await.unwrap();
+
+        let req = test::TestRequest::get().uri("/recipes/equipment").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, json!(["blender", "oven", "stand mixer"]));
+
+        let req = test::TestRequest::get().uri("/recipes?equipment=OVEN,stand%20mixer&fields=title,equipment").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        let recipes = body.as_array().unwrap();
+        assert_eq!(recipes.len(), 1);
+        assert_eq!(recipes[0]["title"], "Cake");
+        assert_eq!(recipes[0]["equipment"], json!(["oven", "stand mixer"]));
+
+        let req = test::TestRequest::get().uri("/recipes?equipment=oven,blender").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), 0);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_update_stale_recipe_merges_or_conflicts() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/{id}", web::put().to(RecipeRoutes::update_one_recipe_without_image))).await;
+
+        let mut base = create_one_recipe_without_image();
+        base.title = "Carbonara".to_string();
+        let recipe_id = dao.insert_recipe(base.clone()).await.unwrap().as_object_id().unwrap().to_owned();
+        let url = format!("/recipes/{}", recipe_id);
+
+        let mut current = base.clone();
+        current.title = "Spaghetti Carbonara".to_string();
+        current.version = 2;
+        let req = test::TestRequest::put().set_json(&current).uri(&url).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let mut stale = base.clone();
+        stale.description = "Creamy".to_string();
+        let req = test::TestRequest::put().set_json(&stale).uri(&url).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let merged = dao.get_one_recipe_without_image(recipe_id.clone()).await.unwrap();
+        assert_eq!(merged.title, "Spaghetti Carbonara");
+        assert_eq!(merged.description, "Creamy");
+        assert_eq!(merged.version, 3);
+
+        let mut stale = base.clone();
+        stale.title = "Amatriciana".to_string();
+        let req = test::TestRequest::put().set_json(&stale).uri(&url).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"], "The recipe changed since version 1 in the fields title");
+        assert_eq!(dao.get_one_recipe_without_image(recipe_id).await.unwrap().title, "Spaghetti Carbonara");
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_recipe_diff() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/addOneRecipe", web::post().to(RecipeRoutes::add_one_recipe))
+            .route("/recipes/{id}", web::put().to(RecipeRoutes::update_one_recipe_without_image))
+            .route("/recipes/{id}/diff", web::get().to(RecipeRoutes::get_recipe_diff))).await;
+
+        let mut recipe = create_one_publishable_recipe();
+        recipe.title = "Carbonara".to_string();
+        recipe.ingredients = vec![
+            Ingredient::new("0", 200.0, "Spaghetti", MeasurementUnit::Gramm),
+            Ingredient::new("1", 100.0, "Cream", MeasurementUnit::Milliliter),
+        ];
+        let req = test::TestRequest::post().set_json(&recipe).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        let body: Bson = test::read_body_json(resp).await;
+        let id = body.as_object_id().unwrap().to_string();
+
+        recipe.title = "Spaghetti Carbonara".to_string();
+        recipe.ingredients.remove(1);
+        recipe.version = 2;
+        let req = test::TestRequest::put().set_json(&recipe).uri(&format!("/recipes/{}", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/diff?from=1&to=2", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        let changes = body.as_array().unwrap();
+        assert_eq!(changes.iter().any(|change| change["op"] == "changed" && change["path"] == "/title"
+            && change["old"] == "Carbonara" && change["new"] == "Spaghetti Carbonara"), true);
+        assert_eq!(changes.iter().any(|change| change["op"] == "removed" && change["path"] == "/ingredients/1"
+            && change["old"]["title"] == "Cream"), true);
+        assert_eq!(changes.iter().any(|change| change["path"] == "/version"), true);
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/diff?from=1&to=5", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/diff?from=1", id)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_many_recipes_streamed_array() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))).await;
+
+        let req = test::TestRequest::get().uri("/recipes").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, json!([]));
+
+        dao.add_many_recipes(create_many_recipes_without_images(2000)).await.unwrap();
+
+        let req = test::TestRequest::get().uri("/recipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+        assert_eq!(body.as_array().unwrap().len(), 2000);
+        assert_eq!(body[1999]["id"].is_string(), true);
+
+        let req = test::TestRequest::get().uri("/recipes?fields=title").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), 2000);
+        assert_eq!(body[0].as_object().unwrap().len(), 2);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_many_recipes_explain() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))).await;
+        dao.add_many_recipes(create_many_recipes_without_images(3)).await.unwrap();
+
+        let (header, admin) = bearer(ADMIN_TOKEN);
+        let req = test::TestRequest::get().header(header, admin).uri("/recipes?explain=true&includeArchived=true").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["plan"].is_object(), true);
+        assert_eq!(body["collectionScan"], true);
+        assert_eq!(body["returned"], 3);
+        assert_eq!(body["docsExamined"], 3);
+
+        let (header, editor) = bearer(EDITOR_TOKEN);
+        let req = test::TestRequest::get().header(header, editor).uri("/recipes?explain=true").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), 3);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_many_recipes_default_sort() {
+        let dao = Dao { default_sort: DefaultSort { sorting: -1 }, ..before().await };
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))).await;
+        dao.add_many_recipes(create_many_recipes_without_images(3)).await.unwrap();
+
+        let req = test::TestRequest::get().uri("/recipes?page=1&items=10&envelope=true").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        let titles = body["data"].as_array().unwrap().iter().map(|recipe| recipe["title"].as_str().unwrap()).collect::<Vec<&str>>();
+        assert_eq!(titles, vec!["2", "1", "0"]);
+        assert_eq!(body["meta"]["sort"], json!({ "created": -1 }));
+        assert_eq!(body["meta"]["pagination"]["sorting"], -1);
+
+        let req = test::TestRequest::get().uri("/recipes").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        let titles = body.as_array().unwrap().iter().map(|recipe| recipe["title"].as_str().unwrap()).collect::<Vec<&str>>();
+        assert_eq!(titles, vec!["2", "1", "0"]);
+
+        let req = test::TestRequest::get().uri("/recipes?page=1&items=10&sorting=1").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body[0]["title"], "0");
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_many_recipes_response_shapes() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))).await;
+        dao.add_many_recipes(create_many_recipes_without_images(3)).await.unwrap();
+
+        for uri in &["/recipes", "/recipes?page=1&items=2&sorting=1"] {
+            let resp = test::call_service(&mut app, test::TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(resp.headers().get(DEPRECATION_HEADER).unwrap(), "true");
+            assert_eq!(resp.headers().get(VARY).unwrap(), "accept");
+            let body: Value = test::read_body_json(resp).await;
+            assert_eq!(body.is_array(), true);
+        }
+
+        let requests = vec![
+            test::TestRequest::get().uri("/recipes?envelope=true"),
+            test::TestRequest::get().uri("/recipes").header(ACCEPT, ENVELOPE_MEDIA_TYPE),
+        ];
+        for req in requests {
+            let resp = test::call_service(&mut app, req.to_request()).await;
+            assert_eq!(resp.headers().contains_key(DEPRECATION_HEADER), false);
+            assert_eq!(resp.headers().get(VARY).unwrap(), "accept");
+            let body: Value = test::read_body_json(resp).await;
+            assert_eq!(body["data"].as_array().unwrap().len(), 3);
+            assert_eq!(body["meta"]["total"], 3);
+        }
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_many_recipes_estimated_count() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .app_data(web::Data::new(CountSettings { estimate_threshold: Some(2) }))
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))).await;
+
+        let mut recipes = create_many_recipes_without_images(3);
+        recipes[0].tags = vec!["vegan".to_string()];
+        dao.add_many_recipes(recipes).await.unwrap();
+
+        let req = test::TestRequest::get().uri("/recipes?envelope=true").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["meta"]["total"], 3);
+        assert_eq!(body["meta"]["countIsEstimate"], true);
+
+        let req = test::TestRequest::get().uri("/recipes?tags=vegan&envelope=true").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["meta"]["total"], 1);
+        assert_eq!(body["meta"]["countIsEstimate"], false);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_many_recipes_filtered_with_meta() {
+        let dao = before().await;
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))
+            .route("/addManyRecipes", web::post().to(RecipeRoutes::add_many_recipes))).await;
+
+        let mut vegan_medium = create_one_recipe().as_document().unwrap().clone();
+        vegan_medium.insert("tags", vec!["vegan", "fast"]);
+        vegan_medium.insert("difficulty", "Medium");
+        let mut vegan_hard = vegan_medium.clone();
+        vegan_hard.insert("difficulty", "Hard");
+        let payload = Bson::Array(vec![
+            Bson::Document(vegan_medium),
+            Bson::Document(vegan_hard),
+            create_one_recipe()]);
+        let req = test::TestRequest::post()
+            .set_json(&payload).uri("/addManyRecipes").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let req = test::TestRequest::get().uri("/recipes?tags=vegan&difficulty=Easy,Medium&envelope=true").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+        assert_eq!(body["meta"]["total"], 1);
+        assert_eq!(body["meta"]["filter"], json!({
+            "tags": { "$all": ["vegan"] },
+            "difficulty": { "$in": ["Easy", "Medium"] }
+        }));
+        assert_eq!(body["meta"]["sort"], json!({ "created": 1 }));
+        assert_eq!(body["meta"]["pagination"], Value::Null);
+
+        let req = test::TestRequest::get().uri("/recipes?tags=vegan&page=1&items=1&sorting=-1&envelope=true").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["meta"]["total"], 2);
+        assert_eq!(body["meta"]["sort"], json!({ "created": -1 }));
+        assert_eq!(body["meta"]["pagination"], json!({ "page": 1, "items": 1, "sorting": -1 }));
+
+        let req = test::TestRequest::get().uri("/recipes?difficulty=Impossible").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_many_recipes_by_offset() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))).await;
+        dao.add_many_recipes(create_many_recipes_without_images(5)).await.unwrap();
+
+        let req = test::TestRequest::get().uri("/recipes?offset=3&limit=10&sorting=1&envelope=true").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.headers().get("link"), None);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["data"].as_array().unwrap().len(), 2);
+        assert_eq!(body["meta"]["pagination"], json!({ "page": null, "items": null, "sorting": 1, "offset": 3, "limit": 10 }));
+
+        let req = test::TestRequest::get().uri("/recipes?page=2&pageSize=2&sorting=1").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), 2);
+
+        let req = test::TestRequest::get().uri("/recipes?page=1&offset=3&limit=10&sorting=1").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"].as_str().unwrap().contains("offset"), true);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_add_recipe_into_collection() {
+        let dao = before().await;
+        let own = ObjectId::new();
+        let foreign = ObjectId::new();
+        let ownerless = ObjectId::new();
+        dao.database.collection("collections").insert_many(vec![
+            doc! {"_id": own.clone(), "name": "Weeknight", "owner": "bob", "recipeIds": []},
+            doc! {"_id": foreign.clone(), "name": "Sunday", "owner": "carol", "recipeIds": []},
+            doc! {"_id": ownerless.clone(), "name": "Classics", "recipeIds": []},
+        ], None).await.unwrap();
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/recipes/{id}", web::post().to(RecipeRoutes::add_one_recipe))).await;
+        let (header, bob) = bearer(EDITOR_TOKEN);
+        let payload = create_one_recipe();
+
+        let req = test::TestRequest::post().header(header, bob.clone()).set_json(&payload)
+            .uri(&format!("/recipes/new?collectionId={}", own)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let id: Bson = test::read_body_json(resp).await;
+        let collection = dao.database.collection("collections")
+            .find_one(doc! {"_id": own.clone()}, None).await.unwrap().unwrap();
+        assert_eq!(collection.get_array("recipeIds").unwrap(), &vec![id]);
+
+        let req = test::TestRequest::post().header(header, bob.clone()).set_json(&payload)
+            .uri(&format!("/recipes/new?collectionId={}", foreign)).to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::FORBIDDEN);
+        let req = test::TestRequest::post().header(header, bob.clone()).set_json(&payload)
+            .uri(&format!("/recipes/new?collectionId={}", ownerless)).to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::FORBIDDEN);
+        let req = test::TestRequest::post().header(header, bob).set_json(&payload)
+            .uri(&format!("/recipes/new?collectionId={}", ObjectId::new())).to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::NOT_FOUND);
+        let req = test::TestRequest::post().set_json(&payload)
+            .uri(&format!("/recipes/new?collectionId={}", own)).to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(dao.count_recipes(doc! {}).await.unwrap(), 1);
+
+        let (_, admin) = bearer(ADMIN_TOKEN);
+        let req = test::TestRequest::post().header(header, admin).set_json(&payload)
+            .uri(&format!("/recipes/new?collectionId={}", ownerless)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        assert_eq!(dao.count_recipes(doc! {}).await.unwrap(), 2);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_my_recipes_isolated_per_identity() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/recipes/mine", web::get().to(RecipeRoutes::get_my_recipes))
+            .route("/recipes/{id}", web::post().to(RecipeRoutes::add_one_recipe))).await;
+        let (header, alice) = bearer(ADMIN_TOKEN);
+        let (_, bob) = bearer(EDITOR_TOKEN);
+
+        for (token, title) in [(&alice, "Alice's pasta"), (&alice, "Alice's soup"), (&bob, "Bob's salad")] {
+            let mut payload = create_one_recipe().as_document().unwrap().clone();
+            payload.insert("title", title);
+            let req = test::TestRequest::post().header(header, token.clone()).set_json(&payload).uri("/recipes/new").to_request();
+            assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::OK);
+        }
+        dao.add_many_recipes(create_many_recipes_without_images(2)).await.unwrap();
+
+        let req = test::TestRequest::get().header(header, alice.clone()).uri("/recipes/mine?page=1&items=10&sorting=1&envelope=true").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["meta"]["total"], 2);
+        let mut titles = body["data"].as_array().unwrap().iter().map(|recipe| recipe["title"].as_str().unwrap()).collect::<Vec<&str>>();
+        titles.sort();
+        assert_eq!(titles, vec!["Alice's pasta", "Alice's soup"]);
+
+        let req = test::TestRequest::get().header(header, bob).uri("/recipes/mine").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["title"], "Bob's salad");
+
+        let req = test::TestRequest::get().header(header, alice).uri("/recipes/mine?q=soup&fuzzy=true").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), 2);
+
+        let req = test::TestRequest::get().uri("/recipes/mine").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_many_recipes_beyond_last_page() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))).await;
+        dao.add_many_recipes(create_many_recipes_without_images(5)).await.unwrap();
+
+        let req = test::TestRequest::get().uri("/recipes?page=50&items=2&sorting=1&envelope=true").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["data"], json!([]));
+        assert_eq!(body["meta"]["total"], 5);
+        assert_eq!(body["meta"]["totalPages"], 3);
+        assert_eq!(body["meta"]["beyondLastPage"], true);
+
+        let req = test::TestRequest::get().uri("/recipes?page=999&items=2&sorting=1&envelope=true").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["data"], json!([]));
+        assert_eq!(body["meta"]["total"], 5);
+        assert_eq!(body["meta"]["totalPages"], 3);
+        assert_eq!(body["meta"]["beyondLastPage"], true);
+
+        let req = test::TestRequest::get().uri(&format!("/recipes?page={}&items=2&sorting=1&envelope=true", usize::MAX)).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["data"], json!([]));
+        assert_eq!(body["meta"]["beyondLastPage"], true);
+
+        let req = test::TestRequest::get().uri("/recipes?page=3&items=2&sorting=1&envelope=true").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+        assert_eq!(body["meta"]["beyondLastPage"], false);
+
+        let req = test::TestRequest::get().uri("/recipes?envelope=true").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["meta"].get("totalPages"), None);
+        assert_eq!(body["meta"].get("beyondLastPage"), None);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_one_recipe_scalable() {
         let dao = before().await;
-
         let mut app = test::init_service(App::new()
             .data(dao.clone())
-            .route("/addOneRecipe", web::post().to(RecipeRoutes::add_one_recipe))).await;
+            .route("/recipes/{id}", web::get().to(RecipeRoutes::get_one_recipe_without_image))).await;
 
-        let req = test::TestRequest::post().uri("/addOneRecipe").to_request();
-        let resp = test::call_service(&mut app, req).await;
-        assert!(resp.status().is_client_error());
+        let scalable = dao.insert_recipe(create_one_recipe_without_image()).await.unwrap().as_object_id().unwrap().to_hex();
+        let zero_servings = Recipe { default_servings: 0, recipe_yield: None, ..create_one_recipe_without_image() };
+        let zero_servings = dao.insert_recipe(zero_servings).await.unwrap().as_object_id().unwrap().to_hex();
 
-        let payload = create_many_recipes();
-        let req = test::TestRequest::post()
-            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}", scalable)).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["scalable"], true);
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}", zero_servings)).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["scalable"], false);
+        assert_eq!(dao.get_one_recipe_document(ObjectId::with_string(&zero_servings).unwrap(), doc! {}, doc! {}).await.unwrap()
+            .contains_key("scalable"), false);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_one_recipe() {
+        let dao = before().await;
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/{id}", web::get().to(RecipeRoutes::get_one_recipe_without_image))
+            .route("/recipes/{id}", web::post().to(RecipeRoutes::add_one_recipe))).await;
+
+        let req = test::TestRequest::get().uri("/recipes/hello").to_request();
         let resp = test::call_service(&mut app, req).await;
         assert!(resp.status().is_client_error(), "{}", resp.status());
 
-        let payload = create_one_recipe_no_ingredients();
+        let payload = create_one_recipe().as_document().unwrap().clone();
+
         let req = test::TestRequest::post()
-            .set_json(&payload).uri("/addOneRecipe").to_request();
+            .set_json(&payload).uri("/recipes/new").to_request();
+
         let resp = test::call_service(&mut app, req).await;
         println!("{:#?}", resp);
         assert!(resp.status().is_success(), "{}", resp.status());
 
-        let payload = create_one_recipe_with_ingredients();
-        let req = test::TestRequest::post()
-            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let req = test::TestRequest::get().uri("/recipes/hello").to_request();
         let resp = test::call_service(&mut app, req).await;
-        println!("{:#?}", resp);
-        assert!(resp.status().is_success(), "{}", resp.status());
+        // let body = resp.response_mut().take_body().try_fold(|e| e);
+        // let x = body.as_ref().unwrap().to_owned();
+        // let x1 = std::str::from_utf8(x).unwrap();
+        // println!("{:#?}", x);
 
-        let payload = create_one_recipe_with_image();
-        let req = test::TestRequest::post()
-            .set_json(&payload).uri("/addOneRecipe").to_request();
+        assert!(resp.status().is_client_error(), "{}", resp.status());
+
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_cookable_now() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/cookableNow", web::get().to(RecipeRoutes::get_cookable_now))).await;
+
+        let recipes = [
+            ("Pancakes", 20, vec!["Milk", "Eggs ", "flour"]),
+            ("Scrambled eggs", 10, vec!["eggs", "milk"]),
+            ("Slow pancakes", 60, vec!["milk", "eggs", "flour"]),
+            ("Omelette", 15, vec!["eggs", "cheese"]),
+            ("Water", 5, vec![]),
+        ];
+        for (title, cooking_time, ingredients) in recipes.iter() {
+            let mut recipe = create_one_recipe_without_image();
+            recipe.title = title.to_string();
+            recipe.cooking_time_in_minutes = *cooking_time;
+            recipe.ingredients = ingredients.iter().enumerate()
+                .map(|(index, ingredient)| Ingredient::new(&index.to_string(), 1.0, ingredient, MeasurementUnit::Piece))
+                .collect();
+            dao.insert_recipe(recipe).await.unwrap();
+        }
+
+        let req = test::TestRequest::get().uri("/recipes/cookableNow?maxTime=30&have=milk&have=EGGS,flour").to_request();
         let resp = test::call_service(&mut app, req).await;
-        println!("{:#?}", resp);
         assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        let mut titles = body["data"].as_array().unwrap().iter().map(|recipe| recipe["title"].as_str().unwrap()).collect::<Vec<&str>>();
+        titles.sort_unstable();
+        assert_eq!(titles, vec!["Pancakes", "Scrambled eggs"]);
+        assert_eq!(body["meta"]["total"], 2);
+        assert_eq!(body["meta"]["pagination"]["page"], 1);
+        assert_eq!(body["meta"]["totalPages"], 1);
+
+        let req = test::TestRequest::get().uri("/recipes/cookableNow?maxTime=30&have=milk&have=eggs&have=flour&items=1").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+        assert_eq!(body["meta"]["total"], 2);
+
+        let req = test::TestRequest::get().uri("/recipes/cookableNow?have=milk").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::BAD_REQUEST);
+        let req = test::TestRequest::get().uri("/recipes/cookableNow?maxTime=30").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::BAD_REQUEST);
 
         cleanup_after(dao).await;
     }
 
     #[actix_rt::test]
     #[serial]
-    async fn test_delete_single_recipe() {
+    async fn test_get_recipes_with_field_mask() {
         let dao = before().await;
-
         let mut app = test::init_service(App::new()
             .data(dao.clone())
-            .route("/deleteOneRecipe/{id}", web::delete().to(RecipeRoutes::delete_one_recipe))
-            .route("/addOneRecipe", web::post().to(RecipeRoutes::add_one_recipe))).await;
+            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))
+            .route("/recipes/{id}", web::get().to(RecipeRoutes::get_one_recipe_without_image))).await;
 
-        let payload = create_one_recipe_with_ingredients();
+        let mut recipe = create_one_recipe_without_image();
+        recipe.title = "Spaghetti".to_string();
+        recipe.description = "long description".to_string();
+        recipe.image_base64 = Some("image".to_string());
+        let id = dao.insert_recipe(recipe).await.unwrap().as_object_id().unwrap().to_hex();
 
-        let req = test::TestRequest::delete()
-            .set_json(&payload).uri("/deleteOneRecipe").to_request();
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}?exclude=image,description", id)).to_request();
         let resp = test::call_service(&mut app, req).await;
-        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["id"], id.as_str());
+        assert_eq!(body["title"], "Spaghetti");
+        assert_eq!(body.get("description").is_none(), true);
+        assert_eq!(body.get("image").is_none(), true);
 
-        let req = test::TestRequest::delete()
-            .set_json(&payload).uri("/deleteOneRecipe/hello").to_request();
-        let resp = test::call_service(&mut app, req).await;
-        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let req = test::TestRequest::get().uri("/recipes?exclude=image,description").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body[0]["title"], "Spaghetti");
+        assert_eq!(body[0].get("description").is_none(), true);
+        assert_eq!(body[0].get("image").is_none(), true);
 
-        let req = test::TestRequest::delete()
-            .set_json(&payload).uri("/deleteOneRecipe/5f7333360051027600b01a36").to_request();
-        let resp = test::call_service(&mut app, req).await;
-        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let req = test::TestRequest::get().uri("/recipes?fields=title").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, json!([{ "id": id, "title": "Spaghetti" }]));
 
-        let req = test::TestRequest::post()
-            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let req = test::TestRequest::get().uri("/recipes?fields%5Brecipe%5D=title").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, json!([{ "id": id, "title": "Spaghetti" }]));
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}?fields%5Brecipe%5D=title,tags", id)).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["title"], "Spaghetti");
+        assert_eq!(body.get("tags").is_some(), true);
+        assert_eq!(body.get("description").is_none(), true);
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}?fields=title&exclude=image", id)).to_request();
         let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 
-        let body: Bson = test::read_body_json(resp).await;
-        let inserted_id = body.as_object_id().unwrap().to_string();
+        let req = test::TestRequest::get().uri("/recipes?fields%5Brecipe%5D=unknown").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 
-        let path = format!("/deleteOneRecipe/{}", inserted_id);
-        let req = test::TestRequest::delete().set_json(&payload).uri(&path).to_request();
+        let req = test::TestRequest::get().uri("/recipes?exclude=unknown").to_request();
         let resp = test::call_service(&mut app, req).await;
-        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 
         cleanup_after(dao).await;
     }
 
     #[actix_rt::test]
     #[serial]
-    async fn test_add_many_recipes() {
+    async fn test_recipe_with_templates() {
         let dao = before().await;
-
         let mut app = test::init_service(App::new()
             .data(dao.clone())
-            .route("/addManyRecipes", web::post().to(RecipeRoutes::add_many_recipes))).await;
+            .route("/recipes/validateMany", web::post().to(RecipeRoutes::validate_many_recipes))
+            .route("/recipes/{id}", web::get().to(RecipeRoutes::get_one_recipe_without_image))
+            .route("/recipes/{id}", web::post().to(RecipeRoutes::add_one_recipe))).await;
 
-        let req = test::TestRequest::post().uri("/addManyRecipes").to_request();
+        let template = IngredientTemplate {
+            _id: ObjectId::new(),
+            name: "Basic pie crust".to_string(),
+            ingredients: vec![Ingredient::new("0", 250.0, "Flour", MeasurementUnit::Gramm)],
+        };
+        let template_id = dao.insert_template(template).await.unwrap().as_object_id().unwrap().to_hex();
+
+        let mut recipe = create_one_publishable_recipe();
+        recipe.ingredients = vec![Ingredient::new("0", 500.0, "Apples", MeasurementUnit::Gramm)];
+        recipe.template_ids = vec![ObjectId::new().to_hex()];
+        let req = test::TestRequest::post().set_json(&recipe).uri("/recipes/new").to_request();
         let resp = test::call_service(&mut app, req).await;
-        assert!(resp.status().is_client_error());
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let mut known = recipe.clone();
+        known.template_ids = vec![template_id.clone()];
+        let req = test::TestRequest::post().set_json(&vec![recipe.clone(), known]).uri("/recipes/validateMany").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, json!([
+            { "valid": false, "errors": [format!("Template '{}' does not exist", recipe.template_ids[0])] },
+            { "valid": true, "errors": [] },
+        ]));
 
-        let payload = create_one_recipe_no_ingredients();
-        let req = test::TestRequest::post()
-            .set_json(&payload)
-            .uri("/addManyRecipes").to_request();
+        recipe.template_ids = vec!["no object id".to_string()];
+        let req = test::TestRequest::post().set_json(&recipe).uri("/recipes/new").to_request();
         let resp = test::call_service(&mut app, req).await;
-        assert!(resp.status().is_client_error());
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
 
-        let payload = create_many_recipes();
-        let req = test::TestRequest::post()
-            .set_json(&payload).uri("/addManyRecipes").to_request();
+        recipe.template_ids = vec![template_id.clone()];
+        let req = test::TestRequest::post().set_json(&recipe).uri("/recipes/new").to_request();
         let resp = test::call_service(&mut app, req).await;
         assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Bson = test::read_body_json(resp).await;
+        let url = format!("/recipes/{}", body.as_object_id().unwrap());
+
+        let req = test::TestRequest::get().uri(&url).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["ingredients"].as_array().unwrap().len(), 1);
+        assert_eq!(body["templateIds"], json!([template_id]));
+
+        let req = test::TestRequest::get().uri(&format!("{}?expandTemplates=true", url)).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        let ingredients = body["ingredients"].as_array().unwrap();
+        assert_eq!(ingredients.len(), 2);
+        assert_eq!(ingredients[0]["title"], "Apples");
+        assert_eq!(ingredients[1]["title"], "Flour");
+        assert_eq!(ingredients[1]["id"], format!("{}-0", template_id));
 
         cleanup_after(dao).await;
     }
 
-
     #[actix_rt::test]
     #[serial]
-    async fn test_get_many_recipes() {
+    async fn test_get_one_recipe_by_id_or_slug() {
         let dao = before().await;
-
+        let mut recipe = create_one_recipe_without_image();
+        recipe.title = "Käsespätzle".to_string();
+        let id = dao.insert_recipe(recipe).await.unwrap().as_object_id().unwrap().to_hex();
         let mut app = test::init_service(App::new()
             .data(dao.clone())
-            .route("/recipes", web::get().to(RecipeRoutes::get_many_recipes))
-            .route("/addManyRecipes", web::post().to(RecipeRoutes::add_many_recipes))).await;
+            .service(web::resource("/recipes/{id}")
+                .route(web::get().to(RecipeRoutes::get_one_recipe_without_image)))
+            .service(web::resource("/strict/{id}")
+                .data(SlugPaths { enabled: false })
+                .route(web::get().to(RecipeRoutes::get_one_recipe_without_image)))).await;
 
-        let req = test::TestRequest::get().uri("/recipes").to_request();
-        let resp = test::call_service(&mut app, req).await;
-        assert!(resp.status().is_success(), "{}", resp.status());
+        for uri in &[format!("/recipes/{}", id), "/recipes/kaesespaetzle".to_string()] {
+            let resp = test::call_service(&mut app, test::TestRequest::get().uri(uri).to_request()).await;
+            assert!(resp.status().is_success(), "{}", resp.status());
+            let body: Value = test::read_body_json(resp).await;
+            assert_eq!(body["id"], id);
+            assert_eq!(body["title"], "Käsespätzle");
+        }
 
+        let req = test::TestRequest::get().uri("/recipes/kaesespaetzle-2").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::NOT_FOUND);
+        let req = test::TestRequest::get().uri("/recipes/Not_A_Slug").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::BAD_REQUEST);
+        let req = test::TestRequest::get().uri("/strict/kaesespaetzle").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::BAD_REQUEST);
+        let req = test::TestRequest::get().uri(&format!("/strict/{}", id)).to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status().is_success(), true);
 
-        let payload = create_many_recipes();
-        let payload = payload.as_array().unwrap().clone();
-        let payload: Vec<Bson> = (0..50).into_iter().map(|_| payload.get(0).unwrap().clone()).collect();
-        let payload = Bson::Array(payload);
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_one_recipe_by_slug() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/addOneRecipe", web::post().to(RecipeRoutes::add_one_recipe))
+            .route("/recipes/by-slug/{slug}", web::get().to(RecipeRoutes::get_one_recipe_by_slug))).await;
 
+        let mut payload = create_one_recipe_with_ingredients().as_document().unwrap().to_owned();
+        payload.insert("title", "Käsespätzle");
+        payload.insert("slug", "ignored");
         let req = test::TestRequest::post()
-            .set_json(&payload).uri("/addManyRecipes").to_request();
+            .set_json(&payload).uri("/addOneRecipe").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        let body: Bson = test::read_body_json(resp).await;
+        let id = body.as_object_id().unwrap().to_string();
+
+        let req = test::TestRequest::get().uri("/recipes/by-slug/kaesespaetzle").to_request();
         let resp = test::call_service(&mut app, req).await;
         assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["id"], id);
+        assert_eq!(body["slug"], "kaesespaetzle");
 
+        let req = test::TestRequest::get().uri("/recipes/by-slug/ignored").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
 
-        let req = test::TestRequest::get().uri("/recipes").to_request();
+        let req = test::TestRequest::get().uri("/recipes/by-slug/Not_A_Slug").to_request();
         let resp = test::call_service(&mut app, req).await;
-        assert!(resp.status().is_success(), "{}", resp.status());
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 
         cleanup_after(dao).await;
     }
 
     #[actix_rt::test]
     #[serial]
-    async fn test_get_one_recipe() {
+    async fn test_ingredient_substitutions() {
         let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/recipes/{id}/substitutions", web::get().to(RecipeRoutes::get_substitutions))
+            .route("/recipes/{id}", web::get().to(RecipeRoutes::get_one_recipe_without_image))
+            .route("/recipes/{id}", web::post().to(RecipeRoutes::add_one_recipe))).await;
+
+        let mut payload = create_one_recipe().as_document().unwrap().clone();
+        payload.insert("ingredients", vec![
+            Bson::Document(doc! { "id": "0", "amount": 50, "title": "Butter", "measurementUnit": "Gramm", "substitutes": ["Margarine ", "margarine", "Coconut oil"] }),
+            Bson::Document(doc! { "id": "1", "amount": 1, "title": "Salt", "measurementUnit": "Gramm" }),
+        ]);
+        let req = test::TestRequest::post().set_json(&payload).uri("/recipes/new").to_request();
+        let body: Bson = test::read_body_json(test::call_service(&mut app, req).await).await;
+        let id = body.as_object_id().unwrap().to_hex();
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}", id)).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["ingredients"][0]["substitutes"], json!(["margarine", "coconut oil"]));
+        assert_eq!(body["ingredients"][1].get("substitutes"), None);
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/substitutions", id)).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, json!([
+            { "id": "0", "title": "Butter", "substitutes": ["margarine", "coconut oil"] },
+            { "id": "1", "title": "Salt", "substitutes": [] },
+        ]));
+
+        let req = test::TestRequest::get().uri("/recipes/5f7333360051027600b01a36/diff?from=1&to=2").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::NOT_FOUND);
+
+        hide_as_draft(&dao, &id).await;
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/diff?from=1&to=2", id)).to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::NOT_FOUND);
+        let (header, value) = bearer(ADMIN_TOKEN);
+        let req = test::TestRequest::get().header(header, value).uri(&format!("/recipes/{}/diff?from=1&to=2", id)).to_request();
+        assert!(test::call_service(&mut app, req).await.status().is_success());
+
+        cleanup_after(dao).await;
+    }
 
+    #[actix_rt::test]
+    #[serial]
+    async fn test_cook_mode_with_step_timers() {
+        let dao = before().await;
         let mut app = test::init_service(App::new()
             .data(dao.clone())
+            .route("/recipes/{id}/cook-mode", web::get().to(RecipeRoutes::get_cook_mode))
             .route("/recipes/{id}", web::get().to(RecipeRoutes::get_one_recipe_without_image))
             .route("/recipes/{id}", web::post().to(RecipeRoutes::add_one_recipe))).await;
 
-        let req = test::TestRequest::get().uri("/recipes/hello").to_request();
+        let mut payload = create_one_recipe().as_document().unwrap().clone();
+        payload.insert("instructions", vec!["Boil water", "Cook pasta", "Serve"]);
+        payload.insert("stepTimers", vec![Bson::Document(doc! { "step": 1, "durationSeconds": 540, "timerLabel": "Pasta" })]);
+        let req = test::TestRequest::post().set_json(&payload).uri("/recipes/new").to_request();
+        let body: Bson = test::read_body_json(test::call_service(&mut app, req).await).await;
+        let id = body.as_object_id().unwrap().to_hex();
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}", id)).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["stepTimers"], json!([{ "step": 1, "durationSeconds": 540, "timerLabel": "Pasta" }]));
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}/cook-mode", id)).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, json!([
+            { "step": 0, "instruction": "Boil water" },
+            { "step": 1, "instruction": "Cook pasta", "durationSeconds": 540, "timerLabel": "Pasta" },
+            { "step": 2, "instruction": "Serve" },
+        ]));
+
+        payload.insert("stepTimers", vec![Bson::Document(doc! { "step": 0, "durationSeconds": -5 })]);
+        let req = test::TestRequest::post().set_json(&payload).uri("/recipes/new").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let req = test::TestRequest::get().uri("/recipes/5f7333360051027600b01a36/cook-mode").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::NOT_FOUND);
+
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_get_one_recipe_full() {
+        let dao = before().await;
+
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/recipes/{id}/full", web::get().to(RecipeRoutes::get_one_recipe_full))
+            .route("/recipes/{id}", web::post().to(RecipeRoutes::add_one_recipe))).await;
+
+        let req = test::TestRequest::get().uri("/recipes/hello/full").to_request();
         let resp = test::call_service(&mut app, req).await;
-        assert!(resp.status().is_client_error(), "{}", resp.status());
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 
-        let payload = create_one_recipe_no_ingredients().as_document().unwrap().clone();
+        let req = test::TestRequest::get().uri("/recipes/5f7333360051027600b01a36/full").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
 
+        let payload = create_one_recipe_with_image();
         let req = test::TestRequest::post()
             .set_json(&payload).uri("/recipes/new").to_request();
-
         let resp = test::call_service(&mut app, req).await;
-        println!("{:#?}", resp);
-        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Bson = test::read_body_json(resp).await;
+        let inserted_id = body.as_object_id().unwrap().to_string();
 
-        let req = test::TestRequest::get().uri("/recipes/hello").to_request();
+        let path = format!("/recipes/{}/full", inserted_id);
+        let req = test::TestRequest::get().uri(&path).to_request();
         let resp = test::call_service(&mut app, req).await;
-        // let body = resp.response_mut().take_body().try_fold(|e| e);
-        // let x = body.as_ref().unwrap().to_owned();
-        // let x1 = std::str::from_utf8(x).unwrap();
-        // println!("{:#?}", x);
+        assert_eq!(resp.status(), StatusCode::OK);
 
-        assert!(resp.status().is_client_error(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["id"], inserted_id);
+        assert_eq!(body["image"], Value::Null);
+        assert_eq!(body["collections"], json!([]));
+        assert_eq!(body["averageRating"], Value::Null);
+        assert_eq!(body["commentCount"], 0);
 
+        hide_as_draft(&dao, &inserted_id).await;
+        let req = test::TestRequest::get().uri(&path).to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::NOT_FOUND);
+        let (header, value) = bearer(ADMIN_TOKEN);
+        let req = test::TestRequest::get().header(header, value).uri(&path).to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::OK);
 
         cleanup_after(dao).await;
     }
 
+    #[test]
+    fn limit_params_test() {
+        assert_eq!(LimitParams { limit: None }.limit_or(10, 50), 10);
+        assert_eq!(LimitParams { limit: Some(0) }.limit_or(10, 50), 1);
+        assert_eq!(LimitParams { limit: Some(20) }.limit_or(10, 50), 20);
+        assert_eq!(LimitParams { limit: Some(500) }.limit_or(10, 50), 50);
+    }
+
     #[actix_rt::test]
     async fn test_update_one_recipe() {
         let dao = before().await;
@@ -410,7 +4434,7 @@ mod tests {
             .route("/recipes/{id}", web::post().to(RecipeRoutes::add_one_recipe))
             .route("/recipes/{id}", web::put().to(RecipeRoutes::update_one_recipe_without_image))).await;
 
-        let mut payload = create_one_recipe_no_ingredients().as_document().unwrap().clone();
+        let mut payload = create_one_recipe().as_document().unwrap().clone();
 
         let req = test::TestRequest::post()
             .set_json(&payload).uri("/recipes/new").to_request();
@@ -433,4 +4457,186 @@ mod tests {
 
         cleanup_after(dao).await;
     }
+
+    #[actix_rt::test]
+    async fn test_stale_recipe_while_database_is_down() {
+        let mut dao = unreachable_dao(CircuitBreaker::default()).await;
+        dao.recipe_cache = RecipeCache::new(std::time::Duration::from_secs(0), 10).with_stale_ttl(std::time::Duration::from_secs(60));
+        let mut recipe = create_one_recipe_without_image();
+        recipe.title = "Known good".to_string();
+        dao.recipe_cache.put(dao.recipe_cache.get(&recipe._id).unwrap_err(), recipe.clone());
+        let mut app = test::init_service(App::new()
+            .data(dao)
+            .route("/recipes/{id}", web::get().to(RecipeRoutes::get_one_recipe_without_image))).await;
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}", recipe._id.to_hex())).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(STALE_HEADER).unwrap(), "true");
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["title"], "Known good");
+
+        let req = test::TestRequest::get().uri("/recipes/5f7333360051027600b01a36").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(resp.headers().get(STALE_HEADER), None);
+    }
+
+    #[actix_rt::test]
+    async fn test_import_from_invalid_url() {
+        let dao = unreachable_dao(CircuitBreaker::default()).await;
+        let mut app = test::init_service(App::new()
+            .data(dao)
+            .data(create_api_tokens())
+            .route("/recipes/importFromUrl", web::post().to(RecipeRoutes::import_recipe_from_url))).await;
+
+        let req = test::TestRequest::post().set_json(&json!({ "url": "https://example.org/soup" }))
+            .uri("/recipes/importFromUrl").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+        let (header, value) = bearer(EDITOR_TOKEN);
+        for url in ["recipes.example.org/soup", "file:///etc/passwd", " ", "http://localhost:8080/soup", "http://169.254.169.254/latest/meta-data"] {
+            let req = test::TestRequest::post().header(header, value.clone()).set_json(&json!({ "url": url }))
+                .uri("/recipes/importFromUrl").to_request();
+            assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::UNPROCESSABLE_ENTITY, "{}", url);
+        }
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_import_recipe_from_url() {
+        let page_server = test::start(|| App::new()
+            .route("/mac", web::get().to(|| HttpResponse::Ok().body(create_recipe_page())))
+            .route("/plain", web::get().to(|| HttpResponse::Ok().body("<html><body>No recipe</body></html>"))));
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .data(FetchPolicy { allow_private_hosts: true })
+            .route("/recipes/importFromUrl", web::post().to(RecipeRoutes::import_recipe_from_url))
+            .route("/recipes/{id}", web::get().to(RecipeRoutes::get_one_recipe_without_image))).await;
+        let (header, value) = bearer(EDITOR_TOKEN);
+
+        let url = format!("http://{}/mac", page_server.addr());
+        let req = test::TestRequest::post().header(header, value.clone()).set_json(&json!({ "url": url }))
+            .uri("/recipes/importFromUrl").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Bson = test::read_body_json(resp).await;
+        let id = body.as_object_id().unwrap().to_string();
+
+        let req = test::TestRequest::get().uri(&format!("/recipes/{}", id)).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["title"], "Mac & Cheese");
+        assert_eq!(body["cookingTimeInMinutes"], 75);
+        assert_eq!(body["slug"], "mac-cheese");
+        assert_eq!(body["ingredients"][0], json!({ "id": "0", "amount": 250, "title": "macaroni", "measurementUnit": "Gramm" }));
+        assert_eq!(body["instructions"].as_array().unwrap().len(), 3);
+
+        let req = test::TestRequest::post().header(header, value.clone()).set_json(&json!({ "url": format!("http://{}/plain", page_server.addr()) }))
+            .uri("/recipes/importFromUrl").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"].as_str().unwrap().contains("JSON-LD"), true);
+
+        let req = test::TestRequest::post().header(header, value.clone()).set_json(&json!({ "url": format!("http://{}/missing", page_server.addr()) }))
+            .uri("/recipes/importFromUrl").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::BAD_GATEWAY);
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_delete_many_recipes_requires_confirmation() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/recipes/deleteMany", web::post().to(RecipeRoutes::delete_many_recipes))).await;
+        let (header, admin) = bearer(ADMIN_TOKEN);
+        let mut recipes = create_many_recipes_without_images(5);
+        for recipe in recipes.iter_mut().take(3) {
+            recipe.tags = vec!["obsolete".to_string()];
+        }
+        dao.add_many_recipes(recipes).await.unwrap();
+        let selection = json!({ "filter": { "tags": "obsolete" } });
+
+        let req = test::TestRequest::post().header(header, admin.clone())
+            .set_json(&selection).uri("/recipes/deleteMany").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["count"], 3);
+
+        let req = test::TestRequest::post().header(header, admin.clone()).header(CONFIRM_DELETE_HEADER, "5")
+            .set_json(&selection).uri("/recipes/deleteMany").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        assert_eq!(dao.count_recipes(doc! {}).await.unwrap(), 5);
+
+        let req = test::TestRequest::post().header(header, admin.clone()).header(CONFIRM_DELETE_HEADER, "3")
+            .set_json(&selection).uri("/recipes/deleteMany").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body, json!({ "deleted": 3 }));
+        assert_eq!(dao.count_recipes(doc! {}).await.unwrap(), 2);
+
+        let (_, editor) = bearer(EDITOR_TOKEN);
+        let req = test::TestRequest::post().header(header, editor).header(CONFIRM_DELETE_HEADER, "2")
+            .set_json(&json!({ "filter": {} })).uri("/recipes/deleteMany").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::FORBIDDEN);
+        cleanup_after(dao).await;
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_apply_retag_rules() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .route("/recipes/retag/apply", web::post().to(RecipeRoutes::apply_retag_rules))).await;
+        let mut recipes = create_many_recipes_without_images(3);
+        recipes[0].ingredients = vec![Ingredient::new("0", 200.0, "Smoked Tofu", MeasurementUnit::Gramm)];
+        recipes[1].ingredients = vec![Ingredient::new("0", 200.0, "tofu", MeasurementUnit::Gramm)];
+        recipes[1].tags = vec!["vegetarian".to_string()];
+        recipes[2].ingredients = vec![Ingredient::new("0", 200.0, "Chicken", MeasurementUnit::Gramm)];
+        let ids = dao.add_many_recipes(recipes).await.unwrap()
+            .as_array().unwrap().iter()
+            .map(|id| id.as_object_id().unwrap().clone())
+            .collect::<Vec<ObjectId>>();
+        let rules = json!({ "rules": [{ "ingredientContains": " tofu", "addTag": "vegetarian" }] });
+
+        let (header, editor) = bearer(EDITOR_TOKEN);
+        let req = test::TestRequest::post().header(header, editor).set_json(&rules).uri("/recipes/retag/apply").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::FORBIDDEN);
+
+        let (header, admin) = bearer(ADMIN_TOKEN);
+        let req = test::TestRequest::post().header(header, admin.clone()).set_json(&rules).uri("/recipes/retag/apply").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body, json!({ "rules": [{ "ingredientContains": "tofu", "addTag": "vegetarian", "tagged": 1 }], "tagged": 1 }));
+        let tagged = dao.get_one_recipe_without_image(ids[0].clone()).await.unwrap();
+        assert_eq!(tagged.tags, vec!["vegetarian".to_string()]);
+        assert_eq!(tagged.version, 2);
+        let untouched = dao.get_one_recipe_without_image(ids[1].clone()).await.unwrap();
+        assert_eq!(untouched.tags, vec!["vegetarian".to_string()]);
+        assert_eq!(untouched.version, 1);
+        assert_eq!(dao.get_one_recipe_without_image(ids[2].clone()).await.unwrap().tags.is_empty(), true);
+
+        let req = test::TestRequest::post().header(header, admin.clone()).set_json(&rules).uri("/recipes/retag/apply").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["tagged"], 0);
+
+        let req = test::TestRequest::post().header(header, admin)
+            .set_json(&json!({ "rules": [{ "ingredientContains": "tofu", "addTag": " " }] })).uri("/recipes/retag/apply").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["field"], "/rules/0/addTag");
+        cleanup_after(dao).await;
+    }
 }
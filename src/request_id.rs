@@ -0,0 +1,77 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use actix_web::dev::ServiceRequest;
+use bson::oid::ObjectId;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+thread_local! {
+    static CURRENT_REQUEST_ID: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// id of the request currently handled on this task, if any
+pub fn current() -> Option<String> {
+    CURRENT_REQUEST_ID.with(|id| id.borrow().clone())
+}
+
+/// takes the id sent by the client, or generates a new one
+pub fn from_request(req: &ServiceRequest) -> String {
+    req.headers().get(REQUEST_ID_HEADER)
+        .and_then(|id| id.to_str().ok())
+        .filter(|id| !id.is_empty())
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| ObjectId::new().to_hex())
+}
+
+/// runs the future with `id` as the current request id
+pub fn scope<F: Future>(id: String, future: F) -> RequestScoped<F> {
+    RequestScoped { id: Some(id), future: Box::pin(future) }
+}
+
+pub struct RequestScoped<F> {
+    id: Option<String>,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for RequestScoped<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let id = self.id.take();
+        let previous = CURRENT_REQUEST_ID.with(|current| current.replace(id));
+        let result = self.future.as_mut().poll(cx);
+        self.id = CURRENT_REQUEST_ID.with(|current| current.replace(previous));
+        result
+    }
+}
+
+
+#[cfg(test)]
+mod request_id_tests {
+    use actix_web::test::TestRequest;
+
+    use crate::request_id::{current, from_request, REQUEST_ID_HEADER, scope};
+
+    #[actix_rt::test]
+    async fn request_id_is_visible_inside_scope_only() {
+        assert_eq!(current(), None);
+        let id = scope("abc".to_string(), async {
+            actix_rt::time::delay_for(std::time::Duration::from_millis(1)).await;
+            current()
+        }).await;
+        assert_eq!(id, Some("abc".to_string()));
+        assert_eq!(current(), None);
+    }
+
+    #[test]
+    fn request_id_from_header_or_generated() {
+        let req = TestRequest::default().header(REQUEST_ID_HEADER, "abc").to_srv_request();
+        assert_eq!(from_request(&req), "abc");
+
+        let req = TestRequest::default().to_srv_request();
+        assert_eq!(from_request(&req).len(), 24);
+    }
+}
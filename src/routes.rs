@@ -0,0 +1,324 @@
+use actix_web::{guard, HttpResponse, web};
+use actix_web::middleware::NormalizePath;
+use actix_web::middleware::normalize::TrailingSlash;
+
+use crate::admin_routes::AdminRoutes;
+use crate::batch_routes::BatchRoutes;
+use crate::bulk_import;
+use crate::collection_routes::CollectionRoutes;
+use crate::discovery_routes::{DiscoveryRoutes, enabled_routes};
+use crate::features::{Feature, FeatureFlags};
+use crate::meal_plan_routes::MealPlanRoutes;
+use crate::recipe_routes::RecipeRoutes;
+use crate::slug::SlugPaths;
+use crate::template_routes::TemplateRoutes;
+use crate::unit_routes::UnitRoutes;
+
+/// Paths are resolved without trailing slashes, `/api/v1/recipes/` is `/api/v1/recipes`,
+/// and repeated slashes are merged, so no route answers 404 just for the way it is written
+pub fn normalize_path() -> NormalizePath {
+    NormalizePath::new(TrailingSlash::Trim)
+}
+
+/// Registers the routes of `/api/v1`, leaving out the ones of disabled features.
+/// Resources only partly disabled answer 404 for the disabled methods as well.
+/// New routes belong into `discovery_routes::ROUTES` too.
+pub fn configure(cfg: &mut web::ServiceConfig, features: &FeatureFlags) {
+    // `normalize_path` trims the slash of `/api/v1/`, which then only matches the empty path
+    for discovery in ["", "/"].iter() {
+        cfg.service(web::resource(*discovery)
+            .data(enabled_routes(features))
+            .route(web::get().to(DiscoveryRoutes::get_routes))
+        );
+    }
+    let mut recipes = web::resource("/recipes")
+        .route(web::get().to(RecipeRoutes::get_many_recipes));
+    if features.is_enabled(Feature::BulkImport) {
+        recipes = recipes
+            .route(web::post()
+                .guard(guard::fn_guard(bulk_import::is_small_payload))
+                .to(RecipeRoutes::add_many_recipes))
+            .route(web::post().to(RecipeRoutes::add_many_recipes_streamed));
+    }
+    cfg.service(recipes.default_service(web::route().to(HttpResponse::NotFound)));
+
+    if features.is_enabled(Feature::BulkImport) {
+        cfg.service(web::resource("/recipes/validateMany")
+            .route(web::post().to(RecipeRoutes::validate_many_recipes))
+        );
+    }
+    if features.is_enabled(Feature::UrlImport) {
+        cfg.service(web::resource("/recipes/importFromUrl")
+            .route(web::post().to(RecipeRoutes::import_recipe_from_url))
+        );
+    }
+    cfg.service(web::resource("/recipes/deleteMany")
+        .route(web::post().to(RecipeRoutes::delete_many_recipes))
+    );
+    cfg.service(web::resource("/recipes/retag/apply")
+        .route(web::post().to(RecipeRoutes::apply_retag_rules))
+    );
+    cfg.service(web::resource("/recipes/mine")
+        .route(web::get().to(RecipeRoutes::get_my_recipes))
+    );
+    cfg.service(web::resource("/recipes/cookableNow")
+        .route(web::get().to(RecipeRoutes::get_cookable_now))
+    );
+    cfg.service(web::resource("/recipes/grouped")
+        .route(web::get().to(RecipeRoutes::get_grouped_recipes))
+    );
+    cfg.service(web::resource("/recipes/stats")
+        .route(web::get().to(RecipeRoutes::get_recipe_stats))
+    );
+    cfg.service(web::resource("/recipes/tagCombos")
+        .route(web::get().to(RecipeRoutes::get_tag_combos))
+    );
+    cfg.service(web::resource("/recipes/cuisines")
+        .route(web::get().to(RecipeRoutes::get_cuisines))
+    );
+    if features.is_enabled(Feature::Export) {
+        cfg.service(web::resource("/recipes/export/csv")
+            .route(web::get().to(RecipeRoutes::export_recipes_csv))
+        );
+        cfg.service(web::resource("/recipes/preview")
+            .route(web::post().to(RecipeRoutes::preview_recipe))
+        );
+        cfg.service(web::resource("/recipes/cookbook")
+            .route(web::post().to(RecipeRoutes::export_cookbook))
+        );
+    }
+    cfg.service(web::resource("/recipes/trending")
+        .route(web::get().to(RecipeRoutes::get_trending_recipes))
+    );
+    cfg.service(web::resource("/recipes/equipment")
+        .route(web::get().to(RecipeRoutes::get_equipment))
+    );
+    cfg.service(web::resource("/recipes/by-slug/{slug}")
+        .route(web::get().to(RecipeRoutes::get_one_recipe_by_slug))
+    );
+    cfg.service(web::resource("/recipes/{id}")
+        .data(SlugPaths { enabled: features.is_enabled(Feature::SlugPaths) })
+        .route(web::post().to(RecipeRoutes::add_one_recipe))
+        .route(web::get().to(RecipeRoutes::get_one_recipe_without_image))
+        .route(web::put().to(RecipeRoutes::update_one_recipe_without_image))
+        .route(web::delete().to(RecipeRoutes::delete_one_recipe))
+    );
+    cfg.service(web::resource("/recipes/{id}/ingredients")
+        .route(web::patch().to(RecipeRoutes::patch_recipe_ingredients))
+    );
+    cfg.service(web::resource("/recipes/{id}/full")
+        .route(web::get().to(RecipeRoutes::get_one_recipe_full))
+    );
+    cfg.service(web::resource("/recipes/{id}/normalizeServings")
+        .route(web::post().to(RecipeRoutes::normalize_servings))
+    );
+    cfg.service(web::resource("/recipes/{id}/cook-mode")
+        .route(web::get().to(RecipeRoutes::get_cook_mode))
+    );
+    cfg.service(web::resource("/recipes/{id}/substitutions")
+        .route(web::get().to(RecipeRoutes::get_substitutions))
+    );
+    if features.is_enabled(Feature::Export) {
+        cfg.service(web::resource("/recipes/{id}/print")
+            .route(web::get().to(RecipeRoutes::get_one_recipe_print))
+        );
+        cfg.service(web::resource("/recipes/{id}/export")
+            .route(web::get().to(RecipeRoutes::export_one_recipe))
+        );
+    }
+    cfg.service(web::resource("/recipes/{id}/publish")
+        .route(web::post().to(RecipeRoutes::publish_recipe))
+    );
+    cfg.service(web::resource("/recipes/{id}/touch")
+        .route(web::post().to(RecipeRoutes::touch_recipe))
+    );
+    cfg.service(web::resource("/recipes/{id}/archive")
+        .route(web::post().to(RecipeRoutes::archive_recipe))
+    );
+    cfg.service(web::resource("/recipes/{id}/unarchive")
+        .route(web::post().to(RecipeRoutes::unarchive_recipe))
+    );
+    cfg.service(web::resource("/recipes/{id}/diff")
+        .route(web::get().to(RecipeRoutes::get_recipe_diff))
+    );
+    cfg.service(web::resource("/recipes/{id}/similar")
+        .route(web::get().to(RecipeRoutes::get_similar_recipes))
+    );
+    cfg.service(web::resource("/templates")
+        .route(web::get().to(TemplateRoutes::get_templates))
+        .route(web::post().to(TemplateRoutes::add_template))
+    );
+    cfg.service(web::resource("/templates/{id}")
+        .route(web::get().to(TemplateRoutes::get_template))
+        .route(web::put().to(TemplateRoutes::update_template))
+        .route(web::delete().to(TemplateRoutes::delete_template))
+    );
+    cfg.service(web::resource("/units")
+        .route(web::get().to(UnitRoutes::get_units))
+        .route(web::post().to(UnitRoutes::add_unit))
+    );
+    cfg.service(web::resource("/units/convert")
+        .route(web::get().to(UnitRoutes::convert))
+    );
+    cfg.service(web::resource("/meal-plans/{id}/workload")
+        .route(web::get().to(MealPlanRoutes::get_workload))
+    );
+    if features.is_enabled(Feature::Batch) {
+        cfg.service(web::resource("/batch")
+            .route(web::post().to(BatchRoutes::execute))
+        );
+    }
+    if features.is_enabled(Feature::Collections) {
+        cfg.service(web::resource("/collections/{id}/addMany")
+            .route(web::post().to(CollectionRoutes::add_many_recipes))
+        );
+    }
+    if features.is_enabled(Feature::Admin) {
+        cfg.service(web::resource("/admin/stats")
+            .route(web::get().to(AdminRoutes::get_stats))
+        );
+        cfg.service(web::resource("/admin/duplicates")
+            .route(web::get().to(AdminRoutes::get_duplicates))
+        );
+        cfg.service(web::resource("/admin/verifyIntegrity")
+            .route(web::get().to(AdminRoutes::verify_integrity))
+        );
+        cfg.service(web::resource("/admin/normalizeTags")
+            .route(web::post().to(AdminRoutes::normalize_tags))
+        );
+        cfg.service(web::resource("/admin/backup")
+            .route(web::get().to(AdminRoutes::backup))
+        );
+        cfg.service(web::resource("/admin/restore")
+            .route(web::post().to(AdminRoutes::restore))
+        );
+    }
+
+    let mut image = web::resource("/recipes/{id}/image")
+        .route(web::get().to(RecipeRoutes::get_one_recipe_image));
+    if features.is_enabled(Feature::ImageUpload) {
+        image = image
+            .route(web::put().to(RecipeRoutes::update_one_recipe_image))
+            .route(web::delete().to(RecipeRoutes::delete_one_recipe_image));
+    }
+    cfg.service(image.default_service(web::route().to(HttpResponse::NotFound)));
+    if features.is_enabled(Feature::ImageUpload) {
+        cfg.service(web::resource("/recipes/{id}/image/url")
+            .route(web::put().to(RecipeRoutes::update_one_recipe_image_url))
+        );
+    }
+}
+
+
+#[cfg(test)]
+mod routes_tests {
+    use actix_web::{App, test, web};
+    use actix_web::http::{Method, StatusCode};
+    use serde_json::Value;
+    use serial_test::serial;
+
+    use crate::dao::dao_tests::{before, cleanup_after, create_many_recipes_without_images};
+    use crate::discovery_routes::{API_BASE_PATH, ROUTES};
+    use crate::features::FeatureFlags;
+    use crate::routes::{configure, normalize_path};
+    use crate::unit_registry::UnitRegistry;
+
+    async fn status_of(features: FeatureFlags, req: test::TestRequest) -> StatusCode {
+        let mut app = test::init_service(App::new()
+            .service(web::scope("/api/v1").configure(|cfg| configure(cfg, &features)))).await;
+        test::call_service(&mut app, req.to_request()).await.status()
+    }
+
+    #[actix_rt::test]
+    async fn disabled_route_is_not_registered() {
+        let req = || test::TestRequest::post().uri("/api/v1/batch").set_payload("no json");
+        assert_ne!(status_of(FeatureFlags::default(), req()).await, StatusCode::NOT_FOUND);
+        assert_eq!(status_of(FeatureFlags::parse("batch"), req()).await, StatusCode::NOT_FOUND);
+
+        let req = || test::TestRequest::get().uri("/api/v1/admin/stats");
+        assert_ne!(status_of(FeatureFlags::default(), req()).await, StatusCode::NOT_FOUND);
+        assert_eq!(status_of(FeatureFlags::parse("admin"), req()).await, StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn disabled_method_answers_not_found() {
+        let id = "5f7333360051027600b01a36";
+        let upload = || test::TestRequest::put().uri(&format!("/api/v1/recipes/{}/image", id)).set_payload("image");
+        assert_ne!(status_of(FeatureFlags::default(), upload()).await, StatusCode::NOT_FOUND);
+        assert_eq!(status_of(FeatureFlags::parse("image-upload"), upload()).await, StatusCode::NOT_FOUND);
+
+        let import = || test::TestRequest::post().uri("/api/v1/recipes").set_payload("[]");
+        assert_eq!(status_of(FeatureFlags::parse("bulk-import"), import()).await, StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn documented_routes_are_registered() {
+        for route in ROUTES {
+            let path = route.path.replace("{id}", "5f7333360051027600b01a36").replace("{slug}", "pasta");
+            let req = test::TestRequest::with_uri(&format!("{}{}", API_BASE_PATH, path))
+                .method(Method::from_bytes(route.method.as_bytes()).unwrap());
+            let status = status_of(FeatureFlags::default(), req).await;
+            assert!(status != StatusCode::NOT_FOUND && status != StatusCode::METHOD_NOT_ALLOWED,
+                    "{} {} answered {}", route.method, route.path, status);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn discovery_lists_enabled_routes() {
+        let mut app = test::init_service(App::new()
+            .service(web::scope(API_BASE_PATH).configure(|cfg| configure(cfg, &FeatureFlags::parse("batch"))))).await;
+        let req = test::TestRequest::get().uri("/api/v1/").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["basePath"], "/api/v1");
+
+        let routes = body["routes"].as_array().unwrap();
+        let has = |method: &str, path: &str| routes.iter().any(|route| route["method"] == method && route["path"] == path);
+        assert_eq!(has("GET", "/recipes"), true);
+        assert_eq!(has("GET", "/recipes/{id}/cook-mode"), true);
+        assert_eq!(has("POST", "/recipes/{id}/normalizeServings"), true);
+        assert_eq!(has("GET", "/units/convert"), true);
+        assert_eq!(has("POST", "/batch"), false);
+        let list = routes.iter().find(|route| route["method"] == "GET" && route["path"] == "/recipes").unwrap();
+        assert_eq!(list["queryParams"].as_array().unwrap().contains(&Value::from("offset")), true);
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn trailing_slashes_are_trimmed() {
+        let dao = before().await;
+        dao.add_many_recipes(create_many_recipes_without_images(2)).await.unwrap();
+        let mut app = test::init_service(App::new()
+            .wrap(normalize_path())
+            .data(UnitRegistry::default())
+            .data(dao.clone())
+            .service(web::scope(API_BASE_PATH).configure(|cfg| configure(cfg, &FeatureFlags::default())))).await;
+
+        let mut bodies = Vec::new();
+        for uri in ["/api/v1/units/convert", "/api/v1/units/convert/", "/api/v1//units//convert/"].iter() {
+            let req = test::TestRequest::get().uri(&format!("{}?amount=1&from=Kilogramm&to=Gramm", uri)).to_request();
+            let resp = test::call_service(&mut app, req).await;
+            assert_eq!(resp.status(), StatusCode::OK, "{}", uri);
+            bodies.push(test::read_body(resp).await);
+        }
+        assert_eq!(bodies.iter().all(|body| *body == bodies[0]), true);
+
+        for uri in ["/api/v1", "/api/v1/"].iter() {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::OK, "{}", uri);
+        }
+        let mut listings = Vec::new();
+        for uri in ["/api/v1/recipes", "/api/v1/recipes/", "/api/v1//recipes//"].iter() {
+            let req = test::TestRequest::get().uri(&format!("{}?page=1&items=10&sorting=1", uri)).to_request();
+            let resp = test::call_service(&mut app, req).await;
+            assert_eq!(resp.status(), StatusCode::OK, "{}", uri);
+            let listing: Value = test::read_body_json(resp).await;
+            assert_eq!(listing.as_array().unwrap().len(), 2, "{}", uri);
+            listings.push(listing);
+        }
+        assert_eq!(listings.iter().all(|listing| *listing == listings[0]), true);
+
+        cleanup_after(dao).await;
+    }
+}
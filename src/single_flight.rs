@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use bson::oid::ObjectId;
+use futures_util::future::{BoxFuture, FutureExt, Shared};
+
+use crate::dao::DaoError;
+use crate::model::recipe::Recipe;
+use crate::recipe_cache::CacheMiss;
+
+pub const COALESCE_RECIPE_READS_ENV: &str = "COALESCE_RECIPE_READS";
+
+type InFlight<K, V> = Arc<Mutex<HashMap<K, Shared<BoxFuture<'static, V>>>>>;
+/// a recipe read with the cache token taken when its query started
+type RecipeRead = Result<(Recipe, CacheMiss), DaoError>;
+
+/// Lets concurrent calls with the same key share one execution and its result.
+/// Nothing is cached, a call arriving after the shared execution finished starts a new one
+pub struct SingleFlight<K, V> {
+    in_flight: InFlight<K, V>,
+}
+
+impl<K, V> Default for SingleFlight<K, V> {
+    fn default() -> Self {
+        Self { in_flight: Arc::new(Mutex::new(HashMap::new())) }
+    }
+}
+
+impl<K, V> SingleFlight<K, V>
+    where K: Hash + Eq + Clone + Send + Sync + 'static, V: Clone + Send + Sync + 'static {
+    /// the result of the execution in flight for the key, `load` is only started when there is none
+    pub async fn run<F, Fut>(&self, key: K, load: F) -> V
+        where F: FnOnce() -> Fut, Fut: Future<Output=V> + Send + 'static {
+        let shared = {
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(PoisonError::into_inner);
+            match in_flight.get(&key) {
+                Some(shared) => shared.clone(),
+                None => {
+                    let registry = self.in_flight.clone();
+                    let finished_key = key.clone();
+                    let load = load();
+                    let shared = async move {
+                        let value = load.await;
+                        registry.lock().unwrap_or_else(PoisonError::into_inner).remove(&finished_key);
+                        value
+                    }.boxed().shared();
+                    in_flight.insert(key, shared.clone());
+                    shared
+                }
+            }
+        };
+        shared.await
+    }
+}
+
+/// Concurrent reads of the same recipe share one query when switched on by `COALESCE_RECIPE_READS=true`,
+/// which takes load off the database for hot recipes. Off by default. The query shares the cache token
+/// taken when it started, so no read joining it fills the recipe cache after an invalidation
+#[derive(Clone, Default)]
+pub struct ReadCoalescing {
+    pub recipes: Option<Arc<SingleFlight<ObjectId, RecipeRead>>>,
+}
+
+impl ReadCoalescing {
+    pub fn from_env() -> Self {
+        let enabled = std::env::var(COALESCE_RECIPE_READS_ENV)
+            .is_ok_and(|enabled| matches!(enabled.trim().to_lowercase().as_str(), "true" | "1"));
+        info!("Loaded recipe read coalescing={}", enabled);
+        Self::new(enabled)
+    }
+
+    pub fn new(enabled: bool) -> Self {
+        Self { recipes: if enabled { Some(Arc::new(SingleFlight::default())) } else { None } }
+    }
+}
+
+
+#[cfg(test)]
+mod single_flight_tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use futures_util::future::join_all;
+
+    use crate::single_flight::{ReadCoalescing, SingleFlight};
+
+    async fn counted_load(calls: Arc<AtomicUsize>, key: u32) -> u32 {
+        calls.fetch_add(1, Ordering::SeqCst);
+        actix_rt::time::delay_for(Duration::from_millis(20)).await;
+        key * 10
+    }
+
+    #[actix_rt::test]
+    async fn concurrent_calls_share_one_load() {
+        let flight = SingleFlight::<u32, u32>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let results = join_all((0..50).map(|_| flight.run(7, || counted_load(calls.clone(), 7)))).await;
+        assert_eq!(results, vec![70; 50]);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(flight.in_flight.lock().unwrap().is_empty(), true);
+
+        assert_eq!(flight.run(7, || counted_load(calls.clone(), 7)).await, 70);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_rt::test]
+    async fn different_keys_load_separately() {
+        let flight = SingleFlight::<u32, u32>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let results = join_all((0..6).map(|i| {
+            let calls = calls.clone();
+            flight.run(i % 2, move || counted_load(calls, i % 2))
+        })).await;
+        assert_eq!(results, vec![0, 10, 0, 10, 0, 10]);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn coalescing_is_off_by_default() {
+        assert_eq!(ReadCoalescing::default().recipes.is_none(), true);
+        assert_eq!(ReadCoalescing::new(true).recipes.is_some(), true);
+    }
+}
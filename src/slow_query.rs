@@ -0,0 +1,113 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use bson::Document;
+
+use crate::request_id;
+
+pub const SLOW_QUERY_THRESHOLD_ENV: &str = "SLOW_QUERY_THRESHOLD_MS";
+pub const DEFAULT_SLOW_QUERY_THRESHOLD_MS: u64 = 500;
+
+/// Times database calls and warns about the ones exceeding the threshold.
+#[derive(Debug, Clone)]
+pub struct SlowQueryLog {
+    threshold: Duration,
+    slow_queries: Arc<AtomicUsize>,
+}
+
+impl Default for SlowQueryLog {
+    fn default() -> Self { Self::new(Duration::from_millis(DEFAULT_SLOW_QUERY_THRESHOLD_MS)) }
+}
+
+impl SlowQueryLog {
+    pub fn new(threshold: Duration) -> Self {
+        Self { threshold, slow_queries: Arc::new(AtomicUsize::new(0)) }
+    }
+
+    /// threshold in milliseconds from `SLOW_QUERY_THRESHOLD_MS`, the default when unset or invalid
+    pub fn from_env() -> Self {
+        let threshold = std::env::var(SLOW_QUERY_THRESHOLD_ENV).ok()
+            .and_then(|threshold| threshold.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_SLOW_QUERY_THRESHOLD_MS);
+        Self::new(Duration::from_millis(threshold))
+    }
+
+    pub async fn time<F: Future>(&self, operation: &str, filter: &Document, query: F) -> F::Output {
+        let start = Instant::now();
+        let result = query.await;
+        self.check(operation, filter, start.elapsed());
+        result
+    }
+
+    /// logs and returns the warning when `elapsed` exceeds the threshold
+    fn check(&self, operation: &str, filter: &Document, elapsed: Duration) -> Option<String> {
+        if elapsed <= self.threshold {
+            return None;
+        }
+        let total = self.slow_queries.fetch_add(1, Ordering::Relaxed) + 1;
+        let warning = format!("Slow database call. operation={}, filter={}, elapsed={}ms, threshold={}ms, total={}, request_id={}",
+                              operation,
+                              summarize_filter(filter),
+                              elapsed.as_millis(),
+                              self.threshold.as_millis(),
+                              total,
+                              request_id::current().unwrap_or_else(|| "-".to_string()));
+        warn!("{}", warning);
+        Some(warning)
+    }
+}
+
+/// filter keys without their values, keeping user data out of the logs
+fn summarize_filter(filter: &Document) -> String {
+    format!("{{{}}}", filter.keys().cloned().collect::<Vec<String>>().join(", "))
+}
+
+
+#[cfg(test)]
+mod slow_query_tests {
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    use crate::request_id;
+    use crate::slow_query::{SlowQueryLog, summarize_filter};
+
+    #[actix_rt::test]
+    async fn slow_query_warning_fires() {
+        let log = SlowQueryLog::new(Duration::from_millis(10));
+        let delayed_stub = async {
+            actix_rt::time::delay_for(Duration::from_millis(30)).await;
+            42
+        };
+        let result = request_id::scope("req-1".to_string(),
+                                       log.time("get_many_recipes", &doc! {"tags": "x"}, delayed_stub)).await;
+        assert_eq!(result, 42);
+        assert_eq!(log.slow_queries.load(Ordering::Relaxed), 1);
+    }
+
+    #[actix_rt::test]
+    async fn fast_query_does_not_warn() {
+        let log = SlowQueryLog::new(Duration::from_millis(500));
+        log.time("count_recipes", &doc! {}, async {}).await;
+        assert_eq!(log.slow_queries.load(Ordering::Relaxed), 0);
+    }
+
+    #[actix_rt::test]
+    async fn warning_contains_operation_filter_and_request_id() {
+        let log = SlowQueryLog::new(Duration::from_millis(10));
+        let warning = request_id::scope("req-1".to_string(), async {
+            log.check("count_recipes", &doc! {"tags": {"$all": ["x"]}, "difficulty": "Easy"}, Duration::from_millis(20))
+        }).await.unwrap();
+        assert_eq!(warning.contains("operation=count_recipes"), true);
+        assert_eq!(warning.contains("filter={tags, difficulty}"), true);
+        assert_eq!(warning.contains("request_id=req-1"), true);
+
+        assert_eq!(log.check("count_recipes", &doc! {}, Duration::from_millis(5)), None);
+    }
+
+    #[test]
+    fn summarize_empty_filter() {
+        assert_eq!(summarize_filter(&doc! {}), "{}");
+    }
+}
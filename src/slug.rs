@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+
+/// Longest slug derived from a title, the uniqueness suffix comes on top
+pub const MAX_SLUG_LENGTH: usize = 64;
+const MAX_SUFFIX_LENGTH: usize = 8;
+const SEPARATOR: char = '-';
+const FALLBACK_SLUG: &str = "recipe";
+
+/// lowercase ascii words of the title joined by `-`, e.g. `Spaghetti Carbonara!` becomes `spaghetti-carbonara`
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    let mut pending_separator = false;
+    for c in title.chars().flat_map(char::to_lowercase) {
+        let replacement = match c {
+            'ä' => "ae",
+            'ö' => "oe",
+            'ü' => "ue",
+            'ß' => "ss",
+            _ => ""
+        };
+        if !replacement.is_empty() || c.is_ascii_alphanumeric() {
+            if pending_separator && !slug.is_empty() {
+                slug.push(SEPARATOR);
+            }
+            pending_separator = false;
+            if replacement.is_empty() {
+                slug.push(c);
+            } else {
+                slug.push_str(replacement);
+            }
+        } else {
+            pending_separator = true;
+        }
+    }
+
+    if slug.len() > MAX_SLUG_LENGTH {
+        slug.truncate(MAX_SLUG_LENGTH);
+        slug.truncate(slug.trim_end_matches(SEPARATOR).len());
+    }
+    if slug.is_empty() { FALLBACK_SLUG.to_string() } else { slug }
+}
+
+/// Whether `GET /recipes/{id}` looks up path segments which are no object id by slug, registered per resource.
+/// Segments which are both, like 24 hex digits, are taken as id
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SlugPaths {
+    pub enabled: bool,
+}
+
+/// lowercase ascii letters and digits separated by single dashes
+pub fn is_valid_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug.len() <= MAX_SLUG_LENGTH + MAX_SUFFIX_LENGTH
+        && slug.split(SEPARATOR)
+        .all(|word| !word.is_empty() && word.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()))
+}
+
+/// `base` when it is still free, otherwise `base-2`, `base-3`, ...
+pub fn unique_slug(base: &str, taken: &HashSet<String>) -> String {
+    if !taken.contains(base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|suffix| format!("{}{}{}", base, SEPARATOR, suffix))
+        .find(|slug| !taken.contains(slug))
+        .unwrap()
+}
+
+
+#[cfg(test)]
+mod slug_tests {
+    use std::collections::HashSet;
+
+    use crate::slug::{is_valid_slug, MAX_SLUG_LENGTH, slugify, unique_slug};
+
+    #[test]
+    fn slugify_title() {
+        assert_eq!(slugify("Spaghetti Carbonara"), "spaghetti-carbonara");
+        assert_eq!(slugify("  Mom's (best) Pancakes!! "), "mom-s-best-pancakes");
+        assert_eq!(slugify("Käsespätzle mit Röstzwiebeln"), "kaesespaetzle-mit-roestzwiebeln");
+        assert_eq!(slugify("Crème brûlée"), "cr-me-br-l-e");
+        assert_eq!(slugify("???"), "recipe");
+    }
+
+    #[test]
+    fn slugify_long_title() {
+        let slug = slugify(&"word ".repeat(30));
+        assert_eq!(slug.len() <= MAX_SLUG_LENGTH, true);
+        assert_eq!(slug.ends_with('-'), false);
+        assert_eq!(is_valid_slug(&slug), true);
+    }
+
+    #[test]
+    fn valid_slugs() {
+        assert_eq!(is_valid_slug("spaghetti-carbonara"), true);
+        assert_eq!(is_valid_slug("pasta-2"), true);
+        assert_eq!(is_valid_slug(""), false);
+        assert_eq!(is_valid_slug("-pasta"), false);
+        assert_eq!(is_valid_slug("pasta-"), false);
+        assert_eq!(is_valid_slug("pasta--2"), false);
+        assert_eq!(is_valid_slug("Pasta"), false);
+        assert_eq!(is_valid_slug("pasta_2"), false);
+        assert_eq!(is_valid_slug(&"a".repeat(100)), false);
+    }
+
+    #[test]
+    fn unique_slug_adds_suffix_on_collision() {
+        let mut taken = HashSet::new();
+        assert_eq!(unique_slug("pasta", &taken), "pasta");
+
+        taken.insert("pasta".to_string());
+        taken.insert("pasta-2".to_string());
+        assert_eq!(unique_slug("pasta", &taken), "pasta-3");
+    }
+}
@@ -0,0 +1,130 @@
+use actix_web::{Either, HttpRequest, HttpResponse, Responder, web};
+use actix_web::web::Json;
+
+use crate::dao::Dao;
+use crate::model::ingredient_template::IngredientTemplate;
+use crate::recipe_routes::{dao_error_response, extract_id_from_req, validation_error_response};
+
+pub struct TemplateRoutes {}
+
+impl TemplateRoutes {
+    pub async fn add_template(database: web::Data<Dao>, template: Json<IngredientTemplate>) -> Either<impl Responder, impl Responder> {
+        if let Err(err) = template.validate() {
+            return Either::B(validation_error_response(err.error));
+        }
+        match database.insert_template(template.into_inner()).await {
+            Ok(id) => Either::A(HttpResponse::Ok().json(id)),
+            Err(err) => Either::B(dao_error_response(err)),
+        }
+    }
+
+    pub async fn get_templates(database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        match database.get_templates(None).await {
+            Ok(templates) => Either::A(HttpResponse::Ok().json(templates)),
+            Err(err) => Either::B(dao_error_response(err)),
+        }
+    }
+
+    pub async fn get_template(req: HttpRequest, database: web::Data<Dao>) -> Either<impl Responder, impl Responder> {
+        let id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return Either::B(HttpResponse::BadRequest().finish())
+        };
+
+        match database.get_template(id).await {
+            Ok(template) => Either::A(HttpResponse::Ok().json(template)),
+            Err(err) => Either::B(dao_error_response(err)),
+        }
+    }
+
+    pub async fn update_template(req: HttpRequest, database: web::Data<Dao>, template: Json<IngredientTemplate>) -> HttpResponse {
+        let id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return HttpResponse::BadRequest().finish()
+        };
+        if let Err(err) = template.validate() {
+            return validation_error_response(err.error);
+        }
+
+        match database.update_template(id, template.into_inner()).await {
+            Ok(_) => HttpResponse::Ok().finish(),
+            Err(err) => dao_error_response(err),
+        }
+    }
+
+    pub async fn delete_template(req: HttpRequest, database: web::Data<Dao>) -> HttpResponse {
+        let id = match extract_id_from_req(req) {
+            Some(id) => id,
+            None => return HttpResponse::BadRequest().finish()
+        };
+
+        match database.delete_template(id).await {
+            Ok(_) => HttpResponse::Ok().finish(),
+            Err(err) => dao_error_response(err),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{App, test, web};
+    use actix_web::http::StatusCode;
+    use bson::Bson;
+    use serde_json::{json, Value};
+    use serial_test::serial;
+
+    use crate::dao::dao_tests::{before, cleanup_after};
+    use crate::template_routes::TemplateRoutes;
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_template_crud() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .route("/templates", web::post().to(TemplateRoutes::add_template))
+            .route("/templates", web::get().to(TemplateRoutes::get_templates))
+            .route("/templates/{id}", web::get().to(TemplateRoutes::get_template))
+            .route("/templates/{id}", web::put().to(TemplateRoutes::update_template))
+            .route("/templates/{id}", web::delete().to(TemplateRoutes::delete_template))).await;
+
+        let mut template = json!({
+            "name": "Basic pie crust",
+            "ingredients": [{ "id": "0", "amount": 250, "title": "Flour", "measurementUnit": "Gramm" }]
+        });
+        let req = test::TestRequest::post().set_json(&template).uri("/templates").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let body: Bson = test::read_body_json(resp).await;
+        let url = format!("/templates/{}", body.as_object_id().unwrap());
+
+        template["name"] = json!("Sweet pie crust");
+        let req = test::TestRequest::put().set_json(&template).uri(&url).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+
+        let req = test::TestRequest::get().uri(&url).to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body["name"], "Sweet pie crust");
+        assert_eq!(body["ingredients"][0]["title"], "Flour");
+
+        let req = test::TestRequest::get().uri("/templates").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+
+        template["ingredients"] = json!([]);
+        let req = test::TestRequest::post().set_json(&template).uri("/templates").to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let req = test::TestRequest::delete().uri(&url).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let req = test::TestRequest::get().uri(&url).to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        cleanup_after(dao).await;
+    }
+}
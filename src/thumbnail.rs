@@ -0,0 +1,300 @@
+use std::io::Cursor;
+
+use image::{DynamicImage, GenericImageView, ImageFormat};
+use image::codecs::jpeg::JpegEncoder;
+use image::io::Reader;
+
+use crate::LogExtensionErr;
+
+pub const THUMBNAIL_MAX_DIMENSION_ENV: &str = "THUMBNAIL_MAX_DIMENSION";
+pub const DEFAULT_THUMBNAIL_MAX_DIMENSION: u32 = 256;
+pub const IMAGE_COMPRESSION_THRESHOLD_ENV: &str = "IMAGE_COMPRESSION_THRESHOLD_BYTES";
+const COMPRESSION_QUALITY: u8 = 80;
+const COMPRESSED_CONTENT_TYPE: &str = "image/jpeg";
+const DATA_URL_PREFIX: &str = "data:";
+const DATA_URL_SEPARATOR: &str = ";base64,";
+/// images with more pixels are rejected before decoding, decoded they would take hundreds of megabytes
+const MAX_DECODED_PIXELS: u64 = 40_000_000;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ThumbnailError { pub error: String }
+
+impl From<&str> for ThumbnailError {
+    fn from(error: &str) -> Self { Self { error: error.to_string() } }
+}
+
+impl From<String> for ThumbnailError {
+    fn from(error: String) -> Self { Self { error } }
+}
+
+/// longest side of generated thumbnails from `THUMBNAIL_MAX_DIMENSION`, the default when unset or invalid
+pub fn max_dimension_from_env() -> u32 {
+    std::env::var(THUMBNAIL_MAX_DIMENSION_ENV).ok()
+        .and_then(|dimension| dimension.parse::<u32>().ok())
+        .filter(|dimension| *dimension > 0)
+        .unwrap_or(DEFAULT_THUMBNAIL_MAX_DIMENSION)
+}
+
+/// Uploads larger than `IMAGE_COMPRESSION_THRESHOLD_BYTES` (decoded) are re-encoded as jpeg,
+/// smaller ones only when the client asks for it. Unset, only requested compressions happen
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct ImageCompression {
+    pub threshold_bytes: Option<usize>,
+}
+
+impl ImageCompression {
+    pub fn from_env() -> Self {
+        let threshold_bytes = std::env::var(IMAGE_COMPRESSION_THRESHOLD_ENV).ok()
+            .and_then(|threshold| threshold.parse::<usize>().ok());
+        Self { threshold_bytes }
+    }
+
+    pub fn should_compress(&self, image_base64: &str, requested: bool) -> bool {
+        let decoded_size = split_data_url(image_base64.trim()).1.len() / 4 * 3;
+        requested || self.threshold_bytes.is_some_and(|threshold| decoded_size > threshold)
+    }
+}
+
+/// The image to store after an upload
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ProcessedImage {
+    pub image: String,
+    /// content type of the upload when the stored image was re-encoded
+    pub original_content_type: Option<String>,
+    pub thumbnail: Option<String>,
+}
+
+/// Compresses the upload when asked to and generates its thumbnail, both fall back on failure:
+/// to the uploaded image and to no thumbnail. Cpu bound, call it from a blocking task pool.
+pub fn process_upload(image_base64: String, compress: bool, max_dimension: u32) -> ProcessedImage {
+    let (image, original_content_type) = match compress {
+        true => match compress_image(&image_base64) {
+            Ok(Some(compressed)) => (compressed, content_type_of(&image_base64).map(String::from)),
+            Ok(None) => (image_base64, None),
+            Err(err) => {
+                warn!("Could not compress image, storing the original instead. Err={}", err.error);
+                (image_base64, None)
+            }
+        },
+        false => (image_base64, None),
+    };
+    let thumbnail = generate_thumbnail(&image, max_dimension)
+        .log_if_err(|err| warn!("Could not generate thumbnail, serving the original instead. Err={:?}", err))
+        .ok();
+    ProcessedImage { image, original_content_type, thumbnail }
+}
+
+/// Re-encodes the base64 image (optionally a data url) as jpeg, None when that is not smaller.
+/// WebP would be more compact, but the image crate can only decode it.
+pub fn compress_image(image_base64: &str) -> Result<Option<String>, ThumbnailError> {
+    let (prefix, data) = split_data_url(image_base64.trim());
+    let bytes = base64::decode(data).map_err(|err| format!("Image is no valid base64. Err={}", err))?;
+    let (image, _) = decode_image(&bytes)?;
+
+    let mut compressed = Vec::new();
+    JpegEncoder::new_with_quality(&mut compressed, COMPRESSION_QUALITY)
+        .encode_image(&image.to_rgb8())
+        .map_err(|err| format!("Could not encode image. Err={}", err))?;
+    if compressed.len() >= bytes.len() {
+        return Ok(None);
+    }
+
+    let prefix = if prefix.is_empty() { String::new() } else { format!("{}{}{}", DATA_URL_PREFIX, COMPRESSED_CONTENT_TYPE, DATA_URL_SEPARATOR) };
+    Ok(Some(format!("{}{}", prefix, base64::encode(compressed))))
+}
+
+/// content type sniffed from the first bytes of the base64 image, None for unknown formats
+pub fn content_type_of(image_base64: &str) -> Option<&'static str> {
+    let (_, data) = split_data_url(image_base64.trim());
+    let data = data.as_bytes();
+    let header = base64::decode(data.get(..32).unwrap_or(data)).ok()?;
+    content_type(image::guess_format(&header).ok()?)
+}
+
+fn content_type(format: ImageFormat) -> Option<&'static str> {
+    match format {
+        ImageFormat::Png => Some("image/png"),
+        ImageFormat::Jpeg => Some("image/jpeg"),
+        ImageFormat::Gif => Some("image/gif"),
+        ImageFormat::WebP => Some("image/webp"),
+        ImageFormat::Bmp => Some("image/bmp"),
+        _ => None,
+    }
+}
+
+/// decodes the image once the dimensions of its header stay within `MAX_DECODED_PIXELS`
+fn decode_image(bytes: &[u8]) -> Result<(DynamicImage, ImageFormat), ThumbnailError> {
+    let reader = Reader::new(Cursor::new(bytes)).with_guessed_format()
+        .map_err(|err| format!("Could not read image. Err={}", err))?;
+    let format = reader.format().ok_or("Unknown image format")?;
+    let (width, height) = reader.into_dimensions().map_err(|err| format!("Could not read image dimensions. Err={}", err))?;
+    if u64::from(width) * u64::from(height) > MAX_DECODED_PIXELS {
+        return Err(format!("Image of {}x{} pixels exceeds {} pixels", width, height, MAX_DECODED_PIXELS).into());
+    }
+    let image = image::load_from_memory_with_format(bytes, format)
+        .map_err(|err| format!("Could not decode image. Err={}", err))?;
+    Ok((image, format))
+}
+
+/// Scales the base64 image (optionally a data url) down to fit into `max_dimension`,
+/// keeping its format and aspect ratio, formats without encoder become png with a matching data url prefix.
+/// Smaller images are returned unchanged.
+/// Decoding and resizing is cpu bound, call it from a blocking task pool.
+pub fn generate_thumbnail(image_base64: &str, max_dimension: u32) -> Result<String, ThumbnailError> {
+    let (prefix, data) = split_data_url(image_base64.trim());
+    let bytes = base64::decode(data).map_err(|err| format!("Image is no valid base64. Err={}", err))?;
+    let (image, format) = decode_image(&bytes)?;
+
+    if image.width() <= max_dimension && image.height() <= max_dimension {
+        return Ok(image_base64.trim().to_string());
+    }
+
+    let mut thumbnail = Vec::new();
+    let format = output_format(format);
+    image.thumbnail(max_dimension, max_dimension)
+        .write_to(&mut Cursor::new(&mut thumbnail), format)
+        .map_err(|err| format!("Could not encode thumbnail. Err={}", err))?;
+
+    let prefix = match (prefix.is_empty(), content_type(format)) {
+        (false, Some(content_type)) => format!("{}{}{}", DATA_URL_PREFIX, content_type, DATA_URL_SEPARATOR),
+        _ => String::new(),
+    };
+    Ok(format!("{}{}", prefix, base64::encode(thumbnail)))
+}
+
+/// splits `data:image/png;base64,...` into the prefix and the base64 data
+fn split_data_url(image: &str) -> (&str, &str) {
+    match image.find(DATA_URL_SEPARATOR) {
+        Some(index) if image.starts_with(DATA_URL_PREFIX) => image.split_at(index + DATA_URL_SEPARATOR.len()),
+        _ => ("", image)
+    }
+}
+
+/// the enabled encoders, everything else is stored as png
+fn output_format(format: ImageFormat) -> ImageFormat {
+    match format {
+        ImageFormat::Jpeg => ImageFormat::Jpeg,
+        _ => ImageFormat::Png,
+    }
+}
+
+
+#[cfg(test)]
+pub mod thumbnail_tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, GenericImageView, ImageFormat};
+
+    use crate::thumbnail::{compress_image, content_type_of, generate_thumbnail, ImageCompression, process_upload, split_data_url};
+
+    pub fn create_png_base64(width: u32, height: u32) -> String {
+        let mut bytes = Vec::new();
+        DynamicImage::new_rgb8(width, height)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        base64::encode(bytes)
+    }
+
+    /// noise compresses badly as png, like photos do
+    pub fn create_noisy_png_base64(width: u32, height: u32) -> String {
+        let image = image::RgbImage::from_fn(width, height, |x, y| {
+            let value = (x.wrapping_mul(7919) ^ y.wrapping_mul(104729)).wrapping_mul(2654435761) >> 24;
+            image::Rgb([value as u8, (value >> 3) as u8, (value >> 5) as u8])
+        });
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgb8(image)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        base64::encode(bytes)
+    }
+
+    fn dimensions(image_base64: &str) -> (u32, u32) {
+        let (_, data) = split_data_url(image_base64);
+        image::load_from_memory(&base64::decode(data).unwrap()).unwrap().dimensions()
+    }
+
+    #[test]
+    fn thumbnail_keeps_aspect_ratio() {
+        let thumbnail = generate_thumbnail(&create_png_base64(600, 300), 100).unwrap();
+        assert_eq!(dimensions(&thumbnail), (100, 50));
+    }
+
+    #[test]
+    fn thumbnail_keeps_data_url_prefix() {
+        let image = format!("data:image/png;base64,{}", create_png_base64(300, 600));
+        let thumbnail = generate_thumbnail(&image, 100).unwrap();
+        assert_eq!(thumbnail.starts_with("data:image/png;base64,"), true);
+        assert_eq!(dimensions(&thumbnail), (50, 100));
+    }
+
+    #[test]
+    fn thumbnail_prefix_matches_its_format() {
+        let jpeg = compress_image(&create_noisy_png_base64(400, 300)).unwrap().unwrap();
+        let thumbnail = generate_thumbnail(&format!("data:image/png;base64,{}", jpeg), 100).unwrap();
+        assert_eq!(thumbnail.starts_with("data:image/jpeg;base64,"), true);
+        assert_eq!(content_type_of(&thumbnail), Some("image/jpeg"));
+    }
+
+    #[test]
+    fn oversized_image_is_not_decoded() {
+        let mut jpeg = base64::decode(compress_image(&create_noisy_png_base64(40, 30)).unwrap().unwrap()).unwrap();
+        let frame = jpeg.windows(2).position(|marker| marker == [0xFF, 0xC0]).unwrap();
+        jpeg[frame + 5..frame + 9].copy_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF]);
+        let err = generate_thumbnail(&base64::encode(&jpeg), 100).unwrap_err();
+        assert_eq!(err.error.contains("65535x65535"), true, "{}", err.error);
+        assert_eq!(compress_image(&base64::encode(&jpeg)).is_err(), true);
+    }
+
+    #[test]
+    fn small_image_is_not_scaled() {
+        let image = create_png_base64(20, 10);
+        assert_eq!(generate_thumbnail(&image, 100).unwrap(), image);
+    }
+
+    #[test]
+    fn invalid_image_fails() {
+        assert_eq!(generate_thumbnail("image", 100).is_err(), true);
+        assert_eq!(generate_thumbnail(&base64::encode("no image"), 100).is_err(), true);
+    }
+
+    #[test]
+    fn compressed_image_is_smaller_jpeg() {
+        let image = create_noisy_png_base64(400, 300);
+        let compressed = compress_image(&image).unwrap().unwrap();
+        assert_eq!(compressed.len() < image.len(), true);
+        assert_eq!(content_type_of(&compressed), Some("image/jpeg"));
+        assert_eq!(dimensions(&compressed), (400, 300));
+
+        let data_url = format!("data:image/png;base64,{}", image);
+        assert_eq!(compress_image(&data_url).unwrap().unwrap().starts_with("data:image/jpeg;base64,"), true);
+    }
+
+    #[test]
+    fn content_type_of_non_ascii_image() {
+        assert_eq!(content_type_of(&format!("{}ü", "A".repeat(31))), None);
+        assert_eq!(content_type_of("data:image/png;base64,ääää"), None);
+        assert_eq!(content_type_of(&create_png_base64(20, 10)), Some("image/png"));
+    }
+
+    #[test]
+    fn compression_threshold() {
+        let image = create_png_base64(20, 10);
+        assert_eq!(ImageCompression::default().should_compress(&image, false), false);
+        assert_eq!(ImageCompression::default().should_compress(&image, true), true);
+        assert_eq!(ImageCompression { threshold_bytes: Some(10) }.should_compress(&image, false), true);
+        assert_eq!(ImageCompression { threshold_bytes: Some(1 << 20) }.should_compress(&image, false), false);
+    }
+
+    #[test]
+    fn process_upload_keeps_original_content_type() {
+        let processed = process_upload(create_noisy_png_base64(400, 300), true, 100);
+        assert_eq!(processed.original_content_type, Some("image/png".to_string()));
+        assert_eq!(content_type_of(&processed.image), Some("image/jpeg"));
+        assert_eq!(dimensions(&processed.thumbnail.unwrap()), (100, 75));
+
+        let processed = process_upload("image".to_string(), true, 100);
+        assert_eq!(processed.image, "image");
+        assert_eq!(processed.original_content_type, None);
+        assert_eq!(processed.thumbnail, None);
+    }
+}
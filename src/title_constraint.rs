@@ -0,0 +1,72 @@
+pub const UNIQUE_TITLES_ENV: &str = "UNIQUE_TITLES";
+pub const UNIQUE_TITLES_PER_AUTHOR_ENV: &str = "UNIQUE_TITLES_PER_AUTHOR";
+/// the title folded by `fold_title`, stored next to the title for the per author constraint
+pub const JSON_ATTR_FOLDED_TITLE: &str = "foldedTitle";
+
+/// Whether recipe titles have to be unique, configured via `UNIQUE_TITLES=true`, `UNIQUE_TITLES=false` drops
+/// the constraint again. An unset or invalid flag leaves the stored indexes as they are, so a deployment
+/// missing the variable does not drop a constraint turned on before.
+///
+/// Curated datasets benefit from unique titles: a second "Pasta" is rejected with 409 and a title
+/// identifies one recipe. Crowd-sourced datasets have many recipes of the same name, there the
+/// constraint rejects legitimate recipes, so it is off by default. Titles are compared exactly,
+/// "Pasta" and "pasta" are different titles. Turning it on fails, and keeps the constraint off,
+/// while the stored recipes already contain duplicate titles. Slugs stay unique either way.
+///
+/// `UNIQUE_TITLES_PER_AUTHOR=true` only keeps the titles unique within the recipes of one author,
+/// compared ignoring surrounding spaces and case, so a user can't have "Pasta" and "pasta ".
+/// Recipes without author are left out
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct TitleConstraint {
+    pub unique: Option<bool>,
+    pub per_author: Option<bool>,
+}
+
+impl TitleConstraint {
+    pub fn from_env() -> Self {
+        let constraint = Self {
+            unique: std::env::var(UNIQUE_TITLES_ENV).ok().and_then(|unique| parse_flag(&unique)),
+            per_author: std::env::var(UNIQUE_TITLES_PER_AUTHOR_ENV).ok().and_then(|unique| parse_flag(&unique)),
+        };
+        info!("Loaded title constraint={:?}", constraint);
+        constraint
+    }
+}
+
+/// the title trimmed and lowercased. Only ASCII letters are lowercased, like `$toLower` of the
+/// database does when folding the titles stored before
+pub fn fold_title(title: &str) -> String {
+    title.trim().to_ascii_lowercase()
+}
+
+/// None for values which are neither on nor off
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod title_constraint_tests {
+    use crate::title_constraint::{fold_title, parse_flag};
+
+    #[test]
+    fn fold_title_test() {
+        assert_eq!(fold_title(" Pasta al Forno "), "pasta al forno");
+        assert_eq!(fold_title("pasta"), fold_title("PASTA"));
+    }
+
+    #[test]
+    fn parse_flag_test() {
+        assert_eq!(parse_flag("true"), Some(true));
+        assert_eq!(parse_flag(" ON "), Some(true));
+        assert_eq!(parse_flag("1"), Some(true));
+        assert_eq!(parse_flag("false"), Some(false));
+        assert_eq!(parse_flag("Off"), Some(false));
+        assert_eq!(parse_flag("maybe"), None);
+        assert_eq!(parse_flag(""), None);
+    }
+}
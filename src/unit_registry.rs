@@ -0,0 +1,142 @@
+use std::sync::{Arc, RwLock};
+
+use crate::dao::Dao;
+use crate::model::amount::round_amount;
+use crate::model::measurement_unit::MeasurementUnit;
+use crate::model::unit_definition::{UnitDefinition, UnitFamily};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum UnitError {
+    UnknownUnit(String),
+    /// the units belong to different families, e.g. gram and liter
+    IncompatibleUnits(String, String),
+    /// a built-in or registered unit has this name already
+    DuplicateUnit(String),
+}
+
+impl UnitError {
+    pub fn message(&self) -> String {
+        match self {
+            UnitError::UnknownUnit(unit) => format!("The unit '{}' is unknown", unit),
+            UnitError::IncompatibleUnits(from, to) => format!("The unit '{}' cannot be converted to '{}'", from, to),
+            UnitError::DuplicateUnit(unit) => format!("The unit '{}' exists already", unit),
+        }
+    }
+}
+
+/// Units available for conversions: the convertible measurement units of ingredients and the units
+/// registered by operators, which are persisted and loaded at startup. Names match case insensitive.
+/// Ingredients keep using the measurement units, registered units are only available for conversions
+#[derive(Debug, Clone)]
+pub struct UnitRegistry {
+    units: Arc<RwLock<Vec<UnitDefinition>>>,
+}
+
+impl Default for UnitRegistry {
+    fn default() -> Self { Self::new(vec![]) }
+}
+
+impl UnitRegistry {
+    /// the built-in units followed by the registered ones, registered units named like an earlier one are skipped
+    pub fn new(registered: Vec<UnitDefinition>) -> Self {
+        let registry = Self { units: Arc::new(RwLock::new(built_in_units())) };
+        for unit in registered {
+            registry.register(unit)
+                .unwrap_or_else(|err| warn!("Skipping registered unit. Err={}", err.message()));
+        }
+        registry
+    }
+
+    /// falls back to the built-in units when the registered units cannot be read
+    pub async fn load(database: &Dao) -> Self {
+        let registered = database.get_units().await
+            .unwrap_or_else(|err| {
+                error!("Could not load registered units, using the built-in ones. Err={:?}", err);
+                vec![]
+            });
+        let registry = Self::new(registered);
+        info!("Loaded unit registry={:?}", registry.units());
+        registry
+    }
+
+    pub fn units(&self) -> Vec<UnitDefinition> {
+        self.units.read().map(|units| units.clone()).unwrap_or_default()
+    }
+
+    pub fn find(&self, name: &str) -> Option<UnitDefinition> {
+        self.units().into_iter().find(|unit| unit.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// the unit has to be validated before
+    pub fn register(&self, unit: UnitDefinition) -> Result<(), UnitError> {
+        let mut units = match self.units.write() {
+            Ok(units) => units,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if units.iter().any(|known| known.name.eq_ignore_ascii_case(unit.name.trim())) {
+            return Err(UnitError::DuplicateUnit(unit.name));
+        }
+        units.push(UnitDefinition { name: unit.name.trim().to_string(), ..unit });
+        Ok(())
+    }
+
+    /// the amount in the other unit of the same family, rounded like scaled amounts
+    pub fn convert(&self, amount: f64, from: &str, to: &str) -> Result<f64, UnitError> {
+        let from_unit = self.find(from).ok_or_else(|| UnitError::UnknownUnit(from.to_string()))?;
+        let to_unit = self.find(to).ok_or_else(|| UnitError::UnknownUnit(to.to_string()))?;
+        if from_unit.family != to_unit.family {
+            return Err(UnitError::IncompatibleUnits(from_unit.name, to_unit.name));
+        }
+        Ok(round_amount(amount * from_unit.factor / to_unit.factor))
+    }
+}
+
+/// packs differ in size, so they convert to nothing
+fn built_in_units() -> Vec<UnitDefinition> {
+    vec![
+        UnitDefinition::new(&MeasurementUnit::Gramm.to_string(), UnitFamily::Mass, 1.0),
+        UnitDefinition::new(&MeasurementUnit::Kilogramm.to_string(), UnitFamily::Mass, 1000.0),
+        UnitDefinition::new(&MeasurementUnit::Milliliter.to_string(), UnitFamily::Volume, 1.0),
+        UnitDefinition::new(&MeasurementUnit::Liter.to_string(), UnitFamily::Volume, 1000.0),
+        UnitDefinition::new(&MeasurementUnit::Piece.to_string(), UnitFamily::Count, 1.0),
+    ]
+}
+
+
+#[cfg(test)]
+mod unit_registry_tests {
+    use crate::model::unit_definition::{UnitDefinition, UnitFamily};
+    use crate::unit_registry::{UnitError, UnitRegistry};
+
+    #[test]
+    fn convert_built_in_units() {
+        let registry = UnitRegistry::default();
+        assert_eq!(registry.convert(1.5, "Kilogramm", "gramm"), Ok(1500.0));
+        assert_eq!(registry.convert(250.0, "Milliliter", "Liter"), Ok(0.25));
+        assert_eq!(registry.convert(1.0, "Liter", "Gramm"), Err(UnitError::IncompatibleUnits("Liter".to_string(), "Gramm".to_string())));
+        assert_eq!(registry.convert(1.0, "Pack", "Piece"), Err(UnitError::UnknownUnit("Pack".to_string())));
+    }
+
+    #[test]
+    fn register_and_convert() {
+        let registry = UnitRegistry::default();
+        assert_eq!(registry.register(UnitDefinition::new(" cup ", UnitFamily::Volume, 240.0)), Ok(()));
+        assert_eq!(registry.convert(2.0, "Cup", "Milliliter"), Ok(480.0));
+        assert_eq!(registry.convert(100.0, "Milliliter", "cup"), Ok(0.42));
+        assert_eq!(registry.find("CUP").unwrap().name, "cup");
+
+        assert_eq!(registry.register(UnitDefinition::new("Cup", UnitFamily::Volume, 250.0)), Err(UnitError::DuplicateUnit("Cup".to_string())));
+        assert_eq!(registry.register(UnitDefinition::new("liter", UnitFamily::Volume, 1000.0)).is_err(), true);
+        assert_eq!(registry.units().len(), 6);
+    }
+
+    #[test]
+    fn registered_units_skip_duplicates() {
+        let registry = UnitRegistry::new(vec![
+            UnitDefinition::new("dozen", UnitFamily::Count, 12.0),
+            UnitDefinition::new("Gramm", UnitFamily::Mass, 2.0),
+        ]);
+        assert_eq!(registry.convert(2.0, "dozen", "Piece"), Ok(24.0));
+        assert_eq!(registry.convert(1.0, "Kilogramm", "Gramm"), Ok(1000.0));
+    }
+}
@@ -0,0 +1,130 @@
+use actix_web::{HttpResponse, web};
+use actix_web::web::{Json, Query};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::AdminIdentity;
+use crate::dao::Dao;
+use crate::error_body::ErrorBody;
+use crate::model::unit_definition::UnitDefinition;
+use crate::recipe_routes::{dao_error_response, validation_error_response};
+use crate::unit_registry::{UnitError, UnitRegistry};
+
+pub struct UnitRoutes {}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ConversionParams {
+    pub amount: f64,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Conversion {
+    pub amount: f64,
+    pub unit: String,
+}
+
+impl UnitRoutes {
+    /// the built-in units followed by the registered ones
+    pub async fn get_units(registry: web::Data<UnitRegistry>) -> HttpResponse {
+        HttpResponse::Ok().json(registry.units())
+    }
+
+    /// persists the unit and adds it to the registry, 409 when a unit with this name exists
+    pub async fn add_unit(admin: AdminIdentity, registry: web::Data<UnitRegistry>, database: web::Data<Dao>, unit: Json<UnitDefinition>) -> HttpResponse {
+        let unit = unit.into_inner();
+        if let Err(err) = unit.validate() {
+            return validation_error_response(err.error);
+        }
+        let unit = UnitDefinition { name: unit.name.trim().to_string(), ..unit };
+        if registry.find(&unit.name).is_some() {
+            return unit_error_response(UnitError::DuplicateUnit(unit.name));
+        }
+
+        info!("Registering unit={:?} by admin={}", unit, admin.0.user);
+        if let Err(err) = database.insert_unit(unit.clone()).await {
+            return dao_error_response(err);
+        }
+        match registry.register(unit.clone()) {
+            Ok(_) => HttpResponse::Created().json(unit),
+            Err(err) => unit_error_response(err),
+        }
+    }
+
+    /// `?amount=2&from=cup&to=Milliliter`, 422 for unknown units and units of different families
+    pub async fn convert(registry: web::Data<UnitRegistry>, params: Query<ConversionParams>) -> HttpResponse {
+        match registry.convert(params.amount, &params.from, &params.to) {
+            Ok(amount) => HttpResponse::Ok().json(Conversion { amount, unit: params.to.trim().to_string() }),
+            Err(err) => unit_error_response(err),
+        }
+    }
+}
+
+fn unit_error_response(error: UnitError) -> HttpResponse {
+    match error {
+        UnitError::DuplicateUnit(_) => HttpResponse::Conflict().json(ErrorBody::new(&error.message())),
+        _ => validation_error_response(error.message()),
+    }
+}
+
+
+#[cfg(test)]
+mod unit_routes_tests {
+    use actix_web::{App, test, web};
+    use actix_web::http::StatusCode;
+    use serde_json::{json, Value};
+    use serial_test::serial;
+
+    use crate::auth::auth_tests::{ADMIN_TOKEN, bearer, create_api_tokens, EDITOR_TOKEN};
+    use crate::dao::dao_tests::{before, cleanup_after};
+    use crate::unit_registry::UnitRegistry;
+    use crate::unit_routes::UnitRoutes;
+
+    #[actix_rt::test]
+    async fn convert_built_in_units() {
+        let mut app = test::init_service(App::new()
+            .data(UnitRegistry::default())
+            .route("/units/convert", web::get().to(UnitRoutes::convert))).await;
+
+        let req = test::TestRequest::get().uri("/units/convert?amount=1.5&from=Kilogramm&to=Gramm").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, json!({ "amount": 1500.0, "unit": "Gramm" }));
+
+        let req = test::TestRequest::get().uri("/units/convert?amount=1&from=Liter&to=Gramm").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[actix_rt::test]
+    #[serial]
+    async fn test_register_unit_and_convert() {
+        let dao = before().await;
+        let mut app = test::init_service(App::new()
+            .data(dao.clone())
+            .data(create_api_tokens())
+            .data(UnitRegistry::default())
+            .route("/units", web::post().to(UnitRoutes::add_unit))
+            .route("/units/convert", web::get().to(UnitRoutes::convert))).await;
+        let (header, admin) = bearer(ADMIN_TOKEN);
+        let (_, editor) = bearer(EDITOR_TOKEN);
+        let cup = json!({ "name": "cup", "family": "volume", "factor": 240 });
+
+        let req = test::TestRequest::post().header(header, editor).set_json(&cup).uri("/units").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::post().header(header, admin.clone()).set_json(&cup).uri("/units").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::CREATED);
+        let req = test::TestRequest::post().header(header, admin.clone()).set_json(&cup).uri("/units").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::CONFLICT);
+        let req = test::TestRequest::post().header(header, admin.clone())
+            .set_json(&json!({ "name": "pinch", "family": "mass", "factor": 0 })).uri("/units").to_request();
+        assert_eq!(test::call_service(&mut app, req).await.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let req = test::TestRequest::get().uri("/units/convert?amount=2&from=Cup&to=Milliliter").to_request();
+        let body: Value = test::read_body_json(test::call_service(&mut app, req).await).await;
+        assert_eq!(body, json!({ "amount": 480.0, "unit": "Milliliter" }));
+
+        let reloaded = UnitRegistry::load(&dao).await;
+        assert_eq!(reloaded.convert(1.0, "cup", "Liter"), Ok(0.24));
+        cleanup_after(dao).await;
+    }
+}
@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use mongodb::options::{Acknowledgment, ClientOptions, WriteConcern};
+
+use crate::LogExtensionErr;
+
+pub const WRITE_CONCERN_ENV: &str = "WRITE_CONCERN";
+pub const WRITE_CONCERN_TIMEOUT_ENV: &str = "WRITE_CONCERN_TIMEOUT_MS";
+pub const WRITE_CONCERN_JOURNAL_ENV: &str = "WRITE_CONCERN_JOURNAL";
+
+/// The write concern of every insert, update and delete of the `Dao`, the server default when unset.
+///
+/// `WRITE_CONCERN=majority` acknowledges a write once most replica set members hold it, so it
+/// survives a failover, at the cost of waiting for the slowest of those members on every write.
+/// `WRITE_CONCERN=1` acknowledges on the primary alone and is fastest, but a failover may roll the
+/// write back. `WRITE_CONCERN_JOURNAL=true` additionally waits for the on-disk journal.
+/// `WRITE_CONCERN_TIMEOUT_MS` bounds the wait, a timed out write reports an error although it may
+/// still be applied later
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WriteConcernSettings {
+    pub write_concern: Option<WriteConcern>,
+}
+
+impl WriteConcernSettings {
+    /// falls back to the server default when the configuration is invalid
+    pub fn from_env() -> Self {
+        let settings = parse(
+            std::env::var(WRITE_CONCERN_ENV).ok().as_deref(),
+            std::env::var(WRITE_CONCERN_TIMEOUT_ENV).ok().as_deref(),
+            std::env::var(WRITE_CONCERN_JOURNAL_ENV).ok().as_deref())
+            .log_if_err(|err| error!("Invalid write concern, using the server default. Err={}", err))
+            .unwrap_or_default();
+        info!("Loaded write concern settings={:?}", settings);
+        settings
+    }
+
+    /// the client handles every database and collection inherit the write concern from
+    pub fn apply(&self, options: &mut ClientOptions) {
+        options.write_concern = self.write_concern.clone();
+    }
+}
+
+fn parse(w: Option<&str>, timeout: Option<&str>, journal: Option<&str>) -> Result<WriteConcernSettings, String> {
+    let w = w.map(str::trim).filter(|w| !w.is_empty()).map(|w| match w.parse::<i32>() {
+        Ok(nodes) => Acknowledgment::Nodes(nodes),
+        Err(_) => Acknowledgment::from(w.to_string()),
+    });
+    let w_timeout = timeout.map(|timeout| timeout.trim().parse::<u64>()
+        .map(Duration::from_millis)
+        .map_err(|_| format!("{} is no number of milliseconds", timeout)))
+        .transpose()?;
+    let journal = journal.map(|journal| journal.trim().parse::<bool>()
+        .map_err(|_| format!("{} is no boolean", journal)))
+        .transpose()?;
+    if w.is_none() && w_timeout.is_none() && journal.is_none() {
+        return Ok(WriteConcernSettings::default());
+    }
+
+    let write_concern = WriteConcern::builder().w(w).w_timeout(w_timeout).journal(journal).build();
+    write_concern.validate().map_err(|err| err.to_string())?;
+    Ok(WriteConcernSettings { write_concern: Some(write_concern) })
+}
+
+
+#[cfg(test)]
+pub mod write_concern_tests {
+    use std::time::Duration;
+
+    use mongodb::options::{Acknowledgment, WriteConcern};
+
+    use crate::write_concern::{parse, WriteConcernSettings};
+
+    pub fn majority() -> WriteConcernSettings {
+        WriteConcernSettings { write_concern: Some(WriteConcern::builder().w(Acknowledgment::Majority).build()) }
+    }
+
+    #[test]
+    fn parse_write_concern() {
+        assert_eq!(parse(None, None, None), Ok(WriteConcernSettings::default()));
+        assert_eq!(parse(Some(" "), None, None), Ok(WriteConcernSettings::default()));
+        assert_eq!(parse(Some("majority"), None, None), Ok(majority()));
+
+        let settings = parse(Some("2"), Some("500"), Some("true")).unwrap().write_concern.unwrap();
+        assert_eq!(settings.w, Some(Acknowledgment::Nodes(2)));
+        assert_eq!(settings.w_timeout, Some(Duration::from_millis(500)));
+        assert_eq!(settings.journal, Some(true));
+
+        assert_eq!(parse(Some("majority"), Some("soon"), None).is_err(), true);
+        assert_eq!(parse(None, None, Some("maybe")).is_err(), true);
+        assert_eq!(parse(Some("0"), None, Some("true")).is_err(), true);
+        assert_eq!(parse(Some("-1"), None, None).is_err(), true);
+    }
+}